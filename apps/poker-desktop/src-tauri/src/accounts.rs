@@ -0,0 +1,306 @@
+// Account profiles, for computers shared by several players. Each profile keeps its
+// own data directory under `profiles/<name>/` in the app data directory - database,
+// backups, event buffers, solver work files - and its own keyring entries, so one
+// player's hand histories, notes and login never show up for another. The profile is
+// picked at startup from `--account=<name>`, PRIMO_ACCOUNT or the last one switched
+// to; switching restarts the app, since the database is opened once.
+//
+// Installs from before profiles keep everything at the top of the app data
+// directory. That layout is served as the default profile until
+// `migrate_profile_data` moves it into a profile directory, which happens on the
+// next launch, before the database is opened. Its keyring entries are copied to the
+// profile's names at the same time.
+
+use crate::instance;
+use chrono::{DateTime, Utc};
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const REGISTRY_FILE: &str = "accounts.json";
+const PROFILES_DIR: &str = "profiles";
+pub const DEFAULT_PROFILE: &str = "default";
+const MAX_NAME_LEN: usize = 32;
+// Everything the single-profile layout kept at the top of the data directory
+const LEGACY_ENTRIES: &[&str] = &[
+    "primo-poker.db",
+    "primo-poker.db-wal",
+    "primo-poker.db-shm",
+    "primo-poker.db-journal",
+    "backups",
+    "event-buffers",
+    "event-dumps",
+    "solver",
+];
+// Keyring entries the single-profile layout kept under their bare names
+const LEGACY_KEYRING_ENTRIES: &[&str] = &["auth-token", "device-trust", "hand-cards-key", "hand-cards-key-next", "dm-identity"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountProfile {
+    name: String,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct Registry {
+    active: Option<String>,
+    profiles: Vec<AccountProfile>,
+    // Profile to move the single-profile layout into on the next launch
+    pending_migration: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountProfiles {
+    active: String,
+    profiles: Vec<AccountProfile>,
+    // Data from before profiles is still at the top of the data directory
+    legacy_layout: bool,
+}
+
+struct Active {
+    name: String,
+    data_dir: PathBuf,
+}
+
+static ROOT: OnceLock<PathBuf> = OnceLock::new();
+static ACTIVE: OnceLock<Active> = OnceLock::new();
+
+fn root() -> Result<&'static Path, String> {
+    ROOT.get().map(|p| p.as_path()).ok_or_else(|| "Account profiles are not set up yet".to_string())
+}
+
+fn load_registry(root: &Path) -> Result<Registry, String> {
+    match fs::read_to_string(root.join(REGISTRY_FILE)) {
+        Ok(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid account registry: {}", e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Registry::default()),
+        Err(e) => Err(format!("Failed to read account registry: {}", e)),
+    }
+}
+
+fn save_registry(root: &Path, registry: &Registry) -> Result<(), String> {
+    fs::create_dir_all(root).map_err(|e| format!("Failed to create data directory: {}", e))?;
+    let data = serde_json::to_string_pretty(registry).map_err(|e| e.to_string())?;
+    fs::write(root.join(REGISTRY_FILE), data).map_err(|e| format!("Failed to write account registry: {}", e))
+}
+
+fn profile_dir(root: &Path, name: &str) -> PathBuf {
+    root.join(PROFILES_DIR).join(name)
+}
+
+fn has_legacy_data(root: &Path) -> bool {
+    root.join(LEGACY_ENTRIES[0]).exists()
+}
+
+fn check_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Profile names are 1 to {} lowercase letters, digits, '-' or '_'",
+            MAX_NAME_LEN
+        ))
+    }
+}
+
+fn requested() -> Option<String> {
+    std::env::args()
+        .find_map(|arg| arg.strip_prefix("--account=").map(|p| p.to_string()))
+        .or_else(|| std::env::var("PRIMO_ACCOUNT").ok())
+        .filter(|name| !name.is_empty())
+}
+
+// Move the single-profile layout into `name`'s directory. Entries already present
+// there are left alone rather than overwritten.
+fn migrate(root: &Path, name: &str) -> Result<usize, String> {
+    let target = profile_dir(root, name);
+    fs::create_dir_all(&target).map_err(|e| format!("Failed to create profile directory: {}", e))?;
+    let mut moved = 0;
+    for entry in LEGACY_ENTRIES {
+        let from = root.join(entry);
+        let to = target.join(entry);
+        if !from.exists() {
+            continue;
+        }
+        if to.exists() {
+            eprintln!("Not moving {} into profile {}: it already has one", entry, name);
+            continue;
+        }
+        fs::rename(&from, &to).map_err(|e| format!("Failed to move {}: {}", entry, e))?;
+        moved += 1;
+    }
+    if name != DEFAULT_PROFILE {
+        for entry in LEGACY_KEYRING_ENTRIES {
+            if let Err(e) = copy_keyring_entry(entry, &format!("{}@{}", entry, name)) {
+                eprintln!("Not copying keyring entry {} into profile {}: {}", entry, name, e);
+            }
+        }
+    }
+    Ok(moved)
+}
+
+// Copy a keyring entry unless the target already has one
+fn copy_keyring_entry(from: &str, to: &str) -> Result<(), String> {
+    let keyring_error = |e: keyring::Error| format!("Keyring error: {}", e);
+    let target = Entry::new("primo-poker", to).map_err(keyring_error)?;
+    match target.get_password() {
+        Ok(_) => return Err("it already has one".to_string()),
+        Err(keyring::Error::NoEntry) => {}
+        Err(e) => return Err(keyring_error(e)),
+    }
+    match Entry::new("primo-poker", from).map_err(keyring_error)?.get_password() {
+        Ok(secret) => target.set_password(&secret).map_err(keyring_error),
+        Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(keyring_error(e)),
+    }
+}
+
+fn ensure_profile(registry: &mut Registry, name: &str) {
+    if !registry.profiles.iter().any(|p| p.name == name) {
+        registry.profiles.push(AccountProfile { name: name.to_string(), created_at: Utc::now() });
+    }
+}
+
+// Pick the profile for this run and return its data directory. Called once from
+// setup, before anything reads or writes the data directory.
+pub fn select(root: &Path) -> Result<PathBuf, String> {
+    let root = ROOT.get_or_init(|| root.to_path_buf());
+    let mut registry = load_registry(root)?;
+
+    if let Some(name) = registry.pending_migration.take() {
+        match migrate(root, &name) {
+            Ok(moved) => eprintln!("Moved {} data entries into profile {}", moved, name),
+            Err(e) => eprintln!("Profile data migration failed, keeping the old layout: {}", e),
+        }
+        ensure_profile(&mut registry, &name);
+    }
+
+    let name = match requested() {
+        Some(name) => {
+            check_name(&name)?;
+            name
+        }
+        None => registry.active.clone().unwrap_or_else(|| DEFAULT_PROFILE.to_string()),
+    };
+    ensure_profile(&mut registry, &name);
+    registry.active = Some(name.clone());
+    save_registry(root, &registry)?;
+
+    let data_dir = if name == DEFAULT_PROFILE && has_legacy_data(root) {
+        root.to_path_buf()
+    } else {
+        profile_dir(root, &name)
+    };
+    let active = ACTIVE.get_or_init(|| Active { name, data_dir });
+    Ok(active.data_dir.clone())
+}
+
+pub fn active_name() -> &'static str {
+    ACTIVE.get().map_or(DEFAULT_PROFILE, |a| a.name.as_str())
+}
+
+// Data directory of the running profile
+pub fn data_dir() -> Result<PathBuf, String> {
+    ACTIVE.get().map(|a| a.data_dir.clone()).ok_or_else(|| "Account profiles are not set up yet".to_string())
+}
+
+// Keyring entry name for the running profile; the default profile keeps the names
+// used before profiles so existing logins survive the upgrade
+pub fn keyring_key(base: &str) -> String {
+    match active_name() {
+        DEFAULT_PROFILE => base.to_string(),
+        name => format!("{}@{}", base, name),
+    }
+}
+
+fn listing(root: &Path, registry: Registry) -> AccountProfiles {
+    let mut profiles = registry.profiles;
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    AccountProfiles { active: active_name().to_string(), profiles, legacy_layout: has_legacy_data(root) }
+}
+
+#[tauri::command]
+pub async fn list_account_profiles() -> Result<AccountProfiles, String> {
+    let root = root()?;
+    Ok(listing(root, load_registry(root)?))
+}
+
+#[tauri::command]
+pub async fn create_account_profile(name: String) -> Result<AccountProfiles, String> {
+    check_name(&name)?;
+    let root = root()?;
+    let mut registry = load_registry(root)?;
+    if registry.profiles.iter().any(|p| p.name == name) {
+        return Err(format!("Profile {} already exists", name));
+    }
+    ensure_profile(&mut registry, &name);
+    save_registry(root, &registry)?;
+    Ok(listing(root, registry))
+}
+
+// Make `name` the active profile and restart into it
+#[tauri::command]
+pub async fn switch_account_profile(app: tauri::AppHandle, name: String) -> Result<(), String> {
+    let root = root()?;
+    let mut registry = load_registry(root)?;
+    if !registry.profiles.iter().any(|p| p.name == name) {
+        return Err(format!("Unknown profile {}", name));
+    }
+    if name == active_name() {
+        return Ok(());
+    }
+    registry.active = Some(name);
+    save_registry(root, &registry)?;
+    instance::release();
+    app.restart();
+    Ok(())
+}
+
+// Delete a profile and everything in its data directory. The running profile cannot
+// be deleted.
+#[tauri::command]
+pub async fn delete_account_profile(name: String) -> Result<AccountProfiles, String> {
+    check_name(&name)?;
+    if name == active_name() {
+        return Err("Switch to another profile before deleting this one".to_string());
+    }
+    let root = root()?;
+    let mut registry = load_registry(root)?;
+    let before = registry.profiles.len();
+    registry.profiles.retain(|p| p.name != name);
+    if registry.profiles.len() == before {
+        return Err(format!("Unknown profile {}", name));
+    }
+    let dir = profile_dir(root, &name);
+    if dir.exists() {
+        fs::remove_dir_all(&dir).map_err(|e| format!("Failed to delete profile data: {}", e))?;
+    }
+    save_registry(root, &registry)?;
+    Ok(listing(root, registry))
+}
+
+// Move data from the single-profile layout into a profile (the default one unless
+// named) and restart, so the move happens while the database is closed
+#[tauri::command]
+pub async fn migrate_profile_data(app: tauri::AppHandle, name: Option<String>) -> Result<(), String> {
+    let name = name.unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+    check_name(&name)?;
+    let root = root()?;
+    if !has_legacy_data(root) {
+        return Err("There is no data from before profiles to migrate".to_string());
+    }
+    let mut registry = load_registry(root)?;
+    registry.pending_migration = Some(name.clone());
+    registry.active = Some(name);
+    save_registry(root, &registry)?;
+    instance::release();
+    app.restart();
+    Ok(())
+}
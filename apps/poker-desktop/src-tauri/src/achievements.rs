@@ -0,0 +1,204 @@
+// Achievements and daily missions. Definitions and progress come from the backend;
+// between syncs, progress is advanced locally from saved hands so progress bars move
+// in real time. Local counters are dropped at the next sync, since by then the
+// backend has counted the same hands. Crossing a target emits `achievement_unlocked`
+// and shows a desktop notification.
+
+use crate::db::{Database, Db};
+use crate::history::HandRecord;
+use crate::leaks::is_preflop;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+
+const KEY_STATE: &str = "achievements.state";
+
+// Metrics that can be advanced locally
+const HANDS_PLAYED: &str = "hands_played";
+const FLOPS_SEEN: &str = "flops_seen";
+const POTS_WON: &str = "pots_won";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Achievement {
+    id: String,
+    title: String,
+    #[serde(default)]
+    description: String,
+    // achievement | mission
+    #[serde(default = "default_kind")]
+    kind: String,
+    metric: String,
+    target: u32,
+    #[serde(default)]
+    progress: u32,
+    #[serde(default)]
+    unlocked: bool,
+    // Daily missions expire at the end of their day
+    #[serde(default)]
+    expires_at: Option<DateTime<Utc>>,
+}
+
+fn default_kind() -> String {
+    "achievement".to_string()
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct AchievementsState {
+    items: Vec<Achievement>,
+    synced_at: Option<DateTime<Utc>>,
+    // Metric -> count since the last sync
+    local: HashMap<String, u32>,
+    // Unlocked locally but not yet confirmed by a sync
+    unlocked_locally: Vec<String>,
+}
+
+impl AchievementsState {
+    // Items with local progress applied, expired missions left out
+    fn view(&self) -> Vec<Achievement> {
+        let now = Utc::now();
+        self.items
+            .iter()
+            .filter(|item| item.expires_at.is_none_or(|at| at > now))
+            .map(|item| {
+                let local = self.local.get(&item.metric).copied().unwrap_or(0);
+                let progress = (item.progress + local).min(item.target);
+                Achievement {
+                    progress,
+                    unlocked: item.unlocked || self.unlocked_locally.contains(&item.id),
+                    ..item.clone()
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AchievementsView {
+    achievements: Vec<Achievement>,
+    missions: Vec<Achievement>,
+    synced_at: Option<DateTime<Utc>>,
+}
+
+fn load(db: &Database) -> Result<AchievementsState, String> {
+    match db.get_value(KEY_STATE)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid achievements state: {}", e)),
+        None => Ok(AchievementsState::default()),
+    }
+}
+
+fn save(db: &Database, state: &AchievementsState) -> Result<(), String> {
+    let data = serde_json::to_string(state).map_err(|e| e.to_string())?;
+    db.set_value(KEY_STATE, &data)
+}
+
+fn split(state: &AchievementsState) -> AchievementsView {
+    let (missions, achievements) = state.view().into_iter().partition(|item| item.kind == "mission");
+    AchievementsView { achievements, missions, synced_at: state.synced_at }
+}
+
+async fn fetch(api_url: &str, path: &str) -> Result<Vec<Achievement>, String> {
+    let client = crate::create_http_client()?;
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+
+    let response = crate::http::send(client.get(format!("{}{}", api_url, path))
+        .header("Authorization", format!("Bearer {}", token)))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        return Err("Failed to fetch achievements".to_string());
+    }
+
+    let api_response: crate::ApiResponse<Vec<Achievement>> = response.json().await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    if api_response.success {
+        Ok(api_response.data.unwrap_or_default())
+    } else {
+        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    }
+}
+
+// Metrics a saved hand advances for the hero
+fn hand_metrics(hand: &HandRecord) -> Vec<&'static str> {
+    let Some(hero) = hand.hero_id.as_deref() else { return Vec::new() };
+    let Some(player) = hand.players.iter().find(|p| p.player_id == hero) else { return Vec::new() };
+
+    let mut metrics = vec![HANDS_PLAYED];
+    let folded_preflop = hand
+        .actions
+        .iter()
+        .any(|a| a.player_id == hero && a.action == "fold" && is_preflop(&a.street));
+    if hand.board.len() >= 3 && !folded_preflop {
+        metrics.push(FLOPS_SEEN);
+    }
+    if player.net > 0 {
+        metrics.push(POTS_WON);
+    }
+    metrics
+}
+
+fn notify(app: &AppHandle, item: &Achievement) {
+    let title = if item.kind == "mission" { "Mission complete" } else { "Achievement unlocked" };
+    let shown = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier)
+        .title(title)
+        .body(&item.title)
+        .show();
+    if let Err(e) = shown {
+        eprintln!("Failed to show achievement notification: {}", e);
+    }
+}
+
+// Advance local progress for a newly saved hand
+pub fn record_hand(app: &AppHandle, db: &Database, hand: &HandRecord) -> Result<(), String> {
+    let metrics = hand_metrics(hand);
+    if metrics.is_empty() {
+        return Ok(());
+    }
+
+    let mut state = load(db)?;
+    let before: HashMap<String, bool> = state.view().into_iter().map(|item| (item.id, item.unlocked)).collect();
+    for metric in metrics {
+        *state.local.entry(metric.to_string()).or_insert(0) += 1;
+    }
+
+    let mut unlocked = Vec::new();
+    for item in state.view() {
+        if !item.unlocked && item.progress >= item.target && before.get(&item.id) == Some(&false) {
+            unlocked.push(item);
+        }
+    }
+    state.unlocked_locally.extend(unlocked.iter().map(|item| item.id.clone()));
+    save(db, &state)?;
+
+    for item in unlocked {
+        let _ = app.emit_all("achievement_unlocked", item.clone());
+        notify(app, &item);
+    }
+    Ok(())
+}
+
+// Locally tracked state, without contacting the backend
+#[tauri::command]
+pub async fn get_achievements(db: Db<'_>) -> Result<AchievementsView, String> {
+    Ok(split(&load(&db)?))
+}
+
+// Replace definitions and progress with the backend's and drop local counters
+#[tauri::command]
+pub async fn sync_achievements(db: Db<'_>, api_url: String) -> Result<AchievementsView, String> {
+    let mut items = fetch(&api_url, "/api/achievements").await?;
+    items.extend(fetch(&api_url, "/api/missions/daily").await?.into_iter().map(|mission| Achievement {
+        kind: "mission".to_string(),
+        ..mission
+    }));
+
+    let state = AchievementsState { items, synced_at: Some(Utc::now()), ..AchievementsState::default() };
+    save(&db, &state)?;
+    Ok(split(&state))
+}
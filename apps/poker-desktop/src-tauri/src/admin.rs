@@ -0,0 +1,110 @@
+// Tournament director commands. Always compiled in, but every call first checks the
+// access token for a staff role (see guard.rs) and refuses before anything is sent;
+// the backend repeats the check with the verified token.
+
+use crate::audit;
+use crate::error::CommandError;
+use crate::guard;
+use serde_json::{json, Value};
+use tauri::AppHandle;
+
+// Largest single clock adjustment, in seconds
+const MAX_CLOCK_ADJUST: i64 = 3600;
+
+async fn post_admin(api_url: &str, tournament_id: &str, action: &str, body: &Value) -> Result<Value, String> {
+    let client = crate::create_http_client()?;
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+
+    let response = crate::http::send(client.post(format!("{}/api/admin/tournaments/{}/{}", api_url, tournament_id, action))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(body))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("Tournament {} failed: {}", action, error_text));
+    }
+
+    let api_response: crate::ApiResponse<Value> = response.json().await.map_err(|e| e.to_string())?;
+
+    if api_response.success {
+        Ok(api_response.data.unwrap_or(json!({})))
+    } else {
+        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    }
+}
+
+async fn admin_action(
+    app: &AppHandle,
+    api_url: &str,
+    tournament_id: &str,
+    action: &str,
+    body: Value,
+) -> Result<Value, CommandError> {
+    let result = match guard::require(action, &guard::TOURNAMENT_ADMIN) {
+        Ok(_) => post_admin(api_url, tournament_id, action, &body).await.map_err(CommandError::from),
+        Err(e) => Err(e),
+    };
+    audit::record(app, &format!("admin_{}", action), json!({ "tournamentId": tournament_id, "request": body }), &result);
+    result
+}
+
+#[tauri::command]
+pub async fn admin_pause_tournament(
+    app: AppHandle,
+    api_url: String,
+    tournament_id: String,
+    reason: Option<String>,
+) -> Result<Value, CommandError> {
+    admin_action(&app, &api_url, &tournament_id, "pause", json!({ "reason": reason })).await
+}
+
+#[tauri::command]
+pub async fn admin_resume_tournament(
+    app: AppHandle,
+    api_url: String,
+    tournament_id: String,
+) -> Result<Value, CommandError> {
+    admin_action(&app, &api_url, &tournament_id, "resume", json!({})).await
+}
+
+// Add (positive) or remove (negative) time from the current level, or jump to `level`
+#[tauri::command]
+pub async fn admin_adjust_clock(
+    app: AppHandle,
+    api_url: String,
+    tournament_id: String,
+    seconds: i64,
+    level: Option<u32>,
+) -> Result<Value, CommandError> {
+    if seconds.unsigned_abs() > MAX_CLOCK_ADJUST as u64 {
+        return Err(format!("Clock adjustments are limited to {} seconds", MAX_CLOCK_ADJUST).into());
+    }
+    admin_action(&app, &api_url, &tournament_id, "clock", json!({ "seconds": seconds, "level": level })).await
+}
+
+#[tauri::command]
+pub async fn admin_disqualify_player(
+    app: AppHandle,
+    api_url: String,
+    tournament_id: String,
+    player_id: String,
+    reason: String,
+) -> Result<Value, CommandError> {
+    if reason.trim().is_empty() {
+        return Err("A reason is required to disqualify a player".to_string().into());
+    }
+    admin_action(&app, &api_url, &tournament_id, "disqualify", json!({ "playerId": player_id, "reason": reason.trim() })).await
+}
+
+// Ask the server to move players so table sizes differ by at most one
+#[tauri::command]
+pub async fn admin_rebalance_tables(
+    app: AppHandle,
+    api_url: String,
+    tournament_id: String,
+) -> Result<Value, CommandError> {
+    admin_action(&app, &api_url, &tournament_id, "rebalance", json!({})).await
+}
@@ -0,0 +1,122 @@
+// Per-table overrides of sounds, OS notifications and focus-follows-action, so the
+// player can mute a recreational home game and keep every alert for the table that
+// matters. Overrides are kept in the kv table by table id; a setting left unset
+// follows the app-wide behaviour, which is on. Overrides of tables that closed are
+// dropped when the lobby is refreshed: a table that is no longer listed, not seated
+// and not on screen is gone.
+
+use crate::db::{Database, Db};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+const KEY_OVERRIDES: &str = "table.alert_overrides";
+const MAX_TABLES: usize = 200;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertOverrides {
+    #[serde(default)]
+    sounds: Option<bool>,
+    #[serde(default)]
+    notifications: Option<bool>,
+    // Bring the table's window forward when it is the player's turn
+    #[serde(default)]
+    focus_on_action: Option<bool>,
+}
+
+impl AlertOverrides {
+    fn is_empty(&self) -> bool {
+        *self == AlertOverrides::default()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableAlertOverrides {
+    table_id: String,
+    #[serde(flatten)]
+    overrides: AlertOverrides,
+    updated_at: DateTime<Utc>,
+}
+
+// What is in force at a table once overrides are applied
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableAlerts {
+    pub sounds: bool,
+    pub notifications: bool,
+    pub focus_on_action: bool,
+}
+
+impl Default for TableAlerts {
+    fn default() -> Self {
+        Self { sounds: true, notifications: true, focus_on_action: true }
+    }
+}
+
+fn load(db: &Database) -> Result<HashMap<String, TableAlertOverrides>, String> {
+    match db.get_value(KEY_OVERRIDES)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid table alert overrides: {}", e)),
+        None => Ok(HashMap::new()),
+    }
+}
+
+fn save(db: &Database, overrides: &HashMap<String, TableAlertOverrides>) -> Result<(), String> {
+    let data = serde_json::to_string(overrides).map_err(|e| e.to_string())?;
+    db.set_value(KEY_OVERRIDES, &data)
+}
+
+pub fn for_table(db: &Database, table_id: &str) -> Result<TableAlerts, String> {
+    let overrides = load(db)?.remove(table_id).map(|t| t.overrides).unwrap_or_default();
+    let defaults = TableAlerts::default();
+    Ok(TableAlerts {
+        sounds: overrides.sounds.unwrap_or(defaults.sounds),
+        notifications: overrides.notifications.unwrap_or(defaults.notifications),
+        focus_on_action: overrides.focus_on_action.unwrap_or(defaults.focus_on_action),
+    })
+}
+
+// Drop the overrides of every table not in `open`
+pub fn prune(db: &Database, open: &HashSet<String>) -> Result<(), String> {
+    let mut overrides = load(db)?;
+    let before = overrides.len();
+    overrides.retain(|table_id, _| open.contains(table_id));
+    if overrides.len() == before {
+        return Ok(());
+    }
+    save(db, &overrides)
+}
+
+#[tauri::command]
+pub async fn get_table_alert_overrides(db: Db<'_>) -> Result<Vec<TableAlertOverrides>, String> {
+    let mut overrides: Vec<TableAlertOverrides> = load(&db)?.into_values().collect();
+    overrides.sort_by(|a, b| a.table_id.cmp(&b.table_id));
+    Ok(overrides)
+}
+
+// Replace the overrides of `table_id`; leaving every setting unset removes them
+#[tauri::command]
+pub async fn set_table_alert_overrides(
+    db: Db<'_>,
+    table_id: String,
+    overrides: AlertOverrides,
+) -> Result<TableAlerts, String> {
+    let mut stored = load(&db)?;
+    if overrides.is_empty() {
+        stored.remove(&table_id);
+    } else {
+        if !stored.contains_key(&table_id) && stored.len() >= MAX_TABLES {
+            return Err(format!("Overrides can be kept for at most {} tables", MAX_TABLES));
+        }
+        let entry = TableAlertOverrides { table_id: table_id.clone(), overrides, updated_at: Utc::now() };
+        stored.insert(table_id.clone(), entry);
+    }
+    save(&db, &stored)?;
+    for_table(&db, &table_id)
+}
+
+#[tauri::command]
+pub async fn get_table_alerts(db: Db<'_>, table_id: String) -> Result<TableAlerts, String> {
+    for_table(&db, &table_id)
+}
@@ -0,0 +1,312 @@
+// Animation timelines for the table views. Each table event the view receives is
+// handed over here; the table state before and after it is compared and the
+// difference is laid out as tracks of keyframes: bets moving from a seat to its bet
+// spot, bets swept into the pot when a street ends, the pot going out to whoever won
+// it, board cards dealt and hole cards turned over. Positions are fractions of the
+// table area with seat 0 at the bottom going clockwise, as in the thumbnails, so every
+// window animating the same table shows the same thing and only interpolates.
+// Timelines are returned and also raised as `animation_timeline`.
+
+use crate::cards::Card;
+use crate::preview;
+use crate::sizing::{self, ChipCount};
+use crate::table_state::{self, SeatState, TableMirror};
+use crate::ws::WsMessage;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+const MAX_TABLES: usize = 24;
+const MIN_SEATS: usize = 6;
+const BET_MS: u32 = 350;
+const SWEEP_MS: u32 = 400;
+const AWARD_MS: u32 = 600;
+const CARD_MS: u32 = 300;
+// Between cards dealt together
+const CARD_STAGGER_MS: u32 = 120;
+const PAUSE_MS: u32 = 150;
+
+const CENTER: Point = Point { x: 0.5, y: 0.5 };
+const POT: Point = Point { x: 0.5, y: 0.6 };
+const DECK: Point = Point { x: 0.5, y: 0.3 };
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Point {
+    x: f64,
+    y: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrackKind {
+    Bet,
+    Sweep,
+    Award,
+    DealBoard,
+    DealHole,
+    Reveal,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Keyframe {
+    // 0 to 1 through the track
+    at: f64,
+    x: f64,
+    y: f64,
+    scale: f64,
+    opacity: f64,
+    face_up: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Track {
+    kind: TrackKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    player_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seat: Option<usize>,
+    // Chips moved, with the stack that shows them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    amount: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    chips: Vec<ChipCount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    card: Option<String>,
+    start_ms: u32,
+    duration_ms: u32,
+    easing: &'static str,
+    keyframes: Vec<Keyframe>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnimationTimeline {
+    table_id: String,
+    event: String,
+    hand_number: u32,
+    duration_ms: u32,
+    tracks: Vec<Track>,
+}
+
+#[derive(Default)]
+pub struct AnimationState {
+    tables: Mutex<HashMap<String, TableMirror>>,
+}
+
+struct Layout {
+    seats: usize,
+}
+
+impl Layout {
+    fn new(mirror: &TableMirror, max_seats: Option<u8>) -> Self {
+        let highest = mirror.players.iter().enumerate().map(|(i, p)| seat_of(p, i) + 1).max().unwrap_or(0);
+        Self { seats: highest.max(max_seats.unwrap_or(0) as usize).max(MIN_SEATS) }
+    }
+
+    fn seat(&self, seat: usize) -> Point {
+        let angle = PI / 2.0 + seat as f64 * 2.0 * PI / self.seats as f64;
+        Point { x: CENTER.x + 0.42 * angle.cos(), y: CENTER.y + 0.38 * angle.sin() }
+    }
+
+    // Where a seat's bet sits, part of the way in towards the middle
+    fn bet_spot(&self, seat: usize) -> Point {
+        let at = self.seat(seat);
+        Point { x: CENTER.x + (at.x - CENTER.x) * 0.6, y: CENTER.y + (at.y - CENTER.y) * 0.6 }
+    }
+
+    fn board_slot(&self, index: usize) -> Point {
+        Point { x: 0.5 + (index as f64 - 2.0) * 0.08, y: 0.45 }
+    }
+}
+
+// Short notation such as "Ah", or the wire form for a card that does not parse
+fn card_name(card: &table_state::Card) -> String {
+    Card::from_wire(card).map_or_else(|| format!("{}{}", card.rank, card.suit), |c| c.to_string())
+}
+
+fn seat_of(player: &SeatState, index: usize) -> usize {
+    player.position.as_ref().map_or(index, |p| p.seat as usize)
+}
+
+fn frame(at: f64, point: Point, scale: f64, opacity: f64, face_up: bool) -> Keyframe {
+    Keyframe { at, x: point.x, y: point.y, scale, opacity, face_up }
+}
+
+fn chip_track(kind: TrackKind, player: &SeatState, seat: usize, amount: u32, from: Point, to: Point, start_ms: u32) -> Track {
+    let (duration_ms, keyframes) = match kind {
+        // Winnings lift slightly on the way out
+        TrackKind::Award => {
+            let middle = Point { x: (from.x + to.x) / 2.0, y: (from.y + to.y) / 2.0 - 0.04 };
+            (AWARD_MS, vec![frame(0.0, from, 1.0, 1.0, true), frame(0.5, middle, 1.15, 1.0, true), frame(1.0, to, 1.0, 0.0, true)])
+        }
+        TrackKind::Sweep => (SWEEP_MS, vec![frame(0.0, from, 1.0, 1.0, true), frame(1.0, to, 0.8, 0.0, true)]),
+        _ => (BET_MS, vec![frame(0.0, from, 0.8, 1.0, true), frame(1.0, to, 1.0, 1.0, true)]),
+    };
+    Track {
+        kind,
+        player_id: Some(player.id.clone()),
+        seat: Some(seat),
+        amount: Some(amount),
+        chips: sizing::chip_breakdown(amount),
+        card: None,
+        start_ms,
+        duration_ms,
+        easing: "ease_out",
+        keyframes,
+    }
+}
+
+fn card_track(kind: TrackKind, card: String, player: Option<(&SeatState, usize)>, from: Point, to: Point, start_ms: u32) -> Track {
+    let keyframes = match kind {
+        // Turned over where it lies
+        TrackKind::Reveal => vec![frame(0.0, to, 1.0, 1.0, false), frame(0.5, to, 1.1, 1.0, true), frame(1.0, to, 1.0, 1.0, true)],
+        _ => vec![frame(0.0, from, 0.6, 0.0, false), frame(0.7, to, 1.0, 1.0, false), frame(1.0, to, 1.0, 1.0, true)],
+    };
+    Track {
+        kind,
+        player_id: player.map(|(p, _)| p.id.clone()),
+        seat: player.map(|(_, seat)| seat),
+        amount: None,
+        chips: Vec::new(),
+        card: Some(card),
+        start_ms,
+        duration_ms: CARD_MS,
+        easing: "ease_in_out",
+        keyframes,
+    }
+}
+
+// Lay out what changed between two states of a table
+fn timeline(before: &TableMirror, after: &TableMirror, layout: &Layout) -> Vec<Track> {
+    let mut tracks = Vec::new();
+    let paired: Vec<(&SeatState, Option<&SeatState>, usize)> = after
+        .players
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (p, before.player(&p.id), seat_of(p, i)))
+        .collect();
+    let same_hand = before.hand_number == after.hand_number || after.hand_number == 0;
+
+    // Bets swept into the pot: the street moved on or the hand ended with bets out.
+    // A call that closed the street is swept with the rest of the bet.
+    let hand_ended = same_hand && (after.pot < before.pot || (after.is_hand_over() && !before.is_hand_over()));
+    let street_ended = same_hand && (before.phase != after.phase || hand_ended);
+    let mut clock = 0;
+    if street_ended {
+        let mut swept = false;
+        for (player, previous, seat) in &paired {
+            let Some(previous) = previous else { continue };
+            let amount = previous.current_bet + previous.chips.saturating_sub(player.chips);
+            if previous.current_bet > 0 && amount > 0 {
+                tracks.push(chip_track(TrackKind::Sweep, player, *seat, amount, layout.bet_spot(*seat), POT, 0));
+                swept = true;
+            }
+        }
+        if swept {
+            clock = SWEEP_MS + PAUSE_MS;
+        }
+    }
+
+    // New bets, from the stack to the bet spot
+    for (player, previous, seat) in &paired {
+        let previous_bet = previous.filter(|_| same_hand).map_or(0, |p| p.current_bet);
+        if player.current_bet > previous_bet && !street_ended {
+            let amount = player.current_bet - previous_bet;
+            tracks.push(chip_track(TrackKind::Bet, player, *seat, amount, layout.seat(*seat), layout.bet_spot(*seat), 0));
+        }
+    }
+
+    // Board cards, one after another
+    let dealt = if same_hand { before.community_cards.len() } else { 0 };
+    for (i, card) in after.community_cards.iter().enumerate().skip(dealt) {
+        let start = clock + (i - dealt) as u32 * CARD_STAGGER_MS;
+        tracks.push(card_track(TrackKind::DealBoard, card_name(card), None, DECK, layout.board_slot(i), start));
+    }
+    if after.community_cards.len() > dealt {
+        clock += CARD_MS + (after.community_cards.len() - dealt - 1) as u32 * CARD_STAGGER_MS + PAUSE_MS;
+    }
+
+    // Hole cards: dealt face down to the hero at the start, shown at showdown
+    let mut card_index = 0;
+    for (player, previous, seat) in &paired {
+        let had = previous.filter(|_| same_hand).and_then(|p| p.cards.as_ref()).is_some_and(|c| !c.is_empty());
+        let Some(cards) = player.cards.as_ref().filter(|c| !c.is_empty() && !had) else { continue };
+        let kind = if after.is_hand_over() { TrackKind::Reveal } else { TrackKind::DealHole };
+        let spot = layout.seat(*seat);
+        for (i, card) in cards.iter().enumerate() {
+            let to = Point { x: spot.x + (i as f64 - 0.5) * 0.03, y: spot.y - 0.05 };
+            let start = clock + card_index * CARD_STAGGER_MS;
+            tracks.push(card_track(kind, card_name(card), Some((player, *seat)), DECK, to, start));
+            card_index += 1;
+        }
+    }
+    if card_index > 0 {
+        clock += CARD_MS + (card_index - 1) * CARD_STAGGER_MS + PAUSE_MS;
+    }
+
+    // The pot, or its split, going out to the winners
+    if hand_ended {
+        for (player, previous, seat) in &paired {
+            let Some(previous) = previous else { continue };
+            let won = player.chips.saturating_sub(previous.chips);
+            if won > 0 {
+                tracks.push(chip_track(TrackKind::Award, player, *seat, won, POT, layout.seat(*seat), clock));
+            }
+        }
+    }
+    tracks
+}
+
+// Feed one frame received at `table_id` and get the animations for it; None when
+// nothing on the table moved. `max_seats` spaces the seats when the table is not full.
+#[tauri::command]
+pub async fn animate_table_event(
+    app: AppHandle,
+    state: State<'_, AnimationState>,
+    table_id: String,
+    message: WsMessage,
+    max_seats: Option<u8>,
+) -> Result<Option<AnimationTimeline>, String> {
+    let mut tables = state.tables.lock().map_err(|_| "Animation lock poisoned".to_string())?;
+    if !tables.contains_key(&table_id) && tables.len() >= MAX_TABLES {
+        if let Some(oldest) = tables.keys().next().cloned() {
+            tables.remove(&oldest);
+        }
+    }
+    let before = tables.entry(table_id.clone()).or_default();
+    let after = match message.kind.as_str() {
+        "table_state_update" | "state_sync_response" => preview::mirror_from_state(&message.payload)?,
+        _ => {
+            let mut after = before.clone();
+            after.apply(&message)?;
+            after
+        }
+    };
+    let layout = Layout::new(&after, max_seats);
+    let tracks = timeline(before, &after, &layout);
+    *before = after;
+    if tracks.is_empty() {
+        return Ok(None);
+    }
+
+    let timeline = AnimationTimeline {
+        table_id,
+        event: message.kind,
+        hand_number: before.hand_number,
+        duration_ms: tracks.iter().map(|t| t.start_ms + t.duration_ms).max().unwrap_or(0),
+        tracks,
+    };
+    let _ = app.emit_all("animation_timeline", timeline.clone());
+    Ok(Some(timeline))
+}
+
+// Called when a table view closes
+#[tauri::command]
+pub async fn forget_table_animations(state: State<'_, AnimationState>, table_id: String) -> Result<(), String> {
+    state.tables.lock().map_err(|_| "Animation lock poisoned".to_string())?.remove(&table_id);
+    Ok(())
+}
@@ -0,0 +1,300 @@
+// Server announcements: maintenance windows, promotions and the message of the day.
+// They are fetched once the database is open and again every few minutes, and the
+// backend can push new ones over the notification relay. The list and what the
+// player has read or dismissed are kept in the kv table, so a dismissed banner stays
+// dismissed across launches. An upcoming maintenance window also raises
+// `maintenance_warning` at fixed lead times while the player is seated somewhere,
+// naming the tables that will be affected; maintenance.rs then winds play down
+// before the window starts.
+
+use crate::db::{Database, Db};
+use crate::idle::IdleState;
+use crate::maintenance;
+use crate::profile::BackendProfile;
+use crate::ws::WsMessage;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const KEY_CACHE: &str = "announcements.cache";
+const KEY_STATE: &str = "announcements.state";
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const FETCH_EVERY: u32 = 20;
+// Minutes before a maintenance window at which seated players are warned
+const WARNING_LEADS: &[i64] = &[30, 10, 5, 1];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Announcement {
+    id: String,
+    // maintenance | promotion | motd | info
+    kind: String,
+    title: String,
+    #[serde(default)]
+    body: String,
+    #[serde(default)]
+    link: Option<String>,
+    // For maintenance, when the window starts and ends
+    #[serde(default)]
+    starts_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    ends_at: Option<DateTime<Utc>>,
+    // Not shown after this
+    #[serde(default)]
+    expires_at: Option<DateTime<Utc>>,
+}
+
+impl Announcement {
+    fn is_live(&self, now: DateTime<Utc>) -> bool {
+        let over = self.expires_at.or(self.ends_at);
+        over.is_none_or(|at| at > now)
+    }
+}
+
+// A maintenance announcement's window, for the shutdown in maintenance.rs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceWindow {
+    pub id: String,
+    pub title: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct ReadState {
+    read: HashSet<String>,
+    dismissed: HashSet<String>,
+    // Smallest lead time already warned about, per maintenance announcement
+    warned: HashMap<String, i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnouncementView {
+    #[serde(flatten)]
+    announcement: Announcement,
+    read: bool,
+    dismissed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MaintenanceWarning {
+    announcement_id: String,
+    title: String,
+    starts_at: DateTime<Utc>,
+    minutes: i64,
+    // Seated tables the window will interrupt
+    tables: Vec<String>,
+}
+
+fn load<T: serde::de::DeserializeOwned + Default>(db: &Database, key: &str) -> Result<T, String> {
+    match db.get_value(key)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid {}: {}", key, e)),
+        None => Ok(T::default()),
+    }
+}
+
+fn save<T: Serialize>(db: &Database, key: &str, value: &T) -> Result<(), String> {
+    let data = serde_json::to_string(value).map_err(|e| e.to_string())?;
+    db.set_value(key, &data)
+}
+
+fn views(db: &Database, include_dismissed: bool) -> Result<Vec<AnnouncementView>, String> {
+    let now = Utc::now();
+    let announcements: Vec<Announcement> = load(db, KEY_CACHE)?;
+    let state: ReadState = load(db, KEY_STATE)?;
+    Ok(announcements
+        .into_iter()
+        .filter(|a| a.is_live(now))
+        .map(|a| AnnouncementView {
+            read: state.read.contains(&a.id),
+            dismissed: state.dismissed.contains(&a.id),
+            announcement: a,
+        })
+        .filter(|v| include_dismissed || !v.dismissed)
+        .collect())
+}
+
+fn emit_updated(app: &AppHandle, db: &Database) {
+    match views(db, false) {
+        Ok(views) => {
+            let _ = app.emit_all("announcements_updated", views);
+        }
+        Err(e) => eprintln!("Failed to load announcements: {}", e),
+    }
+}
+
+// Replace the cached list and drop read state for announcements that are gone
+fn store(db: &Database, announcements: &[Announcement]) -> Result<(), String> {
+    save(db, KEY_CACHE, &announcements)?;
+    let mut state: ReadState = load(db, KEY_STATE)?;
+    let ids: HashSet<&str> = announcements.iter().map(|a| a.id.as_str()).collect();
+    state.read.retain(|id| ids.contains(id.as_str()));
+    state.dismissed.retain(|id| ids.contains(id.as_str()));
+    state.warned.retain(|id, _| ids.contains(id.as_str()));
+    save(db, KEY_STATE, &state)
+}
+
+async fn fetch(api_url: &str) -> Result<Vec<Announcement>, String> {
+    let client = crate::create_http_client()?;
+    let mut request = client.get(format!("{}/api/announcements", api_url));
+    if let Ok(token) = crate::get_token_from_keyring() {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+
+    let response = crate::http::send(request).await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err("Failed to fetch announcements".to_string());
+    }
+
+    let api_response: crate::ApiResponse<Vec<Announcement>> = response.json().await
+        .map_err(|e| format!("Failed to parse announcements: {}", e))?;
+    match api_response.data {
+        Some(announcements) if api_response.success => Ok(announcements),
+        _ => Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string())),
+    }
+}
+
+// Announced maintenance windows that have not ended, soonest first
+pub fn maintenance_windows(db: &Database, now: DateTime<Utc>) -> Result<Vec<MaintenanceWindow>, String> {
+    let announcements: Vec<Announcement> = load(db, KEY_CACHE)?;
+    let mut windows: Vec<MaintenanceWindow> = announcements
+        .into_iter()
+        .filter(|a| a.kind == "maintenance" && a.is_live(now))
+        .filter_map(|a| {
+            let starts_at = a.starts_at?;
+            Some(MaintenanceWindow { id: a.id, title: a.title, starts_at, ends_at: a.ends_at })
+        })
+        .collect();
+    windows.sort_by_key(|w| w.starts_at);
+    Ok(windows)
+}
+
+// An announcement pushed over the notification relay
+pub fn receive(app: &AppHandle, message: &WsMessage) {
+    let announcement: Announcement = match serde_json::from_value(message.payload.clone()) {
+        Ok(announcement) => announcement,
+        Err(e) => {
+            eprintln!("Ignoring malformed announcement: {}", e);
+            return;
+        }
+    };
+    let Some(db) = app.try_state::<Database>() else { return };
+    let result = load::<Vec<Announcement>>(&db, KEY_CACHE).and_then(|mut announcements| {
+        announcements.retain(|a| a.id != announcement.id);
+        announcements.push(announcement);
+        store(&db, &announcements)
+    });
+    match result {
+        Ok(()) => emit_updated(app, &db),
+        Err(e) => eprintln!("Failed to store announcement: {}", e),
+    }
+}
+
+// Warn once per lead time about maintenance starting while the player is seated
+fn check_maintenance(app: &AppHandle, db: &Database, now: DateTime<Utc>) -> Result<(), String> {
+    let tables = app.state::<IdleState>().seated_tables();
+    if tables.is_empty() {
+        return Ok(());
+    }
+    let announcements: Vec<Announcement> = load(db, KEY_CACHE)?;
+    let mut state: ReadState = load(db, KEY_STATE)?;
+    let mut changed = false;
+    for announcement in announcements.iter().filter(|a| a.kind == "maintenance") {
+        let Some(starts_at) = announcement.starts_at else { continue };
+        let minutes = (starts_at - now).num_minutes();
+        if minutes < 0 {
+            continue;
+        }
+        // The closest lead time already reached
+        let Some(&lead) = WARNING_LEADS.iter().rev().find(|&&lead| minutes < lead) else { continue };
+        if state.warned.get(&announcement.id).is_some_and(|&warned| warned <= lead) {
+            continue;
+        }
+        state.warned.insert(announcement.id.clone(), lead);
+        changed = true;
+
+        let warning = MaintenanceWarning {
+            announcement_id: announcement.id.clone(),
+            title: announcement.title.clone(),
+            starts_at,
+            minutes,
+            tables: tables.clone(),
+        };
+        let shown = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier)
+            .title("Scheduled maintenance")
+            .body(format!("Maintenance starts in {} min; {} of your tables will be interrupted", minutes.max(1), tables.len()))
+            .show();
+        if let Err(e) = shown {
+            eprintln!("Failed to show maintenance warning: {}", e);
+        }
+        let _ = app.emit_all("maintenance_warning", warning);
+    }
+    if changed {
+        save(db, KEY_STATE, &state)?;
+    }
+    Ok(())
+}
+
+// Fetch loop and maintenance warnings, started once the database is open
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut checks = 0;
+        loop {
+            let Some(db) = app.try_state::<Database>() else { return };
+            if checks % FETCH_EVERY == 0 {
+                let api_url = app.state::<BackendProfile>().api_url.clone();
+                match fetch(&api_url).await {
+                    Ok(announcements) => match store(&db, &announcements) {
+                        Ok(()) => emit_updated(&app, &db),
+                        Err(e) => eprintln!("Failed to store announcements: {}", e),
+                    },
+                    Err(e) => eprintln!("Announcement fetch failed, using cached copy: {}", e),
+                }
+            }
+            checks += 1;
+
+            let now = Utc::now();
+            if let Err(e) = check_maintenance(&app, &db, now) {
+                eprintln!("Maintenance warning check failed: {}", e);
+            }
+            if let Err(e) = maintenance::check(&app, &db, now) {
+                eprintln!("Maintenance shutdown check failed: {}", e);
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+// Current announcements, without dismissed ones unless asked for
+#[tauri::command]
+pub async fn get_announcements(
+    db: Db<'_>,
+    include_dismissed: Option<bool>,
+) -> Result<Vec<AnnouncementView>, String> {
+    views(&db, include_dismissed.unwrap_or(false))
+}
+
+#[tauri::command]
+pub async fn mark_announcement_read(db: Db<'_>, id: String) -> Result<(), String> {
+    let mut state: ReadState = load(&db, KEY_STATE)?;
+    if state.read.insert(id) {
+        save(&db, KEY_STATE, &state)?;
+    }
+    Ok(())
+}
+
+// Hide an announcement's banner for good; dismissing also marks it read
+#[tauri::command]
+pub async fn dismiss_announcement(db: Db<'_>, id: String) -> Result<(), String> {
+    let mut state: ReadState = load(&db, KEY_STATE)?;
+    state.read.insert(id.clone());
+    state.dismissed.insert(id);
+    save(&db, KEY_STATE, &state)
+}
@@ -0,0 +1,176 @@
+use crate::db::{Database, Db};
+use crate::history::to_millis;
+use chrono::{DateTime, TimeZone, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+
+// Hash used as the predecessor of the first entry
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    id: i64,
+    timestamp: DateTime<Utc>,
+    action: String,
+    detail: serde_json::Value,
+    success: bool,
+    error: Option<String>,
+    prev_hash: String,
+    hash: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditFilter {
+    action: Option<String>,
+    success: Option<bool>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditVerification {
+    valid: bool,
+    entries_checked: usize,
+    first_invalid_id: Option<i64>,
+}
+
+fn entry_hash(prev_hash: &str, timestamp: i64, action: &str, detail: &str, success: bool, error: Option<&str>) -> String {
+    let mut hasher = Sha256::new();
+    for part in [prev_hash, &timestamp.to_string(), action, detail, if success { "1" } else { "0" }, error.unwrap_or("")] {
+        hasher.update(part.as_bytes());
+        hasher.update([0u8]);
+    }
+    hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn append(conn: &Connection, action: &str, detail: &serde_json::Value, error: Option<&str>) -> rusqlite::Result<()> {
+    let tx = conn.unchecked_transaction()?;
+
+    let prev_hash: String = tx
+        .query_row("SELECT hash FROM audit_log ORDER BY id DESC LIMIT 1", [], |row| row.get(0))
+        .optional()?
+        .unwrap_or_else(|| GENESIS_HASH.to_string());
+
+    let timestamp = to_millis(&Utc::now());
+    let detail = detail.to_string();
+    let success = error.is_none();
+    let hash = entry_hash(&prev_hash, timestamp, action, &detail, success, error);
+
+    tx.execute(
+        "INSERT INTO audit_log (timestamp, action, detail, success, error, prev_hash, hash)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![timestamp, action, detail, success, error, prev_hash, hash],
+    )?;
+
+    tx.commit()
+}
+
+// Record the outcome of an authenticated command. Auditing never fails the command
+// itself; when the database is unavailable the entry is only logged to stderr.
+pub fn record<T, E: std::fmt::Display>(
+    app: &AppHandle,
+    action: &str,
+    detail: serde_json::Value,
+    result: &Result<T, E>,
+) {
+    let error = result.as_ref().err().map(|e| e.to_string());
+
+    let Some(db) = app.try_state::<Database>() else {
+        eprintln!("Audit log unavailable, dropping entry for {}", action);
+        return;
+    };
+
+    if let Err(e) = db.with_conn(|conn| append(conn, action, &detail, error.as_deref())) {
+        eprintln!("Failed to write audit entry for {}: {}", action, e);
+    }
+}
+
+fn entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<AuditEntry> {
+    let timestamp: i64 = row.get(1)?;
+    let detail: String = row.get(3)?;
+    Ok(AuditEntry {
+        id: row.get(0)?,
+        timestamp: Utc.timestamp_millis_opt(timestamp).single().unwrap_or_else(Utc::now),
+        action: row.get(2)?,
+        detail: serde_json::from_str(&detail).unwrap_or(serde_json::Value::Null),
+        success: row.get(4)?,
+        error: row.get(5)?,
+        prev_hash: row.get(6)?,
+        hash: row.get(7)?,
+    })
+}
+
+#[tauri::command]
+pub async fn get_audit_log(db: Db<'_>, filters: Option<AuditFilter>) -> Result<Vec<AuditEntry>, String> {
+    let filters = filters.unwrap_or_default();
+
+    db.with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, timestamp, action, detail, success, error, prev_hash, hash FROM audit_log
+             WHERE (?1 IS NULL OR action = ?1)
+               AND (?2 IS NULL OR success = ?2)
+               AND (?3 IS NULL OR timestamp >= ?3)
+               AND (?4 IS NULL OR timestamp <= ?4)
+             ORDER BY id DESC LIMIT ?5",
+        )?;
+        let rows = stmt.query_map(
+            params![
+                filters.action,
+                filters.success,
+                filters.since.as_ref().map(to_millis),
+                filters.until.as_ref().map(to_millis),
+                filters.limit.unwrap_or(200)
+            ],
+            entry_from_row,
+        )?;
+        rows.collect()
+    })
+}
+
+// Walk the chain from the start and report the first entry whose hash does not match
+#[tauri::command]
+pub async fn verify_audit_log(db: Db<'_>) -> Result<AuditVerification, String> {
+    let entries = db.with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, timestamp, action, detail, success, error, prev_hash, hash FROM audit_log ORDER BY id ASC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, bool>(4)?,
+                row.get::<_, Option<String>>(5)?,
+                row.get::<_, String>(6)?,
+                row.get::<_, String>(7)?,
+            ))
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+    })?;
+
+    let mut expected_prev = GENESIS_HASH.to_string();
+    for (index, (id, timestamp, action, detail, success, error, prev_hash, hash)) in entries.iter().enumerate() {
+        let recomputed = entry_hash(prev_hash, *timestamp, action, detail, *success, error.as_deref());
+        if *prev_hash != expected_prev || recomputed != *hash {
+            return Ok(AuditVerification {
+                valid: false,
+                entries_checked: index,
+                first_invalid_id: Some(*id),
+            });
+        }
+        expected_prev = hash.clone();
+    }
+
+    Ok(AuditVerification {
+        valid: true,
+        entries_checked: entries.len(),
+        first_invalid_id: None,
+    })
+}
@@ -0,0 +1,328 @@
+// Bankroll management. The roll is the wallet balance plus the chips in front of the
+// player at every table the views still report (see thumbnails.rs). The rules say how
+// many buy-ins of a game the roll should cover, 30 for cash and 100 for sit-and-gos by
+// default, which caps the stakes it can carry: a cash buy-in is counted as 100 big
+// blinds. While the player is seated a background loop checks the tables they are at
+// and raises `bankroll_over_rolled` once for each table above the cap; quick seat
+// uses the same cap to filter the stakes it suggests.
+
+use crate::db::{self, Database, Db};
+use crate::maintenance;
+use crate::profile::BackendProfile;
+use crate::thumbnails::ThumbnailState;
+use crate::tournaments;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const KEY_RULES: &str = "bankroll.rules";
+const CHECK_INTERVAL: Duration = Duration::from_secs(120);
+// Chips taken to a cash table, in big blinds
+const CASH_BUY_IN_BB: u64 = 100;
+const MAX_BUY_INS: f64 = 10_000.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BankrollRules {
+    // Warn about over-rolled tables
+    enabled: bool,
+    // Buy-ins the roll should cover for each kind of game
+    cash_buy_ins: f64,
+    sng_buy_ins: f64,
+    mtt_buy_ins: f64,
+}
+
+impl Default for BankrollRules {
+    fn default() -> Self {
+        Self { enabled: true, cash_buy_ins: 30.0, sng_buy_ins: 100.0, mtt_buy_ins: 200.0 }
+    }
+}
+
+impl BankrollRules {
+    fn validate(&self) -> Result<(), String> {
+        for (name, buy_ins) in [("cash", self.cash_buy_ins), ("sit-and-go", self.sng_buy_ins), ("tournament", self.mtt_buy_ins)] {
+            if !(1.0..=MAX_BUY_INS).contains(&buy_ins) {
+                return Err(format!("The {} rule must be between 1 and {} buy-ins", name, MAX_BUY_INS));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableStack {
+    table_id: String,
+    stack: u32,
+    big_blind: u32,
+    // Buy-ins of this stake the roll covers
+    buy_ins_held: f64,
+    over_rolled: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BankrollStatus {
+    wallet: u64,
+    at_tables: u64,
+    roll: u64,
+    rules: BankrollRules,
+    // Highest stakes the roll carries under the rules
+    max_cash_big_blind: u32,
+    max_sng_buy_in: u64,
+    max_mtt_buy_in: u64,
+    tables: Vec<TableStack>,
+    checked_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StakeSuggestion {
+    small_blind: u32,
+    big_blind: u32,
+    buy_in: u64,
+    tables: u32,
+    open_seats: u32,
+    within_roll: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentSuggestion {
+    tournament_id: String,
+    name: String,
+    start_time: DateTime<Utc>,
+    buy_in: u64,
+    sit_and_go: bool,
+    within_roll: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickSeatStakes {
+    roll: u64,
+    max_cash_big_blind: u32,
+    // Highest stakes first
+    stakes: Vec<StakeSuggestion>,
+    tournaments: Vec<TournamentSuggestion>,
+}
+
+#[derive(Default)]
+pub struct BankrollState {
+    // Tables already warned about, so each raises one warning while over-rolled
+    warned: Mutex<HashSet<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WalletBalance {
+    balance: u64,
+}
+
+fn load_rules(db: &Database) -> Result<BankrollRules, String> {
+    match db.get_value(KEY_RULES)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid bankroll rules: {}", e)),
+        None => Ok(BankrollRules::default()),
+    }
+}
+
+fn cash_buy_in(big_blind: u32) -> u64 {
+    big_blind as u64 * CASH_BUY_IN_BB
+}
+
+fn max_buy_in(roll: u64, buy_ins: f64) -> u64 {
+    (roll as f64 / buy_ins) as u64
+}
+
+fn buy_ins_held(roll: u64, buy_in: u64) -> f64 {
+    roll as f64 / buy_in.max(1) as f64
+}
+
+pub async fn fetch_wallet(api_url: &str) -> Result<u64, String> {
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+    let client = crate::create_http_client()?;
+    let response = crate::http::send(client.get(format!("{}/api/wallet/balance", api_url))
+        .header("Authorization", format!("Bearer {}", token)))
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err("Failed to fetch wallet balance".to_string());
+    }
+
+    let api_response: crate::ApiResponse<WalletBalance> = response.json().await
+        .map_err(|e| format!("Failed to parse wallet balance: {}", e))?;
+    match api_response.data {
+        Some(wallet) if api_response.success => Ok(wallet.balance),
+        _ => Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string())),
+    }
+}
+
+async fn status(app: &AppHandle, api_url: &str) -> Result<BankrollStatus, String> {
+    let rules = load_rules(&*db::get(app)?)?;
+    let stacks = app.state::<ThumbnailState>().hero_stacks()?;
+    let wallet = fetch_wallet(api_url).await?;
+    let at_tables: u64 = stacks.iter().map(|(_, stack, _)| *stack as u64).sum();
+    let roll = wallet + at_tables;
+
+    let mut tables: Vec<TableStack> = stacks
+        .into_iter()
+        .map(|(table_id, stack, big_blind)| {
+            let held = buy_ins_held(roll, cash_buy_in(big_blind));
+            TableStack { table_id, stack, big_blind, buy_ins_held: held, over_rolled: held < rules.cash_buy_ins }
+        })
+        .collect();
+    tables.sort_by_key(|t| std::cmp::Reverse(t.big_blind));
+    Ok(BankrollStatus {
+        wallet,
+        at_tables,
+        roll,
+        max_cash_big_blind: (max_buy_in(roll, rules.cash_buy_ins) / CASH_BUY_IN_BB) as u32,
+        max_sng_buy_in: max_buy_in(roll, rules.sng_buy_ins),
+        max_mtt_buy_in: max_buy_in(roll, rules.mtt_buy_ins),
+        rules,
+        tables,
+        checked_at: Utc::now(),
+    })
+}
+
+// Raise `bankroll_over_rolled` for tables that went over the cap since the last check
+fn warn(app: &AppHandle, status: &BankrollStatus) -> Result<(), String> {
+    let state = app.state::<BankrollState>();
+    let mut warned = state.warned.lock().map_err(|_| "Bankroll lock poisoned".to_string())?;
+    let over: Vec<&TableStack> = status.tables.iter().filter(|t| t.over_rolled).collect();
+    warned.retain(|id| over.iter().any(|t| &t.table_id == id));
+    if !status.rules.enabled {
+        return Ok(());
+    }
+    for table in over {
+        if warned.insert(table.table_id.clone()) {
+            let _ = app.emit_all("bankroll_over_rolled", table.clone());
+        }
+    }
+    Ok(())
+}
+
+// Background check of the tables the player is seated at, started once the database
+// is open. Nothing is fetched while they are not seated anywhere.
+pub fn start_monitor(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            let seated = app.state::<ThumbnailState>().hero_stacks().is_ok_and(|s| !s.is_empty());
+            if !seated || maintenance::is_draining(&app) {
+                continue;
+            }
+            let api_url = app.state::<BackendProfile>().api_url.clone();
+            if let Err(e) = status(&app, &api_url).await.and_then(|s| warn(&app, &s)) {
+                eprintln!("Bankroll check failed: {}", e);
+            }
+        }
+    });
+}
+
+fn tournament_buy_in(tournament: &tournaments::Tournament) -> Option<u64> {
+    tournament.details.get("buyIn").and_then(|v| v.as_u64())
+}
+
+fn is_sit_and_go(tournament: &tournaments::Tournament) -> bool {
+    ["type", "format"].iter().any(|key| {
+        tournament
+            .details
+            .get(*key)
+            .and_then(|v| v.as_str())
+            .is_some_and(|s| matches!(s.to_lowercase().as_str(), "sng" | "sit_and_go" | "sit-and-go" | "sitandgo"))
+    })
+}
+
+#[tauri::command]
+pub async fn get_bankroll_rules(db: Db<'_>) -> Result<BankrollRules, String> {
+    load_rules(&db)
+}
+
+#[tauri::command]
+pub async fn set_bankroll_rules(
+    app: AppHandle,
+    db: Db<'_>,
+    rules: BankrollRules,
+) -> Result<BankrollRules, String> {
+    rules.validate()?;
+    let data = serde_json::to_string(&rules).map_err(|e| e.to_string())?;
+    db.set_value(KEY_RULES, &data)?;
+    // Warn again under the new rules
+    app.state::<BankrollState>().warned.lock().map_err(|_| "Bankroll lock poisoned".to_string())?.clear();
+    Ok(rules)
+}
+
+// The roll and the stakes it carries; also raises any warning now due
+#[tauri::command]
+pub async fn get_bankroll_status(app: AppHandle, api_url: String) -> Result<BankrollStatus, String> {
+    let status = status(&app, &api_url).await?;
+    warn(&app, &status)?;
+    Ok(status)
+}
+
+// Stakes with an open seat for quick seat, and upcoming tournaments from the cached
+// schedule, marked by whether the roll covers them. Only those within the rules are
+// returned unless `include_over_rolled` is set.
+#[tauri::command]
+pub async fn get_quick_seat_stakes(
+    app: AppHandle,
+    api_url: String,
+    include_over_rolled: Option<bool>,
+) -> Result<QuickSeatStakes, String> {
+    let include_over_rolled = include_over_rolled.unwrap_or(false);
+    let status = status(&app, &api_url).await?;
+    let rules = &status.rules;
+
+    let mut by_stake: BTreeMap<(u32, u32), StakeSuggestion> = BTreeMap::new();
+    for table in crate::fetch_tables(&api_url).await? {
+        let open = table.max_players.saturating_sub(table.player_count) as u32;
+        if open == 0 {
+            continue;
+        }
+        let buy_in = cash_buy_in(table.blinds.big);
+        let stake = by_stake.entry((table.blinds.big, table.blinds.small)).or_insert(StakeSuggestion {
+            small_blind: table.blinds.small,
+            big_blind: table.blinds.big,
+            buy_in,
+            tables: 0,
+            open_seats: 0,
+            within_roll: buy_ins_held(status.roll, buy_in) >= rules.cash_buy_ins,
+        });
+        stake.tables += 1;
+        stake.open_seats += open;
+    }
+    let stakes = by_stake
+        .into_values()
+        .rev()
+        .filter(|s| include_over_rolled || s.within_roll)
+        .collect();
+
+    let now = Utc::now();
+    let db = db::get(&app)?;
+    let mut tournaments: Vec<TournamentSuggestion> = tournaments::cached_schedule(&db)?
+        .into_iter()
+        .filter(|t| t.start_time > now || is_sit_and_go(t))
+        .filter_map(|t| {
+            let buy_in = tournament_buy_in(&t)?;
+            let sit_and_go = is_sit_and_go(&t);
+            let required = if sit_and_go { rules.sng_buy_ins } else { rules.mtt_buy_ins };
+            Some(TournamentSuggestion {
+                within_roll: buy_ins_held(status.roll, buy_in) >= required,
+                tournament_id: t.id,
+                name: t.name,
+                start_time: t.start_time,
+                buy_in,
+                sit_and_go,
+            })
+        })
+        .filter(|t| include_over_rolled || t.within_roll)
+        .collect();
+    tournaments.sort_by_key(|t| t.start_time);
+
+    Ok(QuickSeatStakes { roll: status.roll, max_cash_big_blind: status.max_cash_big_blind, stakes, tournaments })
+}
@@ -0,0 +1,203 @@
+// Active bonuses and their clearing requirements. A bonus is released once enough
+// rake has been paid, or enough wagered, after it was granted. The backend tracks
+// the real progress; between syncs, each saved hand adds the hero's rake share (see
+// loyalty.rs) and what they put in, so progress bars move after every hand. Local
+// amounts are dropped at the next sync, since the backend has counted the same
+// hands by then. Reaching the requirement emits `bonus_cleared` and shows a desktop
+// notification.
+
+use crate::db::{Database, Db};
+use crate::ev::committed;
+use crate::history::HandRecord;
+use crate::loyalty::hero_rake;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+const KEY_STATE: &str = "bonuses.state";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bonus {
+    id: String,
+    name: String,
+    // Released to the wallet once cleared
+    amount: f64,
+    // rake | wagered
+    #[serde(default = "default_requirement")]
+    requirement: String,
+    target: f64,
+    #[serde(default)]
+    progress: f64,
+    #[serde(default)]
+    cleared: bool,
+    #[serde(default)]
+    expires_at: Option<DateTime<Utc>>,
+}
+
+fn default_requirement() -> String {
+    "rake".to_string()
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct LocalProgress {
+    rake: f64,
+    wagered: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct BonusState {
+    bonuses: Vec<Bonus>,
+    synced_at: Option<DateTime<Utc>>,
+    // Since the last sync
+    local: LocalProgress,
+    // Cleared locally but not yet confirmed by a sync
+    cleared_locally: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BonusProgress {
+    #[serde(flatten)]
+    bonus: Bonus,
+    // 0 to 100
+    percent: f64,
+    remaining: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BonusesView {
+    bonuses: Vec<BonusProgress>,
+    synced_at: Option<DateTime<Utc>>,
+}
+
+impl BonusState {
+    // Bonuses with local progress applied, expired ones left out
+    fn view(&self) -> Vec<BonusProgress> {
+        let now = Utc::now();
+        self.bonuses
+            .iter()
+            .filter(|bonus| bonus.expires_at.is_none_or(|at| at > now))
+            .map(|bonus| {
+                let local = if bonus.requirement == "wagered" { self.local.wagered } else { self.local.rake };
+                let progress = if bonus.cleared { bonus.target } else { (bonus.progress + local).min(bonus.target) };
+                let percent = if bonus.target > 0.0 { progress / bonus.target * 100.0 } else { 100.0 };
+                BonusProgress {
+                    percent,
+                    remaining: bonus.target - progress,
+                    bonus: Bonus {
+                        progress,
+                        cleared: bonus.cleared || self.cleared_locally.contains(&bonus.id),
+                        ..bonus.clone()
+                    },
+                }
+            })
+            .collect()
+    }
+
+    fn split(&self) -> BonusesView {
+        BonusesView { bonuses: self.view(), synced_at: self.synced_at }
+    }
+}
+
+fn load(db: &Database) -> Result<BonusState, String> {
+    match db.get_value(KEY_STATE)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid bonus state: {}", e)),
+        None => Ok(BonusState::default()),
+    }
+}
+
+fn save(db: &Database, state: &BonusState) -> Result<(), String> {
+    let data = serde_json::to_string(state).map_err(|e| e.to_string())?;
+    db.set_value(KEY_STATE, &data)
+}
+
+async fn fetch(api_url: &str) -> Result<Vec<Bonus>, String> {
+    let client = crate::create_http_client()?;
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+
+    let response = crate::http::send(client.get(format!("{}/api/bonuses/active", api_url))
+        .header("Authorization", format!("Bearer {}", token)))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        return Err("Failed to fetch bonuses".to_string());
+    }
+
+    let api_response: crate::ApiResponse<Vec<Bonus>> = response.json().await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    if api_response.success {
+        Ok(api_response.data.unwrap_or_default())
+    } else {
+        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    }
+}
+
+fn notify(app: &AppHandle, bonus: &Bonus) {
+    let shown = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier)
+        .title("Bonus cleared")
+        .body(format!("{} ({}) has been released", bonus.name, bonus.amount))
+        .show();
+    if let Err(e) = shown {
+        eprintln!("Failed to show bonus notification: {}", e);
+    }
+}
+
+// Advance local progress for a newly saved hand; practice tables do not count
+pub fn record_hand(app: &AppHandle, db: &Database, hand: &HandRecord) -> Result<(), String> {
+    let Some(hero) = hand.hero_id.as_deref() else { return Ok(()) };
+    if hand.table_id.starts_with("practice-") {
+        return Ok(());
+    }
+    let rake = hero_rake(hand);
+    let wagered = committed(hand).get(hero).copied().unwrap_or(0) as f64;
+    if rake == 0.0 && wagered == 0.0 {
+        return Ok(());
+    }
+
+    let mut state = load(db)?;
+    if state.bonuses.is_empty() {
+        return Ok(());
+    }
+    let before: Vec<String> = state.view().into_iter().filter(|b| b.bonus.cleared).map(|b| b.bonus.id).collect();
+    state.local.rake += rake;
+    state.local.wagered += wagered;
+
+    let view = state.view();
+    let cleared: Vec<Bonus> = view
+        .iter()
+        .filter(|b| b.remaining <= 0.0 && !before.contains(&b.bonus.id))
+        .map(|b| b.bonus.clone())
+        .collect();
+    state.cleared_locally.extend(cleared.iter().map(|b| b.id.clone()));
+    save(db, &state)?;
+
+    let _ = app.emit_all("bonus_progress", state.split());
+    for mut bonus in cleared {
+        bonus.cleared = true;
+        notify(app, &bonus);
+        let _ = app.emit_all("bonus_cleared", bonus);
+    }
+    Ok(())
+}
+
+// Locally tracked progress, without contacting the backend
+#[tauri::command]
+pub async fn get_bonuses(db: Db<'_>) -> Result<BonusesView, String> {
+    Ok(load(&db)?.split())
+}
+
+// Replace bonuses and progress with the backend's and drop local amounts
+#[tauri::command]
+pub async fn sync_bonuses(db: Db<'_>, api_url: String) -> Result<BonusesView, String> {
+    let bonuses = fetch(&api_url).await?;
+    let state = BonusState { bonuses, synced_at: Some(Utc::now()), ..BonusState::default() };
+    save(&db, &state)?;
+    Ok(state.split())
+}
@@ -0,0 +1,192 @@
+// In-process event bus. Producers publish typed events once and every subsystem that
+// needs them subscribes on its own, instead of the producer calling each in turn:
+// the WebSocket layer publishes every frame it decodes, by the kind of connection it
+// came in on, and hand history publishes each stored hand. Each topic is its own
+// broadcast channel, so a subscriber only wakes for the topics it asked for and a
+// slow one on a busy topic does not hold up the others. A subscriber that falls more
+// than a channel's capacity behind skips what it missed and the skip is counted.
+
+use crate::db::Database;
+use crate::history::HandRecord;
+use crate::ws::WsMessage;
+use futures_util::future::select_all;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use tauri::{AppHandle, Manager};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+const CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Topic {
+    // Frames from table connections held in this process
+    Table,
+    // The notification relay channel
+    Notification,
+    Voice,
+    // Hands as they are stored
+    Hand,
+}
+
+const TOPICS: [Topic; 4] = [Topic::Table, Topic::Notification, Topic::Voice, Topic::Hand];
+
+impl Topic {
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    Frame { topic: Topic, table_id: Option<String>, message: WsMessage },
+    // `is_new` when the hand was not stored before
+    HandSaved { hand: Box<HandRecord>, is_new: bool },
+}
+
+impl Event {
+    pub fn topic(&self) -> Topic {
+        match self {
+            Event::Frame { topic, .. } => *topic,
+            Event::HandSaved { .. } => Topic::Hand,
+        }
+    }
+}
+
+struct Channel {
+    sender: broadcast::Sender<Arc<Event>>,
+    published: AtomicU64,
+    skipped: AtomicU64,
+}
+
+static CHANNELS: OnceLock<Vec<Channel>> = OnceLock::new();
+
+fn channels() -> &'static [Channel] {
+    CHANNELS.get_or_init(|| {
+        TOPICS
+            .iter()
+            .map(|_| Channel {
+                sender: broadcast::channel(CAPACITY).0,
+                published: AtomicU64::new(0),
+                skipped: AtomicU64::new(0),
+            })
+            .collect()
+    })
+}
+
+fn channel(topic: Topic) -> &'static Channel {
+    &channels()[topic.index()]
+}
+
+pub fn publish(event: Event) {
+    let channel = channel(event.topic());
+    channel.published.fetch_add(1, Ordering::Relaxed);
+    // No subscriber is not an error
+    let _ = channel.sender.send(Arc::new(event));
+}
+
+pub struct Subscription {
+    receivers: Vec<(Topic, broadcast::Receiver<Arc<Event>>)>,
+}
+
+impl Subscription {
+    // The next event on any of the topics, None once the bus is gone
+    pub async fn recv(&mut self) -> Option<Arc<Event>> {
+        loop {
+            if self.receivers.is_empty() {
+                return None;
+            }
+            let (result, index, _) = select_all(self.receivers.iter_mut().map(|(_, r)| Box::pin(r.recv()))).await;
+            match result {
+                Ok(event) => return Some(event),
+                Err(RecvError::Lagged(missed)) => {
+                    channel(self.receivers[index].0).skipped.fetch_add(missed, Ordering::Relaxed);
+                }
+                Err(RecvError::Closed) => {
+                    self.receivers.remove(index);
+                }
+            }
+        }
+    }
+}
+
+// Events published from now on to any of `topics`
+pub fn subscribe(topics: &[Topic]) -> Subscription {
+    Subscription {
+        receivers: topics.iter().map(|topic| (*topic, channel(*topic).sender.subscribe())).collect(),
+    }
+}
+
+// Run `handler` for every event on `topics` for the life of the app. The subscription
+// is taken before this returns, so nothing published afterwards is missed.
+pub fn listen<F>(app: &AppHandle, topics: &[Topic], handler: F)
+where
+    F: Fn(&AppHandle, &Event) + Send + 'static,
+{
+    let mut subscription = subscribe(topics);
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        while let Some(event) = subscription.recv().await {
+            handler(&app, &event);
+        }
+    });
+}
+
+// Run `handler` for each hand stored for the first time; `what` names it in errors
+pub fn on_new_hand<F>(app: &AppHandle, what: &'static str, handler: F)
+where
+    F: Fn(&AppHandle, &Database, &HandRecord) -> Result<(), String> + Send + 'static,
+{
+    listen(app, &[Topic::Hand], move |app, event| {
+        let Event::HandSaved { hand, is_new: true } = event else { return };
+        let Some(db) = app.try_state::<Database>() else { return };
+        if let Err(e) = handler(app, &db, hand) {
+            eprintln!("Failed to update {}: {}", what, e);
+        }
+    });
+}
+
+// Run `handler` for each frame of `kind` on `topic`, or each with a kind starting
+// with it when it ends in `*`
+pub fn on_frame<F>(app: &AppHandle, topic: Topic, kind: &'static str, handler: F)
+where
+    F: Fn(&AppHandle, Option<&str>, &WsMessage) + Send + 'static,
+{
+    listen(app, &[topic], move |app, event| {
+        let Event::Frame { table_id, message, .. } = event else { return };
+        let matches = match kind.strip_suffix('*') {
+            Some(prefix) => message.kind.starts_with(prefix),
+            None => message.kind == kind,
+        };
+        if matches {
+            handler(app, table_id.as_deref(), message);
+        }
+    });
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicStats {
+    topic: Topic,
+    published: u64,
+    subscribers: usize,
+    // Events subscribers fell too far behind to see
+    skipped: u64,
+}
+
+#[tauri::command]
+pub async fn get_event_bus_stats() -> Result<Vec<TopicStats>, String> {
+    Ok(TOPICS
+        .iter()
+        .map(|topic| {
+            let channel = channel(*topic);
+            TopicStats {
+                topic: *topic,
+                published: channel.published.load(Ordering::Relaxed),
+                subscribers: channel.sender.receiver_count(),
+                skipped: channel.skipped.load(Ordering::Relaxed),
+            }
+        })
+        .collect())
+}
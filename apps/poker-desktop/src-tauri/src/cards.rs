@@ -0,0 +1,142 @@
+// Card primitives shared by the local engine, evaluator and history. Cards are written
+// in short notation ("As", "Td", "9c"); the wire format used by the backend spells
+// out suits and ranks ({ suit: "spades", rank: "A" }).
+
+use crate::table_state::Card as WireCard;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Suit {
+    Clubs,
+    Diamonds,
+    Hearts,
+    Spades,
+}
+
+pub const SUITS: [Suit; 4] = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+
+impl Suit {
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    fn symbol(self) -> char {
+        match self {
+            Suit::Clubs => 'c',
+            Suit::Diamonds => 'd',
+            Suit::Hearts => 'h',
+            Suit::Spades => 's',
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Suit::Clubs => "clubs",
+            Suit::Diamonds => "diamonds",
+            Suit::Hearts => "hearts",
+            Suit::Spades => "spades",
+        }
+    }
+
+    fn parse(text: &str) -> Option<Suit> {
+        match text.to_ascii_lowercase().as_str() {
+            "c" | "clubs" => Some(Suit::Clubs),
+            "d" | "diamonds" => Some(Suit::Diamonds),
+            "h" | "hearts" => Some(Suit::Hearts),
+            "s" | "spades" => Some(Suit::Spades),
+            _ => None,
+        }
+    }
+}
+
+// Rank 2..=14, ace high
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Card {
+    pub rank: u8,
+    pub suit: Suit,
+}
+
+pub fn rank_char(rank: u8) -> char {
+    match rank {
+        14 => 'A',
+        13 => 'K',
+        12 => 'Q',
+        11 => 'J',
+        10 => 'T',
+        n => char::from(b'0' + n),
+    }
+}
+
+pub fn parse_rank(text: &str) -> Option<u8> {
+    match text.to_ascii_uppercase().as_str() {
+        "A" => Some(14),
+        "K" => Some(13),
+        "Q" => Some(12),
+        "J" => Some(11),
+        "T" | "10" => Some(10),
+        n => n.parse().ok().filter(|r| (2..=9).contains(r)),
+    }
+}
+
+impl Card {
+    pub fn new(rank: u8, suit: Suit) -> Self {
+        Self { rank, suit }
+    }
+
+    // Parse short notation; "10s" is accepted alongside "Ts"
+    pub fn parse(text: &str) -> Option<Card> {
+        let text = text.trim();
+        let split = text.len().checked_sub(1)?;
+        if !text.is_char_boundary(split) {
+            return None;
+        }
+        let (rank, suit) = text.split_at(split);
+        Some(Card::new(parse_rank(rank)?, Suit::parse(suit)?))
+    }
+
+    pub fn from_wire(card: &WireCard) -> Option<Card> {
+        Some(Card::new(parse_rank(&card.rank)?, Suit::parse(&card.suit)?))
+    }
+
+    pub fn to_wire(self) -> WireCard {
+        let rank = match self.rank {
+            10 => "10".to_string(),
+            r => rank_char(r).to_string(),
+        };
+        WireCard { suit: self.suit.name().to_string(), rank }
+    }
+}
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", rank_char(self.rank), self.suit.symbol())
+    }
+}
+
+impl Serialize for Card {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Card::parse(&text).ok_or_else(|| serde::de::Error::custom(format!("invalid card '{}'", text)))
+    }
+}
+
+pub fn full_deck() -> Vec<Card> {
+    (2..=14)
+        .flat_map(|rank| SUITS.iter().map(move |&suit| Card::new(rank, suit)))
+        .collect()
+}
+
+pub fn shuffled_deck<R: Rng>(rng: &mut R) -> Vec<Card> {
+    let mut deck = full_deck();
+    deck.shuffle(rng);
+    deck
+}
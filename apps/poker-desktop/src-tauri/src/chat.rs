@@ -0,0 +1,229 @@
+// Outgoing table chat. Every message, typed or canned, goes through one pipeline here
+// that trims it, checks its length and rate-limits it per table before raising
+// `chat_send` with the frame for the table view's socket to send. Canned messages
+// ("nh", "ty" or the player's own phrases) are kept in the kv table and can be bound
+// to global hotkeys, which send to the table the player last had in focus.
+
+use crate::claims;
+use crate::db::{self, Database, Db};
+use crate::moderation;
+use crate::thumbnails::ThumbnailState;
+use crate::ws::WsMessage;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, GlobalShortcutManager, Manager, State};
+
+const KEY_CANNED: &str = "chat.canned";
+const MAX_MESSAGE_LEN: usize = 200;
+const MAX_CANNED: usize = 24;
+const MAX_ID_LEN: usize = 16;
+// Messages each table takes per window
+const RATE_WINDOW: Duration = Duration::from_secs(10);
+const MAX_PER_WINDOW: usize = 4;
+// The same text again within this long is dropped as a repeat
+const REPEAT_WINDOW: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CannedMessage {
+    id: String,
+    text: String,
+    // Accelerator such as "CmdOrCtrl+Shift+1"
+    #[serde(default)]
+    hotkey: Option<String>,
+}
+
+fn default_canned() -> Vec<CannedMessage> {
+    [("nh", "nh"), ("ty", "ty"), ("gg", "gg"), ("gl", "gl all")]
+        .iter()
+        .map(|(id, text)| CannedMessage { id: id.to_string(), text: text.to_string(), hotkey: None })
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutgoingChat {
+    table_id: String,
+    frame: WsMessage,
+}
+
+#[derive(Default)]
+pub struct ChatState {
+    // Send times and texts per table, oldest first
+    sent: Mutex<HashMap<String, VecDeque<(Instant, String)>>>,
+    focused_table: Mutex<Option<String>>,
+    // Accelerators registered for canned messages
+    hotkeys: Mutex<Vec<String>>,
+}
+
+fn load_canned(db: &Database) -> Result<Vec<CannedMessage>, String> {
+    match db.get_value(KEY_CANNED)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid canned messages: {}", e)),
+        None => Ok(default_canned()),
+    }
+}
+
+fn validate_text(text: &str) -> Result<String, String> {
+    let text = text.trim();
+    if text.is_empty() || text.chars().count() > MAX_MESSAGE_LEN {
+        return Err(format!("Chat messages must be 1 to {} characters", MAX_MESSAGE_LEN));
+    }
+    Ok(text.to_string())
+}
+
+fn validate_canned(messages: &[CannedMessage]) -> Result<(), String> {
+    if messages.len() > MAX_CANNED {
+        return Err(format!("At most {} canned messages can be kept", MAX_CANNED));
+    }
+    let mut ids = HashSet::new();
+    let mut hotkeys = HashSet::new();
+    for message in messages {
+        let valid_id = !message.id.is_empty()
+            && message.id.len() <= MAX_ID_LEN
+            && message.id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid_id {
+            return Err(format!("Canned message ids must be 1 to {} letters, digits, _ or -", MAX_ID_LEN));
+        }
+        if !ids.insert(message.id.as_str()) {
+            return Err(format!("Canned message id {} is used twice", message.id));
+        }
+        validate_text(&message.text)?;
+        moderation::require(moderation::Field::ChatMacro, &message.text)?;
+        if let Some(hotkey) = &message.hotkey {
+            if hotkey.trim().is_empty() || !hotkeys.insert(hotkey.to_lowercase()) {
+                return Err(format!("Hotkey {} is empty or bound twice", hotkey));
+            }
+        }
+    }
+    Ok(())
+}
+
+// The one way out for chat: checks the message and the table's rate, then raises
+// `chat_send` for the table view to put the frame on its socket
+fn send(app: &AppHandle, table_id: &str, text: &str) -> Result<OutgoingChat, String> {
+    let text = validate_text(text)?;
+    let player_id = claims::current()?.user_id;
+    let state = app.state::<ChatState>();
+    {
+        let mut sent = state.sent.lock().map_err(|_| "Chat lock poisoned".to_string())?;
+        let now = Instant::now();
+        let table = sent.entry(table_id.to_string()).or_default();
+        while table.front().is_some_and(|(at, _)| now.duration_since(*at) > REPEAT_WINDOW) {
+            table.pop_front();
+        }
+        if table.iter().any(|(_, previous)| previous == &text) {
+            return Err("That message was just sent".to_string());
+        }
+        if table.iter().filter(|(at, _)| now.duration_since(*at) <= RATE_WINDOW).count() >= MAX_PER_WINDOW {
+            return Err("Sending messages too quickly, wait a moment".to_string());
+        }
+        table.push_back((now, text.clone()));
+    }
+
+    let username = app
+        .state::<ThumbnailState>()
+        .latest(table_id)?
+        .and_then(|(mirror, _, _)| mirror.player(&player_id).map(|p| p.username.clone()))
+        .unwrap_or_default();
+    let outgoing = OutgoingChat {
+        table_id: table_id.to_string(),
+        frame: WsMessage::new(
+            "chat",
+            json!({ "playerId": player_id, "username": username, "message": text, "isSystem": false }),
+        ),
+    };
+    let _ = app.emit_all("chat_send", outgoing.clone());
+    Ok(outgoing)
+}
+
+fn send_canned(app: &AppHandle, id: &str, table_id: &str) -> Result<OutgoingChat, String> {
+    let canned = load_canned(&*db::get(app)?)?;
+    let message = canned
+        .iter()
+        .find(|m| m.id == id)
+        .ok_or_else(|| format!("No canned message {}", id))?;
+    send(app, table_id, &message.text)
+}
+
+fn on_hotkey(app: &AppHandle, id: &str) {
+    let focused = app.state::<ChatState>().focused_table.lock().ok().and_then(|f| f.clone());
+    let Some(table_id) = focused else { return };
+    if let Err(e) = send_canned(app, id, &table_id) {
+        eprintln!("Canned message {} not sent: {}", id, e);
+        let _ = app.emit_all("chat_send_failed", json!({ "tableId": table_id, "id": id, "error": e }));
+    }
+}
+
+// Bind the canned messages' hotkeys, replacing the previous bindings; called once the
+// database is open and whenever the messages change
+pub fn register_hotkeys(app: &AppHandle) -> Result<(), String> {
+    let canned = load_canned(&*db::get(app)?)?;
+    let state = app.state::<ChatState>();
+    let mut registered = state.hotkeys.lock().map_err(|_| "Chat lock poisoned".to_string())?;
+    let mut manager = app.global_shortcut_manager();
+    for accelerator in registered.drain(..) {
+        if let Err(e) = manager.unregister(&accelerator) {
+            eprintln!("Failed to release hotkey {}: {}", accelerator, e);
+        }
+    }
+
+    let mut failed = Vec::new();
+    for message in canned {
+        let Some(accelerator) = message.hotkey else { continue };
+        let handle = app.clone();
+        let id = message.id;
+        match manager.register(&accelerator, move || on_hotkey(&handle, &id)) {
+            Ok(()) => registered.push(accelerator),
+            Err(e) => failed.push(format!("{} ({})", accelerator, e)),
+        }
+    }
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Could not bind hotkeys: {}", failed.join(", ")))
+    }
+}
+
+// Send a typed message to `table_id`
+#[tauri::command]
+pub async fn send_chat_message(app: AppHandle, table_id: String, message: String) -> Result<OutgoingChat, String> {
+    send(&app, &table_id, &message)
+}
+
+#[tauri::command]
+pub async fn send_canned_message(app: AppHandle, id: String, table_id: String) -> Result<OutgoingChat, String> {
+    send_canned(&app, &id, &table_id)
+}
+
+#[tauri::command]
+pub async fn get_canned_messages(db: Db<'_>) -> Result<Vec<CannedMessage>, String> {
+    load_canned(&db)
+}
+
+// Replace the canned messages and rebind their hotkeys
+#[tauri::command]
+pub async fn set_canned_messages(
+    app: AppHandle,
+    db: Db<'_>,
+    messages: Vec<CannedMessage>,
+) -> Result<Vec<CannedMessage>, String> {
+    let messages: Vec<CannedMessage> = messages
+        .into_iter()
+        .map(|m| CannedMessage { id: m.id.trim().to_string(), text: m.text.trim().to_string(), hotkey: m.hotkey })
+        .collect();
+    validate_canned(&messages)?;
+    let data = serde_json::to_string(&messages).map_err(|e| e.to_string())?;
+    db.set_value(KEY_CANNED, &data)?;
+    register_hotkeys(&app)?;
+    Ok(messages)
+}
+
+// The table hotkeys send to; None when no table view has focus
+#[tauri::command]
+pub async fn set_chat_focus(state: State<'_, ChatState>, table_id: Option<String>) -> Result<(), String> {
+    *state.focused_table.lock().map_err(|_| "Chat lock poisoned".to_string())? = table_id;
+    Ok(())
+}
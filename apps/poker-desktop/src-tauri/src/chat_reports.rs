@@ -0,0 +1,326 @@
+// Chat reports for abuse and other conduct problems. Incoming player chat is kept in
+// `chat_messages` for a few days (translate.rs records each message as the frontend
+// hands it over), so a report carries the reported message together with the
+// messages around it at the same table, each with its time and sender id. The bundle
+// goes to the backend moderation endpoint and every filed report is kept in
+// `chat_reports` so its review status can be followed.
+
+use crate::audit;
+use crate::db::{Database, Db};
+use crate::history::to_millis;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::AppHandle;
+
+const REASONS: &[&str] = &["abuse", "harassment", "spam", "cheating", "other"];
+const MAX_COMMENT_LEN: usize = 2000;
+// Messages taken from each side of the reported one, by default and at most
+const DEFAULT_CONTEXT: u32 = 10;
+const MAX_CONTEXT: u32 = 50;
+const RETENTION_DAYS: i64 = 3;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredMessage {
+    id: String,
+    table_id: String,
+    sender_id: String,
+    username: String,
+    message: String,
+    sent_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ChatEvidence {
+    reason: String,
+    comment: Option<String>,
+    table_id: String,
+    message: StoredMessage,
+    // In the order they were sent, the reported message included
+    context: Vec<StoredMessage>,
+    client_version: &'static str,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RemoteReport {
+    id: String,
+    status: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatReport {
+    id: String,
+    message_id: String,
+    table_id: String,
+    sender_id: String,
+    reason: String,
+    // submitted | under_review | actioned | dismissed
+    status: String,
+    submitted_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+fn time(millis: i64) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(millis).single().unwrap_or_else(Utc::now)
+}
+
+fn message_from_row(row: &rusqlite::Row) -> rusqlite::Result<StoredMessage> {
+    Ok(StoredMessage {
+        id: row.get(0)?,
+        table_id: row.get(1)?,
+        sender_id: row.get(2)?,
+        username: row.get(3)?,
+        message: row.get(4)?,
+        sent_at: time(row.get(5)?),
+    })
+}
+
+fn report_from_row(row: &rusqlite::Row) -> rusqlite::Result<ChatReport> {
+    Ok(ChatReport {
+        id: row.get(0)?,
+        message_id: row.get(1)?,
+        table_id: row.get(2)?,
+        sender_id: row.get(3)?,
+        reason: row.get(4)?,
+        status: row.get(5)?,
+        submitted_at: time(row.get(6)?),
+        updated_at: time(row.get(7)?),
+    })
+}
+
+// Keep an incoming chat message, dropping what is past retention. Returns its id,
+// made up locally when the server sent none.
+pub fn record(
+    db: &Database,
+    table_id: &str,
+    id: Option<&str>,
+    sender_id: &str,
+    username: &str,
+    message: &str,
+) -> Result<String, String> {
+    let now = Utc::now();
+    let id = id.map(String::from).unwrap_or_else(|| format!("local-{}-{:08x}", now.timestamp_millis(), rand::random::<u32>()));
+    db.with_conn(|conn| {
+        conn.execute(
+            "INSERT OR IGNORE INTO chat_messages (id, table_id, sender_id, username, message, sent_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![id, table_id, sender_id, username, message, to_millis(&now)],
+        )?;
+        conn.execute(
+            "DELETE FROM chat_messages WHERE sent_at < ?1",
+            params![to_millis(&(now - Duration::days(RETENTION_DAYS)))],
+        )?;
+        Ok(())
+    })?;
+    Ok(id)
+}
+
+fn get_message(conn: &Connection, id: &str) -> rusqlite::Result<Option<StoredMessage>> {
+    conn.query_row(
+        "SELECT id, table_id, sender_id, username, message, sent_at FROM chat_messages WHERE id = ?1",
+        params![id],
+        message_from_row,
+    )
+    .optional()
+}
+
+// `around` messages each side of `message` at its table, oldest first
+fn surrounding(conn: &Connection, message: &StoredMessage, around: u32) -> rusqlite::Result<Vec<StoredMessage>> {
+    let sent_at = to_millis(&message.sent_at);
+    let mut before: Vec<StoredMessage> = conn
+        .prepare(
+            "SELECT id, table_id, sender_id, username, message, sent_at FROM chat_messages
+             WHERE table_id = ?1 AND (sent_at < ?2 OR (sent_at = ?2 AND id < ?3))
+             ORDER BY sent_at DESC, id DESC LIMIT ?4",
+        )?
+        .query_map(params![message.table_id, sent_at, message.id, around], message_from_row)?
+        .collect::<rusqlite::Result<_>>()?;
+    before.reverse();
+    let after: Vec<StoredMessage> = conn
+        .prepare(
+            "SELECT id, table_id, sender_id, username, message, sent_at FROM chat_messages
+             WHERE table_id = ?1 AND (sent_at > ?2 OR (sent_at = ?2 AND id > ?3))
+             ORDER BY sent_at, id LIMIT ?4",
+        )?
+        .query_map(params![message.table_id, sent_at, message.id, around], message_from_row)?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let mut messages = before;
+    messages.push(message.clone());
+    messages.extend(after);
+    Ok(messages)
+}
+
+fn list_reports(conn: &Connection) -> rusqlite::Result<Vec<ChatReport>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, message_id, table_id, sender_id, reason, status, submitted_at, updated_at
+         FROM chat_reports ORDER BY submitted_at DESC",
+    )?;
+    let rows = stmt.query_map([], report_from_row)?;
+    rows.collect()
+}
+
+fn insert_report(conn: &Connection, report: &ChatReport) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO chat_reports (id, message_id, table_id, sender_id, reason, status, submitted_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            report.id,
+            report.message_id,
+            report.table_id,
+            report.sender_id,
+            report.reason,
+            report.status,
+            to_millis(&report.submitted_at),
+            to_millis(&report.updated_at)
+        ],
+    )?;
+    Ok(())
+}
+
+async fn submit(api_url: &str, evidence: &ChatEvidence) -> Result<RemoteReport, String> {
+    let client = crate::create_http_client()?;
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+
+    let response = crate::http::send(client.post(format!("{}/api/moderation/reports", api_url))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(evidence))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("Failed to submit report: {}", error_text));
+    }
+
+    let api_response: crate::ApiResponse<RemoteReport> = response.json().await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    if api_response.success {
+        api_response.data.ok_or_else(|| "No report returned".to_string())
+    } else {
+        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    }
+}
+
+async fn fetch_statuses(api_url: &str) -> Result<Vec<RemoteReport>, String> {
+    let client = crate::create_http_client()?;
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+
+    let response = crate::http::send(client.get(format!("{}/api/moderation/reports", api_url))
+        .header("Authorization", format!("Bearer {}", token)))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        return Err("Failed to fetch report status".to_string());
+    }
+
+    let api_response: crate::ApiResponse<Vec<RemoteReport>> = response.json().await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    if api_response.success {
+        Ok(api_response.data.unwrap_or_default())
+    } else {
+        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    }
+}
+
+// Report the chat message `message_id` with up to `context` messages either side of it
+#[tauri::command]
+pub async fn report_chat_message(
+    app: AppHandle,
+    db: Db<'_>,
+    api_url: String,
+    message_id: String,
+    reason: Option<String>,
+    comment: Option<String>,
+    context: Option<u32>,
+) -> Result<ChatReport, String> {
+    let reason = reason.unwrap_or_else(|| "abuse".to_string());
+    if !REASONS.contains(&reason.as_str()) {
+        return Err(format!("Unknown report reason: {}", reason));
+    }
+    let comment = comment.map(|c| c.trim().to_string()).filter(|c| !c.is_empty());
+    if comment.as_ref().is_some_and(|c| c.chars().count() > MAX_COMMENT_LEN) {
+        return Err(format!("Comments are limited to {} characters", MAX_COMMENT_LEN));
+    }
+
+    let message = db
+        .with_conn(|conn| get_message(conn, &message_id))?
+        .ok_or_else(|| format!("Message {} is not in local chat history", message_id))?;
+    if crate::claims::current().is_ok_and(|c| c.user_id == message.sender_id) {
+        return Err("You cannot report your own message".to_string());
+    }
+    let reported = db.with_conn(|conn| {
+        conn.query_row("SELECT COUNT(*) FROM chat_reports WHERE message_id = ?1", params![message_id], |row| row.get::<_, i64>(0))
+    })?;
+    if reported > 0 {
+        return Err("This message has already been reported".to_string());
+    }
+    let around = context.unwrap_or(DEFAULT_CONTEXT).min(MAX_CONTEXT);
+    let messages = db.with_conn(|conn| surrounding(conn, &message, around))?;
+
+    let evidence = ChatEvidence {
+        reason: reason.clone(),
+        comment,
+        table_id: message.table_id.clone(),
+        message: message.clone(),
+        context: messages,
+        client_version: env!("CARGO_PKG_VERSION"),
+        created_at: Utc::now(),
+    };
+    let result = submit(&api_url, &evidence).await;
+    audit::record(
+        &app,
+        "report_chat_message",
+        json!({ "messageId": message_id, "tableId": message.table_id, "senderId": message.sender_id, "reason": reason }),
+        &result,
+    );
+    let remote = result?;
+
+    let now = Utc::now();
+    let report = ChatReport {
+        id: remote.id,
+        message_id,
+        table_id: message.table_id,
+        sender_id: message.sender_id,
+        reason,
+        status: remote.status,
+        submitted_at: now,
+        updated_at: now,
+    };
+    db.with_conn(|conn| insert_report(conn, &report))?;
+    Ok(report)
+}
+
+// Filed chat reports, newest first. With `refresh`, statuses are updated from the backend.
+#[tauri::command]
+pub async fn list_chat_reports(
+    db: Db<'_>,
+    api_url: String,
+    refresh: Option<bool>,
+) -> Result<Vec<ChatReport>, String> {
+    if refresh.unwrap_or(false) {
+        let remote = fetch_statuses(&api_url).await?;
+        let now = to_millis(&Utc::now());
+        db.with_conn(|conn| {
+            for report in &remote {
+                conn.execute(
+                    "UPDATE chat_reports SET status = ?2, updated_at = ?3 WHERE id = ?1 AND status != ?2",
+                    params![report.id, report.status, now],
+                )?;
+            }
+            Ok(())
+        })?;
+    }
+    db.with_conn(list_reports)
+}
@@ -0,0 +1,55 @@
+// Claims carried in the stored access token. The client only reads them to decide
+// what to offer; the backend verifies the signature and enforces every permission.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Claims {
+    #[serde(alias = "playerId")]
+    pub user_id: String,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    // Seconds since epoch
+    #[serde(default)]
+    pub exp: Option<i64>,
+    // 0 until identity checks pass, then raised by the backend per verified document
+    #[serde(default)]
+    pub kyc_level: u8,
+    // Restrictions the backend found for the player's location, e.g. `blocked`
+    #[serde(default)]
+    pub geo_flags: Vec<String>,
+}
+
+impl Claims {
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r.eq_ignore_ascii_case(role))
+    }
+
+    pub fn has_geo_flag(&self, flag: &str) -> bool {
+        self.geo_flags.iter().any(|f| f.eq_ignore_ascii_case(flag))
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.exp.is_some_and(|exp| exp <= chrono::Utc::now().timestamp())
+    }
+}
+
+pub fn decode(token: &str) -> Result<Claims, String> {
+    let payload = token.split('.').nth(1).ok_or_else(|| "Malformed access token".to_string())?;
+    let bytes = URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .map_err(|e| format!("Malformed access token: {}", e))?;
+    serde_json::from_slice(&bytes).map_err(|e| format!("Invalid token claims: {}", e))
+}
+
+// Claims of the signed-in player
+pub fn current() -> Result<Claims, String> {
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+    decode(&token)
+}
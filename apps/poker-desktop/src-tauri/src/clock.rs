@@ -0,0 +1,79 @@
+// Offset between the local clock and the backend's. Measured against the health
+// endpoint, taking the midpoint of the round trip, so scheduled work (reminders,
+// countdowns) follows server time even when the local clock is off.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use tauri::State;
+
+#[derive(Default)]
+pub struct ClockState {
+    // Server time minus local time
+    offset_ms: AtomicI64,
+    synced: AtomicBool,
+}
+
+impl ClockState {
+    pub fn server_now(&self) -> DateTime<Utc> {
+        Utc::now() + Duration::milliseconds(self.offset_ms.load(Ordering::Relaxed))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClockStatus {
+    offset_ms: i64,
+    synced: bool,
+    server_now: DateTime<Utc>,
+}
+
+// Server time from the JSON `timestamp` of the health response, else the Date header
+async fn server_time(response: reqwest::Response) -> Option<DateTime<Utc>> {
+    let header = response
+        .headers()
+        .get(reqwest::header::DATE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| DateTime::parse_from_rfc2822(value).ok());
+    let body: serde_json::Value = response.json().await.unwrap_or_default();
+    body["timestamp"]
+        .as_str()
+        .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+        .or(header)
+        .map(|time| time.with_timezone(&Utc))
+}
+
+pub async fn sync(state: &ClockState, api_url: &str) -> Result<i64, String> {
+    let client = crate::create_http_client()?;
+    let sent = Utc::now();
+    let response = crate::http::send(client.get(format!("{}/api/health", api_url)))
+        .await
+        .map_err(|e| e.to_string())?;
+    let received = Utc::now();
+    let server = server_time(response).await.ok_or_else(|| "Server did not report its time".to_string())?;
+
+    let midpoint = sent + (received - sent) / 2;
+    let offset = (server - midpoint).num_milliseconds();
+    state.offset_ms.store(offset, Ordering::Relaxed);
+    state.synced.store(true, Ordering::Relaxed);
+    Ok(offset)
+}
+
+#[tauri::command]
+pub async fn sync_clock(state: State<'_, ClockState>, api_url: String) -> Result<ClockStatus, String> {
+    sync(&state, &api_url).await?;
+    get_clock_status(state).await
+}
+
+pub fn status(state: &ClockState) -> ClockStatus {
+    ClockStatus {
+        offset_ms: state.offset_ms.load(Ordering::Relaxed),
+        synced: state.synced.load(Ordering::Relaxed),
+        server_now: state.server_now(),
+    }
+}
+
+#[tauri::command]
+pub async fn get_clock_status(state: State<'_, ClockState>) -> Result<ClockStatus, String> {
+    Ok(status(&state))
+}
@@ -0,0 +1,380 @@
+// Private home-game clubs. Wraps the backend club endpoints; each club's roster is
+// cached in the kv table so the member list opens without a round trip and is
+// refreshed whenever membership changes.
+
+use crate::audit;
+use crate::db::{Database, Db};
+use crate::moderation;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tauri::AppHandle;
+
+const MAX_NAME_LEN: usize = 40;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Club {
+    id: String,
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    owner_id: String,
+    // Only returned to owners and admins
+    #[serde(default)]
+    invite_code: Option<String>,
+    // The current player's role: owner | admin | member | pending
+    #[serde(default)]
+    role: Option<String>,
+    #[serde(default)]
+    member_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClubMember {
+    player_id: String,
+    username: String,
+    role: String,
+    // active | pending | invited
+    status: String,
+    #[serde(default)]
+    joined_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClubRoster {
+    club_id: String,
+    members: Vec<ClubMember>,
+    fetched_at: DateTime<Utc>,
+    // Served from the local copy because the backend could not be reached
+    #[serde(default)]
+    offline: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClubTableRules {
+    name: String,
+    small_blind: u32,
+    big_blind: u32,
+    #[serde(default)]
+    ante: u32,
+    min_buy_in: u32,
+    max_buy_in: u32,
+    max_players: u8,
+    #[serde(default)]
+    time_bank: u32,
+    #[serde(default)]
+    straddle: bool,
+    #[serde(default)]
+    run_it_twice: bool,
+    // Members only, or anyone holding the table link
+    #[serde(default = "default_true")]
+    members_only: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl ClubTableRules {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Table name is required".to_string());
+        }
+        moderation::require(moderation::Field::TableName, &self.name)?;
+        if self.small_blind == 0 || self.big_blind < self.small_blind {
+            return Err("Big blind must be at least the small blind".to_string());
+        }
+        if self.min_buy_in == 0 || self.max_buy_in < self.min_buy_in {
+            return Err("Maximum buy-in must be at least the minimum".to_string());
+        }
+        if !(2..=10).contains(&self.max_players) {
+            return Err("Tables seat between 2 and 10 players".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LedgerEntry {
+    player_id: String,
+    #[serde(default)]
+    username: Option<String>,
+    // buy_in | cash_out | adjustment
+    kind: String,
+    // Chips; adjustments may be negative
+    amount: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberResult {
+    player_id: String,
+    username: String,
+    buy_ins: i64,
+    cash_outs: i64,
+    adjustments: i64,
+    net: i64,
+    sessions: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClubLedger {
+    club_id: String,
+    // Biggest winner first
+    members: Vec<MemberResult>,
+    total_buy_ins: i64,
+    total_cash_outs: i64,
+}
+
+fn roster_key(club_id: &str) -> String {
+    format!("clubs.roster.{}", club_id)
+}
+
+fn cached_roster(db: &Database, club_id: &str) -> Result<Option<ClubRoster>, String> {
+    match db.get_value(&roster_key(club_id))? {
+        Some(data) => serde_json::from_str(&data).map(Some).map_err(|e| format!("Invalid cached roster: {}", e)),
+        None => Ok(None),
+    }
+}
+
+fn store_roster(db: &Database, roster: &ClubRoster) -> Result<(), String> {
+    let data = serde_json::to_string(roster).map_err(|e| e.to_string())?;
+    db.set_value(&roster_key(&roster.club_id), &data)
+}
+
+// Send an authenticated club request and unwrap the API envelope
+async fn call<T: DeserializeOwned>(method: reqwest::Method, url: String, body: Option<Value>) -> Result<T, String> {
+    let client = crate::create_http_client()?;
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+
+    let mut request = client.request(method, url).header("Authorization", format!("Bearer {}", token));
+    if let Some(body) = body {
+        request = request.json(&body);
+    }
+    let response = crate::http::send(request).await.map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("Club request failed: {}", error_text));
+    }
+
+    let api_response: crate::ApiResponse<T> = response.json().await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    if api_response.success {
+        api_response.data.ok_or_else(|| "No data returned".to_string())
+    } else {
+        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    }
+}
+
+async fn fetch_roster(db: &Database, api_url: &str, club_id: &str) -> Result<ClubRoster, String> {
+    let members: Vec<ClubMember> =
+        call(reqwest::Method::GET, format!("{}/api/clubs/{}/members", api_url, club_id), None).await?;
+    let roster = ClubRoster { club_id: club_id.to_string(), members, fetched_at: Utc::now(), offline: false };
+    store_roster(db, &roster)?;
+    Ok(roster)
+}
+
+#[tauri::command]
+pub async fn create_club(
+    app: AppHandle,
+    api_url: String,
+    name: String,
+    description: Option<String>,
+) -> Result<Club, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(format!("Club names must be 1 to {} characters", MAX_NAME_LEN));
+    }
+
+    let result = call::<Club>(
+        reqwest::Method::POST,
+        format!("{}/api/clubs", api_url),
+        Some(json!({ "name": name, "description": description })),
+    )
+    .await;
+    let club_id = result.as_ref().ok().map(|club| club.id.clone());
+    audit::record(&app, "create_club", json!({ "name": name, "clubId": club_id }), &result);
+    result
+}
+
+// Clubs the current player belongs to or has asked to join
+#[tauri::command]
+pub async fn list_clubs(api_url: String) -> Result<Vec<Club>, String> {
+    call(reqwest::Method::GET, format!("{}/api/clubs", api_url), None).await
+}
+
+// Request membership with an invite code; the club shows as pending until approved
+#[tauri::command]
+pub async fn join_club(app: AppHandle, api_url: String, invite_code: String) -> Result<Club, String> {
+    let result = call::<Club>(
+        reqwest::Method::POST,
+        format!("{}/api/clubs/join", api_url),
+        Some(json!({ "inviteCode": invite_code.trim() })),
+    )
+    .await;
+    let club_id = result.as_ref().ok().map(|club| club.id.clone());
+    audit::record(&app, "join_club", json!({ "clubId": club_id }), &result);
+    result
+}
+
+// Cached roster unless `refresh` is set or nothing is cached yet. Falls back to the
+// cached copy when the backend cannot be reached.
+#[tauri::command]
+pub async fn get_club_roster(
+    db: Db<'_>,
+    api_url: String,
+    club_id: String,
+    refresh: Option<bool>,
+) -> Result<ClubRoster, String> {
+    let cached = cached_roster(&db, &club_id)?;
+    if let Some(roster) = &cached {
+        if !refresh.unwrap_or(false) {
+            return Ok(roster.clone());
+        }
+    }
+
+    match fetch_roster(&db, &api_url, &club_id).await {
+        Ok(roster) => Ok(roster),
+        Err(e) => match cached {
+            Some(roster) => {
+                eprintln!("Roster refresh for club {} failed, using cached copy: {}", club_id, e);
+                Ok(ClubRoster { offline: true, ..roster })
+            }
+            None => Err(e),
+        },
+    }
+}
+
+async fn change_membership(
+    app: &AppHandle,
+    db: &Database,
+    api_url: &str,
+    action: &str,
+    club_id: &str,
+    player_id: &str,
+) -> Result<ClubRoster, String> {
+    let (method, url) = match action {
+        "invite" => (reqwest::Method::POST, format!("{}/api/clubs/{}/invites", api_url, club_id)),
+        "approve" => (reqwest::Method::POST, format!("{}/api/clubs/{}/members/{}/approve", api_url, club_id, player_id)),
+        _ => (reqwest::Method::DELETE, format!("{}/api/clubs/{}/members/{}", api_url, club_id, player_id)),
+    };
+    let result = call::<Value>(method, url, Some(json!({ "playerId": player_id }))).await;
+    audit::record(app, &format!("club_{}", action), json!({ "clubId": club_id, "playerId": player_id }), &result);
+    result?;
+    fetch_roster(db, api_url, club_id).await
+}
+
+#[tauri::command]
+pub async fn invite_club_member(
+    app: AppHandle,
+    db: Db<'_>,
+    api_url: String,
+    club_id: String,
+    player_id: String,
+) -> Result<ClubRoster, String> {
+    change_membership(&app, &db, &api_url, "invite", &club_id, &player_id).await
+}
+
+#[tauri::command]
+pub async fn approve_club_member(
+    app: AppHandle,
+    db: Db<'_>,
+    api_url: String,
+    club_id: String,
+    player_id: String,
+) -> Result<ClubRoster, String> {
+    change_membership(&app, &db, &api_url, "approve", &club_id, &player_id).await
+}
+
+// Remove a member, or withdraw a pending invite or request
+#[tauri::command]
+pub async fn remove_club_member(
+    app: AppHandle,
+    db: Db<'_>,
+    api_url: String,
+    club_id: String,
+    player_id: String,
+) -> Result<ClubRoster, String> {
+    change_membership(&app, &db, &api_url, "remove", &club_id, &player_id).await
+}
+
+#[tauri::command]
+pub async fn create_club_table(
+    app: AppHandle,
+    api_url: String,
+    club_id: String,
+    rules: ClubTableRules,
+) -> Result<Value, String> {
+    rules.validate()?;
+    let body = serde_json::to_value(&rules).map_err(|e| e.to_string())?;
+    let result = call::<Value>(reqwest::Method::POST, format!("{}/api/clubs/{}/tables", api_url, club_id), Some(body)).await;
+    let table_id = result.as_ref().ok().and_then(|table| table["id"].as_str().map(String::from));
+    audit::record(&app, "create_club_table", json!({ "clubId": club_id, "name": rules.name, "tableId": table_id }), &result);
+    result
+}
+
+// Per-member buy-ins, cash-outs and net over the club ledger, optionally limited to
+// a date range
+#[tauri::command]
+pub async fn get_club_ledger(
+    db: Db<'_>,
+    api_url: String,
+    club_id: String,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> Result<ClubLedger, String> {
+    let mut url = format!("{}/api/clubs/{}/ledger", api_url, club_id);
+    let range: Vec<String> = [("from", from), ("to", to)]
+        .iter()
+        .filter_map(|(name, time)| time.map(|t| format!("{}={}", name, t.timestamp_millis())))
+        .collect();
+    if !range.is_empty() {
+        url = format!("{}?{}", url, range.join("&"));
+    }
+    let entries: Vec<LedgerEntry> = call(reqwest::Method::GET, url, None).await?;
+
+    let usernames: HashMap<String, String> = cached_roster(&db, &club_id)?
+        .map(|roster| roster.members.into_iter().map(|m| (m.player_id, m.username)).collect())
+        .unwrap_or_default();
+
+    let mut results: HashMap<String, MemberResult> = HashMap::new();
+    for entry in entries {
+        let result = results.entry(entry.player_id.clone()).or_insert_with(|| MemberResult {
+            username: usernames.get(&entry.player_id).cloned().unwrap_or_default(),
+            player_id: entry.player_id.clone(),
+            ..MemberResult::default()
+        });
+        if let Some(username) = entry.username {
+            result.username = username;
+        }
+        match entry.kind.as_str() {
+            "buy_in" => {
+                result.buy_ins += entry.amount;
+                result.sessions += 1;
+            }
+            "cash_out" => result.cash_outs += entry.amount,
+            _ => result.adjustments += entry.amount,
+        }
+        result.net = result.cash_outs + result.adjustments - result.buy_ins;
+    }
+
+    let mut members: Vec<MemberResult> = results.into_values().collect();
+    members.sort_by(|a, b| b.net.cmp(&a.net).then_with(|| a.username.cmp(&b.username)));
+    Ok(ClubLedger {
+        club_id,
+        total_buy_ins: members.iter().map(|m| m.buy_ins).sum(),
+        total_cash_outs: members.iter().map(|m| m.cash_outs).sum(),
+        members,
+    })
+}
@@ -0,0 +1,218 @@
+use crate::error::CommandError;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::net::UdpSocket;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+// Features that must not be enabled until the backend confirms the jurisdiction allows them
+pub const REAL_MONEY_FEATURES: &[&str] = &["real_money_tables", "deposits", "withdrawals"];
+
+const DEFAULT_TTL_SECS: i64 = 900;
+const NETWORK_POLL_SECS: u64 = 30;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComplianceRuling {
+    jurisdiction: Option<String>,
+    real_money_allowed: bool,
+    restricted_features: Vec<String>,
+    vpn_detected: bool,
+    checked_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+}
+
+impl ComplianceRuling {
+    fn restriction_for(&self, feature: &str) -> Option<String> {
+        if self.vpn_detected {
+            return Some("VPN or proxy connections are not permitted for this feature".to_string());
+        }
+        if !self.real_money_allowed && REAL_MONEY_FEATURES.contains(&feature) {
+            return Some("Real-money play is not permitted in your jurisdiction".to_string());
+        }
+        if self.restricted_features.iter().any(|f| f == feature) {
+            return Some("This feature is restricted in your jurisdiction".to_string());
+        }
+        None
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RemoteRuling {
+    jurisdiction: Option<String>,
+    real_money_allowed: bool,
+    #[serde(default)]
+    restricted_features: Vec<String>,
+    #[serde(default)]
+    vpn_detected: bool,
+    ttl_seconds: Option<i64>,
+}
+
+// Cached ruling plus the network identity it was made for
+#[derive(Default)]
+pub struct ComplianceState {
+    ruling: Mutex<Option<ComplianceRuling>>,
+    network: Mutex<Option<String>>,
+    api_url: Mutex<Option<String>>,
+    watching: AtomicBool,
+}
+
+// Local address of the default route. It changes when switching networks or when a
+// VPN tunnel comes up. Connecting a UDP socket sends no packets.
+fn current_network() -> Option<String> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("1.1.1.1:80").ok()?;
+    socket.local_addr().ok().map(|addr| addr.ip().to_string())
+}
+
+async fn fetch_ruling(api_url: &str) -> Result<ComplianceRuling, CommandError> {
+    let unavailable = |message: String| CommandError::ComplianceUnavailable { message };
+
+    let client = crate::create_http_client()?;
+    let mut request = client.get(format!("{}/api/compliance/geo", api_url));
+    if let Ok(token) = crate::get_token_from_keyring() {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+
+    let response = crate::http::send(request).await.map_err(|e| match e {
+        CommandError::RateLimited { .. } | CommandError::IncompatibleBackend { .. } => e,
+        other => unavailable(other.to_string()),
+    })?;
+
+    if !response.status().is_success() {
+        return Err(unavailable(format!("Compliance endpoint returned {}", response.status())));
+    }
+
+    let api_response: crate::ApiResponse<RemoteRuling> = response.json().await
+        .map_err(|e| unavailable(format!("Failed to parse response: {}", e)))?;
+
+    let remote = match (api_response.success, api_response.data) {
+        (true, Some(remote)) => remote,
+        _ => {
+            return Err(unavailable(
+                api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()),
+            ))
+        }
+    };
+
+    let checked_at = Utc::now();
+    Ok(ComplianceRuling {
+        jurisdiction: remote.jurisdiction,
+        real_money_allowed: remote.real_money_allowed,
+        restricted_features: remote.restricted_features,
+        vpn_detected: remote.vpn_detected,
+        checked_at,
+        expires_at: checked_at + Duration::seconds(remote.ttl_seconds.unwrap_or(DEFAULT_TTL_SECS)),
+    })
+}
+
+// Return the cached ruling, re-checking with the backend when it expired or the network changed
+pub async fn get_ruling(state: &ComplianceState, api_url: &str) -> Result<ComplianceRuling, CommandError> {
+    let network = current_network();
+    {
+        let cached = state.ruling.lock().map_err(|_| "Compliance lock poisoned".to_string())?;
+        let same_network = *state.network.lock().map_err(|_| "Compliance lock poisoned".to_string())? == network;
+        if let Some(ruling) = cached.as_ref() {
+            if same_network && ruling.expires_at > Utc::now() {
+                return Ok(ruling.clone());
+            }
+        }
+    }
+
+    let ruling = fetch_ruling(api_url).await?;
+
+    if let Ok(mut cached) = state.ruling.lock() {
+        *cached = Some(ruling.clone());
+    }
+    if let Ok(mut stored) = state.network.lock() {
+        *stored = network;
+    }
+    if let Ok(mut stored) = state.api_url.lock() {
+        *stored = Some(api_url.to_string());
+    }
+
+    Ok(ruling)
+}
+
+// Guard for feature-gated commands. Fails closed: if no ruling can be obtained the
+// feature stays blocked.
+pub async fn require_feature(state: &ComplianceState, api_url: &str, feature: &str) -> Result<(), CommandError> {
+    let ruling = get_ruling(state, api_url).await?;
+
+    match ruling.restriction_for(feature) {
+        Some(reason) => Err(CommandError::FeatureRestricted {
+            feature: feature.to_string(),
+            jurisdiction: ruling.jurisdiction.clone(),
+            reason,
+        }),
+        None => Ok(()),
+    }
+}
+
+fn invalidate(state: &ComplianceState) {
+    if let Ok(mut cached) = state.ruling.lock() {
+        *cached = None;
+    }
+}
+
+// Watch for network changes and re-check the ruling when one happens
+pub fn start_network_watch(app: &AppHandle) {
+    if app.state::<ComplianceState>().watching.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(NETWORK_POLL_SECS)).await;
+
+            let state = app.state::<ComplianceState>();
+            let network = current_network();
+            let changed = state.network.lock().map(|n| *n != network).unwrap_or(false);
+            let api_url = state.api_url.lock().ok().and_then(|u| u.clone());
+
+            if let (true, Some(api_url)) = (changed, api_url) {
+                invalidate(&state);
+                match get_ruling(&state, &api_url).await {
+                    Ok(ruling) => {
+                        let _ = app.emit_all("compliance_changed", ruling);
+                    }
+                    Err(e) => eprintln!("Compliance re-check failed: {}", e),
+                }
+            }
+        }
+    });
+}
+
+// Run the check after login so real-money features are gated before first use
+pub async fn check_on_login(app: &AppHandle, api_url: &str) {
+    let state = app.state::<ComplianceState>();
+    invalidate(&state);
+    match get_ruling(&state, api_url).await {
+        Ok(ruling) => {
+            let _ = app.emit_all("compliance_changed", ruling);
+        }
+        Err(e) => eprintln!("Compliance check failed: {}", e),
+    }
+    start_network_watch(app);
+}
+
+#[tauri::command]
+pub async fn get_compliance_status(
+    state: State<'_, ComplianceState>,
+    api_url: String,
+) -> Result<ComplianceRuling, CommandError> {
+    get_ruling(&state, &api_url).await
+}
+
+// Explicit check the frontend can use before showing a gated feature
+#[tauri::command]
+pub async fn check_feature_allowed(
+    state: State<'_, ComplianceState>,
+    api_url: String,
+    feature: String,
+) -> Result<(), CommandError> {
+    require_feature(&state, &api_url, &feature).await
+}
@@ -0,0 +1,175 @@
+// Deflate compression for the game WebSocket. The payload format is the one
+// permessage-deflate (RFC 7692) uses - raw DEFLATE blocks with the trailing
+// 00 00 ff ff of a sync flush removed, with the sliding window carried from message
+// to message unless context takeover is off. The offer travels in an
+// `X-Primo-Compression` header and compressed messages in binary frames: the
+// tungstenite version we ship rejects frames with the RSV1 bit set, so the extension
+// cannot be negotiated through `Sec-WebSocket-Extensions` yet. Messages under the
+// size threshold go out as plain text frames.
+
+use crate::db::{Database, Db};
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress, Status};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+pub const OFFER_HEADER: &str = "X-Primo-Compression";
+const KEY_SETTINGS: &str = "ws.compression";
+const SYNC_TAIL: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+// The deflate backend always compresses with a 32 KiB window
+const CLIENT_WINDOW_BITS: u8 = 15;
+const MIN_WINDOW_BITS: u8 = 8;
+const MAX_WINDOW_BITS: u8 = 15;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CompressionSettings {
+    enabled: bool,
+    // Window the server may use for what it sends us, 8 to 15 bits
+    server_max_window_bits: u8,
+    // Messages smaller than this are not worth compressing
+    threshold_bytes: usize,
+    // Share the window across messages; better ratio, more memory on both ends
+    context_takeover: bool,
+}
+
+impl Default for CompressionSettings {
+    fn default() -> Self {
+        Self { enabled: true, server_max_window_bits: 15, threshold_bytes: 256, context_takeover: true }
+    }
+}
+
+static SETTINGS: Mutex<Option<CompressionSettings>> = Mutex::new(None);
+
+pub fn settings() -> CompressionSettings {
+    SETTINGS.lock().ok().and_then(|s| s.clone()).unwrap_or_default()
+}
+
+// Pick up the stored settings once the database is open
+pub fn load(db: &Database) -> Result<(), String> {
+    let stored = match db.get_value(KEY_SETTINGS)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid compression settings: {}", e))?,
+        None => CompressionSettings::default(),
+    };
+    if let Ok(mut settings) = SETTINGS.lock() {
+        *settings = Some(stored);
+    }
+    Ok(())
+}
+
+// Header value offering compression, None when it is turned off
+pub fn offer(settings: &CompressionSettings) -> Option<String> {
+    if !settings.enabled {
+        return None;
+    }
+    let mut offer = format!(
+        "deflate; client_max_window_bits={}; server_max_window_bits={}",
+        CLIENT_WINDOW_BITS, settings.server_max_window_bits
+    );
+    if !settings.context_takeover {
+        offer.push_str("; client_no_context_takeover; server_no_context_takeover");
+    }
+    Some(offer)
+}
+
+// From the server's answer to the offer, the compressor for outgoing messages and
+// the decompressor for incoming ones; None when compression was declined
+pub fn accept(answer: Option<&str>, settings: &CompressionSettings) -> Option<(Deflater, Inflater)> {
+    let mut params = answer?.split(';').map(str::trim);
+    if params.next() != Some("deflate") {
+        return None;
+    }
+    let reset = !settings.context_takeover || params.any(|p| p.ends_with("no_context_takeover"));
+    Some((
+        Deflater { compress: Compress::new(Compression::default(), false), threshold: settings.threshold_bytes, reset },
+        Inflater { decompress: Decompress::new(false), reset },
+    ))
+}
+
+pub struct Deflater {
+    compress: Compress,
+    threshold: usize,
+    // No context takeover: start every message with an empty window
+    reset: bool,
+}
+
+impl Deflater {
+    pub fn worth_compressing(&self, text: &str) -> bool {
+        text.len() >= self.threshold
+    }
+
+    pub fn compress(&mut self, data: &[u8]) -> Result<Vec<u8>, String> {
+        let mut out = Vec::with_capacity(data.len() / 2 + 64);
+        let start = self.compress.total_in();
+        loop {
+            let consumed = (self.compress.total_in() - start) as usize;
+            if out.len() == out.capacity() {
+                out.reserve(out.capacity().max(256));
+            }
+            self.compress
+                .compress_vec(&data[consumed..], &mut out, FlushCompress::Sync)
+                .map_err(|e| format!("Compression failed: {}", e))?;
+            // A sync flush is complete once all input is in and there was room left over
+            if (self.compress.total_in() - start) as usize == data.len() && out.len() < out.capacity() {
+                break;
+            }
+        }
+        if out.ends_with(&SYNC_TAIL) {
+            out.truncate(out.len() - SYNC_TAIL.len());
+        }
+        if self.reset {
+            self.compress.reset();
+        }
+        Ok(out)
+    }
+}
+
+pub struct Inflater {
+    decompress: Decompress,
+    reset: bool,
+}
+
+impl Inflater {
+    pub fn decompress(&mut self, data: &[u8]) -> Result<Vec<u8>, String> {
+        let input: Vec<u8> = data.iter().chain(&SYNC_TAIL).copied().collect();
+        let mut out = Vec::with_capacity(data.len() * 4 + 64);
+        let start = self.decompress.total_in();
+        loop {
+            let consumed = (self.decompress.total_in() - start) as usize;
+            if out.len() == out.capacity() {
+                out.reserve(out.capacity());
+            }
+            let status = self.decompress
+                .decompress_vec(&input[consumed..], &mut out, FlushDecompress::Sync)
+                .map_err(|e| format!("Decompression failed: {}", e))?;
+            let done = (self.decompress.total_in() - start) as usize == input.len();
+            if status == Status::StreamEnd || (done && out.len() < out.capacity()) {
+                break;
+            }
+        }
+        if self.reset {
+            self.decompress.reset(false);
+        }
+        Ok(out)
+    }
+}
+
+#[tauri::command]
+pub async fn get_ws_compression(db: Db<'_>) -> Result<CompressionSettings, String> {
+    load(&db)?;
+    Ok(settings())
+}
+
+// Applies to connections opened from now on
+#[tauri::command]
+pub async fn set_ws_compression(db: Db<'_>, settings: CompressionSettings) -> Result<CompressionSettings, String> {
+    let settings = CompressionSettings {
+        server_max_window_bits: settings.server_max_window_bits.clamp(MIN_WINDOW_BITS, MAX_WINDOW_BITS),
+        ..settings
+    };
+    let data = serde_json::to_string(&settings).map_err(|e| e.to_string())?;
+    db.set_value(KEY_SETTINGS, &data)?;
+    if let Ok(mut current) = SETTINGS.lock() {
+        *current = Some(settings.clone());
+    }
+    Ok(settings)
+}
@@ -0,0 +1,230 @@
+// Worker pool for CPU-heavy work such as equity simulations and stats aggregation.
+// Tasks run on dedicated threads rather than the async runtime, so network and IPC
+// stay responsive while a long analysis runs. Queued tasks start in priority order,
+// oldest first within a priority. A task can be cancelled from the frontend, and is
+// cancelled when the command waiting on it goes away; queued tasks are then skipped,
+// running ones stop at their next `check`.
+
+use serde::Serialize;
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, Once, OnceLock};
+use std::time::Instant;
+
+const MAX_WORKERS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    // Long reports that can wait behind interactive work
+    Low,
+    Normal,
+    // Results shown live at the table
+    High,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskInfo {
+    id: u64,
+    name: String,
+    priority: Priority,
+    running: bool,
+    cancelled: bool,
+    // Time since the task was submitted
+    age_ms: u64,
+}
+
+// Handed to every task; long loops should call `check` now and then
+pub struct Cancel {
+    flag: Arc<AtomicBool>,
+}
+
+impl Cancel {
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::Relaxed)
+    }
+
+    pub fn check(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            Err("Task cancelled".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+struct Job {
+    id: u64,
+    priority: Priority,
+    run: Box<dyn FnOnce(Cancel) + Send>,
+}
+
+impl PartialEq for Job {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Job {}
+
+impl PartialOrd for Job {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Max-heap order: higher priority first, then the earlier submission
+impl Ord for Job {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        self.priority.cmp(&other.priority).then_with(|| other.id.cmp(&self.id))
+    }
+}
+
+struct Task {
+    name: String,
+    priority: Priority,
+    submitted: Instant,
+    running: bool,
+    cancel: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct Pool {
+    queue: Mutex<BinaryHeap<Job>>,
+    ready: Condvar,
+    tasks: Mutex<BTreeMap<u64, Task>>,
+    next_id: AtomicU64,
+}
+
+static POOL: OnceLock<Pool> = OnceLock::new();
+static WORKERS: Once = Once::new();
+
+// Leave a core for the UI and the async runtime
+fn worker_count() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get().saturating_sub(1))
+        .unwrap_or(1)
+        .clamp(1, MAX_WORKERS)
+}
+
+// The pool, starting its workers on first use
+fn pool() -> &'static Pool {
+    let pool = POOL.get_or_init(Pool::default);
+    WORKERS.call_once(|| {
+        for n in 0..worker_count() {
+            let spawned = std::thread::Builder::new().name(format!("compute-{}", n)).spawn(move || work(pool));
+            if let Err(e) = spawned {
+                eprintln!("Failed to start compute worker: {}", e);
+            }
+        }
+    });
+    pool
+}
+
+fn work(pool: &'static Pool) {
+    loop {
+        let job = {
+            let Ok(mut queue) = pool.queue.lock() else { return };
+            loop {
+                if let Some(job) = queue.pop() {
+                    break job;
+                }
+                let Ok(next) = pool.ready.wait(queue) else { return };
+                queue = next;
+            }
+        };
+
+        let flag = match pool.tasks.lock() {
+            Ok(mut tasks) => tasks.get_mut(&job.id).map(|task| {
+                task.running = true;
+                task.cancel.clone()
+            }),
+            Err(_) => None,
+        };
+        (job.run)(Cancel { flag: flag.unwrap_or_default() });
+        if let Ok(mut tasks) = pool.tasks.lock() {
+            tasks.remove(&job.id);
+        }
+    }
+}
+
+// Cancels the task when the future waiting on it is dropped
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+// Run `f` on the pool and wait for its result
+pub async fn run<T, F>(name: &str, priority: Priority, f: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce(&Cancel) -> Result<T, String> + Send + 'static,
+{
+    let pool = pool();
+    let id = pool.next_id.fetch_add(1, Ordering::Relaxed);
+    let cancel = Arc::new(AtomicBool::new(false));
+    let (tx, rx) = tokio::sync::oneshot::channel();
+
+    let task_name = name.to_string();
+    let run = Box::new(move |cancel: Cancel| {
+        let result = if cancel.is_cancelled() {
+            Err("Task cancelled".to_string())
+        } else {
+            // A panicking task must not take its worker down with it
+            catch_unwind(AssertUnwindSafe(|| f(&cancel)))
+                .unwrap_or_else(|_| Err(format!("{} task panicked", task_name)))
+        };
+        let _ = tx.send(result);
+    });
+
+    pool.tasks
+        .lock()
+        .map_err(|_| "Compute task lock poisoned".to_string())?
+        .insert(id, Task { name: name.to_string(), priority, submitted: Instant::now(), running: false, cancel: cancel.clone() });
+    pool.queue
+        .lock()
+        .map_err(|_| "Compute queue lock poisoned".to_string())?
+        .push(Job { id, priority, run });
+    pool.ready.notify_one();
+
+    let _guard = CancelOnDrop(cancel);
+    rx.await.map_err(|_| format!("{} task failed", name))?
+}
+
+// Queued and running tasks, running first
+#[tauri::command]
+pub async fn get_compute_tasks() -> Result<Vec<TaskInfo>, String> {
+    let tasks = pool().tasks.lock().map_err(|_| "Compute task lock poisoned".to_string())?;
+    let mut list: Vec<TaskInfo> = tasks
+        .iter()
+        .map(|(id, task)| TaskInfo {
+            id: *id,
+            name: task.name.clone(),
+            priority: task.priority,
+            running: task.running,
+            cancelled: task.cancel.load(Ordering::Relaxed),
+            age_ms: task.submitted.elapsed().as_millis() as u64,
+        })
+        .collect();
+    list.sort_by(|a, b| b.running.cmp(&a.running).then(b.priority.cmp(&a.priority)).then(a.id.cmp(&b.id)));
+    Ok(list)
+}
+
+// Returns false when the task already finished
+#[tauri::command]
+pub async fn cancel_compute_task(id: u64) -> Result<bool, String> {
+    let tasks = pool().tasks.lock().map_err(|_| "Compute task lock poisoned".to_string())?;
+    Ok(match tasks.get(&id) {
+        Some(task) => {
+            task.cancel.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    })
+}
@@ -0,0 +1,106 @@
+use crate::error::CommandError;
+use crate::startup;
+use rusqlite::Connection;
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::command::{CommandArg, CommandItem};
+use tauri::{AppHandle, InvokeError, Manager, State, Wry};
+
+// Local SQLite database holding hand histories, notes and sync bookkeeping
+pub struct Database {
+    conn: Mutex<Connection>,
+    schema_version: u32,
+}
+
+impl Database {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create data directory: {}", e))?;
+        }
+
+        let mut conn = Connection::open(path)
+            .map_err(|e| format!("Failed to open database: {}", e))?;
+
+        let schema_version = crate::migrations::run(&mut conn, path)?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+            schema_version,
+        })
+    }
+
+    // Leave the file settled before the app exits. Writes are committed as they are
+    // made; this only keeps the planner's statistics current for the next start.
+    pub fn optimize(&self) -> Result<(), String> {
+        self.with_conn(|conn| conn.execute_batch("PRAGMA optimize;"))
+    }
+
+    pub fn schema_version(&self) -> u32 {
+        self.schema_version
+    }
+
+    // Run a closure against the connection, mapping SQLite errors to strings
+    pub fn with_conn<T, F>(&self, f: F) -> Result<T, String>
+    where
+        F: FnOnce(&Connection) -> rusqlite::Result<T>,
+    {
+        let conn = self.conn.lock().map_err(|_| "Database lock poisoned".to_string())?;
+        f(&conn).map_err(|e| format!("Database error: {}", e))
+    }
+
+    pub fn get_value(&self, key: &str) -> Result<Option<String>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT value FROM kv WHERE key = ?1")?;
+            let mut rows = stmt.query([key])?;
+            match rows.next()? {
+                Some(row) => Ok(Some(row.get(0)?)),
+                None => Ok(None),
+            }
+        })
+    }
+
+    pub fn set_value(&self, key: &str, value: &str) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO kv (key, value) VALUES (?1, ?2)
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+                [key, value],
+            )?;
+            Ok(())
+        })
+    }
+}
+
+// The database is only managed once migrations finish, which is after the window
+// shows, and never if they fail. Commands take it as `Db` rather than
+// `State<Database>` so a call made before then fails with `DatabaseNotReady`
+// instead of panicking on unmanaged state.
+pub struct Db<'r>(State<'r, Database>);
+
+impl std::ops::Deref for Db<'_> {
+    type Target = Database;
+    fn deref(&self) -> &Database {
+        &self.0
+    }
+}
+
+impl<'de> CommandArg<'de, Wry> for Db<'de> {
+    fn from_command(command: CommandItem<'de, Wry>) -> Result<Self, InvokeError> {
+        let window = command.message.window_ref();
+        match window.try_state::<Database>() {
+            Some(db) => Ok(Db(db)),
+            None => Err(not_ready(&window.app_handle()).into()),
+        }
+    }
+}
+
+fn not_ready(app: &AppHandle) -> CommandError {
+    let reason = startup::stage_error(app, "database").unwrap_or_else(|| "It is still being opened".to_string());
+    CommandError::DatabaseNotReady { reason }
+}
+
+// The database for work off the command path, or why it is not there
+pub fn get(app: &AppHandle) -> Result<Db<'_>, String> {
+    app.try_state::<Database>().map(Db).ok_or_else(|| not_ready(app).to_string())
+}
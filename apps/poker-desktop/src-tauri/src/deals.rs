@@ -0,0 +1,197 @@
+// Final-table deals. Works out what each player would take from the prize money
+// still to be paid, either by ICM (Malmuth-Harville: the chance of finishing in each
+// place is stack-proportional, place by place) or by chip chop (everyone is paid the
+// last remaining place and the rest is split by chips), and proposes or accepts a
+// deal through the backend with those numbers attached so every player sees the
+// same split.
+
+use crate::audit;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::AppHandle;
+
+// ICM walks every finishing order, which doubles with each player
+const MAX_ICM_PLAYERS: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DealMethod {
+    Icm,
+    ChipChop,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DealStack {
+    player_id: String,
+    chips: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DealShare {
+    player_id: String,
+    chips: u64,
+    chip_share: f64,
+    amount: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Deal {
+    method: DealMethod,
+    // Remaining payouts, first place first
+    payouts: Vec<u64>,
+    shares: Vec<DealShare>,
+    total: u64,
+}
+
+// Prize equity of each stack under Malmuth-Harville. `placed` is the set of players
+// already finished above the rest, as a bit mask; its probability flows on to each
+// remaining player in proportion to their stack.
+fn icm(stacks: &[u64], payouts: &[u64]) -> Vec<f64> {
+    let n = stacks.len();
+    let places = payouts.len().min(n);
+    let mut equity = vec![0.0; n];
+    let mut reach = vec![0.0; 1 << n];
+    reach[0] = 1.0;
+    for placed in 0..(1usize << n) {
+        let place = placed.count_ones() as usize;
+        if reach[placed] == 0.0 || place >= places {
+            continue;
+        }
+        let left: u64 = (0..n).filter(|i| placed & (1 << i) == 0).map(|i| stacks[i]).sum();
+        for i in (0..n).filter(|i| placed & (1 << i) == 0) {
+            let p = reach[placed] * stacks[i] as f64 / left as f64;
+            equity[i] += p * payouts[place] as f64;
+            reach[placed | (1 << i)] += p;
+        }
+    }
+    equity
+}
+
+// Everyone is sure of the last place still paid if all of them are in the money
+fn chip_chop(stacks: &[u64], payouts: &[u64]) -> Vec<f64> {
+    let floor = if payouts.len() >= stacks.len() { payouts[stacks.len() - 1] } else { 0 };
+    let total: u64 = payouts.iter().take(stacks.len()).sum();
+    let chips: u64 = stacks.iter().sum();
+    let rest = (total - floor * stacks.len() as u64) as f64;
+    stacks.iter().map(|&s| floor as f64 + rest * s as f64 / chips as f64).collect()
+}
+
+// Whole amounts adding up to `total`; what rounding down leaves goes to the largest
+// fractions first
+fn round_shares(equity: &[f64], total: u64) -> Vec<u64> {
+    let mut amounts: Vec<u64> = equity.iter().map(|e| e.floor() as u64).collect();
+    let left = total.saturating_sub(amounts.iter().sum());
+    let mut order: Vec<usize> = (0..equity.len()).collect();
+    order.sort_by(|&a, &b| (equity[b] - equity[b].floor()).total_cmp(&(equity[a] - equity[a].floor())));
+    for i in order.into_iter().cycle().take(left as usize) {
+        amounts[i] += 1;
+    }
+    amounts
+}
+
+pub fn calculate(stacks: &[DealStack], payouts: &[u64], method: DealMethod) -> Result<Deal, String> {
+    if stacks.len() < 2 {
+        return Err("A deal needs at least two players".to_string());
+    }
+    if stacks.iter().any(|s| s.chips == 0) {
+        return Err("Every player in the deal needs chips".to_string());
+    }
+    if payouts.is_empty() || payouts.windows(2).any(|w| w[1] > w[0]) {
+        return Err("Remaining payouts must be listed from first place down".to_string());
+    }
+    // Places nobody left can reach are not part of the deal
+    let payouts: Vec<u64> = payouts.iter().take(stacks.len()).copied().collect();
+    let chips: Vec<u64> = stacks.iter().map(|s| s.chips).collect();
+    let equity = match method {
+        DealMethod::Icm if stacks.len() > MAX_ICM_PLAYERS => {
+            return Err(format!("ICM deals are limited to {} players", MAX_ICM_PLAYERS));
+        }
+        DealMethod::Icm => icm(&chips, &payouts),
+        DealMethod::ChipChop => chip_chop(&chips, &payouts),
+    };
+    let total: u64 = payouts.iter().sum();
+    let chip_total: u64 = chips.iter().sum();
+    let shares = stacks
+        .iter()
+        .zip(round_shares(&equity, total))
+        .map(|(stack, amount)| DealShare {
+            player_id: stack.player_id.clone(),
+            chips: stack.chips,
+            chip_share: stack.chips as f64 / chip_total as f64,
+            amount,
+        })
+        .collect();
+    Ok(Deal { method, payouts, shares, total })
+}
+
+async fn post_deal(url: String, body: &Value) -> Result<Value, String> {
+    let client = crate::create_http_client()?;
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+
+    let response = crate::http::send(client.post(url)
+        .header("Authorization", format!("Bearer {}", token))
+        .json(body))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("Deal request failed: {}", error_text));
+    }
+
+    let api_response: crate::ApiResponse<Value> = response.json().await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    if api_response.success {
+        Ok(api_response.data.unwrap_or(json!({})))
+    } else {
+        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    }
+}
+
+#[tauri::command]
+pub async fn calculate_deal(
+    stacks: Vec<DealStack>,
+    remaining_payouts: Vec<u64>,
+    method: DealMethod,
+) -> Result<Deal, String> {
+    calculate(&stacks, &remaining_payouts, method)
+}
+
+// Propose a deal to the table; the backend collects the other players' answers
+#[tauri::command]
+pub async fn propose_deal(
+    app: AppHandle,
+    api_url: String,
+    tournament_id: String,
+    stacks: Vec<DealStack>,
+    remaining_payouts: Vec<u64>,
+    method: DealMethod,
+) -> Result<Value, String> {
+    let deal = calculate(&stacks, &remaining_payouts, method)?;
+    let body = json!({ "deal": deal });
+    let result = post_deal(format!("{}/api/tournaments/{}/deals", api_url, tournament_id), &body).await;
+    audit::record(&app, "propose_deal", json!({ "tournamentId": tournament_id, "deal": deal }), &result);
+    result
+}
+
+// Accept a proposed deal. The split being agreed to is sent along, so it only
+// goes through if it is still the one on the table.
+#[tauri::command]
+pub async fn accept_deal(
+    app: AppHandle,
+    api_url: String,
+    tournament_id: String,
+    deal_id: String,
+    deal: Deal,
+) -> Result<Value, String> {
+    let body = json!({ "deal": deal });
+    let url = format!("{}/api/tournaments/{}/deals/{}/accept", api_url, tournament_id, deal_id);
+    let result = post_deal(url, &body).await;
+    audit::record(&app, "accept_deal", json!({ "tournamentId": tournament_id, "dealId": deal_id }), &result);
+    result
+}
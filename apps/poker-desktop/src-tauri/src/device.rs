@@ -0,0 +1,197 @@
+// Device identity for login. The identifier is a SHA-256 of the OS machine id mixed
+// with an app-specific salt, so it is stable across reinstalls but cannot be matched
+// against the raw machine id or against other apps. Trusting a device stores a token
+// from the backend in the keyring; logins present it so backend policy can skip 2FA.
+
+use crate::accounts;
+use crate::audit;
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+use tauri::AppHandle;
+
+const SALT: &str = "primo-poker-device-v1";
+const KEYRING_TRUST: &str = "device-trust";
+
+const HEADER_DEVICE_ID: &str = "X-Device-Id";
+const HEADER_DEVICE_TRUST: &str = "X-Device-Trust";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrustedDevice {
+    id: String,
+    device_id: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    trusted_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+    // Filled in locally
+    #[serde(default)]
+    this_device: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrustResponse {
+    device: TrustedDevice,
+    trust_token: String,
+}
+
+#[cfg(target_os = "linux")]
+fn machine_id() -> Option<String> {
+    ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+        .map(|id| id.trim().to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn machine_id() -> Option<String> {
+    let output = std::process::Command::new("ioreg").args(["-rd1", "-c", "IOPlatformExpertDevice"]).output().ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find(|line| line.contains("IOPlatformUUID"))
+        .and_then(|line| line.split('"').nth(3))
+        .map(String::from)
+}
+
+#[cfg(target_os = "windows")]
+fn machine_id() -> Option<String> {
+    let output = std::process::Command::new("reg")
+        .args(["query", r"HKLM\SOFTWARE\Microsoft\Cryptography", "/v", "MachineGuid"])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find(|line| line.contains("MachineGuid"))
+        .and_then(|line| line.split_whitespace().last())
+        .map(String::from)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn machine_id() -> Option<String> {
+    None
+}
+
+fn host_name() -> Option<String> {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+// Stable hashed identifier for this machine
+pub fn device_id() -> &'static str {
+    static ID: OnceLock<String> = OnceLock::new();
+    ID.get_or_init(|| {
+        let machine = machine_id()
+            .filter(|id| !id.is_empty())
+            .or_else(host_name)
+            .unwrap_or_default();
+        let mut hasher = Sha256::new();
+        hasher.update(SALT.as_bytes());
+        hasher.update(machine.as_bytes());
+        hasher.update(std::env::consts::OS.as_bytes());
+        hasher.update(std::env::consts::ARCH.as_bytes());
+        hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+    })
+}
+
+// Trust token for this device, if it has been trusted
+pub fn trust_token() -> Option<String> {
+    Entry::new("primo-poker", &accounts::keyring_key(KEYRING_TRUST)).ok()?.get_password().ok()
+}
+
+fn set_trust_token(token: Option<&str>) -> Result<(), String> {
+    let entry = Entry::new("primo-poker", &accounts::keyring_key(KEYRING_TRUST))
+        .map_err(|e| format!("Keyring error: {}", e))?;
+    match token {
+        Some(token) => entry.set_password(token).map_err(|e| format!("Failed to store device trust: {}", e)),
+        None => match entry.delete_password() {
+            Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(format!("Failed to clear device trust: {}", e)),
+        },
+    }
+}
+
+// Attach the device headers sent with login
+pub fn with_device_headers(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    let request = request.header(HEADER_DEVICE_ID, device_id());
+    match trust_token() {
+        Some(token) => request.header(HEADER_DEVICE_TRUST, token),
+        None => request,
+    }
+}
+
+async fn request<T: serde::de::DeserializeOwned>(builder: reqwest::RequestBuilder) -> Result<T, String> {
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+
+    let response = crate::http::send(builder.header("Authorization", format!("Bearer {}", token)))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("Device request failed: {}", error_text));
+    }
+
+    let api_response: crate::ApiResponse<T> = response.json().await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    if api_response.success {
+        api_response.data.ok_or_else(|| "No data returned".to_string())
+    } else {
+        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    }
+}
+
+#[tauri::command]
+pub async fn get_device_id() -> Result<String, String> {
+    Ok(device_id().to_string())
+}
+
+#[tauri::command]
+pub async fn trust_this_device(app: AppHandle, api_url: String, name: Option<String>) -> Result<TrustedDevice, String> {
+    let name = name.or_else(host_name);
+    let client = crate::create_http_client()?;
+    let result = request::<TrustResponse>(client
+        .post(format!("{}/api/auth/devices/trust", api_url))
+        .json(&json!({ "deviceId": device_id(), "name": name })))
+        .await;
+    audit::record(&app, "trust_device", json!({ "name": name }), &result);
+
+    let response = result?;
+    set_trust_token(Some(&response.trust_token))?;
+    Ok(TrustedDevice { this_device: true, ..response.device })
+}
+
+#[tauri::command]
+pub async fn list_trusted_devices(api_url: String) -> Result<Vec<TrustedDevice>, String> {
+    let client = crate::create_http_client()?;
+    let mut devices: Vec<TrustedDevice> = request(client.get(format!("{}/api/auth/devices", api_url))).await?;
+    for device in &mut devices {
+        device.this_device = device.device_id == device_id();
+    }
+    Ok(devices)
+}
+
+// Revoking this device's trust also forgets the local trust token
+#[tauri::command]
+pub async fn revoke_trusted_device(app: AppHandle, api_url: String, id: String) -> Result<(), String> {
+    let client = crate::create_http_client()?;
+    let device = request::<Value>(client.delete(format!("{}/api/auth/devices/{}", api_url, id))).await;
+    audit::record(&app, "revoke_trusted_device", json!({ "id": id }), &device);
+    let device = device?;
+
+    if device["deviceId"].as_str() == Some(device_id()) {
+        set_trust_token(None)?;
+    }
+    Ok(())
+}
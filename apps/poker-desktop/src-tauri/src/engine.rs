@@ -0,0 +1,592 @@
+// Local hold'em engine, no limit or fixed limit. Runs a whole table in-process for practice play and
+// training; state is exposed through the same `TableMirror` snapshot the frontend
+// receives from the backend, and finished hands convert to `HandRecord`s so they can
+// be stored and replayed like online hands.
+
+use crate::cards::{shuffled_deck, Card};
+use crate::evaluator::{evaluate, HandValue};
+use crate::history::{AnteStructure, HandAction, HandPlayer, HandRecord};
+use crate::showdown::{self, Participant, Pot, ShowdownExplanation};
+use crate::sizing::{self, Limit, TableRules};
+use crate::table_state::{LegalAction, Position, SeatState, TableMirror};
+use crate::verify::{self, Violation};
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Street {
+    Waiting,
+    PreFlop,
+    Flop,
+    Turn,
+    River,
+    Showdown,
+    Finished,
+}
+
+impl Street {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Street::Waiting => "waiting",
+            Street::PreFlop => "pre_flop",
+            Street::Flop => "flop",
+            Street::Turn => "turn",
+            Street::River => "river",
+            Street::Showdown => "showdown",
+            Street::Finished => "finished",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Seat {
+    pub player_id: String,
+    pub username: String,
+    pub stack: u32,
+    // Stack at the start of the current hand
+    pub starting_stack: u32,
+    pub hole_cards: Option<[Card; 2]>,
+    // Chips put in on the current street, and over the whole hand
+    pub street_bet: u32,
+    pub committed: u32,
+    pub in_hand: bool,
+    pub folded: bool,
+    pub all_in: bool,
+    pub acted: bool,
+}
+
+impl Seat {
+    fn can_act(&self) -> bool {
+        self.in_hand && !self.folded && !self.all_in
+    }
+
+    fn is_live(&self) -> bool {
+        self.in_hand && !self.folded
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Winner {
+    pub player_id: String,
+    pub amount: u32,
+    pub hand_description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShownHand {
+    pub player_id: String,
+    pub cards: Vec<Card>,
+    pub hand_description: String,
+}
+
+// Same shape as the backend's `hand_completed` payload
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HandResult {
+    pub hand_number: u32,
+    pub winners: Vec<Winner>,
+    pub showdown: Vec<ShownHand>,
+    // Pot-by-pot narration; not part of the backend payload
+    pub explanation: Option<ShowdownExplanation>,
+}
+
+pub struct LocalTable {
+    pub table_id: String,
+    pub table_name: String,
+    pub small_blind: u32,
+    pub big_blind: u32,
+    pub ante: u32,
+    pub ante_structure: AnteStructure,
+    pub betting_structure: Limit,
+    pub seats: Vec<Seat>,
+    pub button: usize,
+    pub small_blind_seat: Option<usize>,
+    pub big_blind_seat: Option<usize>,
+    pub street: Street,
+    pub board: Vec<Card>,
+    deck: Vec<Card>,
+    pub current_bet: u32,
+    pub min_raise: u32,
+    pub to_act: Option<usize>,
+    pub hand_number: u32,
+    pub started_at: DateTime<Utc>,
+    pub actions: Vec<HandAction>,
+    pub result: Option<HandResult>,
+    // Invariant violations found in verification mode, until the caller takes them
+    pub violations: Vec<Violation>,
+}
+
+impl LocalTable {
+    pub fn new(table_id: &str, table_name: &str, small_blind: u32, big_blind: u32) -> Self {
+        Self {
+            table_id: table_id.to_string(),
+            table_name: table_name.to_string(),
+            small_blind,
+            big_blind,
+            ante: 0,
+            ante_structure: AnteStructure::Standard,
+            betting_structure: Limit::No,
+            seats: Vec::new(),
+            button: 0,
+            small_blind_seat: None,
+            big_blind_seat: None,
+            street: Street::Waiting,
+            board: Vec::new(),
+            deck: Vec::new(),
+            current_bet: 0,
+            min_raise: big_blind,
+            to_act: None,
+            hand_number: 0,
+            started_at: Utc::now(),
+            actions: Vec::new(),
+            result: None,
+            violations: Vec::new(),
+        }
+    }
+
+    // Takes effect from the next hand
+    pub fn set_ante(&mut self, ante: u32, structure: AnteStructure) {
+        self.ante = ante;
+        self.ante_structure = structure;
+    }
+
+    pub fn rules(&self) -> TableRules {
+        TableRules { small_blind: self.small_blind, big_blind: self.big_blind, structure: self.betting_structure }
+    }
+
+    pub fn add_seat(&mut self, player_id: &str, username: &str, stack: u32) {
+        self.seats.push(Seat {
+            player_id: player_id.to_string(),
+            username: username.to_string(),
+            stack,
+            starting_stack: stack,
+            hole_cards: None,
+            street_bet: 0,
+            committed: 0,
+            in_hand: false,
+            folded: false,
+            all_in: false,
+            acted: false,
+        });
+    }
+
+    pub fn is_hand_over(&self) -> bool {
+        matches!(self.street, Street::Waiting | Street::Showdown | Street::Finished)
+    }
+
+    pub fn pot(&self) -> u32 {
+        self.seats.iter().map(|s| s.committed).sum()
+    }
+
+    // Next seat after `from`, clockwise, matching `pred`
+    fn next_seat(&self, from: usize, pred: impl Fn(&Seat) -> bool) -> Option<usize> {
+        let n = self.seats.len();
+        (1..=n).map(|step| (from + step) % n).find(|&i| pred(&self.seats[i]))
+    }
+
+    // Antes are dead money: they go into the pot but do not count towards the bet to
+    // call. A player already all-in posts nothing more.
+    fn post(&mut self, seat: usize, amount: u32, action: &str) {
+        let seat_state = &mut self.seats[seat];
+        if seat_state.stack == 0 {
+            return;
+        }
+        let paid = amount.min(seat_state.stack);
+        seat_state.stack -= paid;
+        if action != "ante" {
+            seat_state.street_bet += paid;
+        }
+        seat_state.committed += paid;
+        if seat_state.stack == 0 {
+            seat_state.all_in = true;
+        }
+        self.actions.push(HandAction {
+            street: self.street.as_str().to_string(),
+            player_id: seat_state.player_id.clone(),
+            action: action.to_string(),
+            amount: paid,
+        });
+    }
+
+    pub fn start_hand<R: Rng>(&mut self, rng: &mut R) -> Result<(), String> {
+        if self.seats.iter().filter(|s| s.stack > 0).count() < 2 {
+            return Err("At least two players with chips are needed to deal".to_string());
+        }
+
+        for seat in &mut self.seats {
+            seat.in_hand = seat.stack > 0;
+            seat.starting_stack = seat.stack;
+            seat.hole_cards = None;
+            seat.street_bet = 0;
+            seat.committed = 0;
+            seat.folded = false;
+            seat.all_in = false;
+            seat.acted = false;
+        }
+
+        self.button = if self.hand_number == 0 {
+            self.seats.iter().position(|s| s.in_hand).unwrap_or(0)
+        } else {
+            self.next_seat(self.button, |s| s.in_hand).unwrap_or(0)
+        };
+        self.hand_number += 1;
+        self.started_at = Utc::now();
+        self.street = Street::PreFlop;
+        self.board.clear();
+        self.actions.clear();
+        self.result = None;
+        self.deck = shuffled_deck(rng);
+
+        for seat in self.seats.iter_mut().filter(|s| s.in_hand) {
+            if let (Some(first), Some(second)) = (self.deck.pop(), self.deck.pop()) {
+                seat.hole_cards = Some([first, second]);
+            }
+        }
+
+        // Heads-up the button posts the small blind and acts first preflop
+        let players = self.seats.iter().filter(|s| s.in_hand).count();
+        let small_blind = if players == 2 {
+            self.button
+        } else {
+            self.next_seat(self.button, |s| s.in_hand).unwrap_or(self.button)
+        };
+        let big_blind = self.next_seat(small_blind, |s| s.in_hand).unwrap_or(small_blind);
+        self.small_blind_seat = Some(small_blind);
+        self.big_blind_seat = Some(big_blind);
+
+        // Standard and button antes go in before the blinds. A big blind ante comes
+        // after the blind, so a short big blind covers the blind first.
+        if self.ante > 0 {
+            match self.ante_structure {
+                AnteStructure::Standard => {
+                    for seat in 0..self.seats.len() {
+                        if self.seats[seat].in_hand {
+                            self.post(seat, self.ante, "ante");
+                        }
+                    }
+                }
+                AnteStructure::Button => self.post(self.button, self.ante, "ante"),
+                AnteStructure::BigBlind => {}
+            }
+        }
+        self.post(small_blind, self.small_blind, "small_blind");
+        self.post(big_blind, self.big_blind, "big_blind");
+        if self.ante > 0 && self.ante_structure == AnteStructure::BigBlind {
+            self.post(big_blind, self.ante, "ante");
+        }
+
+        self.current_bet = self.seats.iter().map(|s| s.street_bet).max().unwrap_or(0);
+        self.min_raise = self.big_blind;
+        self.to_act = self.next_seat(big_blind, Seat::can_act);
+        self.progress(big_blind);
+        self.verify("deal".to_string());
+        Ok(())
+    }
+
+    // Snapshot of the table as `viewer` sees it. Other players' cards are only
+    // visible once they are shown down.
+    pub fn mirror(&self, viewer: Option<&str>) -> TableMirror {
+        let shown = self.result.as_ref().map(|r| &r.showdown);
+        let seat_id = |seat: Option<usize>| seat.map(|i| self.seats[i].player_id.clone());
+
+        TableMirror {
+            table_id: self.table_id.clone(),
+            game_id: Some(format!("{}-{}", self.table_id, self.hand_number)),
+            phase: self.street.as_str().to_string(),
+            pot: self.pot(),
+            side_pots: Vec::new(),
+            current_bet: self.current_bet,
+            min_raise: self.min_raise,
+            active_player_id: seat_id(self.to_act),
+            dealer_id: self.seats.get(self.button).map(|s| s.player_id.clone()),
+            small_blind_id: seat_id(self.small_blind_seat),
+            big_blind_id: seat_id(self.big_blind_seat),
+            small_blind: self.small_blind,
+            big_blind: self.big_blind,
+            ante: self.ante,
+            ante_structure: self.ante_structure,
+            hand_number: self.hand_number,
+            community_cards: self.board.iter().map(|c| c.to_wire()).collect(),
+            players: self
+                .seats
+                .iter()
+                .enumerate()
+                .map(|(i, seat)| {
+                    let visible = viewer == Some(seat.player_id.as_str())
+                        || shown.is_some_and(|s| s.iter().any(|h| h.player_id == seat.player_id));
+                    SeatState {
+                        id: seat.player_id.clone(),
+                        username: seat.username.clone(),
+                        chips: seat.stack,
+                        current_bet: seat.street_bet,
+                        has_acted: seat.acted,
+                        is_folded: seat.folded || !seat.in_hand,
+                        is_all_in: seat.all_in,
+                        position: Some(Position { seat: i as u8 }),
+                        cards: seat
+                            .hole_cards
+                            .filter(|_| visible)
+                            .map(|cards| cards.iter().map(|c| c.to_wire()).collect()),
+                        status: None,
+                    }
+                })
+                .collect(),
+            ..TableMirror::default()
+        }
+    }
+
+    pub fn legal_actions(&self) -> Vec<LegalAction> {
+        match self.to_act {
+            Some(seat) => sizing::legal_actions(&self.mirror(None), &self.rules(), &self.seats[seat].player_id),
+            None => Vec::new(),
+        }
+    }
+
+    // Apply an action for the player to act. `amount` is only read for bet and raise
+    // and counts chips added, as on the backend.
+    pub fn act(&mut self, player_id: &str, action: &str, amount: u32) -> Result<(), String> {
+        let seat = self.to_act.ok_or_else(|| "No action is pending".to_string())?;
+        if self.seats[seat].player_id != player_id {
+            return Err("It is not your turn".to_string());
+        }
+        let legal = self.legal_actions();
+        let option = legal
+            .iter()
+            .find(|a| a.action == action)
+            .ok_or_else(|| format!("'{}' is not a legal action now", action))?;
+
+        let to_call = self.current_bet.saturating_sub(self.seats[seat].street_bet);
+        let paid = match action {
+            "bet" | "raise" => {
+                if amount < option.min_amount || amount > option.max_amount {
+                    return Err(format!(
+                        "Amount must be between {} and {}",
+                        option.min_amount, option.max_amount
+                    ));
+                }
+                amount
+            }
+            "call" => to_call,
+            "all_in" => self.seats[seat].stack,
+            _ => 0,
+        };
+
+        {
+            let seat_state = &mut self.seats[seat];
+            seat_state.stack -= paid;
+            seat_state.street_bet += paid;
+            seat_state.committed += paid;
+            seat_state.acted = true;
+            if action == "fold" {
+                seat_state.folded = true;
+            }
+            if seat_state.stack == 0 && paid > 0 {
+                seat_state.all_in = true;
+            }
+        }
+
+        let street_bet = self.seats[seat].street_bet;
+        if street_bet > self.current_bet {
+            let raise = street_bet - self.current_bet;
+            // A full raise reopens the action for everyone who already acted
+            if raise >= self.min_raise {
+                self.min_raise = raise;
+                for (i, other) in self.seats.iter_mut().enumerate() {
+                    if i != seat {
+                        other.acted = false;
+                    }
+                }
+            }
+            self.current_bet = street_bet;
+        }
+
+        self.actions.push(HandAction {
+            street: self.street.as_str().to_string(),
+            player_id: player_id.to_string(),
+            action: action.to_string(),
+            amount: paid,
+        });
+        self.progress(seat);
+        self.verify(format!("{} {} {}", player_id, action, paid));
+        Ok(())
+    }
+
+    fn verify(&mut self, event: String) {
+        if verify::enabled() {
+            let found = verify::check(self, &event);
+            self.violations.extend(found);
+        }
+    }
+
+    fn round_complete(&self) -> bool {
+        let actors: Vec<&Seat> = self.seats.iter().filter(|s| s.can_act()).collect();
+        if actors.len() <= 1 {
+            return actors.iter().all(|s| s.street_bet >= self.current_bet);
+        }
+        actors.iter().all(|s| s.acted && s.street_bet == self.current_bet)
+    }
+
+    // Move the hand forward after the seat at `last` acted
+    fn progress(&mut self, last: usize) {
+        if self.seats.iter().filter(|s| s.is_live()).count() == 1 {
+            self.win_uncontested();
+            return;
+        }
+
+        if !self.round_complete() {
+            let current_bet = self.current_bet;
+            self.to_act = self.next_seat(last, |s| s.can_act() && (!s.acted || s.street_bet < current_bet));
+            return;
+        }
+
+        loop {
+            for seat in &mut self.seats {
+                seat.street_bet = 0;
+                seat.acted = false;
+            }
+            self.current_bet = 0;
+            self.min_raise = self.big_blind;
+
+            let (next, cards) = match self.street {
+                Street::PreFlop => (Street::Flop, 3),
+                Street::Flop => (Street::Turn, 1),
+                Street::Turn => (Street::River, 1),
+                _ => {
+                    self.showdown();
+                    return;
+                }
+            };
+            self.street = next;
+            for _ in 0..cards {
+                if let Some(card) = self.deck.pop() {
+                    self.board.push(card);
+                }
+            }
+
+            // With fewer than two players able to bet, run the board out
+            if self.seats.iter().filter(|s| s.can_act()).count() >= 2 {
+                self.to_act = self.next_seat(self.button, Seat::can_act);
+                return;
+            }
+        }
+    }
+
+    fn win_uncontested(&mut self) {
+        let pot = self.pot();
+        if let Some(seat) = self.seats.iter().position(Seat::is_live) {
+            let winner = &mut self.seats[seat];
+            winner.stack += pot;
+            let explanation = showdown::explain(
+                &[Pot { amount: pot, eligible: vec![0], winners: vec![0], shares: vec![pot] }],
+                &[Participant { seat: seat as u8, value: None }],
+            );
+            self.result = Some(HandResult {
+                hand_number: self.hand_number,
+                winners: vec![Winner { player_id: winner.player_id.clone(), amount: pot, hand_description: None }],
+                showdown: Vec::new(),
+                explanation: Some(explanation),
+            });
+        }
+        self.street = Street::Finished;
+        self.to_act = None;
+    }
+
+    pub fn hand_value(&self, seat: usize) -> Option<HandValue> {
+        let hole = self.seats.get(seat)?.hole_cards?;
+        let cards: Vec<Card> = hole.iter().chain(self.board.iter()).copied().collect();
+        Some(evaluate(&cards))
+    }
+
+    fn showdown(&mut self) {
+        self.street = Street::Showdown;
+        self.to_act = None;
+
+        let values: Vec<Option<HandValue>> = (0..self.seats.len())
+            .map(|i| if self.seats[i].is_live() { self.hand_value(i) } else { None })
+            .collect();
+
+        let committed: Vec<u32> = self.seats.iter().map(|s| s.committed).collect();
+        let n = self.seats.len();
+        // Odd chips go to the first winner left of the button
+        let order: Vec<usize> = (1..=n).map(|step| (self.button + step) % n).collect();
+        let pots = showdown::side_pots(&committed, &values, &order, 0);
+
+        let mut winnings = vec![0u32; n];
+        for pot in &pots {
+            for (&i, &share) in pot.winners.iter().zip(&pot.shares) {
+                winnings[i] += share;
+            }
+        }
+        let participants: Vec<Participant> = (0..n).map(|i| Participant { seat: i as u8, value: values[i] }).collect();
+        let explanation = showdown::explain(&pots, &participants);
+
+        let mut winners = Vec::new();
+        let mut showdown = Vec::new();
+        for (i, seat) in self.seats.iter_mut().enumerate() {
+            seat.stack += winnings[i];
+            let (Some(value), Some(hole)) = (values[i], seat.hole_cards) else { continue };
+            if winnings[i] > 0 {
+                winners.push(Winner {
+                    player_id: seat.player_id.clone(),
+                    amount: winnings[i],
+                    hand_description: Some(value.describe()),
+                });
+            }
+            showdown.push(ShownHand {
+                player_id: seat.player_id.clone(),
+                cards: hole.to_vec(),
+                hand_description: value.describe(),
+            });
+        }
+        self.result = Some(HandResult { hand_number: self.hand_number, winners, showdown, explanation: Some(explanation) });
+    }
+
+    // History record of the finished hand. Opponents' cards are kept only if shown.
+    pub fn to_hand_record(&self, hero_id: &str) -> HandRecord {
+        let shown = |id: &str| {
+            self.result.as_ref().is_some_and(|r| r.showdown.iter().any(|h| h.player_id == id))
+        };
+        HandRecord {
+            id: format!("{}-{}", self.table_id, self.hand_number),
+            table_id: self.table_id.clone(),
+            table_name: Some(self.table_name.clone()),
+            played_at: self.started_at,
+            updated_at: Utc::now(),
+            game_type: "holdem".to_string(),
+            betting_structure: self.betting_structure.as_str().to_string(),
+            small_blind: self.small_blind,
+            big_blind: self.big_blind,
+            ante: self.ante,
+            ante_structure: self.ante_structure,
+            hero_id: Some(hero_id.to_string()),
+            players: self
+                .seats
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| s.in_hand)
+                .map(|(i, s)| HandPlayer {
+                    player_id: s.player_id.clone(),
+                    username: s.username.clone(),
+                    seat: i as u8,
+                    starting_stack: s.starting_stack,
+                    hole_cards: s
+                        .hole_cards
+                        .filter(|_| s.player_id == hero_id || shown(&s.player_id))
+                        .map(|cards| cards.iter().map(|c| c.to_string()).collect()),
+                    net: s.stack as i64 - s.starting_stack as i64,
+                })
+                .collect(),
+            board: self.board.iter().map(|c| c.to_string()).collect(),
+            actions: self.actions.clone(),
+            pot: self.pot(),
+            rake: 0,
+            promotions: Vec::new(),
+            showdown_choice: None,
+            action_hash: None,
+        }
+    }
+}
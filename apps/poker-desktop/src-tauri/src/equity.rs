@@ -0,0 +1,64 @@
+// Monte Carlo equity. Unknown hole cards are dealt at random on every trial, so the
+// same function covers hero-vs-random and fully known all-in matchups.
+
+use crate::cards::{full_deck, Card};
+use crate::evaluator::evaluate;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+// Share of the pot each player wins on average, ties split. `players` holds each
+// player's hole cards, or None when unknown.
+pub fn equity<R: Rng>(players: &[Option<[Card; 2]>], board: &[Card], trials: u32, rng: &mut R) -> Vec<f64> {
+    let mut shares = vec![0.0; players.len()];
+    if players.is_empty() || trials == 0 || board.len() > 5 {
+        return shares;
+    }
+
+    let dead: Vec<Card> = players.iter().flatten().flatten().chain(board).copied().collect();
+    let mut stub: Vec<Card> = full_deck().into_iter().filter(|c| !dead.contains(c)).collect();
+    let unknown = players.iter().filter(|p| p.is_none()).count();
+    let needed = unknown * 2 + (5 - board.len());
+    if stub.len() < needed {
+        return shares;
+    }
+
+    let mut cards = Vec::with_capacity(7);
+    for _ in 0..trials {
+        let (drawn, _) = stub.partial_shuffle(rng, needed);
+        let (dealt, rest) = drawn.split_at(unknown * 2);
+        let mut dealt = dealt.chunks_exact(2);
+
+        let hands: Vec<[Card; 2]> = players
+            .iter()
+            .filter_map(|known| known.or_else(|| dealt.next().map(|pair| [pair[0], pair[1]])))
+            .collect();
+        let runout: Vec<Card> = board.iter().chain(rest.iter()).copied().collect();
+
+        let values: Vec<_> = hands
+            .iter()
+            .map(|hole| {
+                cards.clear();
+                cards.extend_from_slice(hole);
+                cards.extend_from_slice(&runout);
+                evaluate(&cards)
+            })
+            .collect();
+        let Some(best) = values.iter().max() else { continue };
+        let winners = values.iter().filter(|v| *v == best).count() as f64;
+        for (share, value) in shares.iter_mut().zip(&values) {
+            if value == best {
+                *share += 1.0 / winners;
+            }
+        }
+    }
+
+    shares.iter_mut().for_each(|share| *share /= trials as f64);
+    shares
+}
+
+// Hero's equity against `opponents` random hands
+pub fn equity_vs_random<R: Rng>(hole: [Card; 2], board: &[Card], opponents: usize, trials: u32, rng: &mut R) -> f64 {
+    let mut players = vec![Some(hole)];
+    players.resize(opponents.max(1) + 1, None);
+    equity(&players, board, trials, rng)[0]
+}
@@ -0,0 +1,81 @@
+use crate::ratelimit::EndpointClass;
+use serde::Serialize;
+
+// Typed command error. Serialized with a `code` tag so the frontend can branch on the
+// failure kind instead of matching on message text.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum CommandError {
+    #[serde(rename_all = "camelCase")]
+    FeatureRestricted {
+        feature: String,
+        jurisdiction: Option<String>,
+        reason: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    ComplianceUnavailable { message: String },
+    #[serde(rename_all = "camelCase")]
+    RateLimited {
+        endpoint_class: EndpointClass,
+        retry_after_ms: u64,
+    },
+    // The token's claims rule the action out (see guard.rs)
+    #[serde(rename_all = "camelCase")]
+    Forbidden { action: String, reason: String },
+    #[serde(rename_all = "camelCase")]
+    KycRequired {
+        action: String,
+        required_level: u8,
+        current_level: u8,
+    },
+    #[serde(rename_all = "camelCase")]
+    IncompatibleBackend {
+        server_api_version: Option<u32>,
+        client_version: String,
+        min_client_version: Option<String>,
+        // The backend needs a newer client rather than the other way round
+        update_required: bool,
+        reason: String,
+    },
+    // Called before the local database finished opening, or after it failed to
+    #[serde(rename_all = "camelCase")]
+    DatabaseNotReady { reason: String },
+    #[serde(rename_all = "camelCase")]
+    Network { message: String },
+    #[serde(rename_all = "camelCase")]
+    Other { message: String },
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandError::FeatureRestricted { feature, reason, .. } => {
+                write!(f, "{} is not available: {}", feature, reason)
+            }
+            CommandError::ComplianceUnavailable { message } => {
+                write!(f, "Compliance check unavailable: {}", message)
+            }
+            CommandError::RateLimited { retry_after_ms, .. } => {
+                write!(f, "Too many requests, retry after {}ms", retry_after_ms)
+            }
+            CommandError::Forbidden { action, reason } => {
+                write!(f, "Not allowed to {}: {}", action, reason)
+            }
+            CommandError::KycRequired { action, required_level, current_level } => {
+                write!(f, "Identity verification level {} is needed to {} (yours is {})", required_level, action, current_level)
+            }
+            CommandError::IncompatibleBackend { reason, .. } => {
+                write!(f, "Incompatible backend: {}", reason)
+            }
+            CommandError::DatabaseNotReady { reason } => write!(f, "The local database is not ready: {}", reason),
+            CommandError::Network { message } => write!(f, "Network error: {}", message),
+            CommandError::Other { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::Other { message }
+    }
+}
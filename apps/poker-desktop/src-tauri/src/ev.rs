@@ -0,0 +1,198 @@
+// All-in expected value. Once every player left in a hand is all-in (or all but one)
+// before the river, the rest is luck: the hero's share of each pot is worked out from
+// their equity at that moment and compared with what they actually won. Results are
+// stored per hand in `hand_ev` and recomputed when the hand is rewritten.
+
+use crate::cards::Card;
+use crate::compute::{self, Cancel, Priority};
+use crate::db::{self, Database, Db};
+use crate::equity::equity;
+use crate::history::{self, to_millis, HandRecord};
+use crate::trainer::board_len;
+use rand::Rng;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use tauri::AppHandle;
+
+const TRIALS: u32 = 3000;
+
+// Hands evaluated per database round trip; the lock is released while equity runs
+const BATCH: u32 = 200;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AllInEv {
+    // Street on which the money went in
+    pub street: String,
+    // Hero's equity in the main pot
+    pub equity: f64,
+    pub actual_net: i64,
+    pub expected_net: f64,
+}
+
+pub fn committed(hand: &HandRecord) -> HashMap<&str, u32> {
+    let mut committed: HashMap<&str, u32> = HashMap::new();
+    for action in &hand.actions {
+        *committed.entry(action.player_id.as_str()).or_insert(0) += action.amount;
+    }
+    committed
+}
+
+// None unless the hero was all-in, or called one, with cards still to come
+pub fn all_in_ev<R: Rng>(hand: &HandRecord, rng: &mut R) -> Option<AllInEv> {
+    let hero = hand.hero_id.as_deref()?;
+    let last = hand.actions.last()?;
+    let dealt = board_len(&last.street);
+    if dealt >= 5 || hand.board.len() < dealt {
+        return None;
+    }
+
+    let committed = committed(hand);
+    let folded: HashSet<&str> = hand.actions.iter().filter(|a| a.action == "fold").map(|a| a.player_id.as_str()).collect();
+    let live: Vec<_> = hand.players.iter().filter(|p| !folded.contains(p.player_id.as_str())).collect();
+    let paid = |id: &str| committed.get(id).copied().unwrap_or(0);
+    let all_in = live.iter().filter(|p| paid(&p.player_id) >= p.starting_stack).count();
+    if live.len() < 2 || all_in == 0 || all_in + 1 < live.len() || !live.iter().any(|p| p.player_id == hero) {
+        return None;
+    }
+
+    let holes = live
+        .iter()
+        .map(|p| {
+            let cards = p.hole_cards.as_ref()?;
+            Some([Card::parse(cards.first()?)?, Card::parse(cards.get(1)?)?])
+        })
+        .collect::<Option<Vec<[Card; 2]>>>()?;
+    let board = hand.board[..dealt].iter().map(|c| Card::parse(c)).collect::<Option<Vec<Card>>>()?;
+
+    // Split the money into a main pot and side pots by commitment level
+    let mut levels: Vec<u32> = live.iter().map(|p| paid(&p.player_id)).collect();
+    levels.sort_unstable();
+    levels.dedup();
+    let total: u32 = committed.values().sum();
+    let mut previous = 0;
+    let mut expected = 0.0;
+    let mut main_equity = None;
+    for level in levels {
+        let amount: u32 = committed.values().map(|&c| c.min(level) - c.min(previous)).sum();
+        previous = level;
+        let eligible: Vec<usize> = (0..live.len()).filter(|&i| paid(&live[i].player_id) >= level).collect();
+        let Some(hero_at) = eligible.iter().position(|&i| live[i].player_id == hero) else { continue };
+
+        let share = if eligible.len() == 1 {
+            1.0
+        } else {
+            let hands: Vec<Option<[Card; 2]>> = eligible.iter().map(|&i| Some(holes[i])).collect();
+            equity(&hands, &board, TRIALS, rng)[hero_at]
+        };
+        main_equity.get_or_insert(share);
+        expected += amount as f64 * share;
+    }
+
+    // Rake comes out of what the hero would have won, in proportion
+    if total > 0 {
+        expected -= hand.rake as f64 * expected / total as f64;
+    }
+    let actual_net = hand.players.iter().find(|p| p.player_id == hero)?.net;
+    Some(AllInEv {
+        street: last.street.clone(),
+        equity: main_equity.unwrap_or(0.0),
+        actual_net,
+        expected_net: expected - paid(hero) as f64,
+    })
+}
+
+// Hands with no stored EV, or whose stored EV predates the latest copy of the hand
+fn stale_hands(conn: &Connection, limit: u32) -> rusqlite::Result<Vec<HandRecord>> {
+    let mut stmt = conn.prepare(
+        "SELECT h.data FROM hands h LEFT JOIN hand_ev e ON e.hand_id = h.id
+         WHERE e.hand_id IS NULL OR e.hand_updated_at < h.updated_at
+         LIMIT ?1",
+    )?;
+    let rows = stmt.query_map([limit], |row| row.get::<_, String>(0))?;
+
+    rows.map(|row| row.and_then(history::parse_hand)).collect()
+}
+
+fn store(conn: &Connection, hand: &HandRecord, ev: Option<&AllInEv>) -> rusqlite::Result<()> {
+    let actual_net = hand
+        .hero_id
+        .as_deref()
+        .and_then(|hero| hand.players.iter().find(|p| p.player_id == hero))
+        .map(|p| p.net)
+        .unwrap_or(0);
+    conn.execute(
+        "INSERT INTO hand_ev (hand_id, hand_updated_at, all_in, street, equity, actual_net, expected_net)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(hand_id) DO UPDATE SET
+            hand_updated_at = excluded.hand_updated_at,
+            all_in = excluded.all_in,
+            street = excluded.street,
+            equity = excluded.equity,
+            actual_net = excluded.actual_net,
+            expected_net = excluded.expected_net",
+        params![
+            hand.id,
+            to_millis(&hand.updated_at),
+            ev.is_some(),
+            ev.map(|e| e.street.as_str()),
+            ev.map(|e| e.equity),
+            actual_net,
+            ev.map(|e| e.expected_net)
+        ],
+    )?;
+    Ok(())
+}
+
+// Bring `hand_ev` up to date with the hand table. Returns how many hands were processed.
+// Batches already stored are kept when the task is cancelled.
+pub fn refresh(db: &Database, cancel: &Cancel) -> Result<usize, String> {
+    let mut rng = rand::thread_rng();
+    let mut processed = 0;
+    loop {
+        cancel.check()?;
+        let batch = db.with_conn(|conn| stale_hands(conn, BATCH))?;
+        if batch.is_empty() {
+            return Ok(processed);
+        }
+        let evaluated: Vec<(&HandRecord, Option<AllInEv>)> =
+            batch.iter().map(|hand| (hand, all_in_ev(hand, &mut rng))).collect();
+        db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            for (hand, ev) in &evaluated {
+                store(&tx, hand, ev.as_ref())?;
+            }
+            tx.commit()
+        })?;
+        processed += batch.len();
+    }
+}
+
+// Expected net of every stored all-in hand, keyed by hand id
+pub fn expected_nets(conn: &Connection) -> rusqlite::Result<HashMap<String, f64>> {
+    let mut stmt = conn.prepare("SELECT hand_id, expected_net FROM hand_ev WHERE all_in = 1")?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)))?;
+    rows.collect()
+}
+
+// Stored all-in EV for one hand, or None when the hand was not decided all-in
+#[tauri::command]
+pub async fn get_hand_ev(app: AppHandle, db: Db<'_>, hand_id: String) -> Result<Option<AllInEv>, String> {
+    compute::run("All-in EV", Priority::Normal, move |cancel| refresh(&*db::get(&app)?, cancel)).await?;
+    db.with_conn(|conn| {
+        conn.query_row(
+            "SELECT street, equity, actual_net, expected_net FROM hand_ev WHERE hand_id = ?1 AND all_in = 1",
+            [&hand_id],
+            |row| {
+                Ok(AllInEv {
+                    street: row.get(0)?,
+                    equity: row.get(1)?,
+                    actual_net: row.get(2)?,
+                    expected_net: row.get(3)?,
+                })
+            },
+        )
+        .optional()
+    })
+}
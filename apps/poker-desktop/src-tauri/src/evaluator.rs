@@ -0,0 +1,152 @@
+// Hold'em hand evaluator. Works directly on 5 to 7 cards without enumerating
+// five-card subsets; `HandValue` orders hands so the larger value wins.
+
+use crate::cards::Card;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HandCategory {
+    HighCard,
+    Pair,
+    TwoPair,
+    ThreeOfAKind,
+    Straight,
+    Flush,
+    FullHouse,
+    FourOfAKind,
+    StraightFlush,
+}
+
+// Category plus the ranks that break ties within it, most significant first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct HandValue {
+    pub category: HandCategory,
+    pub ranks: [u8; 5],
+}
+
+fn rank_name(rank: u8, plural: bool) -> String {
+    let (one, many) = match rank {
+        14 => ("Ace", "Aces"),
+        13 => ("King", "Kings"),
+        12 => ("Queen", "Queens"),
+        11 => ("Jack", "Jacks"),
+        10 => ("Ten", "Tens"),
+        9 => ("Nine", "Nines"),
+        8 => ("Eight", "Eights"),
+        7 => ("Seven", "Sevens"),
+        6 => ("Six", "Sixes"),
+        5 => ("Five", "Fives"),
+        4 => ("Four", "Fours"),
+        3 => ("Three", "Threes"),
+        _ => ("Two", "Twos"),
+    };
+    if plural { many.to_string() } else { one.to_string() }
+}
+
+impl HandValue {
+    // Human-readable description, e.g. "Two pair, Kings and Sevens"
+    pub fn describe(&self) -> String {
+        let r = self.ranks;
+        match self.category {
+            HandCategory::HighCard => format!("High card {}", rank_name(r[0], false)),
+            HandCategory::Pair => format!("Pair of {}", rank_name(r[0], true)),
+            HandCategory::TwoPair => format!("Two pair, {} and {}", rank_name(r[0], true), rank_name(r[1], true)),
+            HandCategory::ThreeOfAKind => format!("Three of a kind, {}", rank_name(r[0], true)),
+            HandCategory::Straight => format!("Straight, {} high", rank_name(r[0], false)),
+            HandCategory::Flush => format!("Flush, {} high", rank_name(r[0], false)),
+            HandCategory::FullHouse => format!("Full house, {} full of {}", rank_name(r[0], true), rank_name(r[1], true)),
+            HandCategory::FourOfAKind => format!("Four of a kind, {}", rank_name(r[0], true)),
+            HandCategory::StraightFlush if r[0] == 14 => "Royal flush".to_string(),
+            HandCategory::StraightFlush => format!("Straight flush, {} high", rank_name(r[0], false)),
+        }
+    }
+}
+
+// Highest straight in a rank bitmask (bit n set for rank n, ace also at bit 1)
+fn straight_high(mask: u16) -> Option<u8> {
+    (5..=14u8).rev().find(|&high| {
+        let run = 0b11111u16 << (high - 4);
+        mask & run == run
+    })
+}
+
+fn rank_mask(cards: impl Iterator<Item = u8>) -> u16 {
+    cards.fold(0u16, |mask, rank| {
+        let mask = mask | (1 << rank);
+        if rank == 14 { mask | 0b10 } else { mask }
+    })
+}
+
+// The `n` highest ranks present, skipping ranks already used
+fn top_ranks(counts: &[u8; 15], exclude: &[u8], n: usize) -> Vec<u8> {
+    (2..=14u8)
+        .rev()
+        .filter(|r| counts[*r as usize] > 0 && !exclude.contains(r))
+        .take(n)
+        .collect()
+}
+
+fn value(category: HandCategory, ranks: &[u8]) -> HandValue {
+    let mut padded = [0u8; 5];
+    for (slot, rank) in padded.iter_mut().zip(ranks) {
+        *slot = *rank;
+    }
+    HandValue { category, ranks: padded }
+}
+
+// Best hand out of 5 to 7 cards
+pub fn evaluate(cards: &[Card]) -> HandValue {
+    let mut counts = [0u8; 15];
+    let mut suited: [Vec<u8>; 4] = Default::default();
+    for card in cards {
+        counts[card.rank as usize] += 1;
+        suited[card.suit.index()].push(card.rank);
+    }
+
+    if let Some(flush) = suited.iter().find(|ranks| ranks.len() >= 5) {
+        if let Some(high) = straight_high(rank_mask(flush.iter().copied())) {
+            return value(HandCategory::StraightFlush, &[high]);
+        }
+    }
+
+    let ranks_with = |n: u8| -> Vec<u8> { (2..=14u8).rev().filter(|r| counts[*r as usize] == n).collect() };
+    let quads = ranks_with(4);
+    let trips = ranks_with(3);
+    let pairs = ranks_with(2);
+
+    if let Some(&quad) = quads.first() {
+        return value(HandCategory::FourOfAKind, &[&[quad][..], &top_ranks(&counts, &[quad], 1)].concat());
+    }
+
+    // A second set of trips counts as the pair of a full house
+    let full_pair = trips.get(1).into_iter().chain(pairs.first()).max().copied();
+    if let (Some(&trip), Some(pair)) = (trips.first(), full_pair) {
+        return value(HandCategory::FullHouse, &[trip, pair]);
+    }
+
+    if let Some(flush) = suited.iter().find(|ranks| ranks.len() >= 5) {
+        let mut ranks = flush.clone();
+        ranks.sort_unstable_by(|a, b| b.cmp(a));
+        return value(HandCategory::Flush, &ranks[..5]);
+    }
+
+    if let Some(high) = straight_high(rank_mask(cards.iter().map(|c| c.rank))) {
+        return value(HandCategory::Straight, &[high]);
+    }
+
+    if let Some(&trip) = trips.first() {
+        return value(HandCategory::ThreeOfAKind, &[&[trip][..], &top_ranks(&counts, &[trip], 2)].concat());
+    }
+
+    if pairs.len() >= 2 {
+        let (high, low) = (pairs[0], pairs[1]);
+        return value(HandCategory::TwoPair, &[&[high, low][..], &top_ranks(&counts, &[high, low], 1)].concat());
+    }
+
+    if let Some(&pair) = pairs.first() {
+        return value(HandCategory::Pair, &[&[pair][..], &top_ranks(&counts, &[pair], 3)].concat());
+    }
+
+    value(HandCategory::HighCard, &top_ranks(&counts, &[], 5))
+}
@@ -0,0 +1,257 @@
+// On-disk ring buffer of raw table WebSocket frames, for chasing desyncs and wrong
+// pots after the fact. Every table connection writes gzip segments of one minute
+// under `event-buffers/<table>/`, one JSON line per frame in either direction with
+// the frame text exactly as it crossed the wire. Segments older than the retention
+// window, or past the per-table size cap, are deleted as new ones open.
+// `dump_event_buffer` gathers what is left into a single file to attach to a report.
+
+use crate::compute::{self, Priority};
+use chrono::{DateTime, TimeZone, Utc};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+const SEGMENT_LENGTH: Duration = Duration::from_secs(60);
+const RETENTION: Duration = Duration::from_secs(15 * 60);
+const MAX_TABLE_BYTES: u64 = 8 * 1024 * 1024;
+const SEGMENT_EXTENSION: &str = "jsonl.gz";
+
+static DIR: OnceLock<PathBuf> = OnceLock::new();
+static SEGMENTS: Mutex<Option<HashMap<String, Segment>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Direction {
+    #[serde(rename = "in")]
+    Received,
+    #[serde(rename = "out")]
+    Sent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BufferedFrame {
+    // Milliseconds since epoch
+    at: i64,
+    dir: Direction,
+    frame: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventDump {
+    table_id: String,
+    path: String,
+    frames: usize,
+    first_at: Option<DateTime<Utc>>,
+    last_at: Option<DateTime<Utc>>,
+}
+
+struct Segment {
+    opened: Instant,
+    encoder: GzEncoder<File>,
+}
+
+impl Segment {
+    fn open(dir: &Path) -> Result<Self, String> {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create event buffer directory: {}", e))?;
+        let path = dir.join(format!("{}.{}", Utc::now().timestamp_millis(), SEGMENT_EXTENSION));
+        let file = File::create(&path).map_err(|e| format!("Failed to create event segment: {}", e))?;
+        Ok(Self { opened: Instant::now(), encoder: GzEncoder::new(file, Compression::fast()) })
+    }
+
+    fn finish(self) -> Result<(), String> {
+        self.encoder.finish().map(|_| ()).map_err(|e| format!("Failed to close event segment: {}", e))
+    }
+}
+
+// Table ids come from the server; keep them to characters safe in a file name
+fn table_dir(root: &Path, table_id: &str) -> PathBuf {
+    let name: String = table_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    root.join(name)
+}
+
+// Segments of one table, oldest first, with their start time and size
+fn segments_in(dir: &Path) -> Vec<(i64, PathBuf, u64)> {
+    let Ok(entries) = fs::read_dir(dir) else { return Vec::new() };
+    let mut segments: Vec<(i64, PathBuf, u64)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            let name = path.file_name()?.to_str()?;
+            let started = name.strip_suffix(SEGMENT_EXTENSION)?.strip_suffix('.')?.parse().ok()?;
+            let size = entry.metadata().map(|m| m.len()).unwrap_or_default();
+            Some((started, path, size))
+        })
+        .collect();
+    segments.sort_by_key(|(started, _, _)| *started);
+    segments
+}
+
+// `writing` keeps the newest segment, which is still open
+fn prune(dir: &Path, writing: bool) {
+    let cutoff = Utc::now().timestamp_millis() - RETENTION.as_millis() as i64;
+    let mut segments = segments_in(dir);
+    let mut total: u64 = segments.iter().map(|(_, _, size)| size).sum();
+    if writing {
+        segments.pop();
+    }
+    for (started, path, size) in segments {
+        if started >= cutoff && total <= MAX_TABLE_BYTES {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total = total.saturating_sub(size);
+        }
+    }
+}
+
+// Set where buffers live and drop what has aged out since the last run
+pub fn init(data_dir: &Path) {
+    let root = DIR.get_or_init(|| data_dir.join("event-buffers"));
+    if let Ok(entries) = fs::read_dir(root) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            prune(&entry.path(), false);
+            let _ = fs::remove_dir(entry.path());
+        }
+    }
+}
+
+// The table a game socket URL belongs to; side channels such as voice are skipped
+pub fn table_of(url: &str) -> Option<String> {
+    let (_, query) = url.split_once('?')?;
+    let params: Vec<(&str, &str)> = query.split('&').filter_map(|p| p.split_once('=')).collect();
+    if params.iter().any(|(key, _)| *key == "channel") {
+        return None;
+    }
+    params.iter().find(|(key, _)| *key == "tableId").map(|(_, id)| id.to_string())
+}
+
+fn write(table_id: &str, dir: Direction, frame: &str) -> Result<(), String> {
+    let Some(root) = DIR.get() else { return Ok(()) };
+    let mut guard = SEGMENTS.lock().map_err(|_| "Event buffer lock poisoned".to_string())?;
+    let segments = guard.get_or_insert_with(HashMap::new);
+
+    if !matches!(segments.get(table_id), Some(segment) if segment.opened.elapsed() < SEGMENT_LENGTH) {
+        // Close this table's segment and any left by tables that went quiet
+        let stale: Vec<String> = segments
+            .iter()
+            .filter(|(id, s)| id.as_str() == table_id || s.opened.elapsed() >= SEGMENT_LENGTH)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in stale {
+            if let Some(segment) = segments.remove(&id) {
+                segment.finish()?;
+            }
+        }
+        let dir = table_dir(root, table_id);
+        segments.insert(table_id.to_string(), Segment::open(&dir)?);
+        prune(&dir, true);
+    }
+
+    let Some(segment) = segments.get_mut(table_id) else { return Ok(()) };
+    let line = serde_json::to_string(&BufferedFrame { at: Utc::now().timestamp_millis(), dir, frame: frame.to_string() })
+        .map_err(|e| e.to_string())?;
+    writeln!(segment.encoder, "{}", line).map_err(|e| format!("Failed to write event segment: {}", e))
+}
+
+pub fn record(table_id: &str, dir: Direction, frame: &str) {
+    if let Err(e) = write(table_id, dir, frame) {
+        eprintln!("Event buffer for table {}: {}", table_id, e);
+    }
+}
+
+// Finish every open segment so nothing written is lost when the app exits
+pub fn close_all() {
+    let Ok(mut guard) = SEGMENTS.lock() else { return };
+    for (table_id, segment) in guard.take().unwrap_or_default() {
+        if let Err(e) = segment.finish() {
+            eprintln!("Event buffer for table {}: {}", table_id, e);
+        }
+    }
+}
+
+// Frames of one segment. A segment cut short by a crash is read up to where it ends.
+fn read_segment(path: &Path) -> Vec<BufferedFrame> {
+    let Ok(file) = File::open(path) else { return Vec::new() };
+    BufReader::new(MultiGzDecoder::new(file))
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect()
+}
+
+// Every buffered frame of a table, oldest first. The open segment is closed so its
+// frames are on disk; the next frame starts a new one.
+fn buffered(table_id: &str) -> Result<Vec<BufferedFrame>, String> {
+    let root = DIR.get().ok_or_else(|| "Event buffers are not ready yet".to_string())?;
+    if let Some(segment) = SEGMENTS.lock().ok().and_then(|mut s| s.as_mut()?.remove(table_id)) {
+        segment.finish()?;
+    }
+    Ok(segments_in(&table_dir(root, table_id))
+        .iter()
+        .flat_map(|(_, path, _)| read_segment(path))
+        .collect())
+}
+
+// The last `limit` frames of a table as JSON, each with the frame parsed when it is
+// JSON and kept as text when it is not
+pub fn recent_frames(table_id: &str, limit: usize) -> Result<Vec<serde_json::Value>, String> {
+    let frames = buffered(table_id)?;
+    let skip = frames.len().saturating_sub(limit);
+    Ok(frames
+        .into_iter()
+        .skip(skip)
+        .map(|f| {
+            let frame = serde_json::from_str(&f.frame).unwrap_or(serde_json::Value::String(f.frame));
+            serde_json::json!({ "at": f.at, "dir": f.dir, "frame": frame })
+        })
+        .collect())
+}
+
+// A new file for a report about `table_id`, beside the event dumps
+pub fn report_path(table_id: &str, extension: &str) -> Result<PathBuf, String> {
+    let root = DIR.get().ok_or_else(|| "Event buffers are not ready yet".to_string())?;
+    let dumps = root.with_file_name("event-dumps");
+    fs::create_dir_all(&dumps).map_err(|e| format!("Failed to create dump directory: {}", e))?;
+    Ok(table_dir(&dumps, &format!("{}-{}", table_id, Utc::now().format("%Y%m%dT%H%M%S"))).with_extension(extension))
+}
+
+fn dump(table_id: String) -> Result<EventDump, String> {
+    let frames = buffered(&table_id)?;
+    if frames.is_empty() {
+        return Err(format!("No buffered events for table {}", table_id));
+    }
+
+    let path = report_path(&table_id, SEGMENT_EXTENSION)?;
+    let file = File::create(&path).map_err(|e| format!("Failed to create event dump: {}", e))?;
+    let mut encoder = GzEncoder::new(file, Compression::default());
+    for frame in &frames {
+        let line = serde_json::to_string(frame).map_err(|e| e.to_string())?;
+        writeln!(encoder, "{}", line).map_err(|e| format!("Failed to write event dump: {}", e))?;
+    }
+    encoder.finish().map_err(|e| format!("Failed to write event dump: {}", e))?;
+
+    let at = |frame: Option<&BufferedFrame>| frame.and_then(|f| Utc.timestamp_millis_opt(f.at).single());
+    Ok(EventDump {
+        table_id,
+        path: path.to_string_lossy().into_owned(),
+        frames: frames.len(),
+        first_at: at(frames.first()),
+        last_at: at(frames.last()),
+    })
+}
+
+// Write the buffered frames of a table to one gzip file of JSON lines, oldest first
+#[tauri::command]
+pub async fn dump_event_buffer(table_id: String) -> Result<EventDump, String> {
+    compute::run("Event buffer dump", Priority::Normal, move |_| dump(table_id)).await
+}
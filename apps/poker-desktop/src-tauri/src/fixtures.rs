@@ -0,0 +1,187 @@
+// Record-and-replay of backend traffic for regression tests. Compiled only with the
+// `http-fixtures` feature and enabled at runtime with PRIMO_HTTP_FIXTURES=record|replay;
+// fixture files live in PRIMO_FIXTURE_DIR (default `fixtures/http`).
+
+use crate::error::CommandError;
+use reqwest::{Client, Request, Response};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+const REDACTED: &str = "[REDACTED]";
+const SECRET_FIELDS: &[&str] = &["password", "accessToken", "refreshToken", "token", "pin"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Record,
+    Replay,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Fixture {
+    method: String,
+    url: String,
+    request_body: Option<Value>,
+    status: u16,
+    content_type: Option<String>,
+    body: Value,
+}
+
+// Per-key call counters so repeated identical requests map to successive fixtures
+static SEQUENCE: OnceLock<Mutex<HashMap<String, usize>>> = OnceLock::new();
+
+pub fn mode() -> Option<Mode> {
+    match std::env::var("PRIMO_HTTP_FIXTURES").ok()?.as_str() {
+        "record" => Some(Mode::Record),
+        "replay" => Some(Mode::Replay),
+        _ => None,
+    }
+}
+
+fn fixture_dir() -> PathBuf {
+    std::env::var("PRIMO_FIXTURE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("fixtures/http"))
+}
+
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if SECRET_FIELDS.contains(&key.as_str()) {
+                    *field = Value::String(REDACTED.to_string());
+                } else {
+                    redact(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+fn redacted_url(request: &Request) -> String {
+    let mut url = request.url().clone();
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(k, v)| {
+            let v = if SECRET_FIELDS.contains(&k.as_ref()) { REDACTED.to_string() } else { v.into_owned() };
+            (k.into_owned(), v)
+        })
+        .collect();
+    if pairs.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    // Host is dropped so fixtures recorded against staging replay against any backend
+    format!("{}{}", url.path(), url.query().map(|q| format!("?{}", q)).unwrap_or_default())
+}
+
+fn redacted_body(request: &Request) -> Option<Value> {
+    let bytes = request.body()?.as_bytes()?;
+    let mut value = serde_json::from_slice(bytes).ok()?;
+    redact(&mut value);
+    Some(value)
+}
+
+// Stable file name for the n-th occurrence of a (method, url, body) triple
+fn fixture_path(mode: Mode, method: &str, url: &str, body: &Option<Value>) -> PathBuf {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_bytes());
+    hasher.update(url.as_bytes());
+    if let Some(body) = body {
+        hasher.update(body.to_string().as_bytes());
+    }
+    let key: String = hasher.finalize().iter().take(8).map(|b| format!("{:02x}", b)).collect();
+
+    let sequence = SEQUENCE.get_or_init(|| Mutex::new(HashMap::new()));
+    let index = match sequence.lock() {
+        Ok(mut counters) => {
+            let counter = counters.entry(key.clone()).or_insert(0);
+            *counter += 1;
+            *counter
+        }
+        Err(_) => 1,
+    };
+
+    let dir = fixture_dir();
+    let path = dir.join(format!("{}-{}.json", key, index));
+    // In replay, fall back to the first recording when a request repeats more often than recorded
+    if mode == Mode::Replay && index > 1 && !path.exists() {
+        return dir.join(format!("{}-1.json", key));
+    }
+    path
+}
+
+fn into_response(fixture: &Fixture) -> Result<Response, CommandError> {
+    let mut builder = http::Response::builder().status(fixture.status);
+    if let Some(content_type) = &fixture.content_type {
+        builder = builder.header("content-type", content_type);
+    }
+    let body = match &fixture.body {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    builder
+        .body(body)
+        .map(Response::from)
+        .map_err(|e| CommandError::Other { message: format!("Invalid fixture: {}", e) })
+}
+
+pub async fn send(mode: Mode, client: Client, request: Request) -> Result<Response, CommandError> {
+    let method = request.method().to_string();
+    let url = redacted_url(&request);
+    let request_body = redacted_body(&request);
+    let path = fixture_path(mode, &method, &url, &request_body);
+
+    if mode == Mode::Replay {
+        let data = std::fs::read_to_string(&path).map_err(|_| CommandError::Other {
+            message: format!("No fixture recorded for {} {} ({})", method, url, path.display()),
+        })?;
+        let fixture: Fixture = serde_json::from_str(&data)
+            .map_err(|e| CommandError::Other { message: format!("Invalid fixture {}: {}", path.display(), e) })?;
+        return into_response(&fixture);
+    }
+
+    let response = client.execute(request).await
+        .map_err(|e| CommandError::Network { message: e.to_string() })?;
+    let status = response.status().as_u16();
+    let content_type = response.headers().get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+    let bytes = response.bytes().await
+        .map_err(|e| CommandError::Network { message: e.to_string() })?;
+
+    let mut body = serde_json::from_slice(&bytes)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned()));
+    redact(&mut body);
+
+    let fixture = Fixture { method, url, request_body, status, content_type, body };
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    match serde_json::to_string_pretty(&fixture) {
+        Ok(data) => {
+            if let Err(e) = std::fs::write(&path, data) {
+                eprintln!("Failed to write fixture {}: {}", path.display(), e);
+            }
+        }
+        Err(e) => eprintln!("Failed to serialize fixture: {}", e),
+    }
+
+    // Hand back the original bytes so the command sees the real (unredacted) payload
+    let mut builder = http::Response::builder().status(status);
+    if let Some(content_type) = &fixture.content_type {
+        builder = builder.header("content-type", content_type);
+    }
+    builder
+        .body(bytes)
+        .map(Response::from)
+        .map_err(|e| CommandError::Other { message: format!("Invalid response: {}", e) })
+}
@@ -0,0 +1,204 @@
+// Optional GraphQL client for screens that would otherwise make a round trip per
+// endpoint. Only used when the backend advertises the `graphql` capability. Queries
+// are built with `Selection`, sent as persisted queries (the SHA-256 hash first, the
+// full text only when the backend has not seen it yet), and `graphql_query` is left
+// as an escape hatch for one-off queries from the frontend.
+
+use crate::version;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+
+const PERSISTED_QUERY_NOT_FOUND: &str = "PersistedQueryNotFound";
+
+// One field in a selection set, with optional arguments and sub-fields
+pub struct Selection {
+    name: String,
+    args: Vec<(String, String)>,
+    fields: Vec<Selection>,
+}
+
+impl Selection {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string(), args: Vec::new(), fields: Vec::new() }
+    }
+
+    // `value` is GraphQL source text: a literal such as `10` or a variable such as `$id`
+    pub fn arg(mut self, name: &str, value: &str) -> Self {
+        self.args.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn fields(mut self, names: &[&str]) -> Self {
+        self.fields.extend(names.iter().map(|name| Selection::new(name)));
+        self
+    }
+
+    pub fn select(mut self, field: Selection) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    fn render(&self, out: &mut String) {
+        out.push_str(&self.name);
+        if !self.args.is_empty() {
+            let args: Vec<String> = self.args.iter().map(|(name, value)| format!("{}: {}", name, value)).collect();
+            out.push_str(&format!("({})", args.join(", ")));
+        }
+        if !self.fields.is_empty() {
+            out.push_str(" { ");
+            for (index, field) in self.fields.iter().enumerate() {
+                if index > 0 {
+                    out.push(' ');
+                }
+                field.render(out);
+            }
+            out.push_str(" }");
+        }
+    }
+}
+
+// `query Name($var: Type) { ... }`
+pub fn query(name: &str, variables: &[(&str, &str)], selections: Vec<Selection>) -> String {
+    let mut out = format!("query {}", name);
+    if !variables.is_empty() {
+        let definitions: Vec<String> = variables.iter().map(|(var, kind)| format!("${}: {}", var, kind)).collect();
+        out.push_str(&format!("({})", definitions.join(", ")));
+    }
+    let root = Selection { name: String::new(), args: Vec::new(), fields: selections };
+    root.render(&mut out);
+    out
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphqlError {
+    message: String,
+    #[serde(default)]
+    path: Option<Vec<Value>>,
+    #[serde(default)]
+    extensions: Option<Value>,
+}
+
+impl GraphqlError {
+    fn code(&self) -> Option<&str> {
+        self.extensions.as_ref()?.get("code")?.as_str()
+    }
+}
+
+// Partial data comes back alongside the errors for the fields that failed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphqlResponse {
+    #[serde(default)]
+    data: Option<Value>,
+    #[serde(default)]
+    errors: Vec<GraphqlError>,
+}
+
+fn sha256_hex(text: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(text.as_bytes());
+    hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+async fn post(api_url: &str, body: &Value) -> Result<GraphqlResponse, String> {
+    if !version::supports(version::GRAPHQL) {
+        return Err("This backend does not offer GraphQL".to_string());
+    }
+    let client = crate::create_http_client()?;
+    let mut request = client.post(format!("{}/graphql", api_url)).json(body);
+    if let Ok(token) = crate::get_token_from_keyring() {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+
+    let response = crate::http::send(request).await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() && response.status() != reqwest::StatusCode::BAD_REQUEST {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("GraphQL request failed: {}", error_text));
+    }
+    response.json().await.map_err(|e| format!("Failed to parse response: {}", e))
+}
+
+// Run `query` as a persisted query, registering its text on a cache miss
+pub async fn execute(api_url: &str, query: &str, variables: Value, operation_name: Option<&str>) -> Result<GraphqlResponse, String> {
+    let mut body = Map::new();
+    body.insert("variables".to_string(), variables);
+    if let Some(name) = operation_name {
+        body.insert("operationName".to_string(), json!(name));
+    }
+    body.insert("extensions".to_string(), json!({
+        "persistedQuery": { "version": 1, "sha256Hash": sha256_hex(query) }
+    }));
+
+    let mut body = Value::Object(body);
+    let response = post(api_url, &body).await?;
+    let missing = response.errors.iter().any(|e| e.code() == Some(PERSISTED_QUERY_NOT_FOUND) || e.message == PERSISTED_QUERY_NOT_FOUND);
+    if !missing {
+        return Ok(response);
+    }
+    body["query"] = json!(query);
+    post(api_url, &body).await
+}
+
+// Data from a response, failing only when nothing came back
+fn into_data(response: GraphqlResponse) -> Result<Value, String> {
+    for error in &response.errors {
+        eprintln!("GraphQL error: {}", error.message);
+    }
+    match response.data {
+        Some(data) if !data.is_null() => Ok(data),
+        _ => Err(response.errors.iter().map(|e| e.message.as_str()).collect::<Vec<_>>().join("; ")),
+    }
+}
+
+fn dashboard_query() -> &'static str {
+    static QUERY: OnceLock<String> = OnceLock::new();
+    QUERY.get_or_init(|| {
+        query("Dashboard", &[("tableLimit", "Int")], vec![
+            Selection::new("me")
+                .fields(&["id", "username", "email", "name"])
+                .select(Selection::new("wallet").fields(&["balance", "currency"]))
+                .select(Selection::new("activeTables").fields(&["id", "name", "seat", "chips"]))
+                .select(Selection::new("friends").fields(&["id", "username", "online"])),
+            Selection::new("tables")
+                .arg("first", "$tableLimit")
+                .fields(&["id", "name", "playerCount", "maxPlayers", "gamePhase", "pot"]),
+        ])
+    })
+}
+
+fn presence_query() -> &'static str {
+    static QUERY: OnceLock<String> = OnceLock::new();
+    QUERY.get_or_init(|| {
+        query("Presence", &[], vec![
+            Selection::new("me").select(Selection::new("friends").fields(&["id", "username", "online"])),
+        ])
+    })
+}
+
+// The player's friends and whether each is online
+pub async fn friends_presence(api_url: &str) -> Result<Value, String> {
+    let data = into_data(execute(api_url, presence_query(), json!({}), Some("Presence")).await?)?;
+    Ok(data["me"]["friends"].clone())
+}
+
+// Profile, balance, active tables, friends and the top of the lobby in one request
+#[tauri::command]
+pub async fn get_dashboard(api_url: String, table_limit: Option<u32>) -> Result<Value, String> {
+    let variables = json!({ "tableLimit": table_limit.unwrap_or(20) });
+    into_data(execute(&api_url, dashboard_query(), variables, Some("Dashboard")).await?)
+}
+
+// Escape hatch for queries the client has no command for. Errors for individual
+// fields are returned alongside whatever data resolved.
+#[tauri::command]
+pub async fn graphql_query(
+    api_url: String,
+    query: String,
+    variables: Option<Value>,
+    operation_name: Option<String>,
+) -> Result<GraphqlResponse, String> {
+    execute(&api_url, &query, variables.unwrap_or_else(|| json!({})), operation_name.as_deref()).await
+}
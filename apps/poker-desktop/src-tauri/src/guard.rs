@@ -0,0 +1,75 @@
+// What a command asks of the signed-in player, checked in one place. A guarded
+// command names a `Policy` and calls `require` before anything is sent, or calls
+// `require_host` with the recorded host of the table it acts on; the claims of the
+// stored token decide, and a refusal is `Forbidden` or `KycRequired` so the
+// frontend can tell a missing role from a verification step it can offer. The claims
+// are not verified here, so this only spares a round trip: the backend enforces
+// every permission with the signed token.
+
+use crate::claims::{self, Claims};
+use crate::error::CommandError;
+
+// Geo flags set by the backend
+const GEO_BLOCKED: &str = "blocked";
+const GEO_NO_REAL_MONEY: &str = "real_money_blocked";
+
+pub struct Policy {
+    // Any one of these, when not empty
+    roles: &'static [&'static str],
+    kyc_level: u8,
+    // Geo flags that rule the action out
+    blocked_by: &'static [&'static str],
+}
+
+pub const CREATE_TABLE: Policy = Policy { roles: &[], kyc_level: 0, blocked_by: &[GEO_BLOCKED] };
+
+pub const WITHDRAW: Policy = Policy { roles: &[], kyc_level: 2, blocked_by: &[GEO_BLOCKED, GEO_NO_REAL_MONEY] };
+
+pub const TOURNAMENT_ADMIN: Policy = Policy { roles: &["admin", "tournament_director"], kyc_level: 0, blocked_by: &[] };
+
+// Host actions need no role; who hosts the table decides (see `require_host`)
+const TABLE_HOST: Policy = Policy { roles: &[], kyc_level: 0, blocked_by: &[] };
+
+fn check(claims: &Claims, action: &str, policy: &Policy) -> Result<(), CommandError> {
+    let forbidden = |reason: &str| CommandError::Forbidden { action: action.to_string(), reason: reason.to_string() };
+    if claims.is_expired() {
+        return Err(forbidden("Session expired, sign in again"));
+    }
+    if !policy.roles.is_empty() && !policy.roles.iter().any(|role| claims.has_role(role)) {
+        return Err(forbidden(&format!("Requires the {} role", policy.roles.join(" or "))));
+    }
+    if policy.blocked_by.iter().any(|flag| claims.has_geo_flag(flag)) {
+        return Err(forbidden("Not available from your location"));
+    }
+    if claims.kyc_level < policy.kyc_level {
+        return Err(CommandError::KycRequired {
+            action: action.to_string(),
+            required_level: policy.kyc_level,
+            current_level: claims.kyc_level,
+        });
+    }
+    Ok(())
+}
+
+// The signed-in player's claims when they allow `action` under `policy`
+pub fn require(action: &str, policy: &Policy) -> Result<Claims, CommandError> {
+    let claims = claims::current()
+        .map_err(|e| CommandError::Forbidden { action: action.to_string(), reason: e })?;
+    check(&claims, action, policy)?;
+    Ok(claims)
+}
+
+// The signed-in player's claims when they are the table's host, `host_id` as recorded
+// when it was created, or an admin
+pub fn require_host(action: &str, host_id: Option<&str>) -> Result<Claims, CommandError> {
+    let claims = require(action, &TABLE_HOST)?;
+    let forbidden = |reason: &str| CommandError::Forbidden { action: action.to_string(), reason: reason.to_string() };
+    if claims.has_role("admin") {
+        return Ok(claims);
+    }
+    match host_id {
+        Some(host_id) if host_id == claims.user_id => Ok(claims),
+        Some(_) => Err(forbidden("Another player hosts this table")),
+        None => Err(forbidden("Only the table host can do this")),
+    }
+}
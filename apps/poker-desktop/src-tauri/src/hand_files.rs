@@ -0,0 +1,286 @@
+// Hand history files. `.pph` documents hold one or more hands as exported by
+// `export_hand_file`; a bare hand record or a list of them, as other tools write, is
+// read as well. The app registers itself as the handler for the extension for the
+// current user (the registry on Windows, an xdg MIME type and desktop entry on Linux),
+// so double-clicking a shared file starts the client with its path, or hands the path
+// to the client already running (see instance.rs). macOS passes opened files as an
+// Apple Event rather than an argument, which tauri 1 does not deliver, so there the
+// association is not registered. `open_file` reads the file, stores the hands it
+// does not already have and hands the first to the replayer through
+// `open_hand_replay`; a file given at startup is kept until the frontend takes it
+// with `take_pending_hand_file`, as the window may not be listening yet.
+
+use crate::claims;
+use crate::db::{self, Database, Db};
+use crate::history::{self, HandRecord};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+pub const EXTENSION: &str = "pph";
+const EXPORT_FORMAT: &str = "primo-poker-hands";
+const EXPORT_VERSION: u32 = 1;
+const MAX_FILE_BYTES: u64 = 16 * 1024 * 1024;
+const MAX_EXPORT_HANDS: usize = 1000;
+// Executable the association was last registered for
+const KEY_REGISTERED: &str = "files.association_registered";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HandExport {
+    format: String,
+    version: u32,
+    exported_at: DateTime<Utc>,
+    hands: Vec<HandRecord>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenedHandFile {
+    path: String,
+    hands: Vec<HandRecord>,
+    // Hands not stored before
+    imported: usize,
+}
+
+#[derive(Default)]
+pub struct HandFileState {
+    pending: Mutex<Option<OpenedHandFile>>,
+}
+
+// The hand file the app was started with: `--open-file=<path>`, or a bare path with
+// the extension as the OS passes it
+pub fn file_arg() -> Option<PathBuf> {
+    std::env::args().skip(1).find_map(|arg| match arg.strip_prefix("--open-file=") {
+        Some(path) => Some(PathBuf::from(path)),
+        None => {
+            let path = PathBuf::from(&arg);
+            let matches = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case(EXTENSION));
+            (!arg.starts_with("--") && matches).then_some(path)
+        }
+    })
+}
+
+fn parse(data: &str) -> Result<Vec<HandRecord>, String> {
+    let value: Value = serde_json::from_str(data).map_err(|e| format!("Not a hand history file: {}", e))?;
+    let hands = match value {
+        Value::Object(ref fields) if fields.contains_key("format") => {
+            let export: HandExport = serde_json::from_value(value).map_err(|e| format!("Invalid hand file: {}", e))?;
+            if export.format != EXPORT_FORMAT || export.version > EXPORT_VERSION {
+                return Err(format!("Unsupported hand file {} version {}", export.format, export.version));
+            }
+            export.hands
+        }
+        Value::Array(_) => serde_json::from_value(value).map_err(|e| format!("Invalid hand file: {}", e))?,
+        _ => vec![serde_json::from_value(value).map_err(|e| format!("Invalid hand file: {}", e))?],
+    };
+    if hands.is_empty() {
+        return Err("The file holds no hands".to_string());
+    }
+    Ok(hands)
+}
+
+// Read a hand file and store the hands not stored yet; a hand already stored is never
+// replaced from a file, and is returned as stored. As with shared hand links, hands
+// the player was not in are stored without a hero so they stay out of their own stats.
+fn open(db: &Database, path: &Path) -> Result<OpenedHandFile, String> {
+    let size = std::fs::metadata(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?.len();
+    if size > MAX_FILE_BYTES {
+        return Err(format!("{} is larger than {} MB", path.display(), MAX_FILE_BYTES / (1024 * 1024)));
+    }
+    let data = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut hands = parse(&data)?;
+
+    let viewer = claims::current().ok().map(|c| c.user_id);
+    for hand in &mut hands {
+        if !hand.players.iter().any(|p| Some(&p.player_id) == viewer.as_ref()) {
+            hand.hero_id = None;
+        }
+    }
+    let imported = db.with_conn(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        let mut imported = 0;
+        for hand in &mut hands {
+            match history::get_hand_by_id(&tx, &hand.id)? {
+                Some(stored) => *hand = stored,
+                None => {
+                    history::upsert_hand(&tx, hand)?;
+                    imported += 1;
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(imported)
+    })?;
+    Ok(OpenedHandFile { path: path.display().to_string(), hands, imported })
+}
+
+// Open a file the OS handed over and show its first hand. A failure goes to the
+// frontend as `hand_file_error`, since nothing else is waiting on it.
+pub fn open_and_show(app: &AppHandle, path: &Path) -> Option<OpenedHandFile> {
+    match db::get(app).and_then(|db| open(&db, path)) {
+        Ok(opened) => {
+            if let Some(hand) = opened.hands.first() {
+                let _ = app.emit_all("open_hand_replay", json!({ "handId": hand.id }));
+            }
+            Some(opened)
+        }
+        Err(e) => {
+            eprintln!("Failed to open hand file: {}", e);
+            let _ = app.emit_all("hand_file_error", json!({ "path": path.display().to_string(), "error": e }));
+            None
+        }
+    }
+}
+
+// Open the file given on the command line, if any, once the database is ready
+pub fn open_from_args(app: &AppHandle) {
+    let Some(path) = file_arg() else { return };
+    let Some(opened) = open_and_show(app, &path) else { return };
+    if let Ok(mut pending) = app.state::<HandFileState>().pending.lock() {
+        *pending = Some(opened);
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn register_os(exe: &Path) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    const PROG_ID: &str = "PrimoPoker.HandHistory";
+
+    let exe = exe.display().to_string();
+    let classes = r"HKCU\Software\Classes";
+    let entries = [
+        (format!(r"{}\.{}", classes, EXTENSION), PROG_ID.to_string()),
+        (format!(r"{}\{}", classes, PROG_ID), "Primo Poker hand history".to_string()),
+        (format!(r"{}\{}\DefaultIcon", classes, PROG_ID), format!("\"{}\",0", exe)),
+        (format!(r"{}\{}\shell\open\command", classes, PROG_ID), format!("\"{}\" \"%1\"", exe)),
+    ];
+    for (key, value) in entries {
+        let status = std::process::Command::new("reg")
+            .args(["add", &key, "/ve", "/d", &value, "/f"])
+            .creation_flags(CREATE_NO_WINDOW)
+            .status()
+            .map_err(|e| format!("Failed to run reg: {}", e))?;
+        if !status.success() {
+            return Err(format!("Failed to write {}", key));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn register_os(exe: &Path) -> Result<(), String> {
+    use std::process::Command;
+    const MIME_TYPE: &str = "application/x-primo-poker-hands";
+    const DESKTOP_FILE: &str = "primo-poker-hands.desktop";
+
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .ok_or_else(|| "No home directory".to_string())?;
+    let write = |path: PathBuf, contents: String| -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        std::fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    };
+
+    write(
+        data_home.join("mime/packages/primo-poker-hands.xml"),
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <mime-info xmlns=\"http://www.freedesktop.org/standards/shared-mime-info\">\n\
+             <mime-type type=\"{}\">\n\
+             <comment>Primo Poker hand history</comment>\n\
+             <glob pattern=\"*.{}\"/>\n\
+             </mime-type>\n\
+             </mime-info>\n",
+            MIME_TYPE, EXTENSION
+        ),
+    )?;
+    write(
+        data_home.join("applications").join(DESKTOP_FILE),
+        format!(
+            "[Desktop Entry]\nType=Application\nName=Primo Poker\nExec=\"{}\" %f\nMimeType={};\nNoDisplay=true\nTerminal=false\n",
+            exe.display(),
+            MIME_TYPE
+        ),
+    )?;
+    // Refreshing the caches is best effort; desktops pick the files up regardless
+    let _ = Command::new("update-mime-database").arg(data_home.join("mime")).status();
+    let _ = Command::new("update-desktop-database").arg(data_home.join("applications")).status();
+    let _ = Command::new("xdg-mime").args(["default", DESKTOP_FILE, MIME_TYPE]).status();
+    Ok(())
+}
+
+// Not registered; see the note at the top
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn register_os(_exe: &Path) -> Result<(), String> {
+    Ok(())
+}
+
+// Make this executable the handler for hand files, once per install location.
+// Development builds leave the association alone.
+pub fn register(db: &Database) -> Result<(), String> {
+    if cfg!(debug_assertions) {
+        return Ok(());
+    }
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to locate the executable: {}", e))?;
+    let exe_path = exe.display().to_string();
+    if db.get_value(KEY_REGISTERED)?.as_deref() == Some(exe_path.as_str()) {
+        return Ok(());
+    }
+    register_os(&exe)?;
+    db.set_value(KEY_REGISTERED, &exe_path)
+}
+
+// Open a hand file, as from a file dialog or the OS, and show its first hand
+#[tauri::command]
+pub async fn open_file(app: AppHandle, db: Db<'_>, path: String) -> Result<OpenedHandFile, String> {
+    let opened = open(&db, Path::new(&path))?;
+    if let Some(hand) = opened.hands.first() {
+        let _ = app.emit_all("open_hand_replay", json!({ "handId": hand.id }));
+    }
+    Ok(opened)
+}
+
+// The file the app was started with, for a frontend that loads after it was opened
+#[tauri::command]
+pub async fn take_pending_hand_file(state: State<'_, HandFileState>) -> Result<Option<OpenedHandFile>, String> {
+    let mut pending = state.pending.lock().map_err(|_| "Hand file lock poisoned".to_string())?;
+    Ok(pending.take())
+}
+
+// Write the stored hands named to `path` as a hand file to share
+#[tauri::command]
+pub async fn export_hand_file(db: Db<'_>, hand_ids: Vec<String>, path: String) -> Result<usize, String> {
+    if hand_ids.is_empty() || hand_ids.len() > MAX_EXPORT_HANDS {
+        return Err(format!("Export 1 to {} hands at a time", MAX_EXPORT_HANDS));
+    }
+    let hands = db.with_conn(|conn| {
+        hand_ids
+            .iter()
+            .filter_map(|id| history::get_hand_by_id(conn, id).transpose())
+            .collect::<rusqlite::Result<Vec<HandRecord>>>()
+    })?;
+    if hands.is_empty() {
+        return Err("None of those hands are stored".to_string());
+    }
+    let export = HandExport {
+        format: EXPORT_FORMAT.to_string(),
+        version: EXPORT_VERSION,
+        exported_at: Utc::now(),
+        hands,
+    };
+    let mut path = PathBuf::from(path);
+    if path.extension().is_none() {
+        path.set_extension(EXTENSION);
+    }
+    let data = serde_json::to_string_pretty(&export).map_err(|e| e.to_string())?;
+    std::fs::write(&path, data).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(export.hands.len())
+}
@@ -0,0 +1,292 @@
+// Headless bot mode for load and integration testing. Started with
+// `--headless <scenario.json>`; no webview is created. Each bot logs in, joins its
+// table over HTTP, connects the game socket and plays random-legal or scripted
+// actions until it has played its hands or the scenario runs out of time. On
+// fast-fold tables a bot follows its `pool_reassigned` moves to the next table. A
+// JSON report is printed to stdout and the exit code is non-zero if any bot failed.
+
+use crate::profile::BackendProfile;
+use crate::speed::PoolReassignment;
+use crate::table_state::{LegalAction, TableMirror};
+use crate::ws::{self, WsMessage};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::{Duration, Instant};
+
+fn default_duration_secs() -> u64 {
+    300
+}
+
+fn default_buy_in() -> u32 {
+    1000
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Scenario {
+    // Backend base URL, or "mock" for the embedded mock backend; defaults to production
+    api_url: Option<String>,
+    #[serde(default = "default_duration_secs")]
+    duration_secs: u64,
+    bots: Vec<BotConfig>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Strategy {
+    #[default]
+    Random,
+    Scripted,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BotConfig {
+    email: String,
+    password: String,
+    table_id: String,
+    #[serde(default = "default_buy_in")]
+    buy_in: u32,
+    #[serde(default)]
+    strategy: Strategy,
+    // Scripted actions, cycled: "fold", "check", "call", "bet:40", "raise:100", "all_in"
+    #[serde(default)]
+    actions: Vec<String>,
+    // Stop after this many hands; otherwise play until the scenario times out
+    hands: Option<u32>,
+    // Delay before each action, to mimic a human
+    #[serde(default)]
+    think_ms: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BotReport {
+    email: String,
+    table_id: String,
+    player_id: Option<String>,
+    hands_completed: u32,
+    actions_sent: u32,
+    messages_received: u32,
+    server_errors: u32,
+    // Fast-fold moves to another table of the pool
+    reassignments: u32,
+    error: Option<String>,
+    elapsed_ms: u64,
+}
+
+// Path of the scenario file when started as `--headless <file>` or `--headless=<file>`
+pub fn scenario_arg() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--headless" {
+            return args.next();
+        }
+        if let Some(path) = arg.strip_prefix("--headless=") {
+            return Some(path.to_string());
+        }
+    }
+    None
+}
+
+// Run the scenario to completion and return the process exit code
+pub fn run_from_file(path: &str) -> i32 {
+    let scenario: Scenario = match std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|data| serde_json::from_str(&data).map_err(|e| e.to_string()))
+    {
+        Ok(scenario) => scenario,
+        Err(e) => {
+            eprintln!("Failed to load scenario {}: {}", path, e);
+            return 2;
+        }
+    };
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Failed to start runtime: {}", e);
+            return 2;
+        }
+    };
+    let reports = runtime.block_on(run(scenario));
+
+    match serde_json::to_string_pretty(&reports) {
+        Ok(report) => println!("{}", report),
+        Err(e) => eprintln!("Failed to serialize report: {}", e),
+    }
+    if reports.iter().any(|r| r.error.is_some()) { 1 } else { 0 }
+}
+
+async fn run(scenario: Scenario) -> Vec<BotReport> {
+    // Every bot is a separate user, so the per-user client limiter does not apply
+    crate::ratelimit::disable();
+
+    let profile = match scenario.api_url.as_deref() {
+        // Self-contained run against the embedded mock backend, for CI
+        #[cfg(feature = "mock-backend")]
+        Some("mock") => match crate::mock_backend::start(0).await {
+            Ok(url) => BackendProfile::for_url("mock", &url),
+            Err(e) => {
+                return vec![BotReport { error: Some(e), ..BotReport::default() }];
+            }
+        },
+        Some(url) => BackendProfile::for_url("headless", url),
+        None => BackendProfile::production(),
+    };
+    let deadline = Duration::from_secs(scenario.duration_secs);
+
+    let tasks: Vec<_> = scenario
+        .bots
+        .into_iter()
+        .map(|bot| {
+            let profile = profile.clone();
+            tokio::spawn(async move {
+                let started = Instant::now();
+                let mut report = BotReport {
+                    email: bot.email.clone(),
+                    table_id: bot.table_id.clone(),
+                    ..BotReport::default()
+                };
+                match tokio::time::timeout(deadline, play(&profile, &bot, &mut report)).await {
+                    Ok(Err(e)) => report.error = Some(e),
+                    // Running out of time only counts as a failure when a hand target was set
+                    Err(_) if bot.hands.is_some() => {
+                        report.error = Some("Scenario timed out before the hand target was reached".to_string())
+                    }
+                    _ => {}
+                }
+                report.elapsed_ms = started.elapsed().as_millis() as u64;
+                eprintln!(
+                    "[{}] {} hands, {} actions{}",
+                    report.email,
+                    report.hands_completed,
+                    report.actions_sent,
+                    report.error.as_ref().map(|e| format!(", error: {}", e)).unwrap_or_default()
+                );
+                report
+            })
+        })
+        .collect();
+
+    let mut reports = Vec::new();
+    for task in tasks {
+        match task.await {
+            Ok(report) => reports.push(report),
+            Err(e) => reports.push(BotReport { error: Some(format!("Bot task panicked: {}", e)), ..BotReport::default() }),
+        }
+    }
+    reports
+}
+
+async fn play(profile: &BackendProfile, bot: &BotConfig, report: &mut BotReport) -> Result<(), String> {
+    let login = crate::fetch_login(&profile.api_url, bot.email.clone(), bot.password.clone()).await?;
+    let token = login.tokens.access_token;
+    let player_id = login.user.id;
+    report.player_id = Some(player_id.clone());
+
+    crate::post_join_table(&profile.api_url, &token, &bot.table_id, bot.buy_in).await?;
+
+    let mut table_id = bot.table_id.clone();
+    let (mut socket, mut incoming) = ws::connect(&ws::table_url(&profile.ws_url, &token, &table_id)).await?;
+    socket.send(WsMessage::new("join_table", json!({ "tableId": table_id, "playerId": player_id })))?;
+
+    let mut mirror = TableMirror::default();
+    let mut script = bot.actions.iter().cycle();
+    // Snapshot of the last decision point we acted on, so repeated updates don't double-act
+    let mut last_turn: Option<(u32, String, u32)> = None;
+    let mut was_over = false;
+
+    while let Some(message) = incoming.recv().await {
+        report.messages_received += 1;
+        if message.kind == "error" {
+            report.server_errors += 1;
+            eprintln!("[{}] server error: {}", bot.email, message.payload);
+            continue;
+        }
+        if let Some(moved) = PoolReassignment::parse(&message).filter(|m| m.player_id == player_id) {
+            report.reassignments += 1;
+            table_id = moved.to_table_id.clone();
+            (socket, incoming) = ws::connect(&ws::table_url(&profile.ws_url, &token, &table_id)).await?;
+            socket.send(moved.join_frame())?;
+            mirror = TableMirror::default();
+            last_turn = None;
+            continue;
+        }
+        if let Err(e) = mirror.apply(&message) {
+            eprintln!("[{}] {}", bot.email, e);
+            continue;
+        }
+
+        let over = mirror.is_hand_over();
+        if over && !was_over {
+            report.hands_completed += 1;
+            if bot.hands.is_some_and(|target| report.hands_completed >= target) {
+                socket.send(WsMessage::new("leave_table", json!({ "tableId": table_id })))?;
+                return Ok(());
+            }
+        }
+        was_over = over;
+
+        let legal = mirror.legal_actions(&player_id);
+        if legal.is_empty() {
+            continue;
+        }
+        let turn = (mirror.hand_number, mirror.phase.clone(), mirror.current_bet);
+        if last_turn.as_ref() == Some(&turn) {
+            continue;
+        }
+        last_turn = Some(turn);
+
+        let (action, amount) = match bot.strategy {
+            Strategy::Random => random_action(&legal),
+            Strategy::Scripted => scripted_action(script.next().map(String::as_str), &legal),
+        };
+        if bot.think_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(bot.think_ms)).await;
+        }
+        socket.send(WsMessage::new("player_action", json!({
+            "playerId": player_id,
+            "tableId": table_id,
+            "action": action,
+            "amount": amount,
+        })))?;
+        report.actions_sent += 1;
+    }
+
+    Err("Server closed the connection".to_string())
+}
+
+fn random_action(legal: &[LegalAction]) -> (String, u32) {
+    let mut rng = rand::thread_rng();
+    match legal.choose(&mut rng) {
+        Some(choice) => (choice.action.clone(), rng.gen_range(choice.min_amount..=choice.max_amount)),
+        None => ("fold".to_string(), 0),
+    }
+}
+
+// Play the next scripted step, clamping its amount into the legal range. Steps that
+// are not legal right now fall back to check, then fold.
+fn scripted_action(step: Option<&str>, legal: &[LegalAction]) -> (String, u32) {
+    let step = step.unwrap_or("check");
+    let (name, amount) = match step.split_once(':') {
+        Some((name, amount)) => (name, amount.trim().parse().ok()),
+        None => (step, None),
+    };
+    let find = |name: &str| legal.iter().find(|a| a.action == name);
+
+    // "bet" and "raise" are interchangeable in scripts; use whichever is legal
+    let chosen = match name {
+        "bet" | "raise" => find("bet").or_else(|| find("raise")),
+        other => find(other),
+    };
+    match chosen.or_else(|| find("check")).or_else(|| find("fold")) {
+        Some(action) => {
+            let amount = amount.unwrap_or(action.min_amount).clamp(action.min_amount, action.max_amount);
+            (action.action.clone(), amount)
+        }
+        None => ("fold".to_string(), 0),
+    }
+}
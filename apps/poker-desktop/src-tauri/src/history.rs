@@ -0,0 +1,372 @@
+use crate::bus;
+use crate::claims;
+use crate::db::Db;
+use crate::integrity;
+use crate::muck::{self, ShowdownChoice};
+use crate::promotions::{self, PromotionPayout};
+use crate::recent::RecentActionsState;
+use crate::sizing::Limit;
+use crate::vault;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Manager};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HandPlayer {
+    pub player_id: String,
+    pub username: String,
+    pub seat: u8,
+    pub starting_stack: u32,
+    #[serde(default)]
+    pub hole_cards: Option<Vec<String>>,
+    // Chips won minus chips put in, so winners are positive
+    pub net: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HandAction {
+    pub street: String,
+    pub player_id: String,
+    pub action: String,
+    #[serde(default)]
+    pub amount: u32,
+}
+
+// Who pays the ante. `ante` is per player for standard antes; with a button or big
+// blind ante it is the single amount that seat posts for the whole table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnteStructure {
+    #[default]
+    Standard,
+    Button,
+    BigBlind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HandRecord {
+    pub id: String,
+    pub table_id: String,
+    #[serde(default)]
+    pub table_name: Option<String>,
+    pub played_at: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
+    pub game_type: String,
+    pub betting_structure: String,
+    pub small_blind: u32,
+    pub big_blind: u32,
+    #[serde(default)]
+    pub ante: u32,
+    #[serde(default)]
+    pub ante_structure: AnteStructure,
+    #[serde(default)]
+    pub hero_id: Option<String>,
+    pub players: Vec<HandPlayer>,
+    #[serde(default)]
+    pub board: Vec<String>,
+    #[serde(default)]
+    pub actions: Vec<HandAction>,
+    pub pot: u32,
+    #[serde(default)]
+    pub rake: u32,
+    // Jackpot and high-hand payouts the hand triggered
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub promotions: Vec<PromotionPayout>,
+    // Whether the player showed or mucked when it was up to them, see muck.rs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub showdown_choice: Option<ShowdownChoice>,
+    // Set when stored, see integrity.rs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action_hash: Option<String>,
+}
+
+pub fn to_millis(time: &DateTime<Utc>) -> i64 {
+    time.timestamp_millis()
+}
+
+// Stored rows keep hole cards sealed (see vault.rs); they are opened here so callers
+// always see plain cards
+pub fn parse_hand(data: String) -> rusqlite::Result<HandRecord> {
+    let conversion = |e: serde_json::Error| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+    };
+    let mut value: serde_json::Value = serde_json::from_str(&data).map_err(conversion)?;
+    vault::open(&mut value);
+    serde_json::from_value(value).map_err(conversion)
+}
+
+// The record as compared between copies, without what changes on every save
+fn content(hand: &HandRecord) -> Option<serde_json::Value> {
+    let mut value = serde_json::to_value(hand).ok()?;
+    let fields = value.as_object_mut()?;
+    fields.remove("updatedAt");
+    fields.remove("actionHash");
+    Some(value)
+}
+
+// Insert a hand, or replace the stored copy when the incoming one is newer.
+// Copies that fail the integrity check, repeat the stored one or are a cut-short
+// version of it are skipped. Returns true when the row was written.
+pub fn upsert_hand(conn: &Connection, hand: &HandRecord) -> rusqlite::Result<bool> {
+    if let Err(defect) = integrity::check(hand) {
+        eprintln!("Skipping hand {}: {}", hand.id, defect);
+        return Ok(false);
+    }
+    let hash = integrity::action_hash(hand);
+    // An unreadable stored row is replaced like any other
+    if let Some(stored) = get_hand_by_id(conn, &hand.id).ok().flatten() {
+        if content(&stored) == content(hand) {
+            return Ok(false);
+        }
+        let is_prefix = hand.actions.len() < stored.actions.len()
+            && stored.actions.iter().zip(&hand.actions).all(|(a, b)| {
+                a.street == b.street && a.player_id == b.player_id && a.action == b.action && a.amount == b.amount
+            });
+        if is_prefix {
+            return Ok(false);
+        }
+    }
+
+    let mut value = serde_json::to_value(hand).map_err(|e| {
+        rusqlite::Error::ToSqlConversionFailure(Box::new(e))
+    })?;
+    value["actionHash"] = json!(hash);
+    vault::seal(&mut value);
+    let data = value.to_string();
+
+    let changed = conn.execute(
+        "INSERT INTO hands (id, table_id, played_at, updated_at, data)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(id) DO UPDATE SET
+            table_id = excluded.table_id,
+            played_at = excluded.played_at,
+            updated_at = excluded.updated_at,
+            data = excluded.data
+         WHERE excluded.updated_at > hands.updated_at",
+        params![
+            hand.id,
+            hand.table_id,
+            to_millis(&hand.played_at),
+            to_millis(&hand.updated_at),
+            data
+        ],
+    )?;
+
+    Ok(changed > 0)
+}
+
+pub fn get_hand_by_id(conn: &Connection, hand_id: &str) -> rusqlite::Result<Option<HandRecord>> {
+    let data: Option<String> = conn
+        .query_row("SELECT data FROM hands WHERE id = ?1", [hand_id], |row| row.get(0))
+        .optional()?;
+
+    data.map(parse_hand).transpose()
+}
+
+pub fn list_hands(conn: &Connection, limit: u32, offset: u32) -> rusqlite::Result<Vec<HandRecord>> {
+    let mut stmt = conn.prepare(
+        "SELECT data FROM hands ORDER BY played_at DESC LIMIT ?1 OFFSET ?2",
+    )?;
+    let rows = stmt.query_map(params![limit, offset], |row| row.get::<_, String>(0))?;
+
+    rows.map(|row| row.and_then(parse_hand)).collect()
+}
+
+// Most recent hands at one table, newest first
+pub fn recent_hands_at(conn: &Connection, table_id: &str, limit: u32) -> rusqlite::Result<Vec<HandRecord>> {
+    let mut stmt = conn.prepare(
+        "SELECT data FROM hands WHERE table_id = ?1 ORDER BY played_at DESC LIMIT ?2",
+    )?;
+    let rows = stmt.query_map(params![table_id, limit], |row| row.get::<_, String>(0))?;
+
+    rows.map(|row| row.and_then(parse_hand)).collect()
+}
+
+// Keyset page of hands, most recent first, strictly before (played_at, id)
+pub fn hands_before(conn: &Connection, before: Option<(i64, &str)>, limit: u32) -> rusqlite::Result<Vec<HandRecord>> {
+    let (played_at, id) = before.unwrap_or((i64::MAX, ""));
+    let mut stmt = conn.prepare(
+        "SELECT data FROM hands WHERE played_at < ?1 OR (played_at = ?1 AND id < ?2)
+         ORDER BY played_at DESC, id DESC LIMIT ?3",
+    )?;
+    let rows = stmt.query_map(params![played_at, id, limit], |row| row.get::<_, String>(0))?;
+
+    rows.map(|row| row.and_then(parse_hand)).collect()
+}
+
+// Keyset page of hands modified strictly after (updated_at, id), oldest first. Hands
+// sharing a millisecond are told apart by id, so a page boundary never skips any.
+pub fn hands_updated_since(conn: &Connection, after: (i64, &str), limit: u32) -> rusqlite::Result<Vec<HandRecord>> {
+    let (updated_at, id) = after;
+    let mut stmt = conn.prepare(
+        "SELECT data FROM hands WHERE updated_at > ?1 OR (updated_at = ?1 AND id > ?2)
+         ORDER BY updated_at, id LIMIT ?3",
+    )?;
+    let rows = stmt.query_map(params![updated_at, id, limit], |row| row.get::<_, String>(0))?;
+
+    rows.map(|row| row.and_then(parse_hand)).collect()
+}
+
+// Narrowing applied by the analysis commands. Time bounds are inclusive.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HandFilter {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub big_blind: Option<u32>,
+    pub game_type: Option<String>,
+    pub table_id: Option<String>,
+    // Practice-table hands are left out unless asked for
+    pub include_practice: bool,
+}
+
+impl HandFilter {
+    pub fn matches(&self, hand: &HandRecord) -> bool {
+        self.big_blind.is_none_or(|bb| hand.big_blind == bb)
+            && self.game_type.as_ref().is_none_or(|g| &hand.game_type == g)
+            && self.table_id.as_ref().is_none_or(|t| &hand.table_id == t)
+            && (self.include_practice || !hand.table_id.starts_with("practice-"))
+    }
+}
+
+// Every hand matching the filter, oldest first
+pub fn filtered_hands(conn: &Connection, filter: &HandFilter) -> rusqlite::Result<Vec<HandRecord>> {
+    let from = filter.from.as_ref().map(to_millis).unwrap_or(i64::MIN);
+    let to = filter.to.as_ref().map(to_millis).unwrap_or(i64::MAX);
+    let mut stmt = conn.prepare(
+        "SELECT data FROM hands WHERE played_at BETWEEN ?1 AND ?2 ORDER BY played_at ASC",
+    )?;
+    let rows = stmt.query_map(params![from, to], |row| row.get::<_, String>(0))?;
+
+    let mut hands = Vec::new();
+    for row in rows {
+        let hand = parse_hand(row?)?;
+        if filter.matches(&hand) {
+            hands.push(hand);
+        }
+    }
+    Ok(hands)
+}
+
+// Changes whenever a hand is added or rewritten; used to invalidate cached analysis
+pub fn fingerprint(conn: &Connection) -> rusqlite::Result<(i64, i64)> {
+    conn.query_row("SELECT COUNT(*), COALESCE(MAX(updated_at), 0) FROM hands", [], |row| {
+        Ok((row.get(0)?, row.get(1)?))
+    })
+}
+
+// Save a completed hand reported by the table view
+#[tauri::command]
+pub async fn save_hand_history(app: AppHandle, db: Db<'_>, mut hand: HandRecord) -> Result<(), String> {
+    hand.updated_at = Utc::now();
+    // One spelling per structure, so "limit" and "fixed_limit" hands group together
+    if let Some(structure) = Limit::from_name(&hand.betting_structure) {
+        hand.betting_structure = structure.as_str().to_string();
+    }
+    if let Err(e) = promotions::attach(&db, &mut hand) {
+        eprintln!("Failed to attach promotion payouts: {}", e);
+    }
+    if let Err(e) = muck::attach(&db, &mut hand) {
+        eprintln!("Failed to attach showdown choice: {}", e);
+    }
+    integrity::check(&hand).map_err(|defect| format!("Hand {} is {}", hand.id, defect))?;
+    let is_new = db.with_conn(|conn| {
+        let is_new = get_hand_by_id(conn, &hand.id)?.is_none();
+        upsert_hand(conn, &hand)?;
+        Ok(is_new)
+    })?;
+    app.state::<RecentActionsState>().record(&hand);
+    // Achievements, loyalty, bonuses, the ledger and auto-rebuy follow on the bus
+    bus::publish(bus::Event::HandSaved { hand: Box::new(hand), is_new });
+    Ok(())
+}
+
+// Get stored hands, most recent first
+#[tauri::command]
+pub async fn get_hand_history(
+    db: Db<'_>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<Vec<HandRecord>, String> {
+    db.with_conn(|conn| list_hands(conn, limit.unwrap_or(50), offset.unwrap_or(0)))
+}
+
+// Get a single stored hand
+#[tauri::command]
+pub async fn get_hand(db: Db<'_>, hand_id: String) -> Result<Option<HandRecord>, String> {
+    db.with_conn(|conn| get_hand_by_id(conn, &hand_id))
+}
+
+// Make the viewer the hero of a hand fetched from the backend, or leave it without
+// one when they were not in it, so it stays out of their own stats
+pub fn set_viewer_as_hero(hand: &mut HandRecord) {
+    let viewer = claims::current().ok().map(|c| c.user_id);
+    hand.hero_id = viewer.filter(|viewer| hand.players.iter().any(|p| &p.player_id == viewer));
+}
+
+pub async fn request_hand(api_url: &str, hand_id: &str) -> Result<HandRecord, String> {
+    // Goes into the request path as is
+    if hand_id.is_empty() || !hand_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(format!("Invalid hand id {:?}", hand_id));
+    }
+    let client = crate::create_http_client()?;
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+
+    let response = crate::http::send(client.get(format!("{}/api/hands/{}", api_url, hand_id))
+        .header("Authorization", format!("Bearer {}", token)))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match response.status() {
+        reqwest::StatusCode::NOT_FOUND => return Err(format!("Hand {} not found", hand_id)),
+        reqwest::StatusCode::FORBIDDEN => return Err("You do not have access to this hand".to_string()),
+        status if !status.is_success() => {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(format!("Failed to fetch hand: {}", error_text));
+        }
+        _ => {}
+    }
+
+    let api_response: crate::ApiResponse<HandRecord> = response.json().await
+        .map_err(|e| format!("Failed to parse hand: {}", e))?;
+    match api_response.data {
+        Some(hand) if api_response.success => Ok(hand),
+        _ => Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string())),
+    }
+}
+
+// Open a hand by id, as from a shared link: the local copy when there is one,
+// otherwise the backend's, which is stored for next time. Hands the player was not
+// in are stored without a hero, and the rest with the player as hero. Emits
+// `open_hand_replay` for the replayer.
+#[tauri::command]
+pub async fn fetch_hand_by_id(
+    app: AppHandle,
+    db: Db<'_>,
+    api_url: String,
+    hand_id: String,
+    refresh: Option<bool>,
+) -> Result<HandRecord, String> {
+    let local = db.with_conn(|conn| get_hand_by_id(conn, &hand_id))?;
+    let hand = match local {
+        Some(hand) if !refresh.unwrap_or(false) => hand,
+        _ => {
+            let mut hand = request_hand(&api_url, &hand_id).await?;
+            if hand.id != hand_id {
+                return Err(format!("Backend returned hand {} for {}", hand.id, hand_id));
+            }
+            set_viewer_as_hero(&mut hand);
+            db.with_conn(|conn| upsert_hand(conn, &hand))?;
+            hand
+        }
+    };
+    let _ = app.emit_all("open_hand_replay", json!({ "handId": hand.id }));
+    Ok(hand)
+}
@@ -0,0 +1,172 @@
+// Host controls for private tables the player created: kick, pause and resume, and
+// blind changes that take effect from the next hand. Tables are remembered as hosted
+// when `create_table` succeeds; admins may use the controls on any table.
+
+use crate::audit;
+use crate::claims;
+use crate::db::{Database, Db};
+use crate::error::CommandError;
+use crate::guard;
+use crate::history::AnteStructure;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tauri::AppHandle;
+
+const KEY_HOSTED: &str = "host.tables";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HostedTable {
+    host_id: String,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableConfigUpdate {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    small_blind: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    big_blind: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ante: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ante_structure: Option<AnteStructure>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    time_bank: Option<u32>,
+}
+
+fn load(db: &Database) -> Result<HashMap<String, HostedTable>, String> {
+    match db.get_value(KEY_HOSTED)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid hosted tables: {}", e)),
+        None => Ok(HashMap::new()),
+    }
+}
+
+// Remember that the signed-in player created `table_id`
+pub fn record_hosted(db: &Database, table_id: &str) -> Result<(), String> {
+    let claims = claims::current()?;
+    let mut hosted = load(db)?;
+    hosted.insert(table_id.to_string(), HostedTable { host_id: claims.user_id, created_at: Utc::now() });
+    let data = serde_json::to_string(&hosted).map_err(|e| e.to_string())?;
+    db.set_value(KEY_HOSTED, &data)
+}
+
+fn require_host(db: &Database, table_id: &str, action: &str) -> Result<(), CommandError> {
+    let hosted = load(db)?;
+    guard::require_host(action, hosted.get(table_id).map(|table| table.host_id.as_str()))?;
+    Ok(())
+}
+
+async fn post_host_action(api_url: &str, table_id: &str, action: &str, body: Value) -> Result<Value, String> {
+    let client = crate::create_http_client()?;
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+
+    let response = crate::http::send(client.post(format!("{}/api/tables/{}/{}", api_url, table_id, action))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&body))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("Failed to {} table: {}", action, error_text));
+    }
+
+    let api_response: crate::ApiResponse<Value> = response.json().await.map_err(|e| e.to_string())?;
+
+    if api_response.success {
+        Ok(api_response.data.unwrap_or(json!({})))
+    } else {
+        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    }
+}
+
+async fn host_action(
+    app: &AppHandle,
+    db: &Database,
+    api_url: &str,
+    table_id: &str,
+    action: &str,
+    body: Value,
+) -> Result<Value, CommandError> {
+    let result = match require_host(db, table_id, action) {
+        Ok(()) => post_host_action(api_url, table_id, action, body.clone()).await.map_err(CommandError::from),
+        Err(e) => Err(e),
+    };
+    audit::record(app, &format!("host_{}", action), json!({ "tableId": table_id, "request": body }), &result);
+    result
+}
+
+#[tauri::command]
+pub async fn kick_player(
+    app: AppHandle,
+    db: Db<'_>,
+    api_url: String,
+    table_id: String,
+    player_id: String,
+    reason: Option<String>,
+) -> Result<Value, CommandError> {
+    if claims::current().is_ok_and(|claims| claims.user_id == player_id) {
+        return Err(CommandError::Forbidden {
+            action: "kick".to_string(),
+            reason: "Hosts cannot kick themselves; leave the table instead".to_string(),
+        });
+    }
+    host_action(&app, &db, &api_url, &table_id, "kick", json!({ "playerId": player_id, "reason": reason })).await
+}
+
+// Stops new hands from being dealt; a hand in progress plays out
+#[tauri::command]
+pub async fn pause_table(
+    app: AppHandle,
+    db: Db<'_>,
+    api_url: String,
+    table_id: String,
+) -> Result<Value, CommandError> {
+    host_action(&app, &db, &api_url, &table_id, "pause", json!({})).await
+}
+
+#[tauri::command]
+pub async fn resume_table(
+    app: AppHandle,
+    db: Db<'_>,
+    api_url: String,
+    table_id: String,
+) -> Result<Value, CommandError> {
+    host_action(&app, &db, &api_url, &table_id, "resume", json!({})).await
+}
+
+// Blind and ante changes are applied by the server between hands
+#[tauri::command]
+pub async fn update_table_config(
+    app: AppHandle,
+    db: Db<'_>,
+    api_url: String,
+    table_id: String,
+    update: TableConfigUpdate,
+) -> Result<Value, CommandError> {
+    if let (Some(small), Some(big)) = (update.small_blind, update.big_blind) {
+        if small == 0 || big < small {
+            return Err("Big blind must be at least the small blind".to_string().into());
+        }
+    }
+    if update.small_blind == Some(0) || update.big_blind == Some(0) {
+        return Err("Blinds must be greater than zero".to_string().into());
+    }
+    if let (Some(ante), Some(big)) = (update.ante, update.big_blind) {
+        if ante > big {
+            return Err("The ante cannot be more than the big blind".to_string().into());
+        }
+    }
+    if update.ante == Some(0) && matches!(update.ante_structure, Some(AnteStructure::Button | AnteStructure::BigBlind)) {
+        return Err("Button and big blind antes need an ante amount".to_string().into());
+    }
+
+    let mut body = serde_json::to_value(&update).map_err(|e| e.to_string())?;
+    body["effective"] = json!("next_hand");
+    host_action(&app, &db, &api_url, &table_id, "config", body).await
+}
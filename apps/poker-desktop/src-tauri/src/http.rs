@@ -0,0 +1,60 @@
+use crate::error::CommandError;
+use crate::ratelimit::{self, EndpointClass};
+use crate::version;
+use reqwest::{RequestBuilder, Response};
+use std::future::Future;
+
+// Requests that must still work once the backend has been found incompatible
+const VERSION_EXEMPT: &[&str] = &["/api/version", "/api/health"];
+
+tokio::task_local! {
+    // Set while the client runs a series of requests of its own
+    static PACED: ();
+}
+
+// Run `future` with its backend requests waiting for the rate limiter instead of
+// failing. For series the client makes back to back on its own, such as the pages
+// of one lobby listing, where a rejection partway would throw the rest away.
+pub async fn paced<F: Future>(future: F) -> F::Output {
+    PACED.scope((), future).await
+}
+
+// Single exit point for backend requests so cross-cutting policy (rate limiting,
+// fixture recording, version compatibility) applies to every command
+pub async fn send(builder: RequestBuilder) -> Result<Response, CommandError> {
+    let (client, request) = builder
+        .header("X-Client-Version", version::CLIENT_VERSION)
+        .build_split();
+    let request = request.map_err(|e| CommandError::Other {
+        message: format!("Invalid request: {}", e),
+    })?;
+
+    if !VERSION_EXEMPT.contains(&request.url().path()) {
+        version::check_compatible()?;
+    }
+
+    let endpoint_class = EndpointClass::from_path(request.url().path());
+    if PACED.try_with(|_| ()).is_ok() {
+        ratelimit::wait(endpoint_class).await;
+    } else {
+        ratelimit::acquire(endpoint_class).map_err(|retry_after_ms| CommandError::RateLimited {
+            endpoint_class,
+            retry_after_ms,
+        })?;
+    }
+
+    #[cfg(feature = "http-fixtures")]
+    if let Some(mode) = crate::fixtures::mode() {
+        return crate::fixtures::send(mode, client, request).await;
+    }
+
+    let response = client.execute(request).await.map_err(|e| CommandError::Network {
+        message: e.to_string(),
+    })?;
+    if response.status() == reqwest::StatusCode::UPGRADE_REQUIRED {
+        let reason = response.text().await.unwrap_or_default();
+        let reason = if reason.is_empty() { "The backend requires a newer client".to_string() } else { reason };
+        return Err(version::mark_update_required(reason));
+    }
+    Ok(response)
+}
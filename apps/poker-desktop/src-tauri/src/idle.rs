@@ -0,0 +1,316 @@
+// Idle detection from OS input timestamps. After `away_secs` without input the
+// player's presence is set to away on the backend (friends see it); after
+// `sit_out_secs` the seated tables are sat out. Neither happens while a hand is in
+// progress at one of the player's tables; the frontend reports table activity since
+// the live table sockets belong to the webview.
+
+use crate::db::{Database, Db};
+use crate::ledger;
+use crate::profile::BackendProfile;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+const KEY_THRESHOLDS: &str = "idle.thresholds";
+const CHECK_INTERVAL_SECS: u64 = 5;
+const MIN_AWAY_SECS: u64 = 30;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdleThresholds {
+    away_secs: u64,
+    sit_out_secs: u64,
+}
+
+impl Default for IdleThresholds {
+    fn default() -> Self {
+        Self { away_secs: 300, sit_out_secs: 600 }
+    }
+}
+
+#[derive(Default)]
+struct Monitor {
+    thresholds: IdleThresholds,
+    // Seated tables and whether a hand is in progress at each
+    tables: HashMap<String, bool>,
+    away: bool,
+    sat_out: HashSet<String>,
+    idle_secs: Option<u64>,
+}
+
+#[derive(Default)]
+pub struct IdleState {
+    monitor: Mutex<Monitor>,
+}
+
+impl IdleState {
+    // Tables the player is seated at, as last reported by the frontend
+    pub fn seated_tables(&self) -> Vec<String> {
+        let mut tables: Vec<String> = self.table_activity().into_keys().collect();
+        tables.sort();
+        tables
+    }
+
+    // Seated tables and whether a hand is in progress at each
+    pub fn table_activity(&self) -> HashMap<String, bool> {
+        self.monitor.lock().map(|monitor| monitor.tables.clone()).unwrap_or_default()
+    }
+
+    // A fast-fold pool moved the player on to another table
+    pub fn move_table(&self, from: &str, to: &str) {
+        if let Ok(mut monitor) = self.monitor.lock() {
+            monitor.tables.remove(from);
+            monitor.sat_out.remove(from);
+            monitor.tables.insert(to.to_string(), true);
+        }
+    }
+
+    // Whether the player has been idle long enough to show as away
+    pub fn away(&self) -> bool {
+        self.monitor.lock().map(|monitor| monitor.away).unwrap_or(false)
+    }
+
+    // The player left a table without the frontend reporting it yet
+    pub fn forget_table(&self, table_id: &str) {
+        if let Ok(mut monitor) = self.monitor.lock() {
+            monitor.tables.remove(table_id);
+            monitor.sat_out.remove(table_id);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdleStatus {
+    // None when the OS does not report input times
+    idle_secs: Option<u64>,
+    away: bool,
+    hand_in_progress: bool,
+    sat_out_tables: Vec<String>,
+    thresholds: IdleThresholds,
+}
+
+#[cfg(target_os = "windows")]
+fn os_idle_secs() -> Option<u64> {
+    #[repr(C)]
+    struct LastInputInfo {
+        cb_size: u32,
+        dw_time: u32,
+    }
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetLastInputInfo(plii: *mut LastInputInfo) -> i32;
+    }
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetTickCount() -> u32;
+    }
+
+    let mut info = LastInputInfo { cb_size: std::mem::size_of::<LastInputInfo>() as u32, dw_time: 0 };
+    // SAFETY: `info` is a correctly sized LASTINPUTINFO that outlives the call
+    let ok = unsafe { GetLastInputInfo(&mut info) } != 0;
+    ok.then(|| unsafe { GetTickCount() }.wrapping_sub(info.dw_time) as u64 / 1000)
+}
+
+#[cfg(target_os = "macos")]
+fn os_idle_secs() -> Option<u64> {
+    let output = std::process::Command::new("ioreg").args(["-c", "IOHIDSystem", "-d", "4"]).output().ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find(|line| line.contains("\"HIDIdleTime\""))
+        .and_then(|line| line.rsplit('=').next())
+        .and_then(|nanos| nanos.trim().parse::<u64>().ok())
+        .map(|nanos| nanos / 1_000_000_000)
+}
+
+// X11 through xprintidle, then the GNOME idle monitor for Wayland sessions
+#[cfg(target_os = "linux")]
+fn os_idle_secs() -> Option<u64> {
+    use std::process::Command;
+
+    let xprintidle = Command::new("xprintidle").output().ok().filter(|o| o.status.success());
+    if let Some(output) = xprintidle {
+        return String::from_utf8_lossy(&output.stdout).trim().parse::<u64>().ok().map(|ms| ms / 1000);
+    }
+    let output = Command::new("gdbus")
+        .args([
+            "call", "--session",
+            "--dest", "org.gnome.Mutter.IdleMonitor",
+            "--object-path", "/org/gnome/Mutter/IdleMonitor/Core",
+            "--method", "org.gnome.Mutter.IdleMonitor.GetIdletime",
+        ])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    // Prints "(uint64 12345,)"
+    String::from_utf8_lossy(&output.stdout)
+        .trim_matches(|c: char| !c.is_ascii_digit())
+        .rsplit(|c: char| !c.is_ascii_digit())
+        .next()
+        .and_then(|ms| ms.parse::<u64>().ok())
+        .map(|ms| ms / 1000)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn os_idle_secs() -> Option<u64> {
+    None
+}
+
+fn status(monitor: &Monitor) -> IdleStatus {
+    let mut sat_out_tables: Vec<String> = monitor.sat_out.iter().cloned().collect();
+    sat_out_tables.sort();
+    IdleStatus {
+        idle_secs: monitor.idle_secs,
+        away: monitor.away,
+        hand_in_progress: monitor.tables.values().any(|&in_hand| in_hand),
+        sat_out_tables,
+        thresholds: monitor.thresholds,
+    }
+}
+
+async fn post(api_url: &str, path: &str, body: serde_json::Value) -> Result<(), String> {
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+    let client = crate::create_http_client()?;
+    let response = crate::http::send(client.post(format!("{}{}", api_url, path))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&body))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        Err(format!("Request to {} failed: {}", path, error_text))
+    }
+}
+
+// One check: returns whether presence changed and which tables to sit out now
+fn evaluate(monitor: &mut Monitor, idle_secs: Option<u64>) -> (bool, Vec<String>) {
+    monitor.idle_secs = idle_secs;
+    let idle = idle_secs.unwrap_or(0);
+    let in_hand = monitor.tables.values().any(|&in_hand| in_hand);
+
+    // Input resumed: back online and no longer counted as sat out by us
+    if idle < monitor.thresholds.away_secs {
+        monitor.sat_out.clear();
+        let changed = monitor.away;
+        monitor.away = false;
+        return (changed, Vec::new());
+    }
+    if in_hand {
+        return (false, Vec::new());
+    }
+
+    let changed = !monitor.away;
+    monitor.away = true;
+    let mut sit_out = Vec::new();
+    if idle >= monitor.thresholds.sit_out_secs {
+        for table_id in monitor.tables.keys() {
+            if monitor.sat_out.insert(table_id.clone()) {
+                sit_out.push(table_id.clone());
+            }
+        }
+    }
+    (changed, sit_out)
+}
+
+// Background idle loop, started once the database is open
+pub fn start_monitor(app: &AppHandle) {
+    let app = app.clone();
+    {
+        let Some(db) = app.try_state::<Database>() else { return };
+        let thresholds = match db.get_value(KEY_THRESHOLDS) {
+            Ok(Some(data)) => serde_json::from_str(&data).unwrap_or_default(),
+            _ => IdleThresholds::default(),
+        };
+        if let Ok(mut monitor) = app.state::<IdleState>().monitor.lock() {
+            monitor.thresholds = thresholds;
+        }
+    }
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(CHECK_INTERVAL_SECS)).await;
+            let idle_secs = os_idle_secs();
+            let (changed, sit_out, current) = {
+                let state = app.state::<IdleState>();
+                let Ok(mut monitor) = state.monitor.lock() else { continue };
+                let (changed, sit_out) = evaluate(&mut monitor, idle_secs);
+                (changed, sit_out, status(&monitor))
+            };
+
+            let api_url = app.state::<BackendProfile>().api_url.clone();
+            if changed {
+                let presence = if current.away { "away" } else { "online" };
+                if let Err(e) = post(&api_url, "/api/players/me/presence", json!({ "status": presence })).await {
+                    eprintln!("Failed to update presence: {}", e);
+                }
+                let _ = app.emit_all("idle_status_changed", current.clone());
+            }
+            for table_id in sit_out {
+                match post(&api_url, &format!("/api/tables/{}/sit-out", table_id), json!({ "reason": "idle" })).await {
+                    Ok(()) => {
+                        let _ = app.emit_all("auto_sat_out", json!({ "tableId": table_id }));
+                    }
+                    Err(e) => eprintln!("Failed to sit out at {}: {}", table_id, e),
+                }
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn set_idle_thresholds(
+    db: Db<'_>,
+    state: State<'_, IdleState>,
+    away_secs: u64,
+    sit_out_secs: u64,
+) -> Result<IdleStatus, String> {
+    if away_secs < MIN_AWAY_SECS {
+        return Err(format!("Away threshold must be at least {} seconds", MIN_AWAY_SECS));
+    }
+    if sit_out_secs < away_secs {
+        return Err("Sit-out threshold cannot be shorter than the away threshold".to_string());
+    }
+    let thresholds = IdleThresholds { away_secs, sit_out_secs };
+    let data = serde_json::to_string(&thresholds).map_err(|e| e.to_string())?;
+    db.set_value(KEY_THRESHOLDS, &data)?;
+
+    let mut monitor = state.monitor.lock().map_err(|_| "Idle monitor lock poisoned".to_string())?;
+    monitor.thresholds = thresholds;
+    Ok(status(&monitor))
+}
+
+#[tauri::command]
+pub async fn get_idle_status(state: State<'_, IdleState>) -> Result<IdleStatus, String> {
+    let monitor = state.monitor.lock().map_err(|_| "Idle monitor lock poisoned".to_string())?;
+    Ok(status(&monitor))
+}
+
+// Called by the frontend when the player sits down, stands up, or a hand starts or
+// ends at one of their tables
+#[tauri::command]
+pub async fn report_table_activity(
+    app: AppHandle,
+    state: State<'_, IdleState>,
+    table_id: String,
+    seated: bool,
+    in_hand: bool,
+) -> Result<(), String> {
+    let mut monitor = state.monitor.lock().map_err(|_| "Idle monitor lock poisoned".to_string())?;
+    if seated {
+        monitor.tables.insert(table_id, in_hand);
+        return Ok(());
+    }
+    monitor.sat_out.remove(&table_id);
+    let stood_up = monitor.tables.remove(&table_id).is_some();
+    drop(monitor);
+    if stood_up {
+        ledger::table_left(&app, &table_id);
+    }
+    Ok(())
+}
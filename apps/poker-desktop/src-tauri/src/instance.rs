@@ -0,0 +1,115 @@
+// One running client per user. The first instance listens on a loopback port and
+// writes it, with a random token, to `instance.lock` at the top of the app data
+// directory. A later launch that finds the port answering hands over its hand file
+// argument, if any, and exits; the running instance brings its window forward and
+// opens the file. A launch that gets no answer - no lock file, a stale one, or an
+// instance on its way out - starts as usual and takes the lock over.
+
+use crate::hand_files;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+const LOCK_FILE: &str = "instance.lock";
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+const ACCEPTED: &str = "ok";
+
+// Set once this instance stops taking launches over, as before a restart
+static RELEASED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Serialize, Deserialize)]
+struct Launch {
+    token: String,
+    file: Option<PathBuf>,
+}
+
+fn lock_path(data_root: &Path) -> PathBuf {
+    data_root.join(LOCK_FILE)
+}
+
+// `<port> <token>` from the lock file
+fn read_lock(data_root: &Path) -> Option<(u16, String)> {
+    let data = std::fs::read_to_string(lock_path(data_root)).ok()?;
+    let (port, token) = data.trim().split_once(' ')?;
+    Some((port.parse().ok()?, token.to_string()))
+}
+
+// Hand this launch to a running instance. True when it took it and this process
+// should exit.
+pub fn hand_over(config: &tauri::Config) -> bool {
+    let Some(data_root) = tauri::api::path::app_data_dir(config) else { return false };
+    let Some((port, token)) = read_lock(&data_root) else { return false };
+    let launch = Launch { token, file: hand_files::file_arg() };
+    let send = || -> std::io::Result<bool> {
+        let mut stream = TcpStream::connect_timeout(&SocketAddr::from(([127, 0, 0, 1], port)), CONNECT_TIMEOUT)?;
+        stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+        let line = serde_json::to_string(&launch).map_err(std::io::Error::other)?;
+        stream.write_all(format!("{}\n", line).as_bytes())?;
+        let mut reply = String::new();
+        BufReader::new(stream).read_line(&mut reply)?;
+        Ok(reply.trim() == ACCEPTED)
+    };
+    send().unwrap_or(false)
+}
+
+// Take later launches over for as long as this instance runs
+pub fn listen(app: &AppHandle, data_root: &Path) {
+    let app = app.clone();
+    let lock = lock_path(data_root);
+    tauri::async_runtime::spawn(async move {
+        let listener = match TcpListener::bind(("127.0.0.1", 0)).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                eprintln!("Single instance: failed to listen: {}", e);
+                return;
+            }
+        };
+        let Ok(addr) = listener.local_addr() else { return };
+        let token: String = {
+            let mut rng = rand::thread_rng();
+            (0..32).map(|_| format!("{:02x}", rng.gen::<u8>())).collect()
+        };
+        if let Err(e) = std::fs::write(&lock, format!("{} {}", addr.port(), token)) {
+            eprintln!("Single instance: failed to write {}: {}", lock.display(), e);
+            return;
+        }
+        while let Ok((stream, _)) = listener.accept().await {
+            tauri::async_runtime::spawn(take_over(app.clone(), token.clone(), stream));
+        }
+    });
+}
+
+async fn take_over(app: AppHandle, token: String, mut stream: tokio::net::TcpStream) {
+    let (read, mut write) = stream.split();
+    let mut reader = tokio::io::BufReader::new(read);
+    let mut line = String::new();
+    if !matches!(tokio::time::timeout(REPLY_TIMEOUT, reader.read_line(&mut line)).await, Ok(Ok(_))) {
+        return;
+    }
+    let Ok(launch) = serde_json::from_str::<Launch>(&line) else { return };
+    if launch.token != token || RELEASED.load(Ordering::SeqCst) {
+        return;
+    }
+    let _ = write.write_all(format!("{}\n", ACCEPTED).as_bytes()).await;
+
+    if let Some(window) = app.get_window("main") {
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+    if let Some(path) = launch.file {
+        let _ = hand_files::open_and_show(&app, &path);
+    }
+}
+
+// Stop taking launches over, so the process a restart starts runs on its own
+pub fn release() {
+    RELEASED.store(true, Ordering::SeqCst);
+}
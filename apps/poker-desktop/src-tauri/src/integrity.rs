@@ -0,0 +1,242 @@
+// Hand history integrity. The same hand can reach the database from the table view,
+// a backend fetch, cloud sync or the practice engine; every copy goes through
+// `history::upsert_hand`, which uses the checks here to refuse a damaged copy and to
+// skip one that only repeats what is stored. A hand is fingerprinted by a hash of its
+// action sequence, seats and board, kept in the record so a copy that was altered or
+// cut short on the way is caught. `verify_history_integrity` scans the stored hands
+// for damage and replaces what it finds with the backend's copy.
+
+use crate::compute::{self, Priority};
+use crate::db::{self, Database};
+use crate::history::{self, HandRecord};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fmt;
+use tauri::AppHandle;
+
+// Hands re-fetched per scan
+const MAX_REFETCH: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Problem {
+    // Inconsistent with itself, or not what was hashed
+    Corrupted,
+    // Missing its end: cards, actions or the payout
+    Truncated,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Defect {
+    pub problem: Problem,
+    pub reason: String,
+}
+
+impl fmt::Display for Defect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let problem = match self.problem {
+            Problem::Corrupted => "corrupted",
+            Problem::Truncated => "truncated",
+        };
+        write!(f, "{}: {}", problem, self.reason)
+    }
+}
+
+fn corrupted(reason: String) -> Defect {
+    Defect { problem: Problem::Corrupted, reason }
+}
+
+fn truncated(reason: String) -> Defect {
+    Defect { problem: Problem::Truncated, reason }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DamagedHand {
+    hand_id: String,
+    #[serde(flatten)]
+    defect: Defect,
+    repaired: bool,
+    // Why it could not be repaired
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+    scanned: u32,
+    damaged: Vec<DamagedHand>,
+    repaired: u32,
+    // Practice hands exist only here; damaged ones past the re-fetch budget wait for
+    // the next scan
+    unrepaired: u32,
+}
+
+fn street_rank(street: &str) -> Option<u8> {
+    match street {
+        "waiting" => Some(0),
+        "pre_flop" | "preflop" => Some(1),
+        "flop" => Some(2),
+        "turn" => Some(3),
+        "river" => Some(4),
+        "showdown" => Some(5),
+        _ => None,
+    }
+}
+
+// Hash of what makes the hand: seats, stacks and results, board and actions in order
+pub fn action_hash(hand: &HandRecord) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(hand.id.as_bytes());
+    for player in &hand.players {
+        hasher.update(format!("\nseat|{}|{}|{}|{}", player.seat, player.player_id, player.starting_stack, player.net));
+    }
+    hasher.update(format!("\nboard|{}", hand.board.join(",")));
+    for action in &hand.actions {
+        hasher.update(format!("\n{}|{}|{}|{}", action.street, action.player_id, action.action, action.amount));
+    }
+    hasher.update(format!("\npot|{}|{}", hand.pot, hand.rake));
+    hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Whether the hand holds together: seated players, streets in order, the cards the
+// streets need, every chip accounted for, and the hash it carries, if any
+pub fn check(hand: &HandRecord) -> Result<(), Defect> {
+    if hand.players.is_empty() {
+        return Err(corrupted("no players".to_string()));
+    }
+    let mut ids = HashSet::new();
+    let mut seats = HashSet::new();
+    for player in &hand.players {
+        if !ids.insert(player.player_id.as_str()) || !seats.insert(player.seat) {
+            return Err(corrupted(format!("player {} or seat {} listed twice", player.player_id, player.seat)));
+        }
+    }
+
+    let mut cards = HashSet::new();
+    let dealt = hand.players.iter().filter_map(|p| p.hole_cards.as_ref()).flatten();
+    for card in hand.board.iter().chain(dealt) {
+        if !cards.insert(card.as_str()) {
+            return Err(corrupted(format!("card {} dealt twice", card)));
+        }
+    }
+    if !matches!(hand.board.len(), 0 | 3 | 4 | 5) {
+        return Err(corrupted(format!("board of {} cards", hand.board.len())));
+    }
+
+    let mut reached = 0;
+    for (i, action) in hand.actions.iter().enumerate() {
+        if !ids.contains(action.player_id.as_str()) {
+            return Err(corrupted(format!("action {} by unseated player {}", i, action.player_id)));
+        }
+        let rank = street_rank(&action.street).ok_or_else(|| corrupted(format!("unknown street {}", action.street)))?;
+        if rank < reached {
+            return Err(corrupted(format!("action {} goes back to {}", i, action.street)));
+        }
+        reached = rank;
+    }
+    let needed = match reached {
+        2 => 3,
+        3 => 4,
+        4 => 5,
+        _ => 0,
+    };
+    if hand.board.len() < needed {
+        let street = hand.actions.last().map_or("", |a| a.street.as_str());
+        return Err(truncated(format!("{} board cards for play on the {}", hand.board.len(), street)));
+    }
+    if hand.actions.is_empty() && hand.pot > 0 {
+        return Err(truncated("a pot but no actions".to_string()));
+    }
+    let balance: i64 = hand.players.iter().map(|p| p.net).sum::<i64>() + hand.rake as i64;
+    if balance != 0 {
+        return Err(truncated(format!("results are {} chips off", balance)));
+    }
+
+    if let Some(carried) = &hand.action_hash {
+        if *carried != action_hash(hand) {
+            return Err(corrupted("action hash does not match".to_string()));
+        }
+    }
+    Ok(())
+}
+
+// A damaged hand's id and defect, and whether it is a practice hand
+type Found = (String, Defect, bool);
+
+// Every stored hand that fails to load or to check, and how many were scanned
+fn scan(db: &Database) -> Result<(u32, Vec<Found>), String> {
+    db.with_conn(|conn| {
+        let mut stmt = conn.prepare("SELECT id, table_id, data FROM hands")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?;
+        let mut scanned = 0;
+        let mut damaged = Vec::new();
+        for row in rows {
+            let (id, table_id, data) = row?;
+            scanned += 1;
+            let defect = match history::parse_hand(data) {
+                Ok(hand) => check(&hand).err(),
+                Err(e) => Some(corrupted(format!("unreadable: {}", e))),
+            };
+            if let Some(defect) = defect {
+                damaged.push((id, defect, table_id.starts_with("practice-")));
+            }
+        }
+        Ok((scanned, damaged))
+    })
+}
+
+async fn repair(db: &Database, api_url: &str, hand_id: &str) -> Result<(), String> {
+    let mut hand = history::request_hand(api_url, hand_id).await?;
+    if hand.id != hand_id {
+        return Err(format!("Backend returned hand {} for {}", hand.id, hand_id));
+    }
+    check(&hand).map_err(|defect| format!("The backend's copy is {}", defect))?;
+    history::set_viewer_as_hero(&mut hand);
+    // Newer than the damaged row so it replaces it
+    hand.updated_at = chrono::Utc::now();
+    if db.with_conn(|conn| history::upsert_hand(conn, &hand))? {
+        Ok(())
+    } else {
+        Err("The stored copy could not be replaced".to_string())
+    }
+}
+
+// Scan stored hands for corrupted or truncated ones and, unless `refetch` is false,
+// replace them with the backend's copy
+#[tauri::command]
+pub async fn verify_history_integrity(
+    app: AppHandle,
+    api_url: String,
+    refetch: Option<bool>,
+) -> Result<IntegrityReport, String> {
+    let handle = app.clone();
+    let (scanned, found) = compute::run("History integrity", Priority::Low, move |cancel| {
+        cancel.check()?;
+        let db = db::get(&handle)?;
+        scan(&db)
+    })
+    .await?;
+
+    let db = db::get(&app)?;
+    let mut report = IntegrityReport { scanned, ..Default::default() };
+    let mut budget = if refetch.unwrap_or(true) { MAX_REFETCH } else { 0 };
+    for (hand_id, defect, practice) in found {
+        let result = if practice {
+            Err("Practice hands are only stored locally".to_string())
+        } else if budget == 0 {
+            Err("Not re-fetched".to_string())
+        } else {
+            budget -= 1;
+            repair(&db, &api_url, &hand_id).await
+        };
+        match &result {
+            Ok(()) => report.repaired += 1,
+            Err(_) => report.unrepaired += 1,
+        }
+        report.damaged.push(DamagedHand { hand_id, defect, repaired: result.is_ok(), error: result.err() });
+    }
+    Ok(report)
+}
@@ -0,0 +1,214 @@
+use crate::audit;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+const MAX_DOCUMENT_BYTES: u64 = 10 * 1024 * 1024;
+const POLL_INTERVAL_SECS: u64 = 60;
+
+const DOCUMENT_TYPES: &[&str] = &["passport", "drivers_license", "national_id", "proof_of_address"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KycStatus {
+    // not_started | pending | verified | rejected
+    status: String,
+    #[serde(default)]
+    level: u8,
+    #[serde(default)]
+    required_documents: Vec<String>,
+    #[serde(default)]
+    rejection_reason: Option<String>,
+}
+
+impl KycStatus {
+    fn is_final(&self) -> bool {
+        self.status == "verified" || self.status == "rejected"
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KycUploadResult {
+    document_id: String,
+    status: String,
+}
+
+#[derive(Default)]
+pub struct KycState {
+    last_status: Mutex<Option<KycStatus>>,
+    polling: AtomicBool,
+}
+
+// Detect the document format from its magic bytes rather than trusting the extension
+pub fn detect_mime(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(&[0x89, b'P', b'N', b'G']) {
+        Some("image/png")
+    } else if bytes.starts_with(b"%PDF") {
+        Some("application/pdf")
+    } else {
+        None
+    }
+}
+
+fn require_https(api_url: &str) -> Result<(), String> {
+    let is_local = api_url.starts_with("http://localhost") || api_url.starts_with("http://127.0.0.1");
+    if api_url.starts_with("https://") || is_local {
+        Ok(())
+    } else {
+        Err("Identity documents can only be uploaded over HTTPS".to_string())
+    }
+}
+
+async fn fetch_status(api_url: &str) -> Result<KycStatus, String> {
+    let client = crate::create_http_client()?;
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+
+    let response = crate::http::send(client.get(format!("{}/api/kyc/status", api_url))
+        .header("Authorization", format!("Bearer {}", token)))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        return Err("Failed to fetch KYC status".to_string());
+    }
+
+    let api_response: crate::ApiResponse<KycStatus> = response.json().await.map_err(|e| e.to_string())?;
+
+    if api_response.success {
+        api_response.data.ok_or_else(|| "No KYC status returned".to_string())
+    } else {
+        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    }
+}
+
+// Record the latest status and notify the frontend when it changed
+fn update_status(app: &AppHandle, status: &KycStatus) {
+    let state = app.state::<KycState>();
+    let changed = match state.last_status.lock() {
+        Ok(mut last) => {
+            let changed = last.as_ref() != Some(status);
+            *last = Some(status.clone());
+            changed
+        }
+        Err(_) => false,
+    };
+
+    if changed {
+        let _ = app.emit_all("kyc_status_changed", status.clone());
+    }
+}
+
+#[tauri::command]
+pub async fn get_kyc_status(app: AppHandle, api_url: String) -> Result<KycStatus, String> {
+    let status = fetch_status(&api_url).await?;
+    update_status(&app, &status);
+    Ok(status)
+}
+
+// Upload an identity document as multipart form data
+#[tauri::command]
+pub async fn upload_kyc_document(
+    app: AppHandle,
+    api_url: String,
+    document_type: String,
+    file_path: String,
+) -> Result<KycUploadResult, String> {
+    let result = request_upload(&api_url, &document_type, &file_path).await;
+    audit::record(&app, "upload_kyc_document", serde_json::json!({ "documentType": document_type }), &result);
+    result
+}
+
+async fn request_upload(api_url: &str, document_type: &str, file_path: &str) -> Result<KycUploadResult, String> {
+    require_https(api_url)?;
+
+    if !DOCUMENT_TYPES.contains(&document_type) {
+        return Err(format!("Unsupported document type: {}", document_type));
+    }
+
+    let path = Path::new(file_path);
+    let size = tokio::fs::metadata(path).await
+        .map_err(|e| format!("Failed to read document: {}", e))?
+        .len();
+    if size == 0 || size > MAX_DOCUMENT_BYTES {
+        return Err(format!(
+            "Document must be between 1 byte and {} MB",
+            MAX_DOCUMENT_BYTES / (1024 * 1024)
+        ));
+    }
+
+    let bytes = tokio::fs::read(path).await
+        .map_err(|e| format!("Failed to read document: {}", e))?;
+    let mime = detect_mime(&bytes)
+        .ok_or_else(|| "Document must be a JPEG, PNG or PDF file".to_string())?;
+
+    let file_name = path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "document".to_string());
+    let part = reqwest::multipart::Part::bytes(bytes)
+        .file_name(file_name)
+        .mime_str(mime)
+        .map_err(|e| e.to_string())?;
+    let form = reqwest::multipart::Form::new()
+        .text("documentType", document_type.to_string())
+        .part("file", part);
+
+    let client = crate::create_http_client()?;
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+
+    let response = crate::http::send(client.post(format!("{}/api/kyc/documents", api_url))
+        .header("Authorization", format!("Bearer {}", token))
+        .multipart(form))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("Failed to upload document: {}", error_text));
+    }
+
+    let api_response: crate::ApiResponse<KycUploadResult> = response.json().await.map_err(|e| e.to_string())?;
+
+    if api_response.success {
+        api_response.data.ok_or_else(|| "No upload result returned".to_string())
+    } else {
+        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    }
+}
+
+// Poll verification status in the background until it reaches a final state.
+// Changes are reported through `kyc_status_changed`.
+#[tauri::command]
+pub async fn start_kyc_status_polling(
+    app: AppHandle,
+    state: State<'_, KycState>,
+    api_url: String,
+) -> Result<(), String> {
+    if state.polling.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match fetch_status(&api_url).await {
+                Ok(status) => {
+                    update_status(&app, &status);
+                    if status.is_final() {
+                        break;
+                    }
+                }
+                Err(e) => eprintln!("KYC status poll failed: {}", e),
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(POLL_INTERVAL_SECS)).await;
+        }
+        app.state::<KycState>().polling.store(false, Ordering::SeqCst);
+    });
+
+    Ok(())
+}
@@ -0,0 +1,191 @@
+// Leaderboards. Each board and period is cached in the kv table. Within the server's
+// own cache window the stored copy is returned as is; after that only entries updated
+// since the last fetch are requested and merged in. When the signed-in player's rank
+// changes a `leaderboard_rank_changed` event is emitted so the client can toast it.
+
+use crate::claims;
+use crate::db::{Database, Db};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+
+const PERIODS: &[&str] = &["all_time", "daily", "weekly", "monthly", "yearly"];
+// Board id -> the backend sort key and the entry field it ranks by
+const BOARDS: &[(&str, &str, &str)] = &[
+    ("winnings", "winnings", "lifetimeWinnings"),
+    ("volume", "handsPlayed", "lifetimeHandsPlayed"),
+    ("win_rate", "winRate", "lifetimeWinRate"),
+];
+
+const FRESH_SECS: i64 = 60;
+const BOARD_SIZE: u32 = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaderboardEntry {
+    player_id: String,
+    username: String,
+    #[serde(default)]
+    rank: u32,
+    // Everything else the backend reports for the player
+    #[serde(flatten)]
+    stats: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Leaderboard {
+    board_id: String,
+    period: String,
+    entries: Vec<LeaderboardEntry>,
+    my_rank: Option<u32>,
+    fetched_at: DateTime<Utc>,
+    #[serde(default)]
+    cached: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RankChanged {
+    board_id: String,
+    period: String,
+    previous_rank: Option<u32>,
+    rank: Option<u32>,
+    moved_up: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct LeaderboardPage {
+    leaderboard: Vec<LeaderboardEntry>,
+    // Set when the server honoured `updatedSince` and only sent changed entries
+    #[serde(default)]
+    delta: bool,
+}
+
+fn cache_key(board_id: &str, period: &str) -> String {
+    format!("leaderboard.{}.{}", board_id, period)
+}
+
+fn load(db: &Database, board_id: &str, period: &str) -> Result<Option<Leaderboard>, String> {
+    match db.get_value(&cache_key(board_id, period))? {
+        Some(data) => serde_json::from_str(&data).map(Some).map_err(|e| format!("Invalid cached leaderboard: {}", e)),
+        None => Ok(None),
+    }
+}
+
+fn save(db: &Database, board: &Leaderboard) -> Result<(), String> {
+    let data = serde_json::to_string(board).map_err(|e| e.to_string())?;
+    db.set_value(&cache_key(&board.board_id, &board.period), &data)
+}
+
+async fn fetch_page(api_url: &str, sort_by: &str, period: &str, since: Option<DateTime<Utc>>) -> Result<LeaderboardPage, String> {
+    let client = crate::create_http_client()?;
+    let mut query = vec![
+        ("period", period.to_string()),
+        ("sortBy", sort_by.to_string()),
+        ("limit", BOARD_SIZE.to_string()),
+    ];
+    if let Some(since) = since {
+        query.push(("updatedSince", since.timestamp_millis().to_string()));
+    }
+    let mut request = client.get(format!("{}/api/leaderboards", api_url)).query(&query);
+    if let Ok(token) = crate::get_token_from_keyring() {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+
+    let response = crate::http::send(request).await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err("Failed to fetch leaderboard".to_string());
+    }
+
+    let api_response: crate::ApiResponse<LeaderboardPage> = response.json().await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    if api_response.success {
+        api_response.data.ok_or_else(|| "No leaderboard returned".to_string())
+    } else {
+        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    }
+}
+
+// Fold changed entries into the cached board and re-rank by the board's value
+fn merge(previous: Vec<LeaderboardEntry>, changed: Vec<LeaderboardEntry>, field: &str) -> Vec<LeaderboardEntry> {
+    let mut by_player: HashMap<String, LeaderboardEntry> =
+        previous.into_iter().map(|entry| (entry.player_id.clone(), entry)).collect();
+    for entry in changed {
+        by_player.insert(entry.player_id.clone(), entry);
+    }
+    let value = |entry: &LeaderboardEntry| entry.stats.get(field).and_then(Value::as_f64).unwrap_or(0.0);
+    let mut entries: Vec<LeaderboardEntry> = by_player.into_values().collect();
+    entries.sort_by(|a, b| value(b).total_cmp(&value(a)).then_with(|| a.username.cmp(&b.username)));
+    entries.truncate(BOARD_SIZE as usize);
+    rank(entries)
+}
+
+fn rank(mut entries: Vec<LeaderboardEntry>) -> Vec<LeaderboardEntry> {
+    for (i, entry) in entries.iter_mut().enumerate() {
+        entry.rank = i as u32 + 1;
+    }
+    entries
+}
+
+#[tauri::command]
+pub async fn get_leaderboard(
+    app: AppHandle,
+    db: Db<'_>,
+    api_url: String,
+    board_id: String,
+    period: Option<String>,
+    force_refresh: Option<bool>,
+) -> Result<Leaderboard, String> {
+    let period = period.unwrap_or_else(|| "all_time".to_string());
+    if !PERIODS.contains(&period.as_str()) {
+        return Err(format!("Unknown leaderboard period: {}", period));
+    }
+    let &(_, sort_by, field) = BOARDS
+        .iter()
+        .find(|(id, _, _)| *id == board_id)
+        .ok_or_else(|| format!("Unknown leaderboard: {}", board_id))?;
+
+    let cached = load(&db, &board_id, &period)?;
+    let force = force_refresh.unwrap_or(false);
+    if let Some(board) = &cached {
+        if !force && (Utc::now() - board.fetched_at).num_seconds() < FRESH_SECS {
+            return Ok(Leaderboard { cached: true, ..board.clone() });
+        }
+    }
+
+    let since = cached.as_ref().filter(|_| !force).map(|board| board.fetched_at);
+    let fetched_at = Utc::now();
+    let page = fetch_page(&api_url, sort_by, &period, since).await?;
+    let entries = match (&cached, page.delta) {
+        (Some(board), true) => merge(board.entries.clone(), page.leaderboard, field),
+        _ => rank(page.leaderboard),
+    };
+
+    let my_id = claims::current().ok().map(|claims| claims.user_id);
+    let my_rank = my_id
+        .as_deref()
+        .and_then(|id| entries.iter().find(|entry| entry.player_id == id))
+        .map(|entry| entry.rank);
+    let board = Leaderboard { board_id, period, entries, my_rank, fetched_at, cached: false };
+    save(&db, &board)?;
+
+    if let Some(previous) = cached.filter(|previous| previous.my_rank != my_rank && my_id.is_some()) {
+        let moved_up = match (previous.my_rank, my_rank) {
+            (Some(before), Some(now)) => now < before,
+            (None, Some(_)) => true,
+            _ => false,
+        };
+        let _ = app.emit_all("leaderboard_rank_changed", RankChanged {
+            board_id: board.board_id.clone(),
+            period: board.period.clone(),
+            previous_rank: previous.my_rank,
+            rank: my_rank,
+            moved_up,
+        });
+    }
+    Ok(board)
+}
@@ -0,0 +1,268 @@
+// Leak finder over local hand history. Counts how often the hero limps, folds to
+// 3-bets and skips continuation bets, and flags rates outside configurable limits.
+// Reports are cached per filter until the hand table changes.
+
+use crate::compute::{self, Priority};
+use crate::db::{self, Db};
+use crate::history::{self, HandFilter, HandRecord};
+use crate::memory::{approx_size, CacheUsage, MemoryCache};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, State};
+
+// Hand ids kept per stat so the UI can open examples in the replayer
+const EXAMPLES: usize = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LeakThresholds {
+    max_limp_rate: f64,
+    max_fold_to_three_bet: f64,
+    min_cbet_rate: f64,
+    // Opportunities needed before a rate is judged at all
+    min_sample: u32,
+}
+
+impl Default for LeakThresholds {
+    fn default() -> Self {
+        Self { max_limp_rate: 0.1, max_fold_to_three_bet: 0.6, min_cbet_rate: 0.5, min_sample: 20 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeakStat {
+    id: String,
+    label: String,
+    opportunities: u32,
+    count: u32,
+    rate: Option<f64>,
+    threshold: f64,
+    // "max" when the rate should stay below the threshold, "min" when above
+    direction: String,
+    leak: bool,
+    example_hand_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeakReport {
+    hands_analyzed: usize,
+    generated_at: DateTime<Utc>,
+    stats: Vec<LeakStat>,
+    leaks: usize,
+}
+
+struct LeakCache {
+    fingerprint: (i64, i64),
+    reports: HashMap<String, LeakReport>,
+}
+
+#[derive(Default)]
+pub struct LeakState {
+    cache: Mutex<Option<LeakCache>>,
+}
+
+impl MemoryCache for LeakState {
+    fn usage(&self) -> CacheUsage {
+        let Ok(cache) = self.cache.lock() else { return CacheUsage::default() };
+        let reports = cache.as_ref().map(|c| &c.reports);
+        CacheUsage {
+            entries: reports.map_or(0, HashMap::len),
+            bytes: reports.map_or(0, |reports| reports.iter().map(|(key, report)| key.len() + approx_size(report)).sum()),
+        }
+    }
+
+    fn trim_to(&self, max_bytes: usize) {
+        let Ok(mut cache) = self.cache.lock() else { return };
+        let Some(reports) = cache.as_mut().map(|c| &mut c.reports) else { return };
+        let mut total: usize = reports.iter().map(|(key, report)| key.len() + approx_size(report)).sum();
+        let mut oldest: Vec<(DateTime<Utc>, String)> = reports.iter().map(|(key, r)| (r.generated_at, key.clone())).collect();
+        oldest.sort();
+        for (_, key) in oldest {
+            if total <= max_bytes {
+                break;
+            }
+            if let Some(report) = reports.remove(&key) {
+                total = total.saturating_sub(key.len() + approx_size(&report));
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Counter {
+    opportunities: u32,
+    count: u32,
+    examples: Vec<String>,
+}
+
+impl Counter {
+    fn record(&mut self, hand_id: &str, hit: bool) {
+        self.opportunities += 1;
+        if hit {
+            self.count += 1;
+            if self.examples.len() < EXAMPLES {
+                self.examples.push(hand_id.to_string());
+            }
+        }
+    }
+
+    fn into_stat(self, id: &str, label: &str, threshold: f64, max: bool, min_sample: u32) -> LeakStat {
+        let rate = (self.opportunities > 0).then(|| self.count as f64 / self.opportunities as f64);
+        let leak = self.opportunities >= min_sample
+            && rate.is_some_and(|r| if max { r > threshold } else { r < threshold });
+        LeakStat {
+            id: id.to_string(),
+            label: label.to_string(),
+            opportunities: self.opportunities,
+            count: self.count,
+            rate,
+            threshold,
+            direction: if max { "max" } else { "min" }.to_string(),
+            leak,
+            example_hand_ids: self.examples,
+        }
+    }
+}
+
+pub fn is_preflop(street: &str) -> bool {
+    matches!(street, "pre_flop" | "preflop")
+}
+
+pub fn is_blind(action: &str) -> bool {
+    action.ends_with("_blind") || action == "ante"
+}
+
+#[derive(Default)]
+struct Counters {
+    limp: Counter,
+    fold_to_three_bet: Counter,
+    missed_cbet: Counter,
+}
+
+fn scan_hand(hand: &HandRecord, counters: &mut Counters) {
+    let Some(hero) = hand.hero_id.as_deref() else { return };
+    let hero_is_bb = hand.actions.iter().any(|a| a.player_id == hero && a.action == "big_blind");
+
+    // Preflop: raises are detected from chip totals so shoves count correctly
+    let mut street_bets: HashMap<&str, u32> = HashMap::new();
+    let mut current_bet = 0;
+    let mut raises = 0;
+    let mut last_raiser: Option<&str> = None;
+    let mut hero_acted = false;
+    let mut hero_opened = false;
+    let mut three_bet_answered = false;
+    let mut hero_folded = false;
+
+    for action in hand.actions.iter().filter(|a| is_preflop(&a.street)) {
+        let player = action.player_id.as_str();
+        let bet = street_bets.entry(player).or_insert(0);
+        *bet += action.amount;
+        let raised = *bet > current_bet && !is_blind(&action.action);
+        current_bet = current_bet.max(*bet);
+        if is_blind(&action.action) {
+            continue;
+        }
+
+        if player == hero {
+            if !hero_acted && raises == 0 && !hero_is_bb {
+                counters.limp.record(&hand.id, action.action == "call");
+            }
+            if hero_opened && raises == 2 && !three_bet_answered && last_raiser != Some(hero) {
+                counters.fold_to_three_bet.record(&hand.id, action.action == "fold");
+                three_bet_answered = true;
+            }
+            hero_acted = true;
+            hero_folded |= action.action == "fold";
+        }
+        if raised {
+            raises += 1;
+            last_raiser = Some(player);
+            if player == hero && raises == 1 {
+                hero_opened = true;
+            }
+        }
+    }
+
+    // Flop: a c-bet chance exists when the preflop aggressor is checked to
+    if last_raiser != Some(hero) || hero_folded {
+        return;
+    }
+    for action in hand.actions.iter().filter(|a| a.street == "flop") {
+        if action.player_id == hero {
+            counters.missed_cbet.record(&hand.id, action.action == "check");
+            return;
+        }
+        if action.amount > 0 {
+            return;
+        }
+    }
+}
+
+fn analyze(hands: &[HandRecord], thresholds: &LeakThresholds) -> LeakReport {
+    let mut counters = Counters::default();
+    for hand in hands {
+        scan_hand(hand, &mut counters);
+    }
+
+    let min_sample = thresholds.min_sample;
+    let mut cbet = counters.missed_cbet;
+    // Reported as a c-bet rate; the examples stay the missed ones
+    cbet.count = cbet.opportunities - cbet.count;
+    let stats = vec![
+        counters.limp.into_stat("limp", "Open limps", thresholds.max_limp_rate, true, min_sample),
+        counters.fold_to_three_bet.into_stat(
+            "fold_to_three_bet",
+            "Fold to 3-bet after opening",
+            thresholds.max_fold_to_three_bet,
+            true,
+            min_sample,
+        ),
+        cbet.into_stat("cbet", "Flop continuation bets", thresholds.min_cbet_rate, false, min_sample),
+    ];
+
+    LeakReport {
+        hands_analyzed: hands.len(),
+        generated_at: Utc::now(),
+        leaks: stats.iter().filter(|s| s.leak).count(),
+        stats,
+    }
+}
+
+#[tauri::command]
+pub async fn run_leak_analysis(
+    app: AppHandle,
+    db: Db<'_>,
+    state: State<'_, LeakState>,
+    filters: Option<HandFilter>,
+    thresholds: Option<LeakThresholds>,
+) -> Result<LeakReport, String> {
+    let filters = filters.unwrap_or_default();
+    let thresholds = thresholds.unwrap_or_default();
+    let key = serde_json::to_string(&(&filters, &thresholds)).map_err(|e| format!("Failed to encode filters: {}", e))?;
+
+    let fingerprint = db.with_conn(history::fingerprint)?;
+    {
+        let cache = state.cache.lock().map_err(|_| "Leak cache lock poisoned".to_string())?;
+        if let Some(report) = cache.as_ref().filter(|c| c.fingerprint == fingerprint).and_then(|c| c.reports.get(&key)) {
+            return Ok(report.clone());
+        }
+    }
+
+    let report = compute::run("Leak analysis", Priority::Low, move |_| {
+        let hands = db::get(&app)?.with_conn(|conn| history::filtered_hands(conn, &filters))?;
+        Ok(analyze(&hands, &thresholds))
+    })
+    .await?;
+
+    let mut cache = state.cache.lock().map_err(|_| "Leak cache lock poisoned".to_string())?;
+    let cache = match cache.as_mut() {
+        Some(existing) if existing.fingerprint == fingerprint => existing,
+        _ => cache.insert(LeakCache { fingerprint, reports: HashMap::new() }),
+    };
+    cache.reports.insert(key, report.clone());
+    Ok(report)
+}
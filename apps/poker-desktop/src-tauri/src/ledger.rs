@@ -0,0 +1,535 @@
+// Local money trail. Every buy-in, rebuy and cash-out the client makes is written to
+// a ledger session, with the result of each hand in between, so the expected wallet
+// balance can be worked out without asking the backend. A session opens with the
+// wallet balance before the first buy-in and is reconciled once the player is seated
+// nowhere: wallet transactions of the session are matched to the entries, those the
+// client cannot know of (deposits, transfers, withdrawals, tournament buy-ins) are
+// taken in, and the actual balance is compared to the expected one. A difference is
+// raised as `balance_discrepancy` with the entries that explain it: client entries
+// the backend has no transaction for, and game transactions the client never made.
+// A fast-fold move is written as an entry of its own, so the chips bought in at one
+// pool table are cashed out from the one the player leaves.
+
+use crate::bankroll;
+use crate::db::{self, Database, Db};
+use crate::history::{to_millis, HandRecord};
+use crate::idle::IdleState;
+use crate::profile::BackendProfile;
+use crate::thumbnails::ThumbnailState;
+use crate::wallet::{self, Transaction};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager};
+
+// Wait after the last table is left, so the backend has booked the cash-out
+const RECONCILE_DELAY: std::time::Duration = std::time::Duration::from_secs(15);
+// The client's own transactions may be booked slightly before the session opened, by
+// the backend's clock; anything else from before is in the opening balance already
+const TRANSACTION_SLACK_SECS: i64 = 60;
+const MAX_TRANSACTION_PAGES: usize = 10;
+
+// Entry kinds
+const BUY_IN: &str = "buy_in";
+const REBUY: &str = "rebuy";
+const CASH_OUT: &str = "cash_out";
+// Changes the stack at the table, not the wallet
+const WINNINGS: &str = "winnings";
+// A fast-fold pool moved the stack from `reference` to `table_id`; no money moves
+const MOVE: &str = "move";
+// Wallet transactions of these types are the backend's side of client entries
+const GAME_KINDS: &[&str] = &[BUY_IN, REBUY, CASH_OUT];
+
+// Entry sources
+const CLIENT: &str = "client";
+const BACKEND: &str = "backend";
+// A game transaction with no client entry; shown but not counted
+const UNEXPECTED: &str = "unexpected";
+
+static RECONCILING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LedgerSession {
+    id: String,
+    started_at: DateTime<Utc>,
+    ended_at: Option<DateTime<Utc>>,
+    // None when the balance could not be read at the start
+    opening_balance: Option<i64>,
+    expected_balance: Option<i64>,
+    actual_balance: Option<i64>,
+    discrepancy: Option<i64>,
+    // open | matched | discrepancy | unverified
+    status: String,
+    reconciled_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LedgerEntry {
+    id: i64,
+    session_id: String,
+    kind: String,
+    // Signed, in wallet terms for everything but winnings
+    amount: i64,
+    table_id: Option<String>,
+    // Hand id for winnings, transaction id for what came from the backend
+    reference: Option<String>,
+    source: String,
+    // The wallet transaction matched to a client entry
+    transaction_id: Option<String>,
+    recorded_at: DateTime<Utc>,
+}
+
+impl LedgerEntry {
+    // A buy-in, rebuy or cash-out, or a transaction taken in from the backend
+    fn moves_wallet(&self) -> bool {
+        self.kind != WINNINGS && self.kind != MOVE
+    }
+
+    fn counts_to_wallet(&self) -> bool {
+        self.moves_wallet() && self.source != UNEXPECTED
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LedgerStatement {
+    session: LedgerSession,
+    entries: Vec<LedgerEntry>,
+    // What accounts for a discrepancy, empty when the balance matched
+    contributing: Vec<LedgerEntry>,
+}
+
+fn time(millis: i64) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(millis).single().unwrap_or_else(Utc::now)
+}
+
+fn session_from_row(row: &rusqlite::Row) -> rusqlite::Result<LedgerSession> {
+    let expected_balance: Option<i64> = row.get(4)?;
+    let actual_balance: Option<i64> = row.get(5)?;
+    Ok(LedgerSession {
+        id: row.get(0)?,
+        started_at: time(row.get(1)?),
+        ended_at: row.get::<_, Option<i64>>(2)?.map(time),
+        opening_balance: row.get(3)?,
+        expected_balance,
+        actual_balance,
+        discrepancy: actual_balance.zip(expected_balance).map(|(actual, expected)| actual - expected),
+        status: row.get(6)?,
+        reconciled_at: row.get::<_, Option<i64>>(7)?.map(time),
+    })
+}
+
+fn entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<LedgerEntry> {
+    Ok(LedgerEntry {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        kind: row.get(2)?,
+        amount: row.get(3)?,
+        table_id: row.get(4)?,
+        reference: row.get(5)?,
+        source: row.get(6)?,
+        transaction_id: row.get(7)?,
+        recorded_at: time(row.get(8)?),
+    })
+}
+
+const SESSION_COLUMNS: &str =
+    "id, started_at, ended_at, opening_balance, expected_balance, actual_balance, status, reconciled_at";
+
+fn open_session(conn: &Connection) -> rusqlite::Result<Option<LedgerSession>> {
+    conn.query_row(
+        &format!("SELECT {} FROM ledger_sessions WHERE status = 'open' ORDER BY started_at DESC LIMIT 1", SESSION_COLUMNS),
+        [],
+        session_from_row,
+    )
+    .optional()
+}
+
+fn get_session(conn: &Connection, id: &str) -> rusqlite::Result<Option<LedgerSession>> {
+    conn.query_row(&format!("SELECT {} FROM ledger_sessions WHERE id = ?1", SESSION_COLUMNS), [id], session_from_row)
+        .optional()
+}
+
+fn entries(conn: &Connection, session_id: &str) -> rusqlite::Result<Vec<LedgerEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, session_id, kind, amount, table_id, reference, source, transaction_id, recorded_at
+         FROM ledger_entries WHERE session_id = ?1 ORDER BY recorded_at, id",
+    )?;
+    let rows = stmt.query_map([session_id], entry_from_row)?;
+    rows.collect()
+}
+
+fn create_session(conn: &Connection, opening_balance: Option<i64>) -> rusqlite::Result<String> {
+    let now = Utc::now();
+    let id = format!("session-{}", now.timestamp_millis());
+    conn.execute(
+        "INSERT INTO ledger_sessions (id, started_at, opening_balance, status) VALUES (?1, ?2, ?3, 'open')",
+        params![id, to_millis(&now), opening_balance],
+    )?;
+    Ok(id)
+}
+
+fn insert_entry(
+    conn: &Connection,
+    session_id: &str,
+    kind: &str,
+    amount: i64,
+    table_id: Option<&str>,
+    reference: Option<&str>,
+    source: &str,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT OR IGNORE INTO ledger_entries (session_id, kind, amount, table_id, reference, source, recorded_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![session_id, kind, amount, table_id, reference, source, to_millis(&Utc::now())],
+    )?;
+    Ok(())
+}
+
+// Practice chips never touch the wallet
+fn is_practice(table_id: &str) -> bool {
+    table_id.starts_with("practice-")
+}
+
+// Make sure a session is open before a buy-in, reading the balance it opens with.
+// A session nothing was recorded in yet takes the fresh balance.
+pub async fn before_buy_in(app: &AppHandle, table_id: &str) {
+    if is_practice(table_id) {
+        return;
+    }
+    let Some(db) = app.try_state::<Database>() else { return };
+    let open = match db.with_conn(|conn| {
+        let Some(session) = open_session(conn)? else { return Ok(None) };
+        Ok(Some((session.id.clone(), entries(conn, &session.id)?.is_empty())))
+    }) {
+        Ok(open) => open,
+        Err(e) => return eprintln!("Failed to read the balance ledger: {}", e),
+    };
+    if matches!(open, Some((_, false))) {
+        return;
+    }
+
+    let api_url = app.state::<BackendProfile>().api_url.clone();
+    let balance = match bankroll::fetch_wallet(&api_url).await {
+        Ok(balance) => Some(balance as i64),
+        Err(e) => {
+            eprintln!("Opening ledger session without a balance: {}", e);
+            None
+        }
+    };
+    let result = db.with_conn(|conn| match &open {
+        Some((id, _)) => conn
+            .execute("UPDATE ledger_sessions SET opening_balance = ?2 WHERE id = ?1", params![id, balance])
+            .map(|_| ()),
+        None => create_session(conn, balance).map(|_| ()),
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to open a ledger session: {}", e);
+    }
+}
+
+// Write a client entry to the open session, opening one with no balance if needed
+fn record(db: &Database, kind: &str, amount: i64, table_id: &str, reference: Option<&str>) -> Result<(), String> {
+    if is_practice(table_id) {
+        return Ok(());
+    }
+    db.with_conn(|conn| {
+        let session_id = match open_session(conn)? {
+            Some(session) => session.id,
+            None => create_session(conn, None)?,
+        };
+        insert_entry(conn, &session_id, kind, amount, Some(table_id), reference, CLIENT)
+    })
+}
+
+pub fn record_buy_in(app: &AppHandle, table_id: &str, amount: u32) {
+    let Some(db) = app.try_state::<Database>() else { return };
+    if let Err(e) = record(&db, BUY_IN, -(amount as i64), table_id, None) {
+        eprintln!("Failed to record buy-in in the ledger: {}", e);
+    }
+}
+
+// A fast-fold pool moved the hero from `from` to `to`, taking the stack along
+pub fn table_moved(app: &AppHandle, from: &str, to: &str) {
+    if is_practice(from) {
+        return;
+    }
+    let Some(db) = app.try_state::<Database>() else { return };
+    let result = db.with_conn(|conn| {
+        let Some(session) = open_session(conn)? else { return Ok(()) };
+        insert_entry(conn, &session.id, MOVE, 0, Some(to), Some(from), CLIENT)
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to record a table move in the ledger: {}", e);
+    }
+}
+
+pub fn record_rebuy(app: &AppHandle, table_id: &str, amount: u32) {
+    let Some(db) = app.try_state::<Database>() else { return };
+    if let Err(e) = record(&db, REBUY, -(amount as i64), table_id, None) {
+        eprintln!("Failed to record rebuy in the ledger: {}", e);
+    }
+}
+
+// The hero's result of a stored hand, at a table bought into this session
+pub fn record_hand(db: &Database, hand: &HandRecord) -> Result<(), String> {
+    let Some(hero) = hand.hero_id.as_deref().and_then(|id| hand.players.iter().find(|p| p.player_id == id)) else {
+        return Ok(());
+    };
+    if is_practice(&hand.table_id) || hero.net == 0 {
+        return Ok(());
+    }
+    db.with_conn(|conn| {
+        let Some(session) = open_session(conn)? else { return Ok(()) };
+        insert_entry(conn, &session.id, WINNINGS, hero.net, Some(&hand.table_id), Some(&hand.id), CLIENT)
+    })
+}
+
+// When the open session started, if one is open
+pub fn session_started(db: &Database) -> Result<Option<DateTime<Utc>>, String> {
+    db.with_conn(|conn| Ok(open_session(conn)?.map(|session| session.started_at)))
+}
+
+// How long each of the last `limit` finished sessions ran, most recent first
+pub fn session_lengths(db: &Database, limit: u32) -> Result<Vec<Duration>, String> {
+    db.with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT started_at, ended_at FROM ledger_sessions WHERE ended_at IS NOT NULL
+             ORDER BY started_at DESC LIMIT ?1",
+        )?;
+        let rows = stmt.query_map([limit], |row| Ok(Duration::milliseconds(row.get::<_, i64>(1)? - row.get::<_, i64>(0)?)))?;
+        rows.collect()
+    })
+}
+
+// Rebuys made from `since` on: top-ups, and buy-ins at a table already bought into
+// in the same session
+pub fn rebuys_since(db: &Database, since: DateTime<Utc>) -> Result<u32, String> {
+    db.with_conn(|conn| {
+        conn.query_row(
+            "SELECT COUNT(*) FROM ledger_entries e
+             WHERE e.source = ?1 AND e.recorded_at >= ?2 AND (e.kind = ?3 OR (e.kind = ?4 AND EXISTS (
+                 SELECT 1 FROM ledger_entries p
+                 WHERE p.session_id = e.session_id AND p.table_id = e.table_id AND p.kind = ?4 AND p.id < e.id)))",
+            params![CLIENT, to_millis(&since), REBUY, BUY_IN],
+            |row| row.get(0),
+        )
+    })
+}
+
+// `table_id` and every table the stack there was moved from
+fn moved_from<'a>(entries: &'a [LedgerEntry], table_id: &'a str) -> HashSet<&'a str> {
+    let mut tables = HashSet::from([table_id]);
+    loop {
+        let before = tables.len();
+        for entry in entries.iter().filter(|e| e.kind == MOVE) {
+            if let (Some(to), Some(from)) = (entry.table_id.as_deref(), entry.reference.as_deref()) {
+                if tables.contains(to) {
+                    tables.insert(from);
+                }
+            }
+        }
+        if tables.len() == before {
+            return tables;
+        }
+    }
+}
+
+// Chips the hero should have at `tables` by the ledger: what was bought in, plus
+// winnings, less what was already cashed out
+fn ledger_stack(entries: &[LedgerEntry], tables: &HashSet<&str>) -> i64 {
+    entries
+        .iter()
+        .filter(|e| e.source == CLIENT && e.table_id.as_deref().is_some_and(|id| tables.contains(id)))
+        .map(|e| if e.kind == WINNINGS { e.amount } else { -e.amount })
+        .sum()
+}
+
+// The hero stood up at `table_id`: cash out the stack, and reconcile the session once
+// no seat is left
+pub fn table_left(app: &AppHandle, table_id: &str) {
+    let Some(db) = app.try_state::<Database>() else { return };
+    let shown = app
+        .state::<ThumbnailState>()
+        .hero_stacks()
+        .ok()
+        .and_then(|stacks| stacks.into_iter().find(|(id, _, _)| id == table_id))
+        .map(|(_, chips, _)| chips as i64);
+    let result = db.with_conn(|conn| {
+        let Some(session) = open_session(conn)? else { return Ok(()) };
+        let entries = entries(conn, &session.id)?;
+        let tables = moved_from(&entries, table_id);
+        let bought_in = entries.iter().any(|e| e.kind == BUY_IN && e.table_id.as_deref().is_some_and(|id| tables.contains(id)));
+        if !bought_in {
+            return Ok(());
+        }
+        let stack = shown.unwrap_or_else(|| ledger_stack(&entries, &tables));
+        if stack > 0 {
+            insert_entry(conn, &session.id, CASH_OUT, stack, Some(table_id), None, CLIENT)?;
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to record cash-out in the ledger: {}", e);
+    }
+
+    if app.state::<IdleState>().seated_tables().is_empty() {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(RECONCILE_DELAY).await;
+            // Sat down again meanwhile
+            if !app.state::<IdleState>().seated_tables().is_empty() {
+                return;
+            }
+            let api_url = app.state::<BackendProfile>().api_url.clone();
+            if let Err(e) = reconcile(&app, &api_url).await {
+                eprintln!("Balance reconciliation failed: {}", e);
+            }
+        });
+    }
+}
+
+// Pair client entries with wallet transactions; the transactions left over are
+// returned
+fn match_transactions(conn: &Connection, entries: &[LedgerEntry], transactions: Vec<Transaction>) -> rusqlite::Result<Vec<Transaction>> {
+    let mut left = transactions;
+    for entry in entries.iter().filter(|e| e.source == CLIENT && e.moves_wallet() && e.transaction_id.is_none()) {
+        let found = left.iter().position(|t| {
+            let kind_matches = t.kind == entry.kind || (entry.kind == REBUY && t.kind == BUY_IN);
+            kind_matches && t.amount == entry.amount && t.table_id().is_none_or(|id| Some(id) == entry.table_id.as_deref())
+        });
+        if let Some(index) = found {
+            let transaction = left.remove(index);
+            conn.execute("UPDATE ledger_entries SET transaction_id = ?2 WHERE id = ?1", params![entry.id, transaction.id])?;
+        }
+    }
+    Ok(left)
+}
+
+fn contributing(entries: &[LedgerEntry]) -> Vec<LedgerEntry> {
+    entries
+        .iter()
+        .filter(|e| e.source == UNEXPECTED || (e.source == CLIENT && e.moves_wallet() && e.transaction_id.is_none()))
+        .cloned()
+        .collect()
+}
+
+fn statement(conn: &Connection, session: LedgerSession) -> rusqlite::Result<LedgerStatement> {
+    let entries = entries(conn, &session.id)?;
+    let contributing = if session.discrepancy.unwrap_or(0) != 0 { contributing(&entries) } else { Vec::new() };
+    Ok(LedgerStatement { session, entries, contributing })
+}
+
+// Close the open session and compare it with the backend. None when no session is open.
+async fn reconcile(app: &AppHandle, api_url: &str) -> Result<Option<LedgerStatement>, String> {
+    if RECONCILING.swap(true, Ordering::SeqCst) {
+        return Err("A reconciliation is already running".to_string());
+    }
+    let result = run_reconcile(app, api_url).await;
+    RECONCILING.store(false, Ordering::SeqCst);
+    let statement = result?;
+
+    if let Some(statement) = &statement {
+        let _ = app.emit_all("ledger_reconciled", statement.session.clone());
+        if let Some(discrepancy) = statement.session.discrepancy.filter(|d| *d != 0) {
+            let _ = app.emit_all("balance_discrepancy", json!({
+                "session": statement.session,
+                "discrepancy": discrepancy,
+                "entries": statement.contributing,
+            }));
+        }
+    }
+    Ok(statement)
+}
+
+async fn run_reconcile(app: &AppHandle, api_url: &str) -> Result<Option<LedgerStatement>, String> {
+    let db = db::get(app)?;
+    let Some(session) = db.with_conn(open_session)? else { return Ok(None) };
+
+    let actual = bankroll::fetch_wallet(api_url).await? as i64;
+    let since = session.started_at - Duration::seconds(TRANSACTION_SLACK_SECS);
+    let transactions = wallet::fetch_since(api_url, since, MAX_TRANSACTION_PAGES).await?;
+
+    db.with_conn(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        let left = match_transactions(&tx, &entries(&tx, &session.id)?, transactions)?;
+        let opened = |transaction: &&Transaction| {
+            DateTime::parse_from_rfc3339(&transaction.created_at).map_or(true, |at| at >= session.started_at)
+        };
+        for transaction in left.iter().filter(opened) {
+            let source = if GAME_KINDS.contains(&transaction.kind.as_str()) { UNEXPECTED } else { BACKEND };
+            insert_entry(
+                &tx,
+                &session.id,
+                &transaction.kind,
+                transaction.amount,
+                transaction.table_id(),
+                Some(&transaction.id),
+                source,
+            )?;
+        }
+
+        let entries = entries(&tx, &session.id)?;
+        let expected = session
+            .opening_balance
+            .map(|opening| opening + entries.iter().filter(|e| e.counts_to_wallet()).map(|e| e.amount).sum::<i64>());
+        let status = match expected {
+            None => "unverified",
+            Some(expected) if expected == actual => "matched",
+            Some(_) => "discrepancy",
+        };
+        let now = to_millis(&Utc::now());
+        tx.execute(
+            "UPDATE ledger_sessions SET ended_at = COALESCE(ended_at, ?2), expected_balance = ?3, actual_balance = ?4,
+                status = ?5, reconciled_at = ?2
+             WHERE id = ?1",
+            params![session.id, now, expected, actual, status],
+        )?;
+        tx.commit()?;
+
+        let session = get_session(conn, &session.id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+        statement(conn, session).map(Some)
+    })
+}
+
+// The ledger of `session_id`, or of the latest session
+#[tauri::command]
+pub async fn get_ledger(db: Db<'_>, session_id: Option<String>) -> Result<Option<LedgerStatement>, String> {
+    db.with_conn(|conn| {
+        let session = match session_id {
+            Some(id) => get_session(conn, &id)?,
+            None => conn
+                .query_row(
+                    &format!("SELECT {} FROM ledger_sessions ORDER BY started_at DESC LIMIT 1", SESSION_COLUMNS),
+                    [],
+                    session_from_row,
+                )
+                .optional()?,
+        };
+        session.map(|session| statement(conn, session)).transpose()
+    })
+}
+
+// Sessions, most recent first
+#[tauri::command]
+pub async fn list_ledger_sessions(db: Db<'_>, limit: Option<u32>) -> Result<Vec<LedgerSession>, String> {
+    db.with_conn(|conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM ledger_sessions ORDER BY started_at DESC LIMIT ?1",
+            SESSION_COLUMNS
+        ))?;
+        let rows = stmt.query_map([limit.unwrap_or(50)], session_from_row)?;
+        rows.collect()
+    })
+}
+
+// Reconcile the open session now rather than when the last table is left
+#[tauri::command]
+pub async fn reconcile_balance(app: AppHandle, api_url: String) -> Result<Option<LedgerStatement>, String> {
+    reconcile(&app, &api_url).await
+}
@@ -0,0 +1,194 @@
+// Session counters per player, built only from the table event stream the views
+// forward while the tables are open: hands seen, how often each player put money in
+// voluntarily preflop and how often they raised. Nothing is read from or written to
+// the database, so the numbers are there from the first hand at a new table and go
+// away with the session.
+
+use crate::leaks::{is_blind, is_preflop};
+use crate::memory::{self, approx_size, CacheUsage, MemoryCache};
+use crate::table_state::TableMirror;
+use crate::ws::WsMessage;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::State;
+
+const MAX_TABLES: usize = 24;
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Counters {
+    username: String,
+    hands: u32,
+    vpip_hands: u32,
+    pfr_hands: u32,
+    // Every preflop bet or raise, re-raises included
+    preflop_raises: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerLiveStats {
+    player_id: String,
+    username: String,
+    hands: u32,
+    vpip_hands: u32,
+    vpip: f64,
+    pfr_hands: u32,
+    pfr: f64,
+    preflop_raises: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveStats {
+    table_id: String,
+    hands_observed: u32,
+    // Most hands seen first
+    players: Vec<PlayerLiveStats>,
+}
+
+#[derive(Default)]
+struct LiveTable {
+    mirror: TableMirror,
+    used: Option<Instant>,
+    hands: u32,
+    players: HashMap<String, Counters>,
+    // Who has already counted this hand as VPIP or PFR
+    vpip: HashSet<String>,
+    pfr: HashSet<String>,
+}
+
+impl LiveTable {
+    fn start_hand(&mut self) {
+        self.hands += 1;
+        self.vpip.clear();
+        self.pfr.clear();
+        for player in &self.mirror.players {
+            let counters = self.players.entry(player.id.clone()).or_default();
+            counters.username = player.username.clone();
+            counters.hands += 1;
+        }
+    }
+
+    fn action(&mut self, player_id: &str, action: &str) {
+        if !is_preflop(&self.mirror.phase) || is_blind(action) || matches!(action, "fold" | "check") {
+            return;
+        }
+        let Some(counters) = self.players.get_mut(player_id) else { return };
+        if self.vpip.insert(player_id.to_string()) {
+            counters.vpip_hands += 1;
+        }
+        if matches!(action, "bet" | "raise" | "all_in") {
+            counters.preflop_raises += 1;
+            if self.pfr.insert(player_id.to_string()) {
+                counters.pfr_hands += 1;
+            }
+        }
+    }
+}
+
+fn table_size(table: &LiveTable) -> usize {
+    approx_size(&table.mirror) + approx_size(&table.players)
+}
+
+fn rate(count: u32, hands: u32) -> f64 {
+    if hands == 0 { 0.0 } else { count as f64 / hands as f64 }
+}
+
+#[derive(Default)]
+pub struct LiveStatsState {
+    tables: Mutex<HashMap<String, LiveTable>>,
+}
+
+impl LiveStatsState {
+    // Hands and VPIP hands of one player over every open table
+    pub fn totals(&self, player_id: &str) -> (u32, u32) {
+        let Ok(tables) = self.tables.lock() else { return (0, 0) };
+        tables
+            .values()
+            .filter_map(|table| table.players.get(player_id))
+            .fold((0, 0), |(hands, vpip), c| (hands + c.hands, vpip + c.vpip_hands))
+    }
+}
+
+impl MemoryCache for LiveStatsState {
+    fn usage(&self) -> CacheUsage {
+        let Ok(tables) = self.tables.lock() else { return CacheUsage::default() };
+        CacheUsage { entries: tables.len(), bytes: tables.values().map(table_size).sum() }
+    }
+
+    fn trim_to(&self, max_bytes: usize) {
+        if let Ok(mut tables) = self.tables.lock() {
+            memory::trim_oldest(&mut tables, max_bytes, table_size, |t| t.used.unwrap_or_else(Instant::now));
+        }
+    }
+}
+
+// Feed one frame received at `table_id`
+#[tauri::command]
+pub async fn record_live_event(
+    state: State<'_, LiveStatsState>,
+    table_id: String,
+    message: WsMessage,
+) -> Result<(), String> {
+    let mut tables = state.tables.lock().map_err(|_| "Live stats lock poisoned".to_string())?;
+    if !tables.contains_key(&table_id) && tables.len() >= MAX_TABLES {
+        if let Some(stalest) = tables.iter().min_by_key(|(_, t)| t.used).map(|(id, _)| id.clone()) {
+            tables.remove(&stalest);
+        }
+    }
+    let table = tables.entry(table_id).or_default();
+    table.used = Some(Instant::now());
+
+    // Actions are counted against the street they were made on, before the mirror moves
+    if message.kind == "player_action" {
+        let player_id = message.payload["playerId"].as_str().unwrap_or_default();
+        let action = message.payload["action"].as_str().unwrap_or_default();
+        table.action(player_id, action);
+    }
+    let previous_hand = table.mirror.hand_number;
+    table.mirror.apply(&message)?;
+    let new_hand = match message.kind.as_str() {
+        "hand_started" => true,
+        // Joined mid-session: a snapshot with a new hand number also starts one
+        "game_update" => table.mirror.hand_number != previous_hand && is_preflop(&table.mirror.phase),
+        _ => false,
+    };
+    if new_hand {
+        table.start_hand();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_live_stats(state: State<'_, LiveStatsState>, table_id: String) -> Result<LiveStats, String> {
+    let tables = state.tables.lock().map_err(|_| "Live stats lock poisoned".to_string())?;
+    let Some(table) = tables.get(&table_id) else {
+        return Ok(LiveStats { table_id, hands_observed: 0, players: Vec::new() });
+    };
+    let mut players: Vec<PlayerLiveStats> = table
+        .players
+        .iter()
+        .map(|(id, c)| PlayerLiveStats {
+            player_id: id.clone(),
+            username: c.username.clone(),
+            hands: c.hands,
+            vpip_hands: c.vpip_hands,
+            vpip: rate(c.vpip_hands, c.hands),
+            pfr_hands: c.pfr_hands,
+            pfr: rate(c.pfr_hands, c.hands),
+            preflop_raises: c.preflop_raises,
+        })
+        .collect();
+    players.sort_by(|a, b| b.hands.cmp(&a.hands).then_with(|| a.username.cmp(&b.username)));
+    Ok(LiveStats { table_id, hands_observed: table.hands, players })
+}
+
+// Forget a table's counters once its view closes
+#[tauri::command]
+pub async fn clear_live_stats(state: State<'_, LiveStatsState>, table_id: String) -> Result<(), String> {
+    state.tables.lock().map_err(|_| "Live stats lock poisoned".to_string())?.remove(&table_id);
+    Ok(())
+}
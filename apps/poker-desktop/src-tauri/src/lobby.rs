@@ -0,0 +1,149 @@
+// Local lobby preferences: favorite tables, per-table nicknames and the tables joined
+// most recently. Stored in the kv table and applied to the backend table list so
+// favorites sort to the top.
+
+use crate::db::{Database, Db};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const KEY_PREFS: &str = "lobby.prefs";
+const KEY_SNAPSHOT: &str = "lobby.snapshot";
+const MAX_RECENT: usize = 20;
+const MAX_ALIAS_LEN: usize = 40;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentTable {
+    table_id: String,
+    // Last name seen for the table in the lobby, if any
+    name: Option<String>,
+    last_joined_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LobbyPrefs {
+    favorites: Vec<String>,
+    aliases: HashMap<String, String>,
+    // Most recent first
+    recent: Vec<RecentTable>,
+}
+
+impl LobbyPrefs {
+    pub fn is_favorite(&self, table_id: &str) -> bool {
+        self.favorites.iter().any(|id| id == table_id)
+    }
+
+    pub fn alias(&self, table_id: &str) -> Option<String> {
+        self.aliases.get(table_id).cloned()
+    }
+
+    // Remember the current name of a recent table; returns true when it changed
+    pub fn note_name(&mut self, table_id: &str, name: &str) -> bool {
+        match self.recent.iter_mut().find(|r| r.table_id == table_id) {
+            Some(recent) if recent.name.as_deref() != Some(name) => {
+                recent.name = Some(name.to_string());
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+pub fn load(db: &Database) -> Result<LobbyPrefs, String> {
+    match db.get_value(KEY_PREFS)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid lobby preferences: {}", e)),
+        None => Ok(LobbyPrefs::default()),
+    }
+}
+
+pub fn save(db: &Database, prefs: &LobbyPrefs) -> Result<(), String> {
+    let data = serde_json::to_string(prefs).map_err(|e| e.to_string())?;
+    db.set_value(KEY_PREFS, &data)
+}
+
+// Last complete table list fetched from the backend
+pub fn save_snapshot<T: Serialize>(db: &Database, tables: &[T]) -> Result<(), String> {
+    let data = serde_json::to_string(tables).map_err(|e| e.to_string())?;
+    db.set_value(KEY_SNAPSHOT, &data)
+}
+
+pub fn load_snapshot<T: DeserializeOwned>(db: &Database) -> Result<Option<Vec<T>>, String> {
+    match db.get_value(KEY_SNAPSHOT)? {
+        Some(data) => serde_json::from_str(&data).map(Some).map_err(|e| format!("Invalid lobby snapshot: {}", e)),
+        None => Ok(None),
+    }
+}
+
+// Move the table to the front of the recent list
+pub fn record_join(db: &Database, table_id: &str) -> Result<(), String> {
+    let mut prefs = load(db)?;
+    let name = prefs.recent.iter().find(|r| r.table_id == table_id).and_then(|r| r.name.clone());
+    prefs.recent.retain(|r| r.table_id != table_id);
+    prefs.recent.insert(0, RecentTable { table_id: table_id.to_string(), name, last_joined_at: Utc::now() });
+    prefs.recent.truncate(MAX_RECENT);
+    save(db, &prefs)
+}
+
+#[tauri::command]
+pub async fn get_lobby_prefs(db: Db<'_>) -> Result<LobbyPrefs, String> {
+    load(&db)
+}
+
+#[tauri::command]
+pub async fn favorite_table(db: Db<'_>, table_id: String, favorite: bool) -> Result<LobbyPrefs, String> {
+    let mut prefs = load(&db)?;
+    prefs.favorites.retain(|id| *id != table_id);
+    if favorite {
+        prefs.favorites.push(table_id);
+    }
+    save(&db, &prefs)?;
+    Ok(prefs)
+}
+
+// Set or clear (empty or missing alias) a table's nickname
+#[tauri::command]
+pub async fn set_table_alias(
+    db: Db<'_>,
+    table_id: String,
+    alias: Option<String>,
+) -> Result<LobbyPrefs, String> {
+    let alias = alias.map(|a| a.trim().to_string()).filter(|a| !a.is_empty());
+    if alias.as_ref().is_some_and(|a| a.chars().count() > MAX_ALIAS_LEN) {
+        return Err(format!("Nicknames are limited to {} characters", MAX_ALIAS_LEN));
+    }
+
+    let mut prefs = load(&db)?;
+    match alias {
+        Some(alias) => prefs.aliases.insert(table_id, alias),
+        None => prefs.aliases.remove(&table_id),
+    };
+    save(&db, &prefs)?;
+    Ok(prefs)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentTableView {
+    #[serde(flatten)]
+    table: RecentTable,
+    alias: Option<String>,
+    favorite: bool,
+}
+
+#[tauri::command]
+pub async fn get_recent_tables(db: Db<'_>, limit: Option<usize>) -> Result<Vec<RecentTableView>, String> {
+    let prefs = load(&db)?;
+    Ok(prefs
+        .recent
+        .iter()
+        .take(limit.unwrap_or(MAX_RECENT))
+        .map(|table| RecentTableView {
+            alias: prefs.alias(&table.table_id),
+            favorite: prefs.is_favorite(&table.table_id),
+            table: table.clone(),
+        })
+        .collect())
+}
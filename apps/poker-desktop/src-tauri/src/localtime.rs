@@ -0,0 +1,199 @@
+// Rendering schedule times for the player. Everything the backend sends is UTC; the
+// tournament and reminder commands convert it here so the frontend shows ready-made
+// strings instead of parsing timestamps itself. The time zone is the system's unless
+// one is set in settings, with daylight saving applied from the IANA database. The
+// locale (from settings, else LC_ALL / LC_TIME / LANG) only decides the clock and
+// the order of day and month; names stay English, like the rest of the client.
+
+use crate::db::{Database, Db};
+use chrono::{DateTime, Utc};
+use jiff::tz::TimeZone;
+use jiff::{Timestamp, Zoned};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+const KEY_SETTINGS: &str = "display.time";
+const DEFAULT_LOCALE: &str = "en-US";
+// Regions that read a 12-hour clock
+const TWELVE_HOUR_REGIONS: &[&str] = &["US", "CA", "AU", "NZ", "PH", "IN", "PK", "EG", "SA"];
+// Languages writing year, month, day
+const YEAR_FIRST_LANGUAGES: &[&str] = &["ja", "zh", "ko", "hu", "lt"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TimeSettings {
+    // IANA name such as Europe/Berlin; the system zone when unset
+    time_zone: Option<String>,
+    // BCP 47 tag such as en-GB; taken from the environment when unset
+    locale: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeSettingsView {
+    #[serde(flatten)]
+    settings: TimeSettings,
+    // What is actually used
+    time_zone_in_use: String,
+    locale_in_use: String,
+}
+
+// A UTC instant as the player should see it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalTime {
+    utc: DateTime<Utc>,
+    // RFC 3339 with the local offset
+    local: String,
+    date: String,
+    pub time: String,
+    // "Today 19:30", "Tomorrow 08:00", "Wed 21:00" within a week, else date and time
+    label: String,
+    zone: String,
+    offset_minutes: i32,
+}
+
+struct Style {
+    twelve_hour: bool,
+    date_format: &'static str,
+}
+
+static SETTINGS: Mutex<Option<TimeSettings>> = Mutex::new(None);
+
+fn settings() -> TimeSettings {
+    SETTINGS.lock().ok().and_then(|s| s.clone()).unwrap_or_default()
+}
+
+// Pick up the stored settings once the database is open
+pub fn load(db: &Database) -> Result<(), String> {
+    let stored = match db.get_value(KEY_SETTINGS)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid time settings: {}", e))?,
+        None => TimeSettings::default(),
+    };
+    if let Ok(mut settings) = SETTINGS.lock() {
+        *settings = Some(stored);
+    }
+    Ok(())
+}
+
+fn system_locale() -> String {
+    ["LC_ALL", "LC_TIME", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty() && value != "C" && value != "POSIX")
+        // en_GB.UTF-8 -> en-GB
+        .map(|value| value.split(['.', '@']).next().unwrap_or_default().replace('_', "-"))
+        .filter(|tag| !tag.is_empty())
+        .unwrap_or_else(|| DEFAULT_LOCALE.to_string())
+}
+
+fn time_zone(settings: &TimeSettings) -> TimeZone {
+    match settings.time_zone.as_deref().map(TimeZone::get) {
+        Some(Ok(zone)) => zone,
+        Some(Err(e)) => {
+            eprintln!("Unknown time zone in settings, using the system zone: {}", e);
+            TimeZone::system()
+        }
+        None => TimeZone::system(),
+    }
+}
+
+fn locale(settings: &TimeSettings) -> String {
+    settings.locale.clone().unwrap_or_else(system_locale)
+}
+
+fn style(locale: &str) -> Style {
+    let mut parts = locale.split('-');
+    let language = parts.next().unwrap_or_default().to_lowercase();
+    let region = parts.find(|p| p.len() == 2).map(|p| p.to_uppercase());
+    let region = region.as_deref().unwrap_or(if language == "en" { "US" } else { "" });
+
+    let twelve_hour = TWELVE_HOUR_REGIONS.contains(&region) && !(region == "CA" && language == "fr");
+    let date_format = if YEAR_FIRST_LANGUAGES.contains(&language.as_str()) {
+        "%Y-%m-%d"
+    } else if region == "US" || region == "PH" {
+        "%b %-d, %Y"
+    } else if ["de", "ru", "pl", "cs", "fi", "nb", "da"].contains(&language.as_str()) {
+        "%d.%m.%Y"
+    } else {
+        "%-d %b %Y"
+    };
+    Style { twelve_hour, date_format }
+}
+
+fn to_zoned(at: DateTime<Utc>, zone: &TimeZone) -> Result<Zoned, String> {
+    Timestamp::from_millisecond(at.timestamp_millis())
+        .map(|ts| ts.to_zoned(zone.clone()))
+        .map_err(|e| format!("Time out of range: {}", e))
+}
+
+// Render `at` relative to `now` with the current settings
+pub fn render(at: DateTime<Utc>, now: DateTime<Utc>) -> Result<LocalTime, String> {
+    let settings = settings();
+    let zone = time_zone(&settings);
+    let style = style(&locale(&settings));
+    let zoned = to_zoned(at, &zone)?;
+    let today = to_zoned(now, &zone)?.date();
+
+    let time = zoned.strftime(if style.twelve_hour { "%-I:%M %p" } else { "%H:%M" }).to_string();
+    let date = zoned.strftime(style.date_format).to_string();
+    let days = today.until(zoned.date()).map(|span| span.get_days()).unwrap_or(i32::MAX);
+    let label = match days {
+        0 => format!("Today {}", time),
+        1 => format!("Tomorrow {}", time),
+        -1 => format!("Yesterday {}", time),
+        2..=6 => format!("{} {}", zoned.strftime("%a"), time),
+        _ => format!("{} {}", date, time),
+    };
+
+    Ok(LocalTime {
+        utc: at,
+        local: zoned.timestamp().display_with_offset(zoned.offset()).to_string(),
+        date,
+        time,
+        label,
+        zone: zoned.strftime("%Z").to_string(),
+        offset_minutes: zoned.offset().seconds() / 60,
+    })
+}
+
+fn view(settings: TimeSettings) -> TimeSettingsView {
+    let zone = time_zone(&settings);
+    TimeSettingsView {
+        time_zone_in_use: zone.iana_name().unwrap_or("system").to_string(),
+        locale_in_use: locale(&settings),
+        settings,
+    }
+}
+
+#[tauri::command]
+pub async fn get_time_settings(db: Db<'_>) -> Result<TimeSettingsView, String> {
+    load(&db)?;
+    Ok(view(settings()))
+}
+
+// Applies to every time rendered from now on
+#[tauri::command]
+pub async fn set_time_settings(db: Db<'_>, settings: TimeSettings) -> Result<TimeSettingsView, String> {
+    let settings = TimeSettings {
+        time_zone: settings.time_zone.filter(|z| !z.trim().is_empty()),
+        locale: settings.locale.filter(|l| !l.trim().is_empty()),
+    };
+    if let Some(name) = &settings.time_zone {
+        TimeZone::get(name).map_err(|_| format!("Unknown time zone {}", name))?;
+    }
+    let data = serde_json::to_string(&settings).map_err(|e| e.to_string())?;
+    db.set_value(KEY_SETTINGS, &data)?;
+    if let Ok(mut current) = SETTINGS.lock() {
+        *current = Some(settings.clone());
+    }
+    Ok(view(settings))
+}
+
+// IANA zone names for the override picker
+#[tauri::command]
+pub async fn list_time_zones() -> Result<Vec<String>, String> {
+    let mut zones: Vec<String> = jiff::tz::db().available().map(|name| name.as_str().to_string()).collect();
+    zones.sort();
+    Ok(zones)
+}
@@ -0,0 +1,202 @@
+// Loyalty points and rakeback. The backend settles points from rake periodically; in
+// between, the hero's share of the rake in each saved hand is added locally so the
+// points counter moves after every hand. Rake is attributed in proportion to what
+// the hero put into the pot.
+
+use crate::audit;
+use crate::db::{Database, Db};
+use crate::ev::committed;
+use crate::history::{filtered_hands, HandFilter, HandRecord};
+use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Manager};
+
+const KEY_STATUS: &str = "loyalty.status";
+const PERIODS: &[&str] = &["day", "week", "month"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoyaltyStatus {
+    tier: String,
+    points: f64,
+    // Points credited per chip of rake paid
+    points_per_rake: f64,
+    #[serde(default)]
+    next_tier: Option<String>,
+    #[serde(default)]
+    points_to_next_tier: Option<f64>,
+    // Rake up to this time is already included in `points`
+    settled_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoyaltyView {
+    #[serde(flatten)]
+    status: LoyaltyStatus,
+    // Rake and points from hands played since the last settlement
+    pending_rake: f64,
+    pending_points: f64,
+    // Served from the last known status because the backend could not be reached
+    offline: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RemoteRakeback {
+    rake_paid: f64,
+    rakeback_percent: f64,
+    rakeback_earned: f64,
+    #[serde(default)]
+    claimable: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RakebackSummary {
+    period: String,
+    from: DateTime<Utc>,
+    #[serde(flatten)]
+    remote: RemoteRakeback,
+    // Hero rake in the period from local hand history
+    local_rake_paid: f64,
+    local_hands: u32,
+}
+
+// The hero's share of the hand's rake
+pub fn hero_rake(hand: &HandRecord) -> f64 {
+    let Some(hero) = hand.hero_id.as_deref() else { return 0.0 };
+    if hand.rake == 0 {
+        return 0.0;
+    }
+    let committed = committed(hand);
+    let total: u32 = committed.values().sum();
+    let paid = committed.get(hero).copied().unwrap_or(0);
+    if total == 0 {
+        0.0
+    } else {
+        hand.rake as f64 * paid as f64 / total as f64
+    }
+}
+
+fn cached_status(db: &Database) -> Result<Option<LoyaltyStatus>, String> {
+    match db.get_value(KEY_STATUS)? {
+        Some(data) => serde_json::from_str(&data).map(Some).map_err(|e| format!("Invalid loyalty status: {}", e)),
+        None => Ok(None),
+    }
+}
+
+// Rake from real-money hands (practice tables excluded) played after `from`
+fn local_rake(db: &Database, from: DateTime<Utc>) -> Result<(f64, u32), String> {
+    let filter = HandFilter { from: Some(from), ..HandFilter::default() };
+    let hands = db.with_conn(|conn| filtered_hands(conn, &filter))?;
+    let rake: f64 = hands.iter().map(hero_rake).sum();
+    Ok((rake, hands.len() as u32))
+}
+
+fn view(db: &Database, status: LoyaltyStatus, offline: bool) -> Result<LoyaltyView, String> {
+    let (pending_rake, _) = local_rake(db, status.settled_at + Duration::milliseconds(1))?;
+    Ok(LoyaltyView { pending_points: pending_rake * status.points_per_rake, pending_rake, status, offline })
+}
+
+async fn request<T: serde::de::DeserializeOwned>(builder: reqwest::RequestBuilder) -> Result<T, String> {
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+
+    let response = crate::http::send(builder.header("Authorization", format!("Bearer {}", token)))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("Loyalty request failed: {}", error_text));
+    }
+
+    let api_response: crate::ApiResponse<T> = response.json().await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    if api_response.success {
+        api_response.data.ok_or_else(|| "No data returned".to_string())
+    } else {
+        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    }
+}
+
+fn period_start(period: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    let today = now.date_naive();
+    let start = match period {
+        "day" => today,
+        "week" => today - Duration::days(today.weekday().num_days_from_monday() as i64),
+        "month" => today.with_day(1).unwrap_or(today),
+        _ => return Err(format!("Unknown rakeback period: {}", period)),
+    };
+    let midnight = start.and_hms_opt(0, 0, 0).ok_or_else(|| "Invalid period start".to_string())?;
+    Ok(Utc.from_utc_datetime(&midnight))
+}
+
+// Tell the client how many points are pending after a newly saved hand
+pub fn record_hand(app: &AppHandle, db: &Database, hand: &HandRecord) -> Result<(), String> {
+    if hero_rake(hand) == 0.0 || hand.table_id.starts_with("practice-") {
+        return Ok(());
+    }
+    let Some(status) = cached_status(db)? else { return Ok(()) };
+    let view = view(db, status, false)?;
+    let _ = app.emit_all("loyalty_points_updated", json!({
+        "points": view.status.points + view.pending_points,
+        "pendingPoints": view.pending_points,
+        "pendingRake": view.pending_rake,
+    }));
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_loyalty_status(db: Db<'_>, api_url: String) -> Result<LoyaltyView, String> {
+    let client = crate::create_http_client()?;
+    match request::<LoyaltyStatus>(client.get(format!("{}/api/loyalty/status", api_url))).await {
+        Ok(status) => {
+            let data = serde_json::to_string(&status).map_err(|e| e.to_string())?;
+            db.set_value(KEY_STATUS, &data)?;
+            view(&db, status, false)
+        }
+        Err(e) => match cached_status(&db)? {
+            Some(status) => {
+                eprintln!("Loyalty status fetch failed, using last known status: {}", e);
+                view(&db, status, true)
+            }
+            None => Err(e),
+        },
+    }
+}
+
+// Rakeback for the current day, week (from Monday) or month, in UTC
+#[tauri::command]
+pub async fn get_rakeback_summary(
+    db: Db<'_>,
+    api_url: String,
+    period: Option<String>,
+) -> Result<RakebackSummary, String> {
+    let period = period.unwrap_or_else(|| "week".to_string());
+    if !PERIODS.contains(&period.as_str()) {
+        return Err(format!("Unknown rakeback period: {}", period));
+    }
+    let from = period_start(&period, Utc::now())?;
+
+    let client = crate::create_http_client()?;
+    let remote: RemoteRakeback = request(client
+        .get(format!("{}/api/loyalty/rakeback", api_url))
+        .query(&[("period", period.as_str())]))
+        .await?;
+    let (local_rake_paid, local_hands) = local_rake(&db, from)?;
+
+    Ok(RakebackSummary { period, from, remote, local_rake_paid, local_hands })
+}
+
+// Claim accrued rakeback and redeemable rewards into the wallet
+#[tauri::command]
+pub async fn claim_rewards(app: AppHandle, api_url: String) -> Result<serde_json::Value, String> {
+    let client = crate::create_http_client()?;
+    let result = request::<serde_json::Value>(client.post(format!("{}/api/loyalty/claim", api_url)).json(&json!({}))).await;
+    audit::record(&app, "claim_rewards", json!({}), &result);
+    result
+}
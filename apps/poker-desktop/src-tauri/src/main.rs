@@ -6,6 +6,119 @@ use keyring::Entry;
 use chrono::{DateTime, Utc, Duration};
 use reqwest::{Client, header};
 
+mod accounts;
+mod achievements;
+mod admin;
+mod alerts;
+mod animation;
+mod announcements;
+mod audit;
+mod bankroll;
+mod bonuses;
+mod bus;
+mod cards;
+mod chat;
+mod chat_reports;
+mod claims;
+mod clock;
+mod clubs;
+mod compliance;
+mod compression;
+mod compute;
+mod db;
+mod deals;
+mod device;
+mod engine;
+mod equity;
+mod error;
+mod event_buffer;
+mod ev;
+mod evaluator;
+#[cfg(feature = "http-fixtures")]
+mod fixtures;
+mod graphql;
+mod guard;
+mod hand_files;
+mod headless;
+mod history;
+mod host;
+mod idle;
+mod http;
+mod instance;
+mod integrity;
+mod kyc;
+mod leaderboards;
+mod leaks;
+mod ledger;
+mod live_stats;
+mod lobby;
+mod localtime;
+mod loyalty;
+mod maintenance;
+mod memory;
+mod messages;
+mod metrics;
+mod migrations;
+mod missed_blinds;
+#[cfg(feature = "mock-backend")]
+mod mock_backend;
+mod moderation;
+mod muck;
+mod notes;
+mod onboarding;
+mod pagination;
+mod pinpad;
+mod players;
+mod practice;
+mod preflop;
+mod preview;
+mod private_games;
+mod profile;
+mod promotions;
+mod ranges;
+mod ratelimit;
+mod rebuy;
+mod recent;
+mod relay;
+mod renewal;
+mod reports;
+mod results;
+mod retention;
+mod scanner;
+mod schema;
+mod sessions;
+mod showdown;
+mod shutdown;
+mod sizing;
+mod snapshot;
+mod solver;
+mod spectate;
+mod spectator_delay;
+mod speed;
+mod startup;
+mod strength;
+mod subscriptions;
+mod support;
+mod sync;
+mod table_state;
+mod table_stats;
+mod templates;
+mod thumbnails;
+mod tickets;
+mod tilt;
+mod timer;
+mod tournaments;
+mod tracker_api;
+mod trainer;
+mod translate;
+mod variance;
+mod vault;
+mod verify;
+mod version;
+mod voice;
+mod wallet;
+mod ws;
+
 #[derive(Debug, Serialize, Deserialize)]
 struct ConnectionStatus {
     connected: bool,
@@ -49,6 +162,24 @@ struct User {
     name: Option<String>,
 }
 
+const USER_SCHEMA: schema::Kind = schema::Kind::Object(&[
+    schema::Field::critical("id", schema::Kind::String),
+    schema::Field::critical("username", schema::Kind::String),
+    schema::Field::defaulted("email", schema::Kind::String),
+    schema::Field::optional("name", schema::Kind::String),
+]);
+
+const TOKENS_SCHEMA: schema::Kind = schema::Kind::Object(&[
+    schema::Field::critical("accessToken", schema::Kind::String),
+    schema::Field::critical("refreshToken", schema::Kind::String),
+]);
+
+const LOGIN_SCHEMA: schema::Kind = schema::Kind::Object(&[
+    schema::Field::critical("user", USER_SCHEMA),
+    schema::Field::critical("tokens", TOKENS_SCHEMA),
+    schema::Field::defaulted("message", schema::Kind::String),
+]);
+
 #[derive(Debug, Serialize, Deserialize)]
 struct ApiResponse<T> {
     success: bool,
@@ -61,7 +192,7 @@ struct ApiError {
     message: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct TableConfig {
     name: String,
     #[serde(rename = "gameType")]
@@ -81,10 +212,16 @@ struct TableConfig {
     #[serde(rename = "bigBlind")]
     big_blind: u32,
     ante: u32,
+    #[serde(rename = "anteStructure", default)]
+    ante_structure: history::AnteStructure,
     #[serde(rename = "timeBank")]
     time_bank: u32,
     #[serde(rename = "isPrivate")]
     is_private: bool,
+    #[serde(rename = "gameSpeed", default)]
+    game_speed: speed::GameSpeed,
+    #[serde(rename = "fastFold", default)]
+    fast_fold: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -100,6 +237,16 @@ struct Table {
     pot: u32,
     blinds: BlindsConfig,
     config: Option<TableConfigResponse>,
+    #[serde(rename = "spectatorCount", default)]
+    spectator_count: Option<u32>,
+    // Local lobby preferences, filled in by get_tables
+    #[serde(default)]
+    favorite: bool,
+    #[serde(default)]
+    alias: Option<String>,
+    // Rolling statistics from what this client has seen of the table
+    #[serde(default, skip_deserializing)]
+    stats: table_stats::TableStats,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -110,6 +257,14 @@ struct TableConfigResponse {
     small_blind: u32,
     #[serde(rename = "bigBlind")]
     big_blind: u32,
+    #[serde(default)]
+    ante: u32,
+    #[serde(rename = "anteStructure", default)]
+    ante_structure: history::AnteStructure,
+    #[serde(rename = "gameSpeed", default)]
+    game_speed: speed::GameSpeed,
+    #[serde(rename = "fastFold", default)]
+    fast_fold: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -118,8 +273,42 @@ struct BlindsConfig {
     big: u32,
 }
 
+const TABLE_SCHEMA: schema::Kind = schema::Kind::Object(&[
+    schema::Field::critical("id", schema::Kind::String),
+    schema::Field::critical("name", schema::Kind::String),
+    schema::Field::defaulted("playerCount", schema::Kind::Number),
+    schema::Field::defaulted("maxPlayers", schema::Kind::Number),
+    schema::Field::defaulted("gamePhase", schema::Kind::String),
+    schema::Field::defaulted("pot", schema::Kind::Number),
+    schema::Field::critical("blinds", schema::Kind::Object(&[
+        schema::Field::critical("small", schema::Kind::Number),
+        schema::Field::critical("big", schema::Kind::Number),
+    ])),
+    schema::Field::optional("spectatorCount", schema::Kind::Number),
+    schema::Field::optional("config", schema::Kind::Object(&[
+        schema::Field::defaulted("maxPlayers", schema::Kind::Number),
+        schema::Field::defaulted("smallBlind", schema::Kind::Number),
+        schema::Field::defaulted("bigBlind", schema::Kind::Number),
+        schema::Field::defaulted("ante", schema::Kind::Number),
+        schema::Field::defaulted("anteStructure", schema::Kind::String),
+        schema::Field::defaulted("gameSpeed", schema::Kind::String),
+        schema::Field::defaulted("fastFold", schema::Kind::Any),
+    ])),
+]);
+
+const LOBBY_PAGE_SIZE: u32 = 200;
+const MAX_LOBBY_PAGES: usize = 50;
+
+// Built once and shared; a reqwest client holds its connection pool and TLS setup,
+// which are slow to recreate on every request
+static HTTP_CLIENT: std::sync::OnceLock<Client> = std::sync::OnceLock::new();
+
 // Helper function to create a properly configured HTTP client
 fn create_http_client() -> Result<Client, String> {
+    if let Some(client) = HTTP_CLIENT.get() {
+        return Ok(client.clone());
+    }
+
     let mut headers = header::HeaderMap::new();
     headers.insert(
         header::USER_AGENT,
@@ -130,14 +319,73 @@ fn create_http_client() -> Result<Client, String> {
         header::HeaderValue::from_static("application/json")
     );
     
-    Client::builder()
+    let client = Client::builder()
         .default_headers(headers)
         .timeout(std::time::Duration::from_secs(30))
         .connect_timeout(std::time::Duration::from_secs(10))
         // Use native TLS for better compatibility
         .use_native_tls()
         .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    Ok(HTTP_CLIENT.get_or_init(|| client).clone())
+}
+
+// Database and the subsystems that need it. Runs after the window is shown, since
+// migrations can take a while on a large history.
+fn open_database(app: &tauri::AppHandle, data_dir: &std::path::Path) {
+    use tauri::Manager;
+
+    startup::timed(app, "event buffers", true, || event_buffer::init(data_dir));
+
+    // A failed migration leaves the database unmanaged; commands that take it fail
+    // with `DatabaseNotReady` (see db::Db) and the frontend is told why through
+    // `startup_error`
+    let opened = startup::timed(app, "database", true, || db::Database::open(&data_dir.join("primo-poker.db")));
+    match opened {
+        Ok(database) => {
+            eprintln!("Local database ready (schema v{})", database.schema_version());
+            if let Err(e) = compression::load(&database) {
+                eprintln!("Using default WebSocket compression settings: {}", e);
+            }
+            if let Err(e) = localtime::load(&database) {
+                eprintln!("Using the system time zone and locale: {}", e);
+            }
+            if let Err(e) = hand_files::register(&database) {
+                eprintln!("Hand file association: {}", e);
+            }
+            // Before the database is managed, so no stored hand is published unheard
+            bus::on_new_hand(app, "achievement progress", achievements::record_hand);
+            bus::on_new_hand(app, "loyalty points", loyalty::record_hand);
+            bus::on_new_hand(app, "bonus progress", bonuses::record_hand);
+            bus::on_new_hand(app, "the balance ledger", |_, db, hand| ledger::record_hand(db, hand));
+            bus::on_new_hand(app, "auto-rebuy", |app, _, hand| {
+                rebuy::after_hand(app, hand);
+                Ok(())
+            });
+            bus::on_new_hand(app, "tilt detection", tilt::after_hand);
+            app.manage(database);
+            startup::timed(app, "background tasks", true, || {
+                sync::start_background_sync(app);
+                tournaments::start_reminders(app);
+                renewal::start(app);
+                retention::start(app);
+                tracker_api::start(app);
+                idle::start_monitor(app);
+                memory::start_monitor(app);
+                scanner::start_scanner(app);
+                announcements::start(app);
+                maintenance::announce_restore(app);
+                hand_files::open_from_args(app);
+                support::start_polling(app);
+                bankroll::start_monitor(app);
+                if let Err(e) = chat::register_hotkeys(app) {
+                    eprintln!("Chat hotkeys: {}", e);
+                }
+            });
+        }
+        Err(e) => startup::report_error(app, "database", e),
+    }
+    startup::mark_fully_ready(app);
 }
 
 // Check backend connection
@@ -147,7 +395,7 @@ async fn check_backend_connection(api_url: String) -> Result<ConnectionStatus, S
     
     let client = create_http_client()?;
     
-    match client.get(&format!("{}/api/health", api_url)).send().await {
+    match http::send(client.get(format!("{}/api/health", api_url))).await {
         Ok(response) => {
             let latency_ms = start.elapsed().as_millis() as u32;
             let is_success = response.status().is_success();
@@ -175,44 +423,67 @@ async fn check_backend_connection(api_url: String) -> Result<ConnectionStatus, S
 
 // Login user
 #[tauri::command]
-async fn login(api_url: String, email: String, password: String) -> Result<LoginResponse, String> {
+async fn login(app: tauri::AppHandle, api_url: String, email: String, password: String) -> Result<LoginResponse, String> {
+    let result = request_login(&api_url, email.clone(), password).await;
+    audit::record(&app, "login", serde_json::json!({ "email": email }), &result);
+    
+    if result.is_ok() {
+        // Gate real-money features on the jurisdiction ruling without delaying login
+        tauri::async_runtime::spawn(async move {
+            compliance::check_on_login(&app, &api_url).await;
+        });
+    }
+    
+    result
+}
+
+async fn request_login(api_url: &str, email: String, password: String) -> Result<LoginResponse, String> {
+    let login_response = fetch_login(api_url, email, password).await?;
+
+    // Store tokens securely
+    let auth_token = AuthToken {
+        access_token: login_response.tokens.access_token.clone(),
+        refresh_token: login_response.tokens.refresh_token.clone(),
+        expires_at: Utc::now() + Duration::hours(24), // Assuming 24h expiry
+    };
+
+    store_auth_token_secure(auth_token)?;
+
+    // Convert to expected format for frontend
+    Ok(LoginResponse {
+        user: login_response.user,
+        tokens: login_response.tokens,
+        message: login_response.message,
+    })
+}
+
+// Login request without touching the keyring; also used by headless bots
+async fn fetch_login(api_url: &str, email: String, password: String) -> Result<LoginResponse, String> {
     let client = create_http_client()?;
-    let response = client
-        .post(&format!("{}/api/auth/login", api_url))
+    let response = http::send(device::with_device_headers(client
+        .post(format!("{}/api/auth/login", api_url))
         .header(header::CONTENT_TYPE, "application/json")
-        .json(&LoginRequest { username: email.clone(), password })
-        .send()
+        .json(&LoginRequest { username: email, password })))
         .await
-        .map_err(|e| format!("Network error: {}", e))?;
-
-    if response.status().is_success() {
-        let login_response: LoginResponse = response.json().await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
-        
-        // Store tokens securely
-        let auth_token = AuthToken {
-            access_token: login_response.tokens.access_token.clone(),
-            refresh_token: login_response.tokens.refresh_token.clone(),
-            expires_at: Utc::now() + Duration::hours(24), // Assuming 24h expiry
-        };
-        
-        store_auth_token_secure(auth_token)?;
-        
-        // Convert to expected format for frontend
-        Ok(LoginResponse {
-            user: login_response.user,
-            tokens: login_response.tokens,
-            message: login_response.message,
-        })
-    } else {
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
         let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        Err(format!("Login failed: {}", error_text))
+        return Err(format!("Login failed: {}", error_text));
+    }
+
+    // Newer backends wrap the login answer like every other response
+    if version::supports(version::LOGIN_ENVELOPE) {
+        schema::api_data(response, &LOGIN_SCHEMA, "login response").await?
+            .ok_or_else(|| "No login data returned".to_string())
+    } else {
+        schema::body(response, &LOGIN_SCHEMA, "login response").await
     }
 }
 
 // Store auth token securely using system keyring
 fn store_auth_token_secure(token: AuthToken) -> Result<(), String> {
-    let entry = Entry::new("primo-poker", "auth-token")
+    let entry = Entry::new("primo-poker", &accounts::keyring_key("auth-token"))
         .map_err(|e| format!("Keyring error: {}", e))?;
     
     let token_json = serde_json::to_string(&token)
@@ -227,7 +498,7 @@ fn store_auth_token_secure(token: AuthToken) -> Result<(), String> {
 // Retrieve auth token
 #[tauri::command]
 async fn get_auth_token() -> Result<Option<AuthToken>, String> {
-    let entry = Entry::new("primo-poker", "auth-token")
+    let entry = Entry::new("primo-poker", &accounts::keyring_key("auth-token"))
         .map_err(|e| format!("Keyring error: {}", e))?;
     
     match entry.get_password() {
@@ -250,8 +521,14 @@ async fn get_auth_token() -> Result<Option<AuthToken>, String> {
 
 // Logout user
 #[tauri::command]
-async fn logout() -> Result<(), String> {
-    let entry = Entry::new("primo-poker", "auth-token")
+async fn logout(app: tauri::AppHandle) -> Result<(), String> {
+    let result = clear_auth_token();
+    audit::record(&app, "logout", serde_json::json!({}), &result);
+    result
+}
+
+fn clear_auth_token() -> Result<(), String> {
+    let entry = Entry::new("primo-poker", &accounts::keyring_key("auth-token"))
         .map_err(|e| format!("Keyring error: {}", e))?;
     
     match entry.delete_password() {
@@ -278,64 +555,195 @@ async fn get_user() -> Result<Option<User>, String> {
 }
 
 fn get_token_from_keyring() -> Result<String, String> {
-    let entry = Entry::new("primo-poker", "auth-token")
+    stored_auth_token().map(|token| token.access_token)
+}
+
+// Tokens as stored, whether or not they have expired
+fn stored_auth_token() -> Result<AuthToken, String> {
+    let entry = Entry::new("primo-poker", &accounts::keyring_key("auth-token"))
         .map_err(|e| format!("Keyring error: {}", e))?;
     
     let token_json = entry.get_password()
         .map_err(|e| format!("Failed to get token: {}", e))?;
     
-    let token: AuthToken = serde_json::from_str(&token_json)
-        .map_err(|e| format!("Failed to parse token: {}", e))?;
-    
-    Ok(token.access_token)
+    serde_json::from_str(&token_json)
+        .map_err(|e| format!("Failed to parse token: {}", e))
 }
 
-// Get tables from backend
+// Get tables from backend, favorites first. Paged lobbies are stitched into one
+// list, which is also kept as the lobby snapshot for when the backend is unreachable.
 #[tauri::command]
-async fn get_tables(api_url: String) -> Result<Vec<Table>, String> {
-    let client = create_http_client()?;
-    
-    // Get token from keyring if available
-    let token = match get_token_from_keyring() {
-        Ok(token) => Some(token),
-        Err(_) => None,
+async fn get_tables(
+    db: db::Db<'_>,
+    stats: tauri::State<'_, table_stats::TableStatsState>,
+    idle: tauri::State<'_, idle::IdleState>,
+    thumbnails: tauri::State<'_, thumbnails::ThumbnailState>,
+    api_url: String,
+) -> Result<Vec<Table>, String> {
+    let mut tables = match fetch_tables(&api_url).await {
+        Ok(mut tables) => {
+            observe_tables(&stats, &mut tables)?;
+            lobby::save_snapshot(&db, &tables)?;
+            let mut open: std::collections::HashSet<String> = tables.iter().map(|t| t.id.clone()).collect();
+            open.extend(idle.seated_tables());
+            open.extend(thumbnails.seated()?.into_keys());
+            alerts::prune(&db, &open)?;
+            tables
+        }
+        Err(e) => match lobby::load_snapshot::<Table>(&db)? {
+            Some(mut tables) => {
+                eprintln!("Using cached lobby snapshot: {}", e);
+                for table in &mut tables {
+                    table.stats = stats.stats(&table.id)?;
+                }
+                tables
+            }
+            None => return Err(e),
+        },
     };
-    
-    let mut request = client.get(format!("{}/api/tables", api_url));
-    
-    if let Some(token) = token {
-        request = request.header("Authorization", format!("Bearer {}", token));
+    decorate_tables(&db, &mut tables)?;
+    Ok(tables)
+}
+
+// One page of the lobby; `cursor` comes from a previous page
+#[tauri::command]
+async fn get_tables_page(
+    db: db::Db<'_>,
+    stats: tauri::State<'_, table_stats::TableStatsState>,
+    api_url: String,
+    limit: Option<u32>,
+    cursor: Option<String>,
+) -> Result<pagination::Page<Table>, String> {
+    let after = cursor.as_deref().map(pagination::remote_position).transpose()?;
+    fetch_tables_page(&db, &stats, &api_url, pagination::clamp_limit(limit), after.as_deref()).await
+}
+
+async fn fetch_tables_page(
+    db: &db::Database,
+    stats: &table_stats::TableStatsState,
+    api_url: &str,
+    limit: u32,
+    after: Option<&str>,
+) -> Result<pagination::Page<Table>, String> {
+    let (mut tables, next) = request_tables(api_url, limit, after).await?;
+    observe_tables(stats, &mut tables)?;
+    decorate_tables(db, &mut tables)?;
+    Ok(pagination::Page { items: tables, next_cursor: pagination::lobby_cursor(api_url, limit, next) })
+}
+
+// Fold a fresh lobby listing into the rolling table stats
+fn observe_tables(stats: &table_stats::TableStatsState, tables: &mut [Table]) -> Result<(), String> {
+    for table in tables.iter_mut() {
+        stats.observe(&table.id, &table_stats::Observation {
+            phase: table.game_phase.clone(),
+            pot: table.pot,
+            players: table.player_count,
+            hand_number: None,
+            in_hand: None,
+            spectators: table.spectator_count,
+        })?;
+        table.stats = stats.stats(&table.id)?;
     }
-    
-    let response = request.send().await.map_err(|e| e.to_string())?;
-    
-    if !response.status().is_success() {
-        return Err("Failed to fetch tables".to_string());
+    Ok(())
+}
+
+// Apply local lobby preferences, favorites first
+fn decorate_tables(db: &db::Database, tables: &mut [Table]) -> Result<(), String> {
+    let mut prefs = lobby::load(db)?;
+    let mut renamed = false;
+    for table in tables.iter_mut() {
+        table.favorite = prefs.is_favorite(&table.id);
+        table.alias = prefs.alias(&table.id);
+        renamed |= prefs.note_name(&table.id, &table.name);
     }
-    
-    let api_response: ApiResponse<Vec<Table>> = response.json().await.map_err(|e| e.to_string())?;
-    
-    if api_response.success {
-        Ok(api_response.data.unwrap_or_default())
-    } else {
-        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    if renamed {
+        lobby::save(db, &prefs)?;
     }
+    tables.sort_by_key(|table| !table.favorite);
+    Ok(())
+}
+
+// Every lobby page, up to MAX_LOBBY_PAGES. The pages wait for the table rate limit
+// between them rather than failing once its burst is spent.
+async fn fetch_tables(api_url: &str) -> Result<Vec<Table>, String> {
+    http::paced(async {
+        let mut tables = Vec::new();
+        let mut after = None;
+        for _ in 0..MAX_LOBBY_PAGES {
+            let (page, next) = request_tables(api_url, LOBBY_PAGE_SIZE, after.as_deref()).await?;
+            tables.extend(page);
+            match next {
+                Some(next) => after = Some(next),
+                None => break,
+            }
+        }
+        Ok(tables)
+    })
+    .await
+}
+
+async fn request_tables(api_url: &str, limit: u32, after: Option<&str>) -> Result<(Vec<Table>, Option<String>), String> {
+    // Get token from keyring if available
+    let token = get_token_from_keyring().ok();
+    let url = format!("{}/api/tables", api_url);
+    pagination::fetch_remote(&url, token.as_deref(), limit, after, &TABLE_SCHEMA, "table list").await
 }
 
 // Create a new table
 #[tauri::command]
-async fn create_table(api_url: String, config: TableConfig) -> Result<Table, String> {
+async fn create_table(app: tauri::AppHandle, api_url: String, config: TableConfig) -> Result<Table, error::CommandError> {
+    use tauri::Manager;
+
+    let result = match guard::require("create tables", &guard::CREATE_TABLE) {
+        Ok(_) => request_create_table(&api_url, &config).await.map_err(error::CommandError::from),
+        Err(e) => Err(e),
+    };
+    let table_id = result.as_ref().ok().map(|table| table.id.clone());
+    audit::record(&app, "create_table", serde_json::json!({ "name": config.name, "tableId": table_id }), &result);
+    if let (Some(table_id), Some(db)) = (table_id, app.try_state::<db::Database>()) {
+        if let Err(e) = host::record_hosted(&db, &table_id) {
+            eprintln!("Failed to record hosted table: {}", e);
+        }
+    }
+    result
+}
+
+// Checks made before a table is created, so a bad setup is refused without a round
+// trip; table templates go through them too
+fn validate_table_config(config: &TableConfig) -> Result<(), String> {
+    // Fast-fold needs a pool of strangers to move players between
+    if config.fast_fold && config.is_private {
+        return Err("Private tables cannot be fast-fold".to_string());
+    }
+    if config.fast_fold && config.max_players < 6 {
+        return Err("Fast-fold pools need tables of at least six seats".to_string());
+    }
+    moderation::require(moderation::Field::TableName, &config.name)?;
+    if sizing::Limit::from_name(&config.betting_structure).is_none() {
+        return Err(format!("Unknown betting structure {}", config.betting_structure));
+    }
+    // A standard ante is per player, a button or big blind ante is one post for the table
+    if config.ante > config.big_blind {
+        return Err("The ante cannot be more than the big blind".to_string());
+    }
+    if config.ante == 0 && config.ante_structure != history::AnteStructure::Standard {
+        return Err("Button and big blind antes need an ante amount".to_string());
+    }
+    Ok(())
+}
+
+async fn request_create_table(api_url: &str, config: &TableConfig) -> Result<Table, String> {
+    validate_table_config(config)?;
     let client = create_http_client()?;
     
     // Get token from keyring
     let token = get_token_from_keyring()
         .map_err(|_| "Not authenticated".to_string())?;
     
-    let response = client.post(format!("{}/api/tables", api_url))
+    let response = http::send(client.post(format!("{}/api/tables", api_url))
         .header("Authorization", format!("Bearer {}", token))
         .header("Content-Type", "application/json")
-        .json(&config)
-        .send()
+        .json(config))
         .await
         .map_err(|e| e.to_string())?;
     
@@ -344,29 +752,48 @@ async fn create_table(api_url: String, config: TableConfig) -> Result<Table, Str
         return Err(format!("Failed to create table: {}", error_text));
     }
     
-    let api_response: ApiResponse<Table> = response.json().await.map_err(|e| e.to_string())?;
-    
-    if api_response.success {
-        Ok(api_response.data.ok_or_else(|| "No table data returned".to_string())?)
-    } else {
-        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
-    }
+    schema::api_data(response, &TABLE_SCHEMA, "table").await?
+        .ok_or_else(|| "No table data returned".to_string())
 }
 
 // Join a table
 #[tauri::command]
-async fn join_table(api_url: String, table_id: String, buy_in: u32) -> Result<serde_json::Value, String> {
-    let client = create_http_client()?;
-    
+async fn join_table(app: tauri::AppHandle, api_url: String, table_id: String, buy_in: u32) -> Result<serde_json::Value, String> {
+    use tauri::Manager;
+
+    if maintenance::is_draining(&app) {
+        return Err("Seating is paused until maintenance is over".to_string());
+    }
+    tilt::check_join(&app)?;
+    ledger::before_buy_in(&app, &table_id).await;
+    let result = request_join_table(&api_url, &table_id, buy_in).await;
+    audit::record(&app, "join_table", serde_json::json!({ "tableId": table_id, "buyIn": buy_in }), &result);
+    if result.is_ok() {
+        ledger::record_buy_in(&app, &table_id, buy_in);
+        if let Some(db) = app.try_state::<db::Database>() {
+            if let Err(e) = lobby::record_join(&db, &table_id) {
+                eprintln!("Failed to record recent table: {}", e);
+            }
+        }
+    }
+    result
+}
+
+async fn request_join_table(api_url: &str, table_id: &str, buy_in: u32) -> Result<serde_json::Value, String> {
     // Get token from keyring
     let token = get_token_from_keyring()
         .map_err(|_| "Not authenticated".to_string())?;
+
+    post_join_table(api_url, &token, table_id, buy_in).await
+}
+
+async fn post_join_table(api_url: &str, token: &str, table_id: &str, buy_in: u32) -> Result<serde_json::Value, String> {
+    let client = create_http_client()?;
     
-    let response = client.post(format!("{}/api/tables/{}/join", api_url, table_id))
+    let response = http::send(client.post(format!("{}/api/tables/{}/join", api_url, table_id))
         .header("Authorization", format!("Bearer {}", token))
         .header("Content-Type", "application/json")
-        .json(&serde_json::json!({ "buyIn": buy_in }))
-        .send()
+        .json(&serde_json::json!({ "buyIn": buy_in })))
         .await
         .map_err(|e| e.to_string())?;
     
@@ -385,14 +812,90 @@ async fn join_table(api_url: String, table_id: String, buy_in: u32) -> Result<se
 }
 
 fn main() {
+    startup::mark_process_start();
+    if let Some(scenario) = headless::scenario_arg() {
+        std::process::exit(headless::run_from_file(&scenario));
+    }
+
+    let context = tauri::generate_context!();
+    if instance::hand_over(context.config()) {
+        return;
+    }
+
     tauri::Builder::default()
         .setup(|app| {
+            use tauri::Manager;
+
             #[cfg(debug_assertions)]
             {
-                use tauri::Manager;
                 let window = app.get_window("main").unwrap();
                 window.open_devtools();
             }
+
+            ratelimit::init(app.handle());
+            app.manage(startup::StartupStatus::default());
+            let handle = app.handle();
+            let profile = startup::timed(&handle, "profile", false, || profile::select(&handle));
+            app.manage(profile);
+            // Before anything touches the data directory or the keyring
+            let data_root = app.path_resolver().app_data_dir()
+                .ok_or_else(|| "Could not resolve app data directory".to_string())?;
+            let data_dir = startup::timed(&handle, "account profile", false, || accounts::select(&data_root))?;
+            instance::listen(&handle, &data_root);
+            startup::timed(&handle, "state", false, || {
+                app.manage(version::VersionState::default());
+                version::init(app.handle());
+                app.manage(sync::SyncEngine::default());
+                app.manage(compliance::ComplianceState::default());
+                app.manage(kyc::KycState::default());
+                app.manage(practice::PracticeState::default());
+                app.manage(trainer::TrainerState::default());
+                app.manage(solver::SolverState::default());
+                app.manage(leaks::LeakState::default());
+                app.manage(players::PlayerSearchState::default());
+                app.manage(preview::PreviewState::default());
+                app.manage(table_stats::TableStatsState::default());
+                app.manage(clock::ClockState::default());
+                app.manage(pinpad::PinPadState::default());
+                app.manage(idle::IdleState::default());
+                app.manage(strength::StrengthState::default());
+                app.manage(translate::TranslationState::default());
+                app.manage(voice::VoiceState::default());
+                app.manage(relay::RelayState::default());
+                app.manage(thumbnails::ThumbnailState::default());
+                app.manage(recent::RecentActionsState::default());
+                app.manage(live_stats::LiveStatsState::default());
+                app.manage(missed_blinds::MissedBlindsState::default());
+                app.manage(bankroll::BankrollState::default());
+                app.manage(chat::ChatState::default());
+                app.manage(animation::AnimationState::default());
+                app.manage(timer::TimerState::default());
+                app.manage(muck::MuckState::default());
+                app.manage(spectator_delay::SpectatorDelayState::default());
+                app.manage(subscriptions::SubscriptionState::default());
+                app.manage(shutdown::ShutdownState::default());
+                app.manage(renewal::RenewalState::default());
+                app.manage(scanner::ScannerState::default());
+                app.manage(maintenance::MaintenanceState::default());
+                app.manage(hand_files::HandFileState::default());
+                app.manage(tracker_api::TrackerApiState::default());
+                app.manage(tilt::TiltState::default());
+                app.manage(support::SupportState::default());
+            });
+            // Both only spawn tasks; the network and keyring work happens off this thread
+            startup::timed(&handle, "network tasks", false, || {
+                version::start_negotiation(&handle);
+                bus::on_frame(&handle, bus::Topic::Notification, "announcement", |app, _, message| {
+                    announcements::receive(app, message)
+                });
+                bus::on_frame(&handle, bus::Topic::Notification, "promotion_*", |app, _, message| {
+                    promotions::receive(app, message)
+                });
+                relay::start_relay(&handle);
+            });
+
+            startup::mark_window_ready(&handle);
+            tauri::async_runtime::spawn_blocking(move || open_database(&handle, &data_dir));
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -402,9 +905,292 @@ fn main() {
             get_auth_token,
             get_user,
             get_tables,
+            get_tables_page,
             create_table,
-            join_table
+            join_table,
+            startup::get_startup_errors,
+            startup::get_startup_timing,
+            profile::get_backend_profile,
+            compliance::get_compliance_status,
+            compliance::check_feature_allowed,
+            kyc::get_kyc_status,
+            kyc::upload_kyc_document,
+            kyc::start_kyc_status_polling,
+            audit::get_audit_log,
+            audit::verify_audit_log,
+            history::save_hand_history,
+            history::get_hand_history,
+            history::get_hand,
+            notes::set_player_note,
+            notes::get_player_note,
+            notes::get_player_notes,
+            sync::enable_cloud_sync,
+            sync::disable_cloud_sync,
+            sync::get_cloud_sync_status,
+            sync::sync_now,
+            practice::start_practice,
+            practice::practice_action,
+            practice::next_practice_hand,
+            practice::get_practice_state,
+            practice::end_practice,
+            trainer::get_training_spot,
+            trainer::submit_training_answer,
+            solver::get_solver_config,
+            solver::allow_solver_binary,
+            solver::remove_solver_binary,
+            solver::solve_spot,
+            preflop::get_preflop_advice,
+            ranges::analyze_range,
+            ranges::expand_range,
+            ranges::range_vs_range_equity,
+            leaks::run_leak_analysis,
+            results::get_results_report,
+            results::get_ev_line,
+            ev::get_hand_ev,
+            players::search_players,
+            lobby::get_lobby_prefs,
+            lobby::favorite_table,
+            lobby::set_table_alias,
+            lobby::get_recent_tables,
+            preview::get_table_preview,
+            table_stats::get_table_stats,
+            clubs::create_club,
+            clubs::list_clubs,
+            clubs::join_club,
+            clubs::get_club_roster,
+            clubs::invite_club_member,
+            clubs::approve_club_member,
+            clubs::remove_club_member,
+            clubs::create_club_table,
+            clubs::get_club_ledger,
+            host::kick_player,
+            host::pause_table,
+            host::resume_table,
+            host::update_table_config,
+            admin::admin_pause_tournament,
+            admin::admin_resume_tournament,
+            admin::admin_adjust_clock,
+            admin::admin_disqualify_player,
+            admin::admin_rebalance_tables,
+            leaderboards::get_leaderboard,
+            achievements::get_achievements,
+            achievements::sync_achievements,
+            loyalty::get_loyalty_status,
+            loyalty::get_rakeback_summary,
+            loyalty::claim_rewards,
+            clock::sync_clock,
+            clock::get_clock_status,
+            tournaments::get_tournament_schedule,
+            tournaments::set_tournament_reminder,
+            tournaments::clear_tournament_reminder,
+            tournaments::list_tournament_reminders,
+            reports::report_hand,
+            reports::list_hand_reports,
+            sessions::list_active_sessions,
+            sessions::revoke_session,
+            sessions::logout_all_devices,
+            sessions::handle_session_revoked,
+            device::get_device_id,
+            device::trust_this_device,
+            device::list_trusted_devices,
+            device::revoke_trusted_device,
+            pinpad::get_pin_pad_enabled,
+            pinpad::set_pin_pad_enabled,
+            pinpad::start_pin_entry,
+            pinpad::submit_pin_entry,
+            pinpad::cancel_pin_entry,
+            idle::set_idle_thresholds,
+            idle::get_idle_status,
+            idle::report_table_activity,
+            verify::set_engine_verification,
+            verify::get_engine_violations,
+            showdown::explain_showdown,
+            strength::get_hand_strength_settings,
+            strength::set_hand_strength_settings,
+            strength::update_hand_strength,
+            translate::get_translation_settings,
+            translate::set_translation_settings,
+            translate::process_chat_message,
+            voice::join_voice,
+            voice::leave_voice,
+            voice::get_voice_status,
+            voice::send_voice_signal,
+            voice::set_voice_muted,
+            voice::set_voice_deafened,
+            voice::set_push_to_talk,
+            voice::push_to_talk,
+            voice::set_player_volume,
+            relay::take_pending_deep_link,
+            version::get_backend_version,
+            version::negotiate_backend_version,
+            schema::set_permissive_parsing,
+            pagination::get_hand_history_page,
+            pagination::fetch_next_page,
+            wallet::get_transactions,
+            graphql::get_dashboard,
+            graphql::graphql_query,
+            compression::get_ws_compression,
+            compression::set_ws_compression,
+            metrics::get_network_metrics,
+            metrics::reset_network_metrics,
+            messages::send_private_message,
+            messages::get_private_messages,
+            messages::decrypt_private_message,
+            messages::get_key_verification,
+            messages::mark_contact_verified,
+            messages::accept_contact_key,
+            vault::rotate_hand_key,
+            compute::get_compute_tasks,
+            compute::cancel_compute_task,
+            memory::get_memory_report,
+            memory::get_memory_settings,
+            memory::set_memory_settings,
+            event_buffer::dump_event_buffer,
+            thumbnails::update_table_thumbnail,
+            thumbnails::get_table_thumbnails,
+            thumbnails::remove_table_thumbnail,
+            sizing::get_bet_slider_model,
+            sizing::get_chip_breakdown,
+            rebuy::get_auto_rebuy,
+            rebuy::set_auto_rebuy,
+            rebuy::get_auto_rebuy_log,
+            recent::get_recent_actions,
+            deals::calculate_deal,
+            deals::propose_deal,
+            deals::accept_deal,
+            tickets::list_tickets,
+            tickets::set_ticket_auto_register,
+            tickets::cancel_ticket_auto_register,
+            tickets::register_with_ticket,
+            spectate::sit_here,
+            live_stats::record_live_event,
+            live_stats::get_live_stats,
+            live_stats::clear_live_stats,
+            notes::set_player_label,
+            notes::get_player_labels,
+            notes::find_labeled_tables,
+            scanner::get_scanner_rules,
+            scanner::set_scanner_rules,
+            scanner::get_scanner_matches,
+            history::fetch_hand_by_id,
+            announcements::get_announcements,
+            announcements::mark_announcement_read,
+            announcements::dismiss_announcement,
+            maintenance::get_maintenance_status,
+            maintenance::restore_after_maintenance,
+            maintenance::clear_maintenance_snapshot,
+            bonuses::get_bonuses,
+            bonuses::sync_bonuses,
+            localtime::get_time_settings,
+            localtime::set_time_settings,
+            localtime::list_time_zones,
+            accounts::list_account_profiles,
+            accounts::create_account_profile,
+            accounts::switch_account_profile,
+            accounts::delete_account_profile,
+            accounts::migrate_profile_data,
+            onboarding::get_onboarding_state,
+            onboarding::complete_onboarding_step,
+            onboarding::reset_onboarding,
+            support::pick_support_attachments,
+            support::submit_support_ticket,
+            support::get_my_tickets,
+            speed::get_timing_profile,
+            speed::follow_pool_reassignment,
+            missed_blinds::record_blind_event,
+            missed_blinds::get_return_options,
+            missed_blinds::return_to_table,
+            missed_blinds::clear_missed_blinds,
+            promotions::get_current_promotions,
+            variance::get_variance_report,
+            bankroll::get_bankroll_rules,
+            bankroll::set_bankroll_rules,
+            bankroll::get_bankroll_status,
+            bankroll::get_quick_seat_stakes,
+            chat::send_chat_message,
+            chat::send_canned_message,
+            chat::get_canned_messages,
+            chat::set_canned_messages,
+            chat::set_chat_focus,
+            table_stats::record_lobby_event,
+            table_stats::get_popular_tables,
+            integrity::verify_history_integrity,
+            animation::animate_table_event,
+            animation::forget_table_animations,
+            snapshot::export_table_snapshot,
+            timer::record_latency,
+            timer::track_action_clock,
+            timer::get_latency_status,
+            timer::get_timer_settings,
+            timer::set_timer_settings,
+            muck::handle_showdown_option,
+            muck::show_hand,
+            muck::muck_hand,
+            muck::get_muck_preferences,
+            muck::set_muck_rule,
+            moderation::validate_text,
+            templates::list_table_templates,
+            templates::save_table_template,
+            templates::delete_table_template,
+            templates::create_table_from_template,
+            templates::export_table_templates,
+            templates::import_table_templates,
+            private_games::schedule_private_game,
+            private_games::list_private_games,
+            private_games::invite_to_private_game,
+            private_games::respond_to_private_game,
+            private_games::record_private_game_rsvp,
+            private_games::cancel_private_game,
+            spectator_delay::subscribe_spectator,
+            spectator_delay::unsubscribe_spectator,
+            subscriptions::subscribe,
+            subscriptions::ack_subscription,
+            subscriptions::unsubscribe,
+            shutdown::confirm_shutdown,
+            shutdown::cancel_shutdown,
+            shutdown::report_sockets_closed,
+            shutdown::force_exit,
+            alerts::get_table_alert_overrides,
+            alerts::set_table_alert_overrides,
+            alerts::get_table_alerts,
+            renewal::refresh_auth_token,
+            chat_reports::report_chat_message,
+            chat_reports::list_chat_reports,
+            retention::get_retention_settings,
+            retention::set_retention_settings,
+            retention::prune_hand_history,
+            retention::get_storage_usage,
+            retention::get_hand_summaries,
+            ledger::get_ledger,
+            ledger::list_ledger_sessions,
+            ledger::reconcile_balance,
+            hand_files::open_file,
+            hand_files::take_pending_hand_file,
+            hand_files::export_hand_file,
+            bus::get_event_bus_stats,
+            tracker_api::get_tracker_api_status,
+            tracker_api::set_tracker_api_settings,
+            tracker_api::list_tracker_apps,
+            tracker_api::register_tracker_app,
+            tracker_api::revoke_tracker_app,
+            tilt::get_tilt_settings,
+            tilt::set_tilt_settings,
+            tilt::get_tilt_status
         ])
-        .run(tauri::generate_context!())
+        .on_window_event(|event| {
+            use tauri::Manager;
+
+            match event.event() {
+                tauri::WindowEvent::Focused(true) if event.window().label() == "main" => {
+                    relay::on_focus(&event.window().app_handle());
+                }
+                tauri::WindowEvent::CloseRequested { api, .. } if event.window().label() == "main" => {
+                    api.prevent_close();
+                    shutdown::on_close_requested(&event.window().app_handle());
+                }
+                _ => {}
+            }
+        })
+        .run(context)
         .expect("error while running tauri application");
 }
\ No newline at end of file
@@ -0,0 +1,260 @@
+// Winding play down ahead of announced maintenance. A few minutes before a window
+// starts the client begins draining: auto-rebuys, the table scanner and ticket
+// auto-registration stop, new seats are refused, and each table is left as soon as
+// no hand is in progress there (sat out first, then left), so no connection dies
+// mid-hand. Whatever is still running when the window opens is left then. Every
+// table left this way is written to a snapshot with its seat and stack, which is
+// offered back after the restart so the player can sit down again where they were.
+
+use crate::announcements::{self, MaintenanceWindow};
+use crate::audit;
+use crate::db::{self, Database, Db};
+use crate::idle::IdleState;
+use crate::ledger;
+use crate::profile::BackendProfile;
+use crate::thumbnails::ThumbnailState;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+const KEY_SNAPSHOT: &str = "maintenance.snapshot";
+// Draining starts this many minutes before a window
+const DRAIN_LEAD_MINS: i64 = 5;
+const DRAIN_TICK: std::time::Duration = std::time::Duration::from_secs(2);
+// Windows announced without an end are assumed over after this long
+const DEFAULT_WINDOW_HOURS: i64 = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedSeat {
+    table_id: String,
+    seat: Option<u8>,
+    // Stack when the table was left, the buy-in to come back with
+    chips: u32,
+    left_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceSnapshot {
+    window: MaintenanceWindow,
+    tables: Vec<SavedSeat>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceStatus {
+    // The window being drained for, if any
+    draining: Option<MaintenanceWindow>,
+    // Tables waiting to be rejoined once maintenance is over
+    snapshot: Option<MaintenanceSnapshot>,
+    restore_ready: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreResult {
+    table_id: String,
+    ok: bool,
+    error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct MaintenanceState {
+    draining: Mutex<Option<MaintenanceWindow>>,
+}
+
+fn window_end(window: &MaintenanceWindow) -> DateTime<Utc> {
+    window.ends_at.unwrap_or(window.starts_at + Duration::hours(DEFAULT_WINDOW_HOURS))
+}
+
+fn load_snapshot(db: &Database) -> Result<Option<MaintenanceSnapshot>, String> {
+    match db.get_value(KEY_SNAPSHOT)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid maintenance snapshot: {}", e)),
+        None => Ok(None),
+    }
+}
+
+fn save_snapshot(db: &Database, snapshot: Option<&MaintenanceSnapshot>) -> Result<(), String> {
+    let data = serde_json::to_string(&snapshot).map_err(|e| e.to_string())?;
+    db.set_value(KEY_SNAPSHOT, &data)
+}
+
+fn restore_ready(snapshot: &MaintenanceSnapshot, now: DateTime<Utc>) -> bool {
+    !snapshot.tables.is_empty() && window_end(&snapshot.window) <= now
+}
+
+// Whether play is being wound down; automatic buy-ins and new seats check this
+pub fn is_draining(app: &AppHandle) -> bool {
+    app.try_state::<MaintenanceState>()
+        .and_then(|state| state.draining.lock().ok().map(|draining| draining.is_some()))
+        .unwrap_or(false)
+}
+
+async fn post(api_url: &str, path: &str, body: Value) -> Result<(), String> {
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+    let client = crate::create_http_client()?;
+    let response = crate::http::send(client.post(format!("{}{}", api_url, path))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&body))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        Err(format!("Request to {} failed: {}", path, error_text))
+    }
+}
+
+// Snapshot the hero's seat at `table_id`, then sit out and leave
+async fn leave_table(app: &AppHandle, window: &MaintenanceWindow, table_id: &str) -> Result<(), String> {
+    let latest = app.state::<ThumbnailState>().latest(table_id)?;
+    let hero = latest.as_ref().and_then(|(mirror, _, hero_id)| mirror.player(hero_id.as_deref()?).cloned());
+    if let Some(hero) = hero.filter(|h| h.chips > 0) {
+        let db = db::get(app)?;
+        let mut snapshot = load_snapshot(&db)?
+            .filter(|s| s.window.id == window.id)
+            .unwrap_or_else(|| MaintenanceSnapshot { window: window.clone(), tables: Vec::new() });
+        snapshot.tables.retain(|t| t.table_id != table_id);
+        snapshot.tables.push(SavedSeat {
+            table_id: table_id.to_string(),
+            seat: hero.position.map(|p| p.seat),
+            chips: hero.chips,
+            left_at: Utc::now(),
+        });
+        save_snapshot(&db, Some(&snapshot))?;
+    }
+
+    let api_url = app.state::<BackendProfile>().api_url.clone();
+    let sit_out = post(&api_url, &format!("/api/tables/{}/sit-out", table_id), json!({ "reason": "maintenance" })).await;
+    if let Err(e) = sit_out {
+        eprintln!("Failed to sit out at {} before maintenance: {}", table_id, e);
+    }
+    post(&api_url, &format!("/api/tables/{}/leave", table_id), json!({ "reason": "maintenance" })).await?;
+    app.state::<IdleState>().forget_table(table_id);
+    ledger::table_left(app, table_id);
+    let _ = app.emit_all("maintenance_left_table", json!({ "tableId": table_id, "windowId": window.id }));
+    Ok(())
+}
+
+// Leave tables between hands until none are left or the window opens
+async fn drain(app: AppHandle, window: MaintenanceWindow) {
+    loop {
+        let now = Utc::now();
+        let opened = now >= window.starts_at;
+        let tables = app.state::<IdleState>().table_activity();
+        if tables.is_empty() {
+            break;
+        }
+        for (table_id, in_hand) in tables {
+            if in_hand && !opened {
+                continue;
+            }
+            if let Err(e) = leave_table(&app, &window, &table_id).await {
+                eprintln!("Failed to leave {} before maintenance: {}", table_id, e);
+                // Do not keep retrying a table the backend will not let go of
+                app.state::<IdleState>().forget_table(&table_id);
+            }
+        }
+        if opened {
+            break;
+        }
+        tokio::time::sleep(DRAIN_TICK).await;
+    }
+    let _ = app.emit_all("maintenance_drain_finished", window);
+}
+
+// Start draining when a window is close and stop once it is over. Called from the
+// announcement loop.
+pub fn check(app: &AppHandle, db: &Database, now: DateTime<Utc>) -> Result<(), String> {
+    let state = app.state::<MaintenanceState>();
+    let mut draining = state.draining.lock().map_err(|_| "Maintenance lock poisoned".to_string())?;
+    let windows = announcements::maintenance_windows(db, now)?;
+
+    if let Some(current) = draining.as_ref() {
+        let still_announced = windows.iter().any(|w| w.id == current.id);
+        if !still_announced || window_end(current) <= now {
+            let _ = app.emit_all("maintenance_over", current.clone());
+            *draining = None;
+        }
+        return Ok(());
+    }
+
+    let due = windows
+        .into_iter()
+        .find(|w| w.starts_at - Duration::minutes(DRAIN_LEAD_MINS) <= now && window_end(w) > now);
+    if let Some(window) = due {
+        *draining = Some(window.clone());
+        let _ = app.emit_all("maintenance_drain_started", window.clone());
+        tauri::async_runtime::spawn(drain(app.clone(), window));
+    }
+    Ok(())
+}
+
+// Tell the frontend about tables left for maintenance, once the database is open
+pub fn announce_restore(app: &AppHandle) {
+    match db::get(app).and_then(|db| load_snapshot(&db)) {
+        Ok(Some(snapshot)) if restore_ready(&snapshot, Utc::now()) => {
+            let _ = app.emit_all("maintenance_restore_available", snapshot);
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("Failed to read maintenance snapshot: {}", e),
+    }
+}
+
+#[tauri::command]
+pub async fn get_maintenance_status(
+    db: Db<'_>,
+    state: State<'_, MaintenanceState>,
+) -> Result<MaintenanceStatus, String> {
+    let draining = state.draining.lock().map_err(|_| "Maintenance lock poisoned".to_string())?.clone();
+    let snapshot = load_snapshot(&db)?;
+    let restore_ready = snapshot.as_ref().is_some_and(|s| restore_ready(s, Utc::now()));
+    Ok(MaintenanceStatus { draining, snapshot, restore_ready })
+}
+
+// Sit back down at every table left for maintenance, each with the stack it was
+// left with. Tables that could not be rejoined stay in the snapshot.
+#[tauri::command]
+pub async fn restore_after_maintenance(
+    app: AppHandle,
+    db: Db<'_>,
+    api_url: String,
+) -> Result<Vec<RestoreResult>, String> {
+    let Some(mut snapshot) = load_snapshot(&db)? else { return Ok(Vec::new()) };
+    if is_draining(&app) {
+        return Err("Maintenance has not finished yet".to_string());
+    }
+    let mut results = Vec::new();
+    let mut remaining = Vec::new();
+    for seat in snapshot.tables {
+        ledger::before_buy_in(&app, &seat.table_id).await;
+        let result = crate::request_join_table(&api_url, &seat.table_id, seat.chips).await;
+        audit::record(
+            &app,
+            "maintenance_rejoin",
+            json!({ "tableId": seat.table_id, "buyIn": seat.chips }),
+            &result,
+        );
+        if result.is_ok() {
+            ledger::record_buy_in(&app, &seat.table_id, seat.chips);
+        }
+        results.push(RestoreResult { table_id: seat.table_id.clone(), ok: result.is_ok(), error: result.err() });
+        if results.last().is_some_and(|r| !r.ok) {
+            remaining.push(seat);
+        }
+    }
+    snapshot.tables = remaining;
+    save_snapshot(&db, Some(&snapshot).filter(|s| !s.tables.is_empty()))?;
+    Ok(results)
+}
+
+#[tauri::command]
+pub async fn clear_maintenance_snapshot(db: Db<'_>) -> Result<(), String> {
+    save_snapshot(&db, None)
+}
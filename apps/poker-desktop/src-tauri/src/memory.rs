@@ -0,0 +1,221 @@
+// Memory monitoring for long multi-tabling sessions. The in-memory caches report
+// how many entries they hold and roughly how many bytes, and a background check
+// trims them, oldest entries first, when together they pass the cache budget.
+// Passing the RSS cap trims harder and emits `memory_warning` once; it is raised
+// again only after usage has dropped back under the cap.
+
+use crate::db::{self, Database, Db};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+const KEY_SETTINGS: &str = "memory.settings";
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const MB: u64 = 1024 * 1024;
+const MIN_CACHE_BUDGET_MB: u64 = 4;
+const MAX_CACHE_BUDGET_MB: u64 = 16 * 1024;
+const MIN_RSS_CAP_MB: u64 = 128;
+const MAX_RSS_CAP_MB: u64 = 64 * 1024;
+
+static OVER_CAP: AtomicBool = AtomicBool::new(false);
+static TRIMS: AtomicU64 = AtomicU64::new(0);
+static LAST_TRIM: Mutex<Option<DateTime<Utc>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MemorySettings {
+    // Resident size that raises `memory_warning`
+    rss_cap_mb: u64,
+    // Combined size the caches are trimmed back to
+    cache_budget_mb: u64,
+}
+
+impl Default for MemorySettings {
+    fn default() -> Self {
+        Self { rss_cap_mb: 1024, cache_budget_mb: 64 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CacheUsage {
+    pub entries: usize,
+    pub bytes: usize,
+}
+
+// Implemented by every state type holding a cache that grows with play
+pub trait MemoryCache {
+    fn usage(&self) -> CacheUsage;
+    // Drop entries, oldest first, until at most `max_bytes` remain
+    fn trim_to(&self, max_bytes: usize);
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheReport {
+    name: &'static str,
+    entries: usize,
+    approx_bytes: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryReport {
+    // None where the platform does not tell us
+    rss_bytes: Option<u64>,
+    rss_cap_bytes: u64,
+    cache_bytes: usize,
+    cache_budget_bytes: u64,
+    caches: Vec<CacheReport>,
+    trims: u64,
+    last_trim_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MemoryWarning {
+    rss_bytes: u64,
+    rss_cap_bytes: u64,
+}
+
+// Rough in-memory size of a cached value, from its serialized length
+pub fn approx_size<T: Serialize>(value: &T) -> usize {
+    serde_json::to_vec(value).map(|bytes| bytes.len()).unwrap_or_default()
+}
+
+// Remove the oldest entries of `map` until the rest fit in `max_bytes`
+pub fn trim_oldest<K: Clone + Eq + Hash, V>(
+    map: &mut HashMap<K, V>,
+    max_bytes: usize,
+    size: impl Fn(&V) -> usize,
+    at: impl Fn(&V) -> Instant,
+) {
+    let mut total: usize = map.values().map(&size).sum();
+    if total <= max_bytes {
+        return;
+    }
+    let mut oldest: Vec<(Instant, K)> = map.iter().map(|(key, value)| (at(value), key.clone())).collect();
+    oldest.sort_by_key(|(at, _)| *at);
+    for (_, key) in oldest {
+        if total <= max_bytes {
+            break;
+        }
+        if let Some(value) = map.remove(&key) {
+            total = total.saturating_sub(size(&value));
+        }
+    }
+}
+
+fn caches(app: &AppHandle) -> Vec<(&'static str, &dyn MemoryCache)> {
+    vec![
+        ("playerSearch", app.state::<crate::players::PlayerSearchState>().inner() as &dyn MemoryCache),
+        ("tablePreview", app.state::<crate::preview::PreviewState>().inner()),
+        ("handStrength", app.state::<crate::strength::StrengthState>().inner()),
+        ("translation", app.state::<crate::translate::TranslationState>().inner()),
+        ("leakReports", app.state::<crate::leaks::LeakState>().inner()),
+        ("tableStats", app.state::<crate::table_stats::TableStatsState>().inner()),
+        ("tableThumbnails", app.state::<crate::thumbnails::ThumbnailState>().inner()),
+        ("recentActions", app.state::<crate::recent::RecentActionsState>().inner()),
+        ("liveStats", app.state::<crate::live_stats::LiveStatsState>().inner()),
+    ]
+}
+
+fn load_settings(db: &Database) -> Result<MemorySettings, String> {
+    match db.get_value(KEY_SETTINGS)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid memory settings: {}", e)),
+        None => Ok(MemorySettings::default()),
+    }
+}
+
+fn rss_bytes() -> Option<u64> {
+    memory_stats::memory_stats().map(|stats| stats.physical_mem as u64)
+}
+
+// Share `budget` between the caches in proportion to their current size
+fn trim_all(caches: &[(&'static str, &dyn MemoryCache)], total: usize, budget: usize) {
+    for (_, cache) in caches {
+        let bytes = cache.usage().bytes;
+        cache.trim_to((bytes as u128 * budget as u128 / total.max(1) as u128) as usize);
+    }
+    TRIMS.fetch_add(1, Ordering::Relaxed);
+    if let Ok(mut last) = LAST_TRIM.lock() {
+        *last = Some(Utc::now());
+    }
+}
+
+fn check(app: &AppHandle, settings: &MemorySettings) {
+    let caches = caches(app);
+    let total: usize = caches.iter().map(|(_, cache)| cache.usage().bytes).sum();
+    // Settings stored before they were capped can still be out of range
+    let budget = settings.cache_budget_mb.saturating_mul(MB) as usize;
+    let cap = settings.rss_cap_mb.saturating_mul(MB);
+    let rss = rss_bytes();
+
+    match rss {
+        // Over the cap the caches give back half their budget as well
+        Some(rss) if rss > cap => {
+            trim_all(&caches, total, budget.min(total) / 2);
+            if !OVER_CAP.swap(true, Ordering::Relaxed) {
+                let _ = app.emit_all("memory_warning", MemoryWarning { rss_bytes: rss, rss_cap_bytes: cap });
+            }
+        }
+        _ => {
+            OVER_CAP.store(false, Ordering::Relaxed);
+            if total > budget {
+                trim_all(&caches, total, budget);
+            }
+        }
+    }
+}
+
+pub fn start_monitor(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            let settings = db::get(&app).and_then(|db| load_settings(&db)).unwrap_or_default();
+            check(&app, &settings);
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_memory_report(app: AppHandle, db: Db<'_>) -> Result<MemoryReport, String> {
+    let settings = load_settings(&db)?;
+    let caches: Vec<CacheReport> = caches(&app)
+        .into_iter()
+        .map(|(name, cache)| {
+            let usage = cache.usage();
+            CacheReport { name, entries: usage.entries, approx_bytes: usage.bytes }
+        })
+        .collect();
+    Ok(MemoryReport {
+        rss_bytes: rss_bytes(),
+        rss_cap_bytes: settings.rss_cap_mb.saturating_mul(MB),
+        cache_bytes: caches.iter().map(|c| c.approx_bytes).sum(),
+        cache_budget_bytes: settings.cache_budget_mb.saturating_mul(MB),
+        caches,
+        trims: TRIMS.load(Ordering::Relaxed),
+        last_trim_at: LAST_TRIM.lock().ok().and_then(|last| *last),
+    })
+}
+
+#[tauri::command]
+pub async fn get_memory_settings(db: Db<'_>) -> Result<MemorySettings, String> {
+    load_settings(&db)
+}
+
+#[tauri::command]
+pub async fn set_memory_settings(db: Db<'_>, settings: MemorySettings) -> Result<MemorySettings, String> {
+    let settings = MemorySettings {
+        rss_cap_mb: settings.rss_cap_mb.clamp(MIN_RSS_CAP_MB, MAX_RSS_CAP_MB),
+        cache_budget_mb: settings.cache_budget_mb.clamp(MIN_CACHE_BUDGET_MB, MAX_CACHE_BUDGET_MB),
+    };
+    let data = serde_json::to_string(&settings).map_err(|e| e.to_string())?;
+    db.set_value(KEY_SETTINGS, &data)?;
+    Ok(settings)
+}
@@ -0,0 +1,495 @@
+// End-to-end encrypted direct messages between friends. Each install has an X25519
+// identity key kept in the OS keyring; only the public half is published. A message
+// is sealed with ChaCha20-Poly1305 under a key derived (HKDF-SHA256) from the
+// X25519 shared secret of the two identities, so the backend only relays ciphertext.
+// Contacts' keys are pinned on first use, whether fetched to send or first seen on a
+// message received: a changed key stops sending, and messages under it are not
+// opened, until the player accepts it, and comparing safety numbers out of band
+// marks a contact as verified. A key pinned from a received message is only as
+// trustworthy as the relay that first delivered it, until it is verified. The key
+// is static per pair - there is no ratchet yet, so a leaked identity key exposes
+// past messages.
+
+use crate::accounts;
+use crate::audit;
+use crate::db::{Database, Db};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use chrono::{DateTime, Utc};
+use hkdf::Hkdf;
+use keyring::Entry;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+const KEYRING_IDENTITY: &str = "dm-identity";
+const KEY_CONTACTS: &str = "messages.contacts";
+const KEY_PUBLISHED: &str = "messages.published_key";
+const HKDF_INFO: &[u8] = b"primo-poker-dm-v1";
+const ENVELOPE_VERSION: u32 = 1;
+const MAX_MESSAGE_LEN: usize = 2000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Contact {
+    public_key: String,
+    verified: bool,
+    pinned_at: DateTime<Utc>,
+    // A different key the backend offered since pinning, waiting to be accepted
+    #[serde(default)]
+    pending_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Envelope {
+    #[serde(default)]
+    id: Option<String>,
+    sender_id: String,
+    recipient_id: String,
+    sender_key: String,
+    recipient_key: String,
+    nonce: String,
+    ciphertext: String,
+    #[serde(default)]
+    sent_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrivateMessage {
+    id: Option<String>,
+    sender_id: String,
+    recipient_id: String,
+    sent_at: Option<DateTime<Utc>>,
+    outgoing: bool,
+    // None when the message could not be opened; `error` says why
+    text: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyVerification {
+    player_id: String,
+    // Same on both sides; read aloud or compared in person
+    safety_number: String,
+    own_fingerprint: String,
+    contact_fingerprint: String,
+    verified: bool,
+    key_changed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct KeyChanged {
+    player_id: String,
+    verified_before: bool,
+}
+
+fn encode(bytes: &[u8]) -> String {
+    STANDARD.encode(bytes)
+}
+
+fn decode_key(key: &str) -> Result<PublicKey, String> {
+    let bytes: [u8; 32] = STANDARD
+        .decode(key)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| "Invalid public key".to_string())?;
+    Ok(PublicKey::from(bytes))
+}
+
+// Identity key from the keyring, created on first use
+fn identity() -> Result<StaticSecret, String> {
+    let entry = Entry::new("primo-poker", &accounts::keyring_key(KEYRING_IDENTITY))
+        .map_err(|e| format!("Keyring error: {}", e))?;
+    match entry.get_password() {
+        Ok(stored) => {
+            let bytes: [u8; 32] = STANDARD
+                .decode(stored)
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| "Stored message key is corrupt".to_string())?;
+            Ok(StaticSecret::from(bytes))
+        }
+        Err(keyring::Error::NoEntry) => {
+            let secret = StaticSecret::random_from_rng(OsRng);
+            entry
+                .set_password(&encode(&secret.to_bytes()))
+                .map_err(|e| format!("Failed to store message key: {}", e))?;
+            Ok(secret)
+        }
+        Err(e) => Err(format!("Failed to read message key: {}", e)),
+    }
+}
+
+async fn request<T: serde::de::DeserializeOwned>(builder: reqwest::RequestBuilder) -> Result<T, String> {
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+
+    let response = crate::http::send(builder.header("Authorization", format!("Bearer {}", token)))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("Message request failed: {}", error_text));
+    }
+
+    let api_response: crate::ApiResponse<T> = response.json().await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    if api_response.success {
+        api_response.data.ok_or_else(|| "No data returned".to_string())
+    } else {
+        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    }
+}
+
+// Own identity, publishing the public key if the backend does not have it yet
+async fn own_key(db: &Database, api_url: &str) -> Result<(StaticSecret, String), String> {
+    let secret = identity()?;
+    let public = encode(PublicKey::from(&secret).as_bytes());
+    if db.get_value(KEY_PUBLISHED)?.as_deref() != Some(public.as_str()) {
+        let client = crate::create_http_client()?;
+        request::<serde_json::Value>(client
+            .put(format!("{}/api/messages/keys", api_url))
+            .json(&json!({ "publicKey": public })))
+            .await?;
+        db.set_value(KEY_PUBLISHED, &public)?;
+    }
+    Ok((secret, public))
+}
+
+fn contacts(db: &Database) -> Result<HashMap<String, Contact>, String> {
+    match db.get_value(KEY_CONTACTS)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid message contacts: {}", e)),
+        None => Ok(HashMap::new()),
+    }
+}
+
+// Players the player exchanges messages with, who count as friends elsewhere
+pub fn contact_ids(db: &Database) -> Result<Vec<String>, String> {
+    Ok(contacts(db)?.into_keys().collect())
+}
+
+fn save_contacts(db: &Database, contacts: &HashMap<String, Contact>) -> Result<(), String> {
+    let data = serde_json::to_string(contacts).map_err(|e| e.to_string())?;
+    db.set_value(KEY_CONTACTS, &data)
+}
+
+// The contact's key as the backend has it, pinned when seen for the first time.
+// A key that differs from the pinned one is held back and reported.
+async fn contact_key(app: &AppHandle, db: &Database, api_url: &str, player_id: &str) -> Result<Contact, String> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct KeyResponse {
+        public_key: String,
+    }
+
+    let client = crate::create_http_client()?;
+    let remote: KeyResponse = request(client.get(format!("{}/api/messages/keys/{}", api_url, player_id))).await?;
+    decode_key(&remote.public_key)?;
+
+    let mut contacts = contacts(db)?;
+    let contact = match contacts.get_mut(player_id) {
+        Some(contact) if contact.public_key == remote.public_key => {
+            contact.pending_key = None;
+            contact.clone()
+        }
+        Some(contact) => {
+            if contact.pending_key.as_deref() != Some(remote.public_key.as_str()) {
+                contact.pending_key = Some(remote.public_key);
+                let _ = app.emit_all("dm_key_changed", KeyChanged {
+                    player_id: player_id.to_string(),
+                    verified_before: contact.verified,
+                });
+            }
+            contact.clone()
+        }
+        None => {
+            let contact = Contact { public_key: remote.public_key, verified: false, pinned_at: Utc::now(), pending_key: None };
+            contacts.insert(player_id.to_string(), contact.clone());
+            contact
+        }
+    };
+    save_contacts(db, &contacts)?;
+    Ok(contact)
+}
+
+// Message key for the pair; the same on both ends. Keys are ordered so the order the
+// two sides pass them in does not matter.
+fn message_key(secret: &StaticSecret, own: &PublicKey, other: &PublicKey) -> Result<[u8; 32], String> {
+    let shared = secret.diffie_hellman(other);
+    if !shared.was_contributory() {
+        return Err("Contact key is not usable".to_string());
+    }
+    let (first, second) = if own.as_bytes() <= other.as_bytes() { (own, other) } else { (other, own) };
+    let info: Vec<u8> = HKDF_INFO.iter().chain(first.as_bytes()).chain(second.as_bytes()).copied().collect();
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(None, shared.as_bytes())
+        .expand(&info, &mut key)
+        .map_err(|_| "Key derivation failed".to_string())?;
+    Ok(key)
+}
+
+// The direction is bound into the ciphertext so a message cannot be replayed as if
+// the other side had sent it
+fn associated_data(sender_key: &str, recipient_key: &str) -> Vec<u8> {
+    format!("{}|{}|{}", ENVELOPE_VERSION, sender_key, recipient_key).into_bytes()
+}
+
+fn seal(secret: &StaticSecret, own: &str, other: &str, text: &str) -> Result<(String, String), String> {
+    let key = message_key(secret, &decode_key(own)?, &decode_key(other)?)?;
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(&key))
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: text.as_bytes(), aad: &associated_data(own, other) })
+        .map_err(|_| "Encryption failed".to_string())?;
+    Ok((encode(&nonce), encode(&ciphertext)))
+}
+
+fn open(secret: &StaticSecret, own: &str, envelope: &Envelope) -> Result<(bool, String), String> {
+    let outgoing = envelope.sender_key == own;
+    let other = if outgoing { &envelope.recipient_key } else { &envelope.sender_key };
+    if !outgoing && envelope.recipient_key != own {
+        return Err("Sealed for a different key".to_string());
+    }
+    let key = message_key(secret, &decode_key(own)?, &decode_key(other)?)?;
+    let nonce = STANDARD.decode(&envelope.nonce).ok().filter(|n| n.len() == 12).ok_or_else(|| "Invalid nonce".to_string())?;
+    let ciphertext = STANDARD.decode(&envelope.ciphertext).map_err(|_| "Invalid ciphertext".to_string())?;
+    let plain = ChaCha20Poly1305::new(Key::from_slice(&key))
+        .decrypt(Nonce::from_slice(&nonce), Payload {
+            msg: &ciphertext,
+            aad: &associated_data(&envelope.sender_key, &envelope.recipient_key),
+        })
+        .map_err(|_| "Message could not be decrypted".to_string())?;
+    let text = String::from_utf8(plain).map_err(|_| "Message is not valid text".to_string())?;
+    Ok((outgoing, text))
+}
+
+// Open an envelope, checking the contact's key against the pinned one. A sender with
+// no pinned key has the one they wrote with pinned once the message opens; a sender
+// writing with another key than the pinned one has it held back as a pending change.
+// Returns whether `contacts` changed.
+fn read(secret: &StaticSecret, own: &str, contacts: &mut HashMap<String, Contact>, envelope: Envelope) -> (PrivateMessage, bool) {
+    let outgoing = envelope.sender_key == own;
+    let (contact_id, contact_key) = if outgoing {
+        (&envelope.recipient_id, &envelope.recipient_key)
+    } else {
+        (&envelope.sender_id, &envelope.sender_key)
+    };
+    let mut changed = false;
+    let result = match contacts.get_mut(contact_id) {
+        Some(contact) if &contact.public_key != contact_key => {
+            if !outgoing && contact.pending_key.as_ref() != Some(contact_key) && decode_key(contact_key).is_ok() {
+                contact.pending_key = Some(contact_key.clone());
+                changed = true;
+            }
+            Err("Sent with a key that is not the pinned one".to_string())
+        }
+        Some(_) => open(secret, own, &envelope),
+        None => {
+            let opened = open(secret, own, &envelope);
+            if opened.is_ok() && !outgoing {
+                let contact = Contact { public_key: contact_key.clone(), verified: false, pinned_at: Utc::now(), pending_key: None };
+                contacts.insert(contact_id.clone(), contact);
+                changed = true;
+            }
+            opened
+        }
+    };
+    let (text, error) = match result {
+        Ok((_, text)) => (Some(text), None),
+        Err(e) => (None, Some(e)),
+    };
+    let message = PrivateMessage {
+        id: envelope.id,
+        sender_id: envelope.sender_id,
+        recipient_id: envelope.recipient_id,
+        sent_at: envelope.sent_at,
+        outgoing,
+        text,
+        error,
+    };
+    (message, changed)
+}
+
+// Open envelopes against the stored contacts, saving any key they pinned
+fn read_all(db: &Database, secret: &StaticSecret, own: &str, envelopes: Vec<Envelope>) -> Result<Vec<PrivateMessage>, String> {
+    let mut contacts = contacts(db)?;
+    let mut changed = false;
+    let messages = envelopes
+        .into_iter()
+        .map(|envelope| {
+            let (message, pinned) = read(secret, own, &mut contacts, envelope);
+            changed |= pinned;
+            message
+        })
+        .collect();
+    if changed {
+        save_contacts(db, &contacts)?;
+    }
+    Ok(messages)
+}
+
+// 30 digits in groups of five from both public keys, in a fixed order
+fn safety_number(own: &str, other: &str) -> Result<String, String> {
+    let own = decode_key(own)?;
+    let other = decode_key(other)?;
+    let (first, second) = if own.as_bytes() <= other.as_bytes() { (own, other) } else { (other, own) };
+    let mut hasher = Sha256::new();
+    hasher.update(HKDF_INFO);
+    hasher.update(first.as_bytes());
+    hasher.update(second.as_bytes());
+    let digest = hasher.finalize();
+    let groups: Vec<String> = digest
+        .chunks(5)
+        .take(6)
+        .map(|chunk| {
+            let value = chunk.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+            format!("{:05}", value % 100_000)
+        })
+        .collect();
+    Ok(groups.join(" "))
+}
+
+fn fingerprint(key: &str) -> String {
+    let digest = Sha256::digest(key.as_bytes());
+    digest.iter().take(8).map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(":")
+}
+
+#[tauri::command]
+pub async fn send_private_message(
+    app: AppHandle,
+    db: Db<'_>,
+    api_url: String,
+    recipient_id: String,
+    text: String,
+) -> Result<PrivateMessage, String> {
+    let text = text.trim().to_string();
+    if text.is_empty() || text.chars().count() > MAX_MESSAGE_LEN {
+        return Err(format!("Messages must be 1 to {} characters", MAX_MESSAGE_LEN));
+    }
+    let (secret, own) = own_key(&db, &api_url).await?;
+    let contact = contact_key(&app, &db, &api_url, &recipient_id).await?;
+    if contact.pending_key.is_some() {
+        return Err("This contact's key has changed; verify and accept it before sending".to_string());
+    }
+
+    let (nonce, ciphertext) = seal(&secret, &own, &contact.public_key, &text)?;
+    let client = crate::create_http_client()?;
+    let result = request::<Envelope>(client
+        .post(format!("{}/api/messages", api_url))
+        .json(&json!({
+            "recipientId": recipient_id,
+            "senderKey": own,
+            "recipientKey": contact.public_key,
+            "nonce": nonce,
+            "ciphertext": ciphertext,
+            "version": ENVELOPE_VERSION,
+        })))
+        .await;
+    audit::record(&app, "send_private_message", json!({ "recipientId": recipient_id }), &result);
+
+    let (message, _) = read(&secret, &own, &mut contacts(&db)?, result?);
+    Ok(message)
+}
+
+// Conversation with one contact, opened locally
+#[tauri::command]
+pub async fn get_private_messages(
+    db: Db<'_>,
+    api_url: String,
+    player_id: String,
+    limit: Option<u32>,
+) -> Result<Vec<PrivateMessage>, String> {
+    let (secret, own) = own_key(&db, &api_url).await?;
+    let client = crate::create_http_client()?;
+    let envelopes: Vec<Envelope> = request(client
+        .get(format!("{}/api/messages/{}", api_url, player_id))
+        .query(&[("limit", limit.unwrap_or(50).min(200))]))
+        .await?;
+    read_all(&db, &secret, &own, envelopes)
+}
+
+// Open an envelope pushed over the WebSocket
+#[tauri::command]
+pub async fn decrypt_private_message(db: Db<'_>, envelope: Envelope) -> Result<PrivateMessage, String> {
+    let secret = identity()?;
+    let own = encode(PublicKey::from(&secret).as_bytes());
+    let mut messages = read_all(&db, &secret, &own, vec![envelope])?;
+    messages.pop().ok_or_else(|| "No message".to_string())
+}
+
+#[tauri::command]
+pub async fn get_key_verification(
+    app: AppHandle,
+    db: Db<'_>,
+    api_url: String,
+    player_id: String,
+) -> Result<KeyVerification, String> {
+    let (_, own) = own_key(&db, &api_url).await?;
+    let contact = contact_key(&app, &db, &api_url, &player_id).await?;
+    // A changed key is shown as what it would be, so it can be checked before accepting
+    let shown = contact.pending_key.clone().unwrap_or_else(|| contact.public_key.clone());
+    Ok(KeyVerification {
+        player_id,
+        safety_number: safety_number(&own, &shown)?,
+        own_fingerprint: fingerprint(&own),
+        contact_fingerprint: fingerprint(&shown),
+        verified: contact.verified && contact.pending_key.is_none(),
+        key_changed: contact.pending_key.is_some(),
+    })
+}
+
+// Mark the contact verified after comparing safety numbers; a pending key change is
+// accepted at the same time
+#[tauri::command]
+pub async fn mark_contact_verified(
+    app: AppHandle,
+    db: Db<'_>,
+    player_id: String,
+    safety_number: String,
+) -> Result<(), String> {
+    let secret = identity()?;
+    let own = encode(PublicKey::from(&secret).as_bytes());
+    let mut contacts = contacts(&db)?;
+    let contact = contacts.get_mut(&player_id).ok_or_else(|| "No key known for this contact".to_string())?;
+    let key = contact.pending_key.clone().unwrap_or_else(|| contact.public_key.clone());
+
+    let normalize = |s: &str| s.chars().filter(char::is_ascii_digit).collect::<String>();
+    let result = if normalize(&self::safety_number(&own, &key)?) == normalize(&safety_number) {
+        Ok(())
+    } else {
+        Err("Safety numbers do not match".to_string())
+    };
+    audit::record(&app, "verify_contact_key", json!({ "playerId": player_id }), &result);
+    result?;
+
+    *contact = Contact { public_key: key, verified: true, pinned_at: Utc::now(), pending_key: None };
+    save_contacts(&db, &contacts)
+}
+
+// Trust a changed key without verifying it
+#[tauri::command]
+pub async fn accept_contact_key(app: AppHandle, db: Db<'_>, player_id: String) -> Result<(), String> {
+    let mut contacts = contacts(&db)?;
+    let result = match contacts.get_mut(&player_id).and_then(|contact| contact.pending_key.take()) {
+        Some(key) => {
+            contacts.insert(player_id.clone(), Contact { public_key: key, verified: false, pinned_at: Utc::now(), pending_key: None });
+            save_contacts(&db, &contacts)
+        }
+        None => Err("This contact's key has not changed".to_string()),
+    };
+    audit::record(&app, "accept_contact_key", json!({ "playerId": player_id }), &result);
+    result
+}
@@ -0,0 +1,69 @@
+// Process-wide network counters. WebSocket traffic is counted twice - bytes on the
+// wire and the same messages uncompressed - so the saving from compression can be
+// read off directly.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static WS_RECEIVED: AtomicU64 = AtomicU64::new(0);
+static WS_RECEIVED_RAW: AtomicU64 = AtomicU64::new(0);
+static WS_SENT: AtomicU64 = AtomicU64::new(0);
+static WS_SENT_RAW: AtomicU64 = AtomicU64::new(0);
+static WS_COMPRESSED_MESSAGES: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkMetrics {
+    ws_bytes_received: u64,
+    ws_bytes_received_uncompressed: u64,
+    ws_bytes_sent: u64,
+    ws_bytes_sent_uncompressed: u64,
+    ws_compressed_messages: u64,
+    // Share of uncompressed bytes that did not cross the wire, 0.0 to 1.0
+    ws_savings: f64,
+}
+
+pub fn record_ws_received(wire: usize, raw: usize, compressed: bool) {
+    WS_RECEIVED.fetch_add(wire as u64, Ordering::Relaxed);
+    WS_RECEIVED_RAW.fetch_add(raw as u64, Ordering::Relaxed);
+    if compressed {
+        WS_COMPRESSED_MESSAGES.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub fn record_ws_sent(wire: usize, raw: usize, compressed: bool) {
+    WS_SENT.fetch_add(wire as u64, Ordering::Relaxed);
+    WS_SENT_RAW.fetch_add(raw as u64, Ordering::Relaxed);
+    if compressed {
+        WS_COMPRESSED_MESSAGES.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub fn snapshot() -> NetworkMetrics {
+    let received = WS_RECEIVED.load(Ordering::Relaxed);
+    let received_raw = WS_RECEIVED_RAW.load(Ordering::Relaxed);
+    let sent = WS_SENT.load(Ordering::Relaxed);
+    let sent_raw = WS_SENT_RAW.load(Ordering::Relaxed);
+    let raw = received_raw + sent_raw;
+    NetworkMetrics {
+        ws_bytes_received: received,
+        ws_bytes_received_uncompressed: received_raw,
+        ws_bytes_sent: sent,
+        ws_bytes_sent_uncompressed: sent_raw,
+        ws_compressed_messages: WS_COMPRESSED_MESSAGES.load(Ordering::Relaxed),
+        ws_savings: if raw == 0 { 0.0 } else { 1.0 - (received + sent) as f64 / raw as f64 },
+    }
+}
+
+#[tauri::command]
+pub async fn get_network_metrics() -> Result<NetworkMetrics, String> {
+    Ok(snapshot())
+}
+
+#[tauri::command]
+pub async fn reset_network_metrics() -> Result<NetworkMetrics, String> {
+    for counter in [&WS_RECEIVED, &WS_RECEIVED_RAW, &WS_SENT, &WS_SENT_RAW, &WS_COMPRESSED_MESSAGES] {
+        counter.store(0, Ordering::Relaxed);
+    }
+    Ok(snapshot())
+}
@@ -0,0 +1,223 @@
+use chrono::Utc;
+use rusqlite::Connection;
+use std::path::Path;
+
+// A single schema step. Versions are applied in order and recorded in PRAGMA user_version.
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub sql: &'static str,
+    // Destructive migrations drop or rewrite data, so the database is backed up first
+    pub destructive: bool,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        sql: "CREATE TABLE IF NOT EXISTS hands (
+                id TEXT PRIMARY KEY,
+                table_id TEXT NOT NULL,
+                played_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                data TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_hands_updated_at ON hands(updated_at);
+            CREATE INDEX IF NOT EXISTS idx_hands_played_at ON hands(played_at);
+            CREATE TABLE IF NOT EXISTS notes (
+                player_id TEXT PRIMARY KEY,
+                text TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS kv (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );",
+        destructive: false,
+    },
+    Migration {
+        version: 2,
+        name: "audit_log",
+        sql: "CREATE TABLE audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                action TEXT NOT NULL,
+                detail TEXT NOT NULL,
+                success INTEGER NOT NULL,
+                error TEXT,
+                prev_hash TEXT NOT NULL,
+                hash TEXT NOT NULL
+            );
+            CREATE INDEX idx_audit_log_action ON audit_log(action);
+            CREATE TRIGGER audit_log_no_update BEFORE UPDATE ON audit_log
+            BEGIN SELECT RAISE(ABORT, 'audit log is append-only'); END;
+            CREATE TRIGGER audit_log_no_delete BEFORE DELETE ON audit_log
+            BEGIN SELECT RAISE(ABORT, 'audit log is append-only'); END;",
+        destructive: false,
+    },
+    Migration {
+        version: 3,
+        name: "hand_ev",
+        sql: "CREATE TABLE hand_ev (
+                hand_id TEXT PRIMARY KEY,
+                hand_updated_at INTEGER NOT NULL,
+                all_in INTEGER NOT NULL,
+                street TEXT,
+                equity REAL,
+                actual_net INTEGER NOT NULL,
+                expected_net REAL
+            );",
+        destructive: false,
+    },
+    Migration {
+        version: 4,
+        name: "hand_reports",
+        sql: "CREATE TABLE hand_reports (
+                id TEXT PRIMARY KEY,
+                hand_id TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                players TEXT NOT NULL,
+                status TEXT NOT NULL,
+                submitted_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE INDEX idx_hand_reports_hand_id ON hand_reports(hand_id);",
+        destructive: false,
+    },
+    Migration {
+        version: 5,
+        name: "note_labels",
+        sql: "ALTER TABLE notes ADD COLUMN label TEXT;",
+        destructive: false,
+    },
+    Migration {
+        version: 6,
+        name: "chat_reports",
+        sql: "CREATE TABLE chat_messages (
+                id TEXT PRIMARY KEY,
+                table_id TEXT NOT NULL,
+                sender_id TEXT NOT NULL,
+                username TEXT NOT NULL,
+                message TEXT NOT NULL,
+                sent_at INTEGER NOT NULL
+            );
+            CREATE INDEX idx_chat_messages_table ON chat_messages(table_id, sent_at);
+            CREATE INDEX idx_chat_messages_sent_at ON chat_messages(sent_at);
+            CREATE TABLE chat_reports (
+                id TEXT PRIMARY KEY,
+                message_id TEXT NOT NULL,
+                table_id TEXT NOT NULL,
+                sender_id TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                status TEXT NOT NULL,
+                submitted_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE INDEX idx_chat_reports_message_id ON chat_reports(message_id);",
+        destructive: false,
+    },
+    Migration {
+        version: 7,
+        name: "hand_summaries",
+        sql: "CREATE TABLE hand_summaries (
+                id TEXT PRIMARY KEY,
+                table_id TEXT NOT NULL,
+                played_at INTEGER NOT NULL,
+                data TEXT NOT NULL
+            );
+            CREATE INDEX idx_hand_summaries_played_at ON hand_summaries(played_at);",
+        destructive: false,
+    },
+    Migration {
+        version: 8,
+        name: "balance_ledger",
+        sql: "CREATE TABLE ledger_sessions (
+                id TEXT PRIMARY KEY,
+                started_at INTEGER NOT NULL,
+                ended_at INTEGER,
+                opening_balance INTEGER,
+                expected_balance INTEGER,
+                actual_balance INTEGER,
+                status TEXT NOT NULL,
+                reconciled_at INTEGER
+            );
+            CREATE INDEX idx_ledger_sessions_started_at ON ledger_sessions(started_at);
+            CREATE TABLE ledger_entries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                amount INTEGER NOT NULL,
+                table_id TEXT,
+                reference TEXT,
+                source TEXT NOT NULL,
+                transaction_id TEXT,
+                recorded_at INTEGER NOT NULL
+            );
+            CREATE INDEX idx_ledger_entries_session ON ledger_entries(session_id);
+            CREATE UNIQUE INDEX idx_ledger_entries_reference ON ledger_entries(session_id, kind, reference);",
+        destructive: false,
+    },
+];
+
+pub fn current_version(conn: &Connection) -> rusqlite::Result<u32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+// Copy the live database to `backups/` using VACUUM INTO so the snapshot is consistent
+fn backup(conn: &Connection, db_path: &Path, from_version: u32) -> Result<(), String> {
+    let dir = db_path
+        .parent()
+        .map(|p| p.join("backups"))
+        .ok_or_else(|| "Database path has no parent directory".to_string())?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+
+    let target = dir.join(format!(
+        "primo-poker-v{}-{}.db",
+        from_version,
+        Utc::now().format("%Y%m%d%H%M%S")
+    ));
+
+    conn.execute("VACUUM INTO ?1", [target.to_string_lossy()])
+        .map_err(|e| format!("Failed to back up database: {}", e))?;
+
+    Ok(())
+}
+
+fn apply(conn: &mut Connection, migration: &Migration) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute_batch(migration.sql)?;
+    tx.pragma_update(None, "user_version", migration.version)?;
+    tx.commit()
+}
+
+// Bring the schema up to date. Each migration runs in its own transaction, so a failure
+// leaves the database at the last successfully applied version.
+pub fn run(conn: &mut Connection, db_path: &Path) -> Result<u32, String> {
+    let mut version = current_version(conn)
+        .map_err(|e| format!("Failed to read schema version: {}", e))?;
+
+    let latest = MIGRATIONS.last().map(|m| m.version).unwrap_or(0);
+    if version > latest {
+        return Err(format!(
+            "Database schema v{} is newer than this client supports (v{})",
+            version, latest
+        ));
+    }
+
+    let pending: Vec<&Migration> = MIGRATIONS.iter().filter(|m| m.version > version).collect();
+    if pending.iter().any(|m| m.destructive) && version > 0 {
+        backup(conn, db_path, version)?;
+    }
+
+    for migration in pending {
+        apply(conn, migration).map_err(|e| format!(
+            "Migration v{} ({}) failed: {}",
+            migration.version, migration.name, e
+        ))?;
+
+        version = migration.version;
+    }
+
+    Ok(version)
+}
@@ -0,0 +1,230 @@
+// Missed blinds. A player sitting out while the blinds go past their seat owes them
+// on coming back: the big blind live, counting towards their bet, and the small blind
+// dead. They can post what they owe and play the next hand, or wait for the big
+// blind to reach them and owe nothing. The tracker follows the table event stream the
+// views forward, as live_stats does, and prices both choices from the blinds last
+// reported for the table; the server does the posting, and the dead blinds it takes
+// are checked by the table mirror.
+
+use crate::audit;
+use crate::claims;
+use crate::table_state::TableMirror;
+use crate::thumbnails::ThumbnailState;
+use crate::ws::WsMessage;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+const MAX_TABLES: usize = 24;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Owed {
+    small_blind: bool,
+    big_blind: bool,
+}
+
+#[derive(Default)]
+struct Tracker {
+    mirror: TableMirror,
+    owed: Owed,
+    // Seats the blinds were on in the last hand
+    small_blind_seat: Option<u8>,
+    big_blind_seat: Option<u8>,
+}
+
+#[derive(Default)]
+pub struct MissedBlindsState {
+    tables: Mutex<HashMap<String, Tracker>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReturnChoice {
+    PostNow,
+    WaitForBigBlind,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReturnOptions {
+    table_id: String,
+    owed: Owed,
+    // Posting now: the live part counts towards the first bet, the dead part does not
+    live: u32,
+    dead: u32,
+    post_now_cost: u32,
+    // Hands from now until the big blind reaches the player, when their seat is known
+    hands_until_big_blind: Option<u32>,
+}
+
+fn seat_of(mirror: &TableMirror, player_id: Option<&str>) -> Option<u8> {
+    mirror.player(player_id?)?.position.as_ref().map(|p| p.seat)
+}
+
+// Whether moving clockwise from `from` to `to` goes past `seat`, `to` included
+fn passed(seat: u8, from: u8, to: u8) -> bool {
+    match from.cmp(&to) {
+        Ordering::Less => seat > from && seat <= to,
+        Ordering::Greater => seat > from || seat <= to,
+        Ordering::Equal => false,
+    }
+}
+
+impl Tracker {
+    fn hand_started(&mut self, hero_id: &str) {
+        let small_blind = seat_of(&self.mirror, self.mirror.small_blind_id.as_deref());
+        let big_blind = seat_of(&self.mirror, self.mirror.big_blind_id.as_deref());
+        if let Some(hero) = self.mirror.player(hero_id) {
+            let seat = hero.position.as_ref().map(|p| p.seat);
+            if !hero.is_sitting_out() {
+                // Dealt in: what was owed has been posted, or the big blind came round
+                self.owed = Owed::default();
+            } else if let Some(seat) = seat {
+                if let (Some(from), Some(to)) = (self.small_blind_seat, small_blind) {
+                    self.owed.small_blind |= passed(seat, from, to);
+                }
+                if let (Some(from), Some(to)) = (self.big_blind_seat, big_blind) {
+                    self.owed.big_blind |= passed(seat, from, to);
+                }
+            }
+        }
+        self.small_blind_seat = small_blind.or(self.small_blind_seat);
+        self.big_blind_seat = big_blind.or(self.big_blind_seat);
+    }
+
+    // Players still dealt in between the big blind and the hero, plus the hand in
+    // which the blind moves on to the hero
+    fn hands_until_big_blind(&self, hero_id: &str) -> Option<u32> {
+        let hero = seat_of(&self.mirror, Some(hero_id))?;
+        let big_blind = self.big_blind_seat?;
+        let between = self
+            .mirror
+            .players
+            .iter()
+            .filter(|p| !p.is_sitting_out() && p.id != hero_id)
+            .filter_map(|p| p.position.as_ref().map(|pos| pos.seat))
+            .filter(|&seat| seat != hero && passed(seat, big_blind, hero))
+            .count() as u32;
+        Some(between + 1)
+    }
+}
+
+fn options(state: &MissedBlindsState, thumbnails: &ThumbnailState, table_id: &str) -> Result<ReturnOptions, String> {
+    let hero_id = claims::current()?.user_id;
+    let tables = state.tables.lock().map_err(|_| "Missed blinds lock poisoned".to_string())?;
+    let tracker = tables.get(table_id);
+    let owed = tracker.map(|t| t.owed).unwrap_or_default();
+    let (small_blind, big_blind) = match thumbnails.latest(table_id)? {
+        Some((_, rules, _)) if rules.big_blind > 0 => (rules.small_blind, rules.big_blind),
+        _ if owed == Owed::default() => (0, 0),
+        _ => return Err(format!("The blinds at table {} are not known yet", table_id)),
+    };
+    let live = if owed.big_blind { big_blind } else { 0 };
+    let dead = if owed.small_blind { small_blind } else { 0 };
+    Ok(ReturnOptions {
+        table_id: table_id.to_string(),
+        owed,
+        live,
+        dead,
+        post_now_cost: live + dead,
+        hands_until_big_blind: tracker.and_then(|t| t.hands_until_big_blind(&hero_id)),
+    })
+}
+
+async fn request_sit_in(api_url: &str, table_id: &str, body: serde_json::Value) -> Result<(), String> {
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+    let client = crate::create_http_client()?;
+    let response = crate::http::send(client.post(format!("{}/api/tables/{}/sit-in", api_url, table_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&body))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        Err(format!("Failed to sit back in: {}", error_text))
+    }
+}
+
+// Feed one frame received at `table_id`. Raises `missed_blinds_owed` when the player
+// starts owing another blind.
+#[tauri::command]
+pub async fn record_blind_event(
+    app: AppHandle,
+    state: State<'_, MissedBlindsState>,
+    thumbnails: State<'_, ThumbnailState>,
+    table_id: String,
+    message: WsMessage,
+) -> Result<(), String> {
+    let hero_id = claims::current()?.user_id;
+    let small_blind = thumbnails.latest(&table_id)?.map_or(0, |(_, rules, _)| rules.small_blind);
+    let mut tables = state.tables.lock().map_err(|_| "Missed blinds lock poisoned".to_string())?;
+    if !tables.contains_key(&table_id) && tables.len() >= MAX_TABLES {
+        if let Some(idle) = tables.iter().find(|(_, t)| t.owed == Owed::default()).map(|(id, _)| id.clone()) {
+            tables.remove(&idle);
+        }
+    }
+    let tracker = tables.entry(table_id.clone()).or_default();
+
+    // Hand events carry no blinds; keep the table's so dead blinds can be checked
+    if tracker.mirror.small_blind == 0 {
+        tracker.mirror.small_blind = small_blind;
+    }
+    tracker.mirror.apply(&message)?;
+    if message.kind == "hand_started" {
+        let before = tracker.owed;
+        tracker.hand_started(&hero_id);
+        if tracker.owed != before && tracker.owed != Owed::default() {
+            let _ = app.emit_all("missed_blinds_owed", json!({ "tableId": table_id, "owed": tracker.owed }));
+        }
+    }
+    Ok(())
+}
+
+// What coming back costs at `table_id`: posting what is owed now, or waiting
+#[tauri::command]
+pub async fn get_return_options(
+    state: State<'_, MissedBlindsState>,
+    thumbnails: State<'_, ThumbnailState>,
+    table_id: String,
+) -> Result<ReturnOptions, String> {
+    options(&state, &thumbnails, &table_id)
+}
+
+// Sit back in, posting the missed blinds now or waiting for the big blind
+#[tauri::command]
+pub async fn return_to_table(
+    app: AppHandle,
+    state: State<'_, MissedBlindsState>,
+    thumbnails: State<'_, ThumbnailState>,
+    api_url: String,
+    table_id: String,
+    choice: ReturnChoice,
+) -> Result<ReturnOptions, String> {
+    let options = options(&state, &thumbnails, &table_id)?;
+    let post_now = choice == ReturnChoice::PostNow;
+    let body = json!({
+        "postMissedBlinds": post_now,
+        "waitForBigBlind": !post_now,
+        "liveBlind": if post_now { options.live } else { 0 },
+        "deadBlind": if post_now { options.dead } else { 0 },
+    });
+    let result = request_sit_in(&api_url, &table_id, body.clone()).await;
+    audit::record(&app, "return_to_table", json!({ "tableId": table_id, "request": body }), &result);
+    result?;
+    Ok(options)
+}
+
+// Forget a table once the player has left it
+#[tauri::command]
+pub async fn clear_missed_blinds(state: State<'_, MissedBlindsState>, table_id: String) -> Result<(), String> {
+    state.tables.lock().map_err(|_| "Missed blinds lock poisoned".to_string())?.remove(&table_id);
+    Ok(())
+}
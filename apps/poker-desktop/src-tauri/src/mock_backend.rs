@@ -0,0 +1,300 @@
+// Dev-only stand-in for the Cloudflare backend. Implements the subset of the HTTP API
+// the client uses plus a `/ws` endpoint that plays scripted hands, so frontend work
+// and E2E suites can run offline. Compiled only with the `mock-backend` feature.
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message;
+
+pub const DEFAULT_PORT: u16 = 8787;
+
+const MAX_HEAD_BYTES: usize = 64 * 1024;
+
+struct MockState {
+    tables: Vec<Value>,
+    next_table_id: u32,
+}
+
+type Shared = Arc<Mutex<MockState>>;
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl HttpRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+fn mock_table(id: &str, name: &str, small_blind: u32, big_blind: u32, max_players: u8) -> Value {
+    json!({
+        "id": id,
+        "name": name,
+        "playerCount": 0,
+        "maxPlayers": max_players,
+        "gamePhase": "waiting",
+        "pot": 0,
+        "blinds": { "small": small_blind, "big": big_blind },
+        "config": { "maxPlayers": max_players, "smallBlind": small_blind, "bigBlind": big_blind }
+    })
+}
+
+fn ok(data: Value) -> (u16, Value) {
+    (200, json!({ "success": true, "data": data }))
+}
+
+fn not_found() -> (u16, Value) {
+    (404, json!({ "success": false, "error": { "message": "Not found" } }))
+}
+
+fn route(state: &Shared, request: &HttpRequest) -> (u16, Value) {
+    let path = request.path.split('?').next().unwrap_or("");
+    let body: Value = serde_json::from_slice(&request.body).unwrap_or(Value::Null);
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["api", "health"]) => ok(json!({ "status": "ok", "mock": true })),
+        ("POST", ["api", "auth", "login"]) => {
+            let username = body["username"].as_str().unwrap_or("mock-player");
+            (200, json!({
+                "user": {
+                    "id": "mock-user-1",
+                    "username": username,
+                    "email": username,
+                    "name": "Mock Player"
+                },
+                "tokens": { "accessToken": "mock-access-token", "refreshToken": "mock-refresh-token" },
+                "message": "Login successful"
+            }))
+        }
+        ("POST", ["api", "auth", "refresh"]) => ok(json!({
+            "accessToken": "mock-access-token",
+            "refreshToken": "mock-refresh-token"
+        })),
+        ("GET", ["api", "tables"]) => {
+            let tables = state.lock().map(|s| s.tables.clone()).unwrap_or_default();
+            ok(Value::Array(tables))
+        }
+        ("POST", ["api", "tables"]) => {
+            let Ok(mut state) = state.lock() else { return not_found() };
+            state.next_table_id += 1;
+            let table = mock_table(
+                &format!("mock-table-{}", state.next_table_id),
+                body["name"].as_str().unwrap_or("Mock Table"),
+                body["smallBlind"].as_u64().unwrap_or(1) as u32,
+                body["bigBlind"].as_u64().unwrap_or(2) as u32,
+                body["maxPlayers"].as_u64().unwrap_or(9) as u8,
+            );
+            state.tables.push(table.clone());
+            ok(table)
+        }
+        ("POST", ["api", "tables", table_id, "join"]) => {
+            let Ok(mut state) = state.lock() else { return not_found() };
+            match state.tables.iter_mut().find(|t| t["id"] == *table_id) {
+                Some(table) => {
+                    let count = table["playerCount"].as_u64().unwrap_or(0) + 1;
+                    table["playerCount"] = json!(count);
+                    ok(json!({ "tableId": table_id, "seat": count, "chips": body["buyIn"] }))
+                }
+                None => not_found(),
+            }
+        }
+        ("GET", ["api", "players", "search"]) => {
+            let query = query_param(&request.path, "q").unwrap_or_default().to_lowercase();
+            let players: Vec<Value> = ["mock-user-1:you", "mock-bot-1:bot", "mock-bot-2:river_rat", "mock-bot-3:nit_nancy"]
+                .iter()
+                .filter_map(|entry| entry.split_once(':'))
+                .filter(|(_, username)| username.contains(&query))
+                .map(|(id, username)| json!({ "id": id, "username": username, "online": true }))
+                .collect();
+            ok(Value::Array(players))
+        }
+        ("GET", ["api", "wallet", "balance"]) => ok(json!({ "balance": 10000, "currency": "chips" })),
+        _ => not_found(),
+    }
+}
+
+// One scripted hand for the given table, replayed in a loop over the WebSocket
+fn scripted_hand(table_id: &str) -> Vec<Value> {
+    let players = json!([
+        { "id": "mock-user-1", "username": "you", "chips": 1000, "position": { "seat": 1 } },
+        { "id": "mock-bot-1", "username": "bot", "chips": 1000, "position": { "seat": 2 } }
+    ]);
+    let card = |rank: &str, suit: &str| json!({ "rank": rank, "suit": suit });
+    let board = [card("A", "spades"), card("K", "hearts"), card("7", "clubs"), card("2", "diamonds"), card("9", "spades")];
+
+    [
+        ("pre_flop", 3, 0),
+        ("flop", 10, 3),
+        ("turn", 20, 4),
+        ("river", 40, 5),
+        ("showdown", 40, 5),
+    ]
+    .iter()
+    .map(|(phase, pot, cards)| {
+        json!({
+            "type": "game_update",
+            "payload": {
+                "tableId": table_id,
+                "players": players,
+                "phase": phase,
+                "pot": pot,
+                "currentBet": 0,
+                "minRaise": 2,
+                "activePlayerId": "mock-user-1",
+                "communityCards": board[..*cards].to_vec()
+            },
+            "timestamp": chrono::Utc::now().timestamp_millis()
+        })
+    })
+    .collect()
+}
+
+fn query_param(path: &str, name: &str) -> Option<String> {
+    let query = path.split_once('?')?.1;
+    query.split('&').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        (key == name).then(|| value.to_string())
+    })
+}
+
+async fn serve_websocket(stream: TcpStream, path: String) {
+    let Ok(mut socket) = tokio_tungstenite::accept_async(stream).await else { return };
+    let table_id = query_param(&path, "tableId").unwrap_or_else(|| "mock-table-1".to_string());
+
+    let hello = json!({
+        "type": "connection_established",
+        "payload": { "playerId": "mock-user-1", "tableId": table_id },
+        "timestamp": chrono::Utc::now().timestamp_millis()
+    });
+    if socket.send(Message::Text(hello.to_string())).await.is_err() {
+        return;
+    }
+
+    let mut script = scripted_hand(&table_id).into_iter().cycle();
+    let mut ticker = tokio::time::interval(Duration::from_secs(2));
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                let Some(message) = script.next() else { break };
+                if socket.send(Message::Text(message.to_string())).await.is_err() {
+                    break;
+                }
+            }
+            incoming = socket.next() => match incoming {
+                Some(Ok(Message::Text(text))) => {
+                    // Answer pings; echo anything else back as an acknowledgement
+                    let parsed: Value = serde_json::from_str(&text).unwrap_or(Value::Null);
+                    let reply = if parsed["type"] == "ping" {
+                        json!({ "type": "pong", "payload": {}, "timestamp": chrono::Utc::now().timestamp_millis() })
+                    } else {
+                        json!({ "type": "ack", "payload": parsed, "timestamp": chrono::Utc::now().timestamp_millis() })
+                    };
+                    if socket.send(Message::Text(reply.to_string())).await.is_err() {
+                        break;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                Some(Ok(_)) => {}
+            }
+        }
+    }
+}
+
+fn parse_head(head: &[u8]) -> Option<HttpRequest> {
+    let text = std::str::from_utf8(head).ok()?;
+    let mut lines = text.split("\r\n");
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+    let headers = lines
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            Some((key.trim().to_string(), value.trim().to_string()))
+        })
+        .collect();
+
+    Some(HttpRequest { method, path, headers, body: Vec::new() })
+}
+
+async fn handle_connection(state: Shared, mut stream: TcpStream) {
+    // Peek so a WebSocket upgrade can hand the untouched stream to tungstenite
+    let mut peek_buf = vec![0u8; MAX_HEAD_BYTES];
+    let Ok(peeked) = stream.peek(&mut peek_buf).await else { return };
+    let Some(head_end) = peek_buf[..peeked].windows(4).position(|w| w == b"\r\n\r\n") else { return };
+    let Some(mut request) = parse_head(&peek_buf[..head_end]) else { return };
+
+    if request.header("upgrade").is_some_and(|v| v.eq_ignore_ascii_case("websocket")) {
+        serve_websocket(stream, request.path).await;
+        return;
+    }
+
+    let mut head = vec![0u8; head_end + 4];
+    if stream.read_exact(&mut head).await.is_err() {
+        return;
+    }
+    let content_length = request.header("content-length").and_then(|v| v.parse().ok()).unwrap_or(0);
+    let mut body = vec![0u8; content_length];
+    if stream.read_exact(&mut body).await.is_err() {
+        return;
+    }
+    request.body = body;
+
+    let (status, payload) = if request.method == "OPTIONS" {
+        (204, Value::Null)
+    } else {
+        route(&state, &request)
+    };
+    let body = if payload.is_null() { String::new() } else { payload.to_string() };
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\nAccess-Control-Allow-Headers: *\r\n\
+         Access-Control-Allow-Methods: GET, POST, PUT, DELETE, OPTIONS\r\nConnection: close\r\n\r\n{}",
+        status,
+        match status {
+            200 => "OK",
+            204 => "No Content",
+            _ => "Not Found",
+        },
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+// Bind the mock server and serve connections in the background. Returns the base URL.
+pub async fn start(port: u16) -> Result<String, String> {
+    let listener = TcpListener::bind(("127.0.0.1", port)).await
+        .map_err(|e| format!("Failed to start mock backend: {}", e))?;
+    let address = listener.local_addr()
+        .map_err(|e| format!("Failed to start mock backend: {}", e))?;
+
+    let state: Shared = Arc::new(Mutex::new(MockState {
+        tables: vec![
+            mock_table("mock-table-1", "Mock Hold'em 1/2", 1, 2, 9),
+            mock_table("mock-table-2", "Mock Heads-Up 5/10", 5, 10, 2),
+        ],
+        next_table_id: 2,
+    }));
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(handle_connection(state.clone(), stream));
+        }
+    });
+
+    Ok(format!("http://{}", address))
+}
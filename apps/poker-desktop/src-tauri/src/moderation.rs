@@ -0,0 +1,290 @@
+// Local checks on names and canned chat before they are sent, so the player hears
+// what is wrong at once rather than getting a bare rejection from the backend. Each
+// field has a length range and a character-set policy; every field refuses invisible
+// and direction-override characters and words that mix look-alike scripts (a Cyrillic
+// "а" in a Latin name), and names may not pass for staff. Words are matched against
+// a blocklist in several languages after folding case, accents, look-alike letters,
+// digit-for-letter swaps and repeated letters, so "Sh1iit" and "shit" read the same.
+// Usernames have no spaces, so longer blocked words are also looked for inside them.
+
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+// Blocked words inside a username are only caught from this length, so short ones do
+// not flag innocent names
+const MIN_EMBEDDED_LEN: usize = 5;
+
+// Words that make a name look like staff's, and the ones a username may not start with
+const RESERVED: &[&str] = &["admin", "administrator", "moderator", "mod", "staff", "official", "system", "primopoker"];
+const RESERVED_PREFIXES: &[&str] = &["admin", "moderator", "official", "primopoker"];
+
+const BLOCKLIST: &[(&str, &[&str])] = &[
+    ("English", &["fuck", "fucker", "fucking", "motherfucker", "shit", "cunt", "bitch", "asshole", "bastard", "dick", "cock", "pussy", "whore", "slut", "fag", "faggot", "nigger", "nigga", "retard", "wanker", "twat"]),
+    ("Spanish", &["puta", "puto", "mierda", "cabron", "coño", "gilipollas", "pendejo", "maricon", "joder", "verga", "chinga", "culero"]),
+    ("French", &["merde", "putain", "salope", "connard", "connasse", "enculé", "batard", "pute", "nique", "niquer"]),
+    ("German", &["scheiße", "scheisse", "arschloch", "fotze", "hurensohn", "wichser", "schlampe", "ficken", "missgeburt"]),
+    ("Portuguese", &["caralho", "porra", "buceta", "merda", "viado", "foda", "cuzão", "arrombado"]),
+    ("Italian", &["cazzo", "stronzo", "vaffanculo", "puttana", "minchia", "coglione", "troia"]),
+    ("Russian", &["блять", "блядь", "сука", "хуй", "пизда", "ебать", "мудак", "пидор", "blyat", "suka", "pizda"]),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Field {
+    Username,
+    TableName,
+    // Canned chat messages
+    ChatMacro,
+}
+
+impl Field {
+    fn label(self) -> &'static str {
+        match self {
+            Field::Username => "Username",
+            Field::TableName => "Table name",
+            Field::ChatMacro => "Canned message",
+        }
+    }
+
+    fn length(self) -> (usize, usize) {
+        match self {
+            Field::Username => (3, 20),
+            Field::TableName => (3, 40),
+            Field::ChatMacro => (1, 200),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    Length,
+    Characters,
+    Spacing,
+    MixedScripts,
+    Reserved,
+    Profanity,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Violation {
+    rule: Rule,
+    reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Validation {
+    field: Field,
+    valid: bool,
+    violations: Vec<Violation>,
+}
+
+#[derive(PartialEq)]
+enum Script {
+    Latin,
+    Cyrillic,
+    Greek,
+    Other,
+}
+
+fn script(c: char) -> Option<Script> {
+    if !c.is_alphabetic() {
+        return None;
+    }
+    Some(match c as u32 {
+        0x41..=0x5A | 0x61..=0x7A | 0xC0..=0x24F => Script::Latin,
+        0x370..=0x3FF => Script::Greek,
+        0x400..=0x52F => Script::Cyrillic,
+        _ => Script::Other,
+    })
+}
+
+// Zero-width characters, direction overrides and byte-order marks
+fn is_invisible(c: char) -> bool {
+    matches!(c as u32, 0x200B..=0x200F | 0x202A..=0x202E | 0x2060..=0x2064 | 0x2066..=0x2069 | 0xFEFF)
+}
+
+fn fold(c: char) -> &'static str {
+    match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | '4' | '@' | 'а' | 'α' => "a",
+        'ç' | 'ć' | 'с' => "c",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | '3' | 'е' | 'ε' => "e",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | '1' | '!' | 'і' | 'ι' => "i",
+        'ñ' | 'ń' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | '0' | 'о' | 'ο' => "o",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'υ' => "u",
+        'ý' | 'ÿ' | 'у' => "y",
+        '5' | '$' | 'ѕ' => "s",
+        '7' | 'τ' => "t",
+        'р' | 'ρ' => "p",
+        'х' | 'χ' => "x",
+        'к' | 'κ' => "k",
+        'ј' => "j",
+        'ß' => "ss",
+        'æ' => "ae",
+        'œ' => "oe",
+        _ => "",
+    }
+}
+
+// The form words are compared in: lower case, accents and look-alikes folded, digits
+// read as the letters they stand in for, runs of a letter cut to one
+fn normalize(word: &str) -> String {
+    let mut folded = String::new();
+    for c in word.chars().flat_map(char::to_lowercase) {
+        match fold(c) {
+            "" => folded.push(c),
+            plain => folded.push_str(plain),
+        }
+    }
+    let mut out = String::with_capacity(folded.len());
+    for c in folded.chars().filter(|c| c.is_alphabetic()) {
+        if !out.ends_with(c) {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn blocklist() -> &'static [(&'static str, String)] {
+    static TERMS: OnceLock<Vec<(&'static str, String)>> = OnceLock::new();
+    TERMS.get_or_init(|| {
+        BLOCKLIST
+            .iter()
+            .flat_map(|(language, words)| words.iter().map(move |w| (*language, normalize(w))))
+            .collect()
+    })
+}
+
+// Words as typed: runs of letters and of the digits and symbols that stand in for them
+fn words(text: &str) -> Vec<&str> {
+    text.split(|c: char| !c.is_alphanumeric() && fold(c).is_empty())
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+fn check_characters(field: Field, text: &str, violations: &mut Vec<Violation>) {
+    let mut push = |rule, reason: String| violations.push(Violation { rule, reason });
+    if text.chars().any(|c| is_invisible(c) || c.is_control()) {
+        push(Rule::Characters, "Contains invisible or control characters".to_string());
+    }
+    match field {
+        Field::Username => {
+            let mut bad: Vec<char> = text.chars().filter(|c| !(c.is_alphanumeric() || "_-.".contains(*c))).collect();
+            bad.dedup();
+            if !bad.is_empty() {
+                let shown: String = bad.iter().filter(|c| !c.is_control() && !is_invisible(**c)).collect();
+                push(Rule::Characters, format!("Only letters, digits, _ - and . are allowed, not '{}'", shown));
+            }
+            if !text.chars().next().is_some_and(char::is_alphabetic) {
+                push(Rule::Characters, "Must start with a letter".to_string());
+            }
+            let separators: Vec<char> = text.chars().collect();
+            if separators.windows(2).any(|w| "_-.".contains(w[0]) && "_-.".contains(w[1])) || text.ends_with(['_', '-', '.']) {
+                push(Rule::Characters, "_ - and . must sit between letters or digits".to_string());
+            }
+        }
+        Field::TableName => {
+            let mut bad: Vec<char> = text
+                .chars()
+                .filter(|c| !(c.is_alphanumeric() || " '-_.,!#&():/".contains(*c)))
+                .collect();
+            bad.dedup();
+            if !bad.is_empty() {
+                let shown: String = bad.iter().filter(|c| !c.is_control() && !is_invisible(**c)).collect();
+                push(Rule::Characters, format!("Only letters, digits, spaces and ' - _ . , ! # & ( ) : / are allowed, not '{}'", shown));
+            }
+        }
+        Field::ChatMacro => {}
+    }
+    if field != Field::Username && (text.starts_with(char::is_whitespace) || text.ends_with(char::is_whitespace) || text.contains("  ")) {
+        push(Rule::Spacing, "No spaces at the ends or two in a row".to_string());
+    }
+}
+
+fn check_scripts(text: &str, violations: &mut Vec<Violation>) {
+    for word in text.split(|c: char| !c.is_alphabetic()).filter(|w| !w.is_empty()) {
+        let mut scripts: Vec<Script> = Vec::new();
+        // Only these alphabets share look-alike letters
+        for script in word.chars().filter_map(script).filter(|s| *s != Script::Other) {
+            if !scripts.contains(&script) {
+                scripts.push(script);
+            }
+        }
+        if scripts.len() > 1 {
+            violations.push(Violation {
+                rule: Rule::MixedScripts,
+                reason: format!("'{}' mixes letters of different alphabets", word),
+            });
+            return;
+        }
+    }
+}
+
+fn check_words(field: Field, text: &str, violations: &mut Vec<Violation>) {
+    let typed = words(text);
+    let normalized: Vec<String> = typed.iter().map(|w| normalize(w)).collect();
+    if field != Field::ChatMacro {
+        let collapsed = normalize(text);
+        let reserved = RESERVED.iter().any(|r| normalized.contains(&normalize(r)))
+            || (field == Field::Username && RESERVED_PREFIXES.iter().any(|r| collapsed.starts_with(&normalize(r))));
+        if reserved {
+            violations.push(Violation {
+                rule: Rule::Reserved,
+                reason: format!("{}s may not look like they belong to staff", field.label()),
+            });
+        }
+    }
+
+    let collapsed = (field == Field::Username).then(|| normalize(text));
+    let mut flagged = Vec::new();
+    for (language, term) in blocklist() {
+        let hit = typed.iter().zip(&normalized).find(|(_, n)| *n == term).map(|(w, _)| w.to_string()).or_else(|| {
+            collapsed
+                .as_ref()
+                .filter(|c| term.chars().count() >= MIN_EMBEDDED_LEN && c.contains(term.as_str()))
+                .map(|_| text.to_string())
+        });
+        if let Some(word) = hit {
+            if !flagged.contains(&word) {
+                violations.push(Violation { rule: Rule::Profanity, reason: format!("'{}' is not allowed ({})", word, language) });
+                flagged.push(word);
+            }
+        }
+    }
+}
+
+pub fn check(field: Field, text: &str) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let (min, max) = field.length();
+    let length = text.trim().chars().count();
+    if length < min || length > max {
+        violations.push(Violation {
+            rule: Rule::Length,
+            reason: format!("{}s must be {} to {} characters", field.label(), min, max),
+        });
+    }
+    check_characters(field, text, &mut violations);
+    check_scripts(text, &mut violations);
+    check_words(field, text, &mut violations);
+    violations
+}
+
+// `check` as an error naming every problem, for commands that submit the text
+pub fn require(field: Field, text: &str) -> Result<(), String> {
+    let violations = check(field, text);
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let reasons: Vec<String> = violations.into_iter().map(|v| v.reason).collect();
+    Err(format!("{}: {}", field.label(), reasons.join("; ")))
+}
+
+// Check a username, table name or canned message as the player types it
+#[tauri::command]
+pub async fn validate_text(field: Field, text: String) -> Result<Validation, String> {
+    let violations = check(field, &text);
+    Ok(Validation { field, valid: violations.is_empty(), violations })
+}
@@ -0,0 +1,246 @@
+// Show or muck at showdown. Where the rules leave it to the player, a pot won without
+// a call or a hand already beaten by one shown, the backend offers the choice with a
+// `showdown_option` frame. The table view hands that frame over here and the table's
+// auto-muck rule settles it on the spot, so the answer goes out even while the webview
+// is busy; a rule of `ask` leaves it to `show_hand` or `muck_hand`. Either way the
+// answer is raised as `showdown_send` with the frame for the table's socket, and the
+// choice is kept and put on the hand when it is saved (see history.rs).
+
+use crate::claims;
+use crate::db::{self, Database, Db};
+use crate::history::{self, HandRecord};
+use crate::ws::WsMessage;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+const KEY_PREFERENCES: &str = "showdown.muck";
+const KEY_CHOICES: &str = "showdown.choices";
+// Choices kept for hands not saved yet
+const MAX_CHOICES: usize = 200;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MuckRule {
+    // Always leave it to the player
+    Ask,
+    // Muck beaten hands, ask after an uncalled pot
+    #[default]
+    MuckLosing,
+    // Muck beaten hands and uncalled winners
+    MuckAll,
+    ShowAll,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MuckPreferences {
+    default_rule: MuckRule,
+    // Rules for single tables, over the default
+    tables: HashMap<String, MuckRule>,
+}
+
+impl MuckPreferences {
+    fn rule(&self, table_id: &str) -> MuckRule {
+        self.tables.get(table_id).copied().unwrap_or(self.default_rule)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShowdownChoice {
+    pub hand_id: String,
+    pub shown: bool,
+    // Settled by the auto-muck rule rather than by the player
+    pub automatic: bool,
+    pub rule: MuckRule,
+    pub chosen_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShowdownOutcome {
+    table_id: String,
+    hand_id: String,
+    // Won without a call, rather than beaten
+    uncontested: bool,
+    // None while waiting on the player
+    choice: Option<ShowdownChoice>,
+    frame: Option<WsMessage>,
+}
+
+#[derive(Debug, Clone)]
+struct Offer {
+    hand_id: String,
+    uncontested: bool,
+}
+
+#[derive(Default)]
+pub struct MuckState {
+    // Open choices by table
+    offers: Mutex<HashMap<String, Offer>>,
+}
+
+fn load<T: serde::de::DeserializeOwned>(db: &Database, key: &str) -> Result<Option<T>, String> {
+    match db.get_value(key)? {
+        Some(data) => serde_json::from_str(&data).map(Some).map_err(|e| format!("Invalid {}: {}", key, e)),
+        None => Ok(None),
+    }
+}
+
+fn save<T: Serialize>(db: &Database, key: &str, value: &T) -> Result<(), String> {
+    let data = serde_json::to_string(value).map_err(|e| e.to_string())?;
+    db.set_value(key, &data)
+}
+
+// The choice made for `hand`, before it is saved
+pub fn attach(db: &Database, hand: &mut HandRecord) -> Result<(), String> {
+    let choices: Vec<ShowdownChoice> = load(db, KEY_CHOICES)?.unwrap_or_default();
+    if let Some(choice) = choices.into_iter().rev().find(|c| c.hand_id == hand.id) {
+        hand.showdown_choice = Some(choice);
+    }
+    Ok(())
+}
+
+fn store_choice(db: &Database, choice: &ShowdownChoice) -> Result<(), String> {
+    let mut choices: Vec<ShowdownChoice> = load(db, KEY_CHOICES)?.unwrap_or_default();
+    choices.retain(|c| c.hand_id != choice.hand_id);
+    choices.push(choice.clone());
+    let excess = choices.len().saturating_sub(MAX_CHOICES);
+    choices.drain(..excess);
+    save(db, KEY_CHOICES, &choices)?;
+
+    // The hand may already be stored
+    let stored = db.with_conn(|conn| history::get_hand_by_id(conn, &choice.hand_id))?;
+    if let Some(mut hand) = stored {
+        hand.showdown_choice = Some(choice.clone());
+        hand.updated_at = Utc::now();
+        db.with_conn(|conn| history::upsert_hand(conn, &hand))?;
+    }
+    Ok(())
+}
+
+// What the rule does with the offer; None leaves it to the player
+fn decide(rule: MuckRule, uncontested: bool) -> Option<bool> {
+    match (rule, uncontested) {
+        (MuckRule::Ask, _) | (MuckRule::MuckLosing, true) => None,
+        (MuckRule::MuckLosing, false) | (MuckRule::MuckAll, _) => Some(false),
+        (MuckRule::ShowAll, _) => Some(true),
+    }
+}
+
+// Close the offer at `table_id` with `shown`, record it and raise the frame
+fn answer(app: &AppHandle, table_id: &str, offer: Offer, shown: bool, automatic: bool) -> Result<ShowdownOutcome, String> {
+    let db = db::get(app)?;
+    let player_id = claims::current()?.user_id;
+    let rule = load::<MuckPreferences>(&db, KEY_PREFERENCES)?.unwrap_or_default().rule(table_id);
+    let choice = ShowdownChoice { hand_id: offer.hand_id.clone(), shown, automatic, rule, chosen_at: Utc::now() };
+    let frame = WsMessage::new(
+        if shown { "show_cards" } else { "muck_cards" },
+        json!({ "tableId": table_id, "handId": offer.hand_id, "playerId": player_id }),
+    );
+    let outcome = ShowdownOutcome {
+        table_id: table_id.to_string(),
+        hand_id: offer.hand_id,
+        uncontested: offer.uncontested,
+        choice: Some(choice.clone()),
+        frame: Some(frame),
+    };
+    let _ = app.emit_all("showdown_send", outcome.clone());
+    if let Err(e) = store_choice(&db, &choice) {
+        eprintln!("Failed to record showdown choice for {}: {}", choice.hand_id, e);
+    }
+    Ok(outcome)
+}
+
+fn take_offer(app: &AppHandle, table_id: &str) -> Result<Offer, String> {
+    app.state::<MuckState>()
+        .offers
+        .lock()
+        .map_err(|_| "Showdown lock poisoned".to_string())?
+        .remove(table_id)
+        .ok_or_else(|| format!("No show or muck choice is open at table {}", table_id))
+}
+
+// Feed a `showdown_option` frame received at `table_id`. The table's rule answers it
+// at once; when it asks, `showdown_choice_needed` is raised and the offer stays open.
+#[tauri::command]
+pub async fn handle_showdown_option(
+    app: AppHandle,
+    state: State<'_, MuckState>,
+    db: Db<'_>,
+    table_id: String,
+    message: WsMessage,
+) -> Result<ShowdownOutcome, String> {
+    if message.kind != "showdown_option" {
+        return Err(format!("Not a showdown option: {}", message.kind));
+    }
+    let payload = &message.payload;
+    let hand_id = payload["handId"].as_str().ok_or_else(|| "Showdown option without a hand".to_string())?;
+    if let Some(player_id) = payload["playerId"].as_str() {
+        if player_id != claims::current()?.user_id {
+            return Err("This showdown option is for another player".to_string());
+        }
+    }
+    let offer = Offer { hand_id: hand_id.to_string(), uncontested: payload["uncontested"].as_bool().unwrap_or(false) };
+
+    let rule = load::<MuckPreferences>(&db, KEY_PREFERENCES)?.unwrap_or_default().rule(&table_id);
+    match decide(rule, offer.uncontested) {
+        Some(shown) => answer(&app, &table_id, offer, shown, true),
+        None => {
+            let outcome = ShowdownOutcome {
+                table_id: table_id.clone(),
+                hand_id: offer.hand_id.clone(),
+                uncontested: offer.uncontested,
+                choice: None,
+                frame: None,
+            };
+            state.offers.lock().map_err(|_| "Showdown lock poisoned".to_string())?.insert(table_id, offer);
+            let _ = app.emit_all("showdown_choice_needed", outcome.clone());
+            Ok(outcome)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn show_hand(app: AppHandle, table_id: String) -> Result<ShowdownOutcome, String> {
+    let offer = take_offer(&app, &table_id)?;
+    answer(&app, &table_id, offer, true, false)
+}
+
+#[tauri::command]
+pub async fn muck_hand(app: AppHandle, table_id: String) -> Result<ShowdownOutcome, String> {
+    let offer = take_offer(&app, &table_id)?;
+    answer(&app, &table_id, offer, false, false)
+}
+
+#[tauri::command]
+pub async fn get_muck_preferences(db: Db<'_>) -> Result<MuckPreferences, String> {
+    Ok(load(&db, KEY_PREFERENCES)?.unwrap_or_default())
+}
+
+// Set the rule for `table_id`, or the default when no table is given. A table given
+// without a rule goes back to the default.
+#[tauri::command]
+pub async fn set_muck_rule(
+    db: Db<'_>,
+    table_id: Option<String>,
+    rule: Option<MuckRule>,
+) -> Result<MuckPreferences, String> {
+    let mut preferences: MuckPreferences = load(&db, KEY_PREFERENCES)?.unwrap_or_default();
+    match (table_id, rule) {
+        (Some(table_id), Some(rule)) => {
+            preferences.tables.insert(table_id, rule);
+        }
+        (Some(table_id), None) => {
+            preferences.tables.remove(&table_id);
+        }
+        (None, Some(rule)) => preferences.default_rule = rule,
+        (None, None) => return Err("Give a rule or a table to reset".to_string()),
+    }
+    save(&db, KEY_PREFERENCES, &preferences)?;
+    Ok(preferences)
+}
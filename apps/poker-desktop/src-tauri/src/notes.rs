@@ -0,0 +1,222 @@
+use crate::db::Db;
+use crate::preview::PreviewState;
+use crate::thumbnails::ThumbnailState;
+use chrono::{DateTime, TimeZone, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager, State};
+
+// Quick player labels: name, colour tag, and whether the label marks a weak player
+const LABELS: &[(&str, &str, bool)] = &[
+    ("fish", "#2e86de", true),
+    ("calling_station", "#48c9b0", true),
+    ("reg", "#7f8c8d", false),
+    ("nit", "#f1c40f", false),
+    ("maniac", "#e74c3c", false),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerNote {
+    pub player_id: String,
+    pub text: String,
+    // One of LABELS; kept with the note so it syncs along with it
+    #[serde(default)]
+    pub label: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerLabel {
+    pub name: &'static str,
+    pub color: &'static str,
+    pub weak: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LabeledTable {
+    table_id: String,
+    // Seated players carrying a matching label
+    players: Vec<String>,
+}
+
+fn label(name: &str) -> Option<PlayerLabel> {
+    LABELS
+        .iter()
+        .find(|(label, _, _)| *label == name)
+        .map(|&(name, color, weak)| PlayerLabel { name, color, weak })
+}
+
+// `filter` is a label name, or "weak" for any label marking a weak player
+pub fn label_matches(label: &PlayerLabel, filter: &str) -> bool {
+    label.name == filter || (filter == "weak" && label.weak)
+}
+
+pub fn is_label_filter(filter: &str) -> bool {
+    filter == "weak" || label(filter).is_some()
+}
+
+fn note_from_row(row: &rusqlite::Row) -> rusqlite::Result<PlayerNote> {
+    let updated_at: i64 = row.get(2)?;
+    Ok(PlayerNote {
+        player_id: row.get(0)?,
+        text: row.get(1)?,
+        label: row.get(3)?,
+        updated_at: Utc.timestamp_millis_opt(updated_at).single().unwrap_or_else(Utc::now),
+    })
+}
+
+// Insert a note, or replace the stored copy when the incoming one is newer
+pub fn upsert_note(conn: &Connection, note: &PlayerNote) -> rusqlite::Result<bool> {
+    let changed = conn.execute(
+        "INSERT INTO notes (player_id, text, updated_at, label) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(player_id) DO UPDATE SET
+            text = excluded.text,
+            updated_at = excluded.updated_at,
+            label = excluded.label
+         WHERE excluded.updated_at > notes.updated_at",
+        params![note.player_id, note.text, note.updated_at.timestamp_millis(), note.label],
+    )?;
+
+    Ok(changed > 0)
+}
+
+pub fn get_note(conn: &Connection, player_id: &str) -> rusqlite::Result<Option<PlayerNote>> {
+    conn.query_row(
+        "SELECT player_id, text, updated_at, label FROM notes WHERE player_id = ?1",
+        [player_id],
+        note_from_row,
+    )
+    .optional()
+}
+
+pub fn list_notes(conn: &Connection) -> rusqlite::Result<Vec<PlayerNote>> {
+    let mut stmt = conn.prepare("SELECT player_id, text, updated_at, label FROM notes ORDER BY player_id")?;
+    let rows = stmt.query_map([], note_from_row)?;
+    rows.collect()
+}
+
+// Keyset page of notes modified strictly after (updated_at, player_id), oldest first,
+// so notes sharing a millisecond are never skipped at a page boundary
+pub fn notes_updated_since(conn: &Connection, after: (i64, &str), limit: u32) -> rusqlite::Result<Vec<PlayerNote>> {
+    let (updated_at, player_id) = after;
+    let mut stmt = conn.prepare(
+        "SELECT player_id, text, updated_at, label FROM notes
+         WHERE updated_at > ?1 OR (updated_at = ?1 AND player_id > ?2)
+         ORDER BY updated_at, player_id LIMIT ?3",
+    )?;
+    let rows = stmt.query_map(params![updated_at, player_id, limit], note_from_row)?;
+    rows.collect()
+}
+
+// Labels of those of `player_ids` that carry one
+pub fn labels_for<'a>(
+    conn: &Connection,
+    player_ids: impl IntoIterator<Item = &'a str>,
+) -> rusqlite::Result<HashMap<String, PlayerLabel>> {
+    let mut stmt = conn.prepare_cached("SELECT label FROM notes WHERE player_id = ?1 AND label IS NOT NULL")?;
+    let mut labels = HashMap::new();
+    for id in player_ids {
+        let name: Option<String> = stmt.query_row([id], |row| row.get(0)).optional()?;
+        if let Some(label) = name.as_deref().and_then(label) {
+            labels.insert(id.to_string(), label);
+        }
+    }
+    Ok(labels)
+}
+
+// Create or replace the note for a player, keeping their label
+#[tauri::command]
+pub async fn set_player_note(
+    db: Db<'_>,
+    player_id: String,
+    text: String,
+) -> Result<PlayerNote, String> {
+    let label = db.with_conn(|conn| get_note(conn, &player_id))?.and_then(|n| n.label);
+    let note = PlayerNote {
+        player_id,
+        text,
+        label,
+        updated_at: Utc::now(),
+    };
+    db.with_conn(|conn| upsert_note(conn, &note))?;
+    Ok(note)
+}
+
+// Set or, with no label, clear a player's label, keeping the note text
+#[tauri::command]
+pub async fn set_player_label(
+    app: AppHandle,
+    db: Db<'_>,
+    player_id: String,
+    label: Option<String>,
+) -> Result<PlayerNote, String> {
+    if let Some(name) = label.as_deref().filter(|name| self::label(name).is_none()) {
+        let known: Vec<&str> = LABELS.iter().map(|(name, _, _)| *name).collect();
+        return Err(format!("Unknown label {}; use one of {}", name, known.join(", ")));
+    }
+    let text = db.with_conn(|conn| get_note(conn, &player_id))?.map(|n| n.text).unwrap_or_default();
+    let note = PlayerNote {
+        player_id,
+        text,
+        label,
+        updated_at: Utc::now(),
+    };
+    db.with_conn(|conn| upsert_note(conn, &note))?;
+    let _ = app.emit_all("player_label_changed", note.clone());
+    Ok(note)
+}
+
+// Every label with its colour
+#[tauri::command]
+pub async fn get_player_labels() -> Result<Vec<PlayerLabel>, String> {
+    Ok(LABELS.iter().map(|&(name, color, weak)| PlayerLabel { name, color, weak }).collect())
+}
+
+// Open or previewed tables with at least `min_players` seated players labelled
+// `label` ("weak" matches every weak label), most matches first
+#[tauri::command]
+pub async fn find_labeled_tables(
+    db: Db<'_>,
+    previews: State<'_, PreviewState>,
+    thumbnails: State<'_, ThumbnailState>,
+    label: String,
+    min_players: Option<usize>,
+) -> Result<Vec<LabeledTable>, String> {
+    if !is_label_filter(&label) {
+        return Err(format!("Unknown label {}", label));
+    }
+    let mut seated = previews.seated()?;
+    for (table_id, players) in thumbnails.seated()? {
+        seated.insert(table_id, players);
+    }
+    let min_players = min_players.unwrap_or(1).max(1);
+    let mut tables = Vec::new();
+    for (table_id, players) in seated {
+        let labels = db.with_conn(|conn| labels_for(conn, players.iter().map(String::as_str)))?;
+        let matching: Vec<String> = players
+            .into_iter()
+            .filter(|id| labels.get(id).is_some_and(|l| label_matches(l, &label)))
+            .collect();
+        if matching.len() >= min_players {
+            tables.push(LabeledTable { table_id, players: matching });
+        }
+    }
+    tables.sort_by(|a, b| b.players.len().cmp(&a.players.len()).then_with(|| a.table_id.cmp(&b.table_id)));
+    Ok(tables)
+}
+
+// Get the note for a single player
+#[tauri::command]
+pub async fn get_player_note(db: Db<'_>, player_id: String) -> Result<Option<PlayerNote>, String> {
+    db.with_conn(|conn| get_note(conn, &player_id))
+}
+
+// Get all player notes
+#[tauri::command]
+pub async fn get_player_notes(db: Db<'_>) -> Result<Vec<PlayerNote>, String> {
+    db.with_conn(list_notes)
+}
@@ -0,0 +1,159 @@
+// First-run setup. The steps run in a fixed order and each one is recorded in the kv
+// table as it is completed or skipped, so setup interrupted by a restart resumes at
+// the first step still open. Choosing a backend and signing in are required; the
+// rest can be skipped. A stored login counts as the sign-in step, so players who
+// were signed in before onboarding existed go straight past it.
+
+use crate::db::{Database, Db};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+
+const KEY_STATE: &str = "onboarding.state";
+
+// id, required
+const STEPS: &[(&str, bool)] = &[
+    ("backend", true),
+    ("account", true),
+    ("sound", false),
+    ("hotkeys", false),
+    ("limits", false),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StepRecord {
+    // completed | skipped
+    status: String,
+    at: DateTime<Utc>,
+    // Whatever the step's screen wants back when it is revisited
+    #[serde(default)]
+    data: Option<Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct OnboardingProgress {
+    steps: HashMap<String, StepRecord>,
+    started_at: Option<DateTime<Utc>>,
+    finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OnboardingStep {
+    id: String,
+    required: bool,
+    // pending | completed | skipped
+    status: String,
+    at: Option<DateTime<Utc>>,
+    data: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OnboardingState {
+    steps: Vec<OnboardingStep>,
+    // First step still open, None once setup is done
+    current: Option<String>,
+    finished: bool,
+    started_at: Option<DateTime<Utc>>,
+    finished_at: Option<DateTime<Utc>>,
+}
+
+fn load(db: &Database) -> Result<OnboardingProgress, String> {
+    match db.get_value(KEY_STATE)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid onboarding state: {}", e)),
+        None => Ok(OnboardingProgress::default()),
+    }
+}
+
+fn save(db: &Database, progress: &OnboardingProgress) -> Result<(), String> {
+    let data = serde_json::to_string(progress).map_err(|e| e.to_string())?;
+    db.set_value(KEY_STATE, &data)
+}
+
+fn record(progress: &mut OnboardingProgress, step: &str, status: &str, data: Option<Value>) {
+    let now = Utc::now();
+    progress.started_at.get_or_insert(now);
+    progress.steps.insert(step.to_string(), StepRecord { status: status.to_string(), at: now, data });
+    let done = STEPS.iter().all(|(id, _)| progress.steps.contains_key(*id));
+    progress.finished_at = if done { progress.finished_at.or(Some(now)) } else { None };
+}
+
+fn view(progress: &OnboardingProgress) -> OnboardingState {
+    let steps: Vec<OnboardingStep> = STEPS
+        .iter()
+        .map(|(id, required)| {
+            let record = progress.steps.get(*id);
+            OnboardingStep {
+                id: id.to_string(),
+                required: *required,
+                status: record.map_or("pending".to_string(), |r| r.status.clone()),
+                at: record.map(|r| r.at),
+                data: record.and_then(|r| r.data.clone()),
+            }
+        })
+        .collect();
+    let current = steps.iter().find(|s| s.status == "pending").map(|s| s.id.clone());
+    OnboardingState {
+        finished: current.is_none(),
+        current,
+        steps,
+        started_at: progress.started_at,
+        finished_at: progress.finished_at,
+    }
+}
+
+// Progress so far, counting a stored login as the sign-in step
+#[tauri::command]
+pub async fn get_onboarding_state(db: Db<'_>) -> Result<OnboardingState, String> {
+    let mut progress = load(&db)?;
+    if !progress.steps.contains_key("account") && crate::get_token_from_keyring().is_ok() {
+        record(&mut progress, "account", "completed", None);
+        save(&db, &progress)?;
+    }
+    Ok(view(&progress))
+}
+
+// Complete or skip `step`. Steps go in order: every step before it has to be done
+// first, and required steps cannot be skipped. Done steps can be redone to change
+// their data.
+#[tauri::command]
+pub async fn complete_onboarding_step(
+    app: AppHandle,
+    db: Db<'_>,
+    step: String,
+    skipped: Option<bool>,
+    data: Option<Value>,
+) -> Result<OnboardingState, String> {
+    let index = STEPS
+        .iter()
+        .position(|(id, _)| *id == step)
+        .ok_or_else(|| format!("Unknown onboarding step {}", step))?;
+    let skipped = skipped.unwrap_or(false);
+    if skipped && STEPS[index].1 {
+        return Err(format!("The {} step cannot be skipped", step));
+    }
+
+    let mut progress = load(&db)?;
+    if let Some((open, _)) = STEPS[..index].iter().find(|(id, _)| !progress.steps.contains_key(*id)) {
+        return Err(format!("Finish the {} step first", open));
+    }
+    record(&mut progress, &step, if skipped { "skipped" } else { "completed" }, data);
+    save(&db, &progress)?;
+
+    let state = view(&progress);
+    let _ = app.emit_all("onboarding_updated", state.clone());
+    Ok(state)
+}
+
+// Start setup over, e.g. from the settings screen
+#[tauri::command]
+pub async fn reset_onboarding(db: Db<'_>) -> Result<OnboardingState, String> {
+    let progress = OnboardingProgress::default();
+    save(&db, &progress)?;
+    Ok(view(&progress))
+}
@@ -0,0 +1,159 @@
+// Cursor pagination for the lobby, wallet transactions and local hand history. A
+// page comes back with an opaque `nextCursor`; handing it to `fetch_next_page` returns
+// the following page of the same list, whichever source it came from. Remote cursors
+// wrap the backend's own cursor; hand history pages by (played_at, id) so new hands
+// arriving between pages do not shift the results.
+
+use crate::db::{Database, Db};
+use crate::history::{self, HandRecord};
+use crate::schema::{self, Kind};
+use crate::table_stats::TableStatsState;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::State;
+
+pub const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 200;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "camelCase")]
+enum Cursor {
+    #[serde(rename_all = "camelCase")]
+    Lobby { api_url: String, limit: u32, after: String },
+    #[serde(rename_all = "camelCase")]
+    Transactions { api_url: String, limit: u32, after: String },
+    #[serde(rename_all = "camelCase")]
+    Hands { limit: u32, played_at: i64, id: String },
+}
+
+impl Cursor {
+    fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    fn decode(cursor: &str) -> Result<Self, String> {
+        let bytes = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| "Invalid page cursor".to_string())?;
+        serde_json::from_slice(&bytes).map_err(|_| "Invalid page cursor".to_string())
+    }
+}
+
+pub fn clamp_limit(limit: Option<u32>) -> u32 {
+    limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+}
+
+pub fn lobby_cursor(api_url: &str, limit: u32, after: Option<String>) -> Option<String> {
+    after.map(|after| Cursor::Lobby { api_url: api_url.to_string(), limit, after }.encode())
+}
+
+pub fn transactions_cursor(api_url: &str, limit: u32, after: Option<String>) -> Option<String> {
+    after.map(|after| Cursor::Transactions { api_url: api_url.to_string(), limit, after }.encode())
+}
+
+// Backend cursor inside a cursor from `lobby_cursor` or `transactions_cursor`
+pub fn remote_position(cursor: &str) -> Result<String, String> {
+    match Cursor::decode(cursor)? {
+        Cursor::Lobby { after, .. } | Cursor::Transactions { after, .. } => Ok(after),
+        Cursor::Hands { .. } => Err("Hand history cursors are for local history".to_string()),
+    }
+}
+
+// One page of a backend list. Backends without cursor support send the whole list
+// as a plain array, which comes back as a single final page.
+pub async fn fetch_remote<T: DeserializeOwned>(
+    url: &str,
+    token: Option<&str>,
+    limit: u32,
+    after: Option<&str>,
+    item: &'static Kind,
+    what: &str,
+) -> Result<(Vec<T>, Option<String>), String> {
+    let client = crate::create_http_client()?;
+    let paged = crate::version::supports(crate::version::CURSOR_PAGINATION);
+    let mut request = client.get(url);
+    if paged {
+        request = request.query(&[("limit", limit.to_string())]);
+        if let Some(after) = after {
+            request = request.query(&[("cursor", after)]);
+        }
+    }
+    if let Some(token) = token {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+
+    let response = crate::http::send(request).await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("Failed to fetch {}: {}", what, error_text));
+    }
+
+    let data: Option<Value> = schema::api_data(response, &Kind::Any, what).await?;
+    let (items, next) = match data {
+        Some(Value::Object(mut page)) if paged => {
+            let next = page.get("nextCursor").and_then(Value::as_str).map(String::from);
+            (page.remove("items").unwrap_or(Value::Null), next)
+        }
+        Some(items) => (items, None),
+        None => return Ok((Vec::new(), None)),
+    };
+    Ok((schema::parse(items, &Kind::Array(item), what)?, next))
+}
+
+// Local hand history, most recent first
+pub fn hand_page(db: &Database, limit: u32, before: Option<(i64, String)>) -> Result<Page<HandRecord>, String> {
+    let hands = db.with_conn(|conn| history::hands_before(conn, before.as_ref().map(|(at, id)| (*at, id.as_str())), limit + 1))?;
+    let more = hands.len() > limit as usize;
+    let items: Vec<HandRecord> = hands.into_iter().take(limit as usize).collect();
+    let next_cursor = match items.last() {
+        Some(last) if more => Some(Cursor::Hands { limit, played_at: history::to_millis(&last.played_at), id: last.id.clone() }.encode()),
+        _ => None,
+    };
+    Ok(Page { items, next_cursor })
+}
+
+fn to_values<T: Serialize>(page: Page<T>) -> Result<Page<Value>, String> {
+    let items = page.items.iter().map(serde_json::to_value).collect::<Result<_, _>>().map_err(|e| e.to_string())?;
+    Ok(Page { items, next_cursor: page.next_cursor })
+}
+
+// Get the hand history page before `cursor`, most recent first
+#[tauri::command]
+pub async fn get_hand_history_page(
+    db: Db<'_>,
+    limit: Option<u32>,
+    cursor: Option<String>,
+) -> Result<Page<HandRecord>, String> {
+    let limit = clamp_limit(limit);
+    match cursor.as_deref().map(Cursor::decode).transpose()? {
+        None => hand_page(&db, limit, None),
+        Some(Cursor::Hands { played_at, id, .. }) => hand_page(&db, limit, Some((played_at, id))),
+        Some(_) => Err("Cursor does not belong to hand history".to_string()),
+    }
+}
+
+// The page following `cursor`, for any paged list
+#[tauri::command]
+pub async fn fetch_next_page(
+    db: Db<'_>,
+    stats: State<'_, TableStatsState>,
+    cursor: String,
+) -> Result<Page<Value>, String> {
+    match Cursor::decode(&cursor)? {
+        Cursor::Lobby { api_url, limit, after } => {
+            to_values(crate::fetch_tables_page(&db, &stats, &api_url, limit, Some(&after)).await?)
+        }
+        Cursor::Transactions { api_url, limit, after } => {
+            to_values(crate::wallet::fetch_transactions(&api_url, limit, Some(&after)).await?)
+        }
+        Cursor::Hands { limit, played_at, id } => to_values(hand_page(&db, limit, Some((played_at, id)))?),
+    }
+}
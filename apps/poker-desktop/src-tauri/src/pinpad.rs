@@ -0,0 +1,260 @@
+// On-screen PIN pad for withdrawals and transfers. Each attempt shuffles the digit
+// layout in Rust and hands the webview only rasterized key images; the webview sends
+// back the positions that were pressed. The PIN itself is assembled here and sent
+// to the backend, which returns a short-lived token for the guarded operation.
+//
+// What this protects against: keystrokes never carry the digits, the DOM holds no
+// digit text, and the key images have no structure to read the digit from. Each one
+// is a bitmap drawn afresh with a jittered, warped stroke and speckle noise, so equal
+// digits never share pixels. Code in the page can still capture the images and run
+// OCR on them; that is not defended against.
+
+use crate::audit;
+use crate::compliance::{self, ComplianceState};
+use crate::db::Db;
+use crate::error::CommandError;
+use crate::guard;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use flate2::write::ZlibEncoder;
+use flate2::{Compression, Crc};
+use rand::rngs::OsRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, State};
+
+const KEY_ENABLED: &str = "pinpad.enabled";
+
+const OPERATIONS: &[&str] = &["withdrawal", "transfer"];
+const ATTEMPT_TTL_SECS: i64 = 120;
+const MIN_PIN_LEN: usize = 4;
+const MAX_PIN_LEN: usize = 8;
+
+const KEY_WIDTH: usize = 44;
+const KEY_HEIGHT: usize = 64;
+// Seven-segment glyph endpoints on a 40x60 key: a, b, c, d, e, f, g
+const SEGMENTS: [(f32, f32, f32, f32); 7] = [
+    (8.0, 6.0, 32.0, 6.0),
+    (32.0, 6.0, 32.0, 30.0),
+    (32.0, 30.0, 32.0, 54.0),
+    (8.0, 54.0, 32.0, 54.0),
+    (8.0, 30.0, 8.0, 54.0),
+    (8.0, 6.0, 8.0, 30.0),
+    (8.0, 30.0, 32.0, 30.0),
+];
+// Lit segments per digit, bit 0 = a
+const DIGIT_SEGMENTS: [u8; 10] = [
+    0b0111111, 0b0000110, 0b1011011, 0b1001111, 0b1100110,
+    0b1101101, 0b1111101, 0b0000111, 0b1111111, 0b1101111,
+];
+
+struct Attempt {
+    operation: String,
+    layout: Vec<u8>,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Default)]
+pub struct PinPadState {
+    attempts: Mutex<HashMap<String, Attempt>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PinPad {
+    attempt_id: String,
+    // Key images in display order, as PNG data URLs. The glyph is in the alpha
+    // channel, so the view can tint it through a CSS mask.
+    keys: Vec<String>,
+    min_length: usize,
+    max_length: usize,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PinToken {
+    pin_token: String,
+    expires_at: DateTime<Utc>,
+}
+
+fn distance_to_segment(x: f32, y: f32, (x1, y1, x2, y2): (f32, f32, f32, f32)) -> f32 {
+    let (vx, vy) = (x2 - x1, y2 - y1);
+    let t = (((x - x1) * vx + (y - y1) * vy) / (vx * vx + vy * vy).max(f32::EPSILON)).clamp(0.0, 1.0);
+    ((x - x1 - t * vx).powi(2) + (y - y1 - t * vy).powi(2)).sqrt()
+}
+
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let mut crc = Crc::new();
+    crc.update(kind);
+    crc.update(data);
+    out.extend_from_slice(&crc.sum().to_be_bytes());
+}
+
+// 8-bit grey and alpha PNG from rows that each start with their filter byte
+fn encode_png(width: usize, height: usize, rows: &[u8]) -> Result<Vec<u8>, String> {
+    use std::io::Write;
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(rows).map_err(|e| e.to_string())?;
+    let data = encoder.finish().map_err(|e| e.to_string())?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    header.extend_from_slice(&[8, 4, 0, 0, 0]);
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    png_chunk(&mut png, b"IHDR", &header);
+    png_chunk(&mut png, b"IDAT", &data);
+    png_chunk(&mut png, b"IEND", &[]);
+    Ok(png)
+}
+
+// Bitmap of one key: the glyph's strokes are jittered, thickened at random and
+// warped, and every pixel gets noise, so the image differs on each render
+fn render_key(digit: u8, rng: &mut OsRng) -> Result<String, String> {
+    let (dx, dy) = (rng.gen_range(0.0..=4.0), rng.gen_range(0.0..=4.0));
+    let half_width = rng.gen_range(2.0..3.2);
+    let (amplitude, frequency, phase) = (rng.gen_range(0.8..2.0), rng.gen_range(0.15..0.35), rng.gen_range(0.0..std::f32::consts::TAU));
+    let mut jitter = || rng.gen_range(-1.5..=1.5);
+    let strokes: Vec<(f32, f32, f32, f32)> = SEGMENTS
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| DIGIT_SEGMENTS[digit as usize] & (1 << i) != 0)
+        .map(|(_, &(x1, y1, x2, y2))| (x1 + jitter(), y1 + jitter(), x2 + jitter(), y2 + jitter()))
+        .collect();
+
+    let mut rows = Vec::with_capacity((KEY_WIDTH * 2 + 1) * KEY_HEIGHT);
+    for y in 0..KEY_HEIGHT {
+        rows.push(0);
+        for x in 0..KEY_WIDTH {
+            let sx = x as f32 - dx + amplitude * (y as f32 * frequency + phase).sin();
+            let sy = y as f32 - dy + amplitude * (x as f32 * frequency + phase).cos();
+            let distance = strokes.iter().map(|&s| distance_to_segment(sx, sy, s)).fold(f32::MAX, f32::min);
+            let coverage = (half_width + 0.5 - distance).clamp(0.0, 1.0) * rng.gen_range(0.75..=1.0);
+            let alpha = coverage.max(rng.gen_range(0.0..0.2));
+            rows.extend_from_slice(&[0, (alpha * 255.0) as u8]);
+        }
+    }
+    let png = encode_png(KEY_WIDTH, KEY_HEIGHT, &rows)?;
+    Ok(format!("data:image/png;base64,{}", STANDARD.encode(png)))
+}
+
+fn random_id(rng: &mut OsRng) -> String {
+    (0..16).map(|_| format!("{:02x}", rng.gen::<u8>())).collect()
+}
+
+async fn verify_pin(api_url: &str, operation: &str, pin: &str) -> Result<PinToken, String> {
+    let client = crate::create_http_client()?;
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+
+    let response = crate::http::send(client.post(format!("{}/api/wallet/pin/verify", api_url))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "operation": operation, "pin": pin })))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("PIN verification failed: {}", error_text));
+    }
+
+    let api_response: crate::ApiResponse<PinToken> = response.json().await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    if api_response.success {
+        api_response.data.ok_or_else(|| "No PIN token returned".to_string())
+    } else {
+        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    }
+}
+
+#[tauri::command]
+pub async fn get_pin_pad_enabled(db: Db<'_>) -> Result<bool, String> {
+    Ok(db.get_value(KEY_ENABLED)?.is_some_and(|v| v == "true"))
+}
+
+#[tauri::command]
+pub async fn set_pin_pad_enabled(db: Db<'_>, enabled: bool) -> Result<(), String> {
+    db.set_value(KEY_ENABLED, if enabled { "true" } else { "false" })
+}
+
+// New shuffled layout for one entry attempt. Starting again discards earlier
+// attempts for the same operation.
+#[tauri::command]
+pub async fn start_pin_entry(
+    state: State<'_, PinPadState>,
+    compliance: State<'_, ComplianceState>,
+    api_url: String,
+    operation: String,
+) -> Result<PinPad, CommandError> {
+    if !OPERATIONS.contains(&operation.as_str()) {
+        return Err(format!("Unknown PIN operation: {}", operation).into());
+    }
+    if operation == "withdrawal" {
+        guard::require("withdraw", &guard::WITHDRAW)?;
+        compliance::require_feature(&compliance, &api_url, "withdrawals").await?;
+    }
+
+    let mut rng = OsRng;
+    let mut layout: Vec<u8> = (0..10).collect();
+    layout.shuffle(&mut rng);
+    let keys = layout.iter().map(|&digit| render_key(digit, &mut rng)).collect::<Result<_, _>>()?;
+    let attempt_id = random_id(&mut rng);
+    let now = Utc::now();
+    let expires_at = now + Duration::seconds(ATTEMPT_TTL_SECS);
+
+    let mut attempts = state.attempts.lock().map_err(|_| "PIN pad lock poisoned".to_string())?;
+    attempts.retain(|_, a| a.expires_at > now && a.operation != operation);
+    attempts.insert(attempt_id.clone(), Attempt { operation, layout, expires_at });
+
+    Ok(PinPad { attempt_id, keys, min_length: MIN_PIN_LEN, max_length: MAX_PIN_LEN, expires_at })
+}
+
+// Resolve the pressed key positions against the attempt's layout and verify the
+// PIN. Each attempt can be submitted once.
+#[tauri::command]
+pub async fn submit_pin_entry(
+    app: AppHandle,
+    state: State<'_, PinPadState>,
+    api_url: String,
+    attempt_id: String,
+    positions: Vec<usize>,
+) -> Result<PinToken, CommandError> {
+    let attempt = state
+        .attempts
+        .lock()
+        .map_err(|_| "PIN pad lock poisoned".to_string())?
+        .remove(&attempt_id)
+        .ok_or_else(|| "This PIN pad has expired, start again".to_string())?;
+    if attempt.expires_at <= Utc::now() {
+        return Err("This PIN pad has expired, start again".to_string().into());
+    }
+    if !(MIN_PIN_LEN..=MAX_PIN_LEN).contains(&positions.len()) {
+        return Err(format!("PINs are {} to {} digits", MIN_PIN_LEN, MAX_PIN_LEN).into());
+    }
+
+    let pin = positions
+        .iter()
+        .map(|&i| attempt.layout.get(i).map(|d| char::from(b'0' + d)))
+        .collect::<Option<String>>()
+        .ok_or_else(|| "Invalid key position".to_string())?;
+
+    let result = verify_pin(&api_url, &attempt.operation, &pin).await;
+    audit::record(&app, "verify_pin", json!({ "operation": attempt.operation }), &result);
+    Ok(result?)
+}
+
+#[tauri::command]
+pub async fn cancel_pin_entry(state: State<'_, PinPadState>, attempt_id: String) -> Result<(), String> {
+    state.attempts.lock().map_err(|_| "PIN pad lock poisoned".to_string())?.remove(&attempt_id);
+    Ok(())
+}
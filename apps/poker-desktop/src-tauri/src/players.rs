@@ -0,0 +1,209 @@
+// Player search. Backend results are cached briefly and merged with what the client
+// knows locally (notes, stats from stored hands) so the social and notes screens get
+// one enriched list. Calls for a query typed in quick succession are debounced: only
+// the latest one reaches the backend.
+
+use crate::db::{Database, Db};
+use crate::history::{list_hands, HandRecord};
+use crate::leaks::{is_blind, is_preflop};
+use crate::memory::{self, approx_size, CacheUsage, MemoryCache};
+use crate::notes::{get_note, PlayerNote};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::State;
+
+const DEBOUNCE: Duration = Duration::from_millis(300);
+const CACHE_TTL: Duration = Duration::from_secs(60);
+const MAX_CACHED_QUERIES: usize = 100;
+const MIN_QUERY_LEN: usize = 2;
+
+// Recent hands scanned for per-player stats
+const STATS_HANDS: u32 = 2000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemotePlayer {
+    id: String,
+    username: String,
+    // Anything else the backend returns is passed through untouched
+    #[serde(flatten)]
+    profile: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalStats {
+    pub hands: u32,
+    // Shares of hands where the player put money in voluntarily / raised preflop
+    pub vpip: Option<f64>,
+    pub pfr: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerSearchResult {
+    #[serde(flatten)]
+    player: RemotePlayer,
+    note: Option<PlayerNote>,
+    stats: LocalStats,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerSearchResponse {
+    query: String,
+    players: Vec<PlayerSearchResult>,
+    cached: bool,
+    // A newer search started while this one was waiting; the UI should drop it
+    superseded: bool,
+}
+
+#[derive(Default)]
+pub struct PlayerSearchState {
+    cache: Mutex<HashMap<String, (Instant, Vec<RemotePlayer>)>>,
+    generation: AtomicU64,
+}
+
+impl MemoryCache for PlayerSearchState {
+    fn usage(&self) -> CacheUsage {
+        let Ok(cache) = self.cache.lock() else { return CacheUsage::default() };
+        CacheUsage { entries: cache.len(), bytes: cache.iter().map(|(query, (_, players))| query.len() + approx_size(players)).sum() }
+    }
+
+    fn trim_to(&self, max_bytes: usize) {
+        if let Ok(mut cache) = self.cache.lock() {
+            memory::trim_oldest(&mut cache, max_bytes, |(_, players)| approx_size(players), |(at, _)| *at);
+        }
+    }
+}
+
+impl PlayerSearchState {
+    fn cached(&self, query: &str) -> Result<Option<Vec<RemotePlayer>>, String> {
+        let cache = self.cache.lock().map_err(|_| "Player search lock poisoned".to_string())?;
+        Ok(cache
+            .get(query)
+            .filter(|(at, _)| at.elapsed() < CACHE_TTL)
+            .map(|(_, players)| players.clone()))
+    }
+
+    fn store(&self, query: &str, players: &[RemotePlayer]) -> Result<(), String> {
+        let mut cache = self.cache.lock().map_err(|_| "Player search lock poisoned".to_string())?;
+        cache.retain(|_, (at, _)| at.elapsed() < CACHE_TTL);
+        if cache.len() >= MAX_CACHED_QUERIES {
+            if let Some(oldest) = cache.iter().min_by_key(|(_, (at, _))| *at).map(|(q, _)| q.clone()) {
+                cache.remove(&oldest);
+            }
+        }
+        cache.insert(query.to_string(), (Instant::now(), players.to_vec()));
+        Ok(())
+    }
+}
+
+async fn fetch_players(api_url: &str, query: &str) -> Result<Vec<RemotePlayer>, String> {
+    let client = crate::create_http_client()?;
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+
+    let response = crate::http::send(client.get(format!("{}/api/players/search", api_url))
+        .query(&[("q", query)])
+        .header("Authorization", format!("Bearer {}", token)))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        return Err("Failed to search players".to_string());
+    }
+
+    let api_response: crate::ApiResponse<Vec<RemotePlayer>> = response.json().await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    if api_response.success {
+        Ok(api_response.data.unwrap_or_default())
+    } else {
+        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    }
+}
+
+// VPIP and PFR for each of `ids` over the given hands
+pub fn local_stats(hands: &[HandRecord], ids: &HashSet<&str>) -> HashMap<String, LocalStats> {
+    let mut counts: HashMap<&str, (u32, u32, u32)> = HashMap::new();
+    for hand in hands {
+        let mut voluntary = HashSet::new();
+        let mut raised = HashSet::new();
+        let mut current_bet = 0;
+        let mut street_bets: HashMap<&str, u32> = HashMap::new();
+        for action in hand.actions.iter().filter(|a| is_preflop(&a.street)) {
+            let bet = street_bets.entry(action.player_id.as_str()).or_insert(0);
+            *bet += action.amount;
+            if !is_blind(&action.action) && action.amount > 0 {
+                voluntary.insert(action.player_id.as_str());
+                if *bet > current_bet {
+                    raised.insert(action.player_id.as_str());
+                }
+            }
+            current_bet = current_bet.max(*bet);
+        }
+
+        for player in hand.players.iter().filter(|p| ids.contains(p.player_id.as_str())) {
+            let entry = counts.entry(player.player_id.as_str()).or_default();
+            entry.0 += 1;
+            entry.1 += voluntary.contains(player.player_id.as_str()) as u32;
+            entry.2 += raised.contains(player.player_id.as_str()) as u32;
+        }
+    }
+
+    counts
+        .into_iter()
+        .map(|(id, (hands, vpip, pfr))| {
+            let rate = |n: u32| (hands > 0).then(|| n as f64 / hands as f64);
+            (id.to_string(), LocalStats { hands, vpip: rate(vpip), pfr: rate(pfr) })
+        })
+        .collect()
+}
+
+fn enrich(db: &Database, players: Vec<RemotePlayer>) -> Result<Vec<PlayerSearchResult>, String> {
+    let ids: HashSet<&str> = players.iter().map(|p| p.id.as_str()).collect();
+    let hands = db.with_conn(|conn| list_hands(conn, STATS_HANDS, 0))?;
+    let mut stats = local_stats(&hands, &ids);
+
+    players
+        .into_iter()
+        .map(|player| {
+            let note = db.with_conn(|conn| get_note(conn, &player.id))?;
+            let stats = stats.remove(&player.id).unwrap_or_default();
+            Ok(PlayerSearchResult { player, note, stats })
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn search_players(
+    db: Db<'_>,
+    state: State<'_, PlayerSearchState>,
+    api_url: String,
+    query: String,
+) -> Result<PlayerSearchResponse, String> {
+    let query = query.trim().to_lowercase();
+    let respond = |players, cached, superseded| PlayerSearchResponse { query: query.clone(), players, cached, superseded };
+    if query.chars().count() < MIN_QUERY_LEN {
+        return Ok(respond(Vec::new(), false, false));
+    }
+
+    if let Some(players) = state.cached(&query)? {
+        return Ok(respond(enrich(&db, players)?, true, false));
+    }
+
+    let generation = state.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    tokio::time::sleep(DEBOUNCE).await;
+    if state.generation.load(Ordering::SeqCst) != generation {
+        return Ok(respond(Vec::new(), false, true));
+    }
+
+    let players = fetch_players(&api_url, &query).await?;
+    state.store(&query, &players)?;
+    Ok(respond(enrich(&db, players)?, false, false))
+}
@@ -0,0 +1,489 @@
+// Offline practice table against local AI opponents. Runs entirely on the local
+// engine; no backend connection or real chips are involved. Finished hands are saved
+// to local history under a `practice-` table id so they can be replayed.
+
+use crate::cards::Card;
+use crate::db::Database;
+use crate::engine::{HandResult, LocalTable};
+use crate::evaluator::{evaluate, HandCategory};
+use crate::history::{self, AnteStructure, HandAction};
+use crate::preflop;
+use crate::ranges::HandClass;
+use crate::sizing::{self, BettingHint, Limit, TableRules};
+use crate::table_state::{LegalAction, TableMirror};
+use crate::verify;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+pub const HERO_ID: &str = "hero";
+
+// Safety net against a bot loop that never hands the action back
+const MAX_BOT_ACTIONS: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AiProfile {
+    Tight,
+    Loose,
+    Aggressive,
+}
+
+// Decision thresholds on a 0..1 hand strength scale
+struct Style {
+    play: f64,
+    raise: f64,
+    bluff: f64,
+    // Bet size as a fraction of the pot
+    sizing: f64,
+}
+
+impl AiProfile {
+    fn style(self) -> Style {
+        match self {
+            AiProfile::Tight => Style { play: 0.45, raise: 0.7, bluff: 0.03, sizing: 0.6 },
+            AiProfile::Loose => Style { play: 0.25, raise: 0.75, bluff: 0.08, sizing: 0.5 },
+            AiProfile::Aggressive => Style { play: 0.32, raise: 0.55, bluff: 0.2, sizing: 0.9 },
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            AiProfile::Tight => "Tight",
+            AiProfile::Loose => "Loose",
+            AiProfile::Aggressive => "Aggressive",
+        }
+    }
+}
+
+fn default_opponents() -> Vec<AiProfile> {
+    vec![AiProfile::Tight, AiProfile::Loose, AiProfile::Aggressive]
+}
+
+fn default_starting_stack() -> u32 {
+    1000
+}
+
+fn default_small_blind() -> u32 {
+    5
+}
+
+fn default_big_blind() -> u32 {
+    10
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PracticeConfig {
+    #[serde(default = "default_opponents")]
+    opponents: Vec<AiProfile>,
+    #[serde(default = "default_starting_stack")]
+    starting_stack: u32,
+    #[serde(default = "default_small_blind")]
+    small_blind: u32,
+    #[serde(default = "default_big_blind")]
+    big_blind: u32,
+    #[serde(default)]
+    ante: u32,
+    #[serde(default)]
+    ante_structure: AnteStructure,
+    #[serde(default)]
+    betting_structure: Limit,
+}
+
+impl Default for PracticeConfig {
+    fn default() -> Self {
+        Self {
+            opponents: default_opponents(),
+            starting_stack: default_starting_stack(),
+            small_blind: default_small_blind(),
+            big_blind: default_big_blind(),
+            ante: 0,
+            ante_structure: AnteStructure::Standard,
+            betting_structure: Limit::No,
+        }
+    }
+}
+
+struct PracticeSession {
+    table: LocalTable,
+    profiles: Vec<(String, AiProfile)>,
+    starting_stack: u32,
+    rng: StdRng,
+    // Index into the hand's action log up to which the frontend has been shown
+    seen_actions: usize,
+    saved_hand: u32,
+}
+
+#[derive(Default)]
+pub struct PracticeState {
+    session: Mutex<Option<PracticeSession>>,
+}
+
+impl PracticeState {
+    // The practice table for the bet slider, when `table_id` is the one in play
+    pub fn slider_source(&self, table_id: &str) -> Result<Option<(TableMirror, TableRules, String)>, String> {
+        let session = self.session.lock().map_err(|_| "Practice lock poisoned".to_string())?;
+        Ok(session.as_ref().filter(|s| s.table.table_id == table_id).map(|s| {
+            (s.table.mirror(Some(HERO_ID)), s.table.rules(), HERO_ID.to_string())
+        }))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PracticeView {
+    table: TableMirror,
+    hero_id: String,
+    legal_actions: Vec<LegalAction>,
+    // Actions since the previous view, in order, so the table can animate them
+    new_actions: Vec<HandAction>,
+    result: Option<HandResult>,
+    // Betting on the current street under the table's structure
+    hint: Option<BettingHint>,
+}
+
+// Rough preflop strength from the Chen formula, scaled to 0..1
+fn preflop_strength(hole: [Card; 2]) -> f64 {
+    let (high, low) = if hole[0].rank >= hole[1].rank { (hole[0], hole[1]) } else { (hole[1], hole[0]) };
+    let mut score = match high.rank {
+        14 => 10.0,
+        13 => 8.0,
+        12 => 7.0,
+        11 => 6.0,
+        r => r as f64 / 2.0,
+    };
+    if high.rank == low.rank {
+        score = (score * 2.0).max(5.0);
+    } else {
+        if high.suit == low.suit {
+            score += 2.0;
+        }
+        let gap = high.rank - low.rank - 1;
+        score -= match gap {
+            0 => 0.0,
+            1 => 1.0,
+            2 => 2.0,
+            3 => 4.0,
+            _ => 5.0,
+        };
+        if gap <= 1 && high.rank < 12 {
+            score += 1.0;
+        }
+    }
+    (score / 20.0).clamp(0.0, 1.0)
+}
+
+// Postflop strength from the made hand, discounted when the board alone makes it
+fn postflop_strength(hole: [Card; 2], board: &[Card]) -> f64 {
+    let cards: Vec<Card> = hole.iter().chain(board).copied().collect();
+    let value = evaluate(&cards);
+    let base = match value.category {
+        HandCategory::HighCard => 0.05 + (value.ranks[0] as f64 - 2.0) / 12.0 * 0.15,
+        HandCategory::Pair => 0.35 + (value.ranks[0] as f64 - 2.0) / 12.0 * 0.2,
+        HandCategory::TwoPair => 0.65,
+        HandCategory::ThreeOfAKind => 0.75,
+        HandCategory::Straight => 0.82,
+        HandCategory::Flush => 0.86,
+        HandCategory::FullHouse => 0.93,
+        HandCategory::FourOfAKind | HandCategory::StraightFlush => 0.98,
+    };
+    if evaluate(board).category == value.category {
+        base * 0.5
+    } else {
+        base
+    }
+}
+
+// Seat's chart position this hand, counted clockwise from the button
+fn chart_position(table: &LocalTable, seat: usize) -> &'static str {
+    let n = table.seats.len();
+    let order: Vec<usize> = (0..n)
+        .map(|step| (table.button + step) % n)
+        .filter(|&i| table.seats[i].in_hand)
+        .collect();
+    let after_button = order.iter().position(|&i| i == seat).unwrap_or(0);
+    preflop::position_name(after_button, order.len())
+}
+
+// Preflop play sampled from the bundled charts, skewed by profile. None when no
+// chart covers the spot (e.g. the big blind in a limped pot).
+fn preflop_decision(
+    table: &LocalTable,
+    seat: usize,
+    hole: [Card; 2],
+    profile: AiProfile,
+    rng: &mut StdRng,
+) -> Option<(String, u32)> {
+    let raises = table
+        .actions
+        .iter()
+        .filter(|a| matches!(a.action.as_str(), "bet" | "raise" | "all_in"))
+        .count();
+    let facing = match raises {
+        0 => "unopened",
+        1 => "raise",
+        _ => "three_bet",
+    };
+    let stack_bb = table.seats[seat].starting_stack as f64 / table.big_blind.max(1) as f64;
+    let (mut raise, mut call, mut fold) =
+        preflop::frequencies(chart_position(table, seat), facing, stack_bb, HandClass::of(hole)).ok()?;
+    match profile {
+        AiProfile::Tight => {}
+        AiProfile::Loose => {
+            call += fold * 0.35;
+            fold *= 0.65;
+        }
+        AiProfile::Aggressive => {
+            raise += call * 0.4 + fold * 0.1;
+            call *= 0.6;
+            fold *= 0.9;
+        }
+    }
+
+    let legal = table.legal_actions();
+    let find = |name: &str| legal.iter().find(|a| a.action == name);
+    let street_bet = table.seats[seat].street_bet;
+    let to_call = table.current_bet.saturating_sub(street_bet);
+    let roll = rng.gen::<f64>() * (raise + call + fold).max(f64::EPSILON);
+
+    if roll < raise {
+        if let Some(option) = find("raise").or_else(|| find("bet")) {
+            // Open to 2.5 big blinds, otherwise three times the bet faced
+            let target = if raises == 0 { table.big_blind * 5 / 2 } else { table.current_bet * 3 };
+            let mut amount = target.saturating_sub(street_bet).clamp(option.min_amount, option.max_amount);
+            // Shove rather than leave a sliver behind
+            if amount * 2 >= option.max_amount {
+                amount = option.max_amount;
+            }
+            return Some((option.action.clone(), amount));
+        }
+    }
+    if to_call == 0 {
+        return Some(("check".to_string(), 0));
+    }
+    if roll < raise + call {
+        if find("call").is_some() {
+            return Some(("call".to_string(), to_call));
+        }
+        return Some(("all_in".to_string(), 0));
+    }
+    Some(("fold".to_string(), 0))
+}
+
+fn decide(table: &LocalTable, seat: usize, profile: AiProfile, rng: &mut StdRng) -> (String, u32) {
+    let legal = table.legal_actions();
+    let find = |name: &str| legal.iter().find(|a| a.action == name);
+    let Some(hole) = table.seats[seat].hole_cards else { return ("fold".to_string(), 0) };
+    if table.board.is_empty() {
+        if let Some(decision) = preflop_decision(table, seat, hole, profile, rng) {
+            return decision;
+        }
+    }
+
+    let style = profile.style();
+    let strength = if table.board.is_empty() {
+        preflop_strength(hole)
+    } else {
+        postflop_strength(hole, &table.board)
+    } + rng.gen_range(-0.05..0.05);
+
+    let to_call = table.current_bet.saturating_sub(table.seats[seat].street_bet);
+    let pot = table.pot();
+
+    if strength >= style.raise || rng.gen_bool(style.bluff) {
+        if let Some(option) = find("bet").or_else(|| find("raise")) {
+            let target = to_call + (pot as f64 * style.sizing) as u32;
+            return (option.action.clone(), target.clamp(option.min_amount, option.max_amount));
+        }
+    }
+    if to_call == 0 {
+        return ("check".to_string(), 0);
+    }
+
+    let pot_odds = to_call as f64 / (pot + to_call) as f64;
+    if strength >= style.play && strength >= pot_odds {
+        if find("call").is_some() {
+            return ("call".to_string(), to_call);
+        }
+        if strength >= style.raise {
+            return ("all_in".to_string(), 0);
+        }
+    }
+    ("fold".to_string(), 0)
+}
+
+impl PracticeSession {
+    fn new(config: &PracticeConfig) -> Self {
+        let table_id = format!("practice-{}", chrono::Utc::now().timestamp_millis());
+        let mut table = LocalTable::new(&table_id, "Practice", config.small_blind, config.big_blind);
+        table.set_ante(config.ante, config.ante_structure);
+        table.betting_structure = config.betting_structure;
+        table.add_seat(HERO_ID, "You", config.starting_stack);
+
+        let profiles: Vec<(String, AiProfile)> = config
+            .opponents
+            .iter()
+            .enumerate()
+            .map(|(i, profile)| (format!("bot-{}", i + 1), *profile))
+            .collect();
+        for (i, (id, profile)) in profiles.iter().enumerate() {
+            table.add_seat(id, &format!("{} Bot {}", profile.label(), i + 1), config.starting_stack);
+        }
+
+        Self {
+            table,
+            profiles,
+            starting_stack: config.starting_stack,
+            rng: StdRng::from_entropy(),
+            seen_actions: 0,
+            saved_hand: 0,
+        }
+    }
+
+    fn deal(&mut self) -> Result<(), String> {
+        // Practice chips are free: top up anyone who busted
+        for seat in &mut self.table.seats {
+            if seat.stack == 0 {
+                seat.stack = self.starting_stack;
+            }
+        }
+        self.table.start_hand(&mut self.rng)?;
+        self.seen_actions = 0;
+        self.run_bots()
+    }
+
+    // Let the bots act until it is the hero's turn or the hand ends
+    fn run_bots(&mut self) -> Result<(), String> {
+        for _ in 0..MAX_BOT_ACTIONS {
+            let Some(seat) = self.table.to_act else { return Ok(()) };
+            let player_id = self.table.seats[seat].player_id.clone();
+            if player_id == HERO_ID {
+                return Ok(());
+            }
+            let profile = self
+                .profiles
+                .iter()
+                .find(|(id, _)| *id == player_id)
+                .map(|(_, profile)| *profile)
+                .unwrap_or(AiProfile::Tight);
+            let (action, amount) = decide(&self.table, seat, profile, &mut self.rng);
+            if self.table.act(&player_id, &action, amount).is_err() {
+                // Should not happen, but never leave the table stuck on a bot
+                let fallback = if self.table.legal_actions().iter().any(|a| a.action == "check") { "check" } else { "fold" };
+                self.table.act(&player_id, fallback, 0)?;
+            }
+        }
+        Err("Practice bots did not finish acting".to_string())
+    }
+
+    fn view(&mut self) -> PracticeView {
+        let new_actions = self.table.actions.get(self.seen_actions..).unwrap_or_default().to_vec();
+        self.seen_actions = self.table.actions.len();
+        let hero_turn = self.table.to_act.is_some_and(|i| self.table.seats[i].player_id == HERO_ID);
+
+        let table = self.table.mirror(Some(HERO_ID));
+        PracticeView {
+            hint: sizing::street_hint(&table, &self.table.rules()),
+            table,
+            hero_id: HERO_ID.to_string(),
+            legal_actions: if hero_turn { self.table.legal_actions() } else { Vec::new() },
+            new_actions,
+            result: self.table.result.clone(),
+        }
+    }
+
+    // Store the hand once it is over, if the local database is available
+    fn save_if_finished(&mut self, app: &AppHandle) {
+        if self.table.result.is_none() || self.saved_hand == self.table.hand_number {
+            return;
+        }
+        self.saved_hand = self.table.hand_number;
+        if let Some(db) = app.try_state::<Database>() {
+            let record = self.table.to_hand_record(HERO_ID);
+            if let Err(e) = db.with_conn(|conn| history::upsert_hand(conn, &record)) {
+                eprintln!("Failed to save practice hand: {}", e);
+            }
+        }
+    }
+}
+
+fn with_session<T>(
+    state: &PracticeState,
+    f: impl FnOnce(&mut PracticeSession) -> Result<T, String>,
+) -> Result<T, String> {
+    let mut session = state.session.lock().map_err(|_| "Practice lock poisoned".to_string())?;
+    let session = session.as_mut().ok_or_else(|| "No practice table is open".to_string())?;
+    f(session)
+}
+
+// Open a practice table and deal the first hand
+#[tauri::command]
+pub async fn start_practice(
+    app: AppHandle,
+    state: State<'_, PracticeState>,
+    config: Option<PracticeConfig>,
+) -> Result<PracticeView, String> {
+    let config = config.unwrap_or_default();
+    if config.opponents.is_empty() || config.opponents.len() > 8 {
+        return Err("Practice tables need between 1 and 8 opponents".to_string());
+    }
+    if config.small_blind == 0 || config.big_blind < config.small_blind || config.starting_stack < config.big_blind {
+        return Err("Invalid blinds or starting stack".to_string());
+    }
+    if config.ante > config.big_blind {
+        return Err("The ante cannot be more than the big blind".to_string());
+    }
+
+    let mut session = PracticeSession::new(&config);
+    session.deal()?;
+    session.save_if_finished(&app);
+    verify::report(&app, std::mem::take(&mut session.table.violations));
+    let view = session.view();
+
+    *state.session.lock().map_err(|_| "Practice lock poisoned".to_string())? = Some(session);
+    Ok(view)
+}
+
+#[tauri::command]
+pub async fn practice_action(
+    app: AppHandle,
+    state: State<'_, PracticeState>,
+    action: String,
+    amount: Option<u32>,
+) -> Result<PracticeView, String> {
+    with_session(&state, |session| {
+        session.table.act(HERO_ID, &action, amount.unwrap_or(0))?;
+        session.run_bots()?;
+        session.save_if_finished(&app);
+        verify::report(&app, std::mem::take(&mut session.table.violations));
+        Ok(session.view())
+    })
+}
+
+#[tauri::command]
+pub async fn next_practice_hand(app: AppHandle, state: State<'_, PracticeState>) -> Result<PracticeView, String> {
+    with_session(&state, |session| {
+        if !session.table.is_hand_over() {
+            return Err("The current hand is still in progress".to_string());
+        }
+        session.deal()?;
+        session.save_if_finished(&app);
+        verify::report(&app, std::mem::take(&mut session.table.violations));
+        Ok(session.view())
+    })
+}
+
+#[tauri::command]
+pub async fn get_practice_state(state: State<'_, PracticeState>) -> Result<Option<PracticeView>, String> {
+    let mut session = state.session.lock().map_err(|_| "Practice lock poisoned".to_string())?;
+    Ok(session.as_mut().map(|s| s.view()))
+}
+
+#[tauri::command]
+pub async fn end_practice(state: State<'_, PracticeState>) -> Result<(), String> {
+    *state.session.lock().map_err(|_| "Practice lock poisoned".to_string())? = None;
+    Ok(())
+}
@@ -0,0 +1,173 @@
+// Preflop charts bundled with the client (`charts/preflop.json`), keyed by position,
+// the action faced and stack depth. Each chart lists a raise range and a call range in
+// standard notation with optional weights ("A5s:0.5"); hands in neither fold.
+
+use crate::cards::Card;
+use crate::ranges::{parse_range, HandClass};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+const CHARTS: &str = include_str!("../charts/preflop.json");
+
+pub const FACING: &[&str] = &["unopened", "raise", "three_bet"];
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChartFile {
+    charts: Vec<ChartEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChartEntry {
+    position: String,
+    facing: String,
+    stack_bb: u32,
+    raise: String,
+    #[serde(default)]
+    call: String,
+}
+
+struct Chart {
+    position: String,
+    facing: String,
+    stack_bb: u32,
+    // Raise and call frequency per class
+    frequencies: HashMap<HandClass, (f64, f64)>,
+}
+
+static PARSED: OnceLock<Result<Vec<Chart>, String>> = OnceLock::new();
+
+fn charts() -> Result<&'static [Chart], String> {
+    let parsed = PARSED.get_or_init(|| {
+        let file: ChartFile = serde_json::from_str(CHARTS).map_err(|e| format!("Invalid preflop charts: {}", e))?;
+        file.charts
+            .into_iter()
+            .map(|entry| {
+                let mut frequencies: HashMap<HandClass, (f64, f64)> = HashMap::new();
+                for (hand, weight) in parse_range(&entry.raise)? {
+                    frequencies.entry(hand).or_default().0 += weight;
+                }
+                for (hand, weight) in parse_range(&entry.call)? {
+                    let slot = frequencies.entry(hand).or_default();
+                    slot.1 = (slot.1 + weight).min(1.0 - slot.0.min(1.0));
+                }
+                Ok(Chart { position: entry.position, facing: entry.facing, stack_bb: entry.stack_bb, frequencies })
+            })
+            .collect()
+    });
+    parsed.as_deref().map_err(|e| e.clone())
+}
+
+pub fn normalize_position(position: &str) -> Option<&'static str> {
+    let upper = position.trim().to_ascii_uppercase();
+    let name = match upper.as_str() {
+        "UTG" | "UTG+1" | "UTG+2" | "EP" => "UTG",
+        "MP" | "MP1" | "MP2" | "LJ" | "HJ" => "MP",
+        "CO" | "CUTOFF" => "CO",
+        "BTN" | "BU" | "BUTTON" | "D" => "BTN",
+        "SB" => "SB",
+        "BB" => "BB",
+        _ => return None,
+    };
+    Some(name)
+}
+
+// Chart position for the seat `after_button` places clockwise of the button
+pub fn position_name(after_button: usize, players: usize) -> &'static str {
+    if players <= 2 {
+        return if after_button == 0 { "SB" } else { "BB" };
+    }
+    match after_button {
+        0 => "BTN",
+        1 => "SB",
+        2 => "BB",
+        n if n + 1 == players => "CO",
+        n if n + 2 == players => "MP",
+        _ => "UTG",
+    }
+}
+
+// Closest chart for the situation, preferring the shallower one on ties
+fn find_chart(position: &str, facing: &str, stack_bb: f64) -> Result<&'static Chart, String> {
+    let position = normalize_position(position).ok_or_else(|| format!("Unknown position '{}'", position))?;
+    if !FACING.contains(&facing) {
+        return Err(format!("Unknown action faced '{}'", facing));
+    }
+    charts()?
+        .iter()
+        .filter(|c| c.position == position && c.facing == facing)
+        .min_by(|a, b| {
+            let distance = |c: &Chart| (c.stack_bb as f64 - stack_bb).abs();
+            distance(a).total_cmp(&distance(b)).then(a.stack_bb.cmp(&b.stack_bb))
+        })
+        .ok_or_else(|| format!("No chart for {} facing {}", position, facing))
+}
+
+// (raise, call, fold) frequencies for one hand
+pub fn frequencies(position: &str, facing: &str, stack_bb: f64, hand: HandClass) -> Result<(f64, f64, f64), String> {
+    let chart = find_chart(position, facing, stack_bb)?;
+    let (raise, call) = chart.frequencies.get(&hand).copied().unwrap_or_default();
+    let raise = raise.min(1.0);
+    Ok((raise, call, (1.0 - raise - call).max(0.0)))
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HandFrequencies {
+    hand: String,
+    raise: f64,
+    call: f64,
+    fold: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflopAdvice {
+    position: String,
+    action_facing: String,
+    // Depth of the chart used, which may differ from the requested stack
+    stack_depth: u32,
+    hands: Vec<HandFrequencies>,
+}
+
+// Chart for the situation; all 169 hands, or only `hand` when given ("AKs" or "AhKh")
+#[tauri::command]
+pub async fn get_preflop_advice(
+    position: String,
+    action_facing: String,
+    stack_bb: f64,
+    hand: Option<String>,
+) -> Result<PreflopAdvice, String> {
+    let chart = find_chart(&position, &action_facing, stack_bb)?;
+    let classes = match hand.as_deref() {
+        Some(text) => {
+            let from_cards = || -> Option<HandClass> {
+                // Suit symbols take more than one byte
+                let at = text.len().checked_sub(2).filter(|&at| text.is_char_boundary(at))?;
+                let (first, second) = text.split_at(at);
+                Some(HandClass::of([Card::parse(first)?, Card::parse(second)?]))
+            };
+            let class = HandClass::parse(text).or_else(from_cards).ok_or_else(|| format!("Invalid hand '{}'", text))?;
+            vec![class]
+        }
+        None => HandClass::all(),
+    };
+
+    let hands = classes
+        .into_iter()
+        .map(|class| {
+            let (raise, call) = chart.frequencies.get(&class).copied().unwrap_or_default();
+            let raise = raise.min(1.0);
+            HandFrequencies { hand: class.notation(), raise, call, fold: (1.0 - raise - call).max(0.0) }
+        })
+        .collect();
+
+    Ok(PreflopAdvice {
+        position: chart.position.clone(),
+        action_facing: chart.facing.clone(),
+        stack_depth: chart.stack_bb,
+        hands,
+    })
+}
@@ -0,0 +1,227 @@
+// Lobby table previews. Hovering a table shows who is seated, their stacks and the
+// current pot. The snapshot comes from the table state endpoint, or from a brief
+// spectator subscription when that fails, and is cached so repeated hovers do not
+// hit the backend.
+
+use crate::db::{Database, Db};
+use crate::history::parse_hand;
+use crate::memory::{self, approx_size, CacheUsage, MemoryCache};
+use crate::notes::{self, PlayerLabel};
+use crate::profile::BackendProfile;
+use crate::table_state::TableMirror;
+use crate::table_stats::{Observation, TableStatsState};
+use crate::ws::{self, WsMessage};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::State;
+
+const CACHE_TTL: Duration = Duration::from_secs(15);
+const MAX_CACHED: usize = 50;
+const SPECTATE_TIMEOUT: Duration = Duration::from_secs(3);
+const AVERAGE_POT_HANDS: u32 = 20;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewSeat {
+    id: String,
+    username: String,
+    chips: u32,
+    seat: Option<u8>,
+    // Filled from notes each time the preview is served, so it is never stale
+    label: Option<PlayerLabel>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TablePreview {
+    table_id: String,
+    phase: String,
+    pot: u32,
+    players: Vec<PreviewSeat>,
+    // Over the last hands at this table in local history
+    average_pot: Option<f64>,
+    hands_sampled: u32,
+    // "rest" or "spectator"
+    source: String,
+    fetched_at: DateTime<Utc>,
+}
+
+#[derive(Default)]
+pub struct PreviewState {
+    cache: Mutex<HashMap<String, (Instant, TablePreview)>>,
+}
+
+impl PreviewState {
+    // Players seated at each table with a fresh preview
+    pub fn seated(&self) -> Result<HashMap<String, Vec<String>>, String> {
+        let cache = self.cache.lock().map_err(|_| "Preview cache lock poisoned".to_string())?;
+        Ok(cache
+            .iter()
+            .filter(|(_, (at, _))| at.elapsed() < CACHE_TTL)
+            .map(|(id, (_, preview))| (id.clone(), preview.players.iter().map(|p| p.id.clone()).collect()))
+            .collect())
+    }
+}
+
+fn with_labels(db: &Database, mut preview: TablePreview) -> Result<TablePreview, String> {
+    let mut labels = db.with_conn(|conn| notes::labels_for(conn, preview.players.iter().map(|p| p.id.as_str())))?;
+    for player in &mut preview.players {
+        player.label = labels.remove(&player.id);
+    }
+    Ok(preview)
+}
+
+impl MemoryCache for PreviewState {
+    fn usage(&self) -> CacheUsage {
+        let Ok(cache) = self.cache.lock() else { return CacheUsage::default() };
+        CacheUsage { entries: cache.len(), bytes: cache.values().map(|(_, preview)| approx_size(preview)).sum() }
+    }
+
+    fn trim_to(&self, max_bytes: usize) {
+        if let Ok(mut cache) = self.cache.lock() {
+            memory::trim_oldest(&mut cache, max_bytes, |(_, preview)| approx_size(preview), |(at, _)| *at);
+        }
+    }
+}
+
+// Table state as sent by the backend, with the pot and phase nested under
+// `gameState` in some responses
+pub fn mirror_from_state(state: &Value) -> Result<TableMirror, String> {
+    let mut mirror: TableMirror = serde_json::from_value(state.clone())
+        .map_err(|e| format!("Invalid table state: {}", e))?;
+    let game = &state["gameState"];
+    if let Some(pot) = game["pot"].as_u64() {
+        mirror.pot = pot as u32;
+    }
+    if let Some(phase) = game["phase"].as_str() {
+        mirror.phase = phase.to_string();
+    }
+    Ok(mirror)
+}
+
+pub async fn fetch_state(api_url: &str, table_id: &str) -> Result<TableMirror, String> {
+    let client = crate::create_http_client()?;
+    let mut request = client.get(format!("{}/api/tables/{}", api_url, table_id));
+    if let Ok(token) = crate::get_token_from_keyring() {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+
+    let response = crate::http::send(request).await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err("Failed to fetch table state".to_string());
+    }
+
+    let api_response: crate::ApiResponse<Value> = response.json().await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+    match api_response.data {
+        Some(state) if api_response.success => mirror_from_state(&state),
+        _ => Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string())),
+    }
+}
+
+// Join as a spectator just long enough to receive one table snapshot
+async fn spectate(ws_url: &str, table_id: &str) -> Result<TableMirror, String> {
+    let token = crate::get_token_from_keyring().unwrap_or_default();
+    let (socket, mut incoming) = ws::connect(&ws::table_url(ws_url, &token, table_id)).await?;
+    let spectator_id = format!("preview-{}", Utc::now().timestamp_millis());
+    socket.send(WsMessage::new("spectate_table", json!({ "tableId": table_id, "playerId": spectator_id, "username": "preview" })))?;
+
+    let snapshot = tokio::time::timeout(SPECTATE_TIMEOUT, async {
+        let mut mirror = TableMirror::default();
+        while let Some(message) = incoming.recv().await {
+            match message.kind.as_str() {
+                "spectator_joined" | "table_state_update" => {
+                    let state = message.payload.get("tableState").unwrap_or(&message.payload);
+                    return mirror_from_state(state);
+                }
+                "game_update" => {
+                    mirror.apply(&message)?;
+                    return Ok(mirror);
+                }
+                _ => {}
+            }
+        }
+        Err("Server closed the connection".to_string())
+    })
+    .await
+    .map_err(|_| "Timed out waiting for the table snapshot".to_string())?;
+
+    let _ = socket.send(WsMessage::new("leave_spectator", json!({ "tableId": table_id, "playerId": spectator_id })));
+    snapshot
+}
+
+// Average final pot over the most recent hands stored for the table
+fn average_pot(db: &Database, table_id: &str) -> Result<(Option<f64>, u32), String> {
+    let pots = db.with_conn(|conn| {
+        let mut stmt = conn.prepare("SELECT data FROM hands WHERE table_id = ?1 ORDER BY played_at DESC LIMIT ?2")?;
+        let rows = stmt.query_map(rusqlite::params![table_id, AVERAGE_POT_HANDS], |row| row.get::<_, String>(0))?;
+        rows.map(|row| row.and_then(parse_hand).map(|hand| hand.pot)).collect::<rusqlite::Result<Vec<u32>>>()
+    })?;
+    let count = pots.len() as u32;
+    let average = (count > 0).then(|| pots.iter().map(|&p| p as f64).sum::<f64>() / count as f64);
+    Ok((average, count))
+}
+
+#[tauri::command]
+pub async fn get_table_preview(
+    db: Db<'_>,
+    profile: State<'_, BackendProfile>,
+    state: State<'_, PreviewState>,
+    stats: State<'_, TableStatsState>,
+    api_url: String,
+    table_id: String,
+) -> Result<TablePreview, String> {
+    let cached = {
+        let cache = state.cache.lock().map_err(|_| "Preview cache lock poisoned".to_string())?;
+        cache.get(&table_id).filter(|(at, _)| at.elapsed() < CACHE_TTL).map(|(_, preview)| preview.clone())
+    };
+    if let Some(preview) = cached {
+        return with_labels(&db, preview);
+    }
+
+    let (mirror, source) = match fetch_state(&api_url, &table_id).await {
+        Ok(mirror) => (mirror, "rest"),
+        Err(e) => {
+            eprintln!("Table state fetch failed for {}, spectating instead: {}", table_id, e);
+            (spectate(&profile.ws_url, &table_id).await?, "spectator")
+        }
+    };
+    stats.observe(&table_id, &Observation::from_mirror(&mirror))?;
+    let (average_pot, hands_sampled) = average_pot(&db, &table_id)?;
+
+    let preview = TablePreview {
+        table_id: table_id.clone(),
+        phase: mirror.phase,
+        pot: mirror.pot,
+        players: mirror
+            .players
+            .into_iter()
+            .map(|p| PreviewSeat {
+                seat: p.position.map(|pos| pos.seat),
+                id: p.id,
+                username: p.username,
+                chips: p.chips,
+                label: None,
+            })
+            .collect(),
+        average_pot,
+        hands_sampled,
+        source: source.to_string(),
+        fetched_at: Utc::now(),
+    };
+
+    let mut cache = state.cache.lock().map_err(|_| "Preview cache lock poisoned".to_string())?;
+    cache.retain(|_, (at, _)| at.elapsed() < CACHE_TTL);
+    if cache.len() >= MAX_CACHED {
+        if let Some(oldest) = cache.iter().min_by_key(|(_, (at, _))| *at).map(|(id, _)| id.clone()) {
+            cache.remove(&oldest);
+        }
+    }
+    cache.insert(table_id, (Instant::now(), preview.clone()));
+    drop(cache);
+    with_labels(&db, preview)
+}
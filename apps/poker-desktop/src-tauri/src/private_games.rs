@@ -0,0 +1,438 @@
+// Private games scheduled ahead. The host picks a time and a setup (a template, see
+// templates.rs, or a full config) and invites friends by id or hands out the game's
+// invite code; invitations and answers travel through the backend, and answers reach
+// the host as `private_game_rsvp` frames, which the frontend hands over here. The
+// tournament reminder loop (see tournaments.rs) checks the schedule against server
+// time: it reminds the host ahead of the start, and at the start creates the private
+// table and tells everyone who has not declined where it is. Games only live on the
+// host's machine, so one whose start passed while the app was closed by more than
+// the grace period is marked missed rather than opened late.
+
+use crate::claims;
+use crate::clock::ClockState;
+use crate::db::{self, Database, Db};
+use crate::localtime;
+use crate::profile::BackendProfile;
+use crate::templates;
+use crate::ws::WsMessage;
+use crate::TableConfig;
+use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager, State};
+
+const KEY_GAMES: &str = "private_games.scheduled";
+// Letters and digits that cannot be mistaken for one another
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+const CODE_LEN: usize = 8;
+const MAX_MINUTES_BEFORE: u32 = 24 * 60;
+const MAX_DAYS_AHEAD: i64 = 90;
+const MAX_INVITES: usize = 50;
+// How late a game is still opened after its start
+const GRACE_MINS: i64 = 30;
+const MAX_ATTEMPTS: u32 = 3;
+// Finished games are kept this long
+const KEEP_DAYS: i64 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameStatus {
+    Scheduled,
+    Started,
+    Missed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rsvp {
+    Pending,
+    Going,
+    Maybe,
+    Declined,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Invitee {
+    player_id: String,
+    #[serde(default)]
+    username: Option<String>,
+    // Invited from the friends list, rather than arrived with the code
+    invited: bool,
+    rsvp: Rsvp,
+    responded_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledGame {
+    id: String,
+    name: String,
+    starts_at: DateTime<Utc>,
+    config: TableConfig,
+    invite_code: String,
+    remind_minutes_before: u32,
+    reminded: bool,
+    invitees: Vec<Invitee>,
+    status: GameStatus,
+    table_id: Option<String>,
+    attempts: u32,
+    last_error: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl ScheduledGame {
+    fn counts(&self, rsvp: Rsvp) -> usize {
+        self.invitees.iter().filter(|i| i.rsvp == rsvp).count()
+    }
+
+    fn view(self, now: DateTime<Utc>) -> Result<ScheduledGameView, String> {
+        Ok(ScheduledGameView {
+            starts_local: localtime::render(self.starts_at, now)?,
+            going: self.counts(Rsvp::Going),
+            maybe: self.counts(Rsvp::Maybe),
+            declined: self.counts(Rsvp::Declined),
+            pending: self.counts(Rsvp::Pending),
+            game: self,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledGameView {
+    #[serde(flatten)]
+    game: ScheduledGame,
+    starts_local: localtime::LocalTime,
+    going: usize,
+    maybe: usize,
+    declined: usize,
+    pending: usize,
+}
+
+fn load(db: &Database) -> Result<Vec<ScheduledGame>, String> {
+    match db.get_value(KEY_GAMES)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid scheduled games: {}", e)),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn save(db: &Database, games: &[ScheduledGame]) -> Result<(), String> {
+    let data = serde_json::to_string(games).map_err(|e| e.to_string())?;
+    db.set_value(KEY_GAMES, &data)
+}
+
+fn new_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..CODE_LEN).map(|_| CODE_ALPHABET[rng.gen_range(0..CODE_ALPHABET.len())] as char).collect()
+}
+
+fn find<'a>(games: &'a mut [ScheduledGame], game_id: &str) -> Result<&'a mut ScheduledGame, String> {
+    games.iter_mut().find(|g| g.id == game_id).ok_or_else(|| format!("No scheduled game {}", game_id))
+}
+
+async fn post(api_url: &str, path: &str, body: Value) -> Result<Value, String> {
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+    let client = crate::create_http_client()?;
+    let response = crate::http::send(client.post(format!("{}/api/private-games{}", api_url, path))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&body))
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("Private game request failed: {}", error_text));
+    }
+
+    let api_response: crate::ApiResponse<Value> = response.json().await.map_err(|e| e.to_string())?;
+    if api_response.success {
+        Ok(api_response.data.unwrap_or(json!({})))
+    } else {
+        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    }
+}
+
+fn notify(app: &AppHandle, title: &str, body: String) {
+    let shown = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier)
+        .title(title)
+        .body(body)
+        .show();
+    if let Err(e) = shown {
+        eprintln!("Failed to show private game notification: {}", e);
+    }
+}
+
+// Open the table for `game` and send everyone who has not declined to it
+async fn start(app: &AppHandle, game: &ScheduledGame) -> Result<String, String> {
+    let api_url = app.state::<BackendProfile>().api_url.clone();
+    let table = crate::create_table(app.clone(), api_url.clone(), game.config.clone())
+        .await
+        .map_err(|e| e.to_string())?;
+    let players: Vec<&str> = game
+        .invitees
+        .iter()
+        .filter(|i| i.rsvp != Rsvp::Declined)
+        .map(|i| i.player_id.as_str())
+        .collect();
+    if !players.is_empty() {
+        let body = json!({ "tableId": table.id, "name": game.name, "playerIds": players });
+        if let Err(e) = post(&api_url, &format!("/{}/start", game.id), body).await {
+            eprintln!("Failed to tell players private game {} started: {}", game.id, e);
+        }
+    }
+    Ok(table.id)
+}
+
+// Remind the host of games coming up, open the ones due and drop those long finished;
+// called from the tournament reminder loop
+pub async fn check_schedule(app: &AppHandle, now: DateTime<Utc>) -> Result<(), String> {
+    let db = db::get(app)?;
+    let mut games = load(&db)?;
+    let before = games.len();
+    games.retain(|g| g.status == GameStatus::Scheduled || g.starts_at > now - Duration::days(KEEP_DAYS));
+    let mut changed = games.len() != before;
+
+    let mut due = Vec::new();
+    for game in games.iter_mut().filter(|g| g.status == GameStatus::Scheduled) {
+        let remind_at = game.starts_at - Duration::minutes(game.remind_minutes_before as i64);
+        if !game.reminded && remind_at <= now && game.starts_at > now {
+            let minutes = (game.starts_at - now).num_minutes().max(1);
+            notify(app, "Private game", format!("{} starts in {} min, {} going", game.name, minutes, game.counts(Rsvp::Going)));
+            let _ = app.emit_all("private_game_reminder", game.clone());
+            game.reminded = true;
+            changed = true;
+        }
+        if game.starts_at > now {
+            continue;
+        }
+        changed = true;
+        if now - game.starts_at > Duration::minutes(GRACE_MINS) {
+            game.status = GameStatus::Missed;
+        } else {
+            game.attempts += 1;
+            due.push(game.clone());
+        }
+    }
+    if changed {
+        save(&db, &games)?;
+    }
+
+    // Opening a table takes a round trip; the list is read again afterwards so
+    // changes made meanwhile are kept
+    for game in due {
+        let result = start(app, &game).await;
+        let mut games = load(&db)?;
+        let Ok(stored) = find(&mut games, &game.id) else { continue };
+        match result {
+            Ok(table_id) => {
+                stored.status = GameStatus::Started;
+                stored.table_id = Some(table_id);
+                stored.last_error = None;
+                notify(app, "Private game", format!("{} is open", stored.name));
+                let _ = app.emit_all("private_game_started", stored.clone());
+            }
+            Err(e) => {
+                eprintln!("Failed to open private game {}: {}", stored.id, e);
+                if stored.attempts >= MAX_ATTEMPTS {
+                    stored.status = GameStatus::Failed;
+                    notify(app, "Private game", format!("{} could not be opened: {}", stored.name, e));
+                    let _ = app.emit_all("private_game_failed", stored.clone());
+                }
+                stored.last_error = Some(e);
+            }
+        }
+        save(&db, &games)?;
+    }
+    Ok(())
+}
+
+// Schedule a private table for `starts_at`, set up from the template `template` or
+// from `config`. The host is reminded `remind_minutes_before` the start, 15 unless given.
+#[tauri::command]
+pub async fn schedule_private_game(
+    db: Db<'_>,
+    clock: State<'_, ClockState>,
+    name: String,
+    starts_at: DateTime<Utc>,
+    template: Option<String>,
+    config: Option<TableConfig>,
+    remind_minutes_before: Option<u32>,
+) -> Result<ScheduledGameView, String> {
+    let now = clock.server_now();
+    if starts_at <= now || starts_at > now + Duration::days(MAX_DAYS_AHEAD) {
+        return Err(format!("Games can be scheduled up to {} days ahead", MAX_DAYS_AHEAD));
+    }
+    let remind_minutes_before = remind_minutes_before.unwrap_or(15);
+    if remind_minutes_before > MAX_MINUTES_BEFORE {
+        return Err(format!("Reminders can be set at most {} minutes ahead", MAX_MINUTES_BEFORE));
+    }
+    let mut config = match (template, config) {
+        (Some(template), None) => templates::template_config(&db, &template)?,
+        (None, Some(config)) => config,
+        _ => return Err("Give either a template or a table config".to_string()),
+    };
+    let name = name.trim().to_string();
+    config.name = name.clone();
+    config.is_private = true;
+    config.fast_fold = false;
+    crate::validate_table_config(&config)?;
+
+    let game = ScheduledGame {
+        id: format!("pg-{}-{}", now.timestamp_millis(), new_code().to_lowercase()),
+        name,
+        starts_at,
+        config,
+        invite_code: new_code(),
+        remind_minutes_before,
+        reminded: false,
+        invitees: Vec::new(),
+        status: GameStatus::Scheduled,
+        table_id: None,
+        attempts: 0,
+        last_error: None,
+        created_at: now,
+    };
+    let mut games = load(&db)?;
+    games.push(game.clone());
+    save(&db, &games)?;
+    game.view(now)
+}
+
+#[tauri::command]
+pub async fn list_private_games(db: Db<'_>, clock: State<'_, ClockState>) -> Result<Vec<ScheduledGameView>, String> {
+    let now = clock.server_now();
+    let mut games = load(&db)?;
+    games.sort_by_key(|g| g.starts_at);
+    games.into_iter().map(|g| g.view(now)).collect()
+}
+
+// Invite friends from the friends list; the backend sends each the game and its code
+#[tauri::command]
+pub async fn invite_to_private_game(
+    db: Db<'_>,
+    clock: State<'_, ClockState>,
+    api_url: String,
+    game_id: String,
+    friend_ids: Vec<String>,
+) -> Result<ScheduledGameView, String> {
+    let mut games = load(&db)?;
+    let game = find(&mut games, &game_id)?;
+    if game.status != GameStatus::Scheduled {
+        return Err("Only games still to start take invitations".to_string());
+    }
+    let new: Vec<String> = friend_ids
+        .into_iter()
+        .filter(|id| !game.invitees.iter().any(|i| &i.player_id == id))
+        .collect();
+    if game.invitees.len() + new.len() > MAX_INVITES {
+        return Err(format!("At most {} players can be invited", MAX_INVITES));
+    }
+    if !new.is_empty() {
+        let body = json!({
+            "gameId": game.id,
+            "name": game.name,
+            "startsAt": game.starts_at,
+            "inviteCode": game.invite_code,
+            "playerIds": new,
+        });
+        post(&api_url, "/invites", body).await?;
+    }
+    for player_id in new {
+        game.invitees.push(Invitee { player_id, username: None, invited: true, rsvp: Rsvp::Pending, responded_at: None });
+    }
+    let game = game.clone();
+    save(&db, &games)?;
+    game.view(clock.server_now())
+}
+
+// Answer an invitation, by the code it carried, as a guest
+#[tauri::command]
+pub async fn respond_to_private_game(api_url: String, invite_code: String, rsvp: Rsvp) -> Result<(), String> {
+    if rsvp == Rsvp::Pending {
+        return Err("Answer going, maybe or declined".to_string());
+    }
+    let code = invite_code.trim().to_uppercase();
+    if code.len() != CODE_LEN || !code.bytes().all(|b| CODE_ALPHABET.contains(&b)) {
+        return Err("That is not a valid invite code".to_string());
+    }
+    post(&api_url, "/rsvp", json!({ "inviteCode": code, "rsvp": rsvp })).await.map(|_| ())
+}
+
+// Feed a `private_game_rsvp` frame received by the host. Players who came with the
+// code are added as they answer.
+#[tauri::command]
+pub async fn record_private_game_rsvp(
+    app: AppHandle,
+    db: Db<'_>,
+    clock: State<'_, ClockState>,
+    message: WsMessage,
+) -> Result<ScheduledGameView, String> {
+    if message.kind != "private_game_rsvp" {
+        return Err(format!("Not a private game answer: {}", message.kind));
+    }
+    let payload = &message.payload;
+    let code = payload["inviteCode"].as_str().unwrap_or_default();
+    let player_id = payload["playerId"].as_str().ok_or_else(|| "Answer without a player".to_string())?;
+    let rsvp: Rsvp = serde_json::from_value(payload["rsvp"].clone()).map_err(|e| format!("Invalid answer: {}", e))?;
+    if player_id == claims::current()?.user_id {
+        return Err("The host does not answer their own game".to_string());
+    }
+
+    let mut games = load(&db)?;
+    let game = games
+        .iter_mut()
+        .find(|g| g.invite_code == code || payload["gameId"].as_str() == Some(g.id.as_str()))
+        .ok_or_else(|| "The answer is for no scheduled game".to_string())?;
+    let now = clock.server_now();
+    let username = payload["username"].as_str().map(String::from);
+    let full = game.invitees.len() >= MAX_INVITES;
+    match game.invitees.iter_mut().find(|i| i.player_id == player_id) {
+        Some(invitee) => {
+            invitee.rsvp = rsvp;
+            invitee.responded_at = Some(now);
+            invitee.username = username.or(invitee.username.take());
+        }
+        None if full => return Err("The game is full".to_string()),
+        None => game.invitees.push(Invitee {
+            player_id: player_id.to_string(),
+            username,
+            invited: false,
+            rsvp,
+            responded_at: Some(now),
+        }),
+    }
+    let game = game.clone();
+    save(&db, &games)?;
+    let view = game.view(now)?;
+    let _ = app.emit_all("private_game_rsvp", view.clone());
+    Ok(view)
+}
+
+// Call off a game that has not started; invited players are told
+#[tauri::command]
+pub async fn cancel_private_game(
+    db: Db<'_>,
+    clock: State<'_, ClockState>,
+    api_url: String,
+    game_id: String,
+) -> Result<ScheduledGameView, String> {
+    let mut games = load(&db)?;
+    let game = find(&mut games, &game_id)?;
+    if game.status != GameStatus::Scheduled {
+        return Err("Only games still to start can be cancelled".to_string());
+    }
+    if !game.invitees.is_empty() {
+        let players: Vec<&str> = game.invitees.iter().map(|i| i.player_id.as_str()).collect();
+        if let Err(e) = post(&api_url, &format!("/{}/cancel", game.id), json!({ "playerIds": players })).await {
+            eprintln!("Failed to tell players private game {} was cancelled: {}", game.id, e);
+        }
+    }
+    game.status = GameStatus::Cancelled;
+    let game = game.clone();
+    save(&db, &games)?;
+    game.view(clock.server_now())
+}
@@ -0,0 +1,74 @@
+use serde::Serialize;
+
+pub const PRODUCTION_API_URL: &str = "https://primo-poker-server.alabamamike.workers.dev";
+
+// Backend the client talks to. Selected once at startup from `--profile=<name>` or
+// the PRIMO_BACKEND_PROFILE environment variable.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendProfile {
+    pub name: String,
+    pub api_url: String,
+    pub ws_url: String,
+}
+
+impl BackendProfile {
+    pub fn production() -> Self {
+        Self::for_url("production", PRODUCTION_API_URL)
+    }
+
+    pub fn for_url(name: &str, api_url: &str) -> Self {
+        let ws_url = if let Some(rest) = api_url.strip_prefix("https://") {
+            format!("wss://{}/ws", rest)
+        } else {
+            format!("ws://{}/ws", api_url.trim_start_matches("http://"))
+        };
+
+        Self {
+            name: name.to_string(),
+            api_url: api_url.to_string(),
+            ws_url,
+        }
+    }
+}
+
+// Name of the requested profile, defaulting to production
+pub fn requested_profile() -> String {
+    std::env::args()
+        .find_map(|arg| arg.strip_prefix("--profile=").map(|p| p.to_string()))
+        .or_else(|| std::env::var("PRIMO_BACKEND_PROFILE").ok())
+        .unwrap_or_else(|| "production".to_string())
+}
+
+// Resolve the requested profile, starting the embedded mock backend when asked for
+pub fn select(app: &tauri::AppHandle) -> BackendProfile {
+    match requested_profile().as_str() {
+        "production" => BackendProfile::production(),
+        #[cfg(feature = "mock-backend")]
+        "mock" => {
+            let port = std::env::var("PRIMO_MOCK_PORT").ok()
+                .and_then(|p| p.parse().ok())
+                .unwrap_or(crate::mock_backend::DEFAULT_PORT);
+            match tauri::async_runtime::block_on(crate::mock_backend::start(port)) {
+                Ok(url) => BackendProfile::for_url("mock", &url),
+                Err(e) => {
+                    crate::startup::report_error(app, "profile", e);
+                    BackendProfile::production()
+                }
+            }
+        }
+        other => {
+            crate::startup::report_error(
+                app,
+                "profile",
+                format!("Unknown or unavailable backend profile '{}', using production", other),
+            );
+            BackendProfile::production()
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_backend_profile(profile: tauri::State<'_, BackendProfile>) -> Result<BackendProfile, String> {
+    Ok(profile.inner().clone())
+}
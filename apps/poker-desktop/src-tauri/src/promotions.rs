@@ -0,0 +1,267 @@
+// Table promotions: the bad-beat jackpot and the high hand of the hour. The running
+// promotions are cached in the kv table and refreshed from the backend at most once a
+// minute. A jackpot hit or high-hand award arrives over the notification relay with
+// its payout breakdown, which is kept so it can be attached to the hand it came from,
+// whether that hand is already stored or saved later. Each hit raises
+// `promotion_celebration` with the sound cue to play, and an OS notification when the
+// signed-in player is paid, unless the table's alerts say otherwise.
+
+use crate::alerts;
+use crate::claims;
+use crate::db::{Database, Db};
+use crate::history::{self, HandRecord};
+use crate::ws::WsMessage;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+const KEY_CACHE: &str = "promotions.cache";
+const KEY_HITS: &str = "promotions.hits";
+const FRESH_SECS: i64 = 60;
+// Hits kept for attaching to hands saved after they arrive
+const MAX_HITS: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HighHandLeader {
+    player_id: String,
+    username: String,
+    // e.g. "Four of a kind, kings"
+    hand: String,
+    #[serde(default)]
+    hand_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Promotion {
+    id: String,
+    // bad_beat_jackpot | high_hand
+    kind: String,
+    name: String,
+    // Jackpot size, or the high-hand prize
+    amount: u64,
+    // What qualifies, e.g. "Aces full of tens beaten"
+    #[serde(default)]
+    qualifier: String,
+    #[serde(default)]
+    starts_at: Option<DateTime<Utc>>,
+    // End of the current high-hand period
+    #[serde(default)]
+    ends_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    leader: Option<HighHandLeader>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromotionPayout {
+    promotion_id: String,
+    kind: String,
+    player_id: String,
+    username: String,
+    // losing_hand | winning_hand | table_share | high_hand
+    share: String,
+    amount: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PromotionHit {
+    promotion_id: String,
+    kind: String,
+    name: String,
+    hand_id: String,
+    table_id: String,
+    #[serde(default)]
+    table_name: Option<String>,
+    payouts: Vec<PromotionPayout>,
+    #[serde(default = "Utc::now")]
+    hit_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Promotions {
+    promotions: Vec<Promotion>,
+    fetched_at: DateTime<Utc>,
+    #[serde(default)]
+    cached: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Celebration {
+    promotion_id: String,
+    kind: String,
+    hand_id: String,
+    table_id: String,
+    total: u64,
+    // What the signed-in player was paid, 0 when it is someone else's hit
+    my_amount: u64,
+    // None when the table's sounds are muted (see alerts.rs)
+    sound: Option<&'static str>,
+}
+
+fn load<T: serde::de::DeserializeOwned>(db: &Database, key: &str) -> Result<Option<T>, String> {
+    match db.get_value(key)? {
+        Some(data) => serde_json::from_str(&data).map(Some).map_err(|e| format!("Invalid {}: {}", key, e)),
+        None => Ok(None),
+    }
+}
+
+fn save<T: Serialize>(db: &Database, key: &str, value: &T) -> Result<(), String> {
+    let data = serde_json::to_string(value).map_err(|e| e.to_string())?;
+    db.set_value(key, &data)
+}
+
+fn sound_for(kind: &str, mine: bool) -> &'static str {
+    match (kind, mine) {
+        ("bad_beat_jackpot", true) => "jackpot_won",
+        ("bad_beat_jackpot", false) => "jackpot_hit",
+        (_, true) => "high_hand_won",
+        _ => "high_hand_hit",
+    }
+}
+
+async fn fetch(api_url: &str) -> Result<Vec<Promotion>, String> {
+    let client = crate::create_http_client()?;
+    let mut request = client.get(format!("{}/api/promotions/active", api_url));
+    if let Ok(token) = crate::get_token_from_keyring() {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+
+    let response = crate::http::send(request).await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err("Failed to fetch promotions".to_string());
+    }
+
+    let api_response: crate::ApiResponse<Vec<Promotion>> = response.json().await
+        .map_err(|e| format!("Failed to parse promotions: {}", e))?;
+    match api_response.data {
+        Some(promotions) if api_response.success => Ok(promotions),
+        _ => Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string())),
+    }
+}
+
+// Payouts from stored hits for `hand`, before it is saved
+pub fn attach(db: &Database, hand: &mut HandRecord) -> Result<(), String> {
+    let hits: Vec<PromotionHit> = load(db, KEY_HITS)?.unwrap_or_default();
+    for hit in hits.into_iter().filter(|h| h.hand_id == hand.id) {
+        hand.promotions.retain(|p| p.promotion_id != hit.promotion_id);
+        hand.promotions.extend(hit.payouts);
+    }
+    Ok(())
+}
+
+fn store_hit(db: &Database, hit: &PromotionHit) -> Result<(), String> {
+    let mut hits: Vec<PromotionHit> = load(db, KEY_HITS)?.unwrap_or_default();
+    hits.retain(|h| !(h.hand_id == hit.hand_id && h.promotion_id == hit.promotion_id));
+    hits.push(hit.clone());
+    let excess = hits.len().saturating_sub(MAX_HITS);
+    hits.drain(..excess);
+    save(db, KEY_HITS, &hits)?;
+
+    // The hand may already be stored
+    let stored = db.with_conn(|conn| history::get_hand_by_id(conn, &hit.hand_id))?;
+    if let Some(mut hand) = stored {
+        attach(db, &mut hand)?;
+        hand.updated_at = Utc::now();
+        db.with_conn(|conn| history::upsert_hand(conn, &hand))?;
+    }
+    Ok(())
+}
+
+fn celebrate(app: &AppHandle, db: &Database, hit: &PromotionHit) {
+    let alerts = alerts::for_table(db, &hit.table_id).unwrap_or_default();
+    let my_id = claims::current().ok().map(|c| c.user_id);
+    let my_amount: u64 = hit
+        .payouts
+        .iter()
+        .filter(|p| my_id.as_deref() == Some(p.player_id.as_str()))
+        .map(|p| p.amount)
+        .sum();
+    let celebration = Celebration {
+        promotion_id: hit.promotion_id.clone(),
+        kind: hit.kind.clone(),
+        hand_id: hit.hand_id.clone(),
+        table_id: hit.table_id.clone(),
+        total: hit.payouts.iter().map(|p| p.amount).sum(),
+        my_amount,
+        sound: alerts.sounds.then(|| sound_for(&hit.kind, my_amount > 0)),
+    };
+    if my_amount > 0 && alerts.notifications {
+        let shown = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier)
+            .title(hit.name.clone())
+            .body(format!(
+                "You were paid {} at {}",
+                my_amount,
+                hit.table_name.as_deref().unwrap_or(&hit.table_id)
+            ))
+            .show();
+        if let Err(e) = shown {
+            eprintln!("Failed to show promotion notification: {}", e);
+        }
+    }
+    let _ = app.emit_all("promotion_celebration", celebration);
+}
+
+// Update one cached promotion, e.g. a new jackpot size or high-hand leader
+fn update_cached(db: &Database, promotion: Promotion) -> Result<(), String> {
+    let Some(mut cache) = load::<Promotions>(db, KEY_CACHE)? else { return Ok(()) };
+    cache.promotions.retain(|p| p.id != promotion.id);
+    cache.promotions.push(promotion);
+    save(db, KEY_CACHE, &cache)
+}
+
+// A promotion event pushed over the notification relay
+pub fn receive(app: &AppHandle, message: &WsMessage) {
+    let Some(db) = app.try_state::<Database>() else { return };
+    let result = match message.kind.as_str() {
+        "promotion_hit" => match serde_json::from_value::<PromotionHit>(message.payload.clone()) {
+            Ok(hit) => store_hit(&db, &hit).map(|_| celebrate(app, &db, &hit)),
+            Err(e) => Err(format!("Malformed promotion hit: {}", e)),
+        },
+        "promotion_updated" => match serde_json::from_value::<Promotion>(message.payload.clone()) {
+            Ok(promotion) => {
+                let _ = app.emit_all("promotion_updated", promotion.clone());
+                update_cached(&db, promotion)
+            }
+            Err(e) => Err(format!("Malformed promotion update: {}", e)),
+        },
+        _ => Ok(()),
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to handle promotion event: {}", e);
+    }
+}
+
+// Running promotions, from the cache while it is fresh or the backend is unreachable
+#[tauri::command]
+pub async fn get_current_promotions(
+    db: Db<'_>,
+    api_url: String,
+    force_refresh: Option<bool>,
+) -> Result<Promotions, String> {
+    let cached: Option<Promotions> = load(&db, KEY_CACHE)?;
+    if let Some(cache) = &cached {
+        if !force_refresh.unwrap_or(false) && (Utc::now() - cache.fetched_at).num_seconds() < FRESH_SECS {
+            return Ok(Promotions { cached: true, ..cache.clone() });
+        }
+    }
+
+    match fetch(&api_url).await {
+        Ok(promotions) => {
+            let fresh = Promotions { promotions, fetched_at: Utc::now(), cached: false };
+            save(&db, KEY_CACHE, &fresh)?;
+            Ok(fresh)
+        }
+        Err(e) => match cached {
+            Some(cache) => {
+                eprintln!("Promotion fetch failed, using cached copy: {}", e);
+                Ok(Promotions { cached: true, ..cache })
+            }
+            None => Err(e),
+        },
+    }
+}
@@ -0,0 +1,367 @@
+// Range notation and combinatorics for the range visualizer. A range is a
+// comma-separated list of hand classes ("22+, ATs+, KQo, A5s-A2s, KJ") where each
+// token may carry a weight ("KJo:0.5"). Classes expand to concrete two-card combos,
+// minus any that use a known (dead) card.
+
+use crate::cards::{full_deck, parse_rank, rank_char, Card, SUITS};
+use crate::evaluator::evaluate;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::Serialize;
+use std::collections::HashSet;
+
+const TOTAL_COMBOS: f64 = 1326.0;
+const DEFAULT_TRIALS: u32 = 10_000;
+const MAX_TRIALS: u32 = 200_000;
+
+// Redraws allowed per range in one trial before the trial is discarded
+const MAX_DRAWS: usize = 50;
+
+// One of the 169 starting hand classes; `high == low` for pocket pairs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HandClass {
+    pub high: u8,
+    pub low: u8,
+    pub suited: bool,
+}
+
+impl HandClass {
+    pub fn of(hole: [Card; 2]) -> Self {
+        let (high, low) = if hole[0].rank >= hole[1].rank { (hole[0], hole[1]) } else { (hole[1], hole[0]) };
+        Self { high: high.rank, low: low.rank, suited: high.rank != low.rank && high.suit == low.suit }
+    }
+
+    pub fn notation(&self) -> String {
+        let (high, low) = (rank_char(self.high), rank_char(self.low));
+        match (self.high == self.low, self.suited) {
+            (true, _) => format!("{}{}", high, low),
+            (false, true) => format!("{}{}s", high, low),
+            (false, false) => format!("{}{}o", high, low),
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        let classes = parse_token(text).ok()?;
+        match classes.as_slice() {
+            [class] => Some(*class),
+            _ => None,
+        }
+    }
+
+    // All 169 classes in grid order: pairs on the diagonal, suited above it
+    pub fn all() -> Vec<HandClass> {
+        let mut classes = Vec::with_capacity(169);
+        for row in (2..=14u8).rev() {
+            for col in (2..=14u8).rev() {
+                classes.push(class(row.max(col), row.min(col), row > col));
+            }
+        }
+        classes
+    }
+
+    // Every concrete combo: 6 for a pair, 4 suited, 12 offsuit
+    pub fn combos(&self) -> Vec<[Card; 2]> {
+        let mut combos = Vec::new();
+        for (i, &first) in SUITS.iter().enumerate() {
+            for (j, &second) in SUITS.iter().enumerate() {
+                let keep = if self.high == self.low {
+                    i < j
+                } else if self.suited {
+                    i == j
+                } else {
+                    i != j
+                };
+                if keep {
+                    combos.push([Card::new(self.high, first), Card::new(self.low, second)]);
+                }
+            }
+        }
+        combos
+    }
+}
+
+fn class(high: u8, low: u8, suited: bool) -> HandClass {
+    HandClass { high, low, suited: suited && high != low }
+}
+
+// Expand one range token ("77+", "JJ-77", "ATs+", "A5s-A2s", "KQo", "KQ")
+fn parse_token(token: &str) -> Result<Vec<HandClass>, String> {
+    let invalid = || format!("Invalid range token '{}'", token);
+    let (body, plus) = match token.strip_suffix('+') {
+        Some(body) => (body, true),
+        None => (token, false),
+    };
+    if let Some((from, to)) = body.split_once('-') {
+        let (from, to) = (parse_token(from)?, parse_token(to)?);
+        let (&[a], &[b]) = (from.as_slice(), to.as_slice()) else { return Err(invalid()) };
+        if a.high == a.low && b.high == b.low {
+            let (lo, hi) = (a.high.min(b.high), a.high.max(b.high));
+            return Ok((lo..=hi).map(|r| class(r, r, false)).collect());
+        }
+        if a.high != b.high || a.suited != b.suited {
+            return Err(invalid());
+        }
+        let (lo, hi) = (a.low.min(b.low), a.low.max(b.low));
+        return Ok((lo..=hi).map(|low| class(a.high, low, a.suited)).collect());
+    }
+
+    let chars: Vec<char> = body.chars().collect();
+    if chars.len() < 2 || chars.len() > 3 {
+        return Err(invalid());
+    }
+    let first = parse_rank(&chars[0].to_string()).ok_or_else(invalid)?;
+    let second = parse_rank(&chars[1].to_string()).ok_or_else(invalid)?;
+    let (high, low) = (first.max(second), first.min(second));
+    let suits: &[bool] = match chars.get(2).map(|c| c.to_ascii_lowercase()) {
+        Some('s') => &[true],
+        Some('o') => &[false],
+        None => &[true, false],
+        _ => return Err(invalid()),
+    };
+    if high == low && chars.len() == 3 {
+        return Err(invalid());
+    }
+
+    let mut classes = Vec::new();
+    for &suited in suits {
+        if high == low {
+            let top = if plus { 14 } else { high };
+            classes.extend((high..=top).map(|r| class(r, r, false)));
+            break;
+        }
+        let top = if plus { high - 1 } else { low };
+        classes.extend((low..=top).map(|k| class(high, k, suited)));
+    }
+    Ok(classes)
+}
+
+// Parse a range into (class, weight) pairs in the order written. A class listed
+// twice appears twice; callers decide whether weights add up or the last one wins.
+pub fn parse_range(range: &str) -> Result<Vec<(HandClass, f64)>, String> {
+    let mut parsed = Vec::new();
+    for token in range.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        let (token, weight) = match token.split_once(':') {
+            Some((token, weight)) => (
+                token.trim(),
+                weight.trim().parse::<f64>().map_err(|_| format!("Invalid weight in '{}'", token))?.clamp(0.0, 1.0),
+            ),
+            None => (token, 1.0),
+        };
+        parsed.extend(parse_token(token)?.into_iter().map(|c| (c, weight)));
+    }
+    Ok(parsed)
+}
+
+// Each class once, keeping the weight written last
+pub fn dedup(classes: Vec<(HandClass, f64)>) -> Vec<(HandClass, f64)> {
+    let mut seen = HashSet::new();
+    let mut unique: Vec<(HandClass, f64)> = classes.into_iter().rev().filter(|(c, _)| seen.insert(*c)).collect();
+    unique.reverse();
+    unique
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Combo {
+    pub cards: [Card; 2],
+    pub weight: f64,
+}
+
+// Concrete combos of the range that do not use any of `dead`
+pub fn expand(classes: &[(HandClass, f64)], dead: &[Card]) -> Vec<Combo> {
+    classes
+        .iter()
+        .filter(|(_, weight)| *weight > 0.0)
+        .flat_map(|(class, weight)| class.combos().into_iter().map(move |cards| Combo { cards, weight: *weight }))
+        .filter(|combo| !combo.cards.iter().any(|c| dead.contains(c)))
+        .collect()
+}
+
+// Share of the pot each range wins on average. Every trial draws one combo per range
+// by weight, redrawing on card conflicts, then runs the board out at random from
+// the cards that are neither on the board nor `dead`.
+pub fn equity<R: Rng>(
+    ranges: &[Vec<Combo>],
+    board: &[Card],
+    dead: &[Card],
+    trials: u32,
+    rng: &mut R,
+) -> Result<Vec<f64>, String> {
+    if ranges.len() < 2 {
+        return Err("At least two ranges are needed".to_string());
+    }
+    if board.len() > 5 {
+        return Err("The board has at most five cards".to_string());
+    }
+    let samplers = ranges
+        .iter()
+        .map(|range| WeightedIndex::new(range.iter().map(|c| c.weight)))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| "Every range needs at least one live combo".to_string())?;
+
+    let deck: Vec<Card> = full_deck().into_iter().filter(|c| !board.contains(c) && !dead.contains(c)).collect();
+    let mut order: Vec<usize> = (0..ranges.len()).collect();
+    let mut shares = vec![0.0; ranges.len()];
+    let mut completed = 0u32;
+    let mut cards = Vec::with_capacity(7);
+
+    'trial: for _ in 0..trials {
+        // Draw in a random order so no range is favoured when resolving conflicts
+        order.shuffle(rng);
+        let mut used: Vec<Card> = board.to_vec();
+        let mut hands = vec![[Card::new(2, SUITS[0]); 2]; ranges.len()];
+        for &i in &order {
+            let drawn = (0..MAX_DRAWS)
+                .map(|_| ranges[i][samplers[i].sample(rng)].cards)
+                .find(|combo| !combo.iter().any(|c| used.contains(c)));
+            let Some(combo) = drawn else { continue 'trial };
+            used.extend_from_slice(&combo);
+            hands[i] = combo;
+        }
+
+        let mut stub: Vec<Card> = deck.iter().filter(|c| !used.contains(c)).copied().collect();
+        let (rest, _) = stub.partial_shuffle(rng, 5 - board.len());
+        let runout: Vec<Card> = board.iter().chain(rest.iter()).copied().collect();
+
+        let values: Vec<_> = hands
+            .iter()
+            .map(|hole| {
+                cards.clear();
+                cards.extend_from_slice(hole);
+                cards.extend_from_slice(&runout);
+                evaluate(&cards)
+            })
+            .collect();
+        let Some(best) = values.iter().max() else { continue };
+        let winners = values.iter().filter(|v| *v == best).count() as f64;
+        for (share, value) in shares.iter_mut().zip(&values) {
+            if value == best {
+                *share += 1.0 / winners;
+            }
+        }
+        completed += 1;
+    }
+
+    if completed == 0 {
+        return Err("The ranges conflict with each other or the board on every combo".to_string());
+    }
+    shares.iter_mut().for_each(|share| *share /= completed as f64);
+    Ok(shares)
+}
+
+pub fn check_known(board: &[Card], dead: &[Card]) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    if let Some(card) = board.iter().chain(dead).find(|c| !seen.insert(**c)) {
+        return Err(format!("Card {} is listed more than once", card));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RangeHand {
+    hand: String,
+    weight: f64,
+    // Combos left after blockers, out of `total_combos`
+    combos: usize,
+    total_combos: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RangeSummary {
+    hands: Vec<RangeHand>,
+    // Weighted combo count after blockers
+    combos: f64,
+    // Weighted share of all 1326 starting hands, ignoring blockers
+    percent: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RangeEquity {
+    equity: Vec<f64>,
+    // Live combos per range after the board and dead cards
+    combos: Vec<usize>,
+    trials: u32,
+}
+
+// Grid view of a range: every class in it with its weight and live combo count
+#[tauri::command]
+pub async fn analyze_range(range: String, dead_cards: Option<Vec<Card>>) -> Result<RangeSummary, String> {
+    let dead = dead_cards.unwrap_or_default();
+    check_known(&[], &dead)?;
+    let classes = dedup(parse_range(&range)?);
+
+    let mut combos = 0.0;
+    let mut percent = 0.0;
+    let hands = classes
+        .iter()
+        .map(|(class, weight)| {
+            let all = class.combos();
+            let live = all.iter().filter(|combo| !combo.iter().any(|c| dead.contains(c))).count();
+            combos += live as f64 * weight;
+            percent += all.len() as f64 * weight / TOTAL_COMBOS * 100.0;
+            RangeHand { hand: class.notation(), weight: *weight, combos: live, total_combos: all.len() }
+        })
+        .collect();
+
+    Ok(RangeSummary { hands, combos, percent })
+}
+
+#[tauri::command]
+pub async fn expand_range(range: String, dead_cards: Option<Vec<Card>>) -> Result<Vec<Combo>, String> {
+    let dead = dead_cards.unwrap_or_default();
+    check_known(&[], &dead)?;
+    Ok(expand(&dedup(parse_range(&range)?), &dead))
+}
+
+#[tauri::command]
+pub async fn range_vs_range_equity(
+    ranges: Vec<String>,
+    board: Option<Vec<Card>>,
+    dead_cards: Option<Vec<Card>>,
+    trials: Option<u32>,
+) -> Result<RangeEquity, String> {
+    let board = board.unwrap_or_default();
+    let dead = dead_cards.unwrap_or_default();
+    check_known(&board, &dead)?;
+    let known: Vec<Card> = board.iter().chain(&dead).copied().collect();
+
+    let expanded = ranges
+        .iter()
+        .map(|range| Ok(expand(&dedup(parse_range(range)?), &known)))
+        .collect::<Result<Vec<_>, String>>()?;
+    let trials = trials.unwrap_or(DEFAULT_TRIALS).clamp(1, MAX_TRIALS);
+    let equity = equity(&expanded, &board, &dead, trials, &mut rand::thread_rng())?;
+
+    Ok(RangeEquity { equity, combos: expanded.iter().map(Vec::len).collect(), trials })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn cards(text: &str) -> Vec<Card> {
+        text.split_whitespace().map(|c| Card::parse(c).unwrap()).collect()
+    }
+
+    fn combo(text: &str) -> Vec<Combo> {
+        let hole = cards(text);
+        vec![Combo { cards: [hole[0], hole[1]], weight: 1.0 }]
+    }
+
+    #[test]
+    fn dead_cards_never_come_out_on_the_runout() {
+        // Aces only beat the set of kings by hitting one of the two aces left
+        let ranges = [combo("Ah Ad"), combo("Kc Ks")];
+        let board = cards("Kh Qd 7c 2s");
+        let live = equity(&ranges, &board, &[], 2_000, &mut StdRng::seed_from_u64(7)).unwrap();
+        assert!(live[0] > 0.0);
+        let dead = equity(&ranges, &board, &cards("Ac As"), 2_000, &mut StdRng::seed_from_u64(7)).unwrap();
+        assert_eq!(dead[0], 0.0);
+    }
+}
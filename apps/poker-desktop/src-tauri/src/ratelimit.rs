@@ -0,0 +1,149 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+// Rejections within the window that mark the frontend as misbehaving
+const WARNING_THRESHOLD: u32 = 10;
+const WARNING_WINDOW_SECS: u64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EndpointClass {
+    Health,
+    Auth,
+    Tables,
+    Sync,
+    Kyc,
+    Other,
+}
+
+impl EndpointClass {
+    pub fn from_path(path: &str) -> Self {
+        let prefixes = [
+            ("/api/health", EndpointClass::Health),
+            ("/api/auth", EndpointClass::Auth),
+            ("/api/tables", EndpointClass::Tables),
+            ("/api/sync", EndpointClass::Sync),
+            ("/api/kyc", EndpointClass::Kyc),
+        ];
+        prefixes
+            .iter()
+            .find(|(prefix, _)| path.starts_with(prefix))
+            .map(|(_, class)| *class)
+            .unwrap_or(EndpointClass::Other)
+    }
+
+    // (burst capacity, tokens refilled per second)
+    fn limits(self) -> (f64, f64) {
+        match self {
+            EndpointClass::Health => (10.0, 1.0),
+            EndpointClass::Auth => (5.0, 0.2),
+            EndpointClass::Tables => (20.0, 5.0),
+            EndpointClass::Sync => (30.0, 10.0),
+            EndpointClass::Kyc => (5.0, 0.5),
+            EndpointClass::Other => (30.0, 10.0),
+        }
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+    rejections: u32,
+    window_start: Instant,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RateLimitWarning {
+    endpoint_class: EndpointClass,
+    rejections: u32,
+    window_secs: u64,
+}
+
+#[derive(Default)]
+struct RateLimiter {
+    buckets: Mutex<HashMap<EndpointClass, Bucket>>,
+}
+
+static LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+// Give the limiter a handle for warning events; requests are limited either way
+pub fn init(app: AppHandle) {
+    let _ = APP.set(app);
+}
+
+// Turn limiting off for the rest of the process; used by headless load tests, where
+// one process speaks for many users
+pub fn disable() {
+    DISABLED.store(true, Ordering::SeqCst);
+}
+
+// Take a token for the endpoint class, or return how long until one is available
+pub fn acquire(class: EndpointClass) -> Result<(), u64> {
+    take(class, true)
+}
+
+// Take a token for the endpoint class, waiting for one if need be. Waiting is not a
+// rejection, so it never counts towards the misbehaving-frontend warning.
+pub async fn wait(class: EndpointClass) {
+    while let Err(retry_after_ms) = take(class, false) {
+        tokio::time::sleep(Duration::from_millis(retry_after_ms.max(1))).await;
+    }
+}
+
+fn take(class: EndpointClass, count_rejection: bool) -> Result<(), u64> {
+    if DISABLED.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+    let limiter = LIMITER.get_or_init(RateLimiter::default);
+    let (capacity, refill_rate) = class.limits();
+    let now = Instant::now();
+
+    let mut buckets = match limiter.buckets.lock() {
+        Ok(buckets) => buckets,
+        Err(_) => return Ok(()),
+    };
+    let bucket = buckets.entry(class).or_insert_with(|| Bucket {
+        tokens: capacity,
+        refilled_at: now,
+        rejections: 0,
+        window_start: now,
+    });
+
+    let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+    bucket.tokens = (bucket.tokens + elapsed * refill_rate).min(capacity);
+    bucket.refilled_at = now;
+
+    if bucket.tokens >= 1.0 {
+        bucket.tokens -= 1.0;
+        return Ok(());
+    }
+    let retry_after_ms = ((1.0 - bucket.tokens) / refill_rate * 1000.0).ceil() as u64;
+    if !count_rejection {
+        return Err(retry_after_ms);
+    }
+
+    if now.duration_since(bucket.window_start).as_secs() >= WARNING_WINDOW_SECS {
+        bucket.window_start = now;
+        bucket.rejections = 0;
+    }
+    bucket.rejections += 1;
+
+    if bucket.rejections == WARNING_THRESHOLD {
+        eprintln!("Rate limiter: {:?} requests are being rejected repeatedly", class);
+        if let Some(app) = APP.get() {
+            let _ = app.emit_all("rate_limit_warning", RateLimitWarning {
+                endpoint_class: class,
+                rejections: bucket.rejections,
+                window_secs: WARNING_WINDOW_SECS,
+            });
+        }
+    }
+    Err(retry_after_ms)
+}
@@ -0,0 +1,280 @@
+// Automatic rebuys. A table can carry a policy such as "top up to 100bb when I drop
+// below 60bb"; it is checked after each hand the hero finishes there. A top-up only
+// goes through when the wallet covers it and it fits both the policy's own daily cap
+// and what is left of the responsible-gaming buy-in limit for the day. The limits
+// fail closed: when they cannot be fetched no automatic purchase is made. Every
+// decision, bought or skipped, is kept in the rebuy log and emitted as `auto_rebuy`;
+// purchases also go to the audit log.
+
+use crate::audit;
+use crate::db::{self, Database, Db};
+use crate::history::HandRecord;
+use crate::ledger;
+use crate::maintenance;
+use crate::profile::BackendProfile;
+use chrono::{DateTime, Local, Utc};
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+const KEY_POLICIES: &str = "rebuy.policies";
+const KEY_LOG: &str = "rebuy.log";
+const MAX_LOG: usize = 500;
+const MAX_TOP_UP_BB: f64 = 1000.0;
+
+// Tables with a top-up on its way, so a quick next hand does not buy twice
+static IN_FLIGHT: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RebuyPolicy {
+    enabled: bool,
+    // Rebuy once the stack is below this many big blinds
+    below_bb: f64,
+    // ...back up to this many
+    top_up_to_bb: f64,
+    // Chips this policy may spend per local day, on top of the account-wide limit
+    #[serde(default)]
+    daily_cap: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RebuyEvent {
+    table_id: String,
+    hand_id: String,
+    at: DateTime<Utc>,
+    stack: u32,
+    amount: u32,
+    // "bought", "skipped" or "failed"
+    outcome: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+}
+
+// Responsible-gaming buy-in limit as the backend reports it; None means no limit set
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GamingLimits {
+    #[serde(default)]
+    daily_buy_in_remaining: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WalletBalance {
+    balance: u64,
+}
+
+fn load_policies(db: &Database) -> Result<HashMap<String, RebuyPolicy>, String> {
+    match db.get_value(KEY_POLICIES)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid rebuy policies: {}", e)),
+        None => Ok(HashMap::new()),
+    }
+}
+
+fn parse_log(data: Option<String>) -> Result<Vec<RebuyEvent>, String> {
+    match data {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid rebuy log: {}", e)),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn load_log(db: &Database) -> Result<Vec<RebuyEvent>, String> {
+    parse_log(db.get_value(KEY_LOG)?)
+}
+
+// Read and store the log in one transaction, so top-ups finishing at two tables at
+// once cannot drop each other's entry and leave a daily cap undercounted
+fn append_log(db: &Database, event: &RebuyEvent) -> Result<(), String> {
+    db.with_conn(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        let stored = tx.query_row("SELECT value FROM kv WHERE key = ?1", [KEY_LOG], |row| row.get(0)).optional()?;
+        let updated = parse_log(stored).and_then(|mut log| {
+            log.insert(0, event.clone());
+            log.truncate(MAX_LOG);
+            serde_json::to_string(&log).map_err(|e| e.to_string())
+        });
+        let Ok(data) = updated else { return Ok(updated.map(|_| ())) };
+        tx.execute(
+            "INSERT INTO kv (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            [KEY_LOG, &data],
+        )?;
+        tx.commit()?;
+        Ok(Ok(()))
+    })?
+}
+
+// Chips bought automatically at `table_id` since local midnight
+fn spent_today(log: &[RebuyEvent], table_id: &str) -> u64 {
+    let today = Local::now().date_naive();
+    log.iter()
+        .filter(|e| e.table_id == table_id && e.outcome == "bought")
+        .filter(|e| e.at.with_timezone(&Local).date_naive() == today)
+        .map(|e| e.amount as u64)
+        .sum()
+}
+
+async fn get_json<T: serde::de::DeserializeOwned>(url: String, token: &str, what: &str) -> Result<T, String> {
+    let client = crate::create_http_client()?;
+    let response = crate::http::send(client.get(url).header("Authorization", format!("Bearer {}", token)))
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to fetch {}", what));
+    }
+    let api_response: crate::ApiResponse<T> = response.json().await
+        .map_err(|e| format!("Failed to parse {}: {}", what, e))?;
+    match api_response.data {
+        Some(data) if api_response.success => Ok(data),
+        _ => Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string())),
+    }
+}
+
+async fn post_rebuy(api_url: &str, token: &str, table_id: &str, amount: u32) -> Result<(), String> {
+    let client = crate::create_http_client()?;
+    let response = crate::http::send(client.post(format!("{}/api/tables/{}/rebuy", api_url, table_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "amount": amount })))
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("Rebuy failed: {}", error_text));
+    }
+    Ok(())
+}
+
+// Whether a top-up of `amount` may go through; Err carries the reason it may not
+async fn check_funds(api_url: &str, token: &str, policy: &RebuyPolicy, spent: u64, amount: u32) -> Result<(), String> {
+    if policy.daily_cap.is_some_and(|cap| spent + amount as u64 > cap) {
+        return Err("Daily auto-rebuy cap reached".to_string());
+    }
+    let limits: GamingLimits = get_json(format!("{}/api/responsible-gaming/limits", api_url), token, "buy-in limits")
+        .await
+        .map_err(|e| format!("Buy-in limits unavailable: {}", e))?;
+    if limits.daily_buy_in_remaining.is_some_and(|left| (amount as u64) > left) {
+        return Err("Daily buy-in limit reached".to_string());
+    }
+    let wallet: WalletBalance = get_json(format!("{}/api/wallet/balance", api_url), token, "wallet balance").await?;
+    if wallet.balance < amount as u64 {
+        return Err("Wallet balance too low".to_string());
+    }
+    Ok(())
+}
+
+async fn evaluate(app: AppHandle, hand: HandRecord) -> Result<(), String> {
+    let db = db::get(&app)?;
+    let Some(policy) = load_policies(&db)?.remove(&hand.table_id).filter(|p| p.enabled) else { return Ok(()) };
+    let Some(hero) = hand.hero_id.as_deref().and_then(|id| hand.players.iter().find(|p| p.player_id == id)) else {
+        return Ok(());
+    };
+    let stack = (hero.starting_stack as i64 + hero.net).max(0) as u32;
+    let big_blind = hand.big_blind.max(1) as f64;
+    // Busted players rebuy through the table's own flow
+    if stack == 0 || stack as f64 >= policy.below_bb * big_blind {
+        return Ok(());
+    }
+    let amount = ((policy.top_up_to_bb * big_blind) as u32).saturating_sub(stack);
+
+    let api_url = app.state::<BackendProfile>().api_url.clone();
+    let token = crate::get_token_from_keyring().map_err(|_| "Not authenticated".to_string())?;
+    let spent = spent_today(&load_log(&db)?, &hand.table_id);
+    let (outcome, reason) = match check_funds(&api_url, &token, &policy, spent, amount).await {
+        Err(reason) => ("skipped", Some(reason)),
+        Ok(()) => {
+            let result = post_rebuy(&api_url, &token, &hand.table_id, amount).await;
+            audit::record(&app, "auto_rebuy", json!({ "tableId": hand.table_id, "amount": amount }), &result);
+            match result {
+                Ok(()) => {
+                    ledger::record_rebuy(&app, &hand.table_id, amount);
+                    ("bought", None)
+                }
+                Err(e) => ("failed", Some(e)),
+            }
+        }
+    };
+
+    let event = RebuyEvent {
+        table_id: hand.table_id.clone(),
+        hand_id: hand.id.clone(),
+        at: Utc::now(),
+        stack,
+        amount,
+        outcome: outcome.to_string(),
+        reason,
+    };
+    append_log(&db, &event)?;
+    let _ = app.emit_all("auto_rebuy", event);
+    Ok(())
+}
+
+// Check the table's policy after a hand the hero finished; never while play is
+// winding down for maintenance
+pub fn after_hand(app: &AppHandle, hand: &HandRecord) {
+    if maintenance::is_draining(app) {
+        return;
+    }
+    let Ok(mut in_flight) = IN_FLIGHT.lock() else { return };
+    if !in_flight.get_or_insert_with(HashSet::new).insert(hand.table_id.clone()) {
+        return;
+    }
+    let (app, hand) = (app.clone(), hand.clone());
+    tauri::async_runtime::spawn(async move {
+        let table_id = hand.table_id.clone();
+        if let Err(e) = evaluate(app, hand).await {
+            eprintln!("Auto-rebuy check for table {} failed: {}", table_id, e);
+        }
+        if let Ok(mut in_flight) = IN_FLIGHT.lock() {
+            if let Some(tables) = in_flight.as_mut() {
+                tables.remove(&table_id);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_auto_rebuy(db: Db<'_>, table_id: String) -> Result<Option<RebuyPolicy>, String> {
+    Ok(load_policies(&db)?.remove(&table_id))
+}
+
+// Set or, with no policy, clear the auto-rebuy policy of a table
+#[tauri::command]
+pub async fn set_auto_rebuy(
+    db: Db<'_>,
+    table_id: String,
+    policy: Option<RebuyPolicy>,
+) -> Result<Option<RebuyPolicy>, String> {
+    let mut policies = load_policies(&db)?;
+    match &policy {
+        Some(p) if p.below_bb <= 0.0 || p.top_up_to_bb <= p.below_bb || p.top_up_to_bb > MAX_TOP_UP_BB => {
+            return Err(format!("Top up to more than the threshold and at most {}bb", MAX_TOP_UP_BB));
+        }
+        Some(p) => {
+            policies.insert(table_id, p.clone());
+        }
+        None => {
+            policies.remove(&table_id);
+        }
+    }
+    let data = serde_json::to_string(&policies).map_err(|e| e.to_string())?;
+    db.set_value(KEY_POLICIES, &data)?;
+    Ok(policy)
+}
+
+// Automatic rebuy decisions, most recent first
+#[tauri::command]
+pub async fn get_auto_rebuy_log(
+    db: Db<'_>,
+    table_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<RebuyEvent>, String> {
+    let mut log = load_log(&db)?;
+    if let Some(table_id) = table_id {
+        log.retain(|e| e.table_id == table_id);
+    }
+    log.truncate(limit.unwrap_or(50));
+    Ok(log)
+}
@@ -0,0 +1,174 @@
+// Rolling action log per table for the in-table history panel. The last hands of
+// each open table are kept in memory, filled from the database once when a table is
+// first asked for and then from every hand saved there, so the panel filters a few
+// dozen hands instead of querying the whole history.
+
+use crate::db::Db;
+use crate::history::{self, HandRecord};
+use crate::leaks::{is_blind, is_preflop};
+use crate::memory::{self, approx_size, CacheUsage, MemoryCache};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::State;
+
+const HANDS_PER_TABLE: usize = 50;
+const MAX_TABLES: usize = 24;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentAction {
+    street: String,
+    player_id: String,
+    username: String,
+    action: String,
+    amount: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentHand {
+    hand_id: String,
+    played_at: DateTime<Utc>,
+    pot: u32,
+    pot_bb: f64,
+    board: Vec<String>,
+    // The hero put chips in voluntarily
+    hero_played: bool,
+    hero_net: Option<i64>,
+    actions: Vec<RecentAction>,
+}
+
+impl RecentHand {
+    fn from_hand(hand: &HandRecord) -> Self {
+        let username = |id: &str| {
+            hand.players.iter().find(|p| p.player_id == id).map(|p| p.username.clone()).unwrap_or_default()
+        };
+        let hero = hand.hero_id.as_deref();
+        let hero_played = hero.is_some_and(|hero| {
+            hand.actions.iter().any(|a| {
+                a.player_id == hero && !is_blind(&a.action) && !matches!(a.action.as_str(), "fold" | "check")
+            })
+        });
+        RecentHand {
+            hand_id: hand.id.clone(),
+            played_at: hand.played_at,
+            pot: hand.pot,
+            pot_bb: hand.pot as f64 / hand.big_blind.max(1) as f64,
+            board: hand.board.clone(),
+            hero_played,
+            hero_net: hero.and_then(|id| hand.players.iter().find(|p| p.player_id == id)).map(|p| p.net),
+            actions: hand
+                .actions
+                .iter()
+                .map(|a| RecentAction {
+                    street: a.street.clone(),
+                    player_id: a.player_id.clone(),
+                    username: username(&a.player_id),
+                    action: a.action.clone(),
+                    amount: a.amount,
+                })
+                .collect(),
+        }
+    }
+
+    fn saw_flop(&self) -> bool {
+        self.actions.iter().any(|a| !is_preflop(&a.street))
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RecentActionFilter {
+    // Only hands the hero put chips into voluntarily
+    played_only: bool,
+    min_pot_bb: Option<f64>,
+    // Only hands this player acted in
+    player_id: Option<String>,
+    // Only hands that reached the flop
+    saw_flop: bool,
+    limit: Option<usize>,
+}
+
+impl RecentActionFilter {
+    fn matches(&self, hand: &RecentHand) -> bool {
+        (!self.played_only || hand.hero_played)
+            && self.min_pot_bb.is_none_or(|min| hand.pot_bb >= min)
+            && self.player_id.as_ref().is_none_or(|id| hand.actions.iter().any(|a| &a.player_id == id))
+            && (!self.saw_flop || hand.saw_flop())
+    }
+}
+
+struct TableLog {
+    used: Instant,
+    hands: VecDeque<RecentHand>,
+}
+
+fn log_size(log: &TableLog) -> usize {
+    log.hands.iter().map(approx_size).sum()
+}
+
+#[derive(Default)]
+pub struct RecentActionsState {
+    tables: Mutex<HashMap<String, TableLog>>,
+}
+
+impl RecentActionsState {
+    // Add or replace a saved hand in its table's log, if that table is being followed
+    pub fn record(&self, hand: &HandRecord) {
+        let Ok(mut tables) = self.tables.lock() else { return };
+        if let Some(log) = tables.get_mut(&hand.table_id) {
+            log.hands.retain(|h| h.hand_id != hand.id);
+            log.hands.push_front(RecentHand::from_hand(hand));
+            log.hands.make_contiguous().sort_by_key(|h| std::cmp::Reverse(h.played_at));
+            log.hands.truncate(HANDS_PER_TABLE);
+        }
+    }
+}
+
+impl MemoryCache for RecentActionsState {
+    fn usage(&self) -> CacheUsage {
+        let Ok(tables) = self.tables.lock() else { return CacheUsage::default() };
+        CacheUsage { entries: tables.values().map(|log| log.hands.len()).sum(), bytes: tables.values().map(log_size).sum() }
+    }
+
+    fn trim_to(&self, max_bytes: usize) {
+        if let Ok(mut tables) = self.tables.lock() {
+            memory::trim_oldest(&mut tables, max_bytes, log_size, |log| log.used);
+        }
+    }
+}
+
+// Recent hands at `table_id`, newest first, narrowed by `filters`
+#[tauri::command]
+pub async fn get_recent_actions(
+    db: Db<'_>,
+    state: State<'_, RecentActionsState>,
+    table_id: String,
+    filters: Option<RecentActionFilter>,
+) -> Result<Vec<RecentHand>, String> {
+    let filters = filters.unwrap_or_default();
+    let mut tables = state.tables.lock().map_err(|_| "Recent actions lock poisoned".to_string())?;
+    if !tables.contains_key(&table_id) {
+        let hands = db.with_conn(|conn| history::recent_hands_at(conn, &table_id, HANDS_PER_TABLE as u32))?;
+        if tables.len() >= MAX_TABLES {
+            if let Some(stalest) = tables.iter().min_by_key(|(_, log)| log.used).map(|(id, _)| id.clone()) {
+                tables.remove(&stalest);
+            }
+        }
+        let log = TableLog { used: Instant::now(), hands: hands.iter().map(RecentHand::from_hand).collect() };
+        tables.insert(table_id.clone(), log);
+    }
+
+    let Some(log) = tables.get_mut(&table_id) else { return Ok(Vec::new()) };
+    log.used = Instant::now();
+    Ok(log
+        .hands
+        .iter()
+        .filter(|hand| filters.matches(hand))
+        .take(filters.limit.unwrap_or(HANDS_PER_TABLE))
+        .cloned()
+        .collect())
+}
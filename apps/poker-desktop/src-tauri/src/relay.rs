@@ -0,0 +1,156 @@
+// Background notification relay. Holds one lightweight socket on the backend's
+// notification channel so a few events still reach the player while the main window
+// is hidden or minimized: tournament seat assignments, friend invites and big wins
+// become OS notifications. Each carries a `primo://` deep link that is handed to the
+// frontend as `open_deep_link` when the window comes back into focus. Server
+// announcements and promotion events arrive on the same channel and go to
+// announcements.rs and promotions.rs, which take them from the event bus.
+
+use crate::profile::BackendProfile;
+use crate::ws::{self, TableSocket, WsMessage};
+use serde::Serialize;
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+const TOPICS: &[&str] = &[
+    "tournament_seat_assigned",
+    "friend_invite",
+    "big_win",
+    "announcement",
+    "promotion_hit",
+    "promotion_updated",
+];
+const SIGNED_OUT_RETRY: Duration = Duration::from_secs(30);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+pub struct RelayState {
+    pending_link: Mutex<Option<String>>,
+    socket: Mutex<Option<TableSocket>>,
+    closed: AtomicBool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeepLink {
+    url: String,
+}
+
+// Notification title, body and deep link for a relayed event
+fn describe(message: &WsMessage) -> Option<(String, String, String)> {
+    let payload = &message.payload;
+    let text = |key: &str| payload[key].as_str().unwrap_or_default().to_string();
+    match message.kind.as_str() {
+        "tournament_seat_assigned" => Some((
+            "Tournament seat assigned".to_string(),
+            format!("{}: you are seated at table {}", text("tournamentName"), text("tableNumber")),
+            format!("primo://tournament/{}/table/{}", text("tournamentId"), text("tableId")),
+        )),
+        "friend_invite" => Some((
+            "Table invite".to_string(),
+            format!("{} invited you to {}", text("fromUsername"), text("tableName")),
+            format!("primo://table/{}", text("tableId")),
+        )),
+        "big_win" => Some((
+            "Big win".to_string(),
+            format!("You won {} at {}", payload["amount"].as_u64().unwrap_or(0), text("tableName")),
+            format!("primo://hand/{}", text("handId")),
+        )),
+        _ => None,
+    }
+}
+
+// The relay only speaks up when the player cannot see the app
+fn window_hidden(app: &AppHandle) -> bool {
+    match app.get_window("main") {
+        Some(window) => !window.is_visible().unwrap_or(true) || window.is_minimized().unwrap_or(false),
+        None => true,
+    }
+}
+
+fn relay(app: &AppHandle, message: &WsMessage) {
+    let Some((title, body, link)) = describe(message) else { return };
+    if !window_hidden(app) {
+        return;
+    }
+    let shown = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier)
+        .title(title)
+        .body(body)
+        .show();
+    if let Err(e) = shown {
+        eprintln!("Failed to show relayed notification: {}", e);
+    }
+    if let Ok(mut pending) = app.state::<RelayState>().pending_link.lock() {
+        *pending = Some(link);
+    }
+}
+
+// Called when the main window regains focus
+pub fn on_focus(app: &AppHandle) {
+    let link = app.state::<RelayState>().pending_link.lock().ok().and_then(|mut pending| pending.take());
+    if let Some(url) = link {
+        let _ = app.emit_all("open_deep_link", DeepLink { url });
+    }
+}
+
+// Keep the notification socket open for the life of the app, reconnecting with
+// backoff and waiting while signed out
+pub fn start_relay(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut backoff = Duration::from_secs(1);
+        loop {
+            if app.state::<RelayState>().closed.load(Ordering::SeqCst) {
+                return;
+            }
+            let Ok(token) = crate::get_token_from_keyring() else {
+                tokio::time::sleep(SIGNED_OUT_RETRY).await;
+                continue;
+            };
+            let ws_url = app.state::<BackendProfile>().ws_url.clone();
+            let url = format!("{}?token={}&channel=notifications", ws_url, token);
+
+            match ws::connect(&url).await {
+                Ok((socket, mut incoming)) => {
+                    backoff = Duration::from_secs(1);
+                    socket.renew_with_session();
+                    if socket.send(WsMessage::new("subscribe", json!({ "topics": TOPICS }))).is_ok() {
+                        if let Ok(mut held) = app.state::<RelayState>().socket.lock() {
+                            *held = Some(socket);
+                        }
+                        // Announcements and promotion events reach their modules over the bus
+                        while let Some(message) = incoming.recv().await {
+                            relay(&app, &message);
+                        }
+                        if let Ok(mut held) = app.state::<RelayState>().socket.lock() {
+                            held.take();
+                        }
+                    }
+                }
+                Err(e) => eprintln!("Notification relay connection failed: {}", e),
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    });
+}
+
+// Unsubscribe and close the socket for good, on the way out of the app
+pub fn close(app: &AppHandle) {
+    let state = app.state::<RelayState>();
+    state.closed.store(true, Ordering::SeqCst);
+    if let Some(socket) = state.socket.lock().ok().and_then(|mut held| held.take()) {
+        let _ = socket.send(WsMessage::new("unsubscribe", json!({ "topics": TOPICS })));
+    }
+}
+
+// Deep link from a notification the player has not followed yet, for a frontend
+// that loads after the focus event fired
+#[tauri::command]
+pub async fn take_pending_deep_link(state: State<'_, RelayState>) -> Result<Option<String>, String> {
+    let mut pending = state.pending_link.lock().map_err(|_| "Relay lock poisoned".to_string())?;
+    Ok(pending.take())
+}
@@ -0,0 +1,133 @@
+// Keeps a long session signed in. A few minutes before the access token expires (its
+// `exp` claim, or the stored expiry when it has none) the refresh token is traded for
+// a new pair at /api/auth/refresh. Open sockets are then re-authenticated in place
+// rather than reconnected, so table subscriptions survive: the ones this process
+// holds get an `auth_renew` frame directly (see ws.rs), and `auth_renewed` hands the
+// same frame to the views to send on their table sockets. `refresh_auth_token`, used
+// by the auth store, goes through the same path, and one refresh runs at a time.
+
+use crate::audit;
+use crate::claims;
+use crate::profile::BackendProfile;
+use crate::ws;
+use crate::{AuthToken, TokenResponse};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use reqwest::StatusCode;
+use serde_json::json;
+use tauri::{AppHandle, Manager};
+
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+// Refresh once the token has less than this left
+const REFRESH_AHEAD_SECS: i64 = 300;
+// Lifetime assumed for tokens that carry no expiry
+const DEFAULT_LIFETIME_HOURS: i64 = 24;
+
+#[derive(Default)]
+pub struct RenewalState {
+    in_progress: tokio::sync::Mutex<()>,
+}
+
+enum RefreshError {
+    // The backend will not renew the session; the player has to sign in again
+    Refused(String),
+    Failed(String),
+}
+
+fn expires_at(access_token: &str) -> Option<DateTime<Utc>> {
+    let exp = claims::decode(access_token).ok()?.exp?;
+    Utc.timestamp_opt(exp, 0).single()
+}
+
+fn due(token: &AuthToken) -> bool {
+    let expiry = expires_at(&token.access_token).unwrap_or(token.expires_at);
+    expiry - Utc::now() <= Duration::seconds(REFRESH_AHEAD_SECS)
+}
+
+async fn request_refresh(api_url: &str, refresh_token: &str) -> Result<TokenResponse, RefreshError> {
+    let client = crate::create_http_client().map_err(RefreshError::Failed)?;
+    let response = crate::http::send(crate::device::with_device_headers(client
+        .post(format!("{}/api/auth/refresh", api_url))
+        .json(&json!({ "refreshToken": refresh_token }))))
+        .await
+        .map_err(|e| RefreshError::Failed(e.to_string()))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        let message = format!("Token refresh failed: {}", error_text);
+        return Err(match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => RefreshError::Refused(message),
+            _ => RefreshError::Failed(message),
+        });
+    }
+
+    let tokens = if crate::version::supports(crate::version::LOGIN_ENVELOPE) {
+        crate::schema::api_data(response, &crate::TOKENS_SCHEMA, "token refresh").await
+            .and_then(|tokens| tokens.ok_or_else(|| "No tokens returned".to_string()))
+    } else {
+        crate::schema::body(response, &crate::TOKENS_SCHEMA, "token refresh").await
+    };
+    tokens.map_err(RefreshError::Failed)
+}
+
+// Renew the stored tokens if they are close to expiry and re-authenticate every open
+// socket with the new one. None when signed out.
+async fn refresh(app: &AppHandle) -> Result<Option<AuthToken>, String> {
+    let state = app.state::<RenewalState>();
+    let _guard = state.in_progress.lock().await;
+    let Ok(current) = crate::stored_auth_token() else { return Ok(None) };
+    // Also covers a refresh finished while this one waited its turn
+    if !due(&current) {
+        return Ok(Some(current));
+    }
+
+    let api_url = app.state::<BackendProfile>().api_url.clone();
+    let tokens = match request_refresh(&api_url, &current.refresh_token).await {
+        Ok(tokens) => tokens,
+        Err(RefreshError::Refused(e)) => {
+            let _ = app.emit_all("auth_renewal_failed", json!({ "reason": e }));
+            return Err(e);
+        }
+        Err(RefreshError::Failed(e)) => return Err(e),
+    };
+    let expiry = expires_at(&tokens.access_token).unwrap_or_else(|| Utc::now() + Duration::hours(DEFAULT_LIFETIME_HOURS));
+    crate::store_auth_token_secure(AuthToken {
+        access_token: tokens.access_token.clone(),
+        refresh_token: tokens.refresh_token,
+        expires_at: expiry,
+    })?;
+
+    let sockets = ws::renew_auth(&tokens.access_token);
+    let _ = app.emit_all("auth_renewed", json!({
+        "expiresAt": expiry,
+        "frame": ws::auth_renew_frame(&tokens.access_token),
+        "socketsRenewed": sockets,
+    }));
+    crate::stored_auth_token().map(Some)
+}
+
+// Refresh ahead of expiry for as long as the app runs
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            let Ok(token) = crate::stored_auth_token() else { continue };
+            if !due(&token) {
+                continue;
+            }
+            let result = refresh(&app).await;
+            audit::record(&app, "refresh_auth_token", json!({ "background": true }), &result);
+            if let Err(e) = result {
+                eprintln!("Token refresh failed: {}", e);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn refresh_auth_token(app: AppHandle) -> Result<Option<AuthToken>, String> {
+    let result = refresh(&app).await;
+    audit::record(&app, "refresh_auth_token", json!({ "background": false }), &result);
+    result
+}
@@ -0,0 +1,264 @@
+// Hand reports for suspected collusion and other integrity problems. The stored hand,
+// the players being reported and the reporter's own notes on them are bundled and
+// sent to the backend security endpoint; each filed report is kept in
+// `hand_reports` so its review status can be followed.
+
+use crate::audit;
+use crate::db::{Database, Db};
+use crate::history::{get_hand_by_id, to_millis, HandRecord};
+use crate::notes::get_note;
+use chrono::{DateTime, TimeZone, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::AppHandle;
+
+const REASONS: &[&str] = &["collusion", "chip_dumping", "soft_play", "bot", "other"];
+const MAX_COMMENT_LEN: usize = 2000;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportedPlayer {
+    player_id: String,
+    username: String,
+    seat: u8,
+    // The reporter's note on this player, if any
+    note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EvidenceBundle {
+    hand_id: String,
+    reason: String,
+    comment: Option<String>,
+    players: Vec<ReportedPlayer>,
+    hand: HandRecord,
+    client_version: &'static str,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RemoteReport {
+    id: String,
+    status: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FiledReport {
+    id: String,
+    hand_id: String,
+    reason: String,
+    player_ids: Vec<String>,
+    // submitted | under_review | actioned | dismissed
+    status: String,
+    submitted_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+fn report_from_row(row: &rusqlite::Row) -> rusqlite::Result<FiledReport> {
+    let players: String = row.get(3)?;
+    let time = |millis: i64| Utc.timestamp_millis_opt(millis).single().unwrap_or_else(Utc::now);
+    Ok(FiledReport {
+        id: row.get(0)?,
+        hand_id: row.get(1)?,
+        reason: row.get(2)?,
+        player_ids: serde_json::from_str(&players).unwrap_or_default(),
+        status: row.get(4)?,
+        submitted_at: time(row.get(5)?),
+        updated_at: time(row.get(6)?),
+    })
+}
+
+fn list_reports(conn: &Connection) -> rusqlite::Result<Vec<FiledReport>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, hand_id, reason, players, status, submitted_at, updated_at
+         FROM hand_reports ORDER BY submitted_at DESC",
+    )?;
+    let rows = stmt.query_map([], report_from_row)?;
+    rows.collect()
+}
+
+fn insert_report(conn: &Connection, report: &FiledReport) -> rusqlite::Result<()> {
+    let players = serde_json::to_string(&report.player_ids)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    conn.execute(
+        "INSERT OR REPLACE INTO hand_reports (id, hand_id, reason, players, status, submitted_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            report.id,
+            report.hand_id,
+            report.reason,
+            players,
+            report.status,
+            to_millis(&report.submitted_at),
+            to_millis(&report.updated_at)
+        ],
+    )?;
+    Ok(())
+}
+
+// Gathers the hand and notes; `player_ids` defaults to every opponent in the hand
+fn build_bundle(
+    db: &Database,
+    hand_id: &str,
+    reason: &str,
+    player_ids: Option<Vec<String>>,
+    comment: Option<String>,
+) -> Result<EvidenceBundle, String> {
+    let hand = db
+        .with_conn(|conn| get_hand_by_id(conn, hand_id))?
+        .ok_or_else(|| format!("Hand {} is not in local history", hand_id))?;
+
+    let hero = hand.hero_id.clone();
+    let ids = player_ids.unwrap_or_else(|| {
+        hand.players
+            .iter()
+            .filter(|p| Some(&p.player_id) != hero.as_ref())
+            .map(|p| p.player_id.clone())
+            .collect()
+    });
+    if ids.is_empty() {
+        return Err("Choose at least one player to report".to_string());
+    }
+
+    let mut players = Vec::new();
+    for id in &ids {
+        let player = hand
+            .players
+            .iter()
+            .find(|p| &p.player_id == id)
+            .ok_or_else(|| format!("Player {} was not in this hand", id))?;
+        let note = db.with_conn(|conn| get_note(conn, id))?;
+        players.push(ReportedPlayer {
+            player_id: id.clone(),
+            username: player.username.clone(),
+            seat: player.seat,
+            note: note.map(|n| n.text),
+        });
+    }
+
+    Ok(EvidenceBundle {
+        hand_id: hand_id.to_string(),
+        reason: reason.to_string(),
+        comment,
+        players,
+        hand,
+        client_version: env!("CARGO_PKG_VERSION"),
+        created_at: Utc::now(),
+    })
+}
+
+async fn submit(api_url: &str, bundle: &EvidenceBundle) -> Result<RemoteReport, String> {
+    let client = crate::create_http_client()?;
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+
+    let response = crate::http::send(client.post(format!("{}/api/security/reports", api_url))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(bundle))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("Failed to submit report: {}", error_text));
+    }
+
+    let api_response: crate::ApiResponse<RemoteReport> = response.json().await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    if api_response.success {
+        api_response.data.ok_or_else(|| "No report returned".to_string())
+    } else {
+        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    }
+}
+
+async fn fetch_statuses(api_url: &str) -> Result<Vec<RemoteReport>, String> {
+    let client = crate::create_http_client()?;
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+
+    let response = crate::http::send(client.get(format!("{}/api/security/reports", api_url))
+        .header("Authorization", format!("Bearer {}", token)))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        return Err("Failed to fetch report status".to_string());
+    }
+
+    let api_response: crate::ApiResponse<Vec<RemoteReport>> = response.json().await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    if api_response.success {
+        Ok(api_response.data.unwrap_or_default())
+    } else {
+        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    }
+}
+
+#[tauri::command]
+pub async fn report_hand(
+    app: AppHandle,
+    db: Db<'_>,
+    api_url: String,
+    hand_id: String,
+    reason: String,
+    player_ids: Option<Vec<String>>,
+    comment: Option<String>,
+) -> Result<FiledReport, String> {
+    if !REASONS.contains(&reason.as_str()) {
+        return Err(format!("Unknown report reason: {}", reason));
+    }
+    let comment = comment.map(|c| c.trim().to_string()).filter(|c| !c.is_empty());
+    if comment.as_ref().is_some_and(|c| c.chars().count() > MAX_COMMENT_LEN) {
+        return Err(format!("Comments are limited to {} characters", MAX_COMMENT_LEN));
+    }
+
+    let bundle = build_bundle(&db, &hand_id, &reason, player_ids, comment)?;
+    let player_ids: Vec<String> = bundle.players.iter().map(|p| p.player_id.clone()).collect();
+
+    let result = submit(&api_url, &bundle).await;
+    audit::record(&app, "report_hand", json!({ "handId": hand_id, "reason": reason, "playerIds": player_ids }), &result);
+    let remote = result?;
+
+    let now = Utc::now();
+    let report = FiledReport {
+        id: remote.id,
+        hand_id,
+        reason,
+        player_ids,
+        status: remote.status,
+        submitted_at: now,
+        updated_at: now,
+    };
+    db.with_conn(|conn| insert_report(conn, &report))?;
+    Ok(report)
+}
+
+// Filed reports, newest first. With `refresh`, statuses are updated from the backend.
+#[tauri::command]
+pub async fn list_hand_reports(
+    db: Db<'_>,
+    api_url: String,
+    refresh: Option<bool>,
+) -> Result<Vec<FiledReport>, String> {
+    if refresh.unwrap_or(false) {
+        let remote = fetch_statuses(&api_url).await?;
+        let now = to_millis(&Utc::now());
+        db.with_conn(|conn| {
+            for report in &remote {
+                conn.execute(
+                    "UPDATE hand_reports SET status = ?2, updated_at = ?3 WHERE id = ?1 AND status != ?2",
+                    params![report.id, report.status, now],
+                )?;
+            }
+            Ok(())
+        })?;
+    }
+    db.with_conn(list_reports)
+}
@@ -0,0 +1,194 @@
+// Aggregate results over local hand history for the graphs screen: net chips, bb/100,
+// showdown vs non-showdown winnings and all-in EV adjusted results, grouped by day,
+// week, stake, position or game type.
+
+use crate::compute::{self, Priority};
+use crate::db;
+use crate::ev;
+use crate::history::{self, HandFilter, HandRecord};
+use crate::preflop;
+use crate::sizing::Limit;
+use chrono::{DateTime, Datelike, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tauri::AppHandle;
+
+const GROUPINGS: &[&str] = &["day", "week", "stake", "position", "game_type", "none"];
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResultsRow {
+    key: String,
+    hands: u32,
+    net: i64,
+    bb_won: f64,
+    bb_per_100: f64,
+    showdown_net: i64,
+    non_showdown_net: i64,
+    all_in_hands: u32,
+    // Net with every all-in hand counted at its expected value instead
+    ev_net: f64,
+    ev_bb_won: f64,
+    ev_bb_per_100: f64,
+}
+
+impl ResultsRow {
+    fn add(&mut self, net: i64, big_blind: u32, showdown: bool, expected: Option<f64>) {
+        self.hands += 1;
+        self.net += net;
+        self.bb_won += net as f64 / big_blind.max(1) as f64;
+        if showdown {
+            self.showdown_net += net;
+        } else {
+            self.non_showdown_net += net;
+        }
+        if expected.is_some() {
+            self.all_in_hands += 1;
+        }
+        self.ev_net += expected.unwrap_or(net as f64);
+        self.ev_bb_won += expected.unwrap_or(net as f64) / big_blind.max(1) as f64;
+    }
+
+    fn finish(&mut self) {
+        let hands = self.hands.max(1) as f64;
+        self.bb_per_100 = self.bb_won / hands * 100.0;
+        self.ev_bb_per_100 = self.ev_bb_won / hands * 100.0;
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResultsReport {
+    group_by: String,
+    rows: Vec<ResultsRow>,
+    total: ResultsRow,
+}
+
+// Chart position of a player, with the button inferred from who posted the blinds
+pub fn position_of(hand: &HandRecord, player_id: &str) -> Option<&'static str> {
+    let mut seats: Vec<_> = hand.players.iter().collect();
+    seats.sort_by_key(|p| p.seat);
+    let n = seats.len();
+    let index = |blind: &str| {
+        let poster = hand.actions.iter().find(|a| a.action == blind)?;
+        seats.iter().position(|p| p.player_id == poster.player_id)
+    };
+
+    let button = match (index("small_blind"), index("big_blind")) {
+        (Some(sb), _) if n == 2 => sb,
+        (Some(sb), _) => (sb + n - 1) % n,
+        (None, Some(bb)) => (bb + n - 2) % n,
+        (None, None) => return None,
+    };
+    let seat = seats.iter().position(|p| p.player_id == player_id)?;
+    Some(preflop::position_name((seat + n - button) % n, n))
+}
+
+// Whether the hand went to showdown with the hero still in it
+fn reached_showdown(hand: &HandRecord, hero: &str) -> bool {
+    let folded: HashSet<&str> = hand.actions.iter().filter(|a| a.action == "fold").map(|a| a.player_id.as_str()).collect();
+    !folded.contains(hero) && hand.players.iter().filter(|p| !folded.contains(p.player_id.as_str())).count() > 1
+}
+
+fn group_key(group_by: &str, hand: &HandRecord, hero: &str) -> String {
+    match group_by {
+        "day" => hand.played_at.format("%Y-%m-%d").to_string(),
+        "week" => {
+            let week = hand.played_at.iso_week();
+            format!("{}-W{:02}", week.year(), week.week())
+        }
+        // Fixed-limit stakes are named by the small and big bet
+        "stake" => match Limit::from_name(&hand.betting_structure) {
+            Some(Limit::Fixed) => format!("{}/{} FL", hand.big_blind, hand.big_blind * 2),
+            _ => format!("{}/{}", hand.small_blind, hand.big_blind),
+        },
+        "position" => position_of(hand, hero).unwrap_or("unknown").to_string(),
+        "game_type" => {
+            let structure = Limit::from_name(&hand.betting_structure).map_or(hand.betting_structure.as_str(), |l| l.as_str());
+            format!("{} {}", hand.game_type, structure)
+        }
+        _ => "all".to_string(),
+    }
+}
+
+// `expected` maps all-in hands to their expected net, as stored by `ev::refresh`
+pub fn build_report(
+    hands: &[HandRecord],
+    expected: &HashMap<String, f64>,
+    group_by: &str,
+) -> Result<ResultsReport, String> {
+    if !GROUPINGS.contains(&group_by) {
+        return Err(format!("Unknown grouping '{}'", group_by));
+    }
+    let mut groups: BTreeMap<String, ResultsRow> = BTreeMap::new();
+    let mut total = ResultsRow { key: "total".to_string(), ..Default::default() };
+
+    for hand in hands {
+        let Some(hero) = hand.hero_id.as_deref() else { continue };
+        let Some(player) = hand.players.iter().find(|p| p.player_id == hero) else { continue };
+        let showdown = reached_showdown(hand, hero);
+        let expected = expected.get(&hand.id).copied();
+
+        let key = group_key(group_by, hand, hero);
+        let row = groups.entry(key.clone()).or_insert_with(|| ResultsRow { key, ..Default::default() });
+        row.add(player.net, hand.big_blind, showdown, expected);
+        total.add(player.net, hand.big_blind, showdown, expected);
+    }
+
+    let mut rows: Vec<ResultsRow> = groups.into_values().collect();
+    rows.iter_mut().for_each(ResultsRow::finish);
+    total.finish();
+    Ok(ResultsReport { group_by: group_by.to_string(), rows, total })
+}
+
+// `group_by` is one of day, week, stake, position, game_type or none
+#[tauri::command]
+pub async fn get_results_report(
+    app: AppHandle,
+    group_by: Option<String>,
+    filters: Option<HandFilter>,
+) -> Result<ResultsReport, String> {
+    compute::run("Results report", Priority::Normal, move |cancel| {
+        let db = db::get(&app)?;
+        ev::refresh(&db, cancel)?;
+        let (hands, expected) = db.with_conn(|conn| {
+            Ok((history::filtered_hands(conn, &filters.unwrap_or_default())?, ev::expected_nets(conn)?))
+        })?;
+        build_report(&hands, &expected, group_by.as_deref().unwrap_or("day"))
+    })
+    .await
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvPoint {
+    hand_id: String,
+    played_at: DateTime<Utc>,
+    // Running totals in chips
+    net: i64,
+    ev_net: f64,
+}
+
+// Cumulative actual and all-in adjusted results, one point per hand, oldest first
+#[tauri::command]
+pub async fn get_ev_line(app: AppHandle, filters: Option<HandFilter>) -> Result<Vec<EvPoint>, String> {
+    compute::run("EV line", Priority::Normal, move |cancel| {
+        let db = db::get(&app)?;
+        ev::refresh(&db, cancel)?;
+        let (hands, expected) = db.with_conn(|conn| {
+            Ok((history::filtered_hands(conn, &filters.unwrap_or_default())?, ev::expected_nets(conn)?))
+        })?;
+
+        let (mut net, mut ev_net) = (0, 0.0);
+        let mut points = Vec::with_capacity(hands.len());
+        for hand in &hands {
+            let Some(hero) = hand.hero_id.as_deref() else { continue };
+            let Some(player) = hand.players.iter().find(|p| p.player_id == hero) else { continue };
+            net += player.net;
+            ev_net += expected.get(&hand.id).copied().unwrap_or(player.net as f64);
+            points.push(EvPoint { hand_id: hand.id.clone(), played_at: hand.played_at, net, ev_net });
+        }
+        Ok(points)
+    })
+    .await
+}
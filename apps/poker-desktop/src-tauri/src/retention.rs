@@ -0,0 +1,435 @@
+// Hand history retention. Full records are kept for a configurable number of days;
+// past that a hand is reduced to a one-row summary (stakes, pot, the hero's result)
+// in `hand_summaries` unless its pot reached the configured size in big blinds or a
+// report still refers to it. When the database is over its storage quota, older
+// hands are summarized ahead of the schedule, never those of the last week. While
+// cloud sync is on, hands it has not uploaded yet are left in full. Pruning is off
+// until the player turns it on; a background job then runs the policy a few times a
+// day, and `prune_hand_history` runs it at once either way. After a large delete the
+// file is compacted. All-in EV rows are kept, so EV totals are unchanged by pruning.
+
+use crate::db::{self, Database, Db};
+use crate::history::{parse_hand, to_millis, HandRecord};
+use crate::sync;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager};
+
+const KEY_SETTINGS: &str = "retention.settings";
+const KEY_LAST_RUN: &str = "retention.last_run";
+const FIRST_RUN_DELAY: std::time::Duration = std::time::Duration::from_secs(120);
+const RUN_INTERVAL: std::time::Duration = std::time::Duration::from_secs(6 * 60 * 60);
+// Hands read and summarized per transaction, so play is not held up
+const BATCH_SIZE: u32 = 500;
+// The quota never reaches hands younger than this
+const MIN_FULL_DAYS: u32 = 7;
+const MAX_FULL_DAYS: u32 = 100 * 365;
+const MIN_QUOTA_MB: u64 = 64;
+const MAX_QUOTA_MB: u64 = 1024 * 1024;
+const MB: u64 = 1024 * 1024;
+// Compact once free pages are this share of the file and at least COMPACT_MIN_MB
+const COMPACT_FREE_SHARE: f64 = 0.25;
+const COMPACT_MIN_MB: u64 = 16;
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+// Clears RUNNING when a run ends, however it ends
+struct RunningGuard;
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        RUNNING.store(false, Ordering::SeqCst);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetentionSettings {
+    enabled: bool,
+    // Hands younger than this are always kept in full
+    keep_full_days: u32,
+    // Pots of at least this many big blinds are kept in full whatever their age
+    keep_pot_bb: u32,
+    // Size of the database the quota pass prunes back to
+    quota_mb: u64,
+}
+
+impl Default for RetentionSettings {
+    fn default() -> Self {
+        Self { enabled: false, keep_full_days: 365, keep_pot_bb: 50, quota_mb: 1024 }
+    }
+}
+
+impl RetentionSettings {
+    fn clamped(self) -> Self {
+        Self {
+            keep_full_days: self.keep_full_days.clamp(MIN_FULL_DAYS, MAX_FULL_DAYS),
+            quota_mb: self.quota_mb.clamp(MIN_QUOTA_MB, MAX_QUOTA_MB),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HandSummary {
+    id: String,
+    table_id: String,
+    #[serde(default)]
+    table_name: Option<String>,
+    played_at: DateTime<Utc>,
+    game_type: String,
+    betting_structure: String,
+    small_blind: u32,
+    big_blind: u32,
+    pot: u32,
+    rake: u32,
+    players: usize,
+    #[serde(default)]
+    hero_id: Option<String>,
+    // The hero's net result, None when they were not in the hand
+    #[serde(default)]
+    hero_net: Option<i64>,
+    board: Vec<String>,
+}
+
+impl HandSummary {
+    fn of(hand: &HandRecord) -> Self {
+        let hero_net = hand
+            .hero_id
+            .as_ref()
+            .and_then(|hero| hand.players.iter().find(|p| &p.player_id == hero))
+            .map(|p| p.net);
+        Self {
+            id: hand.id.clone(),
+            table_id: hand.table_id.clone(),
+            table_name: hand.table_name.clone(),
+            played_at: hand.played_at,
+            game_type: hand.game_type.clone(),
+            betting_structure: hand.betting_structure.clone(),
+            small_blind: hand.small_blind,
+            big_blind: hand.big_blind,
+            pot: hand.pot,
+            rake: hand.rake,
+            players: hand.players.len(),
+            hero_id: hand.hero_id.clone(),
+            hero_net,
+            board: hand.board.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PruneReport {
+    // Summarized because of their age and because of the quota
+    summarized_by_age: u32,
+    summarized_by_quota: u32,
+    compacted: bool,
+    bytes_before: u64,
+    bytes_after: u64,
+    // Still over quota with nothing left that may be summarized
+    over_quota: bool,
+    ran_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableUsage {
+    name: &'static str,
+    rows: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageUsage {
+    // The whole file, free pages included
+    database_bytes: u64,
+    // Pages freed by deletes and not yet compacted away
+    free_bytes: u64,
+    quota_bytes: u64,
+    // Serialized size of the full hand records
+    hand_data_bytes: u64,
+    full_hands: i64,
+    summarized_hands: i64,
+    oldest_full_hand_at: Option<DateTime<Utc>>,
+    tables: Vec<TableUsage>,
+    last_prune: Option<PruneReport>,
+}
+
+// Tables reported by `get_storage_usage`
+const TABLES: &[&str] = &["hands", "hand_summaries", "hand_ev", "hand_reports", "notes", "audit_log", "chat_messages", "chat_reports", "kv"];
+
+fn load_settings(db: &Database) -> Result<RetentionSettings, String> {
+    match db.get_value(KEY_SETTINGS)? {
+        Some(data) => serde_json::from_str(&data)
+            .map(RetentionSettings::clamped)
+            .map_err(|e| format!("Invalid retention settings: {}", e)),
+        None => Ok(RetentionSettings::default()),
+    }
+}
+
+fn load_last_run(db: &Database) -> Result<Option<PruneReport>, String> {
+    match db.get_value(KEY_LAST_RUN)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid prune report: {}", e)),
+        None => Ok(None),
+    }
+}
+
+fn pragma(conn: &Connection, name: &str) -> rusqlite::Result<u64> {
+    conn.query_row(&format!("PRAGMA {}", name), [], |row| row.get::<_, i64>(0)).map(|v| v.max(0) as u64)
+}
+
+// (file size, bytes on free pages)
+fn file_usage(conn: &Connection) -> rusqlite::Result<(u64, u64)> {
+    let page_size = pragma(conn, "page_size")?;
+    Ok((pragma(conn, "page_count")? * page_size, pragma(conn, "freelist_count")? * page_size))
+}
+
+fn used_bytes(conn: &Connection) -> rusqlite::Result<u64> {
+    let (total, free) = file_usage(conn)?;
+    Ok(total.saturating_sub(free))
+}
+
+// A summarized hand is not taken back from a later download
+pub fn is_summarized(conn: &Connection, hand_id: &str) -> rusqlite::Result<bool> {
+    conn.query_row("SELECT 1 FROM hand_summaries WHERE id = ?1", [hand_id], |_| Ok(()))
+        .optional()
+        .map(|found| found.is_some())
+}
+
+fn keep_in_full(hand: &HandRecord, settings: &RetentionSettings) -> bool {
+    hand.big_blind > 0 && hand.pot as u64 >= hand.big_blind as u64 * settings.keep_pot_bb as u64
+}
+
+// Summarize one batch of hands played before `cutoff`, oldest first, starting after
+// the `(played_at, id)` cursor and skipping hands changed after the `(updated_at, id)`
+// sync has uploaded through. Returns how many were summarized and the new cursor,
+// None once the range is exhausted.
+fn summarize_batch(
+    conn: &Connection,
+    settings: &RetentionSettings,
+    cutoff: i64,
+    after: (i64, String),
+    uploaded: Option<&(i64, String)>,
+) -> rusqlite::Result<(u32, Option<(i64, String)>)> {
+    let tx = conn.unchecked_transaction()?;
+    let (uploaded_at, uploaded_id) = uploaded.map(|(at, id)| (*at, id.as_str())).unzip();
+    let rows: Vec<(String, i64, String)> = tx
+        .prepare(
+            "SELECT id, played_at, data FROM hands
+             WHERE played_at < ?1 AND (played_at > ?2 OR (played_at = ?2 AND id > ?3))
+               AND id NOT IN (SELECT hand_id FROM hand_reports)
+               AND (?5 IS NULL OR updated_at < ?5 OR (updated_at = ?5 AND id <= ?6))
+             ORDER BY played_at, id LIMIT ?4",
+        )?
+        .query_map(params![cutoff, after.0, after.1, BATCH_SIZE, uploaded_at, uploaded_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    let Some((last_id, last_played_at, _)) = rows.last() else {
+        return Ok((0, None));
+    };
+    let cursor = Some((*last_played_at, last_id.clone()));
+
+    let mut summarized = 0;
+    for (id, _, data) in rows {
+        // Unreadable rows are left alone rather than lost
+        let Ok(hand) = parse_hand(data) else { continue };
+        if keep_in_full(&hand, settings) {
+            continue;
+        }
+        let summary = serde_json::to_string(&HandSummary::of(&hand))
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        tx.execute(
+            "INSERT OR REPLACE INTO hand_summaries (id, table_id, played_at, data) VALUES (?1, ?2, ?3, ?4)",
+            params![id, hand.table_id, to_millis(&hand.played_at), summary],
+        )?;
+        tx.execute("DELETE FROM hands WHERE id = ?1", [&id])?;
+        summarized += 1;
+    }
+    tx.commit()?;
+    Ok((summarized, cursor))
+}
+
+// Summarize what the policy allows before `cutoff`; with `until_bytes`, stop as soon
+// as the database uses no more than that
+fn summarize_before(
+    db: &Database,
+    settings: &RetentionSettings,
+    cutoff: DateTime<Utc>,
+    until_bytes: Option<u64>,
+) -> Result<u32, String> {
+    let uploaded = sync::uploaded_through(db)?;
+    let mut total = 0;
+    let mut cursor = Some((i64::MIN, String::new()));
+    while let Some(after) = cursor {
+        if let Some(limit) = until_bytes {
+            if db.with_conn(used_bytes)? <= limit {
+                break;
+            }
+        }
+        let (summarized, next) = db.with_conn(|conn| summarize_batch(conn, settings, to_millis(&cutoff), after, uploaded.as_ref()))?;
+        total += summarized;
+        cursor = next;
+    }
+    Ok(total)
+}
+
+fn run(db: &Database, settings: &RetentionSettings) -> Result<PruneReport, String> {
+    let now = Utc::now();
+    let bytes_before = db.with_conn(file_usage)?.0;
+    let quota = settings.quota_mb * MB;
+
+    let days_ago = |days: u32| {
+        now.checked_sub_signed(Duration::days(days as i64))
+            .ok_or_else(|| format!("{} days is too far back", days))
+    };
+
+    let summarized_by_age = summarize_before(db, settings, days_ago(settings.keep_full_days)?, None)?;
+    let summarized_by_quota = if db.with_conn(used_bytes)? > quota {
+        summarize_before(db, settings, days_ago(MIN_FULL_DAYS)?, Some(quota))?
+    } else {
+        0
+    };
+
+    let (size, free) = db.with_conn(file_usage)?;
+    let compacted = free >= COMPACT_MIN_MB * MB && free as f64 >= size as f64 * COMPACT_FREE_SHARE;
+    if compacted {
+        db.with_conn(|conn| conn.execute_batch("VACUUM;"))?;
+    }
+
+    let report = PruneReport {
+        summarized_by_age,
+        summarized_by_quota,
+        compacted,
+        bytes_before,
+        bytes_after: db.with_conn(file_usage)?.0,
+        over_quota: db.with_conn(used_bytes)? > quota,
+        ran_at: now,
+    };
+    let data = serde_json::to_string(&report).map_err(|e| e.to_string())?;
+    db.set_value(KEY_LAST_RUN, &data)?;
+    Ok(report)
+}
+
+// Run the policy unless a run is already going. None when disabled or busy.
+fn prune(app: &AppHandle, db: &Database, force: bool) -> Result<Option<PruneReport>, String> {
+    let settings = load_settings(db)?;
+    if !settings.enabled && !force {
+        return Ok(None);
+    }
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return Ok(None);
+    }
+    let report = {
+        let _running = RunningGuard;
+        run(db, &settings)?
+    };
+
+    if report.summarized_by_age + report.summarized_by_quota > 0 {
+        let _ = app.emit_all("hand_history_pruned", report.clone());
+    }
+    if report.over_quota {
+        let _ = app.emit_all("storage_quota_exceeded", report.clone());
+    }
+    Ok(Some(report))
+}
+
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(FIRST_RUN_DELAY).await;
+        loop {
+            let pruned = {
+                let app = app.clone();
+                tauri::async_runtime::spawn_blocking(move || db::get(&app).and_then(|db| prune(&app, &db, false))).await
+            };
+            match pruned {
+                Ok(Err(e)) => eprintln!("Hand history pruning failed: {}", e),
+                Err(e) => eprintln!("Hand history pruning failed: {}", e),
+                Ok(Ok(_)) => {}
+            }
+            tokio::time::sleep(RUN_INTERVAL).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_retention_settings(db: Db<'_>) -> Result<RetentionSettings, String> {
+    load_settings(&db)
+}
+
+#[tauri::command]
+pub async fn set_retention_settings(db: Db<'_>, settings: RetentionSettings) -> Result<RetentionSettings, String> {
+    let settings = settings.clamped();
+    let data = serde_json::to_string(&settings).map_err(|e| e.to_string())?;
+    db.set_value(KEY_SETTINGS, &data)?;
+    Ok(settings)
+}
+
+// Apply the retention policy now, even when the scheduled runs are disabled
+#[tauri::command]
+pub async fn prune_hand_history(app: AppHandle) -> Result<PruneReport, String> {
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || db::get(&handle).and_then(|db| prune(&handle, &db, true)))
+        .await
+        .map_err(|e| e.to_string())??
+        .ok_or_else(|| "Pruning is already running".to_string())
+}
+
+#[tauri::command]
+pub async fn get_storage_usage(db: Db<'_>) -> Result<StorageUsage, String> {
+    let settings = load_settings(&db)?;
+    let last_prune = load_last_run(&db)?;
+    db.with_conn(|conn| {
+        let (database_bytes, free_bytes) = file_usage(conn)?;
+        let (full_hands, hand_data_bytes, oldest): (i64, i64, Option<i64>) = conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(LENGTH(data)), 0), MIN(played_at) FROM hands",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        let tables = TABLES
+            .iter()
+            .map(|name| {
+                let rows = conn.query_row(&format!("SELECT COUNT(*) FROM {}", name), [], |row| row.get(0))?;
+                Ok(TableUsage { name, rows })
+            })
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let summarized_hands = tables.iter().find(|t| t.name == "hand_summaries").map(|t| t.rows).unwrap_or(0);
+        Ok(StorageUsage {
+            database_bytes,
+            free_bytes,
+            quota_bytes: settings.quota_mb * MB,
+            hand_data_bytes: hand_data_bytes.max(0) as u64,
+            full_hands,
+            summarized_hands,
+            oldest_full_hand_at: oldest.and_then(|ms| Utc.timestamp_millis_opt(ms).single()),
+            tables,
+            last_prune,
+        })
+    })
+}
+
+// Summaries of pruned hands, most recent first
+#[tauri::command]
+pub async fn get_hand_summaries(
+    db: Db<'_>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<Vec<HandSummary>, String> {
+    db.with_conn(|conn| {
+        let mut stmt = conn.prepare("SELECT data FROM hand_summaries ORDER BY played_at DESC LIMIT ?1 OFFSET ?2")?;
+        let rows = stmt.query_map(params![limit.unwrap_or(50), offset.unwrap_or(0)], |row| row.get::<_, String>(0))?;
+        rows.map(|row| {
+            row.and_then(|data| {
+                serde_json::from_str(&data).map_err(|e| {
+                    rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+                })
+            })
+        })
+        .collect()
+    })
+}
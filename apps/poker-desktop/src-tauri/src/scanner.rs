@@ -0,0 +1,233 @@
+// Table scanner. While enabled, a background loop walks the lobby and checks each
+// table against the player's rules: stakes, table size, an open seat and enough
+// seated players carrying a label from the notes (see notes.rs). The lobby listing
+// has no player lists, so only the tables passing the cheap checks get their state
+// fetched, a bounded number per scan. A table that starts matching raises an OS
+// notification and `scanner_match`; it alerts again only after a scan in which it
+// no longer matched.
+
+use crate::db::{self, Database, Db};
+use crate::maintenance;
+use crate::notes;
+use crate::profile::BackendProfile;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+const KEY_RULES: &str = "scanner.rules";
+const SCAN_INTERVAL: Duration = Duration::from_secs(60);
+// Table states fetched per scan, fullest tables first
+const MAX_STATE_FETCHES: usize = 12;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScannerRules {
+    enabled: bool,
+    min_big_blind: Option<u32>,
+    max_big_blind: Option<u32>,
+    // Table sizes, e.g. 6 for six-max; any size when empty
+    table_sizes: Vec<u8>,
+    require_open_seat: bool,
+    // A label name, or "weak" for any weak label
+    label: String,
+    min_labeled: usize,
+}
+
+impl Default for ScannerRules {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_big_blind: None,
+            max_big_blind: None,
+            table_sizes: Vec::new(),
+            require_open_seat: true,
+            label: "weak".to_string(),
+            min_labeled: 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScannerMatch {
+    table_id: String,
+    name: String,
+    small_blind: u32,
+    big_blind: u32,
+    player_count: u8,
+    max_players: u8,
+    // Seated players carrying a matching label
+    labeled: Vec<String>,
+    first_seen: DateTime<Utc>,
+    last_seen: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScannerStatus {
+    enabled: bool,
+    last_scan_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+    // Best first
+    matches: Vec<ScannerMatch>,
+}
+
+#[derive(Default)]
+struct ScanResults {
+    matches: HashMap<String, ScannerMatch>,
+    last_scan_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct ScannerState {
+    results: Mutex<ScanResults>,
+}
+
+fn load_rules(db: &Database) -> Result<ScannerRules, String> {
+    match db.get_value(KEY_RULES)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid scanner rules: {}", e)),
+        None => Ok(ScannerRules::default()),
+    }
+}
+
+fn notify(app: &AppHandle, found: &ScannerMatch) {
+    let body = format!(
+        "{} ({}/{}): {} of {} seated players labeled",
+        found.name,
+        found.small_blind,
+        found.big_blind,
+        found.labeled.len(),
+        found.player_count
+    );
+    let shown = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier)
+        .title("Table found")
+        .body(body)
+        .show();
+    if let Err(e) = shown {
+        eprintln!("Failed to show table scanner alert: {}", e);
+    }
+    let _ = app.emit_all("scanner_match", found.clone());
+}
+
+async fn scan(app: &AppHandle, rules: &ScannerRules) -> Result<(), String> {
+    let api_url = app.state::<BackendProfile>().api_url.clone();
+    let mut candidates: Vec<crate::Table> = crate::fetch_tables(&api_url)
+        .await?
+        .into_iter()
+        .filter(|t| rules.min_big_blind.is_none_or(|min| t.blinds.big >= min))
+        .filter(|t| rules.max_big_blind.is_none_or(|max| t.blinds.big <= max))
+        .filter(|t| rules.table_sizes.is_empty() || rules.table_sizes.contains(&t.max_players))
+        .filter(|t| !rules.require_open_seat || t.player_count < t.max_players)
+        .filter(|t| t.player_count as usize >= rules.min_labeled)
+        .collect();
+    candidates.sort_by_key(|t| std::cmp::Reverse(t.player_count));
+    candidates.truncate(MAX_STATE_FETCHES);
+
+    let now = Utc::now();
+    let mut found = Vec::new();
+    for table in candidates {
+        let mirror = match crate::preview::fetch_state(&api_url, &table.id).await {
+            Ok(mirror) => mirror,
+            Err(e) => {
+                eprintln!("Scanner could not fetch table {}: {}", table.id, e);
+                continue;
+            }
+        };
+        let db = db::get(app)?;
+        let labels = db.with_conn(|conn| notes::labels_for(conn, mirror.players.iter().map(|p| p.id.as_str())))?;
+        let labeled: Vec<String> = mirror
+            .players
+            .iter()
+            .filter(|p| labels.get(&p.id).is_some_and(|l| notes::label_matches(l, &rules.label)))
+            .map(|p| p.id.clone())
+            .collect();
+        if labeled.len() >= rules.min_labeled.max(1) {
+            found.push(ScannerMatch {
+                table_id: table.id,
+                name: table.name,
+                small_blind: table.blinds.small,
+                big_blind: table.blinds.big,
+                player_count: mirror.players.len() as u8,
+                max_players: table.max_players,
+                labeled,
+                first_seen: now,
+                last_seen: now,
+            });
+        }
+    }
+
+    let state = app.state::<ScannerState>();
+    let mut results = state.results.lock().map_err(|_| "Scanner lock poisoned".to_string())?;
+    let mut matches = HashMap::new();
+    for mut table in found {
+        match results.matches.get(&table.table_id) {
+            Some(previous) => table.first_seen = previous.first_seen,
+            None => notify(app, &table),
+        }
+        matches.insert(table.table_id.clone(), table);
+    }
+    results.matches = matches;
+    results.last_scan_at = Some(now);
+    results.last_error = None;
+    Ok(())
+}
+
+// Background scan loop, started once the database is open. Rules are re-read on
+// every pass so changes apply from the next scan; nothing is scanned while play is
+// winding down for maintenance.
+pub fn start_scanner(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let rules = db::get(&app).and_then(|db| load_rules(&db)).unwrap_or_default();
+            if rules.enabled && !maintenance::is_draining(&app) {
+                if let Err(e) = scan(&app, &rules).await {
+                    eprintln!("Table scan failed: {}", e);
+                    if let Ok(mut results) = app.state::<ScannerState>().results.lock() {
+                        results.last_error = Some(e);
+                    }
+                }
+            }
+            tokio::time::sleep(SCAN_INTERVAL).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_scanner_rules(db: Db<'_>) -> Result<ScannerRules, String> {
+    load_rules(&db)
+}
+
+#[tauri::command]
+pub async fn set_scanner_rules(
+    db: Db<'_>,
+    state: State<'_, ScannerState>,
+    rules: ScannerRules,
+) -> Result<ScannerRules, String> {
+    if !notes::is_label_filter(&rules.label) {
+        return Err(format!("Unknown label {}", rules.label));
+    }
+    if let (Some(min), Some(max)) = (rules.min_big_blind, rules.max_big_blind) {
+        if min > max {
+            return Err("Minimum big blind is above the maximum".to_string());
+        }
+    }
+    let data = serde_json::to_string(&rules).map_err(|e| e.to_string())?;
+    db.set_value(KEY_RULES, &data)?;
+    // Matches found under the old rules no longer mean anything
+    *state.results.lock().map_err(|_| "Scanner lock poisoned".to_string())? = ScanResults::default();
+    Ok(rules)
+}
+
+#[tauri::command]
+pub async fn get_scanner_matches(db: Db<'_>, state: State<'_, ScannerState>) -> Result<ScannerStatus, String> {
+    let enabled = load_rules(&db)?.enabled;
+    let results = state.results.lock().map_err(|_| "Scanner lock poisoned".to_string())?;
+    let mut matches: Vec<ScannerMatch> = results.matches.values().cloned().collect();
+    matches.sort_by(|a, b| b.labeled.len().cmp(&a.labeled.len()).then(a.first_seen.cmp(&b.first_seen)));
+    Ok(ScannerStatus { enabled, last_scan_at: results.last_scan_at, last_error: results.last_error.clone(), matches })
+}
@@ -0,0 +1,250 @@
+// Response validation. When a backend payload does not deserialize, the JSON is
+// compared against a declared schema so the error names the fields that are missing
+// or have the wrong type ("data[2].blinds.big: expected number, found string")
+// instead of a bare "Failed to parse response". In permissive mode, non-critical
+// fields that are missing or mistyped are filled with defaults and parsing is retried,
+// so a cosmetic backend rename does not take a whole screen down.
+
+use reqwest::Response;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static PERMISSIVE: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy)]
+pub enum Kind {
+    String,
+    Number,
+    Array(&'static Kind),
+    Object(&'static [Field]),
+    // Anything goes; the type is not checked
+    Any,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Field {
+    name: &'static str,
+    kind: Kind,
+    // Missing or mistyped critical fields always fail the parse
+    critical: bool,
+    // Maps to an Option; null and absence are both fine
+    nullable: bool,
+}
+
+impl Field {
+    pub const fn critical(name: &'static str, kind: Kind) -> Self {
+        Self { name, kind, critical: true, nullable: false }
+    }
+
+    // Filled with a default in permissive mode
+    pub const fn defaulted(name: &'static str, kind: Kind) -> Self {
+        Self { name, kind, critical: false, nullable: false }
+    }
+
+    pub const fn optional(name: &'static str, kind: Kind) -> Self {
+        Self { name, kind, critical: false, nullable: true }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaIssue {
+    path: String,
+    expected: String,
+    // "missing" when the field is absent
+    found: String,
+    critical: bool,
+}
+
+impl std::fmt::Display for SchemaIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.found == "missing" {
+            write!(f, "{}: missing {}", self.path, self.expected)
+        } else {
+            write!(f, "{}: expected {}, found {}", self.path, self.expected, self.found)
+        }
+    }
+}
+
+pub fn permissive() -> bool {
+    PERMISSIVE.load(Ordering::SeqCst) || std::env::var("PRIMO_PERMISSIVE_PARSE").is_ok()
+}
+
+fn kind_name(kind: &Kind) -> String {
+    match kind {
+        Kind::String => "string".to_string(),
+        Kind::Number => "number".to_string(),
+        Kind::Array(item) => format!("array of {}", kind_name(item)),
+        Kind::Object(_) => "object".to_string(),
+        Kind::Any => "any value".to_string(),
+    }
+}
+
+fn value_name(value: &Value) -> String {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+    .to_string()
+}
+
+fn default_for(kind: &Kind) -> Value {
+    match kind {
+        Kind::String => Value::String(String::new()),
+        Kind::Number => Value::from(0),
+        Kind::Array(_) => Value::Array(Vec::new()),
+        Kind::Object(fields) => {
+            let mut object = Value::Object(Map::new());
+            let mut ignored = Vec::new();
+            check_object(fields, &mut object, "", true, &mut ignored);
+            object
+        }
+        Kind::Any => Value::Null,
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+// Collect every mismatch under `value`. With `fill` set, non-critical fields that
+// are missing or mistyped are replaced with defaults as they are found.
+fn check(kind: &Kind, value: &mut Value, path: &str, fill: bool, issues: &mut Vec<SchemaIssue>) -> bool {
+    let matches = matches!(
+        (kind, &*value),
+        (Kind::Any, _)
+            | (Kind::String, Value::String(_))
+            | (Kind::Number, Value::Number(_))
+            | (Kind::Array(_), Value::Array(_))
+            | (Kind::Object(_), Value::Object(_))
+    );
+    if !matches {
+        return false;
+    }
+    match (kind, value) {
+        (Kind::Array(item), Value::Array(items)) => {
+            for (index, entry) in items.iter_mut().enumerate() {
+                let entry_path = format!("{}[{}]", path, index);
+                if !check(item, entry, &entry_path, fill, issues) {
+                    issues.push(SchemaIssue {
+                        path: entry_path,
+                        expected: kind_name(item),
+                        found: value_name(entry),
+                        critical: true,
+                    });
+                }
+            }
+        }
+        (Kind::Object(fields), value) => check_object(fields, value, path, fill, issues),
+        _ => {}
+    }
+    true
+}
+
+fn check_object(fields: &[Field], value: &mut Value, path: &str, fill: bool, issues: &mut Vec<SchemaIssue>) {
+    let Value::Object(object) = value else { return };
+    for field in fields {
+        let field_path = join(path, field.name);
+        let found = match object.get_mut(field.name) {
+            None => "missing".to_string(),
+            Some(Value::Null) if field.nullable => continue,
+            Some(entry) => {
+                if check(&field.kind, entry, &field_path, fill, issues) {
+                    continue;
+                }
+                value_name(entry)
+            }
+        };
+        if field.nullable && fill {
+            object.insert(field.name.to_string(), Value::Null);
+        } else if !field.critical && fill {
+            object.insert(field.name.to_string(), default_for(&field.kind));
+        }
+        if !(field.nullable && found == "missing") {
+            issues.push(SchemaIssue {
+                path: field_path,
+                expected: kind_name(&field.kind),
+                found,
+                critical: field.critical,
+            });
+        }
+    }
+}
+
+// Deserialize `value` as `T`, explaining failures against `kind`. `what` names the
+// payload in errors, e.g. "table list".
+pub fn parse<T: DeserializeOwned>(mut value: Value, kind: &Kind, what: &str) -> Result<T, String> {
+    let error = match serde_json::from_value::<T>(value.clone()) {
+        Ok(parsed) => return Ok(parsed),
+        Err(e) => e,
+    };
+
+    let fill = permissive();
+    let found = value_name(&value);
+    let mut issues = Vec::new();
+    let root_matches = check(kind, &mut value, "", fill, &mut issues);
+    if fill && root_matches && !issues.iter().any(|issue| issue.critical) {
+        if let Ok(parsed) = serde_json::from_value::<T>(value) {
+            for issue in &issues {
+                eprintln!("Filled default for {} field {}", what, issue);
+            }
+            return Ok(parsed);
+        }
+    }
+
+    if !root_matches {
+        issues.push(SchemaIssue {
+            path: "(root)".to_string(),
+            expected: kind_name(kind),
+            found,
+            critical: true,
+        });
+    }
+    if issues.is_empty() {
+        // The schema does not describe whatever serde tripped over
+        return Err(format!("Failed to parse {}: {}", what, error));
+    }
+    let details = issues.iter().map(|issue| issue.to_string()).collect::<Vec<_>>().join("; ");
+    eprintln!("Unexpected {} from backend: {}", what, details);
+    Err(format!("Failed to parse {}: {}", what, details))
+}
+
+async fn read_json(response: Response, what: &str) -> Result<Value, String> {
+    response.json().await.map_err(|e| format!("Failed to parse {}: {}", what, e))
+}
+
+// A bare JSON body
+pub async fn body<T: DeserializeOwned>(response: Response, kind: &Kind, what: &str) -> Result<T, String> {
+    parse(read_json(response, what).await?, kind, what)
+}
+
+// The `data` of an `ApiResponse` envelope, None when the backend sent none. A
+// failure envelope becomes its error message.
+pub async fn api_data<T: DeserializeOwned>(response: Response, kind: &Kind, what: &str) -> Result<Option<T>, String> {
+    let envelope = read_json(response, what).await?;
+    if envelope["success"].as_bool() != Some(true) {
+        return Err(envelope["error"]["message"].as_str().unwrap_or("Unknown error").to_string());
+    }
+    match envelope.get("data") {
+        None | Some(Value::Null) => Ok(None),
+        Some(data) => parse(data.clone(), kind, what).map(Some),
+    }
+}
+
+// Fill defaults for non-critical fields instead of failing; also on with the
+// PRIMO_PERMISSIVE_PARSE environment variable
+#[tauri::command]
+pub async fn set_permissive_parsing(enabled: bool) -> Result<(), String> {
+    PERMISSIVE.store(enabled, Ordering::SeqCst);
+    Ok(())
+}
@@ -0,0 +1,125 @@
+// Signed-in sessions across devices. Sessions can be listed and revoked through the
+// backend session API. When this device's own session is revoked elsewhere, the
+// stored tokens are cleared and `forced_logout` tells the frontend to return to
+// the login screen.
+
+use crate::audit;
+use crate::claims;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveSession {
+    id: String,
+    #[serde(default)]
+    device_name: Option<String>,
+    #[serde(default)]
+    ip_address: Option<String>,
+    #[serde(default)]
+    created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    last_seen_at: Option<DateTime<Utc>>,
+    // Filled in locally
+    #[serde(default)]
+    current: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ForcedLogout {
+    reason: String,
+}
+
+async fn request<T: serde::de::DeserializeOwned>(builder: reqwest::RequestBuilder) -> Result<T, String> {
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+
+    let response = crate::http::send(builder.header("Authorization", format!("Bearer {}", token)))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("Session request failed: {}", error_text));
+    }
+
+    let api_response: crate::ApiResponse<T> = response.json().await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    if api_response.success {
+        api_response.data.ok_or_else(|| "No data returned".to_string())
+    } else {
+        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    }
+}
+
+// Clear local credentials after the backend ended this device's session
+pub fn force_logout(app: &AppHandle, reason: &str) {
+    let result = crate::clear_auth_token();
+    audit::record(app, "forced_logout", json!({ "reason": reason }), &result);
+    let _ = app.emit_all("forced_logout", ForcedLogout { reason: reason.to_string() });
+}
+
+#[tauri::command]
+pub async fn list_active_sessions(app: AppHandle, api_url: String) -> Result<Vec<ActiveSession>, String> {
+    let current = claims::current()?.session_id;
+    let client = crate::create_http_client()?;
+    let mut sessions: Vec<ActiveSession> = request(client.get(format!("{}/api/auth/sessions", api_url))).await?;
+
+    for session in &mut sessions {
+        session.current = current.as_deref() == Some(session.id.as_str());
+    }
+    // The backend no longer knows the session this device is using
+    if current.is_some() && !sessions.iter().any(|s| s.current) {
+        force_logout(&app, "session_revoked");
+        return Err("This session was signed out from another device".to_string());
+    }
+    Ok(sessions)
+}
+
+// Revoking the current session signs this device out as well
+#[tauri::command]
+pub async fn revoke_session(app: AppHandle, api_url: String, session_id: String) -> Result<(), String> {
+    let client = crate::create_http_client()?;
+    let result = request::<Value>(client.delete(format!("{}/api/auth/sessions/{}", api_url, session_id))).await;
+    audit::record(&app, "revoke_session", json!({ "sessionId": session_id }), &result);
+    result?;
+
+    if claims::current().ok().and_then(|c| c.session_id).as_deref() == Some(session_id.as_str()) {
+        force_logout(&app, "session_revoked");
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn logout_all_devices(app: AppHandle, api_url: String) -> Result<(), String> {
+    let client = crate::create_http_client()?;
+    let result = request::<Value>(client.post(format!("{}/api/auth/sessions/revoke-all", api_url)).json(&json!({}))).await;
+    audit::record(&app, "logout_all_devices", json!({}), &result);
+    result?;
+    force_logout(&app, "logout_all_devices");
+    Ok(())
+}
+
+// Called by the frontend when the server pushes `session_revoked`. Without a session
+// id every session is treated as revoked.
+#[tauri::command]
+pub async fn handle_session_revoked(app: AppHandle, session_id: Option<String>) -> Result<bool, String> {
+    let current = match claims::current() {
+        Ok(claims) => claims.session_id,
+        // Already signed out
+        Err(_) => return Ok(false),
+    };
+    let ours = match (&session_id, &current) {
+        (None, _) => true,
+        (Some(revoked), Some(current)) => revoked == current,
+        (Some(_), None) => false,
+    };
+    if ours {
+        force_logout(&app, "session_revoked");
+    }
+    Ok(ours)
+}
@@ -0,0 +1,220 @@
+// Showdown narration. The chips are split into a main pot and side pots by
+// commitment level, each pot is awarded with the local evaluator, and every pot is
+// described in words - "Seat 2 wins the main pot (1200) with a flush, Ace high -
+// beats two pair, Kings and Sevens" - so the table UI and text-to-speech can read
+// the outcome out. Seats are numbered from 1 in the text.
+
+use crate::cards::Card;
+use crate::db::Db;
+use crate::ev;
+use crate::evaluator::{evaluate, HandCategory, HandValue};
+use crate::history::{get_hand_by_id, HandRecord};
+use serde::Serialize;
+use std::collections::HashSet;
+
+// One layer of the pot. `shares` lines up with `winners`.
+pub struct Pot {
+    pub amount: u32,
+    pub eligible: Vec<usize>,
+    pub winners: Vec<usize>,
+    pub shares: Vec<u32>,
+}
+
+pub struct Participant {
+    pub seat: u8,
+    pub value: Option<HandValue>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PotWinner {
+    seat: u8,
+    amount: u32,
+    hand: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PotExplanation {
+    name: String,
+    amount: u32,
+    eligible_seats: Vec<u8>,
+    winners: Vec<PotWinner>,
+    // Best hand that lost this pot, if any was shown
+    beaten: Option<String>,
+    text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShowdownExplanation {
+    pots: Vec<PotExplanation>,
+    summary: String,
+}
+
+// Split `committed` into pots and award them. `values` is None for players who
+// folded or did not show; odd chips go to the earliest winner in `odd_chip_order`.
+// Rake comes out of the main pot.
+pub fn side_pots(committed: &[u32], values: &[Option<HandValue>], odd_chip_order: &[usize], rake: u32) -> Vec<Pot> {
+    let mut levels: Vec<u32> = committed.iter().copied().filter(|&c| c > 0).collect();
+    levels.sort_unstable();
+    levels.dedup();
+
+    let mut pots = Vec::new();
+    let mut previous = 0;
+    for level in levels {
+        let mut amount: u32 = committed.iter().map(|&c| c.min(level) - c.min(previous)).sum();
+        previous = level;
+        if pots.is_empty() {
+            amount = amount.saturating_sub(rake);
+        }
+
+        let eligible: Vec<usize> = (0..committed.len())
+            .filter(|&i| values[i].is_some() && committed[i] >= level)
+            .collect();
+        // Chips only folded players reached go to whoever is still in
+        let contenders = if eligible.is_empty() {
+            (0..committed.len()).filter(|&i| values[i].is_some()).collect()
+        } else {
+            eligible
+        };
+        let Some(best) = contenders.iter().filter_map(|&i| values[i]).max() else { continue };
+        let winners: Vec<usize> = contenders.iter().copied().filter(|&i| values[i] == Some(best)).collect();
+
+        let share = amount / winners.len() as u32;
+        let mut shares = vec![share; winners.len()];
+        if let Some(first) = odd_chip_order.iter().find_map(|seat| winners.iter().position(|w| w == seat)) {
+            shares[first] += amount - share * winners.len() as u32;
+        }
+        pots.push(Pot { amount, eligible: contenders, winners, shares });
+    }
+    pots
+}
+
+// "a flush, Ace high", "two pair, Kings and Sevens"
+fn phrase(value: &HandValue) -> String {
+    let description = value.describe();
+    let mut chars = description.chars();
+    let lower = match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    };
+    match value.category {
+        HandCategory::Pair | HandCategory::Straight | HandCategory::Flush | HandCategory::FullHouse | HandCategory::StraightFlush => {
+            format!("a {}", lower)
+        }
+        _ => lower,
+    }
+}
+
+fn seat_list(seats: &[u8]) -> String {
+    let names: Vec<String> = seats.iter().map(|s| (s + 1).to_string()).collect();
+    match names.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+        None => String::new(),
+    }
+}
+
+pub fn explain(pots: &[Pot], participants: &[Participant]) -> ShowdownExplanation {
+    let explanations: Vec<PotExplanation> = pots
+        .iter()
+        .enumerate()
+        .map(|(index, pot)| {
+            let name = match (index, pots.len()) {
+                (_, 1) => "the pot".to_string(),
+                (0, _) => "the main pot".to_string(),
+                (n, _) => format!("side pot {}", n),
+            };
+            let seats: Vec<u8> = pot.winners.iter().map(|&i| participants[i].seat).collect();
+            let winning = pot.winners.first().and_then(|&i| participants[i].value);
+            let beaten = pot
+                .eligible
+                .iter()
+                .filter(|i| !pot.winners.contains(i))
+                .filter_map(|&i| participants[i].value)
+                .max();
+
+            let mut text = match (pot.eligible.len(), seats.len()) {
+                (1, _) => format!("Seat {} takes {} ({}) uncontested", seat_list(&seats), name, pot.amount),
+                (_, 1) => format!("Seat {} wins {} ({})", seat_list(&seats), name, pot.amount),
+                _ => format!("Seats {} split {} ({})", seat_list(&seats), name, pot.amount),
+            };
+            if let (Some(value), true) = (&winning, pot.eligible.len() > 1) {
+                text.push_str(&format!(" with {}", phrase(value)));
+                if let Some(loser) = &beaten {
+                    text.push_str(&format!(" - beats {}", phrase(loser)));
+                    if loser.describe() == value.describe() {
+                        text.push_str(" on kickers");
+                    }
+                }
+            }
+
+            PotExplanation {
+                name,
+                amount: pot.amount,
+                eligible_seats: pot.eligible.iter().map(|&i| participants[i].seat).collect(),
+                winners: pot
+                    .winners
+                    .iter()
+                    .zip(&pot.shares)
+                    .map(|(&i, &amount)| PotWinner {
+                        seat: participants[i].seat,
+                        amount,
+                        hand: participants[i].value.filter(|_| pot.eligible.len() > 1).map(|v| v.describe()),
+                    })
+                    .collect(),
+                beaten: beaten.map(|v| v.describe()),
+                text,
+            }
+        })
+        .collect();
+
+    let summary = explanations.iter().map(|p| p.text.as_str()).collect::<Vec<_>>().join(". ");
+    ShowdownExplanation { pots: explanations, summary }
+}
+
+// Explanation for a stored hand, from its actions and the cards that were shown
+pub fn explain_hand(hand: &HandRecord) -> Option<ShowdownExplanation> {
+    let committed = ev::committed(hand);
+    let folded: HashSet<&str> = hand.actions.iter().filter(|a| a.action == "fold").map(|a| a.player_id.as_str()).collect();
+    let board = hand.board.iter().map(|c| Card::parse(c)).collect::<Option<Vec<Card>>>()?;
+    let live = hand.players.iter().filter(|p| !folded.contains(p.player_id.as_str())).count();
+
+    let participants: Vec<Participant> = hand
+        .players
+        .iter()
+        .map(|p| {
+            let value = if folded.contains(p.player_id.as_str()) {
+                None
+            } else if live == 1 {
+                // Uncontested: the winner's hand does not matter
+                Some(HandValue { category: HandCategory::HighCard, ranks: [0; 5] })
+            } else {
+                p.hole_cards
+                    .as_ref()
+                    .and_then(|cards| cards.iter().map(|c| Card::parse(c)).collect::<Option<Vec<Card>>>())
+                    .filter(|hole| hole.len() == 2 && board.len() == 5)
+                    .map(|hole| evaluate(&[hole, board.clone()].concat()))
+            };
+            Participant { seat: p.seat, value }
+        })
+        .collect();
+
+    let amounts: Vec<u32> = hand.players.iter().map(|p| committed.get(p.player_id.as_str()).copied().unwrap_or(0)).collect();
+    let values: Vec<Option<HandValue>> = participants.iter().map(|p| p.value).collect();
+    let order: Vec<usize> = (0..participants.len()).collect();
+    let pots = side_pots(&amounts, &values, &order, hand.rake);
+    if pots.is_empty() {
+        return None;
+    }
+    Some(explain(&pots, &participants))
+}
+
+#[tauri::command]
+pub async fn explain_showdown(db: Db<'_>, hand_id: String) -> Result<ShowdownExplanation, String> {
+    let hand = db
+        .with_conn(|conn| get_hand_by_id(conn, &hand_id))?
+        .ok_or_else(|| format!("Hand {} is not in local history", hand_id))?;
+    explain_hand(&hand).ok_or_else(|| "Not enough cards were shown to explain this hand".to_string())
+}
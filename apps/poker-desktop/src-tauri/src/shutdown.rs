@@ -0,0 +1,244 @@
+// Orderly exit. Closing the main window while seated no longer kills the client
+// mid-hand: the close is held and `shutdown_requested` asks the player to sit out now,
+// finish the hands in progress first, or stay. Either way the client then sits out at
+// every table, asks the views to close their table sockets (`shutdown_close_sockets`,
+// answered with `report_sockets_closed`), closes its own notification and voice
+// sockets, runs a last cloud sync, finishes the open event buffer segments, settles
+// the database and exits. A close with no seats goes straight to the cleanup. If the
+// cleanup is still running after its grace period the app exits regardless, and
+// closing the window again or `force_exit` exits at once.
+
+use crate::db::Database;
+use crate::event_buffer;
+use crate::idle::IdleState;
+use crate::profile::BackendProfile;
+use crate::relay;
+use crate::sync;
+use crate::voice;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State, Window};
+
+// Longest wait for hands to end when finishing them
+const HAND_WAIT: Duration = Duration::from_secs(180);
+const HAND_TICK: Duration = Duration::from_secs(1);
+// Time left for everything after the hands, before the hard exit
+const CLEANUP_GRACE: Duration = Duration::from_secs(15);
+const SOCKET_WAIT: Duration = Duration::from_secs(3);
+const SYNC_WAIT: Duration = Duration::from_secs(8);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShutdownMode {
+    SitOut,
+    FinishHand,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Step {
+    WaitingForHands,
+    SittingOut,
+    ClosingSockets,
+    Flushing,
+    Exiting,
+}
+
+#[derive(Default)]
+enum Phase {
+    #[default]
+    Open,
+    // Waiting for the player to choose
+    Asking,
+    Exiting,
+}
+
+#[derive(Default)]
+pub struct ShutdownState {
+    phase: Mutex<Phase>,
+    // Windows that reported their table sockets closed
+    sockets_closed: Mutex<HashSet<String>>,
+}
+
+fn progress(app: &AppHandle, step: Step, tables_remaining: usize) {
+    let _ = app.emit_all("shutdown_progress", json!({ "step": step, "tablesRemaining": tables_remaining }));
+}
+
+async fn sit_out(api_url: &str, table_id: &str) -> Result<(), String> {
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+    let client = crate::create_http_client()?;
+    let response = crate::http::send(client.post(format!("{}/api/tables/{}/sit-out", api_url, table_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "reason": "shutdown" })))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        Err(format!("Sit out failed: {}", error_text))
+    }
+}
+
+// Sit out everywhere, at each table once its hand is over when finishing hands
+async fn leave_tables(app: &AppHandle, mode: ShutdownMode) {
+    let api_url = app.state::<BackendProfile>().api_url.clone();
+    let deadline = Instant::now() + HAND_WAIT;
+    let mut remaining = app.state::<IdleState>().seated_tables();
+    while !remaining.is_empty() {
+        let activity = app.state::<IdleState>().table_activity();
+        let waiting = mode == ShutdownMode::FinishHand && Instant::now() < deadline;
+        let (busy, ready): (Vec<String>, Vec<String>) = remaining
+            .into_iter()
+            .partition(|table_id| waiting && activity.get(table_id).copied().unwrap_or(false));
+        if !ready.is_empty() {
+            progress(app, Step::SittingOut, busy.len() + ready.len());
+        }
+        for table_id in ready {
+            if let Err(e) = sit_out(&api_url, &table_id).await {
+                eprintln!("Failed to sit out at {} before exit: {}", table_id, e);
+            }
+        }
+        remaining = busy;
+        if remaining.is_empty() {
+            break;
+        }
+        progress(app, Step::WaitingForHands, remaining.len());
+        tokio::time::sleep(HAND_TICK).await;
+    }
+}
+
+// Ask every window to close its table sockets and wait a moment for them to say so
+async fn close_view_sockets(app: &AppHandle) {
+    let state = app.state::<ShutdownState>();
+    if let Ok(mut closed) = state.sockets_closed.lock() {
+        closed.clear();
+    }
+    let _ = app.emit_all("shutdown_close_sockets", json!({}));
+    let deadline = Instant::now() + SOCKET_WAIT;
+    while Instant::now() < deadline {
+        let windows: Vec<String> = app.windows().into_keys().collect();
+        let all_closed = state.sockets_closed.lock().is_ok_and(|closed| windows.iter().all(|w| closed.contains(w)));
+        if all_closed {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+async fn run(app: AppHandle, mode: ShutdownMode) {
+    let watchdog = app.clone();
+    let grace = CLEANUP_GRACE + if mode == ShutdownMode::FinishHand { HAND_WAIT } else { Duration::ZERO };
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(grace).await;
+        eprintln!("Shutdown still running after {}s, exiting anyway", grace.as_secs());
+        watchdog.exit(0);
+    });
+
+    leave_tables(&app, mode).await;
+
+    progress(&app, Step::ClosingSockets, 0);
+    close_view_sockets(&app).await;
+    relay::close(&app);
+    if let Err(e) = voice::leave_voice(app.clone(), app.state()).await {
+        eprintln!("Failed to leave voice before exit: {}", e);
+    }
+
+    progress(&app, Step::Flushing, 0);
+    event_buffer::close_all();
+    // No database when it failed to open at startup
+    if let Some(db) = app.try_state::<Database>() {
+        match tokio::time::timeout(SYNC_WAIT, sync::sync_before_exit(&app)).await {
+            Ok(Err(e)) => eprintln!("Cloud sync before exit failed: {}", e),
+            Err(_) => eprintln!("Cloud sync before exit timed out"),
+            Ok(Ok(_)) => {}
+        }
+        if let Err(e) = db.optimize() {
+            eprintln!("Failed to settle the database before exit: {}", e);
+        }
+    }
+
+    progress(&app, Step::Exiting, 0);
+    app.exit(0);
+}
+
+fn begin(app: &AppHandle, phase: &mut Phase, mode: ShutdownMode) {
+    *phase = Phase::Exiting;
+    tauri::async_runtime::spawn(run(app.clone(), mode));
+}
+
+// Called when the main window is asked to close; the close itself is always held and
+// the app exits from here once the shutdown has run
+pub fn on_close_requested(app: &AppHandle) {
+    let state = app.state::<ShutdownState>();
+    let Ok(mut phase) = state.phase.lock() else {
+        app.exit(0);
+        return;
+    };
+    match *phase {
+        // Closing again while shutting down is the way out of a stuck cleanup
+        Phase::Exiting => {
+            drop(phase);
+            app.exit(0);
+        }
+        Phase::Open | Phase::Asking => {
+            let idle = app.state::<IdleState>();
+            let seated = idle.seated_tables();
+            if seated.is_empty() {
+                begin(app, &mut phase, ShutdownMode::SitOut);
+            } else {
+                *phase = Phase::Asking;
+                let activity = idle.table_activity();
+                let in_hand: Vec<&String> = seated.iter().filter(|t| activity.get(*t).copied().unwrap_or(false)).collect();
+                let _ = app.emit_all("shutdown_requested", json!({ "seatedTables": seated, "handsInProgress": in_hand }));
+            }
+        }
+    }
+}
+
+// The player's answer to `shutdown_requested`
+#[tauri::command]
+pub async fn confirm_shutdown(app: AppHandle, state: State<'_, ShutdownState>, mode: ShutdownMode) -> Result<(), String> {
+    let mut phase = state.phase.lock().map_err(|_| "Shutdown lock poisoned".to_string())?;
+    match *phase {
+        Phase::Asking => {
+            begin(&app, &mut phase, mode);
+            Ok(())
+        }
+        Phase::Exiting => Err("Already shutting down".to_string()),
+        Phase::Open => Err("The app is not closing".to_string()),
+    }
+}
+
+// The player chose to stay
+#[tauri::command]
+pub async fn cancel_shutdown(state: State<'_, ShutdownState>) -> Result<(), String> {
+    let mut phase = state.phase.lock().map_err(|_| "Shutdown lock poisoned".to_string())?;
+    match *phase {
+        Phase::Exiting => Err("Already shutting down".to_string()),
+        _ => {
+            *phase = Phase::Open;
+            Ok(())
+        }
+    }
+}
+
+// Sent by each window once its table sockets are closed
+#[tauri::command]
+pub async fn report_sockets_closed(window: Window, state: State<'_, ShutdownState>) -> Result<(), String> {
+    let mut closed = state.sockets_closed.lock().map_err(|_| "Shutdown lock poisoned".to_string())?;
+    closed.insert(window.label().to_string());
+    Ok(())
+}
+
+// Exit at once, skipping whatever cleanup is left
+#[tauri::command]
+pub async fn force_exit(app: AppHandle) -> Result<(), String> {
+    app.exit(0);
+    Ok(())
+}
@@ -0,0 +1,323 @@
+// Bet slider math. Given the table state and its betting structure, works out what
+// the player may bet or raise right now: the range, the step the slider moves in
+// and the sizes worth snapping to, so every window sizes bets the same way and
+// within the rules. Amounts are chips added to the pot, as in `LegalAction`. Fixed
+// limit also narrows the legal actions themselves, so `legal_actions` here is what
+// the engine and the bots go by.
+
+use crate::practice::PracticeState;
+use crate::table_state::{LegalAction, TableMirror, BETTING_PHASES};
+use crate::thumbnails::ThumbnailState;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::State;
+
+const POT_FRACTIONS: &[(&str, u32, u32)] = &[("1/3 pot", 1, 3), ("1/2 pot", 1, 2), ("2/3 pot", 2, 3), ("3/4 pot", 3, 4), ("Pot", 1, 1)];
+// Preflop opens and raises, in big blinds, as tenths
+const BLIND_MULTIPLES: &[u32] = &[20, 25, 30, 40];
+// Bet and three raises per street in fixed limit
+const FIXED_LIMIT_CAP: u32 = 4;
+// Chips the table draws with, largest first
+const DENOMINATIONS: &[u32] = &[100_000, 25_000, 5_000, 1_000, 500, 100, 25, 5, 1];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Limit {
+    #[default]
+    #[serde(rename = "no_limit")]
+    No,
+    #[serde(rename = "pot_limit")]
+    Pot,
+    #[serde(rename = "fixed_limit")]
+    Fixed,
+}
+
+impl Limit {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "no_limit" | "no-limit" | "NL" => Some(Limit::No),
+            "pot_limit" | "pot-limit" | "PL" => Some(Limit::Pot),
+            "fixed_limit" | "fixed-limit" | "limit" | "FL" => Some(Limit::Fixed),
+            _ => None,
+        }
+    }
+
+    // Anything unrecognised is played as no limit
+    pub fn parse(name: &str) -> Self {
+        Self::from_name(name).unwrap_or(Limit::No)
+    }
+
+    // Name stored in hand records
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Limit::No => "no_limit",
+            Limit::Pot => "pot_limit",
+            Limit::Fixed => "fixed_limit",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TableRules {
+    pub small_blind: u32,
+    pub big_blind: u32,
+    pub structure: Limit,
+}
+
+impl TableRules {
+    // Blinds and structure from a backend table state, which carries them at the top
+    // level, under `blinds` or under `config` depending on the endpoint
+    pub fn from_state(state: &Value) -> Self {
+        let number = |paths: &[&[&str]]| {
+            paths
+                .iter()
+                .find_map(|path| path.iter().try_fold(state, |v, key| v.get(*key))?.as_u64())
+                .unwrap_or(0) as u32
+        };
+        let structure = [&state["bettingStructure"], &state["config"]["bettingStructure"]]
+            .iter()
+            .find_map(|v| v.as_str())
+            .map(Limit::parse)
+            .unwrap_or(Limit::No);
+        Self {
+            small_blind: number(&[&["smallBlind"], &["blinds", "small"], &["config", "smallBlind"]]),
+            big_blind: number(&[&["bigBlind"], &["blinds", "big"], &["config", "bigBlind"]]),
+            structure,
+        }
+    }
+
+    // Fixed-limit bet size: the small bet preflop and on the flop, the big bet on
+    // the turn and river
+    pub fn fixed_unit(&self, phase: &str) -> u32 {
+        let big_street = matches!(phase, "turn" | "river");
+        if big_street { self.big_blind * 2 } else { self.big_blind }.max(1)
+    }
+
+    // Bets in on this street, counting the big blind as the first one preflop
+    fn fixed_bets(&self, mirror: &TableMirror) -> u32 {
+        mirror.current_bet / self.fixed_unit(&mirror.phase)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapPoint {
+    label: String,
+    amount: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BetSliderModel {
+    table_id: String,
+    structure: Limit,
+    // "bet" or "raise"
+    action: String,
+    to_call: u32,
+    min: u32,
+    max: u32,
+    // Amounts between min and max move in multiples of this; max is always allowed
+    step: u32,
+    snap_points: Vec<SnapPoint>,
+    // Max puts the player all in
+    max_is_all_in: bool,
+}
+
+// What betting looks like on the street just dealt, for the table to show
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BettingHint {
+    table_id: String,
+    structure: Limit,
+    street: String,
+    // Fixed limit: the one size a bet or raise can be, and the cap on bets
+    bet_unit: Option<u32>,
+    cap: Option<u32>,
+    bets_in: u32,
+    // Smallest bet that opens the street
+    min_bet: u32,
+    text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChipCount {
+    denomination: u32,
+    count: u32,
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+// Smallest chip in play, so sizes never need change the table does not have
+fn step(rules: &TableRules) -> u32 {
+    gcd(rules.small_blind, rules.big_blind).max(1)
+}
+
+fn snap(amount: u32, step: u32) -> u32 {
+    (amount + step / 2) / step * step
+}
+
+// Actions for `player_id` under the table's structure. Fixed limit bets and raises
+// exactly one unit, stops at the cap and only allows all in for no more than that.
+pub fn legal_actions(mirror: &TableMirror, rules: &TableRules, player_id: &str) -> Vec<LegalAction> {
+    let mut legal = mirror.legal_actions(player_id);
+    if rules.structure != Limit::Fixed || legal.is_empty() {
+        return legal;
+    }
+    let Some(player) = mirror.player(player_id) else { return legal };
+    let stack = player.chips;
+    let to_call = mirror.current_bet.saturating_sub(player.current_bet);
+    let capped = rules.fixed_bets(mirror) >= FIXED_LIMIT_CAP;
+    let full = to_call + rules.fixed_unit(&mirror.phase);
+
+    legal.retain(|a| match a.action.as_str() {
+        "bet" | "raise" => false,
+        "all_in" => stack <= if capped { to_call } else { full },
+        _ => true,
+    });
+    if !capped && stack > full {
+        let kind = if mirror.current_bet == 0 { "bet" } else { "raise" };
+        let at = legal.iter().position(|a| a.action == "all_in").unwrap_or(legal.len());
+        legal.insert(at, LegalAction::new(kind, full, full));
+    }
+    legal
+}
+
+// The slider for `player_id`, or None when they cannot bet or raise right now
+pub fn slider_model(mirror: &TableMirror, rules: &TableRules, player_id: &str) -> Option<BetSliderModel> {
+    let legal = legal_actions(mirror, rules, player_id);
+    let player = mirror.player(player_id)?;
+    let stack = player.chips;
+    let to_call = mirror.current_bet.saturating_sub(player.current_bet);
+    let sizing = legal.iter().find(|a| a.action == "bet" || a.action == "raise");
+    let action = match sizing {
+        Some(a) => a.action.clone(),
+        // Short of a full raise the only sizing left is all in, over the call
+        None if stack > to_call && legal.iter().any(|a| a.action == "all_in") => {
+            if mirror.current_bet == 0 { "bet" } else { "raise" }.to_string()
+        }
+        None => return None,
+    };
+    let step = step(rules);
+    // Pot once the call is in, which is what a pot-sized raise is measured against
+    let pot_after_call = mirror.pot + to_call;
+
+    let (min, max) = match rules.structure {
+        // One size only: the unit, or all in for less
+        Limit::Fixed => {
+            let amount = sizing.map_or(stack, |a| a.min_amount);
+            (amount, amount)
+        }
+        Limit::Pot => {
+            let max = (to_call + pot_after_call).min(stack);
+            (sizing.map_or(stack, |a| a.min_amount).min(max), max)
+        }
+        Limit::No => (sizing.map_or(stack, |a| a.min_amount), stack),
+    };
+
+    let mut snap_points = Vec::new();
+    if rules.structure != Limit::Fixed {
+        let preflop = matches!(mirror.phase.as_str(), "pre_flop" | "preflop");
+        if preflop && rules.big_blind > 0 {
+            for tenths in BLIND_MULTIPLES {
+                // Raise to the multiple; what goes in is the total minus what is already out
+                let total = rules.big_blind * tenths / 10;
+                let label = format!("{}x", *tenths as f64 / 10.0);
+                snap_points.push(SnapPoint { label, amount: total.saturating_sub(player.current_bet) });
+            }
+        }
+        for (label, num, den) in POT_FRACTIONS {
+            let amount = to_call + pot_after_call * num / den;
+            snap_points.push(SnapPoint { label: label.to_string(), amount: snap(amount, step) });
+        }
+        snap_points.retain(|p| p.amount >= min && p.amount < max);
+        snap_points.sort_by_key(|p| p.amount);
+        snap_points.dedup_by_key(|p| p.amount);
+    }
+    let max_is_all_in = max == stack;
+    snap_points.push(SnapPoint { label: if max_is_all_in { "All in" } else { "Max" }.to_string(), amount: max });
+
+    Some(BetSliderModel {
+        table_id: mirror.table_id.clone(),
+        structure: rules.structure,
+        action,
+        to_call,
+        min,
+        max,
+        step,
+        snap_points,
+        max_is_all_in,
+    })
+}
+
+// Hint for the street `mirror` is on, or None outside the betting rounds
+pub fn street_hint(mirror: &TableMirror, rules: &TableRules) -> Option<BettingHint> {
+    if !BETTING_PHASES.contains(&mirror.phase.as_str()) {
+        return None;
+    }
+    let street = mirror.phase.clone();
+    let (bet_unit, cap, bets_in, min_bet, text) = match rules.structure {
+        Limit::Fixed => {
+            let unit = rules.fixed_unit(&mirror.phase);
+            let bets_in = rules.fixed_bets(mirror);
+            let on_street = if street == "pre_flop" { "preflop".to_string() } else { format!("on the {}", street) };
+            let text = format!(
+                "Fixed limit: bets and raises are {} {}, capped at {} bets",
+                unit, on_street, FIXED_LIMIT_CAP
+            );
+            (Some(unit), Some(FIXED_LIMIT_CAP), bets_in, unit, text)
+        }
+        Limit::Pot => {
+            let text = format!("Pot limit: bets from {} up to the pot of {}", rules.big_blind, mirror.pot);
+            (None, None, 0, rules.big_blind, text)
+        }
+        Limit::No => (None, None, 0, rules.big_blind, format!("No limit: bets from {}", rules.big_blind)),
+    };
+    Some(BettingHint {
+        table_id: mirror.table_id.clone(),
+        structure: rules.structure,
+        street,
+        bet_unit,
+        cap,
+        bets_in,
+        min_bet,
+        text,
+    })
+}
+
+// Fewest chips making up `amount`, largest first
+pub fn chip_breakdown(amount: u32) -> Vec<ChipCount> {
+    let mut left = amount;
+    DENOMINATIONS
+        .iter()
+        .filter_map(|&denomination| {
+            let count = left / denomination;
+            left %= denomination;
+            (count > 0).then_some(ChipCount { denomination, count })
+        })
+        .collect()
+}
+
+// Slider for the hero at `table_id`: the practice table when it is the one asked
+// for, otherwise the latest state reported by the table's view
+#[tauri::command]
+pub async fn get_bet_slider_model(
+    practice: State<'_, PracticeState>,
+    thumbnails: State<'_, ThumbnailState>,
+    table_id: String,
+) -> Result<Option<BetSliderModel>, String> {
+    if let Some((mirror, rules, hero_id)) = practice.slider_source(&table_id)? {
+        return Ok(slider_model(&mirror, &rules, &hero_id));
+    }
+    let (mirror, rules, hero_id) = thumbnails
+        .latest(&table_id)?
+        .ok_or_else(|| format!("No state reported for table {}", table_id))?;
+    let hero_id = hero_id.ok_or_else(|| "The hero is not seated at this table".to_string())?;
+    Ok(slider_model(&mirror, &rules, &hero_id))
+}
+
+#[tauri::command]
+pub async fn get_chip_breakdown(amount: u32) -> Result<Vec<ChipCount>, String> {
+    Ok(chip_breakdown(amount))
+}
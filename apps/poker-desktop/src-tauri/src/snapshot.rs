@@ -0,0 +1,111 @@
+// Table snapshots for bug reports. One JSON document holds everything needed to
+// replay what a table view showed: the local mirror of the table, the frames buffered
+// for it (see event_buffer.rs), the network counters and the measured clock offset.
+// Hole cards are blanked throughout, in the mirror and in every frame, unless the
+// player asks to keep them. The document is written beside the event dumps so it can
+// be attached to a report as is.
+
+use crate::clock::{self, ClockState};
+use crate::event_buffer;
+use crate::metrics;
+use crate::profile::BackendProfile;
+use crate::thumbnails::ThumbnailState;
+use crate::version;
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs;
+use tauri::{AppHandle, Manager};
+
+const DEFAULT_EVENTS: usize = 500;
+const MAX_EVENTS: usize = 5_000;
+// Keys that hold a player's private cards, wherever they appear
+const HOLE_CARD_KEYS: [&str; 3] = ["cards", "holeCards", "hole_cards"];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableSnapshot {
+    path: String,
+    events: usize,
+    has_mirror: bool,
+    hole_cards_included: bool,
+    document: Value,
+}
+
+fn strip_hole_cards(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if HOLE_CARD_KEYS.contains(&key.as_str()) {
+                    *field = Value::Null;
+                } else {
+                    strip_hole_cards(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_hole_cards),
+        _ => {}
+    }
+}
+
+// Everything known locally about `table_id`, with hole cards blanked unless
+// `include_hole_cards` is set, written out as a JSON file. `max_events` caps the
+// buffered frames taken, newest kept, 500 unless given.
+#[tauri::command]
+pub async fn export_table_snapshot(
+    app: AppHandle,
+    table_id: String,
+    include_hole_cards: Option<bool>,
+    max_events: Option<usize>,
+) -> Result<TableSnapshot, String> {
+    let include_hole_cards = include_hole_cards.unwrap_or(false);
+    let latest = app.state::<ThumbnailState>().latest(&table_id)?;
+    let events = event_buffer::recent_frames(&table_id, max_events.unwrap_or(DEFAULT_EVENTS).clamp(1, MAX_EVENTS))?;
+    if latest.is_none() && events.is_empty() {
+        return Err(format!("Nothing is known locally about table {}", table_id));
+    }
+
+    let profile = app.state::<BackendProfile>();
+    let (mirror, rules, hero_id) = match latest {
+        Some((mirror, rules, hero_id)) => (
+            serde_json::to_value(&mirror).map_err(|e| e.to_string())?,
+            json!({
+                "smallBlind": rules.small_blind,
+                "bigBlind": rules.big_blind,
+                "structure": rules.structure,
+            }),
+            hero_id,
+        ),
+        None => (Value::Null, Value::Null, None),
+    };
+    let has_mirror = !mirror.is_null();
+    let event_count = events.len();
+    let mut document = json!({
+        "generatedAt": Utc::now(),
+        "clientVersion": version::CLIENT_VERSION,
+        "backend": { "name": profile.name, "apiUrl": profile.api_url, "wsUrl": profile.ws_url },
+        "tableId": table_id,
+        "heroId": hero_id,
+        "holeCardsIncluded": include_hole_cards,
+        "mirror": mirror,
+        "rules": rules,
+        "events": events,
+        "network": metrics::snapshot(),
+        "clock": clock::status(&app.state::<ClockState>()),
+    });
+    if !include_hole_cards {
+        strip_hole_cards(&mut document["mirror"]);
+        strip_hole_cards(&mut document["events"]);
+    }
+
+    let path = event_buffer::report_path(&table_id, "snapshot.json")?;
+    let data = serde_json::to_vec_pretty(&document).map_err(|e| e.to_string())?;
+    fs::write(&path, data).map_err(|e| format!("Failed to write snapshot: {}", e))?;
+    Ok(TableSnapshot {
+        path: path.to_string_lossy().into_owned(),
+        events: event_count,
+        has_mirror,
+        hole_cards_included: include_hole_cards,
+        document,
+    })
+}
@@ -0,0 +1,531 @@
+// Bridge to a locally installed solver over a UPI-style line protocol on
+// stdin/stdout. Only binaries the user has allowlisted can be launched, and only
+// while their SHA-256 still matches the one recorded when they were allowed. Each
+// solve copies the binary into the app's own directory, checks the copy and runs
+// the copy, so a binary swapped in after the check never runs.
+//
+// Each command is one line and the reply ends with `<command> ok!`; a line starting
+// with `ERROR` fails the command. `show_strategy` replies with `<action> <frequency>`
+// lines, where the action may carry an amount (`raise:120`).
+//
+// The solver runs sandboxed. On Linux that is bubblewrap: no network and no view of
+// the file system beyond the system directories, its working directory and its own
+// binary. On macOS it is sandbox-exec: no network, no writes outside its working
+// directory and no file contents read from the home directory other than those two.
+// Where neither is available (Windows, or Linux without bubblewrap installed) a
+// binary only runs if the player allowed it to run unsandboxed. On top of that the
+// process gets an empty environment, discarded stderr, a timeout on every reply and
+// bounded output, and it is killed when the bridge drops it.
+
+use crate::accounts;
+use crate::cards::Card;
+use crate::db::{Database, Db};
+use crate::history::get_hand_by_id;
+use crate::table_state::TableMirror;
+use crate::trainer::{board_len, hero_cards, hero_decisions};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, State};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+
+const KEY_CONFIG: &str = "solver.config";
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_SOLVE_SECS: u32 = 20;
+const MAX_SOLVE_SECS: u32 = 600;
+const MAX_LINE_BYTES: u64 = 64 * 1024;
+const MAX_REPLY_LINES: usize = 10_000;
+// Mounted read-only inside the bubblewrap sandbox, where present
+const SYSTEM_DIRS: &[&str] = &["/usr", "/lib", "/lib64", "/lib32", "/bin", "/etc"];
+const BUBBLEWRAP: &[&str] = &["/usr/bin/bwrap", "/bin/bwrap"];
+const SANDBOX_EXEC: &str = "/usr/bin/sandbox-exec";
+
+fn default_timeout_secs() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SolverBinary {
+    name: String,
+    path: String,
+    sha256: String,
+    #[serde(default)]
+    args: Vec<String>,
+    // Run without a sandbox where none is available, as the player chose
+    #[serde(default)]
+    unsandboxed: bool,
+}
+
+// How the solver process is confined
+enum Sandbox {
+    Bubblewrap(PathBuf),
+    // sandbox-exec
+    Seatbelt(PathBuf),
+    None,
+}
+
+impl Sandbox {
+    fn available() -> Self {
+        if cfg!(target_os = "linux") {
+            if let Some(bwrap) = BUBBLEWRAP.iter().map(Path::new).find(|p| p.is_file()) {
+                return Sandbox::Bubblewrap(bwrap.to_path_buf());
+            }
+        }
+        if cfg!(target_os = "macos") && Path::new(SANDBOX_EXEC).is_file() {
+            return Sandbox::Seatbelt(PathBuf::from(SANDBOX_EXEC));
+        }
+        Sandbox::None
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SolverConfig {
+    binaries: Vec<SolverBinary>,
+    // Longest wait for any single reply other than the solve itself
+    #[serde(default = "default_timeout_secs")]
+    timeout_secs: u64,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        Self { binaries: Vec::new(), timeout_secs: DEFAULT_TIMEOUT_SECS }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SolverSpot {
+    street: String,
+    board: Vec<String>,
+    hero_cards: Vec<String>,
+    pot: u32,
+    to_call: u32,
+    effective_stack: u32,
+    #[serde(default)]
+    oop_range: Option<String>,
+    #[serde(default)]
+    ip_range: Option<String>,
+}
+
+// Where to take the spot from: the live table, a stored hand, or given directly
+#[derive(Debug, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum SpotSource {
+    #[serde(rename_all = "camelCase")]
+    Table { table: TableMirror, hero_id: String },
+    // Defaults to the hero's last decision in the hand
+    #[serde(rename_all = "camelCase")]
+    Hand { hand_id: String, action_index: Option<usize> },
+    Manual(SolverSpot),
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StrategyFrequency {
+    action: String,
+    amount: Option<u32>,
+    frequency: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SolverResult {
+    binary: String,
+    spot: SolverSpot,
+    strategy: Vec<StrategyFrequency>,
+    elapsed_ms: u64,
+}
+
+// Only one solve runs at a time; solvers use every core they can get
+#[derive(Default)]
+pub struct SolverState {
+    busy: tokio::sync::Mutex<()>,
+}
+
+fn load_config(db: &Database) -> Result<SolverConfig, String> {
+    match db.get_value(KEY_CONFIG)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid solver config: {}", e)),
+        None => Ok(SolverConfig::default()),
+    }
+}
+
+fn save_config(db: &Database, config: &SolverConfig) -> Result<(), String> {
+    let data = serde_json::to_string(config).map_err(|e| e.to_string())?;
+    db.set_value(KEY_CONFIG, &data)
+}
+
+fn file_sha256(path: &Path) -> Result<String, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(Sha256::digest(&bytes).iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+async fn hash_file(path: PathBuf) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || file_sha256(&path)).await.map_err(|e| e.to_string())?
+}
+
+// Copy an allowlisted binary into `bin_dir` and check the copy against the pinned
+// hash; the copy is what gets started
+fn private_copy(binary: &SolverBinary, bin_dir: &Path) -> Result<PathBuf, String> {
+    if !binary.sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Solver '{}' has an invalid pinned hash", binary.name));
+    }
+    std::fs::create_dir_all(bin_dir).map_err(|e| format!("Failed to create solver directory: {}", e))?;
+    let copy = bin_dir.join(format!("{}{}", binary.sha256, std::env::consts::EXE_SUFFIX));
+    std::fs::copy(&binary.path, &copy).map_err(|e| format!("Failed to copy solver '{}': {}", binary.name, e))?;
+    if file_sha256(&copy)? != binary.sha256 {
+        let _ = std::fs::remove_file(&copy);
+        return Err(format!("Solver '{}' has changed since it was allowed; allow it again to use it", binary.name));
+    }
+    Ok(copy)
+}
+
+// Rules for sandbox-exec, which reads later rules over earlier ones
+fn sandbox_exec_profile(program: &Path, work_dir: &Path) -> Result<String, String> {
+    let home = std::env::var_os("HOME").map(PathBuf::from).ok_or_else(|| "HOME is not set".to_string())?;
+    let quoted = |path: &Path| -> Result<String, String> {
+        let text = path.to_str().ok_or_else(|| format!("{} is not valid UTF-8", path.display()))?;
+        if text.contains(['"', '\\']) {
+            return Err(format!("{} cannot be used in a sandbox profile", text));
+        }
+        Ok(format!("\"{}\"", text))
+    };
+    let (program, work_dir, home) = (quoted(program)?, quoted(work_dir)?, quoted(&home)?);
+    Ok(format!(
+        "(version 1)
+         (allow default)
+         (deny network*)
+         (deny file-write*)
+         (allow file-write* (subpath {work_dir}) (literal \"/dev/null\"))
+         (deny file-read-data (subpath {home}))
+         (allow file-read-data (subpath {work_dir}) (literal {program}))"
+    ))
+}
+
+fn cards_text(cards: &[String]) -> Result<String, String> {
+    cards
+        .iter()
+        .map(|c| Card::parse(c).map(|card| card.to_string()).ok_or_else(|| format!("Invalid card '{}'", c)))
+        .collect()
+}
+
+// Ranges are sent verbatim, so anything that could end the line or start a new
+// command is rejected
+fn validate_range(range: &str) -> Result<&str, String> {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || " ,+-:.".contains(c);
+    if range.len() > 8192 || !range.chars().all(allowed) {
+        return Err("Range contains unsupported characters".to_string());
+    }
+    Ok(range)
+}
+
+fn spot_from_table(table: &TableMirror, hero_id: &str) -> Result<SolverSpot, String> {
+    let hero = table.player(hero_id).ok_or_else(|| "Hero is not seated at this table".to_string())?;
+    let hero_cards: Vec<String> = hero
+        .cards
+        .as_ref()
+        .map(|cards| cards.iter().filter_map(Card::from_wire).map(|c| c.to_string()).collect())
+        .unwrap_or_default();
+    if hero_cards.len() != 2 {
+        return Err("Hero cards are not known for this table".to_string());
+    }
+    let biggest = table
+        .players
+        .iter()
+        .filter(|p| p.id != hero_id && !p.is_folded)
+        .map(|p| p.chips)
+        .max()
+        .unwrap_or(0);
+
+    Ok(SolverSpot {
+        street: table.phase.clone(),
+        board: table.community_cards.iter().filter_map(Card::from_wire).map(|c| c.to_string()).collect(),
+        hero_cards,
+        pot: table.pot,
+        to_call: table.current_bet.saturating_sub(hero.current_bet),
+        effective_stack: hero.chips.min(biggest),
+        oop_range: None,
+        ip_range: None,
+    })
+}
+
+fn spot_from_hand(db: &Database, hand_id: &str, action_index: Option<usize>) -> Result<SolverSpot, String> {
+    let hand = db
+        .with_conn(|conn| get_hand_by_id(conn, hand_id))?
+        .ok_or_else(|| format!("Hand {} not found", hand_id))?;
+    let hero_id = hand.hero_id.clone().ok_or_else(|| "Hand has no hero".to_string())?;
+    let hole = hero_cards(&hand).ok_or_else(|| "Hero cards are not recorded for this hand".to_string())?;
+
+    let decisions = hero_decisions(&hand, &hero_id);
+    let decision = match action_index {
+        Some(index) => decisions.into_iter().find(|d| d.index == index),
+        None => decisions.into_iter().last(),
+    }
+    .ok_or_else(|| "No hero decision at that point in the hand".to_string())?;
+
+    Ok(SolverSpot {
+        board: hand.board.iter().take(board_len(&decision.street)).cloned().collect(),
+        street: decision.street,
+        hero_cards: hole.iter().map(|c| c.to_string()).collect(),
+        pot: decision.pot,
+        to_call: decision.to_call,
+        effective_stack: decision.effective_stack,
+        oop_range: None,
+        ip_range: None,
+    })
+}
+
+struct SolverProcess {
+    // Held so the process is killed when the bridge is dropped
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    timeout: Duration,
+}
+
+impl SolverProcess {
+    fn spawn(program: &Path, args: &[String], work_dir: &Path, sandbox: &Sandbox, timeout: Duration) -> Result<Self, String> {
+        let mut command = match sandbox {
+            Sandbox::Bubblewrap(bwrap) => {
+                let mut command = Command::new(bwrap);
+                command.args(["--unshare-all", "--die-with-parent", "--new-session"]);
+                for dir in SYSTEM_DIRS {
+                    command.args(["--ro-bind-try", dir, dir]);
+                }
+                command.args(["--proc", "/proc", "--dev", "/dev", "--tmpfs", "/tmp"]);
+                command.arg("--bind").arg(work_dir).arg("/work");
+                command.arg("--ro-bind").arg(program).arg("/solver");
+                command.args(["--chdir", "/work", "/solver"]);
+                command
+            }
+            Sandbox::Seatbelt(sandbox_exec) => {
+                let mut command = Command::new(sandbox_exec);
+                command.arg("-p").arg(sandbox_exec_profile(program, work_dir)?).arg(program);
+                command
+            }
+            Sandbox::None => Command::new(program),
+        };
+        command
+            .args(args)
+            .env_clear()
+            .current_dir(work_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+        #[cfg(windows)]
+        command.creation_flags(0x0800_0000); // CREATE_NO_WINDOW
+
+        let mut child = command.spawn().map_err(|e| format!("Failed to start solver: {}", e))?;
+        let stdin = child.stdin.take().ok_or_else(|| "Solver stdin unavailable".to_string())?;
+        let stdout = child.stdout.take().ok_or_else(|| "Solver stdout unavailable".to_string())?;
+        Ok(Self { _child: child, stdin, stdout: BufReader::new(stdout), timeout })
+    }
+
+    async fn read_line(&mut self) -> Result<String, String> {
+        let mut line = String::new();
+        let mut limited = (&mut self.stdout).take(MAX_LINE_BYTES);
+        let bytes = tokio::time::timeout(self.timeout, limited.read_line(&mut line))
+            .await
+            .map_err(|_| "Solver did not respond in time".to_string())?
+            .map_err(|e| format!("Failed to read from solver: {}", e))?;
+        if bytes == 0 {
+            return Err("Solver exited unexpectedly".to_string());
+        }
+        if !line.ends_with('\n') && bytes as u64 >= MAX_LINE_BYTES {
+            return Err("Solver output line too long".to_string());
+        }
+        Ok(line.trim_end().to_string())
+    }
+
+    // Send one command and collect its reply lines, without the terminating `ok!`
+    async fn command(&mut self, line: &str) -> Result<Vec<String>, String> {
+        let verb = line.split_whitespace().next().unwrap_or_default().to_string();
+        self.stdin
+            .write_all(format!("{}\n", line).as_bytes())
+            .await
+            .map_err(|e| format!("Failed to write to solver: {}", e))?;
+        self.stdin.flush().await.map_err(|e| format!("Failed to write to solver: {}", e))?;
+
+        let done = format!("{} ok!", verb);
+        let mut reply = Vec::new();
+        loop {
+            let response = self.read_line().await?;
+            if response == done {
+                return Ok(reply);
+            }
+            if let Some(message) = response.strip_prefix("ERROR") {
+                return Err(format!("Solver rejected '{}': {}", verb, message.trim_start_matches(':').trim()));
+            }
+            reply.push(response);
+            if reply.len() > MAX_REPLY_LINES {
+                return Err("Solver reply too long".to_string());
+            }
+        }
+    }
+}
+
+fn parse_strategy(lines: &[String]) -> Vec<StrategyFrequency> {
+    lines
+        .iter()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let action = parts.next()?;
+            let frequency: f64 = parts.next()?.parse().ok()?;
+            let (action, amount) = match action.split_once(':') {
+                Some((name, amount)) => (name, amount.parse().ok()),
+                None => (action, None),
+            };
+            Some(StrategyFrequency { action: action.to_string(), amount, frequency })
+        })
+        .collect()
+}
+
+async fn run_solver(
+    program: &Path,
+    binary: &SolverBinary,
+    work_dir: &Path,
+    timeout: Duration,
+    spot: &SolverSpot,
+    solve_secs: u32,
+) -> Result<Vec<StrategyFrequency>, String> {
+    let sandbox = Sandbox::available();
+    if matches!(sandbox, Sandbox::None) && !binary.unsandboxed {
+        return Err(format!(
+            "No sandbox is available on this system; allow solver '{}' to run unsandboxed to use it",
+            binary.name
+        ));
+    }
+    let mut solver = SolverProcess::spawn(program, &binary.args, work_dir, &sandbox, timeout)?;
+    solver.command("is_ready").await?;
+    solver.command(&format!("set_board {}", cards_text(&spot.board)?)).await?;
+    solver.command(&format!("set_pot {}", spot.pot)).await?;
+    solver.command(&format!("set_to_call {}", spot.to_call)).await?;
+    solver.command(&format!("set_eff_stack {}", spot.effective_stack)).await?;
+    if let Some(range) = &spot.oop_range {
+        solver.command(&format!("set_range OOP {}", validate_range(range)?)).await?;
+    }
+    if let Some(range) = &spot.ip_range {
+        solver.command(&format!("set_range IP {}", validate_range(range)?)).await?;
+    }
+    solver.command(&format!("set_hand {}", cards_text(&spot.hero_cards)?)).await?;
+
+    // The solve itself gets its own budget on top of the per-reply timeout
+    solver.timeout = timeout + Duration::from_secs(solve_secs as u64);
+    solver.command(&format!("go {}", solve_secs)).await?;
+    solver.timeout = timeout;
+
+    let strategy = parse_strategy(&solver.command("show_strategy").await?);
+    let _ = solver.command("exit").await;
+    Ok(strategy)
+}
+
+#[tauri::command]
+pub async fn get_solver_config(db: Db<'_>) -> Result<SolverConfig, String> {
+    load_config(&db)
+}
+
+// Allowlist a solver binary, pinning its current hash. `unsandboxed` lets it run
+// where no sandbox is available.
+#[tauri::command]
+pub async fn allow_solver_binary(
+    app: AppHandle,
+    db: Db<'_>,
+    name: String,
+    path: String,
+    args: Option<Vec<String>>,
+    unsandboxed: Option<bool>,
+) -> Result<SolverConfig, String> {
+    let unsandboxed = unsandboxed.unwrap_or(false);
+    let result = request_allow_binary(&db, &name, &path, args.unwrap_or_default(), unsandboxed).await;
+    crate::audit::record(
+        &app,
+        "solver_binary_allowed",
+        json!({ "name": name, "path": path, "unsandboxed": unsandboxed }),
+        &result,
+    );
+    result
+}
+
+async fn request_allow_binary(
+    db: &Database,
+    name: &str,
+    path: &str,
+    args: Vec<String>,
+    unsandboxed: bool,
+) -> Result<SolverConfig, String> {
+    let file = PathBuf::from(path);
+    if !file.is_absolute() || !file.is_file() {
+        return Err("Solver path must be an absolute path to an existing file".to_string());
+    }
+    let sha256 = hash_file(file).await?;
+
+    let mut config = load_config(db)?;
+    config.binaries.retain(|b| b.name != name);
+    config.binaries.push(SolverBinary { name: name.to_string(), path: path.to_string(), sha256, args, unsandboxed });
+    save_config(db, &config)?;
+    Ok(config)
+}
+
+#[tauri::command]
+pub async fn remove_solver_binary(app: AppHandle, db: Db<'_>, name: String) -> Result<SolverConfig, String> {
+    let result = load_config(&db).and_then(|mut config| {
+        config.binaries.retain(|b| b.name != name);
+        save_config(&db, &config).map(|_| config)
+    });
+    crate::audit::record(&app, "solver_binary_removed", json!({ "name": name }), &result);
+    result
+}
+
+#[tauri::command]
+pub async fn solve_spot(
+    db: Db<'_>,
+    state: State<'_, SolverState>,
+    binary: String,
+    spot: SpotSource,
+    solve_secs: Option<u32>,
+) -> Result<SolverResult, String> {
+    let _busy = state.busy.try_lock().map_err(|_| "A solve is already running".to_string())?;
+
+    let config = load_config(&db)?;
+    let allowed = config
+        .binaries
+        .iter()
+        .find(|b| b.name == binary)
+        .ok_or_else(|| format!("Solver '{}' is not allowlisted", binary))?;
+
+    let spot = match spot {
+        SpotSource::Table { table, hero_id } => spot_from_table(&table, &hero_id)?,
+        SpotSource::Hand { hand_id, action_index } => spot_from_hand(&db, &hand_id, action_index)?,
+        SpotSource::Manual(spot) => spot,
+    };
+
+    // The binaries live apart from the working directory, which the solver may write to
+    let data_dir = accounts::data_dir()?;
+    let work_dir = data_dir.join("solver");
+    std::fs::create_dir_all(&work_dir).map_err(|e| format!("Failed to create solver directory: {}", e))?;
+    let program = {
+        let (allowed, bin_dir) = (allowed.clone(), data_dir.join("solver-bin"));
+        tauri::async_runtime::spawn_blocking(move || private_copy(&allowed, &bin_dir))
+            .await
+            .map_err(|e| e.to_string())??
+    };
+
+    let started = Instant::now();
+    let solve_secs = solve_secs.unwrap_or(DEFAULT_SOLVE_SECS).clamp(1, MAX_SOLVE_SECS);
+    let timeout = Duration::from_secs(config.timeout_secs);
+    let strategy = run_solver(&program, allowed, &work_dir, timeout, &spot, solve_secs).await?;
+
+    Ok(SolverResult {
+        binary,
+        spot,
+        strategy,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    })
+}
@@ -0,0 +1,92 @@
+// Taking a seat at a table being watched. Rather than leaving and rejoining through
+// the lobby, the buy-in is made over REST and the view sends the returned
+// `join_table` frame on a table connection of its own, keeping the table state it
+// has synced, which is returned alongside. The delayed spectator connection (see
+// spectator_delay.rs) is closed with the seat, so the seated view never reads
+// frames early.
+
+use crate::audit;
+use crate::claims;
+use crate::db::Database;
+use crate::ledger;
+use crate::lobby;
+use crate::maintenance;
+use crate::spectator_delay::SpectatorDelayState;
+use crate::table_state::TableMirror;
+use crate::thumbnails::ThumbnailState;
+use crate::tilt;
+use crate::ws::WsMessage;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager, State};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SitHere {
+    table_id: String,
+    seat: u8,
+    buy_in: u32,
+    // Send on the table connection to take the seat
+    join: WsMessage,
+    // State synced while spectating, to keep rendering from without a resync
+    mirror: TableMirror,
+    // What the backend returned for the buy-in
+    buy_in_result: Value,
+}
+
+fn check_seat(mirror: &TableMirror, player_id: &str, seat: u8) -> Result<(), String> {
+    if mirror.player(player_id).is_some() {
+        return Err("You are already seated at this table".to_string());
+    }
+    if mirror.players.iter().any(|p| p.position.as_ref().is_some_and(|pos| pos.seat == seat)) {
+        return Err(format!("Seat {} is taken", seat));
+    }
+    Ok(())
+}
+
+// Sit down at `seat` of a table being spectated, buying in for `buy_in`
+#[tauri::command]
+pub async fn sit_here(
+    app: AppHandle,
+    thumbnails: State<'_, ThumbnailState>,
+    api_url: String,
+    table_id: String,
+    seat: u8,
+    buy_in: u32,
+    username: String,
+) -> Result<SitHere, String> {
+    if maintenance::is_draining(&app) {
+        return Err("Seating is paused until maintenance is over".to_string());
+    }
+    tilt::check_join(&app)?;
+    let claims = claims::current()?;
+    let (mirror, _, _) = thumbnails
+        .latest(&table_id)?
+        .ok_or_else(|| format!("Table {} is not being spectated", table_id))?;
+    check_seat(&mirror, &claims.user_id, seat)?;
+
+    ledger::before_buy_in(&app, &table_id).await;
+    let result = crate::request_join_table(&api_url, &table_id, buy_in).await;
+    audit::record(&app, "sit_here", json!({ "tableId": table_id, "seat": seat, "buyIn": buy_in }), &result);
+    let buy_in_result = result?;
+    ledger::record_buy_in(&app, &table_id, buy_in);
+    if let Some(db) = app.try_state::<Database>() {
+        if let Err(e) = lobby::record_join(&db, &table_id) {
+            eprintln!("Failed to record recent table: {}", e);
+        }
+    }
+    thumbnails.set_hero(&table_id, &claims.user_id)?;
+    app.state::<SpectatorDelayState>().end(&table_id)?;
+
+    let join = WsMessage::new(
+        "join_table",
+        json!({
+            "tableId": table_id,
+            "playerId": claims.user_id,
+            "username": username,
+            "chipCount": buy_in,
+            "seatIndex": seat,
+        }),
+    );
+    Ok(SitHere { table_id, seat, buy_in, join, mirror, buy_in_result })
+}
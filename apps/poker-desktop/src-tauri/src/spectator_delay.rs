@@ -0,0 +1,229 @@
+// Delayed spectating. The backend holds a table's events back from spectators for the
+// table's mandated delay; when friends of the player are seated the client enforces
+// a delay of its own as well, so a slip on the server side does not let a friend be
+// watched live. Subscribing opens the spectator connection here rather than in the
+// view, sends the handshake that tells the backend the delay in force, and holds
+// every frame received; the view only ever gets them, in order, as `spectator_event`
+// once the delay has passed. The connection is kept off the event bus and the event
+// buffer, which would hand frames on early. A friend sitting down later raises the
+// delay; it never goes down while the subscription lasts. Taking a seat (see
+// spectate.rs) ends the subscription and closes the connection.
+
+use crate::db::Db;
+use crate::messages;
+use crate::profile::BackendProfile;
+use crate::thumbnails::ThumbnailState;
+use crate::ws::{self, TableSocket, WsMessage};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::mpsc;
+
+// Least delay while a friend is seated
+const FRIEND_DELAY_SECS: u32 = 30;
+const MAX_DELAY_SECS: u32 = 15 * 60;
+// Frames held per table before the subscription is given up
+const MAX_QUEUED: usize = 10_000;
+// How often an idle worker looks for new frames
+const IDLE_POLL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpectatorSubscription {
+    table_id: String,
+    delay_secs: u32,
+    friends_seated: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpectatorClosed {
+    table_id: String,
+    reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DelayedEvent {
+    table_id: String,
+    frame: WsMessage,
+    received_at: DateTime<Utc>,
+    delay_secs: u32,
+}
+
+struct Subscription {
+    // Tells a worker left from an earlier subscription to the table to stop
+    generation: u64,
+    delay_secs: u32,
+    friends: HashSet<String>,
+    friends_seated: bool,
+    queue: VecDeque<(Instant, DelayedEvent)>,
+    // Dropped with the subscription, which closes the connection
+    _socket: TableSocket,
+    // The backend closed the connection; ends once the queue has drained
+    closed: bool,
+}
+
+#[derive(Default)]
+pub struct SpectatorDelayState {
+    tables: Mutex<HashMap<String, Subscription>>,
+    next_generation: Mutex<u64>,
+}
+
+impl SpectatorDelayState {
+    // Drop the subscription and whatever it still holds
+    pub fn end(&self, table_id: &str) -> Result<bool, String> {
+        let mut tables = self.tables.lock().map_err(|_| "Spectator delay lock poisoned".to_string())?;
+        Ok(tables.remove(table_id).is_some())
+    }
+}
+
+// Player ids a frame says sat down
+fn seated_ids(message: &WsMessage) -> Vec<String> {
+    let payload = &message.payload;
+    let mut ids = Vec::new();
+    if message.kind == "player_joined" {
+        ids.extend(payload["player"]["id"].as_str().map(String::from));
+    }
+    if let Some(players) = payload["players"].as_array() {
+        ids.extend(players.iter().filter_map(|p| p["id"].as_str().map(String::from)));
+    }
+    ids
+}
+
+fn closed(app: &AppHandle, table_id: &str, reason: &str) {
+    let _ = app.emit_all("spectator_closed", SpectatorClosed { table_id: table_id.to_string(), reason: reason.to_string() });
+}
+
+// Hold a frame of `table_id` until its delay is up
+fn hold(subscription: &mut Subscription, table_id: &str, message: WsMessage) {
+    // The backend confirms its own delay, and friends may sit down while watching
+    if let Some(server) = message.payload["delaySecs"].as_u64() {
+        subscription.delay_secs = subscription.delay_secs.max((server as u32).min(MAX_DELAY_SECS));
+    }
+    if !subscription.friends_seated && seated_ids(&message).iter().any(|id| subscription.friends.contains(id)) {
+        subscription.friends_seated = true;
+        subscription.delay_secs = subscription.delay_secs.max(FRIEND_DELAY_SECS);
+    }
+
+    let delay_secs = subscription.delay_secs;
+    let event = DelayedEvent { table_id: table_id.to_string(), frame: message, received_at: Utc::now(), delay_secs };
+    subscription.queue.push_back((Instant::now() + Duration::from_secs(delay_secs as u64), event));
+}
+
+// Hold every frame the spectator connection receives, until it closes or the
+// subscription ends
+fn spawn_reader(app: &AppHandle, table_id: String, generation: u64, mut incoming: mpsc::UnboundedReceiver<WsMessage>) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        while let Some(message) = incoming.recv().await {
+            let state = app.state::<SpectatorDelayState>();
+            let Ok(mut tables) = state.tables.lock() else { return };
+            let Some(subscription) = tables.get_mut(&table_id).filter(|s| s.generation == generation) else {
+                return;
+            };
+            if subscription.queue.len() >= MAX_QUEUED {
+                tables.remove(&table_id);
+                closed(&app, &table_id, "Too many delayed events held");
+                return;
+            }
+            hold(subscription, &table_id, message);
+        }
+        let state = app.state::<SpectatorDelayState>();
+        let Ok(mut tables) = state.tables.lock() else { return };
+        if let Some(subscription) = tables.get_mut(&table_id).filter(|s| s.generation == generation) {
+            subscription.closed = true;
+        }
+    });
+}
+
+// Hand frames to the view as they come due, until the subscription ends
+fn spawn_worker(app: &AppHandle, table_id: String, generation: u64) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let wait = {
+                let state = app.state::<SpectatorDelayState>();
+                let Ok(mut tables) = state.tables.lock() else { return };
+                let Some(subscription) = tables.get_mut(&table_id).filter(|s| s.generation == generation) else {
+                    return;
+                };
+                let now = Instant::now();
+                while subscription.queue.front().is_some_and(|(due, _)| *due <= now) {
+                    if let Some((_, event)) = subscription.queue.pop_front() {
+                        let _ = app.emit_all("spectator_event", event);
+                    }
+                }
+                if subscription.closed && subscription.queue.is_empty() {
+                    tables.remove(&table_id);
+                    closed(&app, &table_id, "The server closed the connection");
+                    return;
+                }
+                subscription.queue.front().map_or(IDLE_POLL, |(due, _)| due.duration_since(now).min(IDLE_POLL))
+            };
+            tokio::time::sleep(wait).await;
+        }
+    });
+}
+
+// Spectate `table_id` with the delay in force: the backend's `server_delay_secs`,
+// raised to the friend delay when one of `friend_ids` or of the player's message
+// contacts is seated
+#[tauri::command]
+pub async fn subscribe_spectator(
+    app: AppHandle,
+    state: State<'_, SpectatorDelayState>,
+    db: Db<'_>,
+    thumbnails: State<'_, ThumbnailState>,
+    table_id: String,
+    server_delay_secs: Option<u32>,
+    friend_ids: Option<Vec<String>>,
+) -> Result<SpectatorSubscription, String> {
+    let mut friends: HashSet<String> = friend_ids.unwrap_or_default().into_iter().collect();
+    friends.extend(messages::contact_ids(&db)?);
+    let friends_seated = thumbnails
+        .latest(&table_id)?
+        .is_some_and(|(mirror, _, _)| mirror.players.iter().any(|p| friends.contains(&p.id)));
+    let mut delay_secs = server_delay_secs.unwrap_or(0).min(MAX_DELAY_SECS);
+    if friends_seated {
+        delay_secs = delay_secs.max(FRIEND_DELAY_SECS);
+    }
+
+    let token = crate::get_token_from_keyring().unwrap_or_default();
+    let ws_url = app.state::<BackendProfile>().ws_url.clone();
+    let (socket, incoming) = ws::connect(&ws::spectator_url(&ws_url, &token, &table_id)).await?;
+    socket.send(WsMessage::new("spectate_table", json!({ "tableId": table_id, "delaySecs": delay_secs })))?;
+
+    let generation = {
+        let mut next = state.next_generation.lock().map_err(|_| "Spectator delay lock poisoned".to_string())?;
+        *next += 1;
+        *next
+    };
+    state.tables.lock().map_err(|_| "Spectator delay lock poisoned".to_string())?.insert(
+        table_id.clone(),
+        Subscription {
+            generation,
+            delay_secs,
+            friends,
+            friends_seated,
+            queue: VecDeque::new(),
+            _socket: socket,
+            closed: false,
+        },
+    );
+    spawn_reader(&app, table_id.clone(), generation, incoming);
+    spawn_worker(&app, table_id.clone(), generation);
+
+    Ok(SpectatorSubscription { table_id, delay_secs, friends_seated })
+}
+
+// Called when a spectator view closes; frames still held are dropped and the
+// connection closed
+#[tauri::command]
+pub async fn unsubscribe_spectator(state: State<'_, SpectatorDelayState>, table_id: String) -> Result<(), String> {
+    state.end(&table_id).map(|_| ())
+}
@@ -0,0 +1,145 @@
+// Game-speed variants. Turbo and hyper tables shorten the action clock and time bank
+// and speed up blind levels; fast-fold tables are seats in a pool rather than a
+// fixed table, and a player who folds is moved straight on to a new hand at another
+// table of the pool. The backend announces each move with `pool_reassigned`; the
+// table view hands it over here so the local trackers follow the player to the new
+// table and it gets back the frame to join it with.
+
+use crate::claims;
+use crate::idle::IdleState;
+use crate::ledger;
+use crate::thumbnails::ThumbnailState;
+use crate::ws::WsMessage;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Manager, State};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameSpeed {
+    #[default]
+    Regular,
+    Turbo,
+    Hyper,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimingProfile {
+    game_speed: GameSpeed,
+    fast_fold: bool,
+    // Seconds to act before the time bank starts running
+    pub action_secs: u32,
+    // Full time bank, and what is added back every `time_bank_refill_hands` hands
+    time_bank_secs: u32,
+    time_bank_refill_secs: u32,
+    time_bank_refill_hands: u32,
+    // Pause between the end of a hand and the next deal
+    between_hands_ms: u32,
+    // Tournament blind levels
+    blind_level_mins: u32,
+}
+
+// Timers for a table of the given format; `time_bank` is the table's configured bank
+// and is capped for the faster formats
+pub fn timing(game_speed: GameSpeed, fast_fold: bool, time_bank: u32) -> TimingProfile {
+    let (action_secs, bank_cap, refill_secs, between_hands_ms, blind_level_mins) = match game_speed {
+        GameSpeed::Regular => (30, 120, 10, 3000, 15),
+        GameSpeed::Turbo => (15, 60, 5, 2000, 6),
+        GameSpeed::Hyper => (8, 30, 3, 1500, 3),
+    };
+    // Fast-fold deals the next hand at once and has a short clock whatever the speed
+    let (action_secs, between_hands_ms) = if fast_fold {
+        (action_secs.min(12), 0)
+    } else {
+        (action_secs, between_hands_ms)
+    };
+    let time_bank_secs = if time_bank == 0 { bank_cap } else { time_bank.min(bank_cap) };
+    TimingProfile {
+        game_speed,
+        fast_fold,
+        action_secs,
+        time_bank_secs,
+        time_bank_refill_secs: refill_secs,
+        time_bank_refill_hands: if fast_fold { 25 } else { 10 },
+        between_hands_ms,
+        blind_level_mins,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolReassignment {
+    pub pool_id: String,
+    pub player_id: String,
+    pub from_table_id: String,
+    pub to_table_id: String,
+    #[serde(default)]
+    pub seat: Option<u8>,
+    #[serde(default)]
+    pub chip_count: u32,
+}
+
+impl PoolReassignment {
+    pub fn parse(message: &WsMessage) -> Option<Self> {
+        if message.kind != "pool_reassigned" {
+            return None;
+        }
+        serde_json::from_value(message.payload.clone()).ok()
+    }
+
+    // Frame to send on the new table's socket to take the seat
+    pub fn join_frame(&self) -> WsMessage {
+        WsMessage::new(
+            "join_table",
+            json!({
+                "tableId": self.to_table_id,
+                "playerId": self.player_id,
+                "poolId": self.pool_id,
+                "chipCount": self.chip_count,
+                "seatIndex": self.seat,
+            }),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolMove {
+    #[serde(flatten)]
+    reassignment: PoolReassignment,
+    join: WsMessage,
+}
+
+#[tauri::command]
+pub async fn get_timing_profile(
+    game_speed: Option<GameSpeed>,
+    fast_fold: Option<bool>,
+    time_bank: Option<u32>,
+) -> Result<TimingProfile, String> {
+    Ok(timing(game_speed.unwrap_or_default(), fast_fold.unwrap_or(false), time_bank.unwrap_or(0)))
+}
+
+// Move the local trackers from the table the player was taken off to the new one
+// and return the join frame for it. Moves of other players only concern the table
+// mirror and are rejected here.
+#[tauri::command]
+pub async fn follow_pool_reassignment(
+    app: AppHandle,
+    idle: State<'_, IdleState>,
+    thumbnails: State<'_, ThumbnailState>,
+    message: WsMessage,
+) -> Result<PoolMove, String> {
+    let reassignment =
+        PoolReassignment::parse(&message).ok_or_else(|| "Not a pool reassignment".to_string())?;
+    let claims = claims::current()?;
+    if reassignment.player_id != claims.user_id {
+        return Err("This reassignment is for another player".to_string());
+    }
+
+    idle.move_table(&reassignment.from_table_id, &reassignment.to_table_id);
+    ledger::table_moved(&app, &reassignment.from_table_id, &reassignment.to_table_id);
+    thumbnails.forget(&reassignment.from_table_id)?;
+    let _ = app.emit_all("pool_reassigned", reassignment.clone());
+    Ok(PoolMove { join: reassignment.join_frame(), reassignment })
+}
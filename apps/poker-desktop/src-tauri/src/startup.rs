@@ -0,0 +1,125 @@
+use serde::Serialize;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+use tauri::{AppHandle, Manager, State};
+
+static PROCESS_START: OnceLock<Instant> = OnceLock::new();
+
+// Errors raised while initializing subsystems. They are kept so a frontend that
+// subscribes after the event fired can still fetch them.
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupError {
+    stage: String,
+    message: String,
+}
+
+// How long one startup stage took
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageTiming {
+    stage: String,
+    // Offset from process start to the beginning of the stage
+    started_ms: f64,
+    duration_ms: f64,
+    // Ran after the window was shown
+    deferred: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupTiming {
+    stages: Vec<StageTiming>,
+    // Process start to the end of setup, when the window can paint
+    window_ready_ms: Option<f64>,
+    // Process start to the end of the last deferred stage
+    fully_ready_ms: Option<f64>,
+}
+
+#[derive(Default)]
+pub struct StartupStatus {
+    errors: Mutex<Vec<StartupError>>,
+    stages: Mutex<Vec<StageTiming>>,
+    window_ready_ms: Mutex<Option<f64>>,
+    fully_ready_ms: Mutex<Option<f64>>,
+}
+
+fn since_start(at: Instant) -> f64 {
+    let start = *PROCESS_START.get_or_init(Instant::now);
+    at.saturating_duration_since(start).as_secs_f64() * 1000.0
+}
+
+// Called first thing in main so stage offsets include everything before setup
+pub fn mark_process_start() {
+    PROCESS_START.get_or_init(Instant::now);
+}
+
+// Run one startup stage, recording how long it took
+pub fn timed<T>(app: &AppHandle, stage: &str, deferred: bool, f: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let result = f();
+    let timing = StageTiming {
+        stage: stage.to_string(),
+        started_ms: since_start(started),
+        duration_ms: started.elapsed().as_secs_f64() * 1000.0,
+        deferred,
+    };
+    if let Ok(mut stages) = app.state::<StartupStatus>().stages.lock() {
+        stages.push(timing);
+    }
+    result
+}
+
+// Setup is done and the window can show
+pub fn mark_window_ready(app: &AppHandle) {
+    if let Ok(mut ready) = app.state::<StartupStatus>().window_ready_ms.lock() {
+        *ready = Some(since_start(Instant::now()));
+    }
+}
+
+// Deferred work is done; the frontend listens for `startup_complete` before using
+// anything that needs the local database
+pub fn mark_fully_ready(app: &AppHandle) {
+    let ready_ms = since_start(Instant::now());
+    if let Ok(mut ready) = app.state::<StartupStatus>().fully_ready_ms.lock() {
+        *ready = Some(ready_ms);
+    }
+    let _ = app.emit_all("startup_complete", ready_ms);
+}
+
+pub fn report_error(app: &AppHandle, stage: &str, message: String) {
+    eprintln!("Startup error in {}: {}", stage, message);
+
+    let error = StartupError {
+        stage: stage.to_string(),
+        message,
+    };
+
+    if let Ok(mut errors) = app.state::<StartupStatus>().errors.lock() {
+        errors.push(error.clone());
+    }
+    let _ = app.emit_all("startup_error", error);
+}
+
+// The error a stage failed with, if it did
+pub fn stage_error(app: &AppHandle, stage: &str) -> Option<String> {
+    let status = app.state::<StartupStatus>();
+    let errors = status.errors.lock().ok()?;
+    errors.iter().find(|error| error.stage == stage).map(|error| error.message.clone())
+}
+
+#[tauri::command]
+pub async fn get_startup_errors(status: State<'_, StartupStatus>) -> Result<Vec<StartupError>, String> {
+    let errors = status.errors.lock().map_err(|_| "Startup status lock poisoned".to_string())?;
+    Ok(errors.clone())
+}
+
+// Per-stage durations of this launch, to spot startup regressions
+#[tauri::command]
+pub async fn get_startup_timing(status: State<'_, StartupStatus>) -> Result<StartupTiming, String> {
+    let poisoned = || "Startup status lock poisoned".to_string();
+    Ok(StartupTiming {
+        stages: status.stages.lock().map_err(|_| poisoned())?.clone(),
+        window_ready_ms: *status.window_ready_ms.lock().map_err(|_| poisoned())?,
+        fully_ready_ms: *status.fully_ready_ms.lock().map_err(|_| poisoned())?,
+    })
+}
@@ -0,0 +1,259 @@
+// Live hand strength for training. While enabled, the frontend reports the hero's
+// cards on each street and gets back the made-hand category, draws with their outs
+// and equity against a configurable opponent range; results are also emitted as
+// `hand_strength_updated` so every table view can follow along. Work is throttled
+// per table and the Monte Carlo trials are shared out across the tables in play so
+// multi-tabling does not eat the CPU.
+
+use crate::cards::{full_deck, Card};
+use crate::compute::{self, Priority};
+use crate::db::{Database, Db};
+use crate::evaluator::{evaluate, HandCategory};
+use crate::memory::{self, approx_size, CacheUsage, MemoryCache};
+use crate::ranges::{self, Combo, HandClass};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+
+const KEY_SETTINGS: &str = "strength.settings";
+
+const MIN_INTERVAL: Duration = Duration::from_millis(750);
+const ACTIVE_WINDOW: Duration = Duration::from_secs(120);
+const MIN_TRIALS: u32 = 300;
+const MAX_TRIALS: u32 = 20_000;
+const MAX_OPPONENTS: usize = 8;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StrengthSettings {
+    enabled: bool,
+    opponent_range: String,
+    // Trials per update when playing a single table
+    trials: u32,
+}
+
+impl Default for StrengthSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            opponent_range: "22+,A2s+,K8s+,Q9s+,J9s+,T9s,98s,A8o+,KTo+,QTo+,JTo".to_string(),
+            trials: 4000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Draw {
+    kind: String,
+    outs: Vec<Card>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HandStrength {
+    table_id: String,
+    street: &'static str,
+    // "AKs" preflop, the made hand afterwards
+    description: String,
+    category: Option<HandCategory>,
+    draws: Vec<Draw>,
+    // Distinct cards that improve the hand, across all draws
+    outs: usize,
+    equity: f64,
+    opponents: usize,
+    trials: u32,
+}
+
+struct Cached {
+    key: String,
+    at: Instant,
+    result: HandStrength,
+}
+
+#[derive(Default)]
+pub struct StrengthState {
+    tables: Mutex<HashMap<String, Cached>>,
+}
+
+impl MemoryCache for StrengthState {
+    fn usage(&self) -> CacheUsage {
+        let Ok(tables) = self.tables.lock() else { return CacheUsage::default() };
+        CacheUsage { entries: tables.len(), bytes: tables.values().map(|cached| cached.key.len() + approx_size(&cached.result)).sum() }
+    }
+
+    fn trim_to(&self, max_bytes: usize) {
+        if let Ok(mut tables) = self.tables.lock() {
+            memory::trim_oldest(&mut tables, max_bytes, |cached| cached.key.len() + approx_size(&cached.result), |cached| cached.at);
+        }
+    }
+}
+
+fn load_settings(db: &Database) -> Result<StrengthSettings, String> {
+    match db.get_value(KEY_SETTINGS)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid strength settings: {}", e)),
+        None => Ok(StrengthSettings::default()),
+    }
+}
+
+fn street(board: usize) -> Result<&'static str, String> {
+    match board {
+        0 => Ok("pre_flop"),
+        3 => Ok("flop"),
+        4 => Ok("turn"),
+        5 => Ok("river"),
+        n => Err(format!("A board has 0, 3, 4 or 5 cards, not {}", n)),
+    }
+}
+
+// Paired categories of fewer than five board cards, which `evaluate` does not take
+fn board_category(board: &[Card]) -> HandCategory {
+    if board.len() >= 5 {
+        return evaluate(board).category;
+    }
+    let mut counts = [0u8; 15];
+    for card in board {
+        counts[card.rank as usize] += 1;
+    }
+    let pairs = counts.iter().filter(|&&c| c == 2).count();
+    match counts.iter().max().copied().unwrap_or(0) {
+        4 => HandCategory::FourOfAKind,
+        3 => HandCategory::ThreeOfAKind,
+        2 if pairs == 2 => HandCategory::TwoPair,
+        2 => HandCategory::Pair,
+        _ => HandCategory::HighCard,
+    }
+}
+
+// Cards still to come that lift the hand to a better category the board alone does
+// not make, grouped by what they complete
+fn draws(hole: [Card; 2], board: &[Card]) -> Vec<Draw> {
+    let known: Vec<Card> = hole.iter().chain(board).copied().collect();
+    let current = evaluate(&known).category;
+    let mut groups: Vec<Draw> = Vec::new();
+
+    for card in full_deck().into_iter().filter(|c| !known.contains(c)) {
+        let with: Vec<Card> = known.iter().copied().chain([card]).collect();
+        let improved = evaluate(&with).category;
+        let shared: Vec<Card> = board.iter().copied().chain([card]).collect();
+        if improved <= current || improved <= board_category(&shared) {
+            continue;
+        }
+        let kind = match improved {
+            HandCategory::Flush => "flush",
+            HandCategory::Straight => "straight",
+            HandCategory::StraightFlush => "straight_flush",
+            HandCategory::FullHouse | HandCategory::FourOfAKind => "full_house_or_better",
+            HandCategory::ThreeOfAKind => "trips",
+            HandCategory::TwoPair => "two_pair",
+            _ => "pair",
+        };
+        match groups.iter_mut().find(|d| d.kind == kind) {
+            Some(draw) => draw.outs.push(card),
+            None => groups.push(Draw { kind: kind.to_string(), outs: vec![card] }),
+        }
+    }
+    // Straight draws are named by how many ranks complete them
+    for draw in groups.iter_mut().filter(|d| d.kind == "straight") {
+        let mut ranks: Vec<u8> = draw.outs.iter().map(|c| c.rank).collect();
+        ranks.dedup();
+        draw.kind = if ranks.len() >= 2 { "open_ended_straight".to_string() } else { "gutshot".to_string() };
+    }
+    groups
+}
+
+fn compute(
+    table_id: String,
+    hole: [Card; 2],
+    board: Vec<Card>,
+    range: &[(HandClass, f64)],
+    opponents: usize,
+    trials: u32,
+) -> Result<HandStrength, String> {
+    let street = street(board.len())?;
+    ranges::check_known(&board, &hole)?;
+    let known: Vec<Card> = hole.iter().chain(&board).copied().collect();
+    let villain = ranges::expand(range, &known);
+    let mut players = vec![vec![Combo { cards: hole, weight: 1.0 }]];
+    players.extend(vec![villain; opponents]);
+    let equity = ranges::equity(&players, &board, &[], trials, &mut rand::thread_rng())?[0];
+
+    let (description, category, draws) = if board.is_empty() {
+        (HandClass::of(hole).notation(), None, Vec::new())
+    } else {
+        let value = evaluate(&known);
+        let draws = if board.len() < 5 { draws(hole, &board) } else { Vec::new() };
+        (value.describe(), Some(value.category), draws)
+    };
+    let mut outs: Vec<Card> = draws.iter().flat_map(|d| d.outs.iter().copied()).collect();
+    outs.sort();
+    outs.dedup();
+
+    Ok(HandStrength { table_id, street, description, category, draws, outs: outs.len(), equity, opponents, trials })
+}
+
+#[tauri::command]
+pub async fn get_hand_strength_settings(db: Db<'_>) -> Result<StrengthSettings, String> {
+    load_settings(&db)
+}
+
+#[tauri::command]
+pub async fn set_hand_strength_settings(db: Db<'_>, settings: StrengthSettings) -> Result<StrengthSettings, String> {
+    if ranges::expand(&ranges::parse_range(&settings.opponent_range)?, &[]).is_empty() {
+        return Err("The opponent range is empty".to_string());
+    }
+    let settings = StrengthSettings { trials: settings.trials.clamp(MIN_TRIALS, MAX_TRIALS), ..settings };
+    let data = serde_json::to_string(&settings).map_err(|e| e.to_string())?;
+    db.set_value(KEY_SETTINGS, &data)?;
+    Ok(settings)
+}
+
+// Strength of the hero's hand at `table_id`. Returns None when disabled or when the
+// table was updated too recently; repeating the same cards returns the last result.
+#[tauri::command]
+pub async fn update_hand_strength(
+    app: AppHandle,
+    db: Db<'_>,
+    state: State<'_, StrengthState>,
+    table_id: String,
+    hole_cards: [Card; 2],
+    board: Vec<Card>,
+    opponents: Option<usize>,
+) -> Result<Option<HandStrength>, String> {
+    let settings = load_settings(&db)?;
+    if !settings.enabled {
+        return Ok(None);
+    }
+    let opponents = opponents.unwrap_or(1).clamp(1, MAX_OPPONENTS);
+    let key = format!("{}{}|{}|{}", hole_cards[0], hole_cards[1], board.iter().map(|c| c.to_string()).collect::<String>(), opponents);
+
+    let active = {
+        let mut tables = state.tables.lock().map_err(|_| "Hand strength lock poisoned".to_string())?;
+        let now = Instant::now();
+        tables.retain(|_, cached| now.duration_since(cached.at) < ACTIVE_WINDOW);
+        if let Some(cached) = tables.get(&table_id) {
+            if cached.key == key {
+                return Ok(Some(cached.result.clone()));
+            }
+            if now.duration_since(cached.at) < MIN_INTERVAL {
+                return Ok(None);
+            }
+        }
+        tables.keys().filter(|id| **id != table_id).count() + 1
+    };
+
+    let range = ranges::dedup(ranges::parse_range(&settings.opponent_range)?);
+    let trials = (settings.trials / active as u32).max(MIN_TRIALS);
+    let id = table_id.clone();
+    let result = compute::run("Hand strength", Priority::High, move |_| compute(id, hole_cards, board, &range, opponents, trials)).await?;
+
+    state
+        .tables
+        .lock()
+        .map_err(|_| "Hand strength lock poisoned".to_string())?
+        .insert(table_id, Cached { key, at: Instant::now(), result: result.clone() });
+    let _ = app.emit_all("hand_strength_updated", result.clone());
+    Ok(Some(result))
+}
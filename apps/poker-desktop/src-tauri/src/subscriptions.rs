@@ -0,0 +1,264 @@
+// Pushed updates in place of polling. A view calls `subscribe` with a topic and
+// listens for `subscription_update` on its window. One producer per topic fetches on
+// the topic's interval for as long as any window is subscribed, so ten table windows
+// watching the wallet cost one request rather than ten. Updates are coalesced: a value
+// is only sent when it differs from the last one and only the latest is kept, so a
+// window that falls behind skips straight to the current value. Each update has to be
+// acknowledged with `ack_subscription` before the next goes to that subscription; one
+// left unacknowledged is sent again after a few seconds in case the view missed it.
+
+use crate::bankroll;
+use crate::graphql;
+use crate::idle::IdleState;
+use crate::profile::BackendProfile;
+use crate::version;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State, Window};
+
+const EVENT: &str = "subscription_update";
+// How long an update waits for its acknowledgement before it is sent again
+const ACK_TIMEOUT: Duration = Duration::from_secs(5);
+// How often producers look for updates to deliver between fetches
+const TICK: Duration = Duration::from_millis(500);
+const MAX_SUBSCRIPTIONS: usize = 200;
+// Latency changes smaller than this do not count as a new connection status
+const LATENCY_STEP_MS: u64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Topic {
+    Lobby,
+    Wallet,
+    Presence,
+    Connection,
+}
+
+impl Topic {
+    fn interval(self) -> Duration {
+        Duration::from_secs(match self {
+            Topic::Lobby => 5,
+            Topic::Wallet => 15,
+            Topic::Presence | Topic::Connection => 30,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Subscription {
+    id: u64,
+    topic: Topic,
+    // Event the updates arrive as, on the subscribing window only
+    event: &'static str,
+    interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Update {
+    subscription_id: u64,
+    topic: Topic,
+    // Echo in `ack_subscription`
+    seq: u64,
+    // The last value fetched, kept when a later fetch fails
+    data: Option<Value>,
+    error: Option<String>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+struct Snapshot {
+    seq: u64,
+    data: Option<Value>,
+    error: Option<String>,
+    updated_at: DateTime<Utc>,
+}
+
+struct Subscriber {
+    topic: Topic,
+    window: String,
+    // Seq sent and when, while it waits for its acknowledgement
+    in_flight: Option<(u64, Instant)>,
+    acked: u64,
+}
+
+#[derive(Default)]
+struct Feeds {
+    next_id: u64,
+    subscribers: HashMap<u64, Subscriber>,
+    // Latest value of every topic with a running producer; None until the first fetch
+    latest: HashMap<Topic, Option<Snapshot>>,
+}
+
+#[derive(Default)]
+pub struct SubscriptionState {
+    feeds: Mutex<Feeds>,
+}
+
+// Whether `a` and `b` would show the same thing
+fn same(topic: Topic, a: &Value, b: &Value) -> bool {
+    match topic {
+        Topic::Connection => {
+            let bucket = |v: &Value| v["latency_ms"].as_u64().map(|ms| ms / LATENCY_STEP_MS);
+            a["connected"] == b["connected"] && bucket(a) == bucket(b)
+        }
+        _ => a == b,
+    }
+}
+
+async fn fetch(app: &AppHandle, topic: Topic) -> Result<Value, String> {
+    let api_url = app.state::<BackendProfile>().api_url.clone();
+    match topic {
+        Topic::Lobby => {
+            let tables = crate::get_tables(crate::db::get(app)?, app.state(), app.state(), app.state(), api_url).await?;
+            serde_json::to_value(tables).map_err(|e| e.to_string())
+        }
+        Topic::Wallet => Ok(json!({ "balance": bankroll::fetch_wallet(&api_url).await? })),
+        Topic::Presence => {
+            // Friends are only known to backends with GraphQL
+            let friends = if version::supports(version::GRAPHQL) {
+                Some(graphql::friends_presence(&api_url).await?)
+            } else {
+                None
+            };
+            Ok(json!({ "away": app.state::<IdleState>().away(), "friends": friends }))
+        }
+        Topic::Connection => {
+            let status = crate::check_backend_connection(api_url).await?;
+            serde_json::to_value(status).map_err(|e| e.to_string())
+        }
+    }
+}
+
+// Take the result of a fetch as the topic's latest value unless nothing changed
+fn record(feeds: &mut Feeds, topic: Topic, result: Result<Value, String>) {
+    let previous = feeds.latest.get(&topic).cloned().flatten();
+    let seq = previous.as_ref().map_or(0, |p| p.seq) + 1;
+    let snapshot = match (result, previous) {
+        (Ok(data), Some(p)) if p.error.is_none() && p.data.as_ref().is_some_and(|d| same(topic, d, &data)) => return,
+        (Err(e), Some(p)) if p.error.as_ref() == Some(&e) => return,
+        (Ok(data), _) => Snapshot { seq, data: Some(data), error: None, updated_at: Utc::now() },
+        (Err(e), previous) => Snapshot { seq, data: previous.and_then(|p| p.data), error: Some(e), updated_at: Utc::now() },
+    };
+    feeds.latest.insert(topic, Some(snapshot));
+}
+
+// Send the latest value of `topic` to every subscription that is behind and not
+// still waiting on an acknowledgement
+fn deliver(app: &AppHandle, feeds: &mut Feeds, topic: Topic) {
+    let Some(Some(snapshot)) = feeds.latest.get(&topic).cloned() else { return };
+    let mut closed = Vec::new();
+    for (&id, subscriber) in feeds.subscribers.iter_mut().filter(|(_, s)| s.topic == topic) {
+        if subscriber.acked >= snapshot.seq
+            || subscriber.in_flight.is_some_and(|(_, sent)| sent.elapsed() < ACK_TIMEOUT)
+        {
+            continue;
+        }
+        let Some(window) = app.get_window(&subscriber.window) else {
+            closed.push(id);
+            continue;
+        };
+        let update = Update {
+            subscription_id: id,
+            topic,
+            seq: snapshot.seq,
+            data: snapshot.data.clone(),
+            error: snapshot.error.clone(),
+            updated_at: snapshot.updated_at,
+        };
+        match window.emit(EVENT, update) {
+            Ok(()) => subscriber.in_flight = Some((snapshot.seq, Instant::now())),
+            Err(e) => eprintln!("Failed to send {:?} update to {}: {}", topic, subscriber.window, e),
+        }
+    }
+    for id in closed {
+        feeds.subscribers.remove(&id);
+    }
+}
+
+// Fetch `topic` on its interval until nobody is subscribed to it
+fn spawn_producer(app: &AppHandle, topic: Topic) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut next_fetch = Instant::now();
+        loop {
+            let result = if Instant::now() >= next_fetch {
+                next_fetch = Instant::now() + topic.interval();
+                Some(fetch(&app, topic).await)
+            } else {
+                None
+            };
+            {
+                let state = app.state::<SubscriptionState>();
+                let Ok(mut feeds) = state.feeds.lock() else { return };
+                if !feeds.subscribers.values().any(|s| s.topic == topic) {
+                    feeds.latest.remove(&topic);
+                    return;
+                }
+                if let Some(result) = result {
+                    record(&mut feeds, topic, result);
+                }
+                deliver(&app, &mut feeds, topic);
+            }
+            tokio::time::sleep(TICK).await;
+        }
+    });
+}
+
+// Receive `topic` as `subscription_update` events on the calling window, starting
+// with the current value
+#[tauri::command]
+pub async fn subscribe(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, SubscriptionState>,
+    topic: Topic,
+) -> Result<Subscription, String> {
+    let mut feeds = state.feeds.lock().map_err(|_| "Subscription lock poisoned".to_string())?;
+    if feeds.subscribers.len() >= MAX_SUBSCRIPTIONS {
+        return Err(format!("At most {} subscriptions can be open", MAX_SUBSCRIPTIONS));
+    }
+    feeds.next_id += 1;
+    let id = feeds.next_id;
+    let subscriber = Subscriber { topic, window: window.label().to_string(), in_flight: None, acked: 0 };
+    feeds.subscribers.insert(id, subscriber);
+    if let Entry::Vacant(entry) = feeds.latest.entry(topic) {
+        entry.insert(None);
+        spawn_producer(&app, topic);
+    } else {
+        deliver(&app, &mut feeds, topic);
+    }
+    Ok(Subscription { id, topic, event: EVENT, interval_secs: topic.interval().as_secs() })
+}
+
+// Confirm update `seq` was handled, letting the next one through
+#[tauri::command]
+pub async fn ack_subscription(
+    app: AppHandle,
+    state: State<'_, SubscriptionState>,
+    id: u64,
+    seq: u64,
+) -> Result<(), String> {
+    let mut feeds = state.feeds.lock().map_err(|_| "Subscription lock poisoned".to_string())?;
+    let subscriber = feeds.subscribers.get_mut(&id).ok_or_else(|| format!("No subscription {}", id))?;
+    subscriber.acked = subscriber.acked.max(seq);
+    if subscriber.in_flight.is_some_and(|(sent, _)| sent <= seq) {
+        subscriber.in_flight = None;
+    }
+    let topic = subscriber.topic;
+    deliver(&app, &mut feeds, topic);
+    Ok(())
+}
+
+// The topic's producer stops with its last subscription
+#[tauri::command]
+pub async fn unsubscribe(state: State<'_, SubscriptionState>, id: u64) -> Result<(), String> {
+    let mut feeds = state.feeds.lock().map_err(|_| "Subscription lock poisoned".to_string())?;
+    feeds.subscribers.remove(&id).map(|_| ()).ok_or_else(|| format!("No subscription {}", id))
+}
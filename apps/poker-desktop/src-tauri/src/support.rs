@@ -0,0 +1,304 @@
+// Support tickets from inside the client. A ticket goes to the backend as multipart
+// form data with any screenshots or logs the player picked in the file dialog here
+// (no other local file can be attached) and, if they agree, a diagnostics bundle: versions, platform, startup errors and timing, network and
+// memory counters, gzipped JSON and nothing from hand histories. Submitted tickets
+// are kept in the kv table, and a background loop refreshes their status while any
+// is still open, raising `support_ticket_updated` when one changes.
+
+use crate::audit;
+use crate::db::{Database, Db};
+use crate::kyc::detect_mime;
+use crate::profile::BackendProfile;
+use chrono::{DateTime, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::api::dialog::blocking::FileDialogBuilder;
+use tauri::{AppHandle, Manager, State};
+
+const KEY_TICKETS: &str = "support.tickets";
+const MAX_ATTACHMENTS: usize = 5;
+const MAX_ATTACHMENT_BYTES: u64 = 10 * 1024 * 1024;
+const MAX_SUBJECT_LEN: usize = 200;
+const MAX_BODY_LEN: usize = 10_000;
+const POLL_INTERVAL: Duration = Duration::from_secs(300);
+// Plain text attachments, e.g. log files
+const TEXT_EXTENSIONS: &[&str] = &["txt", "log", "json"];
+const PICKABLE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "pdf", "txt", "log", "json"];
+
+// Files the player picked in the attachment dialog
+#[derive(Default)]
+pub struct SupportState {
+    picked: Mutex<HashSet<PathBuf>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SupportTicket {
+    id: String,
+    subject: String,
+    // open | pending | answered | closed
+    status: String,
+    created_at: DateTime<Utc>,
+    #[serde(default)]
+    updated_at: Option<DateTime<Utc>>,
+    // Set when support has replied since the player last looked
+    #[serde(default)]
+    unread_reply: bool,
+}
+
+impl SupportTicket {
+    fn is_open(&self) -> bool {
+        self.status != "closed"
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SupportTickets {
+    tickets: Vec<SupportTicket>,
+    // Served from the last known statuses because the backend could not be reached
+    offline: bool,
+}
+
+fn load(db: &Database) -> Result<Vec<SupportTicket>, String> {
+    match db.get_value(KEY_TICKETS)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid support tickets: {}", e)),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn save(db: &Database, tickets: &[SupportTicket]) -> Result<(), String> {
+    let data = serde_json::to_string(tickets).map_err(|e| e.to_string())?;
+    db.set_value(KEY_TICKETS, &data)
+}
+
+async fn send<T: serde::de::DeserializeOwned>(builder: reqwest::RequestBuilder) -> Result<T, String> {
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+    let response = crate::http::send(builder.header("Authorization", format!("Bearer {}", token)))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("Support request failed: {}", error_text));
+    }
+
+    let api_response: crate::ApiResponse<T> = response.json().await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    if api_response.success {
+        api_response.data.ok_or_else(|| "No data returned".to_string())
+    } else {
+        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    }
+}
+
+async fn diagnostics(app: &AppHandle) -> Result<Vec<u8>, String> {
+    let memory = match crate::db::get(app) {
+        Ok(db) => crate::memory::get_memory_report(app.clone(), db).await.ok(),
+        Err(_) => None,
+    };
+    let bundle = json!({
+        "clientVersion": crate::version::CLIENT_VERSION,
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "backend": app.state::<BackendProfile>().inner().clone(),
+        "backendVersion": crate::version::get_backend_version(app.state()).await.ok().flatten(),
+        "startupErrors": crate::startup::get_startup_errors(app.state()).await.ok(),
+        "startupTiming": crate::startup::get_startup_timing(app.state()).await.ok(),
+        "network": crate::metrics::snapshot(),
+        "memory": memory,
+        "generatedAt": Utc::now(),
+    });
+    let data = serde_json::to_vec(&bundle).map_err(|e| e.to_string())?;
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&data).map_err(|e| format!("Failed to compress diagnostics: {}", e))?;
+    encoder.finish().map_err(|e| format!("Failed to compress diagnostics: {}", e))
+}
+
+async fn attachment(file_path: &str) -> Result<reqwest::multipart::Part, String> {
+    let path = Path::new(file_path);
+    let size = tokio::fs::metadata(path).await
+        .map_err(|e| format!("Failed to read attachment {}: {}", file_path, e))?
+        .len();
+    if size == 0 || size > MAX_ATTACHMENT_BYTES {
+        return Err(format!(
+            "Attachments must be between 1 byte and {} MB",
+            MAX_ATTACHMENT_BYTES / (1024 * 1024)
+        ));
+    }
+
+    let bytes = tokio::fs::read(path).await
+        .map_err(|e| format!("Failed to read attachment {}: {}", file_path, e))?;
+    let is_text = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| TEXT_EXTENSIONS.contains(&e.to_lowercase().as_str()));
+    let mime = detect_mime(&bytes)
+        .or(if is_text { Some("text/plain") } else { None })
+        .ok_or_else(|| format!("{} is not an image, PDF or text file", file_path))?;
+
+    let file_name = path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "attachment".to_string());
+    reqwest::multipart::Part::bytes(bytes)
+        .file_name(file_name)
+        .mime_str(mime)
+        .map_err(|e| e.to_string())
+}
+
+async fn request_submit(
+    app: &AppHandle,
+    api_url: &str,
+    subject: &str,
+    body: &str,
+    attachments: &[String],
+    include_diagnostics: bool,
+) -> Result<SupportTicket, String> {
+    if subject.trim().is_empty() || subject.len() > MAX_SUBJECT_LEN {
+        return Err(format!("The subject must be 1 to {} characters", MAX_SUBJECT_LEN));
+    }
+    if body.trim().is_empty() || body.len() > MAX_BODY_LEN {
+        return Err(format!("The message must be 1 to {} characters", MAX_BODY_LEN));
+    }
+    if attachments.len() > MAX_ATTACHMENTS {
+        return Err(format!("At most {} attachments can be sent", MAX_ATTACHMENTS));
+    }
+
+    let mut form = reqwest::multipart::Form::new()
+        .text("subject", subject.trim().to_string())
+        .text("body", body.to_string());
+    for file_path in attachments {
+        form = form.part("attachments", attachment(file_path).await?);
+    }
+    if include_diagnostics {
+        let part = reqwest::multipart::Part::bytes(diagnostics(app).await?)
+            .file_name("diagnostics.json.gz")
+            .mime_str("application/gzip")
+            .map_err(|e| e.to_string())?;
+        form = form.part("diagnostics", part);
+    }
+
+    let client = crate::create_http_client()?;
+    send(client.post(format!("{}/api/support/tickets", api_url)).multipart(form)).await
+}
+
+// Refresh the stored tickets from the backend, reporting status changes
+async fn refresh(app: &AppHandle, db: &Database, api_url: &str) -> Result<Vec<SupportTicket>, String> {
+    let client = crate::create_http_client()?;
+    let remote: Vec<SupportTicket> = send(client.get(format!("{}/api/support/tickets", api_url))).await?;
+    let mut tickets = load(db)?;
+    for ticket in remote {
+        match tickets.iter_mut().find(|t| t.id == ticket.id) {
+            Some(known) => {
+                if known.status != ticket.status || (ticket.unread_reply && !known.unread_reply) {
+                    let _ = app.emit_all("support_ticket_updated", ticket.clone());
+                }
+                *known = ticket;
+            }
+            // Filed from another device or the website
+            None => tickets.push(ticket),
+        }
+    }
+    tickets.sort_by_key(|t| std::cmp::Reverse(t.created_at));
+    save(db, &tickets)?;
+    Ok(tickets)
+}
+
+// Status poll for open tickets, started once the database is open
+pub fn start_polling(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let Some(db) = app.try_state::<Database>() else { return };
+            let has_open = load(&db).map(|tickets| tickets.iter().any(SupportTicket::is_open)).unwrap_or(false);
+            if has_open {
+                let api_url = app.state::<BackendProfile>().api_url.clone();
+                if let Err(e) = refresh(&app, &db, &api_url).await {
+                    eprintln!("Support ticket poll failed: {}", e);
+                }
+            }
+        }
+    });
+}
+
+// Let the player pick screenshots or logs for a ticket; returns their paths
+#[tauri::command]
+pub async fn pick_support_attachments(state: State<'_, SupportState>) -> Result<Vec<String>, String> {
+    let files = FileDialogBuilder::new()
+        .set_title("Attach to support ticket")
+        .add_filter("Screenshots and logs", PICKABLE_EXTENSIONS)
+        .pick_files()
+        .unwrap_or_default();
+    let mut picked = state.picked.lock().map_err(|_| "Support lock poisoned".to_string())?;
+    Ok(files
+        .into_iter()
+        .map(|path| {
+            let shown = path.to_string_lossy().into_owned();
+            picked.insert(path);
+            shown
+        })
+        .collect())
+}
+
+// File a ticket with optional attachments picked through `pick_support_attachments`
+// and diagnostics, which are only sent when asked for
+#[tauri::command]
+pub async fn submit_support_ticket(
+    app: AppHandle,
+    db: Db<'_>,
+    api_url: String,
+    subject: String,
+    body: String,
+    attachments: Option<Vec<String>>,
+    include_diagnostics: Option<bool>,
+) -> Result<SupportTicket, String> {
+    let attachments = attachments.unwrap_or_default();
+    let include_diagnostics = include_diagnostics.unwrap_or(false);
+    {
+        let picked = app.state::<SupportState>();
+        let picked = picked.picked.lock().map_err(|_| "Support lock poisoned".to_string())?;
+        if let Some(path) = attachments.iter().find(|path| !picked.contains(Path::new(path))) {
+            return Err(format!("{} was not picked as an attachment", path));
+        }
+    }
+    let result = request_submit(&app, &api_url, &subject, &body, &attachments, include_diagnostics).await;
+    audit::record(
+        &app,
+        "submit_support_ticket",
+        json!({ "subject": subject, "attachments": attachments.len(), "diagnostics": include_diagnostics }),
+        &result,
+    );
+    let ticket = result?;
+    if let Ok(mut picked) = app.state::<SupportState>().picked.lock() {
+        picked.clear();
+    }
+
+    let mut tickets = load(&db)?;
+    tickets.retain(|t| t.id != ticket.id);
+    tickets.insert(0, ticket.clone());
+    save(&db, &tickets)?;
+    Ok(ticket)
+}
+
+// Tickets filed by the player, newest first; the stored copy is used when the
+// backend cannot be reached
+#[tauri::command]
+pub async fn get_my_tickets(app: AppHandle, db: Db<'_>, api_url: String) -> Result<SupportTickets, String> {
+    match refresh(&app, &db, &api_url).await {
+        Ok(tickets) => Ok(SupportTickets { tickets, offline: false }),
+        Err(e) => {
+            eprintln!("Support ticket fetch failed, using stored copy: {}", e);
+            Ok(SupportTickets { tickets: load(&db)?, offline: true })
+        }
+    }
+}
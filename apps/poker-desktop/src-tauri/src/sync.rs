@@ -0,0 +1,267 @@
+use crate::db::{self, Database, Db};
+use crate::history::{self, HandRecord};
+use crate::notes::{self, PlayerNote};
+use crate::retention;
+use crate::schema::{self, Field, Kind};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager};
+
+const SYNC_BATCH_SIZE: u32 = 100;
+const SYNC_INTERVAL_SECS: u64 = 300;
+
+const KEY_ENABLED: &str = "sync.enabled";
+const KEY_API_URL: &str = "sync.api_url";
+const KEY_HANDS_UPLOADED: &str = "sync.hands_uploaded_at";
+const KEY_NOTES_UPLOADED: &str = "sync.notes_uploaded_at";
+const KEY_DOWNLOADED: &str = "sync.downloaded_at";
+const KEY_LAST_SYNC: &str = "sync.last_sync_at";
+
+// Guards against overlapping sync runs and duplicate background loops
+#[derive(Default)]
+pub struct SyncEngine {
+    loop_running: AtomicBool,
+    in_progress: tokio::sync::Mutex<()>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStatus {
+    enabled: bool,
+    last_sync_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    uploaded_hands: usize,
+    uploaded_notes: usize,
+    downloaded_hands: usize,
+    downloaded_notes: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RemoteChanges {
+    #[serde(default)]
+    hands: Vec<HandRecord>,
+    #[serde(default)]
+    notes: Vec<PlayerNote>,
+    server_time: i64,
+}
+
+const CHANGES_SCHEMA: Kind = Kind::Object(&[
+    Field::defaulted("hands", Kind::Array(&Kind::Any)),
+    Field::defaulted("notes", Kind::Array(&Kind::Any)),
+    Field::critical("serverTime", Kind::Number),
+]);
+
+fn get_cursor(db: &Database, key: &str) -> Result<i64, String> {
+    Ok(db.get_value(key)?.and_then(|v| v.parse().ok()).unwrap_or(0))
+}
+
+// (updated_at, id) of the last hand or note uploaded, stored as `<millis>:<id>`. A
+// bare millisecond value from before ids were kept resumes from the start of that
+// millisecond.
+fn get_keyset_cursor(db: &Database, key: &str) -> Result<(i64, String), String> {
+    let stored = db.get_value(key)?.unwrap_or_default();
+    let (millis, id) = stored.split_once(':').unwrap_or((&stored, ""));
+    match millis.parse() {
+        Ok(millis) if id.is_empty() => Ok((millis - 1, String::new())),
+        Ok(millis) => Ok((millis, id.to_string())),
+        Err(_) => Ok((0, String::new())),
+    }
+}
+
+fn is_enabled(db: &Database) -> Result<bool, String> {
+    Ok(db.get_value(KEY_ENABLED)?.as_deref() == Some("true"))
+}
+
+// The `(updated_at, id)` every hand up to which has been uploaded; None when sync is
+// off, so nothing is waiting on it
+pub fn uploaded_through(db: &Database) -> Result<Option<(i64, String)>, String> {
+    if !is_enabled(db)? {
+        return Ok(None);
+    }
+    get_keyset_cursor(db, KEY_HANDS_UPLOADED).map(Some)
+}
+
+async fn post_batch<T: Serialize>(
+    client: &reqwest::Client,
+    url: &str,
+    token: &str,
+    body: &T,
+) -> Result<(), String> {
+    let response = crate::http::send(client
+        .post(url)
+        .header("Authorization", format!("Bearer {}", token))
+        .json(body))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("Sync upload failed: {}", error_text));
+    }
+
+    Ok(())
+}
+
+// Upload local changes in batches, then pull and merge changes made on other devices.
+// Records are merged by id and keep whichever copy has the newest updated_at.
+async fn run_sync(db: &Database, api_url: &str) -> Result<SyncReport, String> {
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+    let client = crate::create_http_client()?;
+    let mut report = SyncReport::default();
+
+    let mut cursor = get_keyset_cursor(db, KEY_HANDS_UPLOADED)?;
+    loop {
+        let batch = db.with_conn(|conn| history::hands_updated_since(conn, (cursor.0, &cursor.1), SYNC_BATCH_SIZE))?;
+        let Some(last) = batch.last() else { break };
+        let next_cursor = (history::to_millis(&last.updated_at), last.id.clone());
+
+        post_batch(&client, &format!("{}/api/sync/hands", api_url), &token, &serde_json::json!({ "hands": batch })).await?;
+
+        report.uploaded_hands += batch.len();
+        cursor = next_cursor;
+        db.set_value(KEY_HANDS_UPLOADED, &format!("{}:{}", cursor.0, cursor.1))?;
+    }
+
+    let mut cursor = get_keyset_cursor(db, KEY_NOTES_UPLOADED)?;
+    loop {
+        let batch = db.with_conn(|conn| notes::notes_updated_since(conn, (cursor.0, &cursor.1), SYNC_BATCH_SIZE))?;
+        let Some(last) = batch.last() else { break };
+        let next_cursor = (last.updated_at.timestamp_millis(), last.player_id.clone());
+
+        post_batch(&client, &format!("{}/api/sync/notes", api_url), &token, &serde_json::json!({ "notes": batch })).await?;
+
+        report.uploaded_notes += batch.len();
+        cursor = next_cursor;
+        db.set_value(KEY_NOTES_UPLOADED, &format!("{}:{}", cursor.0, cursor.1))?;
+    }
+
+    let since = get_cursor(db, KEY_DOWNLOADED)?;
+    let response = crate::http::send(client
+        .get(format!("{}/api/sync/changes", api_url))
+        .query(&[("since", since)])
+        .header("Authorization", format!("Bearer {}", token)))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        return Err("Failed to fetch remote changes".to_string());
+    }
+
+    let changes: Option<RemoteChanges> = schema::api_data(response, &CHANGES_SCHEMA, "remote changes").await?;
+    if let Some(changes) = changes {
+        // Downloaded rows keep their remote timestamps, so they may be echoed back on the
+        // next upload; the server merge is idempotent so this only costs bandwidth.
+        db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            for hand in &changes.hands {
+                // Pruned locally, so left pruned
+                if retention::is_summarized(&tx, &hand.id)? {
+                    continue;
+                }
+                if history::upsert_hand(&tx, hand)? {
+                    report.downloaded_hands += 1;
+                }
+            }
+            for note in &changes.notes {
+                if notes::upsert_note(&tx, note)? {
+                    report.downloaded_notes += 1;
+                }
+            }
+            tx.commit()
+        })?;
+        db.set_value(KEY_DOWNLOADED, &changes.server_time.to_string())?;
+    }
+
+    db.set_value(KEY_LAST_SYNC, &Utc::now().timestamp_millis().to_string())?;
+    Ok(report)
+}
+
+async fn sync_and_notify(app: &AppHandle) -> Result<SyncReport, String> {
+    let engine = app.state::<SyncEngine>();
+    let db = db::get(app)?;
+    let _guard = engine.in_progress.lock().await;
+
+    let api_url = db.get_value(KEY_API_URL)?
+        .ok_or_else(|| "Cloud sync has no backend configured".to_string())?;
+
+    let report = run_sync(&db, &api_url).await?;
+    let _ = app.emit_all("cloud_sync_completed", report.clone());
+    Ok(report)
+}
+
+// Start the periodic background sync if it is enabled and not already running
+pub fn start_background_sync(app: &AppHandle) {
+    let enabled = db::get(app).and_then(|db| is_enabled(&db)).unwrap_or(false);
+    if !enabled || app.state::<SyncEngine>().loop_running.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if !db::get(&app).and_then(|db| is_enabled(&db)).unwrap_or(false) {
+                break;
+            }
+            if let Err(e) = sync_and_notify(&app).await {
+                eprintln!("Cloud sync error: {}", e);
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(SYNC_INTERVAL_SECS)).await;
+        }
+        app.state::<SyncEngine>().loop_running.store(false, Ordering::SeqCst);
+    });
+}
+
+// One last upload before the app exits; None when sync is off
+pub async fn sync_before_exit(app: &AppHandle) -> Result<Option<SyncReport>, String> {
+    if !is_enabled(&*db::get(app)?)? {
+        return Ok(None);
+    }
+    sync_and_notify(app).await.map(Some)
+}
+
+// Opt in to cloud sync against the given backend
+#[tauri::command]
+pub async fn enable_cloud_sync(
+    app: AppHandle,
+    db: Db<'_>,
+    api_url: String,
+) -> Result<(), String> {
+    db.set_value(KEY_API_URL, &api_url)?;
+    db.set_value(KEY_ENABLED, "true")?;
+    start_background_sync(&app);
+    Ok(())
+}
+
+// Opt out of cloud sync; locally stored data is left untouched
+#[tauri::command]
+pub async fn disable_cloud_sync(db: Db<'_>) -> Result<(), String> {
+    db.set_value(KEY_ENABLED, "false")
+}
+
+#[tauri::command]
+pub async fn get_cloud_sync_status(db: Db<'_>) -> Result<SyncStatus, String> {
+    let last_sync_at = db.get_value(KEY_LAST_SYNC)?
+        .and_then(|v| v.parse::<i64>().ok())
+        .and_then(|ms| Utc.timestamp_millis_opt(ms).single());
+
+    Ok(SyncStatus {
+        enabled: is_enabled(&db)?,
+        last_sync_at,
+    })
+}
+
+// Run a sync immediately instead of waiting for the next interval
+#[tauri::command]
+pub async fn sync_now(app: AppHandle, db: Db<'_>) -> Result<SyncReport, String> {
+    if !is_enabled(&db)? {
+        return Err("Cloud sync is disabled".to_string());
+    }
+    sync_and_notify(&app).await
+}
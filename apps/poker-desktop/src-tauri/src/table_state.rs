@@ -0,0 +1,287 @@
+// Client-side mirror of a table. Replaced wholesale by `game_update` snapshots and
+// patched by the smaller events the server sends in between.
+
+use crate::history::AnteStructure;
+use crate::speed::{GameSpeed, PoolReassignment};
+use crate::ws::WsMessage;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Card {
+    pub suit: String,
+    pub rank: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Position {
+    pub seat: u8,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SeatState {
+    pub id: String,
+    pub username: String,
+    #[serde(alias = "chipCount")]
+    pub chips: u32,
+    pub current_bet: u32,
+    pub has_acted: bool,
+    pub is_folded: bool,
+    pub is_all_in: bool,
+    pub position: Option<Position>,
+    pub cards: Option<Vec<Card>>,
+    // active | sitting_out | ...
+    pub status: Option<String>,
+}
+
+impl SeatState {
+    pub fn is_sitting_out(&self) -> bool {
+        self.status.as_deref() == Some("sitting_out")
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TableMirror {
+    pub table_id: String,
+    pub game_id: Option<String>,
+    pub phase: String,
+    pub pot: u32,
+    pub side_pots: Vec<u32>,
+    pub current_bet: u32,
+    pub min_raise: u32,
+    pub active_player_id: Option<String>,
+    pub dealer_id: Option<String>,
+    pub small_blind_id: Option<String>,
+    pub big_blind_id: Option<String>,
+    pub small_blind: u32,
+    pub big_blind: u32,
+    pub ante: u32,
+    pub ante_structure: AnteStructure,
+    // Dead blinds posted this hand: in the pot but part of nobody's bet
+    pub dead_money: u32,
+    pub hand_number: u32,
+    pub community_cards: Vec<Card>,
+    pub players: Vec<SeatState>,
+    pub game_speed: GameSpeed,
+    // Fast-fold tables belong to a pool and players come and go between hands
+    pub fast_fold: bool,
+    pub pool_id: Option<String>,
+}
+
+// An action the player may take right now. Amounts are chips added to the pot, which
+// is how the backend interprets `player_action.amount`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LegalAction {
+    pub action: String,
+    pub min_amount: u32,
+    pub max_amount: u32,
+}
+
+impl LegalAction {
+    pub fn new(action: &str, min_amount: u32, max_amount: u32) -> Self {
+        Self { action: action.to_string(), min_amount, max_amount }
+    }
+}
+
+pub const BETTING_PHASES: &[&str] = &["pre_flop", "flop", "turn", "river"];
+
+impl TableMirror {
+    pub fn player(&self, player_id: &str) -> Option<&SeatState> {
+        self.players.iter().find(|p| p.id == player_id)
+    }
+
+    fn player_mut(&mut self, player_id: &str) -> Option<&mut SeatState> {
+        self.players.iter_mut().find(|p| p.id == player_id)
+    }
+
+    pub fn is_hand_over(&self) -> bool {
+        matches!(self.phase.as_str(), "showdown" | "finished")
+    }
+
+    // Apply a server message. Returns whether the mirror changed.
+    pub fn apply(&mut self, message: &WsMessage) -> Result<bool, String> {
+        let payload = &message.payload;
+        match message.kind.as_str() {
+            "game_update" => {
+                let mut snapshot: TableMirror = serde_json::from_value(payload.clone())
+                    .map_err(|e| format!("Invalid game_update: {}", e))?;
+                // Some servers omit the player list on partial updates
+                if snapshot.players.is_empty() {
+                    snapshot.players = std::mem::take(&mut self.players);
+                }
+                *self = snapshot;
+            }
+            "hand_started" => {
+                self.hand_number = payload["handNumber"].as_u64().unwrap_or(0) as u32;
+                self.dealer_id = payload["dealerId"].as_str().map(String::from);
+                self.small_blind_id = payload["smallBlindId"].as_str().map(String::from);
+                self.big_blind_id = payload["bigBlindId"].as_str().map(String::from);
+                self.phase = "pre_flop".to_string();
+                self.community_cards.clear();
+                self.pot = 0;
+                self.dead_money = 0;
+                self.side_pots.clear();
+                for player in &mut self.players {
+                    player.current_bet = 0;
+                    player.has_acted = false;
+                    player.is_folded = false;
+                    player.is_all_in = false;
+                    player.cards = None;
+                }
+                if let Some(ante) = payload["ante"].as_u64() {
+                    self.ante = ante as u32;
+                }
+                if let Some(structure) = payload.get("anteStructure") {
+                    self.ante_structure = serde_json::from_value(structure.clone()).unwrap_or_default();
+                }
+                self.collect_antes(payload["antes"].as_array());
+            }
+            "player_joined" => {
+                let player = &payload["player"];
+                let id = player["id"].as_str().unwrap_or_default().to_string();
+                if id.is_empty() || self.player(&id).is_some() {
+                    return Ok(false);
+                }
+                self.players.push(SeatState {
+                    username: player["username"].as_str().unwrap_or_default().to_string(),
+                    chips: payload["chipCount"].as_u64().unwrap_or(0) as u32,
+                    position: payload["seatNumber"].as_u64().map(|seat| Position { seat: seat as u8 }),
+                    id,
+                    ..SeatState::default()
+                });
+            }
+            "player_left" => {
+                let id = payload["playerId"].as_str().unwrap_or_default();
+                let before = self.players.len();
+                self.players.retain(|p| p.id != id);
+                return Ok(self.players.len() != before);
+            }
+            // A fast-fold player was moved on; their arrival elsewhere comes as
+            // `player_joined` or a snapshot of the new table
+            "pool_reassigned" => {
+                let Some(moved) = PoolReassignment::parse(message) else { return Ok(false) };
+                if moved.from_table_id != self.table_id {
+                    return Ok(false);
+                }
+                let before = self.players.len();
+                self.players.retain(|p| p.id != moved.player_id);
+                return Ok(self.players.len() != before);
+            }
+            "player_action" => {
+                let id = payload["playerId"].as_str().unwrap_or_default();
+                let action = payload["action"].as_str().unwrap_or_default().to_string();
+                // Antes were collected with `hand_started` and are not a turn to act
+                if action == "ante" {
+                    return Ok(false);
+                }
+                if action == "dead_blind" {
+                    let amount = payload["amount"].as_u64().unwrap_or(0) as u32;
+                    return self.post_dead_blind(id, amount).map(|_| true);
+                }
+                let Some(player) = self.player_mut(id) else { return Ok(false) };
+                player.has_acted = true;
+                match action.as_str() {
+                    "fold" => player.is_folded = true,
+                    "all_in" => player.is_all_in = true,
+                    _ => {}
+                }
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    // Take the antes into the pot. The server lists what each player posted; older
+    // servers only send the table's ante, so the posts are worked out from the
+    // structure. Antes are dead money and leave `current_bet` alone, but a player
+    // whose stack does not cover the ante is all-in for what they had.
+    fn collect_antes(&mut self, posted: Option<&Vec<serde_json::Value>>) {
+        let posts: Vec<(String, u32)> = match posted {
+            Some(posted) => posted
+                .iter()
+                .filter_map(|p| Some((p["playerId"].as_str()?.to_string(), p["amount"].as_u64()? as u32)))
+                .collect(),
+            None if self.ante == 0 => Vec::new(),
+            None => {
+                let payer = match self.ante_structure {
+                    AnteStructure::Standard => None,
+                    AnteStructure::Button => self.dealer_id.clone(),
+                    AnteStructure::BigBlind => self.big_blind_id.clone(),
+                };
+                self.players
+                    .iter()
+                    .filter(|p| p.chips > 0 && payer.as_ref().is_none_or(|id| *id == p.id))
+                    .map(|p| (p.id.clone(), self.ante))
+                    .collect()
+            }
+        };
+        for (id, amount) in posts {
+            let Some(player) = self.player_mut(&id) else { continue };
+            let paid = amount.min(player.chips);
+            player.chips -= paid;
+            if player.chips == 0 && paid > 0 {
+                player.is_all_in = true;
+            }
+            self.pot += paid;
+        }
+    }
+
+    // A returning player's missed small blind, which goes into the pot without
+    // counting towards their bet. The server's amount is checked before it is taken:
+    // never more than the small blind, when the blinds are known, or the stack.
+    fn post_dead_blind(&mut self, player_id: &str, amount: u32) -> Result<(), String> {
+        if self.small_blind > 0 && amount > self.small_blind {
+            return Err(format!(
+                "Dead blind of {} from {} is more than the small blind of {}",
+                amount, player_id, self.small_blind
+            ));
+        }
+        let Some(player) = self.player_mut(player_id) else {
+            return Err(format!("Dead blind from {}, who is not seated", player_id));
+        };
+        if amount > player.chips {
+            return Err(format!("Dead blind of {} from {} is more than their {} chips", amount, player_id, player.chips));
+        }
+        player.chips -= amount;
+        if player.chips == 0 {
+            player.is_all_in = true;
+        }
+        self.pot += amount;
+        self.dead_money += amount;
+        Ok(())
+    }
+
+    // Actions available to `player_id`, empty when it is not their turn
+    pub fn legal_actions(&self, player_id: &str) -> Vec<LegalAction> {
+        if self.active_player_id.as_deref() != Some(player_id) || !BETTING_PHASES.contains(&self.phase.as_str()) {
+            return Vec::new();
+        }
+        let Some(player) = self.player(player_id) else { return Vec::new() };
+        if player.is_folded || player.is_all_in || player.chips == 0 {
+            return Vec::new();
+        }
+
+        let stack = player.chips;
+        let to_call = self.current_bet.saturating_sub(player.current_bet);
+        // Smallest bet or raise that reaches current bet + min raise
+        let min_raise = (self.current_bet + self.min_raise.max(1)).saturating_sub(player.current_bet);
+
+        let mut actions = Vec::new();
+        if to_call == 0 {
+            actions.push(LegalAction::new("check", 0, 0));
+        } else {
+            actions.push(LegalAction::new("fold", 0, 0));
+            if stack > to_call {
+                actions.push(LegalAction::new("call", to_call, to_call));
+            }
+        }
+        if stack > min_raise {
+            let kind = if self.current_bet == 0 { "bet" } else { "raise" };
+            actions.push(LegalAction::new(kind, min_raise, stack));
+        }
+        actions.push(LegalAction::new("all_in", stack, stack));
+        actions
+    }
+}
@@ -0,0 +1,411 @@
+// Rolling per-table statistics for the lobby: share of players seeing the flop, hands
+// per hour and average pot. They are built from what the client already observes
+// (lobby polls and table snapshots), so nothing extra is requested from the backend.
+// Hand boundaries are inferred from the phase going back to pre-flop or waiting, the
+// hand number when the feed carries one, and the pot resetting. The lobby events the
+// frontend forwards add the crowd: observer counts and players joining and leaving,
+// from which each table gets a popularity score. Everything covers the last hour and
+// lives in memory only.
+
+use crate::memory::{self, CacheUsage, MemoryCache};
+use crate::table_state::TableMirror;
+use crate::ws::WsMessage;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::State;
+
+const WINDOW: Duration = Duration::from_secs(3600);
+const MAX_HANDS: usize = 200;
+const MAX_TABLES: usize = 500;
+const MAX_CROWD_SAMPLES: usize = 360;
+
+// Longer than this between observations and the hand rate starts over
+const MAX_GAP: Duration = Duration::from_secs(120);
+// Hands per hour is not reported until the table has been watched this long
+const MIN_RATE_SPAN: Duration = Duration::from_secs(300);
+
+// One look at a table from any feed
+#[derive(Debug, Clone)]
+pub struct Observation {
+    pub phase: String,
+    pub pot: u32,
+    pub players: u8,
+    pub hand_number: Option<u32>,
+    // Players still in the hand, when the feed says who folded
+    pub in_hand: Option<u8>,
+    // Spectators, when the feed counts them
+    pub spectators: Option<u32>,
+}
+
+impl Observation {
+    pub fn from_mirror(mirror: &TableMirror) -> Self {
+        Self {
+            phase: mirror.phase.clone(),
+            pot: mirror.pot,
+            players: mirror.players.len() as u8,
+            hand_number: (mirror.hand_number > 0).then_some(mirror.hand_number),
+            in_hand: Some(mirror.players.iter().filter(|p| !p.is_folded).count() as u8),
+            spectators: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableStats {
+    // Hands seen finishing in the last hour
+    hands: u32,
+    // Share of dealt-in players who saw the flop, as in the lobby "Plrs/Flop" column
+    players_per_flop: Option<f64>,
+    hands_per_hour: Option<f64>,
+    average_pot: Option<f64>,
+    observed_secs: u64,
+    observers: Option<u32>,
+    peak_observers: Option<u32>,
+    // Players seen sitting down and getting up in the last hour
+    joins: u32,
+    leaves: u32,
+    // 0 to 100, see `popularity`
+    popularity: f64,
+}
+
+// Observers, seat churn, hand rate and seated players, each worth up to a share of
+// 100 once it reaches the level of a busy table
+fn popularity(observers: u32, churn: u32, hands_per_hour: f64, players: u8) -> f64 {
+    let observers = observers.min(20) as f64 * 1.5;
+    let churn = churn.min(30) as f64;
+    let pace = hands_per_hour.min(120.0) / 4.0;
+    let seated = players.min(10) as f64;
+    ((observers + churn + pace + seated) * 10.0).round() / 10.0
+}
+
+fn street_rank(phase: &str) -> u8 {
+    match phase {
+        "pre_flop" | "preflop" => 1,
+        "flop" => 2,
+        "turn" => 3,
+        "river" => 4,
+        "showdown" => 5,
+        _ => 0,
+    }
+}
+
+struct HandSample {
+    ended_at: Instant,
+    pot: u32,
+    dealt: u8,
+    // None when the hand reached the flop but the feed did not say who was still in
+    saw_flop: Option<u8>,
+}
+
+struct CurrentHand {
+    hand_number: Option<u32>,
+    rank: u8,
+    pot: u32,
+    dealt: u8,
+    reached_flop: bool,
+    saw_flop: Option<u8>,
+}
+
+impl CurrentHand {
+    fn finish(self, now: Instant) -> HandSample {
+        HandSample {
+            ended_at: now,
+            pot: self.pot,
+            dealt: self.dealt,
+            saw_flop: if self.reached_flop { self.saw_flop } else { Some(0) },
+        }
+    }
+}
+
+struct Tracker {
+    // Start of the current unbroken run of observations
+    run_started: Instant,
+    last_seen: Instant,
+    current: Option<CurrentHand>,
+    hands: VecDeque<HandSample>,
+    // Last reported seated players and observers
+    players: Option<u8>,
+    observers: Option<u32>,
+    observer_samples: VecDeque<(Instant, u32)>,
+    // Seat changes, true for a join
+    churn: VecDeque<(Instant, bool)>,
+}
+
+impl Tracker {
+    fn new(now: Instant) -> Self {
+        Self {
+            run_started: now,
+            last_seen: now,
+            current: None,
+            hands: VecDeque::new(),
+            players: None,
+            observers: None,
+            observer_samples: VecDeque::new(),
+            churn: VecDeque::new(),
+        }
+    }
+
+    fn push_churn(&mut self, joined: bool, now: Instant) {
+        self.churn.push_back((now, joined));
+        if self.churn.len() > MAX_CROWD_SAMPLES {
+            self.churn.pop_front();
+        }
+    }
+
+    // Seated players and observers as a feed reports them; a change in the seated
+    // count is taken as that many joins or leaves
+    fn crowd(&mut self, players: Option<u8>, observers: Option<u32>, now: Instant) {
+        self.last_seen = now;
+        while self.churn.front().is_some_and(|(at, _)| now.duration_since(*at) > WINDOW) {
+            self.churn.pop_front();
+        }
+        while self.observer_samples.front().is_some_and(|(at, _)| now.duration_since(*at) > WINDOW) {
+            self.observer_samples.pop_front();
+        }
+        if let Some(players) = players {
+            if let Some(previous) = self.players {
+                for _ in 0..players.abs_diff(previous) {
+                    self.push_churn(players > previous, now);
+                }
+            }
+            self.players = Some(players);
+        }
+        if let Some(observers) = observers {
+            self.observers = Some(observers);
+            self.observer_samples.push_back((now, observers));
+            if self.observer_samples.len() > MAX_CROWD_SAMPLES {
+                self.observer_samples.pop_front();
+            }
+        }
+    }
+
+    // A player sat down or got up, as a join or leave event says
+    fn seat_change(&mut self, joined: bool, now: Instant) {
+        self.last_seen = now;
+        self.push_churn(joined, now);
+        self.players = self.players.map(|p| if joined { p.saturating_add(1) } else { p.saturating_sub(1) });
+    }
+
+    fn observe(&mut self, obs: &Observation, now: Instant) {
+        if now.duration_since(self.last_seen) > MAX_GAP {
+            // We missed the end of whatever hand was running
+            self.current = None;
+            self.run_started = now;
+        }
+        self.crowd(Some(obs.players), obs.spectators, now);
+
+        let rank = street_rank(&obs.phase);
+        let new_hand = self.current.as_ref().is_some_and(|hand| {
+            rank == 0
+                || rank < hand.rank
+                || matches!((hand.hand_number, obs.hand_number), (Some(a), Some(b)) if a != b)
+                || (rank == hand.rank && obs.pot < hand.pot)
+        });
+        if new_hand {
+            if let Some(hand) = self.current.take() {
+                self.hands.push_back(hand.finish(now));
+                if self.hands.len() > MAX_HANDS {
+                    self.hands.pop_front();
+                }
+            }
+        }
+        if rank == 0 {
+            return;
+        }
+
+        let hand = self.current.get_or_insert(CurrentHand {
+            hand_number: obs.hand_number,
+            rank,
+            pot: 0,
+            dealt: obs.players,
+            reached_flop: false,
+            saw_flop: None,
+        });
+        if hand.rank < 2 && rank == 2 {
+            hand.saw_flop = obs.in_hand;
+        }
+        hand.reached_flop |= rank >= 2;
+        hand.rank = rank;
+        hand.pot = hand.pot.max(obs.pot);
+        hand.hand_number = obs.hand_number.or(hand.hand_number);
+    }
+
+    fn stats(&self, now: Instant) -> TableStats {
+        let recent: Vec<&HandSample> =
+            self.hands.iter().filter(|h| now.duration_since(h.ended_at) <= WINDOW).collect();
+        let count = recent.len() as u32;
+
+        let (flopped, dealt) = recent
+            .iter()
+            .filter_map(|h| h.saw_flop.map(|saw| (saw as u32, h.dealt as u32)))
+            .fold((0, 0), |(f, d), (saw, dealt)| (f + saw, d + dealt));
+        let span = now.duration_since(self.run_started).min(WINDOW);
+        let run_hands = recent.iter().filter(|h| h.ended_at >= self.run_started).count();
+        let hands_per_hour = (span >= MIN_RATE_SPAN).then(|| run_hands as f64 * 3600.0 / span.as_secs_f64());
+
+        let recent_churn = self.churn.iter().filter(|(at, _)| now.duration_since(*at) <= WINDOW);
+        let joins = recent_churn.clone().filter(|(_, joined)| *joined).count() as u32;
+        let leaves = recent_churn.count() as u32 - joins;
+        let peak_observers = self
+            .observer_samples
+            .iter()
+            .filter(|(at, _)| now.duration_since(*at) <= WINDOW)
+            .map(|(_, count)| *count)
+            .max();
+
+        TableStats {
+            hands: count,
+            players_per_flop: (dealt > 0).then(|| flopped as f64 / dealt as f64),
+            hands_per_hour,
+            average_pot: (count > 0).then(|| recent.iter().map(|h| h.pot as f64).sum::<f64>() / count as f64),
+            observed_secs: span.as_secs(),
+            observers: self.observers,
+            peak_observers,
+            joins,
+            leaves,
+            popularity: popularity(
+                self.observers.unwrap_or(0),
+                joins + leaves,
+                hands_per_hour.unwrap_or(0.0),
+                self.players.unwrap_or(0),
+            ),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct TableStatsState {
+    tables: Mutex<HashMap<String, Tracker>>,
+}
+
+fn tracker_size(tracker: &Tracker) -> usize {
+    std::mem::size_of::<Tracker>()
+        + tracker.hands.len() * std::mem::size_of::<HandSample>()
+        + tracker.observer_samples.len() * std::mem::size_of::<(Instant, u32)>()
+        + tracker.churn.len() * std::mem::size_of::<(Instant, bool)>()
+}
+
+impl MemoryCache for TableStatsState {
+    fn usage(&self) -> CacheUsage {
+        let Ok(tables) = self.tables.lock() else { return CacheUsage::default() };
+        CacheUsage { entries: tables.len(), bytes: tables.values().map(tracker_size).sum() }
+    }
+
+    // The stalest tables go first
+    fn trim_to(&self, max_bytes: usize) {
+        if let Ok(mut tables) = self.tables.lock() {
+            memory::trim_oldest(&mut tables, max_bytes, tracker_size, |tracker| tracker.last_seen);
+        }
+    }
+}
+
+// The tracker for `table_id`, making room for it by dropping the stalest table
+fn tracker<'a>(tables: &'a mut HashMap<String, Tracker>, table_id: &str, now: Instant) -> &'a mut Tracker {
+    if !tables.contains_key(table_id) && tables.len() >= MAX_TABLES {
+        if let Some(stalest) = tables.iter().min_by_key(|(_, t)| t.last_seen).map(|(id, _)| id.clone()) {
+            tables.remove(&stalest);
+        }
+    }
+    tables.entry(table_id.to_string()).or_insert_with(|| Tracker::new(now))
+}
+
+// Table id, seated players and observers from a lobby listing, whichever of the
+// lobby's field names it uses
+fn listing(table: &Value) -> Option<(String, Option<u8>, Option<u32>)> {
+    let id = table.get("tableId").or_else(|| table.get("id"))?.as_str()?.to_string();
+    let number = |keys: &[&str]| keys.iter().find_map(|key| table.get(*key).and_then(Value::as_u64));
+    let players = number(&["currentPlayers", "playerCount"]).map(|n| n.min(u8::MAX as u64) as u8);
+    let observers = number(&["spectatorCount", "spectators", "observers"]).map(|n| n as u32);
+    Some((id, players, observers))
+}
+
+impl TableStatsState {
+    pub fn observe(&self, table_id: &str, obs: &Observation) -> Result<(), String> {
+        let now = Instant::now();
+        let mut tables = self.tables.lock().map_err(|_| "Table stats lock poisoned".to_string())?;
+        tracker(&mut tables, table_id, now).observe(obs, now);
+        Ok(())
+    }
+
+    pub fn stats(&self, table_id: &str) -> Result<TableStats, String> {
+        let tables = self.tables.lock().map_err(|_| "Table stats lock poisoned".to_string())?;
+        Ok(tables.get(table_id).map(|t| t.stats(Instant::now())).unwrap_or_default())
+    }
+}
+
+// Feed one lobby event. Table-level events (spectator counts, players joining or
+// leaving) may not name their table, so the view passes `table_id` for those.
+#[tauri::command]
+pub async fn record_lobby_event(
+    state: State<'_, TableStatsState>,
+    message: WsMessage,
+    table_id: Option<String>,
+) -> Result<(), String> {
+    let now = Instant::now();
+    let payload = &message.payload;
+    let named = payload.get("tableId").and_then(Value::as_str).map(str::to_string).or(table_id);
+    let mut tables = state.tables.lock().map_err(|_| "Table stats lock poisoned".to_string())?;
+    match message.kind.as_str() {
+        "lobby_state" | "lobby_update" | "tables_update" => {
+            let listings = payload.get("tables").and_then(Value::as_array).into_iter().flatten();
+            for (id, players, observers) in listings.filter_map(listing) {
+                tracker(&mut tables, &id, now).crowd(players, observers, now);
+            }
+        }
+        "table_created" | "table_updated" => {
+            if let Some((id, players, observers)) = payload.get("table").and_then(listing) {
+                tracker(&mut tables, &id, now).crowd(players, observers, now);
+            }
+        }
+        "table_removed" => {
+            if let Some(id) = named {
+                tables.remove(&id);
+            }
+        }
+        "spectator_count_update" | "spectator_joined" | "spectator_left" => {
+            let count = ["count", "spectatorCount"].iter().find_map(|key| payload.get(*key).and_then(Value::as_u64));
+            if let (Some(id), Some(count)) = (named, count) {
+                tracker(&mut tables, &id, now).crowd(None, Some(count as u32), now);
+            }
+        }
+        "player_joined" | "player_left" => {
+            if let Some(id) = named {
+                tracker(&mut tables, &id, now).seat_change(message.kind == "player_joined", now);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PopularTable {
+    table_id: String,
+    #[serde(flatten)]
+    stats: TableStats,
+}
+
+// Tables seen in the last hour, most popular first
+#[tauri::command]
+pub async fn get_popular_tables(state: State<'_, TableStatsState>, limit: Option<usize>) -> Result<Vec<PopularTable>, String> {
+    let now = Instant::now();
+    let tables = state.tables.lock().map_err(|_| "Table stats lock poisoned".to_string())?;
+    let mut popular: Vec<PopularTable> = tables
+        .iter()
+        .filter(|(_, t)| now.duration_since(t.last_seen) <= WINDOW)
+        .map(|(id, t)| PopularTable { table_id: id.clone(), stats: t.stats(now) })
+        .collect();
+    popular.sort_by(|a, b| b.stats.popularity.total_cmp(&a.stats.popularity));
+    popular.truncate(limit.unwrap_or(20));
+    Ok(popular)
+}
+
+#[tauri::command]
+pub async fn get_table_stats(state: State<'_, TableStatsState>, table_id: String) -> Result<TableStats, String> {
+    state.stats(&table_id)
+}
@@ -0,0 +1,225 @@
+// Saved table setups. A template is a named `TableConfig` kept in the kv table, so a
+// setup the player creates often (their home game, a heads-up hyper) is one call to
+// `create_table_from_template`. Two presets are there until the player changes the
+// list. Templates are exported as a small JSON document to share with friends and
+// imported from one; every template is checked like a table about to be created.
+
+use crate::db::{Database, Db};
+use crate::error::CommandError;
+use crate::history::AnteStructure;
+use crate::speed::GameSpeed;
+use crate::{Table, TableConfig};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+const KEY_TEMPLATES: &str = "table.templates";
+const EXPORT_FORMAT: &str = "primo-table-templates";
+const EXPORT_VERSION: u32 = 1;
+const MAX_TEMPLATES: usize = 50;
+const MAX_NAME_LEN: usize = 40;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableTemplate {
+    name: String,
+    config: TableConfig,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TemplateExport {
+    format: String,
+    version: u32,
+    exported_at: DateTime<Utc>,
+    templates: Vec<TableTemplate>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateImport {
+    imported: Vec<String>,
+    // Names already taken, kept as they were unless overwriting
+    skipped: Vec<String>,
+    // Templates refused, with the reason
+    rejected: Vec<String>,
+}
+
+fn preset(name: &str, max_players: u8, blinds: (u32, u32), game_speed: GameSpeed, is_private: bool) -> TableTemplate {
+    let (small_blind, big_blind) = blinds;
+    TableTemplate {
+        name: name.to_string(),
+        config: TableConfig {
+            name: name.to_string(),
+            game_type: "texas_holdem".to_string(),
+            betting_structure: "no_limit".to_string(),
+            game_format: "cash".to_string(),
+            max_players,
+            min_buy_in: big_blind * 50,
+            max_buy_in: big_blind * 200,
+            small_blind,
+            big_blind,
+            ante: 0,
+            ante_structure: AnteStructure::Standard,
+            time_bank: 30,
+            is_private,
+            game_speed,
+            fast_fold: false,
+        },
+        updated_at: Utc::now(),
+    }
+}
+
+fn default_templates() -> Vec<TableTemplate> {
+    vec![
+        preset("Home Game", 9, (25, 50), GameSpeed::Regular, true),
+        preset("Heads-Up Hyper", 2, (50, 100), GameSpeed::Hyper, false),
+    ]
+}
+
+fn load(db: &Database) -> Result<Vec<TableTemplate>, String> {
+    match db.get_value(KEY_TEMPLATES)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid table templates: {}", e)),
+        None => Ok(default_templates()),
+    }
+}
+
+fn save(db: &Database, templates: &[TableTemplate]) -> Result<(), String> {
+    let data = serde_json::to_string(templates).map_err(|e| e.to_string())?;
+    db.set_value(KEY_TEMPLATES, &data)
+}
+
+fn validate(name: &str, config: &TableConfig) -> Result<(), String> {
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(format!("Template names must be 1 to {} characters", MAX_NAME_LEN));
+    }
+    crate::validate_table_config(config)
+}
+
+fn position(templates: &[TableTemplate], name: &str) -> Option<usize> {
+    templates.iter().position(|t| t.name.eq_ignore_ascii_case(name))
+}
+
+// Add `template` or replace the one of the same name; false when the name is taken
+// and `overwrite` is not set
+fn upsert(templates: &mut Vec<TableTemplate>, template: TableTemplate, overwrite: bool) -> Result<bool, String> {
+    match position(templates, &template.name) {
+        Some(_) if !overwrite => Ok(false),
+        Some(i) => {
+            templates[i] = template;
+            Ok(true)
+        }
+        None if templates.len() >= MAX_TEMPLATES => Err(format!("At most {} templates can be kept", MAX_TEMPLATES)),
+        None => {
+            templates.push(template);
+            Ok(true)
+        }
+    }
+}
+
+// The setup saved as `name`
+pub fn template_config(db: &Database, name: &str) -> Result<TableConfig, String> {
+    let templates = load(db)?;
+    position(&templates, name.trim())
+        .map(|i| templates[i].config.clone())
+        .ok_or_else(|| format!("No template named {}", name))
+}
+
+#[tauri::command]
+pub async fn list_table_templates(db: Db<'_>) -> Result<Vec<TableTemplate>, String> {
+    load(&db)
+}
+
+// Save `config` as `name`, replacing a template of the same name
+#[tauri::command]
+pub async fn save_table_template(
+    db: Db<'_>,
+    name: String,
+    config: TableConfig,
+) -> Result<TableTemplate, String> {
+    let name = name.trim().to_string();
+    validate(&name, &config)?;
+    let mut templates = load(&db)?;
+    let template = TableTemplate { name, config, updated_at: Utc::now() };
+    upsert(&mut templates, template.clone(), true)?;
+    save(&db, &templates)?;
+    Ok(template)
+}
+
+#[tauri::command]
+pub async fn delete_table_template(db: Db<'_>, name: String) -> Result<(), String> {
+    let mut templates = load(&db)?;
+    let index = position(&templates, name.trim()).ok_or_else(|| format!("No template named {}", name))?;
+    templates.remove(index);
+    save(&db, &templates)
+}
+
+// Create a table from the template `name`, under `table_name` when given
+#[tauri::command]
+pub async fn create_table_from_template(
+    app: AppHandle,
+    db: Db<'_>,
+    api_url: String,
+    name: String,
+    table_name: Option<String>,
+) -> Result<Table, CommandError> {
+    let mut config = template_config(&db, &name)?;
+    if let Some(table_name) = table_name {
+        config.name = table_name.trim().to_string();
+    }
+    crate::create_table(app, api_url, config).await
+}
+
+// The templates named, or all of them, as a document to share
+#[tauri::command]
+pub async fn export_table_templates(db: Db<'_>, names: Option<Vec<String>>) -> Result<String, String> {
+    let templates: Vec<TableTemplate> = load(&db)?
+        .into_iter()
+        .filter(|t| names.as_ref().is_none_or(|names| names.iter().any(|n| t.name.eq_ignore_ascii_case(n.trim()))))
+        .collect();
+    if templates.is_empty() {
+        return Err("No templates to export".to_string());
+    }
+    let export = TemplateExport {
+        format: EXPORT_FORMAT.to_string(),
+        version: EXPORT_VERSION,
+        exported_at: Utc::now(),
+        templates,
+    };
+    serde_json::to_string_pretty(&export).map_err(|e| e.to_string())
+}
+
+// Add the templates of an exported document. Templates whose name is taken are
+// skipped unless `overwrite` is set.
+#[tauri::command]
+pub async fn import_table_templates(
+    db: Db<'_>,
+    data: String,
+    overwrite: Option<bool>,
+) -> Result<TemplateImport, String> {
+    let export: TemplateExport =
+        serde_json::from_str(&data).map_err(|e| format!("Not a table template export: {}", e))?;
+    if export.format != EXPORT_FORMAT || export.version > EXPORT_VERSION {
+        return Err(format!("Unsupported template export {} version {}", export.format, export.version));
+    }
+
+    let mut templates = load(&db)?;
+    let mut report = TemplateImport::default();
+    for mut template in export.templates {
+        template.name = template.name.trim().to_string();
+        if let Err(e) = validate(&template.name, &template.config) {
+            report.rejected.push(format!("{}: {}", template.name, e));
+            continue;
+        }
+        let name = template.name.clone();
+        template.updated_at = Utc::now();
+        match upsert(&mut templates, template, overwrite.unwrap_or(false)) {
+            Ok(true) => report.imported.push(name),
+            Ok(false) => report.skipped.push(name),
+            Err(e) => report.rejected.push(format!("{}: {}", name, e)),
+        }
+    }
+    save(&db, &templates)?;
+    Ok(report)
+}
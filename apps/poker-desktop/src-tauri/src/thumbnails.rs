@@ -0,0 +1,324 @@
+// Table thumbnails for the table switcher overlay. Each open table view reports its
+// state as it changes; the latest one is rendered into a small SVG (seats around an
+// oval, stacks, who is to act, the pot) plus a compact summary, throttled per table;
+// a state reported inside the throttle window is drawn once the window is over.
+// `get_table_thumbnails` returns every table at once with those waiting on the hero
+// first, so the overlay can show a dozen tables without asking each view to draw.
+// The latest reported state is kept unthrottled for the bet slider in sizing.rs.
+
+use crate::db::{self, Database, Db};
+use crate::memory::{self, approx_size, CacheUsage, MemoryCache};
+use crate::notes::{self, PlayerLabel};
+use crate::sizing::{self, TableRules};
+use crate::table_state::TableMirror;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+
+const MIN_INTERVAL: Duration = Duration::from_millis(500);
+// Tables not reported for this long are assumed closed
+const STALE_AFTER: Duration = Duration::from_secs(600);
+const WIDTH: f64 = 160.0;
+const HEIGHT: f64 = 100.0;
+const MIN_SEATS: usize = 6;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeatSummary {
+    seat: usize,
+    username: String,
+    chips: u32,
+    folded: bool,
+    all_in: bool,
+    to_act: bool,
+    hero: bool,
+    label: Option<PlayerLabel>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableThumbnail {
+    table_id: String,
+    phase: String,
+    pot: u32,
+    hand_number: u32,
+    seats: Vec<SeatSummary>,
+    hero_to_act: bool,
+    // SVG data URL
+    image: String,
+    updated_at: DateTime<Utc>,
+}
+
+struct Entry {
+    rendered_at: Instant,
+    thumbnail: TableThumbnail,
+    mirror: TableMirror,
+    rules: TableRules,
+    hero_id: Option<String>,
+    // A render of the latest state is scheduled for the end of the throttle window
+    trailing: bool,
+}
+
+fn entry_size(entry: &Entry) -> usize {
+    approx_size(&entry.thumbnail) + approx_size(&entry.mirror)
+}
+
+#[derive(Default)]
+pub struct ThumbnailState {
+    tables: Mutex<HashMap<String, Entry>>,
+}
+
+impl ThumbnailState {
+    // Latest state reported for a table, with its rules and the hero's id
+    pub fn latest(&self, table_id: &str) -> Result<Option<(TableMirror, TableRules, Option<String>)>, String> {
+        let tables = self.tables.lock().map_err(|_| "Thumbnail lock poisoned".to_string())?;
+        Ok(tables.get(table_id).map(|e| (e.mirror.clone(), e.rules, e.hero_id.clone())))
+    }
+
+    // Players seated at each table still being reported
+    pub fn seated(&self) -> Result<HashMap<String, Vec<String>>, String> {
+        let tables = self.tables.lock().map_err(|_| "Thumbnail lock poisoned".to_string())?;
+        Ok(tables
+            .iter()
+            .filter(|(_, e)| e.rendered_at.elapsed() < STALE_AFTER)
+            .map(|(id, e)| (id.clone(), e.mirror.players.iter().map(|p| p.id.clone()).collect()))
+            .collect())
+    }
+
+    // The hero's chips at each table still being reported, bets in front included,
+    // with the table's big blind
+    pub fn hero_stacks(&self) -> Result<Vec<(String, u32, u32)>, String> {
+        let tables = self.tables.lock().map_err(|_| "Thumbnail lock poisoned".to_string())?;
+        Ok(tables
+            .iter()
+            .filter(|(_, e)| e.rendered_at.elapsed() < STALE_AFTER)
+            .filter_map(|(id, e)| {
+                let hero = e.mirror.player(e.hero_id.as_deref()?)?;
+                Some((id.clone(), hero.chips + hero.current_bet, e.rules.big_blind))
+            })
+            .collect())
+    }
+
+    pub fn forget(&self, table_id: &str) -> Result<(), String> {
+        self.tables.lock().map_err(|_| "Thumbnail lock poisoned".to_string())?.remove(table_id);
+        Ok(())
+    }
+
+    // The hero took a seat at a table watched so far as a spectator
+    pub fn set_hero(&self, table_id: &str, hero_id: &str) -> Result<(), String> {
+        let mut tables = self.tables.lock().map_err(|_| "Thumbnail lock poisoned".to_string())?;
+        if let Some(entry) = tables.get_mut(table_id) {
+            entry.hero_id = Some(hero_id.to_string());
+        }
+        Ok(())
+    }
+}
+
+impl MemoryCache for ThumbnailState {
+    fn usage(&self) -> CacheUsage {
+        let Ok(tables) = self.tables.lock() else { return CacheUsage::default() };
+        CacheUsage { entries: tables.len(), bytes: tables.values().map(entry_size).sum() }
+    }
+
+    fn trim_to(&self, max_bytes: usize) {
+        if let Ok(mut tables) = self.tables.lock() {
+            memory::trim_oldest(&mut tables, max_bytes, entry_size, |e| e.rendered_at);
+        }
+    }
+}
+
+// 1234 -> "1.2k", 2500000 -> "2.5M"
+fn short_chips(chips: u32) -> String {
+    match chips {
+        0..=999 => chips.to_string(),
+        1_000..=999_999 => format!("{:.1}k", chips as f64 / 1_000.0),
+        _ => format!("{:.1}M", chips as f64 / 1_000_000.0),
+    }
+}
+
+fn summarize(
+    mirror: &TableMirror,
+    hero_id: Option<&str>,
+    labels: &mut HashMap<String, PlayerLabel>,
+) -> Vec<SeatSummary> {
+    let mut seats: Vec<SeatSummary> = mirror
+        .players
+        .iter()
+        .enumerate()
+        .map(|(index, player)| SeatSummary {
+            seat: player.position.as_ref().map_or(index, |p| p.seat as usize),
+            username: player.username.clone(),
+            chips: player.chips,
+            folded: player.is_folded,
+            all_in: player.is_all_in,
+            to_act: mirror.active_player_id.as_deref() == Some(player.id.as_str()),
+            hero: hero_id == Some(player.id.as_str()),
+            label: labels.remove(&player.id),
+        })
+        .collect();
+    seats.sort_by_key(|s| s.seat);
+    seats
+}
+
+fn render(seats: &[SeatSummary], pot: u32) -> String {
+    let count = seats.iter().map(|s| s.seat + 1).max().unwrap_or(0).max(seats.len()).max(MIN_SEATS);
+    let (cx, cy) = (WIDTH / 2.0, HEIGHT / 2.0);
+    let (rx, ry) = (WIDTH / 2.0 - 14.0, HEIGHT / 2.0 - 12.0);
+
+    let seat_marks: String = seats
+        .iter()
+        .map(|seat| {
+            // Seat 0 at the bottom, going clockwise
+            let angle = std::f64::consts::PI / 2.0 + seat.seat as f64 * 2.0 * std::f64::consts::PI / count as f64;
+            let (x, y) = (cx + rx * angle.cos(), cy + ry * angle.sin());
+            let fill = if seat.to_act {
+                "#f5c542"
+            } else if seat.folded {
+                "#555"
+            } else if seat.all_in {
+                "#d9534f"
+            } else {
+                "#3a7bd5"
+            };
+            let ring = if seat.hero { r##" stroke="#fff" stroke-width="2""## } else { "" };
+            format!(
+                r#"<circle cx="{:.1}" cy="{:.1}" r="8" fill="{}"{}/><text x="{:.1}" y="{:.1}">{}</text>"#,
+                x, y, fill, ring, x, y + 17.0, short_chips(seat.chips)
+            )
+        })
+        .collect();
+    let svg = format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" font-family="sans-serif" font-size="9" text-anchor="middle" fill="#fff"><ellipse cx="{cx}" cy="{cy}" rx="{erx}" ry="{ery}" fill="#1d5e3a" stroke="#0d3320" stroke-width="3"/><text x="{cx}" y="{py}">{pot}</text>{seats}</svg>"##,
+        w = WIDTH,
+        h = HEIGHT,
+        cx = cx,
+        cy = cy,
+        erx = rx - 6.0,
+        ery = ry - 8.0,
+        py = cy + 3.0,
+        pot = short_chips(pot),
+        seats = seat_marks,
+    );
+    format!("data:image/svg+xml;base64,{}", STANDARD.encode(svg))
+}
+
+fn thumbnail(db: &Database, table_id: &str, mirror: &TableMirror, hero_id: Option<&str>) -> Result<TableThumbnail, String> {
+    let mut labels = db.with_conn(|conn| notes::labels_for(conn, mirror.players.iter().map(|p| p.id.as_str())))?;
+    let seats = summarize(mirror, hero_id, &mut labels);
+    Ok(TableThumbnail {
+        table_id: table_id.to_string(),
+        phase: mirror.phase.clone(),
+        pot: mirror.pot,
+        hand_number: mirror.hand_number,
+        hero_to_act: seats.iter().any(|s| s.hero && s.to_act),
+        image: render(&seats, mirror.pot),
+        seats,
+        updated_at: Utc::now(),
+    })
+}
+
+// Render whatever state the table reported last once `wait` is over
+fn render_trailing(app: &AppHandle, table_id: String, wait: Duration) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(wait).await;
+        let state = app.state::<ThumbnailState>();
+        let latest = {
+            let Ok(mut tables) = state.tables.lock() else { return };
+            let Some(entry) = tables.get_mut(&table_id) else { return };
+            entry.trailing = false;
+            (entry.mirror.clone(), entry.hero_id.clone())
+        };
+        let rendered = db::get(&app).and_then(|db| thumbnail(&db, &table_id, &latest.0, latest.1.as_deref()));
+        let thumbnail = match rendered {
+            Ok(thumbnail) => thumbnail,
+            Err(e) => {
+                eprintln!("Thumbnail for table {} failed: {}", table_id, e);
+                return;
+            }
+        };
+        let Ok(mut tables) = state.tables.lock() else { return };
+        // Closed while rendering
+        let Some(entry) = tables.get_mut(&table_id) else { return };
+        entry.rendered_at = Instant::now();
+        entry.thumbnail = thumbnail.clone();
+        let _ = app.emit_all("table_thumbnail_updated", thumbnail);
+    });
+}
+
+// Report the state of one open table. `table_state` is the state as the backend
+// sends it. Returns None when the table was rendered too recently to redraw; it is
+// then drawn, and `table_thumbnail_updated` raised, at the end of the window.
+#[tauri::command]
+pub async fn update_table_thumbnail(
+    app: AppHandle,
+    db: Db<'_>,
+    state: State<'_, ThumbnailState>,
+    table_id: String,
+    table_state: Value,
+    hero_id: Option<String>,
+) -> Result<Option<TableThumbnail>, String> {
+    let mirror = crate::preview::mirror_from_state(&table_state)?;
+    let mut rules = TableRules::from_state(&table_state);
+    let mut tables = state.tables.lock().map_err(|_| "Thumbnail lock poisoned".to_string())?;
+    let previous = tables.get(&table_id).map(|e| (e.mirror.hand_number, e.mirror.phase.clone()));
+    if let Some(entry) = tables.get_mut(&table_id) {
+        // Partial updates leave the blinds out; keep what an earlier state said
+        if rules.big_blind == 0 {
+            rules = entry.rules;
+        }
+    }
+    // A street was dealt since the last report: tell the table how betting works on it
+    if previous != Some((mirror.hand_number, mirror.phase.clone())) {
+        if let Some(hint) = sizing::street_hint(&mirror, &rules) {
+            let _ = app.emit_all("betting_hint", hint);
+        }
+    }
+    if let Some(entry) = tables.get_mut(&table_id) {
+        let elapsed = entry.rendered_at.elapsed();
+        if elapsed < MIN_INTERVAL {
+            entry.mirror = mirror;
+            entry.rules = rules;
+            entry.hero_id = hero_id;
+            if !entry.trailing {
+                entry.trailing = true;
+                render_trailing(&app, table_id, MIN_INTERVAL - elapsed);
+            }
+            return Ok(None);
+        }
+    }
+
+    drop(tables);
+    let thumbnail = thumbnail(&db, &table_id, &mirror, hero_id.as_deref())?;
+    let mut tables = state.tables.lock().map_err(|_| "Thumbnail lock poisoned".to_string())?;
+    let trailing = tables.get(&table_id).is_some_and(|e| e.trailing);
+    tables.insert(
+        table_id,
+        Entry { rendered_at: Instant::now(), thumbnail: thumbnail.clone(), mirror, rules, hero_id, trailing },
+    );
+    let _ = app.emit_all("table_thumbnail_updated", thumbnail.clone());
+    Ok(Some(thumbnail))
+}
+
+// Thumbnails of every open table, those waiting on the hero first, then most
+// recently updated
+#[tauri::command]
+pub async fn get_table_thumbnails(state: State<'_, ThumbnailState>) -> Result<Vec<TableThumbnail>, String> {
+    let mut tables = state.tables.lock().map_err(|_| "Thumbnail lock poisoned".to_string())?;
+    tables.retain(|_, e| e.rendered_at.elapsed() < STALE_AFTER);
+    let mut thumbnails: Vec<TableThumbnail> = tables.values().map(|e| e.thumbnail.clone()).collect();
+    thumbnails.sort_by(|a, b| b.hero_to_act.cmp(&a.hero_to_act).then(b.updated_at.cmp(&a.updated_at)));
+    Ok(thumbnails)
+}
+
+// Called when a table view closes
+#[tauri::command]
+pub async fn remove_table_thumbnail(state: State<'_, ThumbnailState>, table_id: String) -> Result<(), String> {
+    state.forget(&table_id)
+}
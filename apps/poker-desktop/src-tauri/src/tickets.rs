@@ -0,0 +1,382 @@
+// Tournament tickets won in satellites. The inventory is cached in the kv table and
+// each ticket is matched against the cached tournament schedule to show the events
+// it can be spent on. A ticket can be set to register itself into one of them: the
+// tournament loop (see tournaments.rs) registers it once entries open and retries a
+// few times if the backend refuses. Events the player is already in that start
+// close to the target are reported as conflicts, both when the auto-registration
+// is set and when it goes through.
+
+use crate::audit;
+use crate::clock::ClockState;
+use crate::db::{self, Database, Db};
+use crate::maintenance;
+use crate::profile::BackendProfile;
+use crate::tournaments::{self, Tournament};
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tauri::{AppHandle, Manager, State};
+
+const KEY_INVENTORY: &str = "tickets.inventory";
+const KEY_AUTO: &str = "tickets.auto_register";
+
+// Events starting this close together are treated as clashing
+const CONFLICT_WINDOW_MINS: i64 = 120;
+const MAX_ATTEMPTS: u32 = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Ticket {
+    id: String,
+    name: String,
+    value: u64,
+    // Tournament ids the ticket buys into
+    #[serde(default)]
+    valid_for: Vec<String>,
+    #[serde(default)]
+    expires_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    won_in: Option<String>,
+    #[serde(flatten)]
+    details: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TicketInventory {
+    tickets: Vec<Ticket>,
+    fetched_at: DateTime<Utc>,
+    #[serde(default)]
+    offline: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TicketEvent {
+    tournament_id: String,
+    name: String,
+    starts_at: DateTime<Utc>,
+    registration_opens_at: DateTime<Utc>,
+}
+
+impl TicketEvent {
+    fn of(tournament: &Tournament) -> Self {
+        Self {
+            tournament_id: tournament.id.clone(),
+            name: tournament.name.clone(),
+            starts_at: tournament.start_time,
+            registration_opens_at: tournament.registration_opens_at(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoRegistration {
+    ticket_id: String,
+    event: TicketEvent,
+    // pending | registered | failed
+    status: String,
+    attempts: u32,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    conflicts: Vec<TicketEvent>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TicketEntry {
+    #[serde(flatten)]
+    ticket: Ticket,
+    // Scheduled events the ticket is valid for, soonest first
+    events: Vec<TicketEvent>,
+    auto_registration: Option<AutoRegistration>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TicketList {
+    tickets: Vec<TicketEntry>,
+    fetched_at: DateTime<Utc>,
+    offline: bool,
+}
+
+fn load<T: serde::de::DeserializeOwned>(db: &Database, key: &str) -> Result<Option<T>, String> {
+    match db.get_value(key)? {
+        Some(data) => serde_json::from_str(&data).map(Some).map_err(|e| format!("Invalid {}: {}", key, e)),
+        None => Ok(None),
+    }
+}
+
+fn save<T: Serialize>(db: &Database, key: &str, value: &T) -> Result<(), String> {
+    let data = serde_json::to_string(value).map_err(|e| e.to_string())?;
+    db.set_value(key, &data)
+}
+
+fn auto_registrations(db: &Database) -> Result<Vec<AutoRegistration>, String> {
+    Ok(load(db, KEY_AUTO)?.unwrap_or_default())
+}
+
+// Read, change and store the auto-registrations in one transaction, so a
+// registration finishing in the background cannot write back a list from before a
+// cancel. Nothing is stored when `f` fails.
+fn update_autos<T>(
+    db: &Database,
+    f: impl FnOnce(&mut Vec<AutoRegistration>) -> Result<T, String>,
+) -> Result<T, String> {
+    db.with_conn(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        let stored: Option<String> =
+            tx.query_row("SELECT value FROM kv WHERE key = ?1", [KEY_AUTO], |row| row.get(0)).optional()?;
+        let parsed = match stored {
+            Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid {}: {}", KEY_AUTO, e)),
+            None => Ok(Vec::new()),
+        };
+        let updated = parsed.and_then(|mut autos| {
+            let result = f(&mut autos)?;
+            Ok((result, serde_json::to_string(&autos).map_err(|e| e.to_string())?))
+        });
+        let Ok((result, data)) = updated else { return Ok(updated.map(|(result, _)| result)) };
+        tx.execute(
+            "INSERT INTO kv (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            [KEY_AUTO, &data],
+        )?;
+        tx.commit()?;
+        Ok(Ok(result))
+    })?
+}
+
+async fn send(request: reqwest::RequestBuilder, what: &str) -> Result<Value, String> {
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+    let response = crate::http::send(request.header("Authorization", format!("Bearer {}", token)))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("{} failed: {}", what, error_text));
+    }
+
+    let api_response: crate::ApiResponse<Value> = response.json().await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    if api_response.success {
+        Ok(api_response.data.unwrap_or(json!({})))
+    } else {
+        Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()))
+    }
+}
+
+async fn fetch_tickets(api_url: &str) -> Result<Vec<Ticket>, String> {
+    let client = crate::create_http_client()?;
+    let data = send(client.get(format!("{}/api/tickets", api_url)), "Ticket fetch").await?;
+    serde_json::from_value(data).map_err(|e| format!("Invalid tickets: {}", e))
+}
+
+async fn register(api_url: &str, ticket_id: &str, tournament_id: &str) -> Result<Value, String> {
+    let client = crate::create_http_client()?;
+    let request = client
+        .post(format!("{}/api/tournaments/{}/register", api_url, tournament_id))
+        .json(&json!({ "ticketId": ticket_id }));
+    send(request, "Registration").await
+}
+
+// Events the player is in, or on their way into, starting close to `target`
+fn conflicts(schedule: &[Tournament], autos: &[AutoRegistration], target: &TicketEvent) -> Vec<TicketEvent> {
+    let window = Duration::minutes(CONFLICT_WINDOW_MINS);
+    let close = |at: DateTime<Utc>| (at - target.starts_at).abs() < window;
+    let mut events: Vec<TicketEvent> = schedule
+        .iter()
+        .filter(|t| t.id != target.tournament_id && t.is_registered() && close(t.start_time))
+        .map(TicketEvent::of)
+        .collect();
+    for auto in autos.iter().filter(|a| a.status != "failed") {
+        let event = &auto.event;
+        if event.tournament_id != target.tournament_id
+            && close(event.starts_at)
+            && !events.iter().any(|e| e.tournament_id == event.tournament_id)
+        {
+            events.push(event.clone());
+        }
+    }
+    events.sort_by_key(|e| e.starts_at);
+    events
+}
+
+fn valid_events(schedule: &[Tournament], ticket: &Ticket, now: DateTime<Utc>) -> Vec<TicketEvent> {
+    schedule
+        .iter()
+        .filter(|t| ticket.valid_for.contains(&t.id) && t.start_time > now)
+        .filter(|t| ticket.expires_at.is_none_or(|expires| t.start_time <= expires))
+        .map(TicketEvent::of)
+        .collect()
+}
+
+// Register pending auto-registrations whose events have opened. Run from the
+// tournament loop on every check; held back while maintenance is coming. Each result
+// is stored against the list as it is after the request, and dropped when the
+// player cancelled or changed that choice in the meantime.
+pub async fn check_auto_registrations(app: &AppHandle, now: DateTime<Utc>) -> Result<(), String> {
+    if maintenance::is_draining(app) {
+        return Ok(());
+    }
+    let db = db::get(app)?;
+    let due: Vec<AutoRegistration> = update_autos(&db, |autos| {
+        autos.retain(|a| a.event.starts_at > now - Duration::days(1));
+        Ok(autos
+            .iter()
+            .filter(|a| a.status == "pending" && a.event.registration_opens_at <= now)
+            .cloned()
+            .collect())
+    })?;
+    if due.is_empty() {
+        return Ok(());
+    }
+
+    let api_url = app.state::<BackendProfile>().api_url.clone();
+    let schedule = tournaments::cached_schedule(&db)?;
+    for auto in due {
+        let found = conflicts(&schedule, &auto_registrations(&db)?, &auto.event);
+        let result = register(&api_url, &auto.ticket_id, &auto.event.tournament_id).await;
+        audit::record(
+            app,
+            "ticket_auto_register",
+            json!({ "ticketId": auto.ticket_id, "tournamentId": auto.event.tournament_id }),
+            &result,
+        );
+        let updated = update_autos(&db, |autos| {
+            let Some(entry) = autos.iter_mut().find(|a| {
+                a.ticket_id == auto.ticket_id
+                    && a.event.tournament_id == auto.event.tournament_id
+                    && a.status == "pending"
+            }) else {
+                return Ok(None);
+            };
+            entry.conflicts = found;
+            entry.attempts += 1;
+            match result {
+                Ok(_) => {
+                    entry.status = "registered".to_string();
+                    entry.error = None;
+                }
+                Err(e) => {
+                    if entry.attempts >= MAX_ATTEMPTS {
+                        entry.status = "failed".to_string();
+                    }
+                    entry.error = Some(e);
+                }
+            }
+            Ok(Some(entry.clone()))
+        })?;
+        if let Some(entry) = updated.filter(|e| e.status != "pending") {
+            let _ = app.emit_all("ticket_auto_registered", entry);
+        }
+    }
+    Ok(())
+}
+
+// Won tickets with the events each is valid for. Cached unless `refresh` is set or
+// nothing is cached yet; the cached copy is used when the backend cannot be reached.
+#[tauri::command]
+pub async fn list_tickets(
+    db: Db<'_>,
+    clock: State<'_, ClockState>,
+    api_url: String,
+    refresh: Option<bool>,
+) -> Result<TicketList, String> {
+    let now = clock.server_now();
+    let cached: Option<TicketInventory> = load(&db, KEY_INVENTORY)?;
+    let inventory = match cached {
+        Some(inventory) if !refresh.unwrap_or(false) => inventory,
+        cached => match fetch_tickets(&api_url).await {
+            Ok(tickets) => {
+                let inventory = TicketInventory { tickets, fetched_at: now, offline: false };
+                save(&db, KEY_INVENTORY, &inventory)?;
+                inventory
+            }
+            Err(e) => match cached {
+                Some(inventory) => {
+                    eprintln!("Ticket fetch failed, using cached copy: {}", e);
+                    TicketInventory { offline: true, ..inventory }
+                }
+                None => return Err(e),
+            },
+        },
+    };
+
+    let schedule = tournaments::cached_schedule(&db)?;
+    let autos = auto_registrations(&db)?;
+    let tickets = inventory
+        .tickets
+        .into_iter()
+        .map(|ticket| {
+            let mut events = valid_events(&schedule, &ticket, now);
+            events.sort_by_key(|e| e.starts_at);
+            let auto_registration = autos.iter().find(|a| a.ticket_id == ticket.id).cloned();
+            TicketEntry { ticket, events, auto_registration }
+        })
+        .collect();
+    Ok(TicketList { tickets, fetched_at: inventory.fetched_at, offline: inventory.offline })
+}
+
+// Spend `ticket_id` on `tournament_id` once its registration opens, replacing any
+// earlier choice for the ticket. Clashing events come back in `conflicts`.
+#[tauri::command]
+pub async fn set_ticket_auto_register(
+    db: Db<'_>,
+    clock: State<'_, ClockState>,
+    ticket_id: String,
+    tournament_id: String,
+) -> Result<AutoRegistration, String> {
+    let now = clock.server_now();
+    let inventory: TicketInventory =
+        load(&db, KEY_INVENTORY)?.ok_or_else(|| "Load the ticket inventory first".to_string())?;
+    let ticket = inventory
+        .tickets
+        .iter()
+        .find(|t| t.id == ticket_id)
+        .ok_or_else(|| format!("Ticket {} is not in the inventory", ticket_id))?;
+    let schedule = tournaments::cached_schedule(&db)?;
+    let event = valid_events(&schedule, ticket, now)
+        .into_iter()
+        .find(|e| e.tournament_id == tournament_id)
+        .ok_or_else(|| "The ticket is not valid for an upcoming event with that id".to_string())?;
+
+    update_autos(&db, |autos| {
+        autos.retain(|a| a.ticket_id != ticket_id);
+        let auto = AutoRegistration {
+            ticket_id,
+            conflicts: conflicts(&schedule, autos, &event),
+            event,
+            status: "pending".to_string(),
+            attempts: 0,
+            error: None,
+        };
+        autos.push(auto.clone());
+        Ok(auto)
+    })
+}
+
+#[tauri::command]
+pub async fn cancel_ticket_auto_register(db: Db<'_>, ticket_id: String) -> Result<(), String> {
+    update_autos(&db, |autos| {
+        autos.retain(|a| a.ticket_id != ticket_id);
+        Ok(())
+    })
+}
+
+// Spend a ticket on an event right away
+#[tauri::command]
+pub async fn register_with_ticket(
+    app: AppHandle,
+    api_url: String,
+    ticket_id: String,
+    tournament_id: String,
+) -> Result<Value, String> {
+    let result = register(&api_url, &ticket_id, &tournament_id).await;
+    audit::record(&app, "register_with_ticket", json!({ "ticketId": ticket_id, "tournamentId": tournament_id }), &result);
+    result
+}
@@ -0,0 +1,303 @@
+// Tilt self-monitor, off until the player turns it on. After each hand they play it
+// looks for three signs: VPIP this session well above their own norm (the live
+// counters of the open tables against the stored hands from before the session),
+// several rebuys in a short time, and a session running well past their usual length
+// (the ledger's finished sessions). A sign raises `tilt_detected` the first time it
+// shows in a session, with the intervention chosen for it: a warning, a prompt to take
+// a break, or a cooldown during which no table can be joined. The cooldown is kept in
+// the database and only runs out, so neither a restart nor turning the monitor off
+// ends it early.
+
+use crate::audit;
+use crate::claims;
+use crate::db::{Database, Db};
+use crate::history::{self, to_millis, HandRecord};
+use crate::ledger;
+use crate::live_stats::LiveStatsState;
+use crate::players;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+const KEY_SETTINGS: &str = "tilt.settings";
+const KEY_COOLDOWN: &str = "tilt.cooldown_until";
+// Stored hands the VPIP norm is read from, and how many it needs
+const NORM_HANDS: u32 = 1000;
+const MIN_NORM_HANDS: u32 = 200;
+// Finished sessions the usual length is the median of, and how many it needs
+const NORM_SESSIONS: u32 = 20;
+const MIN_NORM_SESSIONS: usize = 5;
+// Sessions shorter than this are never long
+const MIN_LONG_SESSION_MINUTES: i64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Intervention {
+    Warning,
+    // Ask the player to step away for `break_minutes`
+    Break,
+    // Refuse table joins for `cooldown_minutes`
+    Cooldown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TiltSettings {
+    pub enabled: bool,
+    // Session VPIP above the norm that counts, as a share of hands
+    pub vpip_rise: f64,
+    // Hands this session before VPIP is compared
+    pub min_session_hands: u32,
+    pub max_rebuys: u32,
+    pub rebuy_window_minutes: u32,
+    // A session this many times the usual length is long
+    pub session_length_factor: f64,
+    pub on_signal: Intervention,
+    // When two or more signs show at once
+    pub on_multiple: Intervention,
+    pub break_minutes: u32,
+    pub cooldown_minutes: u32,
+}
+
+impl Default for TiltSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            vpip_rise: 0.12,
+            min_session_hands: 40,
+            max_rebuys: 3,
+            rebuy_window_minutes: 60,
+            session_length_factor: 1.5,
+            on_signal: Intervention::Warning,
+            on_multiple: Intervention::Break,
+            break_minutes: 15,
+            cooldown_minutes: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignalKind {
+    Vpip,
+    Rebuys,
+    SessionLength,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TiltSignal {
+    kind: SignalKind,
+    // What was seen and where the sign starts: VPIP as a share, rebuys as a count,
+    // session length in minutes
+    value: f64,
+    threshold: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TiltStatus {
+    enabled: bool,
+    session_started_at: Option<DateTime<Utc>>,
+    session_minutes: Option<i64>,
+    usual_session_minutes: Option<i64>,
+    session_hands: u32,
+    session_vpip: Option<f64>,
+    usual_vpip: Option<f64>,
+    recent_rebuys: u32,
+    signals: Vec<TiltSignal>,
+    cooldown_until: Option<DateTime<Utc>>,
+}
+
+// A value with the start of the session it belongs to
+type PerSession<T> = (Option<DateTime<Utc>>, T);
+
+#[derive(Default)]
+pub struct TiltState {
+    // Signs already raised this session
+    raised: Mutex<PerSession<HashSet<SignalKind>>>,
+    // The VPIP norm, read once per session
+    usual_vpip: Mutex<Option<PerSession<Option<f64>>>>,
+}
+
+fn load_settings(db: &Database) -> Result<TiltSettings, String> {
+    match db.get_value(KEY_SETTINGS)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid tilt settings: {}", e)),
+        None => Ok(TiltSettings::default()),
+    }
+}
+
+fn cooldown_until(db: &Database) -> Result<Option<DateTime<Utc>>, String> {
+    let until = db
+        .get_value(KEY_COOLDOWN)?
+        .and_then(|millis| millis.parse().ok())
+        .and_then(|millis| Utc.timestamp_millis_opt(millis).single());
+    Ok(until.filter(|until| *until > Utc::now()))
+}
+
+fn median(mut values: Vec<i64>) -> Option<i64> {
+    values.sort_unstable();
+    values.get(values.len() / 2).copied()
+}
+
+// VPIP over the stored hands from before the session
+fn usual_vpip(app: &AppHandle, db: &Database, hero: &str, started_at: Option<DateTime<Utc>>) -> Result<Option<f64>, String> {
+    let state = app.state::<TiltState>();
+    if let Some((session, vpip)) = *state.usual_vpip.lock().map_err(|_| "Tilt state lock poisoned".to_string())? {
+        if session == started_at {
+            return Ok(vpip);
+        }
+    }
+    let before = started_at.map(|at| (to_millis(&at), ""));
+    let hands = db.with_conn(|conn| history::hands_before(conn, before, NORM_HANDS))?;
+    let vpip = players::local_stats(&hands, &HashSet::from([hero]))
+        .remove(hero)
+        .filter(|stats| stats.hands >= MIN_NORM_HANDS)
+        .and_then(|stats| stats.vpip);
+    if let Ok(mut cached) = state.usual_vpip.lock() {
+        *cached = Some((started_at, vpip));
+    }
+    Ok(vpip)
+}
+
+// Where the player stands now against their own norms
+fn assess(app: &AppHandle, db: &Database, settings: &TiltSettings) -> Result<TiltStatus, String> {
+    let hero = claims::current()?.user_id;
+    let now = Utc::now();
+    let started_at = ledger::session_started(db)?;
+
+    let (session_hands, vpip_hands) = app.state::<LiveStatsState>().totals(&hero);
+    let session_vpip = (session_hands > 0).then(|| vpip_hands as f64 / session_hands as f64);
+    let usual_vpip = usual_vpip(app, db, &hero, started_at)?;
+
+    let recent_rebuys = ledger::rebuys_since(db, now - Duration::minutes(settings.rebuy_window_minutes as i64))?;
+
+    let session_minutes = started_at.map(|at| (now - at).num_minutes());
+    let lengths = ledger::session_lengths(db, NORM_SESSIONS)?;
+    let usual_session_minutes = (lengths.len() >= MIN_NORM_SESSIONS)
+        .then(|| median(lengths.iter().map(Duration::num_minutes).collect()))
+        .flatten();
+
+    let mut signals = Vec::new();
+    if let (Some(vpip), Some(usual)) = (session_vpip, usual_vpip) {
+        if session_hands >= settings.min_session_hands && vpip >= usual + settings.vpip_rise {
+            signals.push(TiltSignal { kind: SignalKind::Vpip, value: vpip, threshold: usual + settings.vpip_rise });
+        }
+    }
+    if recent_rebuys >= settings.max_rebuys {
+        signals.push(TiltSignal {
+            kind: SignalKind::Rebuys,
+            value: recent_rebuys as f64,
+            threshold: settings.max_rebuys as f64,
+        });
+    }
+    if let (Some(minutes), Some(usual)) = (session_minutes, usual_session_minutes) {
+        let threshold = ((usual as f64 * settings.session_length_factor) as i64).max(MIN_LONG_SESSION_MINUTES);
+        if minutes >= threshold {
+            signals.push(TiltSignal { kind: SignalKind::SessionLength, value: minutes as f64, threshold: threshold as f64 });
+        }
+    }
+
+    Ok(TiltStatus {
+        enabled: settings.enabled,
+        session_started_at: started_at,
+        session_minutes,
+        usual_session_minutes,
+        session_hands,
+        session_vpip,
+        usual_vpip,
+        recent_rebuys,
+        signals,
+        cooldown_until: cooldown_until(db)?,
+    })
+}
+
+// Look for signs after a hand the player was in
+pub fn after_hand(app: &AppHandle, db: &Database, hand: &HandRecord) -> Result<(), String> {
+    if hand.hero_id.is_none() {
+        return Ok(());
+    }
+    let settings = load_settings(db)?;
+    if !settings.enabled {
+        return Ok(());
+    }
+    let status = assess(app, db, &settings)?;
+
+    let state = app.state::<TiltState>();
+    let fresh: Vec<SignalKind> = {
+        let mut raised = state.raised.lock().map_err(|_| "Tilt state lock poisoned".to_string())?;
+        if raised.0 != status.session_started_at {
+            *raised = (status.session_started_at, HashSet::new());
+        }
+        status.signals.iter().map(|s| s.kind).filter(|kind| raised.1.insert(*kind)).collect()
+    };
+    if fresh.is_empty() {
+        return Ok(());
+    }
+
+    let intervention = if status.signals.len() > 1 { settings.on_multiple } else { settings.on_signal };
+    let mut until = status.cooldown_until;
+    if intervention == Intervention::Cooldown {
+        let end = Utc::now() + Duration::minutes(settings.cooldown_minutes as i64);
+        let end = until.map_or(end, |until| until.max(end));
+        let result = db.set_value(KEY_COOLDOWN, &to_millis(&end).to_string());
+        audit::record(app, "tilt_cooldown", json!({ "until": end, "signals": fresh }), &result);
+        result?;
+        until = Some(end);
+    }
+    let _ = app.emit_all(
+        "tilt_detected",
+        json!({
+            "signals": status.signals,
+            "new": fresh,
+            "intervention": intervention,
+            "breakMinutes": (intervention == Intervention::Break).then_some(settings.break_minutes),
+            "cooldownUntil": until,
+        }),
+    );
+    Ok(())
+}
+
+// Guard for joining a table; Err while a cooldown runs
+pub fn check_join(app: &AppHandle) -> Result<(), String> {
+    let Some(db) = app.try_state::<Database>() else { return Ok(()) };
+    match cooldown_until(&db)? {
+        Some(until) => {
+            let minutes = (until - Utc::now()).num_minutes() + 1;
+            Err(format!("Joining tables is paused for {} more minutes by your tilt cooldown", minutes))
+        }
+        None => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub async fn get_tilt_settings(db: Db<'_>) -> Result<TiltSettings, String> {
+    load_settings(&db)
+}
+
+#[tauri::command]
+pub async fn set_tilt_settings(db: Db<'_>, settings: TiltSettings) -> Result<TiltSettings, String> {
+    let settings = TiltSettings {
+        vpip_rise: settings.vpip_rise.clamp(0.05, 0.5),
+        min_session_hands: settings.min_session_hands.clamp(10, 1000),
+        max_rebuys: settings.max_rebuys.clamp(1, 20),
+        rebuy_window_minutes: settings.rebuy_window_minutes.clamp(5, 24 * 60),
+        session_length_factor: settings.session_length_factor.clamp(1.1, 5.0),
+        break_minutes: settings.break_minutes.clamp(1, 120),
+        cooldown_minutes: settings.cooldown_minutes.clamp(5, 24 * 60),
+        ..settings
+    };
+    let data = serde_json::to_string(&settings).map_err(|e| e.to_string())?;
+    db.set_value(KEY_SETTINGS, &data)?;
+    Ok(settings)
+}
+
+// Current readings and signs, worked out whether or not the monitor is on
+#[tauri::command]
+pub async fn get_tilt_status(app: AppHandle, db: Db<'_>) -> Result<TiltStatus, String> {
+    let settings = load_settings(&db)?;
+    assess(&app, &db, &settings)
+}
@@ -0,0 +1,385 @@
+// Action clock with latency compensation. The backend starts a player's clock when it
+// sends them the turn and only counts an action once it arrives back, so at a distance
+// the local countdown starts half a round trip late and the action needs another half
+// to get there. The table view reports its socket's ping round trips here and they are
+// smoothed per table, with their jitter, the way TCP does. From them the client treats
+// its deadline as reached a round trip (plus jitter and a safety margin) early, asks
+// for the time bank ahead of that when the round trip is high, and warns when the
+// delay configured on a pre-action preset no longer leaves time for it to land.
+
+use crate::alerts;
+use crate::claims;
+use crate::db::{Database, Db};
+use crate::preview;
+use crate::speed;
+use crate::table_state::TableMirror;
+use crate::ws::WsMessage;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+const KEY_SETTINGS: &str = "timer.settings";
+// Smoothing of round trips and of their jitter, as TCP does
+const RTT_ALPHA: f64 = 0.125;
+const JITTER_BETA: f64 = 0.25;
+const MAX_RTT_MS: f64 = 10_000.0;
+const MAX_PRESETS: usize = 16;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TimerSettings {
+    // End the clock early by the measured latency
+    compensate: bool,
+    // Kept on top of the round trip and jitter
+    safety_margin_ms: u32,
+    // At or above this round trip the time bank is asked for before the clock runs out
+    early_time_bank_rtt_ms: u32,
+    pre_actions: Vec<PreActionPreset>,
+}
+
+impl Default for TimerSettings {
+    fn default() -> Self {
+        Self { compensate: true, safety_margin_ms: 250, early_time_bank_rtt_ms: 400, pre_actions: Vec::new() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreActionPreset {
+    id: String,
+    // check_fold | call_any | ...
+    action: String,
+    // How long after the turn arrives the preset is sent, so its timing gives nothing away
+    delay_ms: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RiskyPreset {
+    table_id: String,
+    preset_id: String,
+    action: String,
+    delay_ms: u32,
+    // When it reaches the server, against the end of the action clock
+    arrives_ms: u32,
+    action_ms: u32,
+    rtt_ms: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionClock {
+    table_id: String,
+    hand_number: u32,
+    // The server's clock, as it sent it
+    action_ms: u32,
+    // Taken off it for the round trip, jitter and margin
+    compensation_ms: u32,
+    // When the client stops waiting for the player
+    deadline: DateTime<Utc>,
+    // When the time bank is asked for early, when the round trip calls for it
+    time_bank_at: Option<DateTime<Utc>>,
+    // Whether to play the turn sound and bring the table forward (see alerts.rs)
+    sound: bool,
+    focus: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyStatus {
+    table_id: String,
+    rtt_ms: Option<u32>,
+    jitter_ms: Option<u32>,
+    compensation_ms: u32,
+    clock: Option<ActionClock>,
+    risky_presets: Vec<RiskyPreset>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TimeBankRequest {
+    table_id: String,
+    hand_number: u32,
+    frame: WsMessage,
+}
+
+#[derive(Default)]
+struct TableTimer {
+    rtt_ms: Option<f64>,
+    jitter_ms: f64,
+    // The speed's action clock, for judging the presets between turns
+    action_ms: Option<u32>,
+    clock: Option<ActionClock>,
+    // Presets already warned about, so each warns once while risky
+    warned: HashSet<String>,
+}
+
+impl TableTimer {
+    fn sample(&mut self, rtt_ms: f64) {
+        match self.rtt_ms {
+            None => {
+                self.rtt_ms = Some(rtt_ms);
+                self.jitter_ms = rtt_ms / 2.0;
+            }
+            Some(smoothed) => {
+                self.jitter_ms += JITTER_BETA * ((smoothed - rtt_ms).abs() - self.jitter_ms);
+                self.rtt_ms = Some(smoothed + RTT_ALPHA * (rtt_ms - smoothed));
+            }
+        }
+    }
+
+    fn compensation_ms(&self, settings: &TimerSettings) -> u32 {
+        if !settings.compensate {
+            return 0;
+        }
+        let latency = self.rtt_ms.map_or(0.0, |rtt| rtt + 2.0 * self.jitter_ms);
+        latency as u32 + settings.safety_margin_ms
+    }
+
+    // Presets that would reach the server after the clock ran out
+    fn risky(&self, table_id: &str, settings: &TimerSettings) -> Vec<RiskyPreset> {
+        let (Some(rtt), Some(action_ms)) = (self.rtt_ms, self.action_ms) else { return Vec::new() };
+        settings
+            .pre_actions
+            .iter()
+            .filter_map(|preset| {
+                // Half a trip for the turn to arrive and half for the action to get back
+                let arrives_ms = preset.delay_ms + (rtt + 2.0 * self.jitter_ms) as u32 + settings.safety_margin_ms;
+                (arrives_ms >= action_ms).then(|| RiskyPreset {
+                    table_id: table_id.to_string(),
+                    preset_id: preset.id.clone(),
+                    action: preset.action.clone(),
+                    delay_ms: preset.delay_ms,
+                    arrives_ms,
+                    action_ms,
+                    rtt_ms: rtt as u32,
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Default)]
+pub struct TimerState {
+    tables: Mutex<HashMap<String, TableTimer>>,
+}
+
+fn load_settings(db: &Database) -> Result<TimerSettings, String> {
+    match db.get_value(KEY_SETTINGS)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid timer settings: {}", e)),
+        None => Ok(TimerSettings::default()),
+    }
+}
+
+fn validate(settings: &TimerSettings) -> Result<(), String> {
+    if settings.pre_actions.len() > MAX_PRESETS {
+        return Err(format!("At most {} pre-action presets can be kept", MAX_PRESETS));
+    }
+    let mut ids = HashSet::new();
+    for preset in &settings.pre_actions {
+        if preset.id.trim().is_empty() || !ids.insert(preset.id.as_str()) {
+            return Err(format!("Pre-action preset id '{}' is empty or used twice", preset.id));
+        }
+    }
+    Ok(())
+}
+
+// Raise `pre_action_risk` for presets that became risky since the last check
+fn warn(app: &AppHandle, table_id: &str, timer: &mut TableTimer, settings: &TimerSettings) -> Vec<RiskyPreset> {
+    let risky = timer.risky(table_id, settings);
+    timer.warned.retain(|id| risky.iter().any(|r| &r.preset_id == id));
+    for preset in &risky {
+        if timer.warned.insert(preset.preset_id.clone()) {
+            let _ = app.emit_all("pre_action_risk", preset.clone());
+        }
+    }
+    risky
+}
+
+fn status(table_id: &str, timer: &TableTimer, settings: &TimerSettings) -> LatencyStatus {
+    LatencyStatus {
+        table_id: table_id.to_string(),
+        rtt_ms: timer.rtt_ms.map(|rtt| rtt as u32),
+        jitter_ms: timer.rtt_ms.map(|_| timer.jitter_ms as u32),
+        compensation_ms: timer.compensation_ms(settings),
+        clock: timer.clock.clone(),
+        risky_presets: timer.risky(table_id, settings),
+    }
+}
+
+// Ask for the time bank at `clock.time_bank_at` unless the clock was stopped or
+// replaced by then
+fn schedule_time_bank(app: &AppHandle, clock: &ActionClock, player_id: String) {
+    let Some(at) = clock.time_bank_at else { return };
+    let wait = (at - Utc::now()).to_std().unwrap_or(Duration::ZERO);
+    let app = app.clone();
+    let table_id = clock.table_id.clone();
+    let deadline = clock.deadline;
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(wait).await;
+        let state = app.state::<TimerState>();
+        let hand_number = {
+            let Ok(tables) = state.tables.lock() else { return };
+            match tables.get(&table_id).and_then(|t| t.clock.as_ref()) {
+                Some(clock) if clock.deadline == deadline => clock.hand_number,
+                _ => return,
+            }
+        };
+        let request = TimeBankRequest {
+            frame: WsMessage::new("use_time_bank", json!({ "tableId": table_id, "playerId": player_id })),
+            table_id,
+            hand_number,
+        };
+        let _ = app.emit_all("time_bank_activate", request);
+    });
+}
+
+// The server's clock for the turn in `message`: what it says is left, else the full
+// clock of the table's speed
+fn action_ms(message: &WsMessage, mirror: &TableMirror) -> u32 {
+    let payload = &message.payload;
+    payload["timeRemainingMs"]
+        .as_u64()
+        .or_else(|| payload["timeRemaining"].as_u64().map(|secs| secs * 1000))
+        .map(|ms| ms as u32)
+        .unwrap_or_else(|| speed::timing(mirror.game_speed, mirror.fast_fold, 0).action_secs * 1000)
+}
+
+// A ping round trip measured by the table view's socket
+#[tauri::command]
+pub async fn record_latency(
+    app: AppHandle,
+    state: State<'_, TimerState>,
+    db: Db<'_>,
+    table_id: String,
+    rtt_ms: f64,
+) -> Result<LatencyStatus, String> {
+    if !rtt_ms.is_finite() || !(0.0..=MAX_RTT_MS).contains(&rtt_ms) {
+        return Err(format!("Round trip of {} ms is out of range", rtt_ms));
+    }
+    let settings = load_settings(&db)?;
+    let mut tables = state.tables.lock().map_err(|_| "Timer lock poisoned".to_string())?;
+    let timer = tables.entry(table_id.clone()).or_default();
+    timer.sample(rtt_ms);
+    warn(&app, &table_id, timer, &settings);
+    Ok(status(&table_id, timer, &settings))
+}
+
+// Feed a frame received at `table_id`. A turn for the player starts the compensated
+// clock, raised as `action_clock`; their action, the end of the hand or leaving the
+// table stops it.
+#[tauri::command]
+pub async fn track_action_clock(
+    app: AppHandle,
+    state: State<'_, TimerState>,
+    db: Db<'_>,
+    table_id: String,
+    message: WsMessage,
+) -> Result<Option<ActionClock>, String> {
+    let player_id = claims::current()?.user_id;
+    let settings = load_settings(&db)?;
+    let mut tables = state.tables.lock().map_err(|_| "Timer lock poisoned".to_string())?;
+    let mirror = match message.kind.as_str() {
+        "game_update" => serde_json::from_value::<TableMirror>(message.payload.clone()).ok(),
+        "table_state_update" | "state_sync_response" => preview::mirror_from_state(&message.payload).ok(),
+        "player_action" => {
+            if message.payload["playerId"].as_str() == Some(player_id.as_str()) {
+                if let Some(timer) = tables.get_mut(&table_id) {
+                    timer.clock = None;
+                }
+            }
+            return Ok(None);
+        }
+        "player_left" => {
+            if message.payload["playerId"].as_str() == Some(player_id.as_str()) {
+                tables.remove(&table_id);
+            }
+            return Ok(None);
+        }
+        _ => return Ok(None),
+    };
+    let Some(mirror) = mirror else { return Ok(None) };
+
+    let timer = tables.entry(table_id.clone()).or_default();
+    timer.action_ms = Some(speed::timing(mirror.game_speed, mirror.fast_fold, 0).action_secs * 1000);
+    warn(&app, &table_id, timer, &settings);
+    let our_turn = mirror.active_player_id.as_deref() == Some(player_id.as_str()) && !mirror.is_hand_over();
+    if !our_turn {
+        timer.clock = None;
+        return Ok(None);
+    }
+    // Repeated snapshots during the same turn keep the clock already running
+    if timer.clock.as_ref().is_some_and(|c| c.hand_number == mirror.hand_number) {
+        return Ok(None);
+    }
+
+    let action_ms = action_ms(&message, &mirror);
+    let compensation_ms = timer.compensation_ms(&settings).min(action_ms);
+    let deadline = Utc::now() + chrono::Duration::milliseconds((action_ms - compensation_ms) as i64);
+    // A high round trip asks for the bank a further trip ahead, so it is in place
+    // before the server's clock runs out
+    let time_bank_at = timer
+        .rtt_ms
+        .filter(|rtt| settings.compensate && *rtt >= settings.early_time_bank_rtt_ms as f64)
+        .map(|rtt| deadline - chrono::Duration::milliseconds(rtt as i64));
+    let alerts = alerts::for_table(&db, &table_id)?;
+    let clock = ActionClock {
+        table_id: table_id.clone(),
+        hand_number: mirror.hand_number,
+        action_ms,
+        compensation_ms,
+        deadline,
+        time_bank_at,
+        sound: alerts.sounds,
+        focus: alerts.focus_on_action,
+    };
+    timer.clock = Some(clock.clone());
+    drop(tables);
+
+    schedule_time_bank(&app, &clock, player_id);
+    let _ = app.emit_all("action_clock", clock.clone());
+    Ok(Some(clock))
+}
+
+#[tauri::command]
+pub async fn get_latency_status(
+    state: State<'_, TimerState>,
+    db: Db<'_>,
+    table_id: String,
+) -> Result<LatencyStatus, String> {
+    let settings = load_settings(&db)?;
+    let tables = state.tables.lock().map_err(|_| "Timer lock poisoned".to_string())?;
+    match tables.get(&table_id) {
+        Some(timer) => Ok(status(&table_id, timer, &settings)),
+        None => Ok(status(&table_id, &TableTimer::default(), &settings)),
+    }
+}
+
+#[tauri::command]
+pub async fn get_timer_settings(db: Db<'_>) -> Result<TimerSettings, String> {
+    load_settings(&db)
+}
+
+// Replace the settings; presets are judged again on every table
+#[tauri::command]
+pub async fn set_timer_settings(
+    app: AppHandle,
+    state: State<'_, TimerState>,
+    db: Db<'_>,
+    settings: TimerSettings,
+) -> Result<Vec<RiskyPreset>, String> {
+    validate(&settings)?;
+    let data = serde_json::to_string(&settings).map_err(|e| e.to_string())?;
+    db.set_value(KEY_SETTINGS, &data)?;
+    let mut tables = state.tables.lock().map_err(|_| "Timer lock poisoned".to_string())?;
+    let mut risky = Vec::new();
+    for (table_id, timer) in tables.iter_mut() {
+        timer.warned.clear();
+        risky.extend(warn(&app, table_id, timer, &settings));
+    }
+    Ok(risky)
+}
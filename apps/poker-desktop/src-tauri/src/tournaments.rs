@@ -0,0 +1,350 @@
+// Scheduled tournaments and start reminders. The schedule is cached in the kv table;
+// reminders are stored alongside it and checked by a background loop against server
+// time (see clock.rs). Reminders that fell due while the app was closed fire on the
+// first check after launch, as long as the tournament has not started yet. Times go
+// out both as UTC and rendered for the player's zone and locale (see localtime.rs).
+
+use crate::clock::{self, ClockState};
+use crate::db::{self, Database, Db};
+use crate::localtime::{self, LocalTime};
+use crate::private_games;
+use crate::profile::BackendProfile;
+use crate::schema::{self, Field, Kind};
+use crate::tickets;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Manager, State};
+
+const KEY_SCHEDULE: &str = "tournaments.schedule";
+const KEY_REMINDERS: &str = "tournaments.reminders";
+
+const SCHEDULE_TTL_SECS: i64 = 300;
+const CHECK_INTERVAL_SECS: u64 = 15;
+const CLOCK_SYNC_EVERY: u32 = 240;
+const MAX_MINUTES_BEFORE: u32 = 24 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tournament {
+    pub id: String,
+    pub name: String,
+    pub start_time: DateTime<Utc>,
+    #[serde(flatten)]
+    pub details: Map<String, Value>,
+}
+
+impl Tournament {
+    // When entries open; the schedule only carries it for events opening ahead of
+    // the start, the rest open with late registration at the start
+    pub fn registration_opens_at(&self) -> DateTime<Utc> {
+        self.details
+            .get("registrationOpensAt")
+            .and_then(|v| v.as_str())
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map_or(self.start_time, |at| at.with_timezone(&Utc))
+    }
+
+    pub fn is_registered(&self) -> bool {
+        self.details.get("isRegistered").and_then(|v| v.as_bool()).unwrap_or(false)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentSchedule {
+    tournaments: Vec<Tournament>,
+    fetched_at: DateTime<Utc>,
+    #[serde(default)]
+    offline: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reminder {
+    tournament_id: String,
+    name: String,
+    starts_at: DateTime<Utc>,
+    minutes_before: u32,
+    fired: bool,
+}
+
+impl Reminder {
+    fn due_at(&self) -> DateTime<Utc> {
+        self.starts_at - Duration::minutes(self.minutes_before as i64)
+    }
+
+    fn view(self, now: DateTime<Utc>) -> Result<ReminderView, String> {
+        Ok(ReminderView {
+            starts_local: localtime::render(self.starts_at, now)?,
+            due_local: localtime::render(self.due_at(), now)?,
+            reminder: self,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentView {
+    #[serde(flatten)]
+    tournament: Tournament,
+    starts_local: LocalTime,
+    registration_opens_local: LocalTime,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleView {
+    tournaments: Vec<TournamentView>,
+    fetched_at: DateTime<Utc>,
+    offline: bool,
+}
+
+impl TournamentSchedule {
+    fn view(self, now: DateTime<Utc>) -> Result<ScheduleView, String> {
+        let tournaments = self
+            .tournaments
+            .into_iter()
+            .map(|tournament| {
+                Ok(TournamentView {
+                    starts_local: localtime::render(tournament.start_time, now)?,
+                    registration_opens_local: localtime::render(tournament.registration_opens_at(), now)?,
+                    tournament,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(ScheduleView { tournaments, fetched_at: self.fetched_at, offline: self.offline })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReminderView {
+    #[serde(flatten)]
+    reminder: Reminder,
+    starts_local: LocalTime,
+    due_local: LocalTime,
+}
+
+const TOURNAMENTS_SCHEMA: Kind = Kind::Array(&Kind::Object(&[
+    Field::critical("id", Kind::String),
+    Field::critical("name", Kind::String),
+    Field::critical("startTime", Kind::String),
+]));
+
+fn load<T: serde::de::DeserializeOwned>(db: &Database, key: &str) -> Result<Option<T>, String> {
+    match db.get_value(key)? {
+        Some(data) => serde_json::from_str(&data).map(Some).map_err(|e| format!("Invalid {}: {}", key, e)),
+        None => Ok(None),
+    }
+}
+
+fn save<T: Serialize>(db: &Database, key: &str, value: &T) -> Result<(), String> {
+    let data = serde_json::to_string(value).map_err(|e| e.to_string())?;
+    db.set_value(key, &data)
+}
+
+// Last fetched schedule, empty until it has been loaded once
+pub fn cached_schedule(db: &Database) -> Result<Vec<Tournament>, String> {
+    Ok(load::<TournamentSchedule>(db, KEY_SCHEDULE)?.map(|s| s.tournaments).unwrap_or_default())
+}
+
+fn reminders(db: &Database) -> Result<Vec<Reminder>, String> {
+    Ok(load(db, KEY_REMINDERS)?.unwrap_or_default())
+}
+
+async fn fetch_schedule(api_url: &str) -> Result<Vec<Tournament>, String> {
+    let client = crate::create_http_client()?;
+    let mut request = client.get(format!("{}/api/tournaments", api_url));
+    if let Ok(token) = crate::get_token_from_keyring() {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+
+    let response = crate::http::send(request).await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err("Failed to fetch tournaments".to_string());
+    }
+
+    let tournaments = schema::api_data(response, &TOURNAMENTS_SCHEMA, "tournament schedule").await?;
+    Ok(tournaments.unwrap_or_default())
+}
+
+// Follow start time changes; a reminder moved later can fire again
+fn reschedule(db: &Database, tournaments: &[Tournament], now: DateTime<Utc>) -> Result<(), String> {
+    let mut reminders = reminders(db)?;
+    let mut changed = false;
+    for reminder in &mut reminders {
+        let Some(tournament) = tournaments.iter().find(|t| t.id == reminder.tournament_id) else { continue };
+        if tournament.start_time != reminder.starts_at {
+            reminder.starts_at = tournament.start_time;
+            reminder.fired = reminder.fired && reminder.due_at() <= now;
+            changed = true;
+        }
+    }
+    if changed {
+        save(db, KEY_REMINDERS, &reminders)?;
+    }
+    Ok(())
+}
+
+fn notify(app: &AppHandle, reminder: &Reminder, now: DateTime<Utc>) {
+    let minutes = (reminder.starts_at - now).num_minutes().max(0);
+    let body = if minutes == 0 {
+        format!("{} is starting now", reminder.name)
+    } else {
+        match localtime::render(reminder.starts_at, now) {
+            Ok(local) => format!("{} starts in {} min, at {}", reminder.name, minutes, local.time),
+            Err(_) => format!("{} starts in {} min", reminder.name, minutes),
+        }
+    };
+    let shown = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier)
+        .title("Tournament reminder")
+        .body(body)
+        .show();
+    if let Err(e) = shown {
+        eprintln!("Failed to show tournament reminder: {}", e);
+    }
+    let _ = app.emit_all("tournament_reminder", reminder.clone());
+}
+
+// Fire due reminders and drop those for tournaments that started a day ago
+fn check_reminders(app: &AppHandle, now: DateTime<Utc>) -> Result<(), String> {
+    let db = db::get(app)?;
+    let mut reminders = reminders(&db)?;
+    let before = reminders.len();
+    reminders.retain(|r| r.starts_at > now - Duration::days(1));
+    let mut changed = reminders.len() != before;
+
+    for reminder in reminders.iter_mut().filter(|r| !r.fired && r.due_at() <= now) {
+        if reminder.starts_at > now {
+            notify(app, reminder, now);
+        }
+        reminder.fired = true;
+        changed = true;
+    }
+    if changed {
+        save(&db, KEY_REMINDERS, &reminders)?;
+    }
+    Ok(())
+}
+
+// Background reminder, ticket auto-registration and private game loop, started once
+// the database is open
+pub fn start_reminders(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut checks = 0;
+        loop {
+            if checks % CLOCK_SYNC_EVERY == 0 {
+                let api_url = app.state::<BackendProfile>().api_url.clone();
+                if let Err(e) = clock::sync(&app.state::<ClockState>(), &api_url).await {
+                    eprintln!("Clock sync failed, using local time: {}", e);
+                }
+            }
+            checks += 1;
+
+            let now = app.state::<ClockState>().server_now();
+            if let Err(e) = check_reminders(&app, now) {
+                eprintln!("Tournament reminder check failed: {}", e);
+            }
+            if let Err(e) = tickets::check_auto_registrations(&app, now).await {
+                eprintln!("Ticket auto-registration check failed: {}", e);
+            }
+            if let Err(e) = private_games::check_schedule(&app, now).await {
+                eprintln!("Private game schedule check failed: {}", e);
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(CHECK_INTERVAL_SECS)).await;
+        }
+    });
+}
+
+// Cached schedule unless it is stale or `refresh` is set; the cached copy is used
+// when the backend cannot be reached
+#[tauri::command]
+pub async fn get_tournament_schedule(
+    db: Db<'_>,
+    clock: State<'_, ClockState>,
+    api_url: String,
+    refresh: Option<bool>,
+) -> Result<ScheduleView, String> {
+    let now = clock.server_now();
+    let cached: Option<TournamentSchedule> = load(&db, KEY_SCHEDULE)?;
+    if let Some(schedule) = &cached {
+        if !refresh.unwrap_or(false) && (now - schedule.fetched_at).num_seconds() < SCHEDULE_TTL_SECS {
+            return schedule.clone().view(now);
+        }
+    }
+
+    match fetch_schedule(&api_url).await {
+        Ok(mut tournaments) => {
+            tournaments.sort_by_key(|t| t.start_time);
+            reschedule(&db, &tournaments, now)?;
+            let schedule = TournamentSchedule { tournaments, fetched_at: now, offline: false };
+            save(&db, KEY_SCHEDULE, &schedule)?;
+            schedule.view(now)
+        }
+        Err(e) => match cached {
+            Some(schedule) => {
+                eprintln!("Tournament schedule fetch failed, using cached copy: {}", e);
+                TournamentSchedule { offline: true, ..schedule }.view(now)
+            }
+            None => Err(e),
+        },
+    }
+}
+
+// Remind `minutes_before` the start of a scheduled tournament, replacing any
+// existing reminder for it
+#[tauri::command]
+pub async fn set_tournament_reminder(
+    db: Db<'_>,
+    clock: State<'_, ClockState>,
+    tournament_id: String,
+    minutes_before: u32,
+) -> Result<ReminderView, String> {
+    if minutes_before > MAX_MINUTES_BEFORE {
+        return Err(format!("Reminders can be set at most {} minutes ahead", MAX_MINUTES_BEFORE));
+    }
+    let schedule: TournamentSchedule =
+        load(&db, KEY_SCHEDULE)?.ok_or_else(|| "Load the tournament schedule first".to_string())?;
+    let tournament = schedule
+        .tournaments
+        .iter()
+        .find(|t| t.id == tournament_id)
+        .ok_or_else(|| format!("Tournament {} is not on the schedule", tournament_id))?;
+    let now = clock.server_now();
+    if tournament.start_time <= now {
+        return Err("This tournament has already started".to_string());
+    }
+
+    let reminder = Reminder {
+        tournament_id: tournament.id.clone(),
+        name: tournament.name.clone(),
+        starts_at: tournament.start_time,
+        minutes_before,
+        fired: false,
+    };
+    let mut reminders = reminders(&db)?;
+    reminders.retain(|r| r.tournament_id != tournament_id);
+    reminders.push(reminder.clone());
+    save(&db, KEY_REMINDERS, &reminders)?;
+    reminder.view(now)
+}
+
+#[tauri::command]
+pub async fn clear_tournament_reminder(db: Db<'_>, tournament_id: String) -> Result<(), String> {
+    let mut reminders = reminders(&db)?;
+    reminders.retain(|r| r.tournament_id != tournament_id);
+    save(&db, KEY_REMINDERS, &reminders)
+}
+
+#[tauri::command]
+pub async fn list_tournament_reminders(
+    db: Db<'_>,
+    clock: State<'_, ClockState>,
+) -> Result<Vec<ReminderView>, String> {
+    let mut reminders = reminders(&db)?;
+    reminders.sort_by_key(|r| r.due_at());
+    let now = clock.server_now();
+    reminders.into_iter().map(|r| r.view(now)).collect()
+}
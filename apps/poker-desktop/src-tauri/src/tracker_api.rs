@@ -0,0 +1,735 @@
+// Read-only hand API for third-party trackers and HUDs. When enabled it listens on
+// 127.0.0.1 only and answers the apps on its allowlist, each with a token of its own
+// that is shown once when the app is added and kept only as a hash. Requests with an
+// `Origin` header or a Host other than the loopback address are refused, so a web
+// page cannot reach it through the browser. Revoking an app ends its open streams.
+//
+// Every request carries `Authorization: Bearer <token>`:
+//   GET /v1/status                      -> { format, version, clientVersion, app }
+//   GET /v1/hands?since=<ms>&limit=<n>  -> { format, version, hands, cursor }
+//       Hands stored or changed after `since` (epoch ms), oldest first, at most
+//       `limit` (default 100, max 500). `cursor` is an opaque string; pass it back
+//       as `cursor=` instead of `since` for the next page, so hands changed in the
+//       same millisecond as the last one are not skipped.
+//   GET /v1/hands/stream?since=<ms>     -> application/x-ndjson, one object per line:
+//       {"type":"hand","hand":{...}} for each completed hand, starting with those
+//       changed after `since` when given, and {"type":"heartbeat","at":<ms>} every
+//       15 seconds so a reader can tell a quiet table from a dropped connection.
+// Hands are in the `primo-tracker-hand` format, version 1 (`TrackerHand` below):
+// amounts in chips, times in epoch milliseconds, cards as rank then suit ("Ah",
+// "Td") and hole cards only for players whose cards the client saw. Errors are
+// { "error": <message> } with a 4xx status.
+
+use crate::audit;
+use crate::bus::{self, Event, Topic};
+use crate::db::{self, Database, Db};
+use crate::history::{self, to_millis, HandRecord};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+const KEY_SETTINGS: &str = "tracker_api.settings";
+const KEY_APPS: &str = "tracker_api.apps";
+const HAND_FORMAT: &str = "primo-tracker-hand";
+const HAND_FORMAT_VERSION: u32 = 1;
+const DEFAULT_PORT: u16 = 47600;
+const MAX_APPS: usize = 20;
+const MAX_NAME_LEN: usize = 64;
+const DEFAULT_PAGE: u32 = 100;
+const MAX_PAGE: u32 = 500;
+// Hands a stream replays from `since` before going live
+const MAX_CATCH_UP: u32 = 5000;
+const MAX_HEAD_BYTES: usize = 8 * 1024;
+const HEAD_TIMEOUT: Duration = Duration::from_secs(10);
+const HEARTBEAT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TrackerApiSettings {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for TrackerApiSettings {
+    fn default() -> Self {
+        Self { enabled: false, port: DEFAULT_PORT }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackerApp {
+    id: String,
+    name: String,
+    // SHA-256 of the token, hex; cleared when the app is revoked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token_hash: Option<String>,
+    created_at: DateTime<Utc>,
+    last_seen_at: Option<DateTime<Utc>>,
+    revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackerAppInfo {
+    id: String,
+    name: String,
+    created_at: DateTime<Utc>,
+    last_seen_at: Option<DateTime<Utc>>,
+    revoked_at: Option<DateTime<Utc>>,
+    // Streams open right now
+    streams: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisteredTrackerApp {
+    app: TrackerAppInfo,
+    // Shown this once; only its hash is kept
+    token: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackerApiStatus {
+    settings: TrackerApiSettings,
+    // Base URL while listening
+    address: Option<String>,
+    streams: usize,
+}
+
+// A hand as third-party tools see it. Kept apart from `HandRecord` so the documented
+// format only changes with its version.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TrackerHand {
+    id: String,
+    table_id: String,
+    table_name: Option<String>,
+    played_at: i64,
+    updated_at: i64,
+    game_type: String,
+    betting_structure: String,
+    small_blind: u32,
+    big_blind: u32,
+    ante: u32,
+    ante_structure: history::AnteStructure,
+    // The player this client belongs to, when they were in the hand
+    hero_id: Option<String>,
+    players: Vec<TrackerPlayer>,
+    board: Vec<String>,
+    actions: Vec<TrackerAction>,
+    pot: u32,
+    rake: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TrackerPlayer {
+    player_id: String,
+    username: String,
+    seat: u8,
+    starting_stack: u32,
+    hole_cards: Option<Vec<String>>,
+    net: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TrackerAction {
+    street: String,
+    player_id: String,
+    action: String,
+    amount: u32,
+}
+
+impl From<&HandRecord> for TrackerHand {
+    fn from(hand: &HandRecord) -> Self {
+        TrackerHand {
+            id: hand.id.clone(),
+            table_id: hand.table_id.clone(),
+            table_name: hand.table_name.clone(),
+            played_at: to_millis(&hand.played_at),
+            updated_at: to_millis(&hand.updated_at),
+            game_type: hand.game_type.clone(),
+            betting_structure: hand.betting_structure.clone(),
+            small_blind: hand.small_blind,
+            big_blind: hand.big_blind,
+            ante: hand.ante,
+            ante_structure: hand.ante_structure,
+            hero_id: hand.hero_id.clone(),
+            players: hand
+                .players
+                .iter()
+                .map(|p| TrackerPlayer {
+                    player_id: p.player_id.clone(),
+                    username: p.username.clone(),
+                    seat: p.seat,
+                    starting_stack: p.starting_stack,
+                    hole_cards: p.hole_cards.clone(),
+                    net: p.net,
+                })
+                .collect(),
+            board: hand.board.clone(),
+            actions: hand
+                .actions
+                .iter()
+                .map(|a| TrackerAction {
+                    street: a.street.clone(),
+                    player_id: a.player_id.clone(),
+                    action: a.action.clone(),
+                    amount: a.amount,
+                })
+                .collect(),
+            pot: hand.pot,
+            rake: hand.rake,
+        }
+    }
+}
+
+struct Server {
+    // The generation it started in, which tells a restart on the same port apart
+    generation: u64,
+    port: u16,
+    task: tauri::async_runtime::JoinHandle<()>,
+}
+
+#[derive(Default)]
+pub struct TrackerApiState {
+    server: Mutex<Option<Server>>,
+    // Bumped when the server stops or an app is revoked, so open streams recheck
+    changed: watch::Sender<u64>,
+    next_stream: AtomicU64,
+    // Open streams by id, to the app they belong to
+    streams: Mutex<HashMap<u64, String>>,
+}
+
+impl TrackerApiState {
+    fn notify(&self) {
+        self.changed.send_modify(|generation| *generation += 1);
+    }
+
+    fn stream_counts(&self) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        if let Ok(streams) = self.streams.lock() {
+            for app_id in streams.values() {
+                *counts.entry(app_id.clone()).or_default() += 1;
+            }
+        }
+        counts
+    }
+}
+
+fn load_settings(db: &Database) -> Result<TrackerApiSettings, String> {
+    match db.get_value(KEY_SETTINGS)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid tracker API settings: {}", e)),
+        None => Ok(TrackerApiSettings::default()),
+    }
+}
+
+fn parse_apps(data: Option<String>) -> Result<Vec<TrackerApp>, String> {
+    match data {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid tracker app list: {}", e)),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn load_apps(db: &Database) -> Result<Vec<TrackerApp>, String> {
+    parse_apps(db.get_value(KEY_APPS)?)
+}
+
+// Read, change and store the app list in one transaction, so a request marking its
+// app as seen cannot write back a list from before a revoke. Nothing is stored when
+// `f` fails.
+fn update_apps<T>(db: &Database, f: impl FnOnce(&mut Vec<TrackerApp>) -> Result<T, String>) -> Result<T, String> {
+    db.with_conn(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        let stored = tx.query_row("SELECT value FROM kv WHERE key = ?1", [KEY_APPS], |row| row.get(0)).optional()?;
+        let updated = parse_apps(stored).and_then(|mut apps| {
+            let result = f(&mut apps)?;
+            Ok((result, serde_json::to_string(&apps).map_err(|e| e.to_string())?))
+        });
+        let Ok((result, data)) = updated else { return Ok(updated.map(|(result, _)| result)) };
+        tx.execute(
+            "INSERT INTO kv (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            [KEY_APPS, &data],
+        )?;
+        tx.commit()?;
+        Ok(Ok(result))
+    })?
+}
+
+fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn new_token() -> String {
+    let mut rng = rand::thread_rng();
+    let hex: String = (0..32).map(|_| format!("{:02x}", rng.gen::<u8>())).collect();
+    format!("ppt_{}", hex)
+}
+
+fn info(app: &TrackerApp, counts: &HashMap<String, usize>) -> TrackerAppInfo {
+    TrackerAppInfo {
+        id: app.id.clone(),
+        name: app.name.clone(),
+        created_at: app.created_at,
+        last_seen_at: app.last_seen_at,
+        revoked_at: app.revoked_at,
+        streams: counts.get(&app.id).copied().unwrap_or(0),
+    }
+}
+
+// The allowed app holding `token_hash`
+fn allowed(db: &Database, token_hash: &str) -> Option<TrackerApp> {
+    load_apps(db)
+        .ok()?
+        .into_iter()
+        .find(|app| app.revoked_at.is_none() && app.token_hash.as_deref() == Some(token_hash))
+}
+
+fn touch(db: &Database, app_id: &str) {
+    let result = update_apps(db, |apps| {
+        if let Some(app) = apps.iter_mut().find(|app| app.id == app_id) {
+            app.last_seen_at = Some(Utc::now());
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to update tracker app {}: {}", app_id, e);
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn route(&self) -> &str {
+        self.path.split_once('?').map_or(self.path.as_str(), |(route, _)| route)
+    }
+
+    fn query_param(&self, name: &str) -> Option<&str> {
+        let query = self.path.split_once('?')?.1;
+        query.split('&').find_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            (key == name).then_some(value)
+        })
+    }
+}
+
+fn parse_head(head: &[u8]) -> Option<Request> {
+    let text = std::str::from_utf8(head).ok()?;
+    let mut lines = text.split("\r\n");
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+    let headers = lines
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            Some((key.trim().to_string(), value.trim().to_string()))
+        })
+        .collect();
+    Some(Request { method, path, headers })
+}
+
+async fn read_head(stream: &mut TcpStream) -> Option<Request> {
+    let mut head = Vec::with_capacity(1024);
+    let mut buf = [0u8; 1024];
+    loop {
+        let read = stream.read(&mut buf).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        head.extend_from_slice(&buf[..read]);
+        if let Some(end) = head.windows(4).position(|w| w == b"\r\n\r\n") {
+            return parse_head(&head[..end]);
+        }
+        if head.len() > MAX_HEAD_BYTES {
+            return None;
+        }
+    }
+}
+
+fn status_text(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    }
+}
+
+async fn respond(stream: &mut TcpStream, status: u16, body: Value) {
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Cache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        status_text(status),
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+async fn fail(stream: &mut TcpStream, status: u16, message: &str) {
+    respond(stream, status, json!({ "error": message })).await;
+}
+
+// `(updated_at, id)` as a URL-safe token
+fn encode_cursor(cursor: (i64, &str)) -> String {
+    URL_SAFE_NO_PAD.encode(format!("{}:{}", cursor.0, cursor.1))
+}
+
+fn decode_cursor(cursor: &str) -> Option<(i64, String)> {
+    let text = String::from_utf8(URL_SAFE_NO_PAD.decode(cursor).ok()?).ok()?;
+    let (millis, id) = text.split_once(':')?;
+    Some((millis.parse().ok()?, id.to_string()))
+}
+
+// Where reading starts: after the `(updated_at, id)` of a `cursor` a page returned,
+// or after `since`
+fn parse_after(request: &Request) -> Result<Option<(i64, String)>, String> {
+    if let Some(cursor) = request.query_param("cursor") {
+        return decode_cursor(cursor).map(Some).ok_or_else(|| "`cursor` is not one this API returned".to_string());
+    }
+    request
+        .query_param("since")
+        .map(|since| {
+            let since = since.parse().map_err(|_| "`since` must be epoch milliseconds".to_string())?;
+            Ok((since, String::new()))
+        })
+        .transpose()
+}
+
+async fn handle_connection(app: AppHandle, port: u16, mut stream: TcpStream) {
+    let Ok(Some(request)) = tokio::time::timeout(HEAD_TIMEOUT, read_head(&mut stream)).await else { return };
+
+    // Browsers always send Origin on cross-site requests, and a rebound DNS name
+    // still shows in Host
+    let loopback = [format!("127.0.0.1:{}", port), format!("localhost:{}", port)];
+    if request.header("origin").is_some() || !request.header("host").is_some_and(|host| loopback.iter().any(|h| h == host)) {
+        return fail(&mut stream, 403, "Only local applications may use this API").await;
+    }
+    if request.method != "GET" {
+        return fail(&mut stream, 405, "Only GET is supported").await;
+    }
+    let Some(db) = app.try_state::<Database>() else {
+        return fail(&mut stream, 500, "Hand history is not available").await;
+    };
+    let Some(token) = request.header("authorization").and_then(|v| v.strip_prefix("Bearer ")) else {
+        return fail(&mut stream, 401, "Missing bearer token").await;
+    };
+    let token_hash = hash_token(token.trim());
+    let Some(client) = allowed(&db, &token_hash) else {
+        return fail(&mut stream, 401, "Unknown or revoked token").await;
+    };
+    touch(&db, &client.id);
+
+    let after = match parse_after(&request) {
+        Ok(after) => after,
+        Err(e) => return fail(&mut stream, 400, &e).await,
+    };
+    match request.route() {
+        "/v1/status" => {
+            let body = json!({
+                "format": HAND_FORMAT,
+                "version": HAND_FORMAT_VERSION,
+                "clientVersion": env!("CARGO_PKG_VERSION"),
+                "app": { "id": client.id, "name": client.name },
+            });
+            respond(&mut stream, 200, body).await;
+        }
+        "/v1/hands" => {
+            let limit = match request.query_param("limit").map(str::parse::<u32>).transpose() {
+                Ok(limit) => limit.unwrap_or(DEFAULT_PAGE).clamp(1, MAX_PAGE),
+                Err(_) => return fail(&mut stream, 400, "`limit` must be a number").await,
+            };
+            let after = after.unwrap_or_default();
+            let hands = match db.with_conn(|conn| history::hands_updated_since(conn, (after.0, &after.1), limit)) {
+                Ok(hands) => hands,
+                Err(e) => return fail(&mut stream, 500, &e).await,
+            };
+            let cursor = match hands.last() {
+                Some(hand) => encode_cursor((to_millis(&hand.updated_at), &hand.id)),
+                None => encode_cursor((after.0, &after.1)),
+            };
+            let hands: Vec<TrackerHand> = hands.iter().map(TrackerHand::from).collect();
+            let body = json!({ "format": HAND_FORMAT, "version": HAND_FORMAT_VERSION, "hands": hands, "cursor": cursor });
+            respond(&mut stream, 200, body).await;
+        }
+        "/v1/hands/stream" => {
+            serve_stream(&app, stream, client, token_hash, after).await;
+        }
+        _ => fail(&mut stream, 404, "No such endpoint").await,
+    }
+}
+
+async fn write_line(stream: &mut TcpStream, value: &Value) -> bool {
+    let mut line = value.to_string();
+    line.push('\n');
+    stream.write_all(line.as_bytes()).await.is_ok()
+}
+
+// Catch-up from after `after`, then each new hand until the client goes away, the server
+// stops or the app is revoked
+async fn serve_stream(app: &AppHandle, mut stream: TcpStream, client: TrackerApp, token_hash: String, after: Option<(i64, String)>) {
+    let state = app.state::<TrackerApiState>();
+    // Subscribe before reading the catch-up so nothing falls between the two
+    let mut hands = bus::subscribe(&[Topic::Hand]);
+    let mut changed = state.changed.subscribe();
+    let server = running(&state);
+
+    let head = "HTTP/1.1 200 OK\r\nContent-Type: application/x-ndjson\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n";
+    if stream.write_all(head.as_bytes()).await.is_err() {
+        return;
+    }
+    let stream_id = state.next_stream.fetch_add(1, Ordering::Relaxed);
+    if let Ok(mut streams) = state.streams.lock() {
+        streams.insert(stream_id, client.id.clone());
+    }
+
+    let mut sent = HashSet::new();
+    if let Some((at, id)) = after {
+        let backlog = db::get(app).and_then(|db| db.with_conn(|conn| history::hands_updated_since(conn, (at, &id), MAX_CATCH_UP)));
+        for hand in backlog.unwrap_or_default() {
+            if !write_line(&mut stream, &json!({ "type": "hand", "hand": TrackerHand::from(&hand) })).await {
+                break;
+            }
+            sent.insert(hand.id);
+        }
+    }
+
+    let mut heartbeat = tokio::time::interval(HEARTBEAT);
+    heartbeat.tick().await;
+    let mut probe = [0u8; 256];
+    loop {
+        tokio::select! {
+            event = hands.recv() => {
+                let Some(event) = event else { break };
+                let Event::HandSaved { hand, is_new: true } = event.as_ref() else { continue };
+                if sent.remove(&hand.id) {
+                    continue;
+                }
+                if !write_line(&mut stream, &json!({ "type": "hand", "hand": TrackerHand::from(hand.as_ref()) })).await {
+                    break;
+                }
+            }
+            _ = heartbeat.tick() => {
+                if !write_line(&mut stream, &json!({ "type": "heartbeat", "at": Utc::now().timestamp_millis() })).await {
+                    break;
+                }
+            }
+            result = changed.changed() => {
+                let db = app.try_state::<Database>();
+                if result.is_err() || running(&state) != server || db.and_then(|db| allowed(&db, &token_hash)).is_none() {
+                    break;
+                }
+            }
+            // The client sends nothing; a read returning means it hung up
+            read = stream.read(&mut probe) => {
+                if matches!(read, Ok(0) | Err(_)) {
+                    break;
+                }
+            }
+        }
+    }
+
+    if let Ok(mut streams) = state.streams.lock() {
+        streams.remove(&stream_id);
+    };
+}
+
+// Generation and port of the running server
+fn running(state: &TrackerApiState) -> Option<(u64, u16)> {
+    state.server.lock().ok().and_then(|s| s.as_ref().map(|s| (s.generation, s.port)))
+}
+
+// Stop the server and wait for its task to finish, which drops the listener and
+// frees the port for a restart on it
+async fn stop(state: &TrackerApiState) {
+    let server = state.server.lock().ok().and_then(|mut server| server.take());
+    if let Some(server) = server {
+        server.task.abort();
+        let _ = server.task.await;
+    }
+    state.notify();
+}
+
+// Stop any running server and start one per `settings`
+async fn apply(app: &AppHandle, settings: &TrackerApiSettings) -> Result<(), String> {
+    let state = app.state::<TrackerApiState>();
+    stop(&state).await;
+    if !settings.enabled {
+        return Ok(());
+    }
+    let listener = TcpListener::bind(("127.0.0.1", settings.port))
+        .await
+        .map_err(|e| format!("Failed to listen on port {}: {}", settings.port, e))?;
+    let port = settings.port;
+    let handle = app.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tauri::async_runtime::spawn(handle_connection(handle.clone(), port, stream));
+        }
+    });
+    if let Ok(mut server) = state.server.lock() {
+        *server = Some(Server { generation: *state.changed.borrow(), port, task });
+    }
+    Ok(())
+}
+
+// Start the API at launch when it was left enabled
+pub fn start(app: &AppHandle) {
+    let settings = match db::get(app).and_then(|db| load_settings(&db)) {
+        Ok(settings) if settings.enabled => settings,
+        Ok(_) => return,
+        Err(e) => {
+            eprintln!("Tracker API: {}", e);
+            return;
+        }
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = apply(&app, &settings).await {
+            eprintln!("Tracker API: {}", e);
+        }
+    });
+}
+
+fn status(state: &TrackerApiState, settings: TrackerApiSettings) -> TrackerApiStatus {
+    TrackerApiStatus {
+        settings,
+        address: running(state).map(|(_, port)| format!("http://127.0.0.1:{}", port)),
+        streams: state.streams.lock().map(|s| s.len()).unwrap_or(0),
+    }
+}
+
+#[tauri::command]
+pub async fn get_tracker_api_status(
+    db: Db<'_>,
+    state: State<'_, TrackerApiState>,
+) -> Result<TrackerApiStatus, String> {
+    Ok(status(&state, load_settings(&db)?))
+}
+
+// Save the settings and start, restart or stop the server to match. Settings that
+// fail to start are not saved.
+#[tauri::command]
+pub async fn set_tracker_api_settings(
+    app: AppHandle,
+    db: Db<'_>,
+    state: State<'_, TrackerApiState>,
+    settings: TrackerApiSettings,
+) -> Result<TrackerApiStatus, String> {
+    if settings.port < 1024 {
+        return Err("Choose a port from 1024 up".to_string());
+    }
+    let result = apply(&app, &settings).await;
+    audit::record(&app, "set_tracker_api_settings", json!({ "enabled": settings.enabled, "port": settings.port }), &result);
+    if let Err(e) = result {
+        // Put back what was running before
+        let _ = apply(&app, &load_settings(&db)?).await;
+        return Err(e);
+    }
+    let data = serde_json::to_string(&settings).map_err(|e| e.to_string())?;
+    db.set_value(KEY_SETTINGS, &data)?;
+    Ok(status(&state, settings))
+}
+
+#[tauri::command]
+pub async fn list_tracker_apps(
+    db: Db<'_>,
+    state: State<'_, TrackerApiState>,
+) -> Result<Vec<TrackerAppInfo>, String> {
+    let counts = state.stream_counts();
+    Ok(load_apps(&db)?.iter().map(|app| info(app, &counts)).collect())
+}
+
+// Allow an app and return its token, which is not shown again
+#[tauri::command]
+pub async fn register_tracker_app(
+    app: AppHandle,
+    db: Db<'_>,
+    name: String,
+) -> Result<RegisteredTrackerApp, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(format!("App names are 1 to {} characters", MAX_NAME_LEN));
+    }
+    let token = new_token();
+    let tracker = TrackerApp {
+        id: format!("app-{}-{:08x}", Utc::now().timestamp_millis(), rand::random::<u32>()),
+        name,
+        token_hash: Some(hash_token(&token)),
+        created_at: Utc::now(),
+        last_seen_at: None,
+        revoked_at: None,
+    };
+    let result = update_apps(&db, |apps| {
+        if apps.iter().filter(|a| a.revoked_at.is_none()).count() >= MAX_APPS {
+            return Err(format!("At most {} apps can be allowed; revoke one first", MAX_APPS));
+        }
+        if apps.iter().any(|a| a.revoked_at.is_none() && a.name.eq_ignore_ascii_case(&tracker.name)) {
+            return Err(format!("{} is already allowed", tracker.name));
+        }
+        apps.push(tracker.clone());
+        Ok(())
+    });
+    audit::record(&app, "register_tracker_app", json!({ "appId": tracker.id, "name": tracker.name }), &result);
+    result?;
+    Ok(RegisteredTrackerApp { app: info(&tracker, &HashMap::new()), token })
+}
+
+// Withdraw an app's access and close its open streams. The entry stays listed.
+#[tauri::command]
+pub async fn revoke_tracker_app(
+    app: AppHandle,
+    db: Db<'_>,
+    state: State<'_, TrackerApiState>,
+    app_id: String,
+) -> Result<TrackerAppInfo, String> {
+    let result = update_apps(&db, |apps| {
+        let tracker = apps
+            .iter_mut()
+            .find(|a| a.id == app_id)
+            .ok_or_else(|| format!("Unknown tracker app {}", app_id))?;
+        if tracker.revoked_at.is_none() {
+            tracker.revoked_at = Some(Utc::now());
+            tracker.token_hash = None;
+        }
+        Ok(tracker.clone())
+    });
+    let name = result.as_ref().ok().map(|revoked| revoked.name.clone());
+    audit::record(&app, "revoke_tracker_app", json!({ "appId": app_id, "name": name }), &result);
+    let revoked = result?;
+    state.notify();
+    Ok(info(&revoked, &state.stream_counts()))
+}
@@ -0,0 +1,329 @@
+// Hand-review trainer. Picks spots from local hand history where the hero faced a bet
+// in a big pot or a close decision, replays the hand up to that point and grades the
+// answer against equity and pot odds.
+
+use crate::cards::Card;
+use crate::compute::{self, Priority};
+use crate::db;
+use crate::equity::equity_vs_random;
+use crate::history::{list_hands, HandAction, HandRecord};
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use tauri::{AppHandle, State};
+
+const SCAN_LIMIT: u32 = 500;
+const CANDIDATES: usize = 20;
+const PICK_FROM: usize = 5;
+const TRIALS: u32 = 1000;
+
+// Equity above pot odds needed before raising is preferred over calling
+const RAISE_MARGIN: f64 = 0.25;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrainingSpot {
+    spot_id: String,
+    hand_id: String,
+    table_name: Option<String>,
+    played_at: DateTime<Utc>,
+    street: String,
+    hero_cards: Vec<String>,
+    board: Vec<String>,
+    pot: u32,
+    to_call: u32,
+    big_blind: u32,
+    hero_stack: u32,
+    opponents: usize,
+    // Actions leading up to the decision, for the replayer
+    history: Vec<HandAction>,
+    options: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrainingFeedback {
+    spot_id: String,
+    chosen: String,
+    recommended: String,
+    // Whether the answer agrees with the recommendation on folding vs continuing
+    correct: bool,
+    equity: f64,
+    pot_odds: f64,
+    actual_action: String,
+    explanation: String,
+}
+
+struct PendingSpot {
+    equity: f64,
+    pot_odds: f64,
+    can_raise: bool,
+    opponents: usize,
+    to_call: u32,
+    pot: u32,
+    actual_action: String,
+}
+
+#[derive(Default)]
+pub struct TrainerState {
+    pending: Mutex<HashMap<String, PendingSpot>>,
+    served: Mutex<HashSet<String>>,
+}
+
+// The table as the hero saw it just before one of their actions
+pub struct Decision {
+    pub index: usize,
+    pub street: String,
+    pub pot: u32,
+    pub to_call: u32,
+    pub hero_stack: u32,
+    // Smaller of the hero's stack and the biggest stack still in the hand against them
+    pub effective_stack: u32,
+    pub opponents: usize,
+    pub action: String,
+}
+
+pub fn board_len(street: &str) -> usize {
+    match street {
+        "flop" => 3,
+        "turn" => 4,
+        "river" => 5,
+        _ => 0,
+    }
+}
+
+pub fn hero_cards(hand: &HandRecord) -> Option<[Card; 2]> {
+    let hero_id = hand.hero_id.as_deref()?;
+    let cards = hand.players.iter().find(|p| p.player_id == hero_id)?.hole_cards.as_ref()?;
+    Some([Card::parse(cards.first()?)?, Card::parse(cards.get(1)?)?])
+}
+
+// Every voluntary action the hero took in the hand, with the state before it
+pub fn hero_decisions(hand: &HandRecord, hero_id: &str) -> Vec<Decision> {
+    let mut pot = 0;
+    let mut street = String::new();
+    let mut street_bets: HashMap<&str, u32> = HashMap::new();
+    let mut committed: HashMap<&str, u32> = HashMap::new();
+    let mut folded: HashSet<&str> = HashSet::new();
+    let mut found = Vec::new();
+
+    let remaining = |committed: &HashMap<&str, u32>, player: &str| {
+        let start = hand.players.iter().find(|p| p.player_id == player).map(|p| p.starting_stack).unwrap_or(0);
+        start.saturating_sub(committed.get(player).copied().unwrap_or(0))
+    };
+
+    for (index, action) in hand.actions.iter().enumerate() {
+        if action.street != street {
+            street = action.street.clone();
+            street_bets.clear();
+        }
+        let player = action.player_id.as_str();
+        let is_blind = action.action.ends_with("_blind") || action.action == "ante";
+
+        if player == hero_id && !is_blind {
+            let current_bet = street_bets.values().copied().max().unwrap_or(0);
+            let to_call = current_bet.saturating_sub(street_bets.get(player).copied().unwrap_or(0));
+            let opponents: Vec<&str> = hand
+                .players
+                .iter()
+                .map(|p| p.player_id.as_str())
+                .filter(|id| *id != hero_id && !folded.contains(id))
+                .collect();
+            let hero_stack = remaining(&committed, hero_id);
+            let biggest = opponents.iter().map(|id| remaining(&committed, id)).max().unwrap_or(0);
+            found.push(Decision {
+                index,
+                street: street.clone(),
+                pot,
+                to_call,
+                hero_stack,
+                effective_stack: hero_stack.min(biggest),
+                opponents: opponents.len(),
+                action: action.action.clone(),
+            });
+        }
+
+        *street_bets.entry(player).or_insert(0) += action.amount;
+        *committed.entry(player).or_insert(0) += action.amount;
+        pot += action.amount;
+        if action.action == "fold" {
+            folded.insert(player);
+        }
+    }
+    found
+}
+
+fn recommend(equity: f64, pot_odds: f64, can_raise: bool) -> &'static str {
+    if equity < pot_odds {
+        "fold"
+    } else if can_raise && equity >= 0.5 && equity - pot_odds >= RAISE_MARGIN {
+        "raise"
+    } else {
+        "call"
+    }
+}
+
+struct Candidate {
+    spot: TrainingSpot,
+    pending: PendingSpot,
+    score: f64,
+}
+
+fn candidates(hands: &[HandRecord], served: &HashSet<String>) -> Vec<Candidate> {
+    let mut raw: Vec<(&HandRecord, Decision, [Card; 2])> = Vec::new();
+    for hand in hands {
+        let (Some(hero_id), Some(hole)) = (hand.hero_id.as_deref(), hero_cards(hand)) else { continue };
+        for decision in hero_decisions(hand, hero_id) {
+            if decision.to_call > 0 && decision.opponents > 0 && !served.contains(&format!("{}:{}", hand.id, decision.index)) {
+                raw.push((hand, decision, hole));
+            }
+        }
+    }
+
+    // Biggest pots first; equity is only worked out for the leading candidates
+    raw.sort_by(|a, b| {
+        let size = |(hand, d, _): &(&HandRecord, Decision, [Card; 2])| (d.pot + d.to_call) as f64 / hand.big_blind.max(1) as f64;
+        size(b).total_cmp(&size(a))
+    });
+    raw.truncate(CANDIDATES);
+
+    let mut rng = rand::thread_rng();
+    raw.into_iter()
+        .filter_map(|(hand, decision, hole)| {
+            let board: Vec<Card> = hand
+                .board
+                .iter()
+                .take(board_len(&decision.street))
+                .filter_map(|c| Card::parse(c))
+                .collect();
+            if board.len() != board_len(&decision.street) {
+                return None;
+            }
+
+            let equity = equity_vs_random(hole, &board, decision.opponents, TRIALS, &mut rng);
+            let pot_odds = decision.to_call as f64 / (decision.pot + decision.to_call) as f64;
+            let can_raise = decision.hero_stack > decision.to_call;
+            let pot_bb = (decision.pot + decision.to_call) as f64 / hand.big_blind.max(1) as f64;
+            // Big pots and decisions near the break-even point score highest
+            let score = pot_bb / (1.0 + 10.0 * (equity - pot_odds).abs());
+
+            let mut options = vec!["fold".to_string(), "call".to_string()];
+            if can_raise {
+                options.push("raise".to_string());
+            }
+
+            Some(Candidate {
+                spot: TrainingSpot {
+                    spot_id: format!("{}:{}", hand.id, decision.index),
+                    hand_id: hand.id.clone(),
+                    table_name: hand.table_name.clone(),
+                    played_at: hand.played_at,
+                    street: decision.street.clone(),
+                    hero_cards: hole.iter().map(|c| c.to_string()).collect(),
+                    board: board.iter().map(|c| c.to_string()).collect(),
+                    pot: decision.pot,
+                    to_call: decision.to_call,
+                    big_blind: hand.big_blind,
+                    hero_stack: decision.hero_stack,
+                    opponents: decision.opponents,
+                    history: hand.actions[..decision.index].to_vec(),
+                    options,
+                },
+                pending: PendingSpot {
+                    equity,
+                    pot_odds,
+                    can_raise,
+                    opponents: decision.opponents,
+                    to_call: decision.to_call,
+                    pot: decision.pot,
+                    actual_action: decision.action,
+                },
+                score,
+            })
+        })
+        .collect()
+}
+
+// Pick a spot from local history, or None when no hand has a suitable decision
+#[tauri::command]
+pub async fn get_training_spot(
+    app: AppHandle,
+    state: State<'_, TrainerState>,
+) -> Result<Option<TrainingSpot>, String> {
+    let served = state.served.lock().map_err(|_| "Trainer lock poisoned".to_string())?.clone();
+    let (mut found, restarted) = compute::run("Training spots", Priority::Normal, move |_| {
+        let hands = db::get(&app)?.with_conn(|conn| list_hands(conn, SCAN_LIMIT, 0))?;
+        let found = candidates(&hands, &served);
+        if found.is_empty() && !served.is_empty() {
+            // Every spot has been shown once; start over
+            return Ok((candidates(&hands, &HashSet::new()), true));
+        }
+        Ok((found, false))
+    })
+    .await?;
+
+    let mut served = state.served.lock().map_err(|_| "Trainer lock poisoned".to_string())?;
+    if restarted {
+        served.clear();
+    }
+    found.sort_by(|a, b| b.score.total_cmp(&a.score));
+    found.truncate(PICK_FROM);
+    if found.is_empty() {
+        return Ok(None);
+    }
+
+    let chosen = found.swap_remove(rand::thread_rng().gen_range(0..found.len()));
+    served.insert(chosen.spot.spot_id.clone());
+    state
+        .pending
+        .lock()
+        .map_err(|_| "Trainer lock poisoned".to_string())?
+        .insert(chosen.spot.spot_id.clone(), chosen.pending);
+    Ok(Some(chosen.spot))
+}
+
+#[tauri::command]
+pub async fn submit_training_answer(
+    state: State<'_, TrainerState>,
+    spot_id: String,
+    action: String,
+) -> Result<TrainingFeedback, String> {
+    let spot = state
+        .pending
+        .lock()
+        .map_err(|_| "Trainer lock poisoned".to_string())?
+        .remove(&spot_id)
+        .ok_or_else(|| "Unknown or already answered training spot".to_string())?;
+
+    let chosen = match action.as_str() {
+        "fold" | "call" => action.clone(),
+        "raise" | "bet" | "all_in" if spot.can_raise => "raise".to_string(),
+        "raise" | "bet" | "all_in" => "call".to_string(),
+        other => return Err(format!("Unknown action '{}'", other)),
+    };
+    let recommended = recommend(spot.equity, spot.pot_odds, spot.can_raise);
+    let correct = (chosen == "fold") == (recommended == "fold");
+
+    let explanation = format!(
+        "Calling {} to win {} needs {:.0}% equity; against {} opponent{} this hand had about {:.0}%.",
+        spot.to_call,
+        spot.pot + spot.to_call,
+        spot.pot_odds * 100.0,
+        spot.opponents,
+        if spot.opponents == 1 { "" } else { "s" },
+        spot.equity * 100.0
+    );
+
+    Ok(TrainingFeedback {
+        spot_id,
+        chosen,
+        recommended: recommended.to_string(),
+        correct,
+        equity: spot.equity,
+        pot_odds: spot.pot_odds,
+        actual_action: spot.actual_action,
+        explanation,
+    })
+}
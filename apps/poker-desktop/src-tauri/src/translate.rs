@@ -0,0 +1,326 @@
+// Optional translation of incoming table chat. The frontend hands each chat message
+// over as it arrives; messages that look like they are in another language are
+// translated through the backend proxy (or a LibreTranslate-compatible endpoint the
+// player configures), cached in memory, and re-emitted as `chat_message_annotated`
+// carrying both the original and the translated text. Player messages are also kept
+// in the local chat history, for context when one is reported (see chat_reports.rs).
+
+use crate::chat_reports;
+use crate::db::{Database, Db};
+use crate::memory::{CacheUsage, MemoryCache};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+const KEY_SETTINGS: &str = "translation.settings";
+const CACHE_SIZE: usize = 500;
+const MAX_MESSAGE_LEN: usize = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TranslationProvider {
+    Backend,
+    // LibreTranslate-compatible `/translate` endpoint
+    Custom { endpoint: String, api_key: Option<String> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TranslationSettings {
+    enabled: bool,
+    target_language: String,
+    provider: TranslationProvider,
+    // Languages the player reads and never wants translated
+    skip_languages: Vec<String>,
+}
+
+impl Default for TranslationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target_language: system_language(),
+            provider: TranslationProvider::Backend,
+            skip_languages: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatMessage {
+    // The server's id; messages without one are given a local id when recorded
+    #[serde(default)]
+    id: Option<String>,
+    player_id: String,
+    username: String,
+    message: String,
+    #[serde(default)]
+    is_system: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnotatedChat {
+    table_id: String,
+    #[serde(flatten)]
+    chat: ChatMessage,
+    original: String,
+    translated: Option<String>,
+    source_language: Option<String>,
+    target_language: String,
+}
+
+#[derive(Debug, Clone)]
+struct Translation {
+    source_language: Option<String>,
+    text: String,
+}
+
+// Keyed by (target language, original text), oldest evicted first
+#[derive(Default)]
+struct Cache {
+    entries: HashMap<(String, String), Translation>,
+    order: VecDeque<(String, String)>,
+}
+
+#[derive(Default)]
+pub struct TranslationState {
+    cache: Mutex<Cache>,
+}
+
+fn entry_size(key: &(String, String), translation: &Translation) -> usize {
+    key.0.len() + key.1.len() + translation.text.len()
+}
+
+impl MemoryCache for TranslationState {
+    fn usage(&self) -> CacheUsage {
+        let Ok(cache) = self.cache.lock() else { return CacheUsage::default() };
+        CacheUsage { entries: cache.entries.len(), bytes: cache.entries.iter().map(|(key, t)| entry_size(key, t)).sum() }
+    }
+
+    fn trim_to(&self, max_bytes: usize) {
+        let Ok(mut cache) = self.cache.lock() else { return };
+        let mut total: usize = cache.entries.iter().map(|(key, t)| entry_size(key, t)).sum();
+        while total > max_bytes {
+            let Some(oldest) = cache.order.pop_front() else { break };
+            if let Some(translation) = cache.entries.remove(&oldest) {
+                total = total.saturating_sub(entry_size(&oldest, &translation));
+            }
+        }
+    }
+}
+
+// Two-letter code from LANG / LC_ALL, e.g. "de_DE.UTF-8" -> "de"
+fn system_language() -> String {
+    ["LC_ALL", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .map(|value| value.chars().take_while(|c| c.is_ascii_alphabetic()).collect::<String>().to_lowercase())
+        .find(|code| code.len() == 2)
+        .unwrap_or_else(|| "en".to_string())
+}
+
+const STOPWORDS: &[(&str, &[&str])] = &[
+    ("en", &["the", "and", "you", "is", "to", "what", "nice", "hand", "lol", "gg", "this", "that"]),
+    ("es", &["el", "la", "que", "de", "y", "es", "no", "por", "una", "mano", "buena", "jaja"]),
+    ("fr", &["le", "la", "et", "est", "que", "je", "pas", "une", "bien", "main", "merci", "mdr"]),
+    ("de", &["der", "die", "und", "ist", "nicht", "ich", "das", "gut", "hand", "schön", "danke"]),
+    ("pt", &["o", "que", "de", "é", "não", "uma", "bem", "mão", "obrigado", "kkk", "boa"]),
+    ("it", &["il", "che", "di", "è", "non", "una", "bella", "mano", "grazie", "ciao"]),
+    ("nl", &["de", "het", "en", "is", "niet", "ik", "een", "goed", "mooie", "bedankt"]),
+];
+
+// Script-based guess, then stopword counts for Latin text. None when unsure.
+fn detect_language(text: &str) -> Option<String> {
+    let script = text.chars().find_map(|c| match c as u32 {
+        0x0400..=0x04FF => Some("ru"),
+        0x0370..=0x03FF => Some("el"),
+        0x0590..=0x05FF => Some("he"),
+        0x0600..=0x06FF => Some("ar"),
+        0x0E00..=0x0E7F => Some("th"),
+        0x3040..=0x30FF => Some("ja"),
+        0xAC00..=0xD7AF => Some("ko"),
+        0x4E00..=0x9FFF => Some("zh"),
+        _ => None,
+    });
+    if let Some(code) = script {
+        return Some(code.to_string());
+    }
+
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let mut scores: Vec<(&str, usize)> = STOPWORDS
+        .iter()
+        .map(|(code, list)| (*code, words.iter().filter(|w| list.contains(&w.as_str())).count()))
+        .collect();
+    scores.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+    match scores.as_slice() {
+        [(code, best), (_, next), ..] if *best > 0 && best > next => Some(code.to_string()),
+        _ => None,
+    }
+}
+
+fn load_settings(db: &Database) -> Result<TranslationSettings, String> {
+    match db.get_value(KEY_SETTINGS)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid translation settings: {}", e)),
+        None => Ok(TranslationSettings::default()),
+    }
+}
+
+// Returns the detected source language (if the provider reports one) and the text
+async fn translate(
+    provider: &TranslationProvider,
+    api_url: &str,
+    text: &str,
+    source: Option<&str>,
+    target: &str,
+) -> Result<Translation, String> {
+    let client = crate::create_http_client()?;
+    let request = match provider {
+        TranslationProvider::Backend => {
+            let token = crate::get_token_from_keyring()
+                .map_err(|_| "Not authenticated".to_string())?;
+            client
+                .post(format!("{}/api/translate", api_url))
+                .header("Authorization", format!("Bearer {}", token))
+                .json(&json!({ "text": text, "source": source, "target": target }))
+        }
+        TranslationProvider::Custom { endpoint, api_key } => client
+            .post(format!("{}/translate", endpoint.trim_end_matches('/')))
+            .json(&json!({ "q": text, "source": source.unwrap_or("auto"), "target": target, "format": "text", "api_key": api_key })),
+    };
+
+    let response = crate::http::send(request).await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("Translation failed: {}", error_text));
+    }
+    let body: Value = response.json().await.map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    // The backend wraps its result in ApiResponse; LibreTranslate answers directly
+    let data = match provider {
+        TranslationProvider::Backend if body["success"].as_bool() == Some(false) => {
+            return Err(body["error"]["message"].as_str().unwrap_or("Unknown error").to_string());
+        }
+        TranslationProvider::Backend => &body["data"],
+        TranslationProvider::Custom { .. } => &body,
+    };
+    let translated = data["translatedText"]
+        .as_str()
+        .ok_or_else(|| "No translation returned".to_string())?
+        .to_string();
+    let source_language = data["detectedLanguage"]["language"]
+        .as_str()
+        .or_else(|| data["detectedLanguage"].as_str())
+        .map(String::from);
+    Ok(Translation { source_language, text: translated })
+}
+
+fn cached(state: &TranslationState, key: &(String, String)) -> Option<Translation> {
+    state.cache.lock().ok()?.entries.get(key).cloned()
+}
+
+fn remember(state: &TranslationState, key: (String, String), translation: Translation) -> Result<(), String> {
+    let mut cache = state.cache.lock().map_err(|_| "Translation cache lock poisoned".to_string())?;
+    if cache.entries.insert(key.clone(), translation).is_none() {
+        cache.order.push_back(key);
+    }
+    while cache.order.len() > CACHE_SIZE {
+        if let Some(oldest) = cache.order.pop_front() {
+            cache.entries.remove(&oldest);
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_translation_settings(db: Db<'_>) -> Result<TranslationSettings, String> {
+    load_settings(&db)
+}
+
+#[tauri::command]
+pub async fn set_translation_settings(
+    db: Db<'_>,
+    settings: TranslationSettings,
+) -> Result<TranslationSettings, String> {
+    if settings.target_language.len() != 2 {
+        return Err("Target language must be a two-letter code".to_string());
+    }
+    if let TranslationProvider::Custom { endpoint, .. } = &settings.provider {
+        if !endpoint.starts_with("https://") && !endpoint.starts_with("http://localhost") {
+            return Err("Custom translation endpoints must use HTTPS".to_string());
+        }
+    }
+    let data = serde_json::to_string(&settings).map_err(|e| e.to_string())?;
+    db.set_value(KEY_SETTINGS, &data)?;
+    Ok(settings)
+}
+
+// Annotate one incoming chat message and emit it. Translation failures leave the
+// message untranslated rather than dropping it.
+#[tauri::command]
+pub async fn process_chat_message(
+    app: AppHandle,
+    db: Db<'_>,
+    state: State<'_, TranslationState>,
+    api_url: String,
+    table_id: String,
+    mut chat: ChatMessage,
+) -> Result<AnnotatedChat, String> {
+    if !chat.is_system {
+        let id = chat_reports::record(&db, &table_id, chat.id.as_deref(), &chat.player_id, &chat.username, &chat.message)?;
+        chat.id = Some(id);
+    }
+    let settings = load_settings(&db)?;
+    let target = settings.target_language.clone();
+    let text = chat.message.trim().to_string();
+    let detected = detect_language(&text);
+
+    let wanted = settings.enabled
+        && !chat.is_system
+        && text.chars().any(char::is_alphabetic)
+        && text.chars().count() <= MAX_MESSAGE_LEN
+        && detected.as_deref().is_none_or(|lang| lang != target && !settings.skip_languages.iter().any(|s| s == lang));
+
+    let (source_language, translated) = if wanted {
+        let key = (target.clone(), text.clone());
+        let found = match cached(&state, &key) {
+            Some(hit) => Some(hit),
+            None => match translate(&settings.provider, &api_url, &text, detected.as_deref(), &target).await {
+                Ok(translation) => {
+                    let translation = Translation { source_language: translation.source_language.or(detected.clone()), ..translation };
+                    remember(&state, key, translation.clone())?;
+                    Some(translation)
+                }
+                Err(e) => {
+                    eprintln!("Chat translation failed: {}", e);
+                    None
+                }
+            },
+        };
+        match found {
+            Some(translation) => (translation.source_language, Some(translation.text)),
+            None => (detected, None),
+        }
+    } else {
+        (detected, None)
+    };
+
+    // Same language after all, or nothing changed
+    let translated = translated.filter(|t| t.trim() != text && source_language.as_deref() != Some(target.as_str()));
+    let annotated = AnnotatedChat {
+        table_id,
+        original: chat.message.clone(),
+        chat,
+        translated,
+        source_language,
+        target_language: target,
+    };
+    let _ = app.emit_all("chat_message_annotated", annotated.clone());
+    Ok(annotated)
+}
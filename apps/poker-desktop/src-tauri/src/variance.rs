@@ -0,0 +1,258 @@
+// Streaks and variance over local hand history for the bankroll dashboard. Hands are
+// split into playing sessions wherever the player took a break longer than the
+// session gap; the streaks count sessions won or lost in a row. Variance is measured
+// in big blinds so hands at different stakes add up: the standard deviation per 100
+// hands, the 95% interval it puts around the win rate, and the deepest and longest
+// drop from a running high.
+
+use crate::compute::{self, Cancel, Priority};
+use crate::db;
+use crate::history::{self, HandFilter, HandRecord};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+use tauri::AppHandle;
+
+const DEFAULT_SESSION_GAP_MINS: i64 = 30;
+const MAX_SESSION_GAP_MINS: i64 = 24 * 60;
+const BIGGEST_POTS: usize = 5;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSummary {
+    started_at: DateTime<Utc>,
+    ended_at: DateTime<Utc>,
+    hands: u32,
+    net: i64,
+    bb_won: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotableHand {
+    hand_id: String,
+    played_at: DateTime<Utc>,
+    pot: u32,
+    net: i64,
+    big_blind: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Streaks {
+    sessions: u32,
+    winning_sessions: u32,
+    losing_sessions: u32,
+    longest_winning_run: u32,
+    longest_losing_run: u32,
+    // Positive while winning sessions in a row, negative while losing
+    current_run: i32,
+    days_played: u32,
+    best_session: Option<SessionSummary>,
+    worst_session: Option<SessionSummary>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Downswing {
+    depth_bb: f64,
+    hands: u32,
+    started_at: Option<DateTime<Utc>>,
+    // None while still below the high it started from
+    recovered_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VarianceReport {
+    hands: u32,
+    bb_won: f64,
+    bb_per_100: f64,
+    std_dev_per_100: f64,
+    // 95% interval for the true win rate, in bb/100
+    win_rate_low: f64,
+    win_rate_high: f64,
+    streaks: Streaks,
+    deepest_downswing: Downswing,
+    longest_downswing: Downswing,
+    current_downswing_bb: f64,
+    biggest_pots_won: Vec<NotableHand>,
+    biggest_pots_lost: Vec<NotableHand>,
+    sessions: Vec<SessionSummary>,
+}
+
+// The hero's net in each hand they played, oldest first
+fn hero_results(hands: &[HandRecord]) -> Vec<(&HandRecord, i64)> {
+    hands
+        .iter()
+        .filter_map(|hand| {
+            let hero = hand.hero_id.as_deref()?;
+            let player = hand.players.iter().find(|p| p.player_id == hero)?;
+            Some((hand, player.net))
+        })
+        .collect()
+}
+
+fn bb(net: i64, big_blind: u32) -> f64 {
+    net as f64 / big_blind.max(1) as f64
+}
+
+fn split_sessions(results: &[(&HandRecord, i64)], gap: Duration) -> Vec<SessionSummary> {
+    let mut sessions: Vec<SessionSummary> = Vec::new();
+    for (hand, net) in results {
+        match sessions.last_mut() {
+            Some(session) if hand.played_at - session.ended_at <= gap => {
+                session.ended_at = hand.played_at;
+                session.hands += 1;
+                session.net += net;
+                session.bb_won += bb(*net, hand.big_blind);
+            }
+            _ => sessions.push(SessionSummary {
+                started_at: hand.played_at,
+                ended_at: hand.played_at,
+                hands: 1,
+                net: *net,
+                bb_won: bb(*net, hand.big_blind),
+            }),
+        }
+    }
+    sessions
+}
+
+fn streaks(results: &[(&HandRecord, i64)], sessions: &[SessionSummary]) -> Streaks {
+    let mut streaks = Streaks { sessions: sessions.len() as u32, ..Default::default() };
+    let mut run = 0i32;
+    for session in sessions {
+        run = match session.net.signum() {
+            1 => {
+                streaks.winning_sessions += 1;
+                run.max(0) + 1
+            }
+            -1 => {
+                streaks.losing_sessions += 1;
+                run.min(0) - 1
+            }
+            _ => 0,
+        };
+        streaks.longest_winning_run = streaks.longest_winning_run.max(run.max(0) as u32);
+        streaks.longest_losing_run = streaks.longest_losing_run.max(run.min(0).unsigned_abs());
+    }
+    streaks.current_run = run;
+    streaks.best_session = sessions.iter().max_by(|a, b| a.bb_won.total_cmp(&b.bb_won)).cloned();
+    streaks.worst_session = sessions.iter().min_by(|a, b| a.bb_won.total_cmp(&b.bb_won)).cloned();
+    let days: HashSet<_> = results.iter().map(|(hand, _)| hand.played_at.date_naive()).collect();
+    streaks.days_played = days.len() as u32;
+    streaks
+}
+
+// Deepest and longest drops of the running total below its high, in big blinds
+fn downswings(results: &[(&HandRecord, i64)]) -> (Downswing, Downswing, f64) {
+    let (mut total, mut peak) = (0.0, 0.0);
+    let mut peak_index = 0;
+    let mut current = Downswing::default();
+    let (mut deepest, mut longest) = (Downswing::default(), Downswing::default());
+    // Whether the deepest downswing is the one in progress
+    let mut deepest_is_current = false;
+    for (i, (hand, net)) in results.iter().enumerate() {
+        total += bb(*net, hand.big_blind);
+        if total >= peak {
+            if current.started_at.is_some() {
+                current.recovered_at = Some(hand.played_at);
+                current.hands = (i - peak_index) as u32;
+                if deepest_is_current {
+                    deepest = current.clone();
+                }
+                if current.hands > longest.hands {
+                    longest = current;
+                }
+            }
+            peak = total;
+            peak_index = i;
+            current = Downswing::default();
+            deepest_is_current = false;
+            continue;
+        }
+        current.started_at.get_or_insert(hand.played_at);
+        current.depth_bb = current.depth_bb.max(peak - total);
+        current.hands = (i - peak_index) as u32;
+        if current.depth_bb > deepest.depth_bb || deepest_is_current {
+            deepest = current.clone();
+            deepest_is_current = true;
+        }
+    }
+    // A downswing still running counts towards the longest, unrecovered
+    if current.hands > longest.hands {
+        longest = current;
+    }
+    (deepest, longest, peak - total)
+}
+
+fn notable(results: &[(&HandRecord, i64)], won: bool) -> Vec<NotableHand> {
+    let mut hands: Vec<NotableHand> = results
+        .iter()
+        .filter(|(_, net)| if won { *net > 0 } else { *net < 0 })
+        .map(|(hand, net)| NotableHand {
+            hand_id: hand.id.clone(),
+            played_at: hand.played_at,
+            pot: hand.pot,
+            net: *net,
+            big_blind: hand.big_blind,
+        })
+        .collect();
+    hands.sort_by_key(|h| std::cmp::Reverse(h.pot));
+    hands.truncate(BIGGEST_POTS);
+    hands
+}
+
+fn build_report(hands: &[HandRecord], session_gap: Duration, cancel: &Cancel) -> Result<VarianceReport, String> {
+    let results = hero_results(hands);
+    if results.is_empty() {
+        return Ok(VarianceReport::default());
+    }
+    cancel.check()?;
+
+    let per_hand: Vec<f64> = results.iter().map(|(hand, net)| bb(*net, hand.big_blind)).collect();
+    let count = per_hand.len() as f64;
+    let bb_won: f64 = per_hand.iter().sum();
+    let mean = bb_won / count;
+    let variance = per_hand.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (count - 1.0).max(1.0);
+    let std_dev_per_100 = variance.sqrt() * 10.0;
+    let bb_per_100 = mean * 100.0;
+    let margin = 1.96 * std_dev_per_100 / (count / 100.0).sqrt();
+
+    let sessions = split_sessions(&results, session_gap);
+    cancel.check()?;
+    let (deepest_downswing, longest_downswing, current_downswing_bb) = downswings(&results);
+    Ok(VarianceReport {
+        hands: results.len() as u32,
+        bb_won,
+        bb_per_100,
+        std_dev_per_100,
+        win_rate_low: bb_per_100 - margin,
+        win_rate_high: bb_per_100 + margin,
+        streaks: streaks(&results, &sessions),
+        deepest_downswing,
+        longest_downswing,
+        current_downswing_bb,
+        biggest_pots_won: notable(&results, true),
+        biggest_pots_lost: notable(&results, false),
+        sessions,
+    })
+}
+
+// `session_gap_minutes` is the break that ends a session, 30 minutes unless given
+// and at most a day
+#[tauri::command]
+pub async fn get_variance_report(
+    app: AppHandle,
+    filters: Option<HandFilter>,
+    session_gap_minutes: Option<i64>,
+) -> Result<VarianceReport, String> {
+    let gap = Duration::minutes(session_gap_minutes.unwrap_or(DEFAULT_SESSION_GAP_MINS).clamp(1, MAX_SESSION_GAP_MINS));
+    compute::run("Variance report", Priority::Normal, move |cancel| {
+        let db = db::get(&app)?;
+        let hands = db.with_conn(|conn| history::filtered_hands(conn, &filters.unwrap_or_default()))?;
+        build_report(&hands, gap, cancel)
+    })
+    .await
+}
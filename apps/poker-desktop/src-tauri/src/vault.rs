@@ -0,0 +1,275 @@
+// Hole cards at rest. Stored hands keep each player's hole cards sealed with
+// ChaCha20-Poly1305 under a key from the OS keyring, so a copy of the database alone
+// does not reveal what was held. Sealing and opening happen in history.rs where hand
+// rows are written and parsed, which keeps every reader (replayer, stats, sync)
+// working on plain `HandRecord`s. Rotation re-seals every row under a fresh key; the
+// new key is parked in the keyring first so an interrupted rotation can be finished.
+
+use crate::accounts;
+use crate::audit;
+use crate::db::{Database, Db};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use keyring::Entry;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use rusqlite::params;
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+const KEYRING_KEY: &str = "hand-cards-key";
+const KEYRING_NEXT: &str = "hand-cards-key-next";
+const SEALED_FIELD: &str = "sealedHoleCards";
+const PLAIN_FIELD: &str = "holeCards";
+const FORMAT: &str = "v1";
+
+#[derive(Clone, Copy)]
+struct HandKey {
+    bytes: [u8; 32],
+}
+
+impl HandKey {
+    fn generate() -> Self {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        Self { bytes }
+    }
+
+    fn id(&self) -> String {
+        Sha256::digest(self.bytes).iter().take(4).map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn cipher(&self) -> ChaCha20Poly1305 {
+        ChaCha20Poly1305::new(Key::from_slice(&self.bytes))
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+struct Keys {
+    current: Option<HandKey>,
+    next: Option<HandKey>,
+}
+
+// Loaded from the keyring on first use
+static KEYS: Mutex<Option<Keys>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RotationReport {
+    key_id: String,
+    hands_resealed: usize,
+}
+
+fn entry(name: &str) -> Result<Entry, String> {
+    Entry::new("primo-poker", &accounts::keyring_key(name)).map_err(|e| format!("Keyring error: {}", e))
+}
+
+fn read_key(name: &str) -> Result<Option<HandKey>, String> {
+    match entry(name)?.get_password() {
+        Ok(stored) => {
+            let bytes: [u8; 32] = STANDARD
+                .decode(stored)
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| "Stored hand key is corrupt".to_string())?;
+            Ok(Some(HandKey { bytes }))
+        }
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read hand key: {}", e)),
+    }
+}
+
+fn write_key(name: &str, key: Option<&HandKey>) -> Result<(), String> {
+    let entry = entry(name)?;
+    match key {
+        Some(key) => entry.set_password(&STANDARD.encode(key.bytes)).map_err(|e| format!("Failed to store hand key: {}", e)),
+        None => match entry.delete_password() {
+            Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(format!("Failed to clear hand key: {}", e)),
+        },
+    }
+}
+
+// Keys from the cache, reading the keyring (and creating the key) the first time
+fn keys() -> Result<Keys, String> {
+    let mut cached = KEYS.lock().map_err(|_| "Hand key lock poisoned".to_string())?;
+    if let Some(keys) = *cached {
+        return Ok(keys);
+    }
+    let current = match read_key(KEYRING_KEY)? {
+        Some(key) => key,
+        None => {
+            let key = HandKey::generate();
+            write_key(KEYRING_KEY, Some(&key))?;
+            key
+        }
+    };
+    let keys = Keys { current: Some(current), next: read_key(KEYRING_NEXT)? };
+    *cached = Some(keys);
+    Ok(keys)
+}
+
+fn set_keys(keys: Keys) {
+    if let Ok(mut cached) = KEYS.lock() {
+        *cached = Some(keys);
+    }
+}
+
+// Cards are bound to their hand and player so sealed values cannot be swapped
+fn associated_data(hand_id: &str, player_id: &str) -> Vec<u8> {
+    format!("{}|{}|{}", FORMAT, hand_id, player_id).into_bytes()
+}
+
+fn seal_cards(key: &HandKey, cards: &Value, hand_id: &str, player_id: &str) -> Result<String, String> {
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+    let plain = serde_json::to_vec(cards).map_err(|e| e.to_string())?;
+    let ciphertext = key
+        .cipher()
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: &plain, aad: &associated_data(hand_id, player_id) })
+        .map_err(|_| "Encryption failed".to_string())?;
+    Ok(format!("{}:{}:{}:{}", FORMAT, key.id(), STANDARD.encode(nonce), STANDARD.encode(ciphertext)))
+}
+
+fn open_cards(keys: &Keys, sealed: &str, hand_id: &str, player_id: &str) -> Result<Value, String> {
+    let parts: Vec<&str> = sealed.split(':').collect();
+    let [FORMAT, key_id, nonce, ciphertext] = parts.as_slice() else {
+        return Err("Unknown sealed card format".to_string());
+    };
+    let key = [keys.current, keys.next]
+        .into_iter()
+        .flatten()
+        .find(|key| key.id() == *key_id)
+        .ok_or_else(|| format!("Hand key {} is not available", key_id))?;
+    let nonce = STANDARD.decode(nonce).ok().filter(|n| n.len() == 12).ok_or_else(|| "Invalid nonce".to_string())?;
+    let ciphertext = STANDARD.decode(ciphertext).map_err(|_| "Invalid sealed cards".to_string())?;
+    let plain = key
+        .cipher()
+        .decrypt(Nonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: &associated_data(hand_id, player_id) })
+        .map_err(|_| "Sealed cards could not be opened".to_string())?;
+    serde_json::from_slice(&plain).map_err(|e| e.to_string())
+}
+
+fn player_ids(hand: &Value) -> (String, Vec<String>) {
+    let hand_id = hand["id"].as_str().unwrap_or_default().to_string();
+    let players = hand["players"]
+        .as_array()
+        .map(|players| players.iter().map(|p| p["playerId"].as_str().unwrap_or_default().to_string()).collect())
+        .unwrap_or_default();
+    (hand_id, players)
+}
+
+fn seal_with(key: &HandKey, hand: &mut Value) -> Result<(), String> {
+    let (hand_id, ids) = player_ids(hand);
+    let Some(players) = hand["players"].as_array_mut() else { return Ok(()) };
+    for (player, player_id) in players.iter_mut().zip(&ids) {
+        let Some(object) = player.as_object_mut() else { continue };
+        let Some(cards) = object.get(PLAIN_FIELD).filter(|cards| !cards.is_null()) else { continue };
+        let sealed = seal_cards(key, cards, &hand_id, player_id)?;
+        object.remove(PLAIN_FIELD);
+        object.insert(SEALED_FIELD.to_string(), Value::String(sealed));
+    }
+    Ok(())
+}
+
+// Replace each player's hole cards with the sealed form before the hand is stored.
+// Without a usable key the cards are stored as they are.
+pub fn seal(hand: &mut Value) {
+    let result = keys().and_then(|keys| match keys.current {
+        Some(key) => seal_with(&key, hand),
+        None => Ok(()),
+    });
+    if let Err(e) = result {
+        eprintln!("Storing hole cards unencrypted: {}", e);
+    }
+}
+
+fn open_with(keys: &Keys, hand: &mut Value) {
+    let (hand_id, ids) = player_ids(hand);
+    let Some(players) = hand["players"].as_array_mut() else { return };
+    for (player, player_id) in players.iter_mut().zip(&ids) {
+        let Some(object) = player.as_object_mut() else { continue };
+        let Some(Value::String(sealed)) = object.get(SEALED_FIELD) else { continue };
+        match open_cards(keys, sealed, &hand_id, player_id) {
+            Ok(cards) => {
+                object.remove(SEALED_FIELD);
+                object.insert(PLAIN_FIELD.to_string(), cards);
+            }
+            // The rest of the hand is still worth showing; the sealed value is kept
+            Err(e) => eprintln!("Hole cards of hand {} unavailable: {}", hand_id, e),
+        }
+    }
+}
+
+// Undo `seal` on a stored hand
+pub fn open(hand: &mut Value) {
+    if !hand["players"].as_array().is_some_and(|players| players.iter().any(|p| p.get(SEALED_FIELD).is_some())) {
+        return;
+    }
+    match keys() {
+        Ok(keys) => open_with(&keys, hand),
+        Err(e) => eprintln!("Hole cards unavailable: {}", e),
+    }
+}
+
+// Re-seal every stored hand under `key`, leaving updated_at alone so sync does not
+// upload them again, then make `key` the current one. Hands are only sealed while the
+// connection is held to write them, so holding it across both steps keeps a hand
+// stored in between from being sealed with the key about to be dropped.
+fn reseal_all(db: &Database, keys: &Keys, key: &HandKey) -> Result<usize, String> {
+    db.with_conn(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        let rows: Vec<(String, String)> = {
+            let mut stmt = tx.prepare("SELECT id, data FROM hands")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        let mut resealed = 0;
+        for (id, data) in rows {
+            let Ok(mut hand) = serde_json::from_str::<Value>(&data) else { continue };
+            open_with(keys, &mut hand);
+            seal_with(key, &mut hand).map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?;
+            tx.execute("UPDATE hands SET data = ?1 WHERE id = ?2", params![hand.to_string(), id])?;
+            resealed += 1;
+        }
+        tx.commit()?;
+        let swapped = write_key(KEYRING_KEY, Some(key)).and_then(|_| {
+            set_keys(Keys { current: Some(*key), next: None });
+            write_key(KEYRING_NEXT, None)
+        });
+        Ok(swapped.map(|_| resealed))
+    })?
+}
+
+fn rotate(db: &Database) -> Result<RotationReport, String> {
+    let mut keys = keys()?;
+    // A rotation that was interrupted is finished with the key it started
+    let next = match keys.next {
+        Some(next) => next,
+        None => {
+            let next = HandKey::generate();
+            write_key(KEYRING_NEXT, Some(&next))?;
+            keys.next = Some(next);
+            set_keys(keys);
+            next
+        }
+    };
+
+    let hands_resealed = reseal_all(db, &keys, &next)?;
+    Ok(RotationReport { key_id: next.id(), hands_resealed })
+}
+
+// Move stored hole cards to a new key. Cards still in plain text from before
+// encryption, or from while the keyring was unavailable, are sealed as well.
+#[tauri::command]
+pub async fn rotate_hand_key(app: AppHandle, db: Db<'_>) -> Result<RotationReport, String> {
+    let result = rotate(&db);
+    let key_id = result.as_ref().ok().map(|report| report.key_id.clone());
+    audit::record(&app, "rotate_hand_key", json!({ "keyId": key_id }), &result);
+    result
+}
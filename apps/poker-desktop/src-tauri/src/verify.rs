@@ -0,0 +1,211 @@
+// Invariant checks for the local engine. With verification on, `LocalTable` checks
+// itself after every deal and action - chips conserved, pot equal to the chips put
+// in minus rake, players without chips all-in, exactly one player to act while
+// betting is open - and keeps any violations for the caller to report. The property
+// tests below play random legal action sequences against the same checks.
+
+use crate::engine::{LocalTable, Street};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Manager};
+
+const MAX_KEPT: usize = 100;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static RECENT: Mutex<Vec<Violation>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Violation {
+    table_id: String,
+    hand_number: u32,
+    // What was applied just before the check, e.g. "deal" or "bot-2 raise 40"
+    event: String,
+    invariant: &'static str,
+    detail: String,
+}
+
+// On when switched on from the frontend or when PRIMO_VERIFY_ENGINE is set
+pub fn enabled() -> bool {
+    static FROM_ENV: OnceLock<bool> = OnceLock::new();
+    ENABLED.load(Ordering::Relaxed) || *FROM_ENV.get_or_init(|| std::env::var_os("PRIMO_VERIFY_ENGINE").is_some())
+}
+
+pub fn check(table: &LocalTable, event: &str) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let mut fail = |invariant: &'static str, detail: String| {
+        violations.push(Violation {
+            table_id: table.table_id.clone(),
+            hand_number: table.hand_number,
+            event: event.to_string(),
+            invariant,
+            detail,
+        });
+    };
+
+    // Winnings are paid into stacks without clearing commitments, so the pot only
+    // counts while the hand is unsettled
+    let total: u32 = table.seats.iter().map(|s| s.starting_stack).sum();
+    let stacks: u32 = table.seats.iter().map(|s| s.stack).sum();
+    let held = if table.result.is_none() { stacks + table.pot() } else { stacks };
+    if held != total {
+        fail("chips_conserved", format!("{} chips on the table, {} at the start of the hand", held, total));
+    }
+
+    // Nothing is raked locally
+    let rake = 0;
+    let put_in: u32 = table.actions.iter().map(|a| a.amount).sum();
+    if table.pot() + rake != put_in {
+        fail("pot_matches_bets", format!("pot {} + rake {} but {} put in", table.pot(), rake, put_in));
+    }
+
+    // Short antes and blinds leave their poster all-in for what they had
+    if table.result.is_none() {
+        for seat in table.seats.iter().filter(|s| s.in_hand && !s.folded && s.stack == 0 && !s.all_in) {
+            fail("broke_player_all_in", format!("{} has no chips left but is not all-in", seat.player_id));
+        }
+    }
+
+    let betting = matches!(table.street, Street::PreFlop | Street::Flop | Street::Turn | Street::River);
+    match (betting, table.to_act) {
+        (true, Some(i)) => {
+            let seat = &table.seats[i];
+            if !seat.in_hand || seat.folded || seat.all_in {
+                fail("one_player_to_act", format!("{} is to act but cannot", seat.player_id));
+            }
+        }
+        (true, None) => fail("one_player_to_act", format!("nobody is to act on the {}", table.street.as_str())),
+        (false, Some(i)) => fail(
+            "one_player_to_act",
+            format!("{} is to act after the hand ended", table.seats[i].player_id),
+        ),
+        (false, None) => {}
+    }
+    violations
+}
+
+// Log violations, keep the latest for `get_engine_violations` and tell the frontend
+pub fn report(app: &AppHandle, violations: Vec<Violation>) {
+    if violations.is_empty() {
+        return;
+    }
+    for violation in &violations {
+        eprintln!(
+            "Engine invariant {} violated at {} hand {} after {}: {}",
+            violation.invariant, violation.table_id, violation.hand_number, violation.event, violation.detail
+        );
+    }
+    if let Ok(mut recent) = RECENT.lock() {
+        recent.extend(violations.iter().cloned());
+        let excess = recent.len().saturating_sub(MAX_KEPT);
+        recent.drain(..excess);
+    }
+    let _ = app.emit_all("engine_invariant_violation", violations);
+}
+
+#[tauri::command]
+pub async fn set_engine_verification(enabled: bool) -> Result<(), String> {
+    ENABLED.store(enabled, Ordering::Relaxed);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_engine_violations() -> Result<Vec<Violation>, String> {
+    let recent = RECENT.lock().map_err(|_| "Violation log lock poisoned".to_string())?;
+    Ok(recent.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::AnteStructure;
+    use crate::sizing::Limit;
+    use proptest::prelude::*;
+    use proptest::sample::Index;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const MAX_ACTIONS_PER_HAND: usize = 500;
+    const ANTES: [AnteStructure; 3] = [AnteStructure::Standard, AnteStructure::Button, AnteStructure::BigBlind];
+
+    #[derive(Debug, Clone)]
+    struct TableSpec {
+        small_blind: u32,
+        fixed_limit: bool,
+        ante: Option<(u32, usize)>,
+        stacks: Vec<u32>,
+    }
+
+    fn table_spec() -> impl Strategy<Value = TableSpec> {
+        (1u32..=50)
+            .prop_flat_map(|small_blind| {
+                let big_blind = small_blind * 2;
+                (
+                    Just(small_blind),
+                    any::<bool>(),
+                    proptest::option::of((1..=big_blind, 0..ANTES.len())),
+                    // Stacks shorter than the blinds included
+                    proptest::collection::vec(1..=big_blind * 300, 2..=9),
+                )
+            })
+            .prop_map(|(small_blind, fixed_limit, ante, stacks)| TableSpec { small_blind, fixed_limit, ante, stacks })
+    }
+
+    fn build(spec: &TableSpec) -> LocalTable {
+        let mut table = LocalTable::new("verify-1", "Verify", spec.small_blind, spec.small_blind * 2);
+        if spec.fixed_limit {
+            table.betting_structure = Limit::Fixed;
+        }
+        if let Some((ante, structure)) = spec.ante {
+            table.set_ante(ante, ANTES[structure]);
+        }
+        for (seat, stack) in spec.stacks.iter().enumerate() {
+            table.add_seat(&format!("p{}", seat + 1), &format!("Player {}", seat + 1), *stack);
+        }
+        table
+    }
+
+    fn assert_invariants(table: &LocalTable, event: &str) -> Result<(), TestCaseError> {
+        let violations = check(table, event);
+        prop_assert!(violations.is_empty(), "{:?}", violations);
+        Ok(())
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(256))]
+
+        // Hands are dealt until one player has the chips or the choices run out; each
+        // choice picks one of the legal actions and where its amount falls in range
+        #[test]
+        fn random_legal_play_keeps_invariants(
+            spec in table_spec(),
+            seed in any::<u64>(),
+            choices in proptest::collection::vec((any::<Index>(), 0.0f64..=1.0), 1..400),
+        ) {
+            let mut table = build(&spec);
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut choices = choices.into_iter();
+            'hands: while table.seats.iter().filter(|s| s.stack > 0).count() >= 2 {
+                table.start_hand(&mut rng).map_err(TestCaseError::fail)?;
+                assert_invariants(&table, "deal")?;
+                for _ in 0..MAX_ACTIONS_PER_HAND {
+                    let Some(seat) = table.to_act else { continue 'hands };
+                    let Some((index, fraction)) = choices.next() else { break 'hands };
+                    let legal = table.legal_actions();
+                    prop_assert!(!legal.is_empty(), "{} is to act with no legal actions", table.seats[seat].player_id);
+                    let option = index.get(&legal);
+                    let span = option.max_amount.saturating_sub(option.min_amount);
+                    let amount = option.min_amount + (span as f64 * fraction) as u32;
+                    let player_id = table.seats[seat].player_id.clone();
+                    let event = format!("{} {} {}", player_id, option.action, amount);
+                    table
+                        .act(&player_id, &option.action, amount)
+                        .map_err(|e| TestCaseError::fail(format!("{} was rejected: {}", event, e)))?;
+                    assert_invariants(&table, &event)?;
+                }
+                prop_assert!(false, "hand {} did not finish within {} actions", table.hand_number, MAX_ACTIONS_PER_HAND);
+            }
+        }
+    }
+}
@@ -0,0 +1,242 @@
+// API version handshake. At startup the client asks `/api/version` which API version
+// and optional capabilities the backend deployment speaks. The answer is kept for the
+// session so the HTTP and WebSocket layers can pick payload shapes the backend
+// understands. Backends from before the endpoint existed are treated as API version 1
+// with no capabilities. A backend too old for this client, or one that requires a
+// newer client, fails every request with `IncompatibleBackend` and the frontend is
+// told through `update_required`.
+
+use crate::error::CommandError;
+use crate::profile::BackendProfile;
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Manager, State};
+
+pub const CLIENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Backend API versions this client can talk to
+const MIN_API_VERSION: u32 = 1;
+const MAX_API_VERSION: u32 = 2;
+
+// Login answers wrapped in the usual `ApiResponse` envelope
+pub const LOGIN_ENVELOPE: &str = "login_envelope";
+// List endpoints take `limit`/`cursor` and answer `{ items, nextCursor }`
+pub const CURSOR_PAGINATION: &str = "cursor_pagination";
+// GraphQL endpoint at `/graphql` with automatic persisted queries
+pub const GRAPHQL: &str = "graphql";
+// Version 2 WebSocket frames, which carry their body in `data`
+pub const WS_PROTOCOL_V2: &str = "ws_protocol_v2";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VersionResponse {
+    api_version: u32,
+    #[serde(default)]
+    min_client_version: Option<String>,
+    #[serde(default)]
+    latest_client_version: Option<String>,
+    #[serde(default)]
+    capabilities: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Negotiated {
+    // Version both sides speak, the lower of the backend's and ours
+    api_version: u32,
+    server_api_version: u32,
+    capabilities: Vec<String>,
+    // The backend has no version endpoint
+    legacy: bool,
+    client_version: String,
+    latest_client_version: Option<String>,
+    update_available: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateRequired {
+    client_version: String,
+    min_client_version: Option<String>,
+    reason: String,
+}
+
+#[derive(Default)]
+pub struct VersionState {
+    negotiated: Mutex<Option<Negotiated>>,
+    incompatible: Mutex<Option<CommandError>>,
+}
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+// Give the request layer access to the negotiated state. Without it (headless runs)
+// every request goes out in the version 1 shapes.
+pub fn init(app: AppHandle) {
+    let _ = APP.set(app);
+}
+
+fn state() -> Option<State<'static, VersionState>> {
+    APP.get()?.try_state::<VersionState>()
+}
+
+// Whether the backend advertised `capability` in the handshake
+pub fn supports(capability: &str) -> bool {
+    state()
+        .and_then(|state| {
+            let negotiated = state.negotiated.lock().ok()?;
+            Some(negotiated.as_ref()?.capabilities.iter().any(|c| c == capability))
+        })
+        .unwrap_or(false)
+}
+
+// Fails once the backend has been found incompatible
+pub fn check_compatible() -> Result<(), CommandError> {
+    let Some(state) = state() else { return Ok(()) };
+    let incompatible = state.incompatible.lock().ok().and_then(|i| i.clone());
+    match incompatible {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+// "1.4.0-beta.2" -> [1, 4, 0]
+fn parse_version(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+fn older_than(version: &str, other: &str) -> bool {
+    parse_version(version) < parse_version(other)
+}
+
+// Remember the incompatibility and tell the frontend. Requests fail from now on.
+fn reject(app: &AppHandle, error: CommandError) -> CommandError {
+    eprintln!("{}", error);
+    if let Some(state) = app.try_state::<VersionState>() {
+        if let Ok(mut incompatible) = state.incompatible.lock() {
+            *incompatible = Some(error.clone());
+        }
+    }
+    if let CommandError::IncompatibleBackend { update_required: true, min_client_version, reason, .. } = &error {
+        let _ = app.emit_all("update_required", UpdateRequired {
+            client_version: CLIENT_VERSION.to_string(),
+            min_client_version: min_client_version.clone(),
+            reason: reason.clone(),
+        });
+    }
+    error
+}
+
+// Called by the request layer when the backend answers 426 Upgrade Required
+pub fn mark_update_required(reason: String) -> CommandError {
+    let error = CommandError::IncompatibleBackend {
+        server_api_version: None,
+        client_version: CLIENT_VERSION.to_string(),
+        min_client_version: None,
+        update_required: true,
+        reason,
+    };
+    match APP.get() {
+        Some(app) => reject(app, error),
+        None => error,
+    }
+}
+
+async fn fetch_version(api_url: &str) -> Result<Option<VersionResponse>, CommandError> {
+    let client = crate::create_http_client()?;
+    let response = crate::http::send(client.get(format!("{}/api/version", api_url))).await?;
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("Version check failed: {}", error_text).into());
+    }
+    let api_response: crate::ApiResponse<VersionResponse> = response.json().await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+    match api_response.data {
+        Some(version) if api_response.success => Ok(Some(version)),
+        _ => Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string()).into()),
+    }
+}
+
+// Run the handshake against `api_url` and store the outcome
+pub async fn negotiate(app: &AppHandle, api_url: &str) -> Result<Negotiated, CommandError> {
+    let response = fetch_version(api_url).await?;
+    let legacy = response.is_none();
+    let response = response.unwrap_or(VersionResponse {
+        api_version: 1,
+        min_client_version: None,
+        latest_client_version: None,
+        capabilities: Vec::new(),
+    });
+
+    let incompatible = |update_required: bool, reason: String| CommandError::IncompatibleBackend {
+        server_api_version: Some(response.api_version),
+        client_version: CLIENT_VERSION.to_string(),
+        min_client_version: response.min_client_version.clone(),
+        update_required,
+        reason,
+    };
+    if let Some(min) = response.min_client_version.as_deref().filter(|min| older_than(CLIENT_VERSION, min)) {
+        let reason = format!("This backend requires client version {} or newer", min);
+        return Err(reject(app, incompatible(true, reason)));
+    }
+    if response.api_version < MIN_API_VERSION {
+        let reason = format!("Backend API version {} is no longer supported", response.api_version);
+        return Err(reject(app, incompatible(false, reason)));
+    }
+
+    let negotiated = Negotiated {
+        api_version: response.api_version.min(MAX_API_VERSION),
+        server_api_version: response.api_version,
+        capabilities: response.capabilities,
+        legacy,
+        client_version: CLIENT_VERSION.to_string(),
+        update_available: response.latest_client_version.as_deref().is_some_and(|latest| older_than(CLIENT_VERSION, latest)),
+        latest_client_version: response.latest_client_version,
+    };
+
+    let state = app.state::<VersionState>();
+    *state.negotiated.lock().map_err(|_| "Version lock poisoned".to_string())? = Some(negotiated.clone());
+    *state.incompatible.lock().map_err(|_| "Version lock poisoned".to_string())? = None;
+    let _ = app.emit_all("backend_version_negotiated", negotiated.clone());
+    Ok(negotiated)
+}
+
+// Handshake with the selected profile's backend without holding up startup
+pub fn start_negotiation(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let api_url = app.state::<BackendProfile>().api_url.clone();
+        match negotiate(&app, &api_url).await {
+            Ok(negotiated) => eprintln!(
+                "Backend API version {} (speaking {})",
+                negotiated.server_api_version, negotiated.api_version
+            ),
+            // Incompatibility was already reported; anything else is retried on demand
+            Err(CommandError::IncompatibleBackend { .. }) => {}
+            Err(e) => crate::startup::report_error(&app, "version", e.to_string()),
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_backend_version(state: State<'_, VersionState>) -> Result<Option<Negotiated>, String> {
+    let negotiated = state.negotiated.lock().map_err(|_| "Version lock poisoned".to_string())?;
+    Ok(negotiated.clone())
+}
+
+// Repeat the handshake, e.g. after the backend URL changed or the version check
+// failed at startup
+#[tauri::command]
+pub async fn negotiate_backend_version(app: AppHandle, api_url: String) -> Result<Negotiated, CommandError> {
+    negotiate(&app, &api_url).await
+}
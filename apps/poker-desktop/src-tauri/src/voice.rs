@@ -0,0 +1,294 @@
+// Opt-in voice channel for private tables. The backend decides which tables allow
+// voice and hands out ICE servers; this module runs the signaling socket, relays
+// offers, answers and ICE candidates between the backend and the webview (which owns
+// the WebRTC peer connections and audio devices), and keeps mute, deafen,
+// push-to-talk and per-player volume so every window sees the same voice state.
+
+use crate::db::{self, Database, Db};
+use crate::profile::BackendProfile;
+use crate::ws::{self, TableSocket, WsMessage};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+const KEY_VOLUMES: &str = "voice.volumes";
+const MAX_VOLUME: f32 = 2.0;
+const SIGNALS: &[&str] = &["offer", "answer", "ice"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceParticipant {
+    player_id: String,
+    username: String,
+    #[serde(default)]
+    muted: bool,
+    #[serde(default)]
+    speaking: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JoinResponse {
+    channel_id: String,
+    ice_servers: Vec<Value>,
+    #[serde(default)]
+    participants: Vec<VoiceParticipant>,
+}
+
+struct Channel {
+    table_id: String,
+    channel_id: String,
+    ice_servers: Vec<Value>,
+    socket: TableSocket,
+    participants: Vec<VoiceParticipant>,
+    muted: bool,
+    deafened: bool,
+    push_to_talk: bool,
+    talking: bool,
+}
+
+impl Channel {
+    // Whether the webview should send microphone audio now
+    fn transmitting(&self) -> bool {
+        !self.muted && !self.deafened && (!self.push_to_talk || self.talking)
+    }
+}
+
+#[derive(Default)]
+pub struct VoiceState {
+    channel: Mutex<Option<Channel>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceStatus {
+    table_id: String,
+    channel_id: String,
+    ice_servers: Vec<Value>,
+    participants: Vec<VoiceParticipant>,
+    muted: bool,
+    deafened: bool,
+    push_to_talk: bool,
+    transmitting: bool,
+    // Playback gain per player, 1.0 when not set
+    volumes: HashMap<String, f32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VoiceSignal {
+    kind: String,
+    from: String,
+    data: Value,
+}
+
+fn volumes(db: &Database) -> Result<HashMap<String, f32>, String> {
+    match db.get_value(KEY_VOLUMES)? {
+        Some(data) => serde_json::from_str(&data).map_err(|e| format!("Invalid voice volumes: {}", e)),
+        None => Ok(HashMap::new()),
+    }
+}
+
+fn status(channel: &Channel, db: &Database) -> Result<VoiceStatus, String> {
+    Ok(VoiceStatus {
+        table_id: channel.table_id.clone(),
+        channel_id: channel.channel_id.clone(),
+        ice_servers: channel.ice_servers.clone(),
+        participants: channel.participants.clone(),
+        muted: channel.muted,
+        deafened: channel.deafened,
+        push_to_talk: channel.push_to_talk,
+        transmitting: channel.transmitting(),
+        volumes: volumes(db)?,
+    })
+}
+
+// Apply `f` to the open channel, tell the server about the new mute state and emit
+// the status to every window
+fn update(app: &AppHandle, state: &VoiceState, f: impl FnOnce(&mut Channel)) -> Result<VoiceStatus, String> {
+    let mut channel = state.channel.lock().map_err(|_| "Voice lock poisoned".to_string())?;
+    let channel = channel.as_mut().ok_or_else(|| "Not in a voice channel".to_string())?;
+    f(channel);
+    channel.socket.send(WsMessage::new("voice_state", json!({
+        "channelId": channel.channel_id,
+        "muted": !channel.transmitting(),
+        "deafened": channel.deafened,
+    })))?;
+    let status = status(channel, &*db::get(app)?)?;
+    let _ = app.emit_all("voice_state", status.clone());
+    Ok(status)
+}
+
+// Relay signaling and roster changes until the socket closes
+fn spawn_reader(app: AppHandle, channel_id: String, mut incoming: tokio::sync::mpsc::UnboundedReceiver<WsMessage>) {
+    tauri::async_runtime::spawn(async move {
+        while let Some(message) = incoming.recv().await {
+            let payload = &message.payload;
+            match message.kind.as_str() {
+                "voice_signal" => {
+                    let signal = VoiceSignal {
+                        kind: payload["kind"].as_str().unwrap_or_default().to_string(),
+                        from: payload["from"].as_str().unwrap_or_default().to_string(),
+                        data: payload["data"].clone(),
+                    };
+                    let _ = app.emit_all("voice_signal", signal);
+                }
+                "voice_participants" => {
+                    let Ok(participants) = serde_json::from_value::<Vec<VoiceParticipant>>(payload["participants"].clone()) else { continue };
+                    let state = app.state::<VoiceState>();
+                    if let Ok(mut channel) = state.channel.lock() {
+                        if let Some(channel) = channel.as_mut().filter(|c| c.channel_id == channel_id) {
+                            channel.participants = participants.clone();
+                        }
+                    }
+                    let _ = app.emit_all("voice_participants", participants);
+                }
+                _ => {}
+            }
+        }
+
+        // Closed by the server, or replaced by another channel
+        let state = app.state::<VoiceState>();
+        if let Ok(mut channel) = state.channel.lock() {
+            if channel.as_ref().is_some_and(|c| c.channel_id == channel_id) {
+                *channel = None;
+                let _ = app.emit_all("voice_disconnected", json!({ "channelId": channel_id }));
+            }
+        };
+    });
+}
+
+#[tauri::command]
+pub async fn join_voice(
+    app: AppHandle,
+    db: Db<'_>,
+    profile: State<'_, BackendProfile>,
+    state: State<'_, VoiceState>,
+    api_url: String,
+    table_id: String,
+) -> Result<VoiceStatus, String> {
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+    let client = crate::create_http_client()?;
+    let response = crate::http::send(client.post(format!("{}/api/voice/{}/join", api_url, table_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({})))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("Could not join voice: {}", error_text));
+    }
+    let api_response: crate::ApiResponse<JoinResponse> = response.json().await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+    let joined = match api_response.data {
+        Some(joined) if api_response.success => joined,
+        _ => return Err(api_response.error.map(|e| e.message).unwrap_or_else(|| "Unknown error".to_string())),
+    };
+
+    let url = format!("{}&channel=voice", ws::table_url(&profile.ws_url, &token, &table_id));
+    let (socket, incoming) = ws::connect(&url).await?;
+    socket.renew_with_session();
+    socket.send(WsMessage::new("voice_join", json!({ "channelId": joined.channel_id, "muted": true })))?;
+
+    let channel = Channel {
+        table_id,
+        channel_id: joined.channel_id.clone(),
+        ice_servers: joined.ice_servers,
+        socket,
+        participants: joined.participants,
+        // Start muted; the player unmutes once the microphone is picked
+        muted: true,
+        deafened: false,
+        push_to_talk: false,
+        talking: false,
+    };
+    let status = status(&channel, &db)?;
+    // Replacing the channel drops the previous socket, which ends its reader
+    *state.channel.lock().map_err(|_| "Voice lock poisoned".to_string())? = Some(channel);
+    spawn_reader(app, joined.channel_id, incoming);
+    Ok(status)
+}
+
+#[tauri::command]
+pub async fn leave_voice(app: AppHandle, state: State<'_, VoiceState>) -> Result<(), String> {
+    let channel = state.channel.lock().map_err(|_| "Voice lock poisoned".to_string())?.take();
+    if let Some(channel) = channel {
+        let _ = channel.socket.send(WsMessage::new("voice_leave", json!({ "channelId": channel.channel_id })));
+        let _ = app.emit_all("voice_disconnected", json!({ "channelId": channel.channel_id }));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_voice_status(db: Db<'_>, state: State<'_, VoiceState>) -> Result<Option<VoiceStatus>, String> {
+    let channel = state.channel.lock().map_err(|_| "Voice lock poisoned".to_string())?;
+    channel.as_ref().map(|c| status(c, &db)).transpose()
+}
+
+// Forward an offer, answer or ICE candidate from the webview to another participant
+#[tauri::command]
+pub async fn send_voice_signal(state: State<'_, VoiceState>, kind: String, to: String, data: Value) -> Result<(), String> {
+    if !SIGNALS.contains(&kind.as_str()) {
+        return Err(format!("Unknown voice signal: {}", kind));
+    }
+    let channel = state.channel.lock().map_err(|_| "Voice lock poisoned".to_string())?;
+    let channel = channel.as_ref().ok_or_else(|| "Not in a voice channel".to_string())?;
+    channel.socket.send(WsMessage::new("voice_signal", json!({
+        "channelId": channel.channel_id,
+        "kind": kind,
+        "to": to,
+        "data": data,
+    })))
+}
+
+#[tauri::command]
+pub async fn set_voice_muted(app: AppHandle, state: State<'_, VoiceState>, muted: bool) -> Result<VoiceStatus, String> {
+    update(&app, &state, |channel| channel.muted = muted)
+}
+
+// Deafening silences playback and the microphone together
+#[tauri::command]
+pub async fn set_voice_deafened(app: AppHandle, state: State<'_, VoiceState>, deafened: bool) -> Result<VoiceStatus, String> {
+    update(&app, &state, |channel| channel.deafened = deafened)
+}
+
+#[tauri::command]
+pub async fn set_push_to_talk(app: AppHandle, state: State<'_, VoiceState>, enabled: bool) -> Result<VoiceStatus, String> {
+    update(&app, &state, |channel| {
+        channel.push_to_talk = enabled;
+        channel.talking = false;
+    })
+}
+
+// Key down / key up for push-to-talk
+#[tauri::command]
+pub async fn push_to_talk(app: AppHandle, state: State<'_, VoiceState>, active: bool) -> Result<VoiceStatus, String> {
+    update(&app, &state, |channel| channel.talking = active)
+}
+
+// Playback gain for one player, 0.0 to 2.0; kept across sessions
+#[tauri::command]
+pub async fn set_player_volume(
+    app: AppHandle,
+    db: Db<'_>,
+    player_id: String,
+    volume: f32,
+) -> Result<HashMap<String, f32>, String> {
+    if !(0.0..=MAX_VOLUME).contains(&volume) {
+        return Err(format!("Volume must be between 0 and {}", MAX_VOLUME));
+    }
+    let mut volumes = volumes(&db)?;
+    if volume == 1.0 {
+        volumes.remove(&player_id);
+    } else {
+        volumes.insert(player_id, volume);
+    }
+    let data = serde_json::to_string(&volumes).map_err(|e| e.to_string())?;
+    db.set_value(KEY_VOLUMES, &data)?;
+    let _ = app.emit_all("voice_volumes", volumes.clone());
+    Ok(volumes)
+}
@@ -0,0 +1,69 @@
+// Wallet transaction history from the backend, in cursor-paged form (see
+// pagination.rs).
+
+use crate::pagination::{self, Page};
+use crate::schema::{Field, Kind};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+const TRANSACTION_SCHEMA: Kind = Kind::Object(&[
+    Field::critical("id", Kind::String),
+    Field::defaulted("type", Kind::String),
+    Field::critical("amount", Kind::Number),
+    Field::critical("createdAt", Kind::String),
+]);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transaction {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    // Signed; withdrawals and buy-ins are negative
+    pub amount: i64,
+    pub created_at: String,
+    #[serde(flatten)]
+    details: Map<String, Value>,
+}
+
+impl Transaction {
+    pub fn table_id(&self) -> Option<&str> {
+        self.details.get("tableId").and_then(Value::as_str)
+    }
+
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.created_at).ok().map(|t| t.with_timezone(&Utc))
+    }
+}
+
+pub async fn fetch_transactions(api_url: &str, limit: u32, after: Option<&str>) -> Result<Page<Transaction>, String> {
+    let token = crate::get_token_from_keyring()
+        .map_err(|_| "Not authenticated".to_string())?;
+    let url = format!("{}/api/wallet/transactions", api_url);
+    let (items, next) = pagination::fetch_remote(&url, Some(&token), limit, after, &TRANSACTION_SCHEMA, "transactions").await?;
+    Ok(Page { items, next_cursor: pagination::transactions_cursor(api_url, limit, next) })
+}
+
+// Every transaction made at or after `since`, newest first, reading at most `max_pages`
+pub async fn fetch_since(api_url: &str, since: DateTime<Utc>, max_pages: usize) -> Result<Vec<Transaction>, String> {
+    let mut transactions = Vec::new();
+    let mut after: Option<String> = None;
+    for _ in 0..max_pages {
+        let page = fetch_transactions(api_url, pagination::clamp_limit(Some(u32::MAX)), after.as_deref()).await?;
+        let reached = page.items.iter().any(|t| t.created_at().is_some_and(|at| at < since));
+        transactions.extend(page.items.into_iter().filter(|t| t.created_at().is_none_or(|at| at >= since)));
+        match page.next_cursor {
+            Some(cursor) if !reached => after = Some(pagination::remote_position(&cursor)?),
+            _ => break,
+        }
+    }
+    Ok(transactions)
+}
+
+// Get wallet transactions, most recent first
+#[tauri::command]
+pub async fn get_transactions(api_url: String, limit: Option<u32>, cursor: Option<String>) -> Result<Page<Transaction>, String> {
+    let after = cursor.as_deref().map(pagination::remote_position).transpose()?;
+    fetch_transactions(&api_url, pagination::clamp_limit(limit), after.as_deref()).await
+}
@@ -0,0 +1,216 @@
+// Game WebSocket client. One connection per table; incoming frames are decoded into
+// `WsMessage` envelopes, published on the event bus (see bus.rs) and handed to the
+// caller over a channel. Connections opened with the player's token can be signed
+// up for renewal, and are handed an `auth_renew` frame with the new token whenever
+// it is refreshed (see renewal.rs), so the backend re-authenticates them in place
+// instead of dropping them at expiry.
+
+use crate::bus::{self, Topic};
+use crate::event_buffer::{self, Direction};
+use crate::{compression, metrics};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::mpsc::{self, WeakUnboundedSender};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+
+const PING_INTERVAL_SECS: u64 = 25;
+
+// Sockets to re-authenticate on a token refresh; weak so they still close when dropped
+static RENEWABLE: Mutex<Vec<WeakUnboundedSender<WsMessage>>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsMessage {
+    #[serde(rename = "type")]
+    pub kind: String,
+    // Version 2 frames name the body `data`
+    #[serde(default, alias = "data")]
+    pub payload: Value,
+    // Milliseconds since epoch; kept raw because some server frames send ISO strings
+    #[serde(default)]
+    pub timestamp: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+}
+
+impl WsMessage {
+    pub fn new(kind: &str, payload: Value) -> Self {
+        Self {
+            kind: kind.to_string(),
+            payload,
+            timestamp: json!(chrono::Utc::now().timestamp_millis()),
+            id: None,
+        }
+    }
+}
+
+// Sending half of a table connection. Dropping it closes the socket.
+pub struct TableSocket {
+    outgoing: mpsc::UnboundedSender<WsMessage>,
+}
+
+impl TableSocket {
+    pub fn send(&self, message: WsMessage) -> Result<(), String> {
+        self.outgoing
+            .send(message)
+            .map_err(|_| "WebSocket connection closed".to_string())
+    }
+
+    // Re-authenticate this connection whenever the player's token is refreshed
+    pub fn renew_with_session(&self) {
+        if let Ok(mut renewable) = RENEWABLE.lock() {
+            renewable.push(self.outgoing.downgrade());
+        }
+    }
+}
+
+// Frame that re-authenticates an open connection with `token`
+pub fn auth_renew_frame(token: &str) -> WsMessage {
+    WsMessage::new("auth_renew", json!({ "token": token }))
+}
+
+// Hand `token` to every open connection signed up for renewal; returns how many
+pub fn renew_auth(token: &str) -> usize {
+    let Ok(mut renewable) = RENEWABLE.lock() else { return 0 };
+    renewable.retain(|weak| weak.upgrade().is_some_and(|sender| sender.send(auth_renew_frame(token)).is_ok()));
+    renewable.len()
+}
+
+// Asks for the version 2 frame shape when the backend advertised it
+pub fn table_url(ws_url: &str, token: &str, table_id: &str) -> String {
+    let url = format!("{}?token={}&tableId={}", ws_url, token, table_id);
+    if crate::version::supports(crate::version::WS_PROTOCOL_V2) {
+        format!("{}&protocol=2", url)
+    } else {
+        url
+    }
+}
+
+// A spectator connection to `table_id`. Its own channel keeps its frames off the event
+// bus and out of the event buffer, so nothing sees them before the spectator delay
+// (see spectator_delay.rs) does.
+pub fn spectator_url(ws_url: &str, token: &str, table_id: &str) -> String {
+    format!("{}&channel=spectator", table_url(ws_url, token, table_id))
+}
+
+// Bus topic for the frames of a connection to `url` and the table it is for, if any
+fn topic_of(url: &str) -> (Option<Topic>, Option<String>) {
+    let params: Vec<(&str, &str)> = url
+        .split_once('?')
+        .map(|(_, query)| query.split('&').filter_map(|p| p.split_once('=')).collect())
+        .unwrap_or_default();
+    let param = |name: &str| params.iter().find(|(key, _)| *key == name).map(|(_, value)| value.to_string());
+    let table_id = param("tableId");
+    let topic = match param("channel").as_deref() {
+        Some("notifications") => Some(Topic::Notification),
+        Some("voice") => Some(Topic::Voice),
+        Some(_) => None,
+        None => table_id.as_ref().map(|_| Topic::Table),
+    };
+    (topic, table_id)
+}
+
+// Open a table connection. The receiver yields decoded messages until the server
+// closes the socket; each is also published on the event bus.
+pub async fn connect(url: &str) -> Result<(TableSocket, mpsc::UnboundedReceiver<WsMessage>), String> {
+    let settings = compression::settings();
+    let mut request = url.into_client_request()
+        .map_err(|e| format!("Invalid WebSocket URL: {}", e))?;
+    if let Some(offer) = compression::offer(&settings).and_then(|o| HeaderValue::from_str(&o).ok()) {
+        request.headers_mut().insert(compression::OFFER_HEADER, offer);
+    }
+    let (stream, response) = tokio_tungstenite::connect_async(request).await
+        .map_err(|e| format!("WebSocket connection failed: {}", e))?;
+    let answer = response.headers().get(compression::OFFER_HEADER).and_then(|v| v.to_str().ok());
+    let (mut deflater, mut inflater) = match compression::accept(answer, &settings) {
+        Some((deflater, inflater)) => (Some(deflater), Some(inflater)),
+        None => (None, None),
+    };
+    let (mut sink, mut source) = stream.split();
+    let (outgoing, mut outgoing_rx) = mpsc::unbounded_channel::<WsMessage>();
+    let (incoming, incoming_rx) = mpsc::unbounded_channel();
+    let table = event_buffer::table_of(url);
+    let sent_table = table.clone();
+    let (topic, topic_table) = topic_of(url);
+
+    tokio::spawn(async move {
+        let mut ping = tokio::time::interval(Duration::from_secs(PING_INTERVAL_SECS));
+        ping.tick().await;
+        loop {
+            let message = tokio::select! {
+                next = outgoing_rx.recv() => match next {
+                    Some(message) => message,
+                    None => break,
+                },
+                _ = ping.tick() => WsMessage::new("ping", json!({})),
+            };
+            let Ok(text) = serde_json::to_string(&message) else { continue };
+            if let Some(table_id) = &sent_table {
+                event_buffer::record(table_id, Direction::Sent, &text);
+            }
+            let raw = text.len();
+            let frame = match deflater.as_mut().filter(|d| d.worth_compressing(&text)) {
+                Some(deflater) => match deflater.compress(text.as_bytes()) {
+                    Ok(compressed) => Message::Binary(compressed),
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        break;
+                    }
+                },
+                None => Message::Text(text),
+            };
+            metrics::record_ws_sent(frame.len(), raw, matches!(frame, Message::Binary(_)));
+            if sink.send(frame).await.is_err() {
+                break;
+            }
+        }
+        let _ = sink.close().await;
+    });
+
+    tokio::spawn(async move {
+        while let Some(frame) = source.next().await {
+            let text = match frame {
+                Ok(Message::Text(text)) => {
+                    metrics::record_ws_received(text.len(), text.len(), false);
+                    text
+                }
+                // Compressed text; a decompression error means the window is out of
+                // sync, so the connection cannot continue
+                Ok(Message::Binary(data)) => match inflater.as_mut().map(|i| i.decompress(&data)) {
+                    Some(Ok(raw)) => {
+                        metrics::record_ws_received(data.len(), raw.len(), true);
+                        String::from_utf8_lossy(&raw).into_owned()
+                    }
+                    Some(Err(e)) => {
+                        eprintln!("{}", e);
+                        break;
+                    }
+                    None => continue,
+                },
+                Ok(Message::Close(_)) | Err(_) => break,
+                Ok(_) => continue,
+            };
+            if let Some(table_id) = &table {
+                event_buffer::record(table_id, Direction::Received, &text);
+            }
+            match serde_json::from_str::<WsMessage>(&text) {
+                Ok(message) => {
+                    if let Some(topic) = topic {
+                        bus::publish(bus::Event::Frame { topic, table_id: topic_table.clone(), message: message.clone() });
+                    }
+                    if incoming.send(message).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => eprintln!("Ignoring malformed WebSocket frame: {}", e),
+            }
+        }
+    });
+
+    Ok((TableSocket { outgoing }, incoming_rx))
+}